@@ -0,0 +1,96 @@
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::capture_indicator::{self, CaptureSource, IndicatorPresenter};
+use crate::{obs, settings};
+
+pub const CAPTURE_INDICATOR_EVENT: &str = "tv_capture_indicator";
+const WINDOW_LABEL: &str = "capture_indicator";
+const WINDOW_SIZE: (f64, f64) = (132.0, 36.0);
+const EDGE_MARGIN_PX: i32 = 16;
+
+#[derive(Debug, Clone, Serialize)]
+struct IndicatorPayload {
+    sources: Vec<CaptureSource>,
+}
+
+struct WindowPresenter {
+    app: AppHandle,
+}
+
+impl IndicatorPresenter for WindowPresenter {
+    fn present(&self, sources: &[CaptureSource]) -> Result<(), String> {
+        let w = self
+            .app
+            .get_webview_window(WINDOW_LABEL)
+            .ok_or_else(|| "indicator window missing".to_string())?;
+        let _ = self.app.emit(
+            CAPTURE_INDICATOR_EVENT,
+            IndicatorPayload {
+                sources: sources.to_vec(),
+            },
+        );
+        if sources.is_empty() {
+            return w.hide().map_err(|e| e.to_string());
+        }
+        place_top_right(&w);
+        w.show().map_err(|e| e.to_string())
+    }
+}
+
+// Click-through light in the top-right corner of the primary monitor; hidden
+// until a capture starts while the indicator is enforced.
+pub fn install(app: &AppHandle) {
+    let built = tauri::WebviewWindowBuilder::new(
+        app,
+        WINDOW_LABEL,
+        tauri::WebviewUrl::App("index.html".into()),
+    )
+    .title("TypeVoice Capture Indicator")
+    .inner_size(WINDOW_SIZE.0, WINDOW_SIZE.1)
+    .resizable(false)
+    .decorations(false)
+    .transparent(true)
+    .always_on_top(true)
+    .visible(false)
+    .skip_taskbar(true)
+    .focused(false)
+    .build();
+    if let Ok(w) = built {
+        let _ = w.set_ignore_cursor_events(true);
+    }
+    capture_indicator::set_presenter(WindowPresenter { app: app.clone() });
+}
+
+pub fn apply_from_settings(data_dir: &Path, s: &settings::Settings) {
+    let enforced = settings::resolve_capture_indicator_enforced(s);
+    if let Err(e) = capture_indicator::set_enforced(enforced) {
+        obs::event_err(
+            data_dir,
+            obs::ErrorEvent {
+                task_id: None,
+                stage: "Record",
+                step_id: "CAPTURE.indicator",
+                kind: "config",
+                code: "E_CAPTURE_INDICATOR_UNAVAILABLE",
+                ctx: None,
+            },
+            &e,
+        );
+    }
+}
+
+fn place_top_right(w: &tauri::WebviewWindow) {
+    let Ok(Some(monitor)) = w.primary_monitor() else {
+        return;
+    };
+    let Ok(size) = w.outer_size() else {
+        return;
+    };
+    let area = monitor.position();
+    let x = area.x + monitor.size().width as i32 - size.width as i32 - EDGE_MARGIN_PX;
+    let y = area.y + EDGE_MARGIN_PX;
+    let _ = w.set_position(tauri::PhysicalPosition::new(x, y));
+}
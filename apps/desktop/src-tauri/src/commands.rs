@@ -1,8 +1,8 @@
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Manager, State};
+use tauri::{AppHandle, Emitter, Manager, State};
 
 use crate::audio_capture::RecordingRegistry;
-use crate::insertion::{InsertResult, InsertTextRequest};
+use crate::insertion::{ExportTargetLock, InsertResult, InsertTextRequest};
 use crate::ports::PortError;
 use crate::record_input_cache::RecordInputCacheState;
 use crate::rewrite::{RewriteResult, RewriteTextRequest};
@@ -11,9 +11,9 @@ use crate::transcription_actor::TranscriptionActor;
 use crate::ui_events::UiEventMailbox;
 use crate::voice_workflow::{
     VoiceWorkflow, WorkflowApplyEventRequest, WorkflowAsrCompletedRequest, WorkflowAsrEmptyRequest,
-    WorkflowCommandDeps, WorkflowCommandRequest, WorkflowError, WorkflowInsertCompletedRequest,
-    WorkflowRewriteCompletedRequest, WorkflowTaskFailedRequest, WorkflowTextCommandRequest,
-    WorkflowView,
+    WorkflowCancelStageRequest, WorkflowCommandDeps, WorkflowCommandRequest, WorkflowError,
+    WorkflowInsertCompletedRequest, WorkflowRewriteCompletedRequest, WorkflowTaskFailedRequest,
+    WorkflowTextCommandRequest, WorkflowView,
 };
 use crate::{data_dir, RuntimeState};
 
@@ -23,6 +23,7 @@ pub fn command_names() -> &'static [&'static str] {
         "record_transcribe_start",
         "record_transcribe_stop",
         "record_transcribe_cancel",
+        "cancel_stage",
         "rewrite_text",
         "insert_text",
         "workflow_snapshot",
@@ -38,6 +39,9 @@ pub fn command_names() -> &'static [&'static str] {
         "workflow_report_insert_completed",
         "workflow_report_insert_failed",
         "overlay_insert_text",
+        "lock_export_target",
+        "unlock_export_target",
+        "export_target_lock_status",
     ]
 }
 
@@ -93,6 +97,7 @@ pub async fn workflow_command(
     app: AppHandle,
     req: WorkflowCommandRequest,
 ) -> Result<WorkflowView, String> {
+    crate::spawn_preflight_if_stale(&app, "lazy");
     let runtime = app.state::<RuntimeState>();
     let workflow = app.state::<VoiceWorkflow>();
     let audio = app.state::<RecordingRegistry>();
@@ -100,6 +105,7 @@ pub async fn workflow_command(
     let streaming_actor = app.state::<TranscriptionActor>();
     let mailbox = app.state::<UiEventMailbox>();
     let record_input_cache = app.state::<RecordInputCacheState>();
+    let task_state = app.state::<crate::task_manager::TaskManager>();
 
     let outcome = workflow
         .run_command(
@@ -110,6 +116,7 @@ pub async fn workflow_command(
                 streaming_actor: &streaming_actor,
                 mailbox: &mailbox,
                 record_input_cache: &record_input_cache,
+                task_state: &task_state,
             },
             req,
         )
@@ -132,14 +139,16 @@ pub fn workflow_apply_event(
         .map_err(render_workflow_error)
 }
 
+// Async so user post-processor plugins never run on the main thread.
 #[tauri::command]
-pub fn workflow_report_asr_completed(
+pub async fn workflow_report_asr_completed(
     workflow: State<'_, VoiceWorkflow>,
     mailbox: State<'_, UiEventMailbox>,
     req: WorkflowAsrCompletedRequest,
 ) -> Result<WorkflowView, String> {
     workflow
         .report_asr_completed(&mailbox, req)
+        .await
         .map_err(render_workflow_error)
 }
 
@@ -272,6 +281,48 @@ pub fn record_transcribe_cancel(
         .map_err(render_workflow_error)
 }
 
+pub const RECORDING_AUTO_STOPPED_EVENT: &str = "recording_auto_stopped";
+
+pub fn auto_stop_recording(app: &AppHandle, stop: crate::vad::AutoStop) {
+    let workflow = app.state::<VoiceWorkflow>();
+    let audio = app.state::<RecordingRegistry>();
+    let mailbox = app.state::<UiEventMailbox>();
+    match workflow.auto_stop_recording(&audio, &mailbox, &stop.recording_session_id) {
+        Ok(task) => {
+            let _ = app.emit(RECORDING_AUTO_STOPPED_EVENT, &stop);
+            if let Some(task) = task {
+                crate::voice_tasks::spawn(app.clone(), task);
+            }
+        }
+        Err(err) => {
+            if let Ok(dir) = data_dir::data_dir() {
+                crate::obs::event(
+                    &dir,
+                    Some(stop.task_id.as_str()),
+                    "Record",
+                    "RECORD.vad_auto_stop_skipped",
+                    "err",
+                    Some(serde_json::json!({
+                        "code": err.code,
+                        "message": err.message,
+                    })),
+                );
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub fn cancel_stage(
+    workflow: State<'_, VoiceWorkflow>,
+    mailbox: State<'_, UiEventMailbox>,
+    req: WorkflowCancelStageRequest,
+) -> Result<WorkflowView, String> {
+    workflow
+        .cancel_stage(&mailbox, req)
+        .map_err(render_workflow_error)
+}
+
 #[tauri::command]
 pub async fn rewrite_text(
     workflow: State<'_, VoiceWorkflow>,
@@ -300,6 +351,7 @@ pub async fn insert_text(
 #[tauri::command]
 pub async fn overlay_insert_text(
     task_state: State<'_, crate::task_manager::TaskManager>,
+    workflow: State<'_, VoiceWorkflow>,
     req: OverlayInsertTextRequest,
 ) -> Result<InsertResult, String> {
     if req.text.trim().is_empty() {
@@ -310,6 +362,10 @@ pub async fn overlay_insert_text(
     })?;
     crate::insertion::insert_text_after_focus(
         InsertTextRequest {
+            template_id: req
+                .transcript_id
+                .as_deref()
+                .and_then(|id| workflow.text_template_id(id)),
             transcript_id: req.transcript_id,
             text: req.text,
         },
@@ -319,6 +375,30 @@ pub async fn overlay_insert_text(
     .map_err(render_port_error)
 }
 
+// The foreground window when invoked from a hotkey; the last external window
+// when invoked from the TypeVoice UI (which is then foreground itself).
+#[tauri::command]
+pub fn lock_export_target(
+    task_state: State<'_, crate::task_manager::TaskManager>,
+) -> Result<ExportTargetLock, String> {
+    let hwnd = crate::export::external_foreground_window()
+        .or_else(|| task_state.last_external_hwnd_best_effort())
+        .ok_or_else(|| {
+            "E_EXPORT_TARGET_LOCK_UNAVAILABLE: no external window to lock".to_string()
+        })?;
+    crate::insertion::lock_export_target(hwnd).map_err(render_port_error)
+}
+
+#[tauri::command]
+pub fn unlock_export_target() -> Option<ExportTargetLock> {
+    crate::insertion::unlock_export_target()
+}
+
+#[tauri::command]
+pub fn export_target_lock_status() -> Option<ExportTargetLock> {
+    crate::insertion::export_target_lock()
+}
+
 fn normalize_task_id(task_id: Option<String>) -> Result<Option<String>, String> {
     let raw = match task_id {
         Some(v) => v.trim().to_string(),
@@ -397,5 +477,8 @@ mod tests {
         assert!(names.contains(&"workflow_report_asr_failed"));
         assert!(names.contains(&"workflow_rewrite"));
         assert!(names.contains(&"workflow_insert"));
+        assert!(names.contains(&"lock_export_target"));
+        assert!(names.contains(&"unlock_export_target"));
+        assert!(names.contains(&"export_target_lock_status"));
     }
 }
@@ -0,0 +1,168 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::dir_lock::{self, DataDirLock, LockOwner};
+use crate::obs;
+
+pub const DATA_DIR_LOCK_EVENT: &str = "tv_data_dir_lock";
+
+// What the UI shows: while `held` is false every store write fails with
+// E_DATA_DIR_LOCKED until the user takes over.
+#[derive(Debug, Clone, Serialize)]
+pub struct DataDirLockStatus {
+    pub held: bool,
+    pub owner: Option<LockOwner>,
+    pub error: Option<String>,
+}
+
+type LockSlot = Arc<Mutex<Option<DataDirLock>>>;
+
+pub struct DataDirLockManager {
+    lock: LockSlot,
+    dir: Mutex<Option<PathBuf>>,
+}
+
+impl Default for DataDirLockManager {
+    fn default() -> Self {
+        Self {
+            lock: Arc::new(Mutex::new(None)),
+            dir: Mutex::new(None),
+        }
+    }
+}
+
+impl DataDirLockManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn status(&self) -> DataDirLockStatus {
+        let held = self.lock.lock().unwrap();
+        let dir = self.dir.lock().unwrap().clone();
+        DataDirLockStatus {
+            held: held.is_some(),
+            owner: match held.as_ref() {
+                Some(l) => Some(l.owner().clone()),
+                None => dir.as_deref().and_then(dir_lock::read_owner),
+            },
+            error: None,
+        }
+    }
+
+    // Called once at setup. Failing to get the lock is not fatal: the app
+    // starts read-only and the UI offers a takeover.
+    pub fn start(&self, app: &AppHandle, data_dir: &Path) -> DataDirLockStatus {
+        dir_lock::enforce(data_dir);
+        *self.dir.lock().unwrap() = Some(data_dir.to_path_buf());
+        let status = self.install(app, data_dir, dir_lock::acquire(data_dir, false));
+        spawn_heartbeat(app.clone(), self.lock.clone(), data_dir.to_path_buf());
+        status
+    }
+
+    // Blocks until the other instance lets go, up to about two heartbeats.
+    pub fn takeover(&self, app: &AppHandle) -> DataDirLockStatus {
+        let Some(dir) = self.dir.lock().unwrap().clone() else {
+            return DataDirLockStatus {
+                held: false,
+                owner: None,
+                error: Some("E_DATA_DIR_LOCK: lock was not started".to_string()),
+            };
+        };
+        if self.lock.lock().unwrap().is_some() {
+            return self.status();
+        }
+        self.install(app, &dir, dir_lock::acquire(&dir, true))
+    }
+
+    fn install(
+        &self,
+        app: &AppHandle,
+        data_dir: &Path,
+        acquired: anyhow::Result<DataDirLock>,
+    ) -> DataDirLockStatus {
+        let status = match acquired {
+            Ok(l) => {
+                *self.lock.lock().unwrap() = Some(l);
+                obs::event(data_dir, None, "App", "APP.data_dir_lock", "ok", None);
+                self.status()
+            }
+            Err(e) => {
+                obs::event_err_anyhow(
+                    data_dir,
+                    obs::ErrorEvent {
+                        task_id: None,
+                        stage: "App",
+                        step_id: "APP.data_dir_lock",
+                        kind: "io",
+                        code: "E_DATA_DIR_LOCKED",
+                        ctx: None,
+                    },
+                    &e,
+                );
+                DataDirLockStatus {
+                    error: Some(e.to_string()),
+                    ..self.status()
+                }
+            }
+        };
+        let _ = app.emit(DATA_DIR_LOCK_EVENT, status.clone());
+        status
+    }
+}
+
+// Keeps the owner file fresh for instances on other hosts, and lets go as
+// soon as another instance has claimed the data dir. The slot is only locked
+// to copy the owner and to apply the result, never across file I/O, so
+// `status` and `takeover` are not held up by a slow disk.
+fn spawn_heartbeat(app: AppHandle, slot: LockSlot, data_dir: PathBuf) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(dir_lock::HEARTBEAT);
+        let Some(mut owner) = slot.lock().unwrap().as_ref().map(|l| l.owner().clone()) else {
+            continue;
+        };
+        let beat = dir_lock::refresh_heartbeat(&data_dir, &mut owner);
+        let mut held = slot.lock().unwrap();
+        if held
+            .as_ref()
+            .is_none_or(|l| l.owner().instance_id != owner.instance_id)
+        {
+            continue;
+        }
+        match beat {
+            Ok(true) => {
+                if let Some(lock) = held.as_mut() {
+                    lock.note_heartbeat(owner.heartbeat_ms);
+                }
+            }
+            Err(_) => {}
+            Ok(false) => {
+                // Dropping the lock touches the owner file; do it outside the slot.
+                let released = held.take();
+                drop(held);
+                drop(released);
+                obs::event(
+                    &data_dir,
+                    None,
+                    "App",
+                    "APP.data_dir_lock",
+                    "err",
+                    Some(serde_json::json!({"note": "taken_over"})),
+                );
+                let _ = app.emit(
+                    DATA_DIR_LOCK_EVENT,
+                    DataDirLockStatus {
+                        held: false,
+                        owner: dir_lock::read_owner(&data_dir),
+                        error: Some(
+                            "E_DATA_DIR_LOCKED: another TypeVoice instance took over the data dir"
+                                .to_string(),
+                        ),
+                    },
+                );
+            }
+        }
+    });
+}
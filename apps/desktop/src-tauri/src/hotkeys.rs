@@ -7,7 +7,7 @@ use tauri::AppHandle;
 use crate::obs::Span;
 use crate::settings::Settings;
 
-#[cfg(windows)]
+#[cfg(any(windows, target_os = "linux"))]
 const GLOBAL_HOTKEY_EVENT: &str = "tv_global_hotkey";
 #[cfg(any(windows, test))]
 const ALT_TAP_MAX_MS: i64 = 350;
@@ -16,6 +16,8 @@ const ALT_TAP_MAX_MS: i64 = 350;
 struct HotkeyConfig {
     enabled: bool,
     primary: KeyKind,
+    debounce_ms: u64,
+    app_allowlist: Vec<String>,
 }
 
 fn hotkey_config_from_settings(s: &Settings) -> anyhow::Result<HotkeyConfig> {
@@ -23,9 +25,41 @@ fn hotkey_config_from_settings(s: &Settings) -> anyhow::Result<HotkeyConfig> {
     Ok(HotkeyConfig {
         enabled: cfg.enabled,
         primary: KeyKind::from_config_value(&cfg.primary)?,
+        debounce_ms: cfg.debounce_ms,
+        app_allowlist: cfg.app_allowlist,
     })
 }
 
+// One intent per burst of presses: a bouncing key, a double tap or the wake
+// word firing right after the hotkey would otherwise start and immediately
+// stop a task. Presses inside the window are dropped without moving it.
+#[derive(Debug, Default)]
+struct PrimaryDebounce {
+    window_ms: u64,
+    last_accepted_ms: Option<i64>,
+}
+
+impl PrimaryDebounce {
+    fn accept(&mut self, now_ms: i64) -> bool {
+        if let Some(last) = self.last_accepted_ms {
+            let since = now_ms.saturating_sub(last);
+            if since >= 0 && (since as u64) < self.window_ms {
+                return false;
+            }
+        }
+        self.last_accepted_ms = Some(now_ms);
+        true
+    }
+}
+
+static PRIMARY_DEBOUNCE: Mutex<PrimaryDebounce> = Mutex::new(PrimaryDebounce {
+    window_ms: crate::settings::DEFAULT_HOTKEY_DEBOUNCE_MS,
+    last_accepted_ms: None,
+});
+
+// Only the key listeners check it; the wake word is not tied to an app.
+static APP_ALLOWLIST: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
 #[derive(Debug, Clone, Serialize)]
 pub struct HotkeyAvailability {
     pub available: bool,
@@ -33,7 +67,16 @@ pub struct HotkeyAvailability {
     pub reason_code: Option<String>,
 }
 
-#[cfg(windows)]
+// Which listener drives the primary hotkey on this system; shown in settings.
+#[derive(Debug, Clone, Serialize)]
+pub struct HotkeyBackendStatus {
+    pub backend: &'static str,
+    pub available: bool,
+    pub reason: Option<String>,
+    pub reason_code: Option<String>,
+}
+
+#[cfg(any(windows, target_os = "linux"))]
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct GlobalHotkeyEvent {
@@ -41,13 +84,13 @@ struct GlobalHotkeyEvent {
     ts_ms: i64,
 }
 
-#[cfg(any(windows, test))]
+#[cfg(any(windows, target_os = "linux", test))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum HotkeyAction {
     Primary,
 }
 
-#[cfg(windows)]
+#[cfg(any(windows, target_os = "linux"))]
 impl HotkeyAction {
     fn as_str(self) -> &'static str {
         match self {
@@ -196,6 +239,8 @@ impl HotkeyManager {
                 return;
             }
         };
+        PRIMARY_DEBOUNCE.lock().unwrap().window_ms = cfg.debounce_ms;
+        *APP_ALLOWLIST.lock().unwrap() = cfg.app_allowlist.clone();
         let span = Span::start(
             data_dir,
             None,
@@ -204,6 +249,8 @@ impl HotkeyManager {
             Some(serde_json::json!({
                 "enabled": cfg.enabled,
                 "mode": "primary",
+                "debounce_ms": cfg.debounce_ms,
+                "app_allowlist": cfg.app_allowlist.len(),
             })),
         );
 
@@ -253,14 +300,18 @@ struct PlatformKeyboardListener {
     event_thread: Option<std::thread::JoinHandle<()>>,
 }
 
-#[cfg(not(windows))]
+#[cfg(target_os = "linux")]
+struct PlatformKeyboardListener {
+    portal_task: Option<tauri::async_runtime::JoinHandle<()>>,
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
 struct PlatformKeyboardListener;
 
 impl PlatformKeyboardListener {
     #[cfg(windows)]
     fn start(app: AppHandle, primary: KeyKind) -> anyhow::Result<Self> {
         use std::sync::mpsc;
-        use tauri::Emitter;
         use windows_sys::Win32::System::Threading::GetCurrentThreadId;
         use windows_sys::Win32::UI::WindowsAndMessaging::{
             DispatchMessageW, GetMessageW, SetWindowsHookExW, TranslateMessage,
@@ -349,14 +400,8 @@ impl PlatformKeyboardListener {
             .spawn(move || {
                 let mut detector = HotkeyDetector::new(primary);
                 while let Ok(signal) = signal_rx.recv() {
-                    if let Some(action) = detector.apply(signal) {
-                        let _ = app.emit(
-                            GLOBAL_HOTKEY_EVENT,
-                            GlobalHotkeyEvent {
-                                action: action.as_str(),
-                                ts_ms: now_ms(),
-                            },
-                        );
+                    if let Some(HotkeyAction::Primary) = detector.apply(signal) {
+                        emit_hotkey_action(&app);
                     }
                 }
             })?;
@@ -418,7 +463,18 @@ impl PlatformKeyboardListener {
         }
     }
 
-    #[cfg(not(windows))]
+    // X11 sessions have no listener yet; Wayland goes through the portal.
+    #[cfg(target_os = "linux")]
+    fn start(app: AppHandle, primary: KeyKind) -> anyhow::Result<Self> {
+        if !portal::wayland_session() {
+            return Ok(Self { portal_task: None });
+        }
+        Ok(Self {
+            portal_task: Some(portal::spawn(app, primary)),
+        })
+    }
+
+    #[cfg(not(any(windows, target_os = "linux")))]
     fn start(_app: AppHandle, _primary: KeyKind) -> anyhow::Result<Self> {
         Ok(Self)
     }
@@ -440,15 +496,120 @@ impl PlatformKeyboardListener {
         }
     }
 
-    #[cfg(not(windows))]
+    // Dropping the connection closes the portal session and its binding.
+    #[cfg(target_os = "linux")]
+    fn stop(&mut self) {
+        if let Some(task) = self.portal_task.take() {
+            task.abort();
+        }
+    }
+
+    #[cfg(not(any(windows, target_os = "linux")))]
     fn stop(&mut self) {}
 }
 
+#[tauri::command]
+pub async fn hotkey_backend_status() -> HotkeyBackendStatus {
+    #[cfg(windows)]
+    {
+        HotkeyBackendStatus {
+            backend: "windows_hook",
+            available: true,
+            reason: None,
+            reason_code: None,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        if portal::wayland_session() {
+            portal::detect().await
+        } else {
+            HotkeyBackendStatus {
+                backend: "none",
+                available: false,
+                reason: Some("global hotkeys are not supported on X11 yet".to_string()),
+                reason_code: Some("E_HOTKEY_BACKEND_UNSUPPORTED".to_string()),
+            }
+        }
+    }
+
+    #[cfg(not(any(windows, target_os = "linux")))]
+    {
+        HotkeyBackendStatus {
+            backend: "none",
+            available: false,
+            reason: Some("global hotkeys are not supported on this platform".to_string()),
+            reason_code: Some("E_HOTKEY_BACKEND_UNSUPPORTED".to_string()),
+        }
+    }
+}
+
+// A press while an app outside the allowlist is focused is dropped before it
+// reaches the debounce, so it does not hold back the next allowed press.
+#[cfg(any(windows, target_os = "linux"))]
+fn emit_hotkey_action(app: &AppHandle) {
+    let allowlist = APP_ALLOWLIST.lock().unwrap().clone();
+    if !allowlist.is_empty() {
+        let foreground = foreground_process_image();
+        if !crate::settings::hotkey_app_allowed(&allowlist, foreground.as_deref()) {
+            let app_name = foreground
+                .as_deref()
+                .map(crate::formatting_profiles::process_file_name);
+            if let Ok(dir) = crate::data_dir::data_dir() {
+                crate::obs::event(
+                    &dir,
+                    None,
+                    "Hotkeys",
+                    "HK.app_not_allowed",
+                    "ok",
+                    Some(serde_json::json!({ "app": app_name })),
+                );
+            }
+            return;
+        }
+    }
+    emit_primary_action(app);
+}
+
+// Linux portals do not expose the focused app, so a non-empty allowlist
+// refuses every press there.
+#[cfg(any(windows, target_os = "linux"))]
+fn foreground_process_image() -> Option<String> {
+    #[cfg(windows)]
+    {
+        crate::context_capture_windows::foreground_window_info_now_best_effort()
+            .and_then(|w| w.process_image)
+    }
+    #[cfg(not(windows))]
+    {
+        None
+    }
+}
+
+// Also used by the wake word listener so both triggers drive the same frontend
+// flow and share one debounce window.
+#[cfg(any(windows, target_os = "linux"))]
+pub(crate) fn emit_primary_action(app: &AppHandle) {
+    use tauri::Emitter;
+    let ts_ms = now_ms();
+    if !PRIMARY_DEBOUNCE.lock().unwrap().accept(ts_ms) {
+        return;
+    }
+    let _ = app.emit(
+        GLOBAL_HOTKEY_EVENT,
+        GlobalHotkeyEvent {
+            action: HotkeyAction::Primary.as_str(),
+            ts_ms,
+        },
+    );
+}
+
 #[cfg(windows)]
 static KEY_SIGNAL_SLOT: std::sync::OnceLock<Mutex<Option<std::sync::mpsc::Sender<KeySignal>>>> =
     std::sync::OnceLock::new();
 
-#[cfg(windows)]
+#[cfg(any(windows, target_os = "linux"))]
 fn now_ms() -> i64 {
     match std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
         Ok(dur) => dur.as_millis() as i64,
@@ -456,10 +617,195 @@ fn now_ms() -> i64 {
     }
 }
 
+// org.freedesktop.portal.GlobalShortcuts: Wayland compositors do not let
+// clients see global key events, so the shortcut is registered with the
+// desktop portal, which shows its own confirmation dialog and signals
+// `Activated` on each press.
+#[cfg(target_os = "linux")]
+mod portal {
+    use std::collections::HashMap;
+
+    use anyhow::{anyhow, Context};
+    use futures_util::StreamExt;
+    use tauri::AppHandle;
+    use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+    use zbus::{Connection, Proxy};
+
+    use super::{HotkeyBackendStatus, KeyKind};
+    use crate::obs::Span;
+
+    const DEST: &str = "org.freedesktop.portal.Desktop";
+    const PATH: &str = "/org/freedesktop/portal/desktop";
+    const IFACE: &str = "org.freedesktop.portal.GlobalShortcuts";
+    const SHORTCUT_ID: &str = "typevoice-primary";
+
+    pub fn wayland_session() -> bool {
+        std::env::var("XDG_SESSION_TYPE").is_ok_and(|v| v.eq_ignore_ascii_case("wayland"))
+            || std::env::var_os("WAYLAND_DISPLAY").is_some()
+    }
+
+    // A portal shortcut needs a non-modifier key, so a bare modifier primary
+    // is offered as modifier+Space. The compositor dialog can rebind it.
+    fn preferred_trigger(primary: KeyKind) -> String {
+        match primary {
+            KeyKind::Alt => "ALT+space".to_string(),
+            KeyKind::Ctrl => "CTRL+space".to_string(),
+            KeyKind::Shift => "SHIFT+space".to_string(),
+            KeyKind::Function(n) => format!("F{n}"),
+            #[cfg(test)]
+            KeyKind::Other => "ALT+space".to_string(),
+        }
+    }
+
+    fn token() -> String {
+        format!("typevoice_{}", uuid::Uuid::new_v4().simple())
+    }
+
+    async fn shortcuts_proxy(conn: &Connection) -> zbus::Result<Proxy<'static>> {
+        Proxy::new(conn, DEST, PATH, IFACE).await
+    }
+
+    pub async fn detect() -> HotkeyBackendStatus {
+        let version = async {
+            let conn = Connection::session().await?;
+            shortcuts_proxy(&conn)
+                .await?
+                .get_property::<u32>("version")
+                .await
+        }
+        .await;
+        match version {
+            Ok(_) => HotkeyBackendStatus {
+                backend: "xdg_portal",
+                available: true,
+                reason: None,
+                reason_code: None,
+            },
+            Err(e) => HotkeyBackendStatus {
+                backend: "xdg_portal",
+                available: false,
+                reason: Some(format!("GlobalShortcuts portal is unavailable: {e}")),
+                reason_code: Some("E_HOTKEY_PORTAL_UNAVAILABLE".to_string()),
+            },
+        }
+    }
+
+    // Portal methods answer through a Request object whose path is derived
+    // from our unique name and the handle token, so the Response signal is
+    // subscribed before the call to avoid missing it.
+    async fn request<B>(
+        conn: &Connection,
+        portal: &Proxy<'_>,
+        method: &str,
+        handle_token: &str,
+        body: &B,
+    ) -> anyhow::Result<HashMap<String, OwnedValue>>
+    where
+        B: serde::Serialize + zbus::zvariant::DynamicType,
+    {
+        let sender = conn
+            .unique_name()
+            .ok_or_else(|| anyhow!("E_HOTKEY_PORTAL: session bus has no unique name"))?
+            .to_string();
+        let sender = sender.trim_start_matches(':').replace('.', "_");
+        let path = format!("{PATH}/request/{sender}/{handle_token}");
+        let req = Proxy::new(conn, DEST, path, "org.freedesktop.portal.Request").await?;
+        let mut responses = req.receive_signal("Response").await?;
+        let _: OwnedObjectPath = portal
+            .call(method, body)
+            .await
+            .with_context(|| format!("E_HOTKEY_PORTAL: {method} failed"))?;
+        let msg = responses
+            .next()
+            .await
+            .ok_or_else(|| anyhow!("E_HOTKEY_PORTAL: {method} got no response"))?;
+        let (code, results): (u32, HashMap<String, OwnedValue>) = msg.body().deserialize()?;
+        if code != 0 {
+            return Err(anyhow!(
+                "E_HOTKEY_PORTAL_DENIED: {method} was cancelled or denied (response {code})"
+            ));
+        }
+        Ok(results)
+    }
+
+    async fn run(app: &AppHandle, primary: KeyKind) -> anyhow::Result<()> {
+        let conn = Connection::session()
+            .await
+            .context("E_HOTKEY_PORTAL_UNAVAILABLE: connect to session bus failed")?;
+        let portal = shortcuts_proxy(&conn)
+            .await
+            .context("E_HOTKEY_PORTAL_UNAVAILABLE: GlobalShortcuts portal not found")?;
+        let mut activated = portal.receive_signal("Activated").await?;
+
+        let create_token = token();
+        let session_token = token();
+        let options = HashMap::from([
+            ("handle_token", Value::from(create_token.as_str())),
+            ("session_handle_token", Value::from(session_token.as_str())),
+        ]);
+        let mut created =
+            request(&conn, &portal, "CreateSession", &create_token, &(options,)).await?;
+        // Spec says `s`, some portals send `o`.
+        let session = match created.remove("session_handle").as_deref() {
+            Some(Value::Str(s)) => s.to_string(),
+            Some(Value::ObjectPath(p)) => p.to_string(),
+            _ => {
+                return Err(anyhow!(
+                    "E_HOTKEY_PORTAL: CreateSession returned no session"
+                ))
+            }
+        };
+        let session = ObjectPath::try_from(session.as_str())?;
+
+        let bind_token = token();
+        let shortcut = HashMap::from([
+            (
+                "description",
+                Value::from("Start or stop TypeVoice dictation"),
+            ),
+            ("preferred_trigger", Value::from(preferred_trigger(primary))),
+        ]);
+        let options = HashMap::from([("handle_token", Value::from(bind_token.as_str()))]);
+        request(
+            &conn,
+            &portal,
+            "BindShortcuts",
+            &bind_token,
+            &(&session, vec![(SHORTCUT_ID, shortcut)], "", options),
+        )
+        .await?;
+
+        while let Some(msg) = activated.next().await {
+            let Ok((handle, id, _ts, _opts)) =
+                msg.body()
+                    .deserialize::<(OwnedObjectPath, String, u64, HashMap<String, OwnedValue>)>()
+            else {
+                continue;
+            };
+            if handle.as_str() == session.as_str() && id == SHORTCUT_ID {
+                super::emit_hotkey_action(app);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn spawn(app: AppHandle, primary: KeyKind) -> tauri::async_runtime::JoinHandle<()> {
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = run(&app, primary).await {
+                if let Ok(dir) = crate::data_dir::data_dir() {
+                    let span = Span::start(&dir, None, "Hotkeys", "HK.portal", None);
+                    span.err_anyhow("portal", "E_HOTKEY_PORTAL", &e, None);
+                }
+            }
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
         hotkey_config_from_settings, HotkeyAction, HotkeyDetector, KeyKind, KeySignal, KeyState,
+        PrimaryDebounce,
     };
     use crate::settings::Settings;
 
@@ -585,4 +931,23 @@ mod tests {
             None
         );
     }
+
+    #[test]
+    fn debounce_drops_presses_inside_the_window() {
+        let mut d = PrimaryDebounce {
+            window_ms: 250,
+            last_accepted_ms: None,
+        };
+        assert!(d.accept(1_000));
+        assert!(!d.accept(1_100));
+        // Dropped presses do not extend the window.
+        assert!(!d.accept(1_249));
+        assert!(d.accept(1_250));
+        // A clock step backwards is not treated as a repeat.
+        assert!(d.accept(900));
+
+        let mut off = PrimaryDebounce::default();
+        assert!(off.accept(5));
+        assert!(off.accept(5));
+    }
 }
@@ -1,20 +1,36 @@
 mod commands;
-pub use typevoice_core::{context_pack, ports};
+pub use typevoice_core::{
+    context_pack, errors, export_format, noise_profile, ports, text_casing, text_normalize,
+};
 pub use typevoice_engine::{
-    audio_capture, rewrite, task_manager, transcription, transcription_actor, ui_events,
-    voice_tasks, voice_workflow, RuntimeState,
+    asr_compare, audio_capture, bug_report, capture_indicator, integrations, mic_test,
+    pipeline_runtime, rewrite, rewrite_compare, scheduler, schemas, task_manager, transcription,
+    transcription_actor, ui_events, vad, voice_tasks, voice_workflow, RuntimeState,
 };
 pub use typevoice_observability::obs;
 #[cfg(windows)]
 pub use typevoice_platform::context_capture_windows;
 pub use typevoice_platform::{
-    audio_device_notifications_windows, audio_devices_windows, context_capture, export, insertion,
-    overlay_layout, pipeline, record_input, record_input_cache, subprocess, toolchain,
+    audio_device_notifications_windows, audio_devices_windows, context_capture, export,
+    ffmpeg_spare, insertion, overlay_layout, permissions, pipeline, power, record_input,
+    record_input_cache, screen_share, subprocess, toolchain,
+};
+pub use typevoice_providers::{asr_warm_pool, doubao_asr, http_client, llm, remote_asr, webhook};
+pub use typevoice_storage::{
+    asr_accuracy, audit_log, backup, corrections, data_dir, dir_lock, formatting_profiles,
+    guest_mode, history, history_bulk, history_writer, integrity, settings, settings_validation,
+    workspaces,
 };
-pub use typevoice_providers::{doubao_asr, llm, remote_asr};
-pub use typevoice_storage::{data_dir, history, settings};
+mod capture_light;
+mod data_dir_lock;
 mod hotkeys;
+mod settings_watch;
+mod wake_word;
 
+use asr_accuracy::AccuracyGroup;
+use audit_log::AuditEntry;
+use corrections::CorrectionSuggestion;
+use formatting_profiles::FormattingProfile;
 use history::HistoryItem;
 use llm::ApiKeyStatus;
 use obs::Span;
@@ -24,14 +40,30 @@ use task_manager::TaskManager;
 use tauri::Emitter;
 use tauri::Manager;
 
+// `status` is the label the overlay shows (themes rename phases), so unlike the
+// backend's own overlay updates it stays free text. Older windows send no
+// version.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 struct OverlayState {
+    #[serde(default = "ui_event_version")]
+    version: u32,
     visible: bool,
     status: String,
     detail: Option<String>,
     ts_ms: i64,
 }
 
+fn ui_event_version() -> u32 {
+    ui_events::UI_EVENT_VERSION
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CaptionsSetTextRequest {
+    text: String,
+    visible: Option<bool>,
+}
+
 #[derive(Debug, Clone, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct OverlayResizeRequest {
@@ -215,7 +247,7 @@ fn overlay_set_state(app: tauri::AppHandle, state: OverlayState) -> Result<(), S
     );
 
     if let Some(w) = app.get_webview_window("overlay") {
-        if state.visible {
+        if state.visible && screen_share::active().is_none() {
             overlay_layout::apply_overlay_layout(&w).map_err(|e| e.to_string())?;
             let _ = w.show();
         } else {
@@ -244,6 +276,18 @@ fn overlay_config() -> Result<settings::OverlayConfigResolved, String> {
     Ok(settings::resolve_overlay_config(&s))
 }
 
+#[tauri::command]
+fn capture_indicator_status() -> Vec<capture_indicator::CaptureSource> {
+    capture_indicator::active_sources()
+}
+
+#[tauri::command]
+fn overlay_get_theme() -> Result<settings::OverlayThemeResolved, String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let s = settings::load_settings_strict(&dir).map_err(|e| e.to_string())?;
+    Ok(settings::resolve_overlay_theme(&s))
+}
+
 #[tauri::command]
 fn overlay_save_position(app: tauri::AppHandle) -> Result<(), String> {
     let Some(w) = app.get_webview_window("overlay") else {
@@ -251,13 +295,64 @@ fn overlay_save_position(app: tauri::AppHandle) -> Result<(), String> {
     };
     let pos = w.outer_position().map_err(|e| e.to_string())?;
     let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
-    let mut s = settings::load_settings_strict(&dir).map_err(|e| e.to_string())?;
-    s.overlay_position_x = Some(pos.x as i64);
-    s.overlay_position_y = Some(pos.y as i64);
-    settings::save_settings(&dir, &s).map_err(|e| e.to_string())?;
+    settings::modify_settings(&dir, |s| {
+        s.overlay_position_x = Some(pos.x as i64);
+        s.overlay_position_y = Some(pos.y as i64);
+        Ok(true)
+    })
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+fn overlay_captions_set_text(
+    app: tauri::AppHandle,
+    req: CaptionsSetTextRequest,
+) -> Result<(), String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(
+        &dir,
+        None,
+        "CMD.overlay_captions_set_text",
+        Some(serde_json::json!({
+            "chars": req.text.chars().count(),
+            "visible": req.visible,
+        })),
+    );
+    let visible = req.visible.unwrap_or(!req.text.trim().is_empty());
+    ui_events::apply_captions_state(
+        &app,
+        ui_events::CaptionsState {
+            visible,
+            text: req.text,
+            ts_ms: obs::schema::now_ms(),
+        },
+    );
+    span.ok(None);
     Ok(())
 }
 
+#[tauri::command]
+fn captions_config() -> Result<settings::CaptionsConfigResolved, String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let s = settings::load_settings_strict(&dir).map_err(|e| e.to_string())?;
+    Ok(settings::resolve_captions_config(&s))
+}
+
+#[tauri::command]
+fn captions_monitors(app: tauri::AppHandle) -> Vec<overlay_layout::CaptionsMonitor> {
+    app.get_webview_window("captions")
+        .map(|w| overlay_layout::list_captions_monitors(&w))
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+fn wake_word_status(
+    wake_word: tauri::State<wake_word::WakeWordManager>,
+) -> wake_word::WakeWordStatus {
+    wake_word.status()
+}
+
 fn cmd_span(
     data_dir: &std::path::Path,
     task_id: Option<&str>,
@@ -274,6 +369,107 @@ fn runtime_toolchain_status(
     Ok(runtime.get_toolchain())
 }
 
+// A toolchain status is rechecked once it is this old, by the background
+// recheck and by a workflow command that finds it stale.
+const PREFLIGHT_RETRY_TTL: std::time::Duration = std::time::Duration::from_secs(15);
+
+#[tauri::command]
+fn refresh_runtime_preflight(app: tauri::AppHandle) -> Result<toolchain::ToolchainStatus, String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    Ok(refresh_preflight(&app, &dir, "command"))
+}
+
+// Runs the recheck on a blocking worker so the caller never waits on hashing
+// or `ffmpeg -version`; skipped while another check is in flight.
+pub(crate) fn spawn_preflight_if_stale(app: &tauri::AppHandle, reason: &'static str) {
+    if !app
+        .state::<RuntimeState>()
+        .toolchain_needs_recheck(PREFLIGHT_RETRY_TTL)
+    {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let runtime = app.state::<RuntimeState>();
+        let Some(_guard) = runtime.try_preflight_guard() else {
+            return;
+        };
+        if !runtime.toolchain_needs_recheck(PREFLIGHT_RETRY_TTL) {
+            return;
+        }
+        if let Ok(dir) = data_dir::data_dir() {
+            run_preflight(&app, &dir, reason);
+        }
+    });
+}
+
+fn refresh_preflight(
+    app: &tauri::AppHandle,
+    dir: &std::path::Path,
+    reason: &'static str,
+) -> toolchain::ToolchainStatus {
+    let runtime = app.state::<RuntimeState>();
+    let _guard = runtime.preflight_guard();
+    run_preflight(app, dir, reason)
+}
+
+// Re-runs the toolchain check; the caller holds the preflight guard. When it
+// turns ready, the startup work that was skipped runs now and the UI is told,
+// so installing ffmpeg needs no restart.
+fn run_preflight(
+    app: &tauri::AppHandle,
+    dir: &std::path::Path,
+    reason: &'static str,
+) -> toolchain::ToolchainStatus {
+    let runtime = app.state::<RuntimeState>();
+    let was_ready = runtime.get_toolchain().ready;
+    let st = toolchain::initialize_and_verify(app, dir);
+    runtime.set_toolchain(st.clone());
+    if st.ready != was_ready {
+        obs::event(
+            dir,
+            None,
+            "App",
+            "APP.toolchain_status_changed",
+            "ok",
+            Some(serde_json::json!({
+                "reason": reason,
+                "ready": st.ready,
+                "code": st.code,
+            })),
+        );
+        if st.ready {
+            on_toolchain_ready(app, dir, reason);
+        }
+        let _ = app.emit("tv_runtime_toolchain_changed", &st);
+    }
+    st
+}
+
+fn spawn_preflight_recheck(app: tauri::AppHandle) {
+    let _ = std::thread::Builder::new()
+        .name("toolchain_preflight_recheck".to_string())
+        .spawn(move || loop {
+            std::thread::sleep(PREFLIGHT_RETRY_TTL);
+            spawn_preflight_if_stale(&app, "background");
+        });
+}
+
+fn on_toolchain_ready(app: &tauri::AppHandle, dir: &std::path::Path, reason: &'static str) {
+    let record_input_cache = app.state::<record_input_cache::RecordInputCacheState>();
+    let _ = record_input_cache.refresh_blocking(dir, reason);
+    let listener = app.state::<audio_device_notifications_windows::AudioDeviceNotificationState>();
+    let handle = app.clone();
+    listener.start_best_effort(
+        dir,
+        record_input_cache.inner().clone(),
+        std::sync::Arc::new(move |change| {
+            let _ = handle.emit("tv_audio_devices_changed", change);
+        }),
+    );
+    app.state::<TaskManager>().warmup_context_best_effort();
+}
+
 #[tauri::command]
 fn abort_pending_task(
     workflow: tauri::State<voice_workflow::VoiceWorkflow>,
@@ -455,6 +651,8 @@ async fn check_remote_asr_api_key(
             }
         }),
         concurrency: 1,
+        lane: remote_asr::AsrLane::Interactive,
+        resume: false,
     };
 
     match remote_asr::check_api_key_live(&cfg).await {
@@ -536,6 +734,248 @@ async fn check_doubao_asr_credentials() -> Result<ApiCheckResult, String> {
     }
 }
 
+#[tauri::command]
+fn set_proxy_credentials(username: &str, password: &str) -> Result<(), String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(
+        &dir,
+        None,
+        "CMD.set_proxy_credentials",
+        Some(serde_json::json!({
+            "username_chars": username.len(),
+            "password_chars": password.len(),
+        })),
+    );
+    match http_client::set_credentials(username, password) {
+        Ok(()) => {
+            span.ok(None);
+            Ok(())
+        }
+        Err(e) => {
+            span.err_anyhow("auth", "E_CMD_SET_PROXY_CREDENTIALS", &e, None);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn clear_proxy_credentials() -> Result<(), String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(&dir, None, "CMD.clear_proxy_credentials", None);
+    match http_client::clear_credentials() {
+        Ok(()) => {
+            span.ok(None);
+            Ok(())
+        }
+        Err(e) => {
+            span.err_anyhow("auth", "E_CMD_CLEAR_PROXY_CREDENTIALS", &e, None);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn proxy_credentials_status() -> Result<ApiKeyStatus, String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(&dir, None, "CMD.proxy_credentials_status", None);
+    let st = http_client::credentials_status();
+    span.ok(Some(
+        serde_json::json!({"configured": st.configured, "source": st.source, "reason": st.reason}),
+    ));
+    Ok(st)
+}
+
+#[tauri::command]
+fn set_webhook_secret(secret: &str) -> Result<(), String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(
+        &dir,
+        None,
+        "CMD.set_webhook_secret",
+        Some(serde_json::json!({ "secret_chars": secret.len() })),
+    );
+    match webhook::set_secret(secret) {
+        Ok(()) => {
+            span.ok(None);
+            Ok(())
+        }
+        Err(e) => {
+            span.err_anyhow("auth", "E_CMD_SET_WEBHOOK_SECRET", &e, None);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn clear_webhook_secret() -> Result<(), String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(&dir, None, "CMD.clear_webhook_secret", None);
+    match webhook::clear_secret() {
+        Ok(()) => {
+            span.ok(None);
+            Ok(())
+        }
+        Err(e) => {
+            span.err_anyhow("auth", "E_CMD_CLEAR_WEBHOOK_SECRET", &e, None);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn webhook_secret_status() -> Result<ApiKeyStatus, String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(&dir, None, "CMD.webhook_secret_status", None);
+    let st = webhook::secret_status();
+    span.ok(Some(
+        serde_json::json!({"configured": st.configured, "source": st.source, "reason": st.reason}),
+    ));
+    Ok(st)
+}
+
+#[tauri::command]
+fn set_mqtt_password(password: &str) -> Result<(), String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(
+        &dir,
+        None,
+        "CMD.set_mqtt_password",
+        Some(serde_json::json!({ "password_chars": password.len() })),
+    );
+    match integrations::mqtt::set_password(password) {
+        Ok(()) => {
+            span.ok(None);
+            Ok(())
+        }
+        Err(e) => {
+            span.err_anyhow("auth", "E_CMD_SET_MQTT_PASSWORD", &e, None);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn clear_mqtt_password() -> Result<(), String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(&dir, None, "CMD.clear_mqtt_password", None);
+    match integrations::mqtt::clear_password() {
+        Ok(()) => {
+            span.ok(None);
+            Ok(())
+        }
+        Err(e) => {
+            span.err_anyhow("auth", "E_CMD_CLEAR_MQTT_PASSWORD", &e, None);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn mqtt_password_status() -> Result<ApiKeyStatus, String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(&dir, None, "CMD.mqtt_password_status", None);
+    let st = integrations::mqtt::password_status();
+    span.ok(Some(
+        serde_json::json!({"configured": st.configured, "source": st.source, "reason": st.reason}),
+    ));
+    Ok(st)
+}
+
+// Tests the unsaved form values with the stored credentials. The target defaults
+// to the configured LLM endpoint, then remote ASR, so the check matches real traffic.
+#[tauri::command]
+async fn test_proxy(
+    proxy_url: String,
+    no_proxy: Vec<String>,
+    target_url: Option<String>,
+) -> Result<ApiCheckResult, String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(
+        &dir,
+        None,
+        "CMD.test_proxy",
+        Some(serde_json::json!({
+            "has_proxy_url": !proxy_url.trim().is_empty(),
+            "no_proxy_count": no_proxy.len(),
+        })),
+    );
+    let saved = settings::load_settings(&dir).unwrap_or_default();
+    let draft = Settings {
+        proxy_url: Some(proxy_url),
+        proxy_no_proxy: Some(no_proxy),
+        ..saved.clone()
+    };
+    if settings::resolve_proxy(&draft).is_none() {
+        return Ok(api_check_failure("Enter the proxy URL.".to_string()));
+    }
+    if let Err(e) = http_client::ensure_online(&draft) {
+        span.err_anyhow("config", "E_CMD_TEST_PROXY", &e, None);
+        return Ok(api_check_failure(proxy_check_error_message(&e)));
+    }
+    let opts = match http_client::client_options(&draft) {
+        Ok(v) => v,
+        Err(e) => {
+            span.err_anyhow("config", "E_CMD_TEST_PROXY", &e, None);
+            return Ok(api_check_failure(proxy_check_error_message(&e)));
+        }
+    };
+    let target = target_url
+        .or(saved.llm_base_url)
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| settings::resolve_remote_asr_url(&saved));
+    match http_client::test_proxy(&opts, &target).await {
+        Ok(out) => {
+            span.ok(Some(serde_json::json!({
+                "status": out.status,
+                "elapsed_ms": out.elapsed_ms,
+            })));
+            Ok(api_check_success(&format!(
+                "Proxy reached {target} (HTTP {}, {} ms).",
+                out.status, out.elapsed_ms
+            )))
+        }
+        Err(e) => {
+            span.err_anyhow("net", "E_CMD_TEST_PROXY", &e, None);
+            Ok(api_check_failure(proxy_check_error_message(&e)))
+        }
+    }
+}
+
+fn proxy_check_error_message(e: &anyhow::Error) -> String {
+    let raw = e.to_string();
+    if raw.contains("E_PROXY_CONFIG") {
+        return "Proxy URL must look like http://host:port or socks5://host:port.".to_string();
+    }
+    if raw.contains("E_SECRETS_") || raw.contains("keyring") {
+        return "Stored proxy credentials could not be read.".to_string();
+    }
+    if let Some(msg) = offline_error_message(&raw).or_else(|| tls_error_message(&raw)) {
+        return msg;
+    }
+    if raw.contains("E_PROXY_TEST_SEND") {
+        return "Cannot reach the target through the proxy. Check the proxy address and credentials.".to_string();
+    }
+    "Proxy check failed.".to_string()
+}
+
+fn offline_error_message(raw: &str) -> Option<String> {
+    raw.contains("E_OFFLINE_MODE")
+        .then(|| "Offline mode is on. Turn it off to run network checks.".to_string())
+}
+
+fn tls_error_message(raw: &str) -> Option<String> {
+    if raw.contains("E_TLS_VERIFY") {
+        return Some(
+            "The server certificate is not trusted. Add the internal CA bundle or pin the certificate fingerprint.".to_string(),
+        );
+    }
+    if raw.contains("E_TLS_CONFIG") {
+        return Some("The custom CA bundle or pinned fingerprint is invalid.".to_string());
+    }
+    None
+}
+
 fn api_check_success(message: &str) -> ApiCheckResult {
     ApiCheckResult {
         ok: true,
@@ -555,7 +995,7 @@ fn llm_check_error_message(e: &anyhow::Error) -> String {
     if raw.contains("E_LLM_CONFIG_MODEL_MISSING") {
         return "Enter the LLM model.".to_string();
     }
-    if raw.contains("keyring") || raw.contains("empty api key") {
+    if raw.contains("keyring") || raw.contains("E_SECRETS_") || raw.contains("empty api key") {
         return "Save the LLM API key or set TYPEVOICE_LLM_API_KEY.".to_string();
     }
     if raw.contains("E_LLM_CHECK_HTTP_STATUS_401") || raw.contains("E_LLM_CHECK_HTTP_STATUS_403") {
@@ -567,6 +1007,9 @@ fn llm_check_error_message(e: &anyhow::Error) -> String {
     if raw.contains("E_LLM_CHECK_HTTP_STATUS_429") {
         return "The LLM API rate limit has been reached.".to_string();
     }
+    if let Some(msg) = offline_error_message(&raw).or_else(|| tls_error_message(&raw)) {
+        return msg;
+    }
     if raw.contains("E_LLM_CHECK_HTTP_SEND") {
         return "Cannot reach the LLM API. Check the base URL and network.".to_string();
     }
@@ -589,6 +1032,10 @@ fn remote_asr_check_error_message(e: &remote_asr::RemoteAsrError) -> String {
         "E_REMOTE_ASR_HTTP_STATUS_429" => {
             "The remote ASR API rate limit has been reached.".to_string()
         }
+        code @ ("E_TLS_VERIFY" | "E_TLS_CONFIG") => {
+            tls_error_message(code).unwrap_or_else(|| "TLS check failed.".to_string())
+        }
+        "E_OFFLINE_MODE" => offline_error_message(&e.code).unwrap_or_default(),
         "E_REMOTE_ASR_HTTP_SEND" => {
             "Cannot reach the remote ASR API. Check the URL and network.".to_string()
         }
@@ -604,10 +1051,14 @@ fn remote_asr_check_error_message(e: &remote_asr::RemoteAsrError) -> String {
 
 fn doubao_check_error_message(e: &anyhow::Error) -> String {
     let raw = e.to_string();
+    if let Some(msg) = offline_error_message(&raw) {
+        return msg;
+    }
     if raw.contains("E_DOUBAO_ASR_CREDENTIALS_MISSING")
         || raw.contains("E_DOUBAO_ASR_APP_KEY_MISSING")
         || raw.contains("E_DOUBAO_ASR_ACCESS_KEY_MISSING")
         || raw.contains("keyring")
+        || raw.contains("E_SECRETS_")
     {
         return "Save the Doubao ASR App Key and Access Key or set the Doubao ASR environment variables.".to_string();
     }
@@ -626,8 +1077,11 @@ fn doubao_check_error_message(e: &anyhow::Error) -> String {
 
 fn history_db_path() -> Result<std::path::PathBuf, String> {
     let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
-    std::fs::create_dir_all(&dir).ok();
-    Ok(dir.join("history.sqlite3"))
+    let db = workspaces::history_db_path(&dir);
+    if let Some(parent) = db.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    Ok(db)
 }
 
 #[tauri::command]
@@ -653,19 +1107,39 @@ fn history_append(item: HistoryItem) -> Result<(), String> {
 }
 
 #[tauri::command]
-fn history_list(limit: i64, before_ms: Option<i64>) -> Result<Vec<HistoryItem>, String> {
+fn history_list(
+    limit: i64,
+    before_ms: Option<i64>,
+    tag: Option<String>,
+) -> Result<Vec<history::TimedHistoryItem>, String> {
     let db = history_db_path()?;
+    // The UI refreshes right after a task completes; let queued writes land
+    // first, but do not wait out a retry backoff.
+    let _ = history_writer::flush(500);
     let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
     let span = cmd_span(
         &dir,
         None,
         "CMD.history_list",
-        Some(serde_json::json!({"limit": limit, "before_ms": before_ms})),
+        Some(serde_json::json!({
+            "limit": limit,
+            "before_ms": before_ms,
+            "tag_filter": tag.is_some(),
+        })),
     );
-    match history::list(&db, limit, before_ms) {
+    let rows = match tag.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        Some(tag) => history::list_by_tag(&db, tag, limit, before_ms),
+        None => history::list(&db, limit, before_ms),
+    };
+    let time_format = settings::load_settings(&dir)
+        .map(|s| settings::resolve_time_format(&s))
+        .unwrap_or_default();
+    match rows {
         Ok(v) => {
             span.ok(Some(serde_json::json!({"count": v.len()})));
-            Ok(v)
+            Ok(v.into_iter()
+                .map(|item| history::TimedHistoryItem::new(item, time_format))
+                .collect())
         }
         Err(e) => {
             span.err_anyhow("history", "E_CMD_HISTORY_LIST", &e, None);
@@ -675,24 +1149,451 @@ fn history_list(limit: i64, before_ms: Option<i64>) -> Result<Vec<HistoryItem>,
 }
 
 #[tauri::command]
-fn history_clear() -> Result<(), String> {
+fn history_search(
+    query: String,
+    limit: i64,
+    before_ms: Option<i64>,
+) -> Result<Vec<history::TimedHistoryItem>, String> {
     let db = history_db_path()?;
+    let _ = history_writer::flush(500);
     let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
-    let span = cmd_span(&dir, None, "CMD.history_clear", None);
-    match history::clear(&db) {
+    let span = cmd_span(
+        &dir,
+        None,
+        "CMD.history_search",
+        Some(serde_json::json!({
+            "query_chars": query.chars().count(),
+            "limit": limit,
+            "before_ms": before_ms,
+        })),
+    );
+    let time_format = settings::load_settings(&dir)
+        .map(|s| settings::resolve_time_format(&s))
+        .unwrap_or_default();
+    match history::search(&db, &query, limit, before_ms) {
+        Ok(v) => {
+            span.ok(Some(serde_json::json!({"count": v.len()})));
+            Ok(v.into_iter()
+                .map(|item| history::TimedHistoryItem::new(item, time_format))
+                .collect())
+        }
+        Err(e) => {
+            span.err_anyhow("history", "E_CMD_HISTORY_SEARCH", &e, None);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn history_update_final_text(task_id: String, final_text: String) -> Result<(), String> {
+    if final_text.trim().is_empty() {
+        return Err("E_HISTORY_EMPTY_TEXT: final text cannot be empty".to_string());
+    }
+    let db = history_db_path()?;
+    // The row may still be queued right after a task completes.
+    let _ = history_writer::flush(500);
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(
+        &dir,
+        Some(task_id.as_str()),
+        "CMD.history_update_final_text",
+        Some(serde_json::json!({"final_chars": final_text.len()})),
+    );
+    match history::update_corrected_text(&db, &task_id, &final_text) {
         Ok(()) => {
-            span.ok(None);
+            let accuracy = history::get(&db, &task_id).ok().flatten().and_then(|row| {
+                asr_accuracy::record_correction(
+                    &dir,
+                    &task_id,
+                    &row.asr_text,
+                    &final_text,
+                    &row.device_used,
+                )
+            });
+            span.ok(Some(serde_json::json!({
+                "wer": accuracy.map(|a| a.wer()),
+                "cer": accuracy.map(|a| a.cer()),
+            })));
             Ok(())
         }
         Err(e) => {
-            span.err_anyhow("history", "E_CMD_HISTORY_CLEAR", &e, None);
+            span.err_anyhow("history", "E_CMD_HISTORY_UPDATE", &e, None);
             Err(e.to_string())
         }
     }
 }
 
 #[tauri::command]
-fn get_settings() -> Result<Settings, String> {
+fn history_set_tags(task_id: String, tags: Vec<String>) -> Result<Vec<String>, String> {
+    let db = history_db_path()?;
+    let _ = history_writer::flush(500);
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(&dir, Some(task_id.as_str()), "CMD.history_set_tags", None);
+    match history::set_tags(&db, &task_id, &tags) {
+        Ok(tags) => {
+            span.ok(Some(serde_json::json!({"tags": tags.len()})));
+            Ok(tags)
+        }
+        Err(e) => {
+            span.err_anyhow("history", "E_CMD_HISTORY_SET_TAGS", &e, None);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn history_set_note(task_id: String, note: String) -> Result<(), String> {
+    let db = history_db_path()?;
+    let _ = history_writer::flush(500);
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(
+        &dir,
+        Some(task_id.as_str()),
+        "CMD.history_set_note",
+        Some(serde_json::json!({"note_chars": note.len()})),
+    );
+    match history::set_note(&db, &task_id, &note) {
+        Ok(()) => {
+            span.ok(None);
+            Ok(())
+        }
+        Err(e) => {
+            span.err_anyhow("history", "E_CMD_HISTORY_SET_NOTE", &e, None);
+            Err(e.to_string())
+        }
+    }
+}
+
+// Runs off the main thread; progress goes out as tv_history_bulk_progress
+// after every batch.
+#[tauri::command]
+async fn history_bulk(
+    app: tauri::AppHandle,
+    action: history_bulk::BulkAction,
+    filter: history::HistoryFilter,
+) -> Result<history_bulk::BulkResult, String> {
+    let db = history_db_path()?;
+    let _ = history_writer::flush(500);
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let time_format = settings::load_settings(&dir)
+        .map(|s| settings::resolve_time_format(&s))
+        .unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        let span = cmd_span(
+            &dir,
+            None,
+            "CMD.history_bulk",
+            Some(serde_json::json!({"action": action.name()})),
+        );
+        let r = history_bulk::run(&db, &action, &filter, time_format, |progress| {
+            let _ = app.emit("tv_history_bulk_progress", progress);
+        });
+        match r {
+            Ok(out) => {
+                span.ok(Some(serde_json::json!({
+                    "matched": out.matched,
+                    "affected": out.affected,
+                })));
+                Ok(out)
+            }
+            Err(e) => {
+                span.err_anyhow("history", "E_CMD_HISTORY_BULK", &e, None);
+                Err(e.to_string())
+            }
+        }
+    })
+    .await
+    .map_err(|e| format!("E_INTERNAL: history bulk join failed: {e}"))?
+}
+
+// Queued history writes land first so the snapshot has them.
+#[tauri::command]
+async fn backup_data(path: String) -> Result<backup::BackupResult, String> {
+    let _ = history_writer::flush(500);
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let span = cmd_span(&dir, None, "CMD.backup_data", None);
+        match backup::backup_data(&dir, std::path::Path::new(path.trim())) {
+            Ok(out) => {
+                span.ok(None);
+                Ok(out)
+            }
+            Err(e) => {
+                span.err_anyhow("io", "E_CMD_BACKUP_DATA", &e, None);
+                Err(e.to_string())
+            }
+        }
+    })
+    .await
+    .map_err(|e| format!("E_INTERNAL: backup join failed: {e}"))?
+}
+
+// Settings reach the UI through tv_settings_changed; the caller reloads history.
+#[tauri::command]
+async fn restore_data(path: String) -> Result<backup::RestoreResult, String> {
+    let _ = history_writer::flush(500);
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let span = cmd_span(&dir, None, "CMD.restore_data", None);
+        match backup::restore_data(&dir, std::path::Path::new(path.trim())) {
+            Ok(out) => {
+                span.ok(None);
+                Ok(out)
+            }
+            Err(e) => {
+                span.err_anyhow("io", "E_CMD_RESTORE_DATA", &e, None);
+                Err(e.to_string())
+            }
+        }
+    })
+    .await
+    .map_err(|e| format!("E_INTERNAL: restore join failed: {e}"))?
+}
+
+#[tauri::command]
+fn suggest_corrections(min_occurrences: Option<u32>) -> Result<Vec<CorrectionSuggestion>, String> {
+    let db = history_db_path()?;
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let min_occurrences = min_occurrences.unwrap_or(corrections::DEFAULT_MIN_OCCURRENCES);
+    let span = cmd_span(
+        &dir,
+        None,
+        "CMD.suggest_corrections",
+        Some(serde_json::json!({"min_occurrences": min_occurrences})),
+    );
+    let glossary = settings::load_settings_strict(&dir)
+        .ok()
+        .and_then(|s| s.rewrite_glossary)
+        .unwrap_or_default();
+    match history::list_corrections(&db, corrections::DEFAULT_SAMPLE_LIMIT) {
+        Ok(samples) => {
+            let out = corrections::suggest(&samples, &glossary, min_occurrences);
+            span.ok(Some(serde_json::json!({
+                "samples": samples.len(),
+                "suggestions": out.len(),
+            })));
+            Ok(out)
+        }
+        Err(e) => {
+            span.err_anyhow("history", "E_CMD_SUGGEST_CORRECTIONS", &e, None);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn asr_accuracy_stats() -> Result<Vec<AccuracyGroup>, String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(&dir, None, "CMD.asr_accuracy_stats", None);
+    let _ = obs::flush(500);
+    let groups = asr_accuracy::stats(&dir);
+    span.ok(Some(serde_json::json!({"groups": groups.len()})));
+    Ok(groups)
+}
+
+#[tauri::command]
+fn history_clear() -> Result<(), String> {
+    let db = history_db_path()?;
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(&dir, None, "CMD.history_clear", None);
+    match history::clear(&db) {
+        Ok(()) => {
+            span.ok(None);
+            Ok(())
+        }
+        Err(e) => {
+            span.err_anyhow("history", "E_CMD_HISTORY_CLEAR", &e, None);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn list_llm_captures() -> Result<Vec<obs::llm_capture::LlmCapture>, String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(&dir, None, "CMD.list_llm_captures", None);
+    match obs::llm_capture::list(&dir) {
+        Ok(v) => {
+            span.ok(Some(serde_json::json!({"count": v.len()})));
+            Ok(v)
+        }
+        Err(e) => {
+            span.err_anyhow("io", "E_CMD_LIST_LLM_CAPTURES", &e, None);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn list_task_artifacts(task_id: &str) -> Result<Vec<obs::debug::TaskArtifact>, String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(&dir, Some(task_id), "CMD.list_task_artifacts", None);
+    match obs::debug::list_task_artifacts(&dir, task_id) {
+        Ok(v) => {
+            span.ok(Some(serde_json::json!({"count": v.len()})));
+            Ok(v)
+        }
+        Err(e) => {
+            span.err_anyhow("io", "E_CMD_LIST_TASK_ARTIFACTS", &e, None);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn read_task_artifact(
+    task_id: &str,
+    name: &str,
+) -> Result<obs::debug::TaskArtifactContent, String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(
+        &dir,
+        Some(task_id),
+        "CMD.read_task_artifact",
+        Some(serde_json::json!({ "name": name })),
+    );
+    match obs::debug::read_task_artifact(&dir, task_id, name) {
+        Ok(v) => {
+            span.ok(Some(serde_json::json!({
+                "bytes": v.bytes,
+                "binary": v.text.is_none(),
+                "truncated": v.truncated,
+            })));
+            Ok(v)
+        }
+        Err(e) => {
+            span.err_anyhow("io", "E_CMD_READ_TASK_ARTIFACT", &e, None);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn audit_list(limit: i64) -> Result<Vec<AuditEntry>, String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).ok();
+    let span = cmd_span(
+        &dir,
+        None,
+        "CMD.audit_list",
+        Some(serde_json::json!({"limit": limit})),
+    );
+    match audit_log::list(&audit_log::audit_db_path(&dir), limit) {
+        Ok(v) => {
+            span.ok(Some(serde_json::json!({"count": v.len()})));
+            Ok(v)
+        }
+        Err(e) => {
+            span.err_anyhow("audit", "E_CMD_AUDIT_LIST", &e, None);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn audit_clear() -> Result<(), String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(&dir, None, "CMD.audit_clear", None);
+    match audit_log::clear(&audit_log::audit_db_path(&dir)) {
+        Ok(()) => {
+            span.ok(None);
+            Ok(())
+        }
+        Err(e) => {
+            span.err_anyhow("audit", "E_CMD_AUDIT_CLEAR", &e, None);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn rewrite_preview(text: String) -> Result<llm::RewritePreview, String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(
+        &dir,
+        None,
+        "CMD.rewrite_preview",
+        Some(serde_json::json!({"text_chars": text.len()})),
+    );
+    let s = match settings::load_settings_strict(&dir) {
+        Ok(v) => v,
+        Err(e) => {
+            span.err_anyhow("settings", "E_CMD_REWRITE_PREVIEW", &e, None);
+            return Err(e.to_string());
+        }
+    };
+    let prompt = s.llm_prompt.clone().unwrap_or_default();
+    let preview = llm::rewrite_preview(&s, &prompt, &text);
+    span.ok(Some(serde_json::json!({
+        "has_preamble": preview.has_preamble,
+        "system_prompt_chars": preview.system_prompt.len(),
+    })));
+    Ok(preview)
+}
+
+#[tauri::command]
+fn list_formatting_profiles() -> Result<Vec<FormattingProfile>, String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(&dir, None, "CMD.list_formatting_profiles", None);
+    match formatting_profiles::load_profiles(&dir) {
+        Ok(v) => {
+            span.ok(Some(serde_json::json!({"count": v.len()})));
+            Ok(v)
+        }
+        Err(e) => {
+            span.err_anyhow("settings", "E_CMD_FORMATTING_PROFILES_LIST", &e, None);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn upsert_formatting_profile(profile: FormattingProfile) -> Result<Vec<FormattingProfile>, String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(
+        &dir,
+        None,
+        "CMD.upsert_formatting_profile",
+        Some(serde_json::json!({
+            "id": profile.id,
+            "process_names": profile.process_names.len(),
+        })),
+    );
+    match formatting_profiles::upsert_profile(&dir, profile) {
+        Ok(v) => {
+            span.ok(Some(serde_json::json!({"count": v.len()})));
+            Ok(v)
+        }
+        Err(e) => {
+            span.err_anyhow("settings", "E_CMD_FORMATTING_PROFILE_UPSERT", &e, None);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn delete_formatting_profile(id: &str) -> Result<Vec<FormattingProfile>, String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(
+        &dir,
+        None,
+        "CMD.delete_formatting_profile",
+        Some(serde_json::json!({"id": id})),
+    );
+    match formatting_profiles::delete_profile(&dir, id) {
+        Ok(v) => {
+            span.ok(Some(serde_json::json!({"count": v.len()})));
+            Ok(v)
+        }
+        Err(e) => {
+            span.err_anyhow("settings", "E_CMD_FORMATTING_PROFILE_DELETE", &e, None);
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn get_settings() -> Result<Settings, String> {
     let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
     let span = cmd_span(&dir, None, "CMD.get_settings", None);
     match settings::load_settings_strict(&dir) {
@@ -758,6 +1659,191 @@ fn list_audio_capture_devices() -> Result<Vec<record_input::AudioCaptureDeviceVi
     }
 }
 
+#[tauri::command]
+async fn calibrate_noise_profile(
+    recordings: tauri::State<'_, audio_capture::RecordingRegistry>,
+    record_input_cache: tauri::State<'_, record_input_cache::RecordInputCacheState>,
+) -> Result<noise_profile::NoiseProfile, String> {
+    let recordings = recordings.inner().clone();
+    let record_input_cache = record_input_cache.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        recordings
+            .calibrate_noise_profile(&record_input_cache)
+            .map_err(|e| e.render())
+    })
+    .await
+    .map_err(|e| format!("E_NOISE_CALIBRATION_JOIN: {e}"))?
+}
+
+#[tauri::command]
+async fn mic_test(
+    recordings: tauri::State<'_, audio_capture::RecordingRegistry>,
+    record_input_cache: tauri::State<'_, record_input_cache::RecordInputCacheState>,
+) -> Result<mic_test::MicTestResult, String> {
+    mic_test::run_mic_test(
+        recordings.inner().clone(),
+        record_input_cache.inner().clone(),
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn record_bug_report_sample(
+    recordings: tauri::State<'_, audio_capture::RecordingRegistry>,
+    record_input_cache: tauri::State<'_, record_input_cache::RecordInputCacheState>,
+    consent: bool,
+    note: Option<String>,
+) -> Result<bug_report::BugReportResult, String> {
+    bug_report::record_bug_report_sample(
+        recordings.inner().clone(),
+        record_input_cache.inner().clone(),
+        consent,
+        note,
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn compare_asr_models(
+    recordings: tauri::State<'_, audio_capture::RecordingRegistry>,
+    asset_or_task_id: String,
+    model_a: String,
+    model_b: String,
+) -> Result<asr_compare::AsrCompareResult, String> {
+    asr_compare::compare_asr_models(
+        recordings.inner().clone(),
+        &asset_or_task_id,
+        &model_a,
+        &model_b,
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn compare_rewrites(
+    task_id: String,
+    variants: Vec<rewrite_compare::RewriteVariant>,
+) -> Result<rewrite_compare::RewriteCompareResult, String> {
+    rewrite_compare::compare_rewrites(&task_id, variants)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn power_status() -> power::PowerStatus {
+    power::status()
+}
+
+#[tauri::command]
+fn asr_warm_pool_status() -> Result<asr_warm_pool::WarmPoolStatus, String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let s = settings::load_settings(&dir).map_err(|e| e.to_string())?;
+    Ok(asr_warm_pool::status(&s))
+}
+
+#[tauri::command]
+async fn daily_summary_run(date: Option<String>) -> Result<scheduler::DailySummaryResult, String> {
+    scheduler::run_daily_summary_now(date.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn guest_mode_enabled() -> bool {
+    guest_mode::enabled()
+}
+
+#[tauri::command]
+fn data_dir_lock_status(
+    lock: tauri::State<data_dir_lock::DataDirLockManager>,
+) -> data_dir_lock::DataDirLockStatus {
+    lock.status()
+}
+
+// The holder lets go on its next heartbeat, so this can take about a minute.
+#[tauri::command]
+async fn data_dir_takeover(app: tauri::AppHandle) -> data_dir_lock::DataDirLockStatus {
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        handle
+            .state::<data_dir_lock::DataDirLockManager>()
+            .takeover(&handle)
+    })
+    .await
+    .unwrap_or_else(|e| data_dir_lock::DataDirLockStatus {
+        held: false,
+        owner: None,
+        error: Some(format!("E_INTERNAL: takeover join failed: {e}")),
+    })
+}
+
+#[tauri::command]
+fn obs_dropped_records() -> obs::DroppedRecords {
+    obs::dropped_records()
+}
+
+#[tauri::command]
+fn crash_report_status() -> Option<obs::panic::CrashReport> {
+    if guest_mode::enabled() {
+        return None;
+    }
+    obs::panic::unacknowledged(&data_dir::data_dir().ok()?)
+}
+
+#[tauri::command]
+fn data_recovery_status() -> Option<integrity::RecoveryReport> {
+    integrity::last_recovery()
+}
+
+#[tauri::command]
+fn acknowledge_crash_report(last_ts_ms: i64) -> Result<(), String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    obs::panic::acknowledge(&dir, last_ts_ms).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn list_workspaces() -> Result<Vec<workspaces::WorkspaceInfo>, String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    workspaces::list(&dir).map_err(|e| e.to_string())
+}
+
+// Re-points history and the template set; the UI reloads from the
+// `tv_settings_changed` event.
+#[tauri::command]
+fn switch_workspace(name: String) -> Result<Settings, String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    workspaces::switch(&dir, &name).map_err(|e| e.to_string())
+}
+
+// None for codes the table does not know; the UI keeps its generic hint then.
+#[tauri::command]
+fn explain_error(code: String) -> Option<errors::ErrorExplanation> {
+    errors::explain(&code)
+}
+
+#[tauri::command]
+fn schema_dump() -> serde_json::Value {
+    schemas::schema_dump()
+}
+
+#[tauri::command]
+fn permissions_status() -> Vec<permissions::PermissionStatus> {
+    permissions::permissions_status()
+}
+
+#[tauri::command]
+fn request_permission(kind: permissions::PermissionKind) -> permissions::PermissionStatus {
+    permissions::request(kind)
+}
+
+#[tauri::command]
+fn open_permission_settings(kind: permissions::PermissionKind) -> Result<(), String> {
+    permissions::open_settings(kind).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 fn set_settings(
     s: Settings,
@@ -767,9 +1853,7 @@ fn set_settings(
     let span = cmd_span(&dir, None, "CMD.set_settings", None);
     match settings::save_settings(&dir, &s) {
         Ok(()) => {
-            if cfg!(windows) {
-                let _ = record_input_cache.refresh_blocking(&dir, "set_settings");
-            }
+            let _ = record_input_cache.refresh_blocking(&dir, "set_settings");
             span.ok(None);
             Ok(())
         }
@@ -784,6 +1868,7 @@ fn set_settings(
 fn update_settings(
     app: tauri::AppHandle,
     hotkeys: tauri::State<hotkeys::HotkeyManager>,
+    wake_word: tauri::State<wake_word::WakeWordManager>,
     record_input_cache: tauri::State<record_input_cache::RecordInputCacheState>,
     patch: SettingsPatch,
 ) -> Result<Settings, String> {
@@ -793,27 +1878,85 @@ fn update_settings(
         "remote_asr_url": patch.remote_asr_url.is_some(),
         "remote_asr_model": patch.remote_asr_model.is_some(),
         "remote_asr_concurrency": patch.remote_asr_concurrency.is_some(),
+        "asr_warm_pool_enabled": patch.asr_warm_pool_enabled.is_some(),
+        "asr_warm_pool_models": patch.asr_warm_pool_models.is_some(),
+        "asr_warm_pool_max_models": patch.asr_warm_pool_max_models.is_some(),
+        "asr_warm_pool_vram_budget_mb": patch.asr_warm_pool_vram_budget_mb.is_some(),
+        "asr_warm_pool_model_vram_mb": patch.asr_warm_pool_model_vram_mb.is_some(),
+        "asr_warm_pool_keepalive_secs": patch.asr_warm_pool_keepalive_secs.is_some(),
+        "remote_asr_live_preview": patch.remote_asr_live_preview.is_some(),
+        "proxy_url": patch.proxy_url.is_some(),
+        "proxy_no_proxy": patch.proxy_no_proxy.is_some(),
+        "tls_ca_bundle_path": patch.tls_ca_bundle_path.is_some(),
+        "tls_pinned_sha256": patch.tls_pinned_sha256.is_some(),
+        "http_connect_timeout_ms": patch.http_connect_timeout_ms.is_some(),
+        "http_request_timeout_ms": patch.http_request_timeout_ms.is_some(),
+        "offline_mode": patch.offline_mode.is_some(),
         "llm_base_url": patch.llm_base_url.is_some(),
         "llm_model": patch.llm_model.is_some(),
         "llm_reasoning_effort": patch.llm_reasoning_effort.is_some(),
+        "llm_capture_enabled": patch.llm_capture_enabled.is_some(),
+        "llm_capture_max_entries": patch.llm_capture_max_entries.is_some(),
+        "gpu_coordination_enabled": patch.gpu_coordination_enabled.is_some(),
+        "gpu_asr_unload_url": patch.gpu_asr_unload_url.is_some(),
+        "gpu_llm_unload_url": patch.gpu_llm_unload_url.is_some(),
         "llm_prompt": patch.llm_prompt.is_some(),
+        "rewrite_system_preamble": patch.rewrite_system_preamble.is_some(),
+        "rewrite_chunk_threshold_tokens": patch.rewrite_chunk_threshold_tokens.is_some(),
+        "rewrite_chunk_tokens": patch.rewrite_chunk_tokens.is_some(),
+        "rewrite_cache_enabled": patch.rewrite_cache_enabled.is_some(),
+        "rewrite_token_cap": patch.rewrite_token_cap.is_some(),
+        "rewrite_output_format": patch.rewrite_output_format.is_some(),
+        "rewrite_output_format_by_template": patch.rewrite_output_format_by_template.is_some(),
+        "rewrite_output_json_schema": patch.rewrite_output_json_schema.is_some(),
+        "rewrite_output_json_schema_by_template": patch.rewrite_output_json_schema_by_template.is_some(),
         "record_input_strategy": patch.record_input_strategy.is_some(),
         "record_follow_default_role": patch.record_follow_default_role.is_some(),
         "record_fixed_endpoint_id": patch.record_fixed_endpoint_id.is_some(),
         "record_fixed_friendly_name": patch.record_fixed_friendly_name.is_some(),
+        "record_device_preference": patch.record_device_preference.is_some(),
+        "record_device_overrides": patch.record_device_overrides.is_some(),
+        "record_avoid_low_quality_profile": patch.record_avoid_low_quality_profile.is_some(),
+        "record_backend": patch.record_backend.is_some(),
+        "record_vad_enabled": patch.record_vad_enabled.is_some(),
+        "record_vad_silence_ms": patch.record_vad_silence_ms.is_some(),
         "rewrite_enabled": patch.rewrite_enabled.is_some(),
         "rewrite_glossary": patch.rewrite_glossary.is_some(),
+        "pipeline_max_background_rewrites": patch.pipeline_max_background_rewrites.is_some(),
+        "task_stall_timeout_ms": patch.task_stall_timeout_ms.is_some(),
         "auto_paste_enabled": patch.auto_paste_enabled.is_some(),
+        "export_elevated_helper_enabled": patch.export_elevated_helper_enabled.is_some(),
+        "clipboard_auto_clear_secs": patch.clipboard_auto_clear_secs.is_some(),
+        "export_format": patch.export_format.is_some(),
+        "export_format_by_template": patch.export_format_by_template.is_some(),
+        "export_targets_by_template": patch.export_targets_by_template.is_some(),
+        "audit_log_enabled": patch.audit_log_enabled.is_some(),
+        "audit_log_include_text": patch.audit_log_include_text.is_some(),
+        "keep_recordings": patch.keep_recordings.is_some(),
+        "recording_format": patch.recording_format.is_some(),
+        "time_format": patch.time_format.is_some(),
+        "audit_log_retention_days": patch.audit_log_retention_days.is_some(),
+        "audit_log_max_entries": patch.audit_log_max_entries.is_some(),
+        "text_casing": patch.text_casing.is_some(),
+        "text_casing_by_mode": patch.text_casing_by_mode.is_some(),
+        "text_normalize_enabled": patch.text_normalize_enabled.is_some(),
+        "text_normalize_locales": patch.text_normalize_locales.is_some(),
         "rewrite_include_glossary": patch.rewrite_include_glossary.is_some(),
         "context_include_history": patch.context_include_history.is_some(),
         "context_history_n": patch.context_history_n.is_some(),
         "context_history_window_ms": patch.context_history_window_ms.is_some(),
+        "context_history_same_app": patch.context_history_same_app.is_some(),
+        "context_history_summarize": patch.context_history_summarize.is_some(),
         "context_include_clipboard": patch.context_include_clipboard.is_some(),
         "context_include_prev_window_meta": patch.context_include_prev_window_meta.is_some(),
         "context_include_prev_window_screenshot": patch.context_include_prev_window_screenshot.is_some(),
+        "context_sections_by_template": patch.context_sections_by_template.is_some(),
+        "context_injection_guard_enabled": patch.context_injection_guard_enabled.is_some(),
         "llm_supports_vision": patch.llm_supports_vision.is_some(),
         "hotkeys_enabled": patch.hotkeys_enabled.is_some(),
         "hotkey_primary": patch.hotkey_primary.is_some(),
+        "hotkey_debounce_ms": patch.hotkey_debounce_ms.is_some(),
+        "hotkey_app_allowlist": patch.hotkey_app_allowlist.is_some(),
         "hotkeys_show_overlay": patch.hotkeys_show_overlay.is_some(),
         "overlay_background_opacity": patch.overlay_background_opacity.is_some(),
         "overlay_font_size_px": patch.overlay_font_size_px.is_some(),
@@ -821,27 +1964,127 @@ fn update_settings(
         "overlay_height_px": patch.overlay_height_px.is_some(),
         "overlay_position_x": patch.overlay_position_x.is_some(),
         "overlay_position_y": patch.overlay_position_y.is_some(),
+        "overlay_status_colors": patch.overlay_status_colors.is_some(),
+        "overlay_font_scale": patch.overlay_font_scale.is_some(),
+        "overlay_status_language": patch.overlay_status_language.is_some(),
+        "captions_enabled": patch.captions_enabled.is_some(),
+        "captions_font_size_px": patch.captions_font_size_px.is_some(),
+        "captions_background_opacity": patch.captions_background_opacity.is_some(),
+        "captions_monitor_index": patch.captions_monitor_index.is_some(),
+        "a11y_announcements": patch.a11y_announcements.is_some(),
+        "capture_indicator_enforced": patch.capture_indicator_enforced.is_some(),
+        "screen_share_guard_enabled": patch.screen_share_guard_enabled.is_some(),
+        "screen_share_extra_processes": patch.screen_share_extra_processes.is_some(),
+        "low_power_mode": patch.low_power_mode.is_some(),
+        "low_power_asr_provider": patch.low_power_asr_provider.is_some(),
+        "low_power_remote_asr_model": patch.low_power_remote_asr_model.is_some(),
+        "wake_word_enabled": patch.wake_word_enabled.is_some(),
+        "wake_word_phrase": patch.wake_word_phrase.is_some(),
+        "wake_word_sensitivity": patch.wake_word_sensitivity.is_some(),
+        "wake_word_detector_command": patch.wake_word_detector_command.is_some(),
+        "postprocessors": patch.postprocessors.is_some(),
+        "markdown_notes_enabled": patch.markdown_notes_enabled.is_some(),
+        "markdown_notes_folder": patch.markdown_notes_folder.is_some(),
+        "markdown_notes_entry_template": patch.markdown_notes_entry_template.is_some(),
+        "markdown_notes_entry_template_by_template": patch
+            .markdown_notes_entry_template_by_template
+            .is_some(),
+        "webhook_enabled": patch.webhook_enabled.is_some(),
+        "webhook_url": patch.webhook_url.is_some(),
+        "webhook_fields": patch.webhook_fields.is_some(),
+        "mqtt_enabled": patch.mqtt_enabled.is_some(),
+        "mqtt_broker_host": patch.mqtt_broker_host.is_some(),
+        "mqtt_broker_port": patch.mqtt_broker_port.is_some(),
+        "mqtt_tls": patch.mqtt_tls.is_some(),
+        "mqtt_topic_prefix": patch.mqtt_topic_prefix.is_some(),
+        "mqtt_username": patch.mqtt_username.is_some(),
         "asr_preprocess_silence_trim_enabled": patch.asr_preprocess_silence_trim_enabled.is_some(),
         "asr_preprocess_silence_threshold_db": patch
             .asr_preprocess_silence_threshold_db
             .is_some(),
         "asr_preprocess_silence_start_ms": patch.asr_preprocess_silence_start_ms.is_some(),
         "asr_preprocess_silence_end_ms": patch.asr_preprocess_silence_end_ms.is_some(),
+        "asr_preprocess_gain_db": patch.asr_preprocess_gain_db.is_some(),
+        "asr_preprocess_warm_spare_enabled": patch.asr_preprocess_warm_spare_enabled.is_some(),
+        "asr_preprocess_native_wav_enabled": patch.asr_preprocess_native_wav_enabled.is_some(),
     });
     let span = cmd_span(&dir, None, "CMD.update_settings", Some(patch_summary));
-    let cur = match settings::load_settings_strict(&dir) {
-        Ok(v) => v,
-        Err(e) => {
-            span.err_anyhow("settings", "E_CMD_UPDATE_SETTINGS_LOAD", &e, None);
-            return Err(e.to_string());
-        }
-    };
     let record_input_changed = patch.record_input_strategy.is_some()
         || patch.record_follow_default_role.is_some()
         || patch.record_fixed_endpoint_id.is_some()
         || patch.record_fixed_friendly_name.is_some()
+        || patch.record_device_preference.is_some()
+        || patch.record_device_overrides.is_some()
+        || patch.record_avoid_low_quality_profile.is_some()
         || patch.record_input_spec.is_some();
-    let mut next = settings::apply_patch(cur, patch);
+    // The patch is applied to the file as loaded under the store lock, so a
+    // concurrent record input cache write is not overwritten.
+    let next = match settings::modify_settings(&dir, |cur| {
+        *cur = normalize_patched_settings(settings::apply_patch(cur.clone(), patch))?;
+        Ok(true)
+    }) {
+        Ok(v) => v,
+        Err(e) => {
+            let code = ports::PortError::from_message("E_CMD_UPDATE_SETTINGS", e.to_string()).code;
+            span.err_anyhow("settings", &code, &e, None);
+            return Err(e.to_string());
+        }
+    };
+    apply_settings_runtime(
+        &app,
+        &dir,
+        &next,
+        &hotkeys,
+        &wake_word,
+        record_input_changed.then_some(&*record_input_cache),
+    );
+
+    span.ok(None);
+    Ok(next)
+}
+
+// Pushes settings that just landed on disk to the running app; shared by
+// update_settings and the settings.json watcher. The input cache is refreshed
+// only when given.
+pub(crate) fn apply_settings_runtime(
+    app: &tauri::AppHandle,
+    dir: &std::path::Path,
+    next: &Settings,
+    hotkeys: &hotkeys::HotkeyManager,
+    wake_word: &wake_word::WakeWordManager,
+    record_input_cache: Option<&record_input_cache::RecordInputCacheState>,
+) {
+    let overlay_config = settings::resolve_overlay_config(next);
+    if let Some(w) = app.get_webview_window("overlay") {
+        let _ = overlay_layout::apply_overlay_layout_with_config(&w, &overlay_config);
+    }
+    let _ = app.emit("tv_overlay_config_changed", overlay_config);
+    let _ = app.emit("tv_overlay_theme", settings::resolve_overlay_theme(next));
+    let captions_config = settings::resolve_captions_config(next);
+    if let Some(w) = app.get_webview_window("captions") {
+        if w.is_visible().unwrap_or(false) {
+            let _ = overlay_layout::apply_captions_layout_with_config(&w, &captions_config);
+        }
+    }
+    let _ = app.emit("tv_captions_config_changed", captions_config);
+    capture_light::apply_from_settings(dir, next);
+    screen_share::apply_from_settings(next);
+    power::apply_from_settings(next);
+    if !settings::resolve_preprocess_warm_spare_enabled(next) {
+        ffmpeg_spare::discard();
+    }
+    // Hotkeys are also best-effort; failures are traced and should not break settings.
+    hotkeys.apply_from_settings_best_effort(app, dir, next);
+    if let Some(cache) = record_input_cache {
+        let _ = cache.refresh_blocking(dir, "settings_changed");
+    }
+    // Restarted after the input cache refresh so the listener opens the current device.
+    wake_word.apply_from_settings_best_effort(app, dir, next);
+}
+
+// Canonicalizes the record input and hotkey fields of a patched settings value;
+// errors carry their `E_...` code for the command span.
+fn normalize_patched_settings(mut next: Settings) -> anyhow::Result<Settings> {
     next.record_input_strategy = Some(
         next.record_input_strategy
             .as_deref()
@@ -867,10 +2110,9 @@ fn update_settings(
             .filter(|v| !v.is_empty())
             .map(ToOwned::to_owned);
         if fixed_id.is_none() {
-            let msg =
-                "E_RECORD_INPUT_FIXED_MISSING: record_fixed_endpoint_id is required when strategy=fixed_device";
-            span.err("config", "E_RECORD_INPUT_FIXED_MISSING", msg, None);
-            return Err(msg.to_string());
+            return Err(anyhow::anyhow!(
+                "E_RECORD_INPUT_FIXED_MISSING: record_fixed_endpoint_id is required when strategy=fixed_device"
+            ));
         }
         next.record_fixed_endpoint_id = fixed_id;
         next.record_fixed_friendly_name = next
@@ -880,37 +2122,104 @@ fn update_settings(
             .filter(|v| !v.is_empty())
             .map(ToOwned::to_owned);
     }
-    match settings::normalize_hotkey_primary(next.hotkey_primary.as_deref()) {
-        Ok(primary) => {
-            next.hotkey_primary = Some(primary);
-        }
+    next.hotkey_primary = Some(settings::normalize_hotkey_primary(
+        next.hotkey_primary.as_deref(),
+    )?);
+    Ok(next)
+}
+
+// Dry run of update_settings: reports per-field errors for the patch without
+// writing settings.json, so the settings screen can flag fields inline.
+#[tauri::command]
+fn validate_settings(patch: SettingsPatch) -> Result<Vec<settings_validation::FieldError>, String> {
+    let dir = data_dir::data_dir().map_err(|e| e.to_string())?;
+    let span = cmd_span(&dir, None, "CMD.validate_settings", None);
+    let cur = match settings::load_settings_strict(&dir) {
+        Ok(v) => v,
         Err(e) => {
-            span.err_anyhow("config", "E_SETTINGS_HOTKEY_PRIMARY_INVALID", &e, None);
+            span.err_anyhow("settings", "E_CMD_VALIDATE_SETTINGS", &e, None);
             return Err(e.to_string());
         }
+    };
+    let mut errors = settings_validation::validate_patch(&cur, &patch);
+    let next = settings::apply_patch(cur, patch.clone());
+    errors.extend(record_input_field_errors(&next, &patch));
+    span.ok(Some(serde_json::json!({
+        "errors": errors.len(),
+        "codes": errors.iter().map(|e| e.code.as_str()).collect::<Vec<_>>(),
+    })));
+    Ok(errors)
+}
+
+// Record input checks need the host: strategy names come from record_input and
+// a fixed endpoint must be one of the active capture devices. Enumeration
+// failures are not reported; the device may just be unplugged for now.
+fn record_input_field_errors(
+    next: &Settings,
+    patch: &SettingsPatch,
+) -> Vec<settings_validation::FieldError> {
+    use settings_validation::FieldError;
+    let mut errors = Vec::new();
+    if patch.record_input_strategy.is_some() {
+        if let Some(v) = next.record_input_strategy.as_deref() {
+            if record_input::normalize_strategy_for_settings(v).is_none() {
+                errors.push(FieldError::new(
+                    "record_input_strategy",
+                    "E_SETTINGS_VALUE_INVALID",
+                    format!("unknown record input strategy: {v}"),
+                ));
+            }
+        }
     }
-    if let Err(e) = settings::save_settings(&dir, &next) {
-        span.err_anyhow("settings", "E_CMD_UPDATE_SETTINGS", &e, None);
-        return Err(e.to_string());
+    if patch.record_follow_default_role.is_some() {
+        if let Some(v) = next.record_follow_default_role.as_deref() {
+            if record_input::normalize_default_role_for_settings(v).is_none() {
+                errors.push(FieldError::new(
+                    "record_follow_default_role",
+                    "E_SETTINGS_VALUE_INVALID",
+                    format!("unknown default device role: {v}"),
+                ));
+            }
+        }
     }
-    let overlay_config = settings::resolve_overlay_config(&next);
-    if let Some(w) = app.get_webview_window("overlay") {
-        let _ = overlay_layout::apply_overlay_layout_with_config(&w, &overlay_config);
+    let touched = patch.record_input_strategy.is_some() || patch.record_fixed_endpoint_id.is_some();
+    if !touched || next.record_input_strategy.as_deref() != Some("fixed_device") {
+        return errors;
     }
-    let _ = app.emit("tv_overlay_config_changed", overlay_config);
-    // Hotkeys are also best-effort; failures are traced and should not break settings.
-    hotkeys.apply_from_settings_best_effort(&app, &dir, &next);
-    if cfg!(windows) && record_input_changed {
-        let _ = record_input_cache.refresh_blocking(&dir, "settings_changed");
+    let fixed_id = next
+        .record_fixed_endpoint_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty());
+    let Some(fixed_id) = fixed_id else {
+        errors.push(FieldError::new(
+            "record_fixed_endpoint_id",
+            "E_RECORD_INPUT_FIXED_MISSING",
+            "a device is required when strategy=fixed_device",
+        ));
+        return errors;
+    };
+    if let Ok(endpoints) = audio_devices_windows::list_active_capture_endpoints() {
+        if !endpoints.iter().any(|e| e.endpoint_id == fixed_id) {
+            errors.push(FieldError::new(
+                "record_fixed_endpoint_id",
+                "E_SETTINGS_ENDPOINT_UNKNOWN",
+                "the selected device is not an active capture endpoint",
+            ));
+        }
     }
-
-    span.ok(None);
-    Ok(next)
+    errors
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // The elevated paste helper must exit before single-instance handling
+    // forwards it to the running app.
+    if let Some(code) = typevoice_platform::elevated_helper::run_from_args() {
+        std::process::exit(code);
+    }
     obs::startup::mark_best_effort("run_enter");
+    guest_mode::set_enabled(guest_mode::requested(std::env::args()));
     obs::panic::install_best_effort();
     obs::startup::mark_best_effort("panic_hook_installed");
     let ctx = tauri::generate_context!();
@@ -924,6 +2233,8 @@ pub fn run() {
         .manage(record_input_cache::RecordInputCacheState::new())
         .manage(audio_device_notifications_windows::AudioDeviceNotificationState::new())
         .manage(hotkeys::HotkeyManager::new())
+        .manage(wake_word::WakeWordManager::new())
+        .manage(data_dir_lock::DataDirLockManager::new())
         .plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
             #[derive(Clone, serde::Serialize)]
             struct Payload {
@@ -951,10 +2262,93 @@ pub fn run() {
         }))
         .setup(|app| {
             obs::startup::mark_best_effort("setup_enter");
-            let mailbox = ui_events::UiEventMailbox::new(app.handle().clone());
-            app.manage(transcription_actor::TranscriptionActor::new(mailbox.clone()));
+            app.manage(pipeline_runtime::PipelineRuntime::new());
+            let mailbox = ui_events::UiEventMailbox::new(
+                app.handle().clone(),
+                app.state::<TaskManager>().heartbeats(),
+            );
+            app.manage(transcription_actor::TranscriptionActor::new(
+                mailbox.clone(),
+            ));
             app.manage(mailbox);
 
+            // Every settings.json write, whatever its source, reaches the UI.
+            let settings_handle = app.handle().clone();
+            settings::set_change_listener(move |s| {
+                let _ = settings_handle.emit("tv_settings_changed", s);
+            });
+
+            // Stopping joins the meter thread that detected the silence, so the
+            // stop runs off it.
+            let vad_handle = app.handle().clone();
+            vad::set_auto_stop_listener(move |stop| {
+                let app = vad_handle.clone();
+                tauri::async_runtime::spawn_blocking(move || {
+                    commands::auto_stop_recording(&app, stop);
+                });
+            });
+
+            // Another instance (other user, or a synced copy on another host) may
+            // hold the data dir; then this one starts read-only. A guest never
+            // writes, so it neither takes the lock nor replays the journal.
+            // Only the owner checks the data files and moves corrupt ones
+            // aside, before the settings watcher and the journal replay open
+            // them; the frontend also pulls data_recovery_status on mount.
+            if let Some(dir) = data_dir::data_dir().ok().filter(|_| !guest_mode::enabled()) {
+                let lock = app
+                    .state::<data_dir_lock::DataDirLockManager>()
+                    .start(app.handle(), &dir);
+                if lock.held {
+                    if let Some(report) = integrity::check_and_quarantine(&dir) {
+                        let _ = app.emit("tv_data_recovered", report);
+                    }
+                }
+            }
+
+            if let Ok(dir) = data_dir::data_dir() {
+                settings_watch::start(app.handle(), dir);
+            }
+
+            // History writes that were still failing when the last run exited.
+            if let Some(db) = history_db_path().ok().filter(|_| !guest_mode::enabled()) {
+                if let Err(e) = history_writer::replay_journal(&db) {
+                    if let Ok(dir) = data_dir::data_dir() {
+                        obs::event_err_anyhow(
+                            &dir,
+                            obs::ErrorEvent {
+                                task_id: None,
+                                stage: "History",
+                                step_id: "HISTORY.replay_journal",
+                                kind: "io",
+                                code: "E_HISTORY_JOURNAL",
+                                ctx: None,
+                            },
+                            &e,
+                        );
+                    }
+                }
+            }
+
+            // Panics from earlier runs are announced until the user dismisses
+            // the notice; the frontend also pulls crash_report_status on mount
+            // because this fires before it listens.
+            if let Some(dir) = data_dir::data_dir().ok().filter(|_| !guest_mode::enabled()) {
+                if let Some(report) = obs::panic::unacknowledged(&dir) {
+                    obs::event(
+                        &dir,
+                        None,
+                        "App",
+                        "APP.crash_detected",
+                        "ok",
+                        Some(serde_json::json!({
+                            "count": report.count,
+                            "last_ts_ms": report.last_ts_ms,
+                        })),
+                    );
+                    let _ = app.emit("tv_crash_detected", report);
+                }
+            }
+
             // Small always-on-top overlay window for hotkey-driven UX.
             // Keep it hidden by default; the frontend will invoke overlay_set_state to show/hide.
             let _overlay = tauri::WebviewWindowBuilder::new(
@@ -973,47 +2367,58 @@ pub fn run() {
             .focused(false)
             .build();
 
-            let mut toolchain_ready = false;
+            // Click-through live captions window; shown by the ui event actor while
+            // recording when captions are enabled, or via overlay_captions_set_text.
+            if let Ok(captions) = tauri::WebviewWindowBuilder::new(
+                app,
+                "captions",
+                tauri::WebviewUrl::App("index.html".into()),
+            )
+            .title("TypeVoice Captions")
+            .inner_size(1280.0, 160.0)
+            .resizable(false)
+            .decorations(false)
+            .transparent(true)
+            .always_on_top(true)
+            .visible(false)
+            .skip_taskbar(true)
+            .focused(false)
+            .build()
+            {
+                let _ = captions.set_ignore_cursor_events(true);
+            }
+            capture_light::install(app.handle());
+
             if let Ok(dir) = data_dir::data_dir() {
                 settings::ensure_settings(&dir)?;
-                let runtime = app.state::<RuntimeState>();
-                let st = toolchain::initialize_and_verify(app.handle(), &dir);
-                toolchain_ready = st.ready;
-                runtime.set_toolchain(st);
-
-                if cfg!(windows) {
-                    let record_input_cache = app.state::<record_input_cache::RecordInputCacheState>();
-                    if toolchain_ready {
-                        let _ = record_input_cache.refresh_blocking(&dir, "app_startup");
-                        let listener =
-                            app.state::<audio_device_notifications_windows::AudioDeviceNotificationState>();
-                        listener.start_best_effort(&dir, record_input_cache.inner().clone());
-                    } else {
-                        obs::event(
-                            &dir,
-                            None,
-                            "App",
-                            "APP.record_input_cache_refresh_skipped",
-                            "ok",
-                            Some(serde_json::json!({
-                                "reason": "toolchain_not_ready",
-                            })),
-                        );
-                    }
+                let st = refresh_preflight(app.handle(), &dir, "app_startup");
+                if !st.ready {
+                    obs::event(
+                        &dir,
+                        None,
+                        "App",
+                        "APP.record_input_cache_refresh_skipped",
+                        "ok",
+                        Some(serde_json::json!({
+                            "reason": "toolchain_not_ready",
+                        })),
+                    );
                 }
             }
-
-            if toolchain_ready {
-                let state = app.state::<TaskManager>();
-                state.warmup_context_best_effort();
-            }
+            spawn_preflight_recheck(app.handle().clone());
 
             // Apply hotkeys from persisted settings.
             if let Ok(dir) = data_dir::data_dir() {
                 match settings::load_settings_strict(&dir) {
                     Ok(s) => {
+                        // Before the wake word listener opens the microphone.
+                        capture_light::apply_from_settings(&dir, &s);
+                        screen_share::apply_from_settings(&s);
+                        power::apply_from_settings(&s);
                         let hk = app.state::<hotkeys::HotkeyManager>();
                         hk.apply_from_settings_best_effort(app.handle(), &dir, &s);
+                        let ww = app.state::<wake_word::WakeWordManager>();
+                        ww.apply_from_settings_best_effort(app.handle(), &dir, &s);
                     }
                     Err(e) => {
                         obs::event(
@@ -1031,6 +2436,23 @@ pub fn run() {
                 }
             }
 
+            if !guest_mode::enabled() {
+                tauri::async_runtime::spawn(scheduler::run());
+            }
+            tauri::async_runtime::spawn(asr_warm_pool::run());
+            voice_tasks::spawn_stall_watchdog(app.handle().clone());
+
+            // Builds with `--features lock-metrics` write one contention
+            // window per instrumented lock each minute.
+            if obs::lock_metrics::enabled() && !guest_mode::enabled() {
+                if let Ok(dir) = data_dir::data_dir() {
+                    std::thread::spawn(move || loop {
+                        std::thread::sleep(std::time::Duration::from_secs(60));
+                        obs::lock_metrics::report(&dir);
+                    });
+                }
+            }
+
             obs::startup::mark_best_effort("setup_exit");
             Ok(())
         })
@@ -1038,6 +2460,7 @@ pub fn run() {
             commands::record_transcribe_start,
             commands::record_transcribe_stop,
             commands::record_transcribe_cancel,
+            commands::cancel_stage,
             commands::rewrite_text,
             commands::insert_text,
             commands::workflow_snapshot,
@@ -1053,6 +2476,9 @@ pub fn run() {
             commands::workflow_report_insert_completed,
             commands::workflow_report_insert_failed,
             commands::overlay_insert_text,
+            commands::lock_export_target,
+            commands::unlock_export_target,
+            commands::export_target_lock_status,
             abort_pending_task,
             set_llm_api_key,
             clear_llm_api_key,
@@ -1066,20 +2492,79 @@ pub fn run() {
             clear_doubao_asr_credentials,
             doubao_asr_credentials_status,
             check_doubao_asr_credentials,
+            set_proxy_credentials,
+            clear_proxy_credentials,
+            proxy_credentials_status,
+            set_webhook_secret,
+            clear_webhook_secret,
+            webhook_secret_status,
+            set_mqtt_password,
+            clear_mqtt_password,
+            mqtt_password_status,
+            test_proxy,
             history_append,
             history_list,
+            history_search,
             history_clear,
+            history_update_final_text,
+            history_set_tags,
+            history_set_note,
+            history_bulk,
+            backup_data,
+            restore_data,
+            suggest_corrections,
+            asr_accuracy_stats,
+            list_task_artifacts,
+            list_llm_captures,
+            read_task_artifact,
+            audit_list,
+            audit_clear,
+            rewrite_preview,
+            list_formatting_profiles,
+            upsert_formatting_profile,
+            delete_formatting_profile,
             get_settings,
             effective_settings_values,
             list_audio_capture_devices,
+            calibrate_noise_profile,
+            mic_test,
+            record_bug_report_sample,
+            compare_asr_models,
+            asr_warm_pool_status,
+            power_status,
+            compare_rewrites,
+            daily_summary_run,
+            guest_mode_enabled,
+            data_dir_lock_status,
+            data_dir_takeover,
+            crash_report_status,
+            obs_dropped_records,
+            acknowledge_crash_report,
+            data_recovery_status,
+            list_workspaces,
+            switch_workspace,
+            explain_error,
+            schema_dump,
+            permissions_status,
+            request_permission,
+            open_permission_settings,
             set_settings,
             update_settings,
+            validate_settings,
             hotkeys::check_hotkey_available,
+            hotkeys::hotkey_backend_status,
             runtime_toolchain_status,
+            refresh_runtime_preflight,
             overlay_config,
+            overlay_get_theme,
+            capture_indicator_status,
             overlay_set_state,
             overlay_resize,
             overlay_save_position,
+            overlay_captions_set_text,
+            captions_config,
+            captions_monitors,
+            wake_word_status,
             ui_log_event,
         ])
         .run(ctx)
@@ -0,0 +1,116 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::settings::{self, ExternalEdit, SettingsConflict};
+use crate::{hotkeys, obs, record_input_cache, wake_word};
+
+pub const SETTINGS_CONFLICT_EVENT: &str = "tv_settings_conflict";
+
+// settings.json is small and rarely edited by hand; polling keeps this free of
+// platform file-notification APIs.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// `merged`: an in-app change was saved on top of a hand edit and both were
+// kept. `overwritten`: the in-app save replaced the hand edit. `invalid`: the
+// hand edit is not valid JSON and was not applied.
+#[derive(Debug, Clone, Serialize)]
+struct ConflictPayload {
+    kind: &'static str,
+    message: Option<String>,
+}
+
+// Hand edits are applied the way update_settings applies a patch.
+pub fn start(app: &AppHandle, data_dir: PathBuf) {
+    let (tx, rx) = mpsc::channel::<SettingsConflict>();
+    // Reported from inside whichever writer raced the edit, which may hold its
+    // own locks (the input cache refresh does); the watcher thread handles it.
+    settings::set_conflict_listener(move |_, conflict| {
+        let _ = tx.send(conflict);
+    });
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(conflict) => on_conflict(&app, &data_dir, conflict),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return,
+        }
+        match settings::poll_external_edit(&data_dir) {
+            Some(ExternalEdit::Reloaded(next)) => {
+                obs::event(
+                    &data_dir,
+                    None,
+                    "Settings",
+                    "SETTINGS.external_edit",
+                    "ok",
+                    None,
+                );
+                apply(&app, &data_dir, &next);
+            }
+            Some(ExternalEdit::Invalid(message)) => {
+                obs::event_err(
+                    &data_dir,
+                    obs::ErrorEvent {
+                        task_id: None,
+                        stage: "Settings",
+                        step_id: "SETTINGS.external_edit",
+                        kind: "settings",
+                        code: "E_SETTINGS_JSON_INVALID",
+                        ctx: None,
+                    },
+                    &message,
+                );
+                let _ = app.emit(
+                    SETTINGS_CONFLICT_EVENT,
+                    ConflictPayload {
+                        kind: "invalid",
+                        message: Some(message),
+                    },
+                );
+            }
+            None => {}
+        }
+    });
+}
+
+fn on_conflict(app: &AppHandle, data_dir: &Path, conflict: SettingsConflict) {
+    let kind = match conflict {
+        SettingsConflict::Merged => "merged",
+        SettingsConflict::Overwritten => "overwritten",
+    };
+    obs::event(
+        data_dir,
+        None,
+        "Settings",
+        "SETTINGS.conflict",
+        "err",
+        Some(serde_json::json!({ "kind": kind })),
+    );
+    // The in-app writer only re-applied the fields it changed.
+    if conflict == SettingsConflict::Merged {
+        if let Ok(next) = settings::load_settings_strict(data_dir) {
+            apply(app, data_dir, &next);
+        }
+    }
+    let _ = app.emit(
+        SETTINGS_CONFLICT_EVENT,
+        ConflictPayload {
+            kind,
+            message: None,
+        },
+    );
+}
+
+fn apply(app: &AppHandle, data_dir: &Path, next: &settings::Settings) {
+    crate::apply_settings_runtime(
+        app,
+        data_dir,
+        next,
+        &app.state::<hotkeys::HotkeyManager>(),
+        &app.state::<wake_word::WakeWordManager>(),
+        Some(&*app.state::<record_input_cache::RecordInputCacheState>()),
+    );
+}
@@ -0,0 +1,717 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::obs::Span;
+use crate::settings::{self, Settings};
+
+pub const WAKE_WORD_STATE_EVENT: &str = "tv_wake_word_state";
+// 80 ms at 16 kHz, the frame size openWakeWord-class detectors consume.
+#[cfg(any(windows, test))]
+const FRAME_SAMPLES: usize = 1280;
+#[cfg(any(windows, test))]
+const RING_CAPACITY_SAMPLES: usize = 16_000 * 2;
+#[cfg(any(windows, test))]
+const TRIGGER_COOLDOWN_MS: i64 = 2_000;
+
+// Explicit mic-indicator state: anything other than `Off` means the
+// microphone is open for wake word detection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WakeWordMicState {
+    Off,
+    Listening,
+    Triggered,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WakeWordStatus {
+    pub state: WakeWordMicState,
+    pub phrase: Option<String>,
+    pub detail: Option<String>,
+    pub ts_ms: i64,
+}
+
+impl WakeWordStatus {
+    fn new(state: WakeWordMicState, phrase: Option<String>, detail: Option<String>) -> Self {
+        Self {
+            state,
+            phrase,
+            detail,
+            ts_ms: crate::obs::schema::now_ms(),
+        }
+    }
+}
+
+type StatusSlot = Arc<Mutex<WakeWordStatus>>;
+
+fn publish_status(app: &AppHandle, slot: &StatusSlot, status: WakeWordStatus) {
+    *slot.lock().unwrap() = status.clone();
+    let _ = app.emit(WAKE_WORD_STATE_EVENT, status);
+}
+
+pub struct WakeWordManager {
+    lock: Mutex<()>,
+    listener: Mutex<Option<WakeWordListener>>,
+    status: StatusSlot,
+}
+
+impl Default for WakeWordManager {
+    fn default() -> Self {
+        Self {
+            lock: Mutex::new(()),
+            listener: Mutex::new(None),
+            status: Arc::new(Mutex::new(WakeWordStatus::new(
+                WakeWordMicState::Off,
+                None,
+                None,
+            ))),
+        }
+    }
+}
+
+impl WakeWordManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn status(&self) -> WakeWordStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    pub fn apply_from_settings_best_effort(&self, app: &AppHandle, data_dir: &Path, s: &Settings) {
+        let _g = self.lock.lock().unwrap();
+        let cfg = settings::resolve_wake_word_config(s);
+        let span = Span::start(
+            data_dir,
+            None,
+            "WakeWord",
+            "WAKE_WORD.apply",
+            Some(serde_json::json!({
+                "enabled": cfg.enabled,
+                "sensitivity": cfg.sensitivity,
+                "has_detector": cfg.detector_command.is_some(),
+            })),
+        );
+
+        self.stop_listener();
+        if !cfg.enabled {
+            publish_status(
+                app,
+                &self.status,
+                WakeWordStatus::new(WakeWordMicState::Off, None, None),
+            );
+            span.ok(Some(serde_json::json!({"status": "disabled"})));
+            return;
+        }
+        let Some(command) = cfg.detector_command.as_deref() else {
+            let msg = "wake_word_detector_command is required when wake word is enabled";
+            span.err("config", "E_WAKE_WORD_DETECTOR_MISSING", msg, None);
+            publish_status(
+                app,
+                &self.status,
+                WakeWordStatus::new(
+                    WakeWordMicState::Error,
+                    Some(cfg.phrase.clone()),
+                    Some(format!("E_WAKE_WORD_DETECTOR_MISSING: {msg}")),
+                ),
+            );
+            return;
+        };
+
+        match WakeWordListener::start(app.clone(), data_dir, &cfg, command, self.status.clone()) {
+            Ok(next) => {
+                *self.listener.lock().unwrap() = Some(next);
+                publish_status(
+                    app,
+                    &self.status,
+                    WakeWordStatus::new(WakeWordMicState::Listening, Some(cfg.phrase), None),
+                );
+                span.ok(Some(serde_json::json!({"status": "listening"})));
+            }
+            Err(e) => {
+                span.err_anyhow("process", "E_WAKE_WORD_START", &e, None);
+                publish_status(
+                    app,
+                    &self.status,
+                    WakeWordStatus::new(
+                        WakeWordMicState::Error,
+                        Some(cfg.phrase),
+                        Some(e.to_string()),
+                    ),
+                );
+            }
+        }
+    }
+
+    fn stop_listener(&self) {
+        let mut listener = self.listener.lock().unwrap();
+        if let Some(mut current) = listener.take() {
+            current.stop();
+        }
+    }
+}
+
+impl Drop for WakeWordManager {
+    fn drop(&mut self) {
+        if let Ok(mut listener) = self.listener.lock() {
+            if let Some(mut current) = listener.take() {
+                current.stop();
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+struct WakeWordListener {
+    stop: Arc<std::sync::atomic::AtomicBool>,
+    recorder: std::process::Child,
+    detector: std::process::Child,
+    threads: Vec<std::thread::JoinHandle<()>>,
+    indicator: Option<crate::capture_indicator::CaptureLease>,
+}
+
+// Never constructed off Windows: `start` always reports E_WAKE_WORD_UNSUPPORTED.
+#[cfg(not(windows))]
+#[allow(dead_code)]
+struct WakeWordListener;
+
+impl WakeWordListener {
+    #[cfg(windows)]
+    fn start(
+        app: AppHandle,
+        data_dir: &Path,
+        cfg: &settings::WakeWordConfigResolved,
+        command: &str,
+        status: StatusSlot,
+    ) -> anyhow::Result<Self> {
+        use std::io::{BufRead, BufReader, Read, Write};
+        use std::process::{Command, Stdio};
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        use anyhow::{anyhow, Context};
+        use tauri::Manager;
+
+        use crate::subprocess::CommandNoConsoleExt;
+
+        let input_spec = app
+            .state::<crate::record_input_cache::RecordInputCacheState>()
+            .get_last_ok()
+            .map(|v| v.resolved.spec.clone())
+            .ok_or_else(|| {
+                anyhow!("E_RECORD_INPUT_CACHE_NOT_READY: record input cache is not ready")
+            })?;
+        let argv = split_command_line(command);
+        let (program, args) = argv
+            .split_first()
+            .ok_or_else(|| anyhow!("E_WAKE_WORD_DETECTOR_MISSING: detector command is empty"))?;
+        let ffmpeg = crate::pipeline::ffmpeg_cmd()?;
+        let indicator =
+            crate::capture_indicator::begin(crate::capture_indicator::CaptureSource::WakeWord)
+                .map_err(|e| anyhow!(e.render()))?;
+
+        let (input_format, input) = crate::record_input::ffmpeg_input(&input_spec);
+        let mut recorder = Command::new(&ffmpeg)
+            .args([
+                "-hide_banner",
+                "-loglevel",
+                "error",
+                "-f",
+                input_format,
+                "-i",
+                input.as_str(),
+                "-ac",
+                "1",
+                "-ar",
+                "16000",
+                "-f",
+                "s16le",
+                "pipe:1",
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .no_console()
+            .spawn()
+            .context("E_WAKE_WORD_CAPTURE_START: failed to start ffmpeg capture")?;
+        let mut detector = match Command::new(program)
+            .args(args)
+            .env("TYPEVOICE_WAKE_WORD_PHRASE", &cfg.phrase)
+            .env(
+                "TYPEVOICE_WAKE_WORD_SENSITIVITY",
+                format!("{:.3}", cfg.sensitivity),
+            )
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .no_console()
+            .spawn()
+        {
+            Ok(v) => v,
+            Err(e) => {
+                let _ = recorder.kill();
+                let _ = recorder.wait();
+                return Err(anyhow!(
+                    "E_WAKE_WORD_DETECTOR_START: failed to start detector '{program}': {e}"
+                ));
+            }
+        };
+
+        let (Some(mut pcm), Some(mut detector_in), Some(detector_out)) = (
+            recorder.stdout.take(),
+            detector.stdin.take(),
+            detector.stdout.take(),
+        ) else {
+            let _ = recorder.kill();
+            let _ = recorder.wait();
+            let _ = detector.kill();
+            let _ = detector.wait();
+            return Err(anyhow!("E_WAKE_WORD_START: process pipes not available"));
+        };
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut threads = Vec::new();
+        let ring = Arc::new(SharedPcmRing::new(RING_CAPACITY_SAMPLES));
+
+        // The detector is fed from its own thread, so a stalled detector only
+        // makes the ring drop old audio and never blocks the capture reads.
+        let feed_ring = ring.clone();
+        threads.push(
+            std::thread::Builder::new()
+                .name("typevoice_wake_word_feed".to_string())
+                .spawn(move || {
+                    while let Some(frame) = feed_ring.next_frame(FRAME_SAMPLES) {
+                        let bytes: Vec<u8> = frame.iter().flat_map(|s| s.to_le_bytes()).collect();
+                        if detector_in.write_all(&bytes).is_err() {
+                            break;
+                        }
+                    }
+                    feed_ring.close();
+                })?,
+        );
+
+        let capture_stop = stop.clone();
+        let capture_app = app.clone();
+        let capture_status = status.clone();
+        let capture_phrase = cfg.phrase.clone();
+        let capture_dir = data_dir.to_path_buf();
+        threads.push(
+            std::thread::Builder::new()
+                .name("typevoice_wake_word_capture".to_string())
+                .spawn(move || {
+                    let mut read_buf = [0_u8; 4096];
+                    loop {
+                        let n = match pcm.read(&mut read_buf) {
+                            Ok(0) | Err(_) => break,
+                            Ok(v) => v,
+                        };
+                        if !ring.push_bytes(&read_buf[..n]) {
+                            break;
+                        }
+                    }
+                    ring.close();
+                    let dropped = ring.dropped();
+                    if dropped > 0 {
+                        crate::obs::event(
+                            &capture_dir,
+                            None,
+                            "WakeWord",
+                            "WAKE_WORD.samples_dropped",
+                            "ok",
+                            Some(serde_json::json!({ "dropped_samples": dropped })),
+                        );
+                    }
+                    if !capture_stop.load(Ordering::SeqCst) {
+                        publish_status(
+                            &capture_app,
+                            &capture_status,
+                            WakeWordStatus::new(
+                                WakeWordMicState::Error,
+                                Some(capture_phrase),
+                                Some(
+                                    "E_WAKE_WORD_CAPTURE_ENDED: capture or detector stopped"
+                                        .to_string(),
+                                ),
+                            ),
+                        );
+                    }
+                })?,
+        );
+
+        let detect_stop = stop.clone();
+        let detect_dir = data_dir.to_path_buf();
+        let phrase = cfg.phrase.clone();
+        let threshold = sensitivity_threshold(cfg.sensitivity);
+        threads.push(
+            std::thread::Builder::new()
+                .name("typevoice_wake_word_detect".to_string())
+                .spawn(move || {
+                    let mut gate = TriggerGate::default();
+                    for line in BufReader::new(detector_out).lines() {
+                        if detect_stop.load(Ordering::SeqCst) {
+                            break;
+                        }
+                        let Ok(line) = line else {
+                            break;
+                        };
+                        let Some(score) = parse_detector_line(&line) else {
+                            continue;
+                        };
+                        let now = crate::obs::schema::now_ms();
+                        if score < threshold || !gate.allow(now) {
+                            continue;
+                        }
+                        crate::obs::event(
+                            &detect_dir,
+                            None,
+                            "WakeWord",
+                            "WAKE_WORD.detected",
+                            "ok",
+                            Some(serde_json::json!({
+                                "score": score,
+                                "threshold": threshold,
+                            })),
+                        );
+                        publish_status(
+                            &app,
+                            &status,
+                            WakeWordStatus::new(
+                                WakeWordMicState::Triggered,
+                                Some(phrase.clone()),
+                                None,
+                            ),
+                        );
+                        crate::hotkeys::emit_primary_action(&app);
+                        publish_status(
+                            &app,
+                            &status,
+                            WakeWordStatus::new(
+                                WakeWordMicState::Listening,
+                                Some(phrase.clone()),
+                                None,
+                            ),
+                        );
+                    }
+                })?,
+        );
+
+        Ok(Self {
+            stop,
+            recorder,
+            detector,
+            threads,
+            indicator: Some(indicator),
+        })
+    }
+
+    #[cfg(not(windows))]
+    fn start(
+        _app: AppHandle,
+        _data_dir: &Path,
+        _cfg: &settings::WakeWordConfigResolved,
+        _command: &str,
+        _status: StatusSlot,
+    ) -> anyhow::Result<Self> {
+        Err(anyhow::anyhow!(
+            "E_WAKE_WORD_UNSUPPORTED: wake word listening is only supported on Windows"
+        ))
+    }
+
+    #[cfg(windows)]
+    fn stop(&mut self) {
+        self.stop.store(true, std::sync::atomic::Ordering::SeqCst);
+        let _ = self.recorder.kill();
+        let _ = self.recorder.wait();
+        let _ = self.detector.kill();
+        let _ = self.detector.wait();
+        for handle in self.threads.drain(..) {
+            let _ = handle.join();
+        }
+        self.indicator.take();
+    }
+
+    #[cfg(not(windows))]
+    fn stop(&mut self) {}
+}
+
+// Bounded buffer between the capture pipe and the detector: reads arrive in
+// arbitrary byte counts, detectors want fixed frames, and a stalled detector
+// drops the oldest audio instead of growing memory.
+#[cfg(any(windows, test))]
+struct PcmRing {
+    samples: std::collections::VecDeque<i16>,
+    capacity: usize,
+    carry: Option<u8>,
+    dropped: u64,
+}
+
+#[cfg(any(windows, test))]
+impl PcmRing {
+    fn new(capacity: usize) -> Self {
+        Self {
+            samples: std::collections::VecDeque::with_capacity(capacity),
+            capacity,
+            carry: None,
+            dropped: 0,
+        }
+    }
+
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        let mut rest = bytes;
+        if let Some(low) = self.carry.take() {
+            match rest.split_first() {
+                Some((high, tail)) => {
+                    self.push_sample(i16::from_le_bytes([low, *high]));
+                    rest = tail;
+                }
+                None => {
+                    self.carry = Some(low);
+                    return;
+                }
+            }
+        }
+        let mut chunks = rest.chunks_exact(2);
+        for pair in &mut chunks {
+            self.push_sample(i16::from_le_bytes([pair[0], pair[1]]));
+        }
+        self.carry = chunks.remainder().first().copied();
+    }
+
+    fn push_sample(&mut self, sample: i16) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+            self.dropped += 1;
+        }
+        self.samples.push_back(sample);
+    }
+
+    fn pop_frame(&mut self, frame_samples: usize) -> Option<Vec<i16>> {
+        if frame_samples == 0 || self.samples.len() < frame_samples {
+            return None;
+        }
+        Some(self.samples.drain(..frame_samples).collect())
+    }
+}
+
+// The ring shared by the capture thread (producer) and the detector feed
+// thread (consumer). Pushing never waits on the consumer; closing wakes it and
+// makes later pushes report that the other side is gone.
+#[cfg(any(windows, test))]
+struct SharedPcmRing {
+    state: Mutex<(PcmRing, bool)>,
+    ready: std::sync::Condvar,
+}
+
+#[cfg(any(windows, test))]
+impl SharedPcmRing {
+    fn new(capacity: usize) -> Self {
+        Self {
+            state: Mutex::new((PcmRing::new(capacity), false)),
+            ready: std::sync::Condvar::new(),
+        }
+    }
+
+    // False once closed, so the producer can stop reading.
+    fn push_bytes(&self, bytes: &[u8]) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.1 {
+            return false;
+        }
+        state.0.push_bytes(bytes);
+        self.ready.notify_one();
+        true
+    }
+
+    // Blocks until a full frame is buffered; None once closed.
+    fn next_frame(&self, frame_samples: usize) -> Option<Vec<i16>> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if state.1 {
+                return None;
+            }
+            if let Some(frame) = state.0.pop_frame(frame_samples) {
+                return Some(frame);
+            }
+            state = self.ready.wait(state).unwrap();
+        }
+    }
+
+    fn close(&self) {
+        self.state.lock().unwrap().1 = true;
+        self.ready.notify_all();
+    }
+
+    fn dropped(&self) -> u64 {
+        self.state.lock().unwrap().0.dropped
+    }
+}
+
+// Detector protocol: one line per result, either "detected [score]" or
+// "score <0..1>". Anything else (banners, logs) is ignored.
+#[cfg(any(windows, test))]
+fn parse_detector_line(line: &str) -> Option<f64> {
+    let mut parts = line.split_whitespace();
+    let head = parts.next()?.to_ascii_lowercase();
+    let score = match head.as_str() {
+        "detected" => match parts.next() {
+            Some(v) => v.parse().ok()?,
+            None => 1.0,
+        },
+        "score" => parts.next()?.parse().ok()?,
+        _ => return None,
+    };
+    Some(f64::clamp(score, 0.0, 1.0))
+}
+
+// Higher sensitivity accepts lower detector scores.
+#[cfg(any(windows, test))]
+fn sensitivity_threshold(sensitivity: f64) -> f64 {
+    (1.0 - sensitivity).clamp(0.05, 0.95)
+}
+
+#[cfg(any(windows, test))]
+#[derive(Debug, Default)]
+struct TriggerGate {
+    last_ms: Option<i64>,
+}
+
+#[cfg(any(windows, test))]
+impl TriggerGate {
+    fn allow(&mut self, now_ms: i64) -> bool {
+        if self
+            .last_ms
+            .is_some_and(|last| now_ms - last < TRIGGER_COOLDOWN_MS)
+        {
+            return false;
+        }
+        self.last_ms = Some(now_ms);
+        true
+    }
+}
+
+#[cfg(any(windows, test))]
+fn split_command_line(raw: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_token = false;
+    for c in raw.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_token = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_token {
+                    out.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_token = true;
+            }
+        }
+    }
+    if has_token {
+        out.push(current);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_detector_line, sensitivity_threshold, split_command_line, PcmRing, SharedPcmRing,
+        TriggerGate, FRAME_SAMPLES,
+    };
+    use std::sync::{mpsc, Arc};
+
+    #[test]
+    fn ring_reassembles_split_samples_and_drops_oldest() {
+        let mut ring = PcmRing::new(4);
+        ring.push_bytes(&[0x01, 0x00, 0x02]);
+        ring.push_bytes(&[0x00, 0x03, 0x00]);
+        assert_eq!(ring.pop_frame(3), Some(vec![1, 2, 3]));
+
+        ring.push_bytes(&[5, 0, 6, 0, 7, 0, 8, 0, 9, 0]);
+        assert_eq!(ring.dropped, 1);
+        assert_eq!(ring.pop_frame(4), Some(vec![6, 7, 8, 9]));
+        assert_eq!(ring.pop_frame(FRAME_SAMPLES), None);
+    }
+
+    #[test]
+    fn stalled_detector_drops_audio_without_blocking_capture() {
+        let ring = Arc::new(SharedPcmRing::new(8));
+        let (took_first, first) = mpsc::channel();
+        let (resume, resumed) = mpsc::channel::<()>();
+        let feed_ring = ring.clone();
+        let feeder = std::thread::spawn(move || {
+            let mut frames = Vec::new();
+            while let Some(frame) = feed_ring.next_frame(2) {
+                frames.push(frame);
+                if frames.len() == 1 {
+                    // A detector that stops reading its stdin.
+                    took_first.send(()).unwrap();
+                    resumed.recv().unwrap();
+                }
+            }
+            frames
+        });
+
+        ring.push_bytes(&[1, 0, 2, 0]);
+        first.recv().unwrap();
+        // Every push returns while the consumer is stuck; the oldest samples go.
+        for v in 3..=20_u8 {
+            assert!(ring.push_bytes(&[v, 0]));
+        }
+        assert_eq!(ring.dropped(), 10);
+
+        resume.send(()).unwrap();
+        while ring.state.lock().unwrap().0.samples.len() >= 2 {
+            std::thread::yield_now();
+        }
+        ring.close();
+        assert!(!ring.push_bytes(&[0, 0]));
+        let frames = feeder.join().unwrap();
+        assert_eq!(frames[0], vec![1, 2]);
+        assert_eq!(frames[1], vec![13, 14]);
+        assert_eq!(frames.last(), Some(&vec![19, 20]));
+    }
+
+    #[test]
+    fn detector_lines_and_threshold() {
+        assert_eq!(parse_detector_line("detected"), Some(1.0));
+        assert_eq!(parse_detector_line("DETECTED 0.72"), Some(0.72));
+        assert_eq!(parse_detector_line("score 1.4"), Some(1.0));
+        assert_eq!(parse_detector_line("loading model hey.onnx"), None);
+        assert_eq!(parse_detector_line("score nan-ish"), None);
+        assert_eq!(sensitivity_threshold(0.5), 0.5);
+        assert_eq!(sensitivity_threshold(1.0), 0.05);
+    }
+
+    #[test]
+    fn trigger_gate_applies_cooldown() {
+        let mut gate = TriggerGate::default();
+        assert!(gate.allow(1_000));
+        assert!(!gate.allow(2_500));
+        assert!(gate.allow(3_000));
+    }
+
+    #[test]
+    fn command_line_split_honors_quotes() {
+        assert_eq!(
+            split_command_line(r#""C:\Program Files\oww\detect.exe" --model "hey tv.onnx" -q"#),
+            vec![
+                r"C:\Program Files\oww\detect.exe",
+                "--model",
+                "hey tv.onnx",
+                "-q"
+            ]
+        );
+        assert!(split_command_line("   ").is_empty());
+    }
+}
@@ -1,7 +1,15 @@
 import { useCallback, useEffect, useState } from "react";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import { defaultTauriGateway } from "./infra/runtimePorts";
-import type { Settings } from "./types";
+import type {
+  CrashReport,
+  DataDirLockStatus,
+  RecordingAutoStopped,
+  RecoveryReport,
+  Settings,
+  SettingsConflict,
+} from "./types";
+import { PixelButton } from "./ui/PixelButton";
 import { PixelTabs, type TabKey } from "./ui/PixelTabs";
 import { PixelToastHost, type ToastItem, type ToastTone } from "./ui/PixelToast";
 import { MainScreen } from "./screens/MainScreen";
@@ -11,6 +19,16 @@ import { userMessageFromError } from "./domain/diagnostic";
 
 let toastSeq = 0;
 
+// The record input cache rewrites these on every recording; ignoring them keeps
+// settings drafts from being reset mid-edit.
+function sameUserSettings(a: Settings, b: Settings) {
+  const strip = (s: Settings) =>
+    JSON.stringify(
+      Object.entries(s).filter(([key]) => !key.startsWith("record_last_working_")),
+    );
+  return strip(a) === strip(b);
+}
+
 function uid() {
   toastSeq += 1;
   return `toast-${toastSeq}`;
@@ -22,6 +40,12 @@ export default function App() {
   const [settings, setSettings] = useState<Settings | null>(null);
   const [settingsError, setSettingsError] = useState<string | null>(null);
   const [epoch, setEpoch] = useState(0);
+  const [dataDirLock, setDataDirLock] = useState<DataDirLockStatus | null>(null);
+  const [takeoverPending, setTakeoverPending] = useState(false);
+  const [guestMode, setGuestMode] = useState(false);
+  const [announcement, setAnnouncement] = useState("");
+  const [crashReport, setCrashReport] = useState<CrashReport | null>(null);
+  const [recoveryReport, setRecoveryReport] = useState<RecoveryReport | null>(null);
 
   const pushToast = useCallback((message: string, tone: ToastTone = "default") => {
     const id = uid();
@@ -65,6 +89,201 @@ export default function App() {
     reloadSettings();
   }, [reloadSettings]);
 
+  useEffect(() => {
+    let cancelled = false;
+    let unlisten: (() => void) | null = null;
+    void (async () => {
+      const stop = await defaultTauriGateway.listen<Settings>("tv_settings_changed", (next) => {
+        if (cancelled) return;
+        setSettings((prev) => (prev && sameUserSettings(prev, next) ? prev : next));
+        setSettingsError(null);
+      });
+      if (cancelled) {
+        stop();
+      } else {
+        unlisten = stop;
+      }
+    })();
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, []);
+
+  useEffect(() => {
+    let cancelled = false;
+    let unlisten: (() => void) | null = null;
+    void (async () => {
+      const stop = await defaultTauriGateway.listen<DataDirLockStatus>("tv_data_dir_lock", (next) => {
+        if (!cancelled) setDataDirLock(next);
+      });
+      if (cancelled) {
+        stop();
+      } else {
+        unlisten = stop;
+      }
+      try {
+        const guest = (await defaultTauriGateway.invoke("guest_mode_enabled")) as boolean;
+        const status = (await defaultTauriGateway.invoke("data_dir_lock_status")) as DataDirLockStatus;
+        if (cancelled) return;
+        setGuestMode(guest);
+        setDataDirLock(status);
+      } catch {
+        // the tv_data_dir_lock event still reports it
+      }
+    })();
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, []);
+
+  // Hand edits to settings.json are applied by the backend watcher; this only
+  // reports the cases that need the user's attention.
+  useEffect(() => {
+    let cancelled = false;
+    let unlisten: (() => void) | null = null;
+    void (async () => {
+      const stop = await defaultTauriGateway.listen<SettingsConflict>("tv_settings_conflict", (next) => {
+        if (cancelled) return;
+        if (next.kind === "invalid") pushToast("SETTINGS.JSON EDIT IS NOT VALID JSON, NOT APPLIED", "danger");
+        else if (next.kind === "overwritten") pushToast("SETTINGS.JSON EDIT WAS OVERWRITTEN BY AN IN-APP SAVE", "danger");
+        else pushToast("SETTINGS.JSON EDIT MERGED WITH AN IN-APP CHANGE", "default");
+      });
+      if (cancelled) {
+        stop();
+      } else {
+        unlisten = stop;
+      }
+    })();
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, [pushToast]);
+
+  // The backend already stopped the recording; this only tells the user why.
+  useEffect(() => {
+    let cancelled = false;
+    let unlisten: (() => void) | null = null;
+    void (async () => {
+      const stop = await defaultTauriGateway.listen<RecordingAutoStopped>("recording_auto_stopped", (next) => {
+        if (!cancelled) pushToast(`STOPPED AFTER ${(next.silenceMs / 1000).toFixed(1)}S OF SILENCE`, "default");
+      });
+      if (cancelled) {
+        stop();
+      } else {
+        unlisten = stop;
+      }
+    })();
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, [pushToast]);
+
+  // Crashes from an earlier run; the event can fire before this listens, so the
+  // status is pulled once as well.
+  useEffect(() => {
+    let cancelled = false;
+    let unlisten: (() => void) | null = null;
+    void (async () => {
+      const stop = await defaultTauriGateway.listen<CrashReport>("tv_crash_detected", (next) => {
+        if (!cancelled) setCrashReport(next);
+      });
+      if (cancelled) {
+        stop();
+      } else {
+        unlisten = stop;
+      }
+      try {
+        const report = (await defaultTauriGateway.invoke("crash_report_status")) as CrashReport | null;
+        if (!cancelled && report) setCrashReport(report);
+      } catch {
+        // the tv_crash_detected event still reports it
+      }
+    })();
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, []);
+
+  // Corrupt data files moved aside at startup; pulled once for the same reason.
+  useEffect(() => {
+    let cancelled = false;
+    let unlisten: (() => void) | null = null;
+    void (async () => {
+      const stop = await defaultTauriGateway.listen<RecoveryReport>("tv_data_recovered", (next) => {
+        if (!cancelled) setRecoveryReport(next);
+      });
+      if (cancelled) {
+        stop();
+      } else {
+        unlisten = stop;
+      }
+      try {
+        const report = (await defaultTauriGateway.invoke("data_recovery_status")) as RecoveryReport | null;
+        if (!cancelled && report) setRecoveryReport(report);
+      } catch {
+        // the tv_data_recovered event still reports it
+      }
+    })();
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, []);
+
+  const dismissCrashReport = useCallback(async () => {
+    if (!crashReport) return;
+    setCrashReport(null);
+    try {
+      await defaultTauriGateway.invoke("acknowledge_crash_report", { lastTsMs: crashReport.last_ts_ms });
+    } catch {
+      // shown again next start
+    }
+  }, [crashReport]);
+
+  // Sent only while `a11y_announcements` is on; the live region lets screen
+  // readers pick it up through the webview.
+  useEffect(() => {
+    let cancelled = false;
+    let unlisten: (() => void) | null = null;
+    void (async () => {
+      const stop = await defaultTauriGateway.listen<{ text: string }>("tv_a11y_announcement", (next) => {
+        if (!cancelled) setAnnouncement(next.text);
+      });
+      if (cancelled) {
+        stop();
+      } else {
+        unlisten = stop;
+      }
+    })();
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, []);
+
+  const takeOverDataDir = useCallback(async () => {
+    setTakeoverPending(true);
+    try {
+      const status = (await defaultTauriGateway.invoke("data_dir_takeover")) as DataDirLockStatus;
+      setDataDirLock(status);
+      if (status.held) {
+        pushToast("DATA DIR TAKEN OVER", "ok");
+        await reloadSettings();
+      } else {
+        pushToast("TAKEOVER FAILED", "danger");
+      }
+    } catch {
+      pushToast("TAKEOVER FAILED", "danger");
+    } finally {
+      setTakeoverPending(false);
+    }
+  }, [pushToast, reloadSettings]);
+
   const savePatch = useCallback(
     async (patch: Record<string, unknown>) => {
       const next = (await defaultTauriGateway.invoke("update_settings", { patch })) as Settings;
@@ -155,6 +374,55 @@ export default function App() {
         </aside>
 
         <main className="contentStage">
+          <div className="srOnly" role="status" aria-live="assertive" aria-atomic="true">
+            {announcement}
+          </div>
+          {guestMode ? (
+            <div className="card">
+              <div className="muted">
+                访客模式：可以听写和插入，但不会保存历史、模板或设置，也不保留音频、上下文和调试数据。
+              </div>
+            </div>
+          ) : null}
+          {!guestMode && dataDirLock && !dataDirLock.held ? (
+            <div className="card">
+              <div className="row" style={{ justifyContent: "space-between" }}>
+                <div className="muted">
+                  数据目录正被
+                  {dataDirLock.owner ? ` ${dataDirLock.owner.user}@${dataDirLock.owner.host}（pid ${dataDirLock.owner.pid}）` : "另一个实例"}
+                  使用，本实例只读，保存设置和历史会失败（E_DATA_DIR_LOCKED）。接管后对方会在一分钟内释放。
+                </div>
+                <PixelButton onClick={takeOverDataDir} tone="danger" disabled={takeoverPending}>
+                  {takeoverPending ? "Waiting" : "Take over"}
+                </PixelButton>
+              </div>
+            </div>
+          ) : null}
+          {recoveryReport ? (
+            <div className="card">
+              <div className="row" style={{ justifyContent: "space-between" }}>
+                <div className="muted">
+                  启动时发现损坏的数据文件（
+                  {recoveryReport.files.map((f) => f.kind).join("、")}
+                  ），已移到 {recoveryReport.corrupt_dir} 并以默认值启动。需要找回内容时请查看该目录。
+                </div>
+                <PixelButton onClick={() => setRecoveryReport(null)}>Dismiss</PixelButton>
+              </div>
+            </div>
+          ) : null}
+          {crashReport ? (
+            <div className="card">
+              <div className="row" style={{ justifyContent: "space-between" }}>
+                <div className="muted">
+                  上次运行异常退出
+                  {crashReport.count > 1 ? `（共 ${crashReport.count} 次）` : ""}，
+                  {new Date(crashReport.last_ts_ms).toLocaleString()}：{crashReport.message || "panic"}。
+                  报告问题时请附上 {crashReport.log_path}。
+                </div>
+                <PixelButton onClick={() => void dismissCrashReport()}>Dismiss</PixelButton>
+              </div>
+            </div>
+          ) : null}
           <div style={{ display: tab === "main" ? "block" : "none" }}>
             <MainScreen
               settings={settings}
@@ -163,7 +431,12 @@ export default function App() {
             />
           </div>
           <div style={{ display: tab === "history" ? "block" : "none" }}>
-            <HistoryScreen epoch={epoch} pushToast={pushToast} />
+            <HistoryScreen
+              epoch={epoch}
+              settings={settings}
+              savePatch={savePatch}
+              pushToast={pushToast}
+            />
           </div>
           <div style={{ display: tab === "settings" ? "block" : "none" }}>
             <SettingsScreen
@@ -0,0 +1,74 @@
+import type { CSSProperties } from "react";
+import { useEffect, useState } from "react";
+import { defaultTauriGateway } from "./infra/runtimePorts";
+import type { CaptionsConfig, CaptionsState } from "./types";
+
+// Captions show the newest words; older text scrolls off the front.
+const CAPTION_MAX_CHARS = 120;
+
+function captionTail(text: string): string {
+  const trimmed = text.trim();
+  if (trimmed.length <= CAPTION_MAX_CHARS) return trimmed;
+  return `…${trimmed.slice(trimmed.length - CAPTION_MAX_CHARS)}`;
+}
+
+const DEFAULT_CAPTIONS_CONFIG: CaptionsConfig = {
+  enabled: false,
+  font_size_px: 44,
+  background_opacity: 0.6,
+  monitor_index: null,
+};
+
+export default function CaptionsApp() {
+  const [config, setConfig] = useState<CaptionsConfig>(DEFAULT_CAPTIONS_CONFIG);
+  const [state, setState] = useState<CaptionsState>({ visible: false, text: "", ts_ms: 0 });
+
+  useEffect(() => {
+    document.body.classList.add("isOverlay");
+    return () => document.body.classList.remove("isOverlay");
+  }, []);
+
+  useEffect(() => {
+    let cancelled = false;
+    const unlistenFns: Array<() => void> = [];
+    const track = (fn: () => void) => {
+      if (cancelled) {
+        fn();
+      } else {
+        unlistenFns.push(fn);
+      }
+    };
+
+    void (async () => {
+      const next = await defaultTauriGateway.invoke<CaptionsConfig>("captions_config");
+      if (!cancelled) setConfig(next);
+      track(await defaultTauriGateway.listen<CaptionsConfig>("tv_captions_config_changed", (updated) => {
+        if (!cancelled) setConfig(updated);
+      }));
+      track(await defaultTauriGateway.listen<CaptionsState>("tv_captions_state", (updated) => {
+        if (!cancelled && updated) setState(updated);
+      }));
+    })();
+
+    return () => {
+      cancelled = true;
+      for (const fn of unlistenFns) fn();
+    };
+  }, []);
+
+  const style = {
+    "--subtitle-bg-opacity": String(config.background_opacity),
+    "--subtitle-font-size": `${config.font_size_px}px`,
+  } as CSSProperties;
+
+  return (
+    <div
+      className={`captionsRoot ${state.visible ? "" : "isHidden"}`}
+      style={style}
+      role="status"
+      aria-live="polite"
+    >
+      <div className="captionsText">{captionTail(state.text) || "…"}</div>
+    </div>
+  );
+}
@@ -0,0 +1,57 @@
+import { useEffect, useState } from "react";
+import { defaultTauriGateway } from "./infra/runtimePorts";
+import type { CaptureIndicatorState, CaptureSource } from "./types";
+
+const SOURCE_LABELS: Record<CaptureSource, string> = {
+  recorder: "REC",
+  mic_test: "MIC TEST",
+  noise_calibration: "CALIBRATING",
+  wake_word: "LISTENING",
+  bug_report: "BUG REPORT",
+};
+
+// The backend shows and hides this window; it only names what is capturing.
+export default function CaptureIndicatorApp() {
+  const [sources, setSources] = useState<CaptureSource[]>([]);
+
+  useEffect(() => {
+    document.body.classList.add("isOverlay");
+    return () => document.body.classList.remove("isOverlay");
+  }, []);
+
+  useEffect(() => {
+    let cancelled = false;
+    let unlisten: (() => void) | null = null;
+    void (async () => {
+      const current = await defaultTauriGateway.invoke<CaptureSource[]>("capture_indicator_status");
+      if (!cancelled) setSources(current);
+      const stop = await defaultTauriGateway.listen<CaptureIndicatorState>(
+        "tv_capture_indicator",
+        (updated) => {
+          if (!cancelled && updated) setSources(updated.sources);
+        },
+      );
+      if (cancelled) {
+        stop();
+      } else {
+        unlisten = stop;
+      }
+    })();
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, []);
+
+  // The recorder outranks the background listeners when several are open.
+  const label = sources.includes("recorder")
+    ? SOURCE_LABELS.recorder
+    : SOURCE_LABELS[sources[0] ?? "recorder"];
+
+  return (
+    <div className="captureIndicator" role="status" aria-label={`Microphone in use: ${label}`}>
+      <span className="captureIndicatorDot" />
+      <span>{label}</span>
+    </div>
+  );
+}
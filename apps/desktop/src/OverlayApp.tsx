@@ -15,7 +15,16 @@ import {
   workflowPhaseName,
   workflowViewFromPayload,
 } from "./domain/workflowView";
-import type { OverlayConfig, Settings, UiEvent, WorkflowView } from "./types";
+import { LevelMeter, useAudioLevel } from "./ui/LevelMeter";
+import type {
+  AudioLevel,
+  OverlayConfig,
+  OverlayStatusStyle,
+  OverlayTheme,
+  Settings,
+  UiEvent,
+  WorkflowView,
+} from "./types";
 
 type GlobalHotkeyEvent = {
   action: "primary";
@@ -36,10 +45,12 @@ export default function OverlayApp() {
   const [draftText, setDraftText] = useState("");
   const [liveText, setLiveText] = useState("");
   const [config, setConfig] = useState<OverlayConfig>(DEFAULT_OVERLAY_CONFIG);
+  const [theme, setTheme] = useState<OverlayTheme | null>(null);
   const phaseRef = useRef("idle");
   const draftRef = useRef("");
   const liveRef = useRef("");
   const dragActiveRef = useRef(false);
+  const primaryInFlightRef = useRef(false);
   const savePositionTimerRef = useRef<number | null>(null);
 
   useEffect(() => {
@@ -69,7 +80,14 @@ export default function OverlayApp() {
     [workflow],
   );
 
-  const subtitleText = displayText.trim() || overlayView.status;
+  // The backend owns labels and colors; the built-in label only covers the
+  // moment before the first overlay_get_theme reply.
+  const statusStyle: OverlayStatusStyle | null =
+    theme?.statuses[workflowPhaseName(workflow.phase)] ?? theme?.statuses.idle ?? null;
+  const statusLabel = statusStyle?.label ?? overlayView.status;
+  const subtitleText = displayText.trim() || statusLabel;
+  const audioLevel = useAudioLevel();
+  const meterLevel = workflowPhaseName(workflow.phase) === "recording" ? audioLevel : null;
 
   const acceptWorkflowView = useCallback((next: WorkflowView) => {
     const phase = workflowPhaseName(next.phase);
@@ -118,6 +136,30 @@ export default function OverlayApp() {
     };
   }, []);
 
+  useEffect(() => {
+    let cancelled = false;
+    let unlisten: (() => void) | null = null;
+    void (async () => {
+      const next = await defaultTauriGateway.invoke<OverlayTheme>("overlay_get_theme");
+      if (!cancelled) setTheme(next);
+      const stop = await defaultTauriGateway.listen<OverlayTheme>(
+        "tv_overlay_theme",
+        (updated) => {
+          if (!cancelled) setTheme(updated);
+        },
+      );
+      if (cancelled) {
+        stop();
+      } else {
+        unlisten = stop;
+      }
+    })();
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, []);
+
   useEffect(() => {
     let unlisten: (() => void) | null = null;
     const currentWindow = getCurrentWindow();
@@ -149,7 +191,7 @@ export default function OverlayApp() {
       await defaultTauriGateway.invoke("overlay_set_state", {
         state: {
           visible: settings.hotkeys_show_overlay === true && overlayView.visible,
-          status: overlayView.status,
+          status: statusLabel,
           detail: overlayView.detail,
           ts_ms: Date.now(),
         },
@@ -158,11 +200,15 @@ export default function OverlayApp() {
     return () => {
       cancelled = true;
     };
-  }, [overlayView.detail, overlayView.status, overlayView.visible]);
+  }, [overlayView.detail, overlayView.visible, statusLabel]);
 
-  const runPrimaryFromAlt = useCallback(async () => {
+  const runPrimaryFromAlt = useCallback(async (idempotencyKey?: string) => {
     const phase = phaseRef.current;
     if (!canTogglePrimaryFromOverlay(phase)) return;
+    // A press that lands while the previous toggle is still in flight would be
+    // judged against a stale phase; it is the same intent, so drop it.
+    if (primaryInFlightRef.current) return;
+    primaryInFlightRef.current = true;
 
     if (phase !== "recording") {
       setLiveText("");
@@ -171,11 +217,13 @@ export default function OverlayApp() {
     try {
       acceptWorkflowView(
         await defaultTauriGateway.invoke<WorkflowView>("workflow_command", {
-          req: { command: "primary" },
+          req: { command: "primary", idempotencyKey: idempotencyKey ?? null },
         }),
       );
     } catch {
       await refreshWorkflowSnapshot();
+    } finally {
+      primaryInFlightRef.current = false;
     }
   }, [acceptWorkflowView, refreshWorkflowSnapshot]);
 
@@ -198,7 +246,8 @@ export default function OverlayApp() {
       track(await defaultTauriGateway.listen<GlobalHotkeyEvent>("tv_global_hotkey", async (event) => {
         if (!event) return;
         if (event.action === "primary") {
-          await runPrimaryFromAlt();
+          // The same hotkey press can reach us twice; its timestamp dedupes it.
+          await runPrimaryFromAlt(typeof event.tsMs === "number" ? `hotkey-${event.tsMs}` : undefined);
         }
       }));
 
@@ -291,7 +340,10 @@ export default function OverlayApp() {
   return (
     <SubtitleOverlay
       config={config}
+      theme={theme}
+      statusColor={displayText.trim() ? null : statusStyle?.color ?? null}
       text={subtitleText}
+      level={meterLevel}
       visible={overlayView.visible}
       onDragActivity={(active) => {
         dragActiveRef.current = active;
@@ -302,20 +354,28 @@ export default function OverlayApp() {
 
 type SubtitleOverlayProps = {
   config: OverlayConfig;
+  theme: OverlayTheme | null;
+  statusColor: string | null;
   text: string;
+  level: AudioLevel | null;
   visible: boolean;
   onDragActivity: (active: boolean) => void;
 };
 
 function SubtitleOverlay({
   config,
+  theme,
+  statusColor,
   text,
+  level,
   visible,
   onDragActivity,
 }: SubtitleOverlayProps) {
+  const fontScale = theme?.font_scale ?? 1;
   const style = {
-    "--subtitle-bg-opacity": String(config.background_opacity),
-    "--subtitle-font-size": `${config.font_size_px}px`,
+    "--subtitle-bg-opacity": String(theme?.background_opacity ?? config.background_opacity),
+    "--subtitle-font-size": `${Math.round(config.font_size_px * fontScale)}px`,
+    ...(statusColor ? { "--subtitle-color": statusColor } : {}),
   } as CSSProperties;
 
   return (
@@ -338,6 +398,11 @@ function SubtitleOverlay({
       >
         {text}
       </div>
+      {level ? (
+        <div className="subtitleOverlayMeter" data-tauri-drag-region>
+          <LevelMeter level={level} />
+        </div>
+      ) : null}
     </div>
   );
 }
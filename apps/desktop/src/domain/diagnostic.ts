@@ -24,6 +24,12 @@ export function extractErrorCode(raw: string): string | null {
   return m ? m[1] : null;
 }
 
+// Busy rejections carry an estimate of when the active task frees its slot.
+export function extractRetryAfterMs(raw: string): number | null {
+  const m = raw.match(/\bretry_after_ms=(\d+)\b/);
+  return m ? Number(m[1]) : null;
+}
+
 export function compactDetail(raw: string, maxChars = 220): string {
   const oneLine = raw.replace(/\s+/g, " ").trim();
   if (!oneLine) return "";
@@ -42,17 +48,27 @@ function titleForCode(code: string, fallback: string): string {
   if (code === "E_REMOTE_ASR_HTTP_STATUS_429") return "Speech recognition service is rate limited";
   if (remoteAsrServerError(code)) return "Speech recognition service is unavailable";
   if (code === "E_REMOTE_ASR_HTTP_SEND") return "Speech recognition service is unreachable";
+  if (code.startsWith("E_TLS_")) return "Server certificate is not trusted";
+  if (code === "E_OFFLINE_MODE") return "Offline mode is on";
   if (code === "E_ASR_EMPTY_TEXT" || code === "E_REMOTE_ASR_EMPTY_TEXT") return "No speech detected";
   if (code.startsWith("E_SETTINGS_")) return "Settings need attention";
   if (code.startsWith("E_TOOLCHAIN_")) return "Local audio tools need repair";
+  if (code === "E_PERMISSION_MICROPHONE_DENIED") return "Microphone access is off";
+  if (code === "E_PERMISSION_ACCESSIBILITY_DENIED") return "Accessibility access is off";
+  if (code === "E_PERMISSION_SCREEN_RECORDING_DENIED") return "Screen recording access is off";
   if (code.startsWith("E_CONTEXT_CAPTURE_")) return "App context is unavailable";
   if (code.startsWith("E_HOTKEY_")) return "Keyboard shortcut could not run";
   if (code === "E_RECORD_ALREADY_ACTIVE" || code === "E_TASK_ALREADY_ACTIVE") return "An action is already running";
   if (code === "E_RECORD_UNSUPPORTED") return "Recording is unavailable on this system";
   if (code.startsWith("E_RECORD_")) return "Recording could not start";
-  if (code.startsWith("E_REMOTE_ASR_WAV_")) return "Recorded audio could not be read";
+  if (code === "E_INPUT_INVALID" || code.startsWith("E_REMOTE_ASR_WAV_")) return "Recorded audio could not be read";
   if (code.startsWith("E_STREAMING_TRANSCRIBE_") || code.startsWith("E_DOUBAO_ASR_") || code.startsWith("E_REMOTE_ASR_")) return "Speech recognition could not start";
   if (code.startsWith("E_REWRITE_") || code.startsWith("HTTP_")) return "Text improvement failed";
+  if (code === "E_EXPORT_IME_COMPOSING") return "Input method is still composing";
+  if (code === "E_EXPORT_TARGET_ELEVATED") return "Target app runs as administrator";
+  if (code === "E_EXPORT_ELEVATION_DECLINED") return "Administrator paste was declined";
+  if (code === "E_EXPORT_TARGET_LOCK_LOST") return "Locked target window was closed";
+  if (code === "E_EXPORT_TARGET_LOCK_FOCUS_FAILED") return "Locked target window could not be activated";
   if (code.startsWith("E_INSERT_") || code.startsWith("E_EXPORT_") || code.startsWith("E_OVERLAY_")) return "Text could not be pasted";
   if (code === "E_CMD_CANCEL") return "Cancel failed";
   return userTitleFromFallback(fallback);
@@ -69,11 +85,14 @@ function actionHintForCode(code: string): string {
   if (code === "E_REMOTE_ASR_HTTP_STATUS_429") return "Wait for the ASR rate limit to reset.";
   if (remoteAsrServerError(code)) return "Try again after the ASR service recovers.";
   if (code === "E_REMOTE_ASR_HTTP_SEND") return "Check the network connection and ASR service URL.";
+  if (code.startsWith("E_TLS_")) return "Add the internal CA bundle or pin the certificate in Settings.";
+  if (code === "E_OFFLINE_MODE") return "Turn off offline mode in Settings to use network services.";
   if (code === "E_ASR_EMPTY_TEXT" || code === "E_REMOTE_ASR_EMPTY_TEXT") return "Continue recording or try again.";
-  if (code.startsWith("E_TOOLCHAIN_")) return "Repair the local audio tools, then restart the app.";
+  if (code.startsWith("E_PERMISSION_")) return "Grant the permission in Settings > Permissions.";
+  if (code.startsWith("E_TOOLCHAIN_")) return "Repair the local audio tools; they are checked again automatically.";
   if (code.startsWith("E_RECORD_")) return "Check the selected microphone and try again.";
-  if (code.startsWith("E_FFMPEG_")) return "Repair the local audio tools, then restart the app.";
-  if (code.startsWith("E_REMOTE_ASR_WAV_")) return "Check the recording device and local audio tools.";
+  if (code.startsWith("E_FFMPEG_") || code.startsWith("E_FFPROBE_")) return "Repair the local audio tools; they are checked again automatically.";
+  if (code === "E_INPUT_INVALID" || code.startsWith("E_REMOTE_ASR_WAV_")) return "Check the recording device and local audio tools.";
   if (
     code.startsWith("E_ASR_") ||
     code.startsWith("E_STREAMING_TRANSCRIBE_") ||
@@ -84,6 +103,12 @@ function actionHintForCode(code: string): string {
     return "Check speech recognition settings and try again.";
   }
   if (code.startsWith("E_REWRITE_") || code.startsWith("HTTP_")) return "Check text improvement settings and try again.";
+  if (code === "E_EXPORT_IME_COMPOSING") return "Finish or cancel the input method composition, then paste again.";
+  if (code === "E_EXPORT_TARGET_ELEVATED" || code === "E_EXPORT_ELEVATION_DECLINED") {
+    return "Run TypeVoice as administrator, or turn on the elevated paste helper in Settings > Export. The text is on the clipboard.";
+  }
+  if (code === "E_EXPORT_TARGET_LOCK_LOST") return "Lock a new target window, or paste from the clipboard.";
+  if (code === "E_EXPORT_TARGET_LOCK_FOCUS_FAILED") return "Bring the locked window to the front, or unlock the target.";
   if (code.startsWith("E_INSERT_") || code.startsWith("E_EXPORT_") || code.startsWith("E_OVERLAY_")) return "Select the target app and try again.";
   if (code === "E_HOTKEY_EVENT_INCOMPLETE") return "Restart the app, then try the shortcut again.";
   if (code === "E_TASK_ALREADY_ACTIVE" || code === "E_RECORD_ALREADY_ACTIVE") {
@@ -111,8 +136,12 @@ function userTitleFromFallback(fallback: string): string {
 export function buildDiagnostic(err: unknown, fallbackTitle: string): DiagnosticView {
   const raw = errorMessage(err);
   const code = extractErrorCode(raw) ?? "E_UNKNOWN";
+  const retryAfterMs = extractRetryAfterMs(raw);
   return {
-    title: titleForCode(code, fallbackTitle),
+    title:
+      retryAfterMs === null
+        ? titleForCode(code, fallbackTitle)
+        : `Busy, ready in ~${Math.ceil(retryAfterMs / 1000)}s`,
     code,
     detail: compactDetail(raw || fallbackTitle),
     actionHint: actionHintForCode(code),
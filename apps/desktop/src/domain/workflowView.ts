@@ -25,6 +25,7 @@ export const EMPTY_WORKFLOW_VIEW: WorkflowView = {
   phase: "idle",
   taskId: null,
   recordingSessionId: null,
+  backgroundTaskIds: [],
   lastTranscriptId: null,
   lastAsrText: "",
   lastText: "",
@@ -62,6 +63,9 @@ export function workflowViewFromPayload(payload: unknown): WorkflowView | null {
     phase: workflowPhaseName(String(raw.phase || "idle")),
     taskId: optionalString(raw.taskId),
     recordingSessionId: optionalString(raw.recordingSessionId),
+    backgroundTaskIds: Array.isArray(raw.backgroundTaskIds)
+      ? raw.backgroundTaskIds.filter((id): id is string => typeof id === "string")
+      : [],
     lastTranscriptId: optionalString(raw.lastTranscriptId),
     lastAsrText: String(raw.lastAsrText || ""),
     lastText: String(raw.lastText || ""),
@@ -73,6 +77,7 @@ export function workflowViewFromPayload(payload: unknown): WorkflowView | null {
     canRewrite: raw.canRewrite === true,
     canInsert: raw.canInsert === true,
     canCopy: raw.canCopy === true,
+    replayedTaskId: optionalString(raw.replayedTaskId),
   };
 }
 
@@ -1,6 +1,8 @@
 import React from "react";
 import ReactDOM from "react-dom/client";
 import App from "./App";
+import CaptionsApp from "./CaptionsApp";
+import CaptureIndicatorApp from "./CaptureIndicatorApp";
 import OverlayApp from "./OverlayApp";
 import "./styles/app.css";
 import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
@@ -9,6 +11,8 @@ function pickRoot() {
   try {
     const w = getCurrentWebviewWindow();
     if (w.label === "overlay") return OverlayApp;
+    if (w.label === "captions") return CaptionsApp;
+    if (w.label === "capture_indicator") return CaptureIndicatorApp;
   } catch {
     // ignore: fallback to main app
   }
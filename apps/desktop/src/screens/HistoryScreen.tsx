@@ -1,19 +1,36 @@
 import { useEffect, useMemo, useRef, useState } from "react";
 import { defaultTauriGateway } from "../infra/runtimePorts";
-import type { HistoryItem } from "../types";
+import type { AccuracyGroup, CorrectionSuggestion, HistoryItem, Settings } from "../types";
 
 type Props = {
   epoch: number;
+  settings: Settings | null;
+  savePatch: (patch: Record<string, unknown>) => Promise<void>;
   pushToast: (msg: string, tone?: "default" | "ok" | "danger") => void;
 };
 
 const PAGE = 50;
 
+type Editing = { taskId: string; original: string; text: string; tags: string; note: string };
+
+function parseTags(raw: string): string[] {
+  return raw.split(",").map((t) => t.trim()).filter(Boolean);
+}
+
 export function HistoryScreen({
   epoch,
+  settings,
+  savePatch,
   pushToast,
 }: Props) {
   const [items, setItems] = useState<HistoryItem[]>([]);
+  const [editing, setEditing] = useState<Editing | null>(null);
+  const [tagFilter, setTagFilter] = useState("");
+  const [activeTag, setActiveTag] = useState("");
+  const [searchText, setSearchText] = useState("");
+  const [activeQuery, setActiveQuery] = useState("");
+  const [suggestions, setSuggestions] = useState<CorrectionSuggestion[]>([]);
+  const [accuracy, setAccuracy] = useState<AccuracyGroup[]>([]);
   const [loading, setLoading] = useState(false);
   const [hasMore, setHasMore] = useState(true);
   const scrollerRef = useRef<HTMLDivElement | null>(null);
@@ -23,14 +40,46 @@ export function HistoryScreen({
     return items[items.length - 1]!.created_at_ms;
   }, [items]);
 
+  async function loadAccuracy() {
+    try {
+      setAccuracy((await defaultTauriGateway.invoke("asr_accuracy_stats")) as AccuracyGroup[]);
+    } catch {
+      setAccuracy([]);
+    }
+  }
+
+  async function loadSuggestions() {
+    try {
+      const rows = (await defaultTauriGateway.invoke("suggest_corrections", {
+        minOccurrences: null,
+      })) as CorrectionSuggestion[];
+      setSuggestions(rows);
+    } catch {
+      setSuggestions([]);
+    }
+  }
+
+  // A search query takes over from the tag filter while it is set.
+  function fetchPage(beforeMs: number | null) {
+    if (activeQuery) {
+      return defaultTauriGateway.invoke("history_search", {
+        query: activeQuery,
+        limit: PAGE,
+        beforeMs,
+      }) as Promise<HistoryItem[]>;
+    }
+    return defaultTauriGateway.invoke("history_list", {
+      limit: PAGE,
+      beforeMs,
+      tag: activeTag || null,
+    }) as Promise<HistoryItem[]>;
+  }
+
   async function loadFirst() {
     setLoading(true);
     setHasMore(true);
     try {
-      const rows = (await defaultTauriGateway.invoke("history_list", {
-        limit: PAGE,
-        beforeMs: null,
-      })) as HistoryItem[];
+      const rows = await fetchPage(null);
       setItems(rows);
       setHasMore(rows.length === PAGE);
       // reset scroll to top when reloading
@@ -48,10 +97,7 @@ export function HistoryScreen({
     if (oldestMs == null) return;
     setLoading(true);
     try {
-      const rows = (await defaultTauriGateway.invoke("history_list", {
-        limit: PAGE,
-        beforeMs: oldestMs,
-      })) as HistoryItem[];
+      const rows = await fetchPage(oldestMs);
       setItems((prev) => [...prev, ...rows]);
       setHasMore(rows.length === PAGE);
     } catch {
@@ -64,7 +110,23 @@ export function HistoryScreen({
   useEffect(() => {
     loadFirst();
     // eslint-disable-next-line react-hooks/exhaustive-deps
-  }, [epoch]);
+  }, [epoch, activeTag, activeQuery, settings?.time_format]);
+
+  useEffect(() => {
+    void loadSuggestions();
+    void loadAccuracy();
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, [epoch, settings?.rewrite_glossary]);
+
+  useEffect(() => {
+    const t = window.setTimeout(() => setActiveTag(tagFilter.trim()), 250);
+    return () => window.clearTimeout(t);
+  }, [tagFilter]);
+
+  useEffect(() => {
+    const t = window.setTimeout(() => setActiveQuery(searchText.trim()), 250);
+    return () => window.clearTimeout(t);
+  }, [searchText]);
 
   function onScroll() {
     const el = scrollerRef.current;
@@ -84,16 +146,139 @@ export function HistoryScreen({
     }
   }
 
+  async function saveEdit() {
+    if (!editing) return;
+    const text = editing.text.trim();
+    if (!text) return;
+    const textChanged = text !== editing.original;
+    try {
+      if (textChanged) {
+        await defaultTauriGateway.invoke("history_update_final_text", {
+          taskId: editing.taskId,
+          finalText: text,
+        });
+      }
+      const tags = (await defaultTauriGateway.invoke("history_set_tags", {
+        taskId: editing.taskId,
+        tags: parseTags(editing.tags),
+      })) as string[];
+      const note = editing.note.trim();
+      await defaultTauriGateway.invoke("history_set_note", { taskId: editing.taskId, note });
+      setItems((prev) =>
+        prev.map((h) => (h.task_id === editing.taskId ? { ...h, final_text: text, tags, note } : h)),
+      );
+      setEditing(null);
+      pushToast(textChanged ? "Correction saved" : "Saved", "ok");
+      if (textChanged) {
+        void loadSuggestions();
+        void loadAccuracy();
+      }
+    } catch {
+      pushToast("Changes could not be saved", "danger");
+    }
+  }
+
+  async function addToGlossary(term: string) {
+    const glossary = settings?.rewrite_glossary ?? [];
+    if (glossary.some((g) => g.trim().toLowerCase() === term.toLowerCase())) return;
+    try {
+      await savePatch({ rewrite_glossary: [...glossary, term] });
+      pushToast(`Added "${term}" to glossary`, "ok");
+    } catch {
+      pushToast("Glossary could not be updated", "danger");
+    }
+  }
+
   return (
     <div className="pageSurface historySurface">
       <div className="pageHeader">
         <div className="sectionTitle">history</div>
         <div className="muted">{items.length} items</div>
+        <input
+          className="historySearch"
+          value={searchText}
+          placeholder="Search text"
+          onChange={(e) => setSearchText(e.target.value)}
+        />
+        <input
+          className="historyTagFilter"
+          disabled={!!activeQuery}
+          value={tagFilter}
+          placeholder="Filter by tag"
+          onChange={(e) => setTagFilter(e.target.value)}
+        />
+      </div>
+
+      <div className="historySuggestions">
+        {accuracy.map((g) => {
+          const trend = g.weekly.slice(-4).map((w) => percent(w.wer)).join(" → ");
+          return (
+            <div key={`${g.asr_model}\u0000${g.microphone}`} className="historySuggestion" title={`Weekly WER: ${trend}`}>
+              <span>{g.asr_model}</span>
+              <span className="muted">{g.microphone}</span>
+              <span>WER {percent(g.wer)}</span>
+              <span>CER {percent(g.cer)}</span>
+              <span className="muted">{g.tasks} corrected</span>
+            </div>
+          );
+        })}
+        {suggestions.map((sug) => (
+          <div key={`${sug.heard}\u0000${sug.corrected}`} className="historySuggestion">
+            <span className="muted">{sug.heard}</span>
+            <span>→ {sug.corrected}</span>
+            <span className="muted">×{sug.occurrences}</span>
+            <button
+              type="button"
+              disabled={sug.in_glossary || !settings}
+              onClick={() => void addToGlossary(sug.corrected)}
+            >
+              {sug.in_glossary ? "In glossary" : "Add to glossary"}
+            </button>
+          </div>
+        ))}
       </div>
 
       <div className="historyScroller" ref={scrollerRef} onScroll={onScroll}>
         {items.map((h) => {
           const text = (h.final_text || h.asr_text || "").trim();
+          if (editing?.taskId === h.task_id) {
+            return (
+              <div key={h.task_id} className="historyRow isEditing">
+                <div className="historyTime">
+                  {formatTime(h)}
+                </div>
+                <div className="historyEdit">
+                  <textarea
+                    value={editing.text}
+                    autoFocus
+                    onChange={(e) => setEditing({ ...editing, text: e.target.value })}
+                    onKeyDown={(e) => {
+                      if (e.key === "Escape") setEditing(null);
+                      if (e.key === "Enter" && (e.ctrlKey || e.metaKey)) void saveEdit();
+                    }}
+                  />
+                  <input
+                    value={editing.tags}
+                    placeholder="Tags, comma separated"
+                    onChange={(e) => setEditing({ ...editing, tags: e.target.value })}
+                  />
+                  <input
+                    value={editing.note}
+                    placeholder="Note"
+                    onChange={(e) => setEditing({ ...editing, note: e.target.value })}
+                  />
+                  <div className="row">
+                    <button type="button" onClick={() => void saveEdit()} disabled={!editing.text.trim()}>
+                      Save
+                    </button>
+                    <button type="button" onClick={() => setEditing(null)}>
+                      Cancel
+                    </button>
+                  </div>
+                </div>
+              </div>
+            );
+          }
           return (
             <div
               key={h.task_id}
@@ -110,11 +295,35 @@ export function HistoryScreen({
               }}
             >
               <div className="historyTime">
-                {new Date(h.created_at_ms).toLocaleString()}
+                {formatTime(h)}
               </div>
               <div className="historyPreview">
                 {text || "-"}
+                {h.tags?.length || h.note ? (
+                  <div className="historyMeta muted">
+                    {h.tags?.map((t) => `#${t}`).join(" ")}
+                    {h.note ? ` ${h.note}` : ""}
+                  </div>
+                ) : null}
               </div>
+              <button
+                type="button"
+                className="historyEditButton"
+                title="Correct this text, tag or annotate it"
+                onClick={(e) => {
+                  e.stopPropagation();
+                  setEditing({
+                    taskId: h.task_id,
+                    original: text,
+                    text,
+                    tags: (h.tags ?? []).join(", "),
+                    note: h.note ?? "",
+                  });
+                }}
+                onKeyDown={(e) => e.stopPropagation()}
+              >
+                Edit
+              </button>
             </div>
           );
         })}
@@ -126,3 +335,11 @@ export function HistoryScreen({
     </div>
   );
 }
+
+function formatTime(h: HistoryItem): string {
+  return h.created_at_display ?? new Date(h.created_at_ms).toLocaleString();
+}
+
+function percent(value: number): string {
+  return `${(value * 100).toFixed(1)}%`;
+}
@@ -11,6 +11,8 @@ import {
   workflowViewFromPayload,
 } from "../domain/workflowView";
 import type {
+  ErrorExplanation,
+  ExportTargetLock,
   RuntimeToolchainStatus,
   Settings,
   TranscriptionMetrics,
@@ -19,6 +21,8 @@ import type {
   WorkflowView,
 } from "../types";
 import { IconStart, IconStop, IconTranscribing } from "../ui/icons";
+import { PixelButton } from "../ui/PixelButton";
+import { PixelDialog } from "../ui/PixelDialog";
 
 type Props = {
   settings: Settings | null;
@@ -33,6 +37,8 @@ export function MainScreen({
 }: Props) {
   const [workflow, setWorkflow] = useState<WorkflowView>(EMPTY_WORKFLOW_VIEW);
   const [liveTranscript, setLiveTranscript] = useState("");
+  const [targetLock, setTargetLock] = useState<ExportTargetLock | null>(null);
+  const [errorHelp, setErrorHelp] = useState<ErrorExplanation | null>(null);
   const autoRewriteStartedRef = useRef<Set<string>>(new Set());
   const autoInsertStartedRef = useRef<Set<string>>(new Set());
 
@@ -67,7 +73,7 @@ export function MainScreen({
     const text = (view.lastText || view.lastAsrText || "").trim();
     if (phase !== "transcribed" || !transcriptId || !text) return;
     if (autoRewriteStartedRef.current.has(transcriptId)) return;
-    if (settings?.rewrite_enabled !== true) return;
+    if (settings?.rewrite_enabled !== true || settings?.offline_mode === true) return;
     autoRewriteStartedRef.current.add(transcriptId);
     try {
       await defaultTauriGateway.invoke("workflow_rewrite", { req: { text } });
@@ -85,7 +91,7 @@ export function MainScreen({
         pushToast(refreshDiag.title, "danger");
       }
     }
-  }, [pushToast, runAutoInsert, settings?.rewrite_enabled]);
+  }, [pushToast, runAutoInsert, settings?.rewrite_enabled, settings?.offline_mode]);
 
   const acceptWorkflowView = useCallback(async (next: WorkflowView, autoContinue: boolean) => {
     setWorkflow(next);
@@ -93,7 +99,7 @@ export function MainScreen({
     if (phase === "recording") setLiveTranscript("");
     if (!autoContinue) return;
     if (phase === "transcribed") {
-      if (settings?.rewrite_enabled === true) {
+      if (settings?.rewrite_enabled === true && settings?.offline_mode !== true) {
         await runAutoRewrite(next);
       } else {
         await runAutoInsert(next);
@@ -103,7 +109,7 @@ export function MainScreen({
     if (phase === "rewritten") {
       await runAutoInsert(next);
     }
-  }, [runAutoInsert, runAutoRewrite, settings?.rewrite_enabled]);
+  }, [runAutoInsert, runAutoRewrite, settings?.rewrite_enabled, settings?.offline_mode]);
 
   useEffect(() => {
     (async () => {
@@ -115,6 +121,13 @@ export function MainScreen({
     });
   }, [acceptWorkflowView, pushToast]);
 
+  useEffect(() => {
+    defaultTauriGateway
+      .invoke<ExportTargetLock | null>("export_target_lock_status")
+      .then((lock) => setTargetLock(lock ?? null))
+      .catch(() => setTargetLock(null));
+  }, []);
+
   useEffect(() => {
     (async () => {
       try {
@@ -125,6 +138,24 @@ export function MainScreen({
     })();
   }, [pushToast]);
 
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    let cancelled = false;
+    defaultTauriGateway
+      .listen<RuntimeToolchainStatus>("tv_runtime_toolchain_changed", (runtime) => {
+        if (runtime?.ready) pushToast("Local audio tools ready", "ok");
+      })
+      .then((fn) => {
+        if (cancelled) fn();
+        else unlisten = fn;
+      })
+      .catch(() => {});
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, [pushToast]);
+
   useEffect(() => {
     let cancelled = false;
     const unlistenFns: Array<() => void> = [];
@@ -229,6 +260,23 @@ export function MainScreen({
           }
           return;
         }
+        if (ev.kind === "diagnostic.warning") {
+          pushToast(
+            ev.errorCode === "E_RECORD_LOW_QUALITY_PROFILE"
+              ? "Low-quality mic profile (Bluetooth hands-free?)"
+              : ev.message,
+            "danger",
+          );
+          return;
+        }
+        if (ev.stage === "RewriteBudget") {
+          pushToast(`Rewrite request: ${ev.message}`, "default");
+          return;
+        }
+        if (ev.stage === "RewriteChunk" && ev.status === "started") {
+          pushToast(`Improving long text: ${ev.message}`, "default");
+          return;
+        }
         if (ev.kind === "rewrite.completed") {
           pushToast("Text improved", "ok");
           onHistoryChanged();
@@ -280,6 +328,47 @@ export function MainScreen({
     }
   }
 
+  async function skipRewrite() {
+    const taskId = workflow.taskId;
+    if (!taskId) return;
+    try {
+      const next = await defaultTauriGateway.invoke<WorkflowView>("cancel_stage", {
+        req: { taskId, stage: "Rewrite" },
+      });
+      await acceptWorkflowView(next, false);
+    } catch (err) {
+      const diag = buildDiagnostic(err, "Cancel failed");
+      pushToast(diag.title, "danger");
+    }
+  }
+
+  async function toggleTargetLock() {
+    try {
+      if (targetLock) {
+        await defaultTauriGateway.invoke<ExportTargetLock | null>("unlock_export_target");
+        setTargetLock(null);
+        pushToast("Target unlocked", "default");
+        return;
+      }
+      const lock = await defaultTauriGateway.invoke<ExportTargetLock>("lock_export_target");
+      setTargetLock(lock);
+      pushToast(`Pasting into ${targetLockLabel(lock)}`, "ok");
+    } catch (err) {
+      const diag = buildDiagnostic(err, "Target could not be locked");
+      pushToast(diag.title, "danger");
+    }
+  }
+
+  async function openErrorHelp(code: string) {
+    try {
+      const help = await defaultTauriGateway.invoke<ErrorExplanation | null>("explain_error", { code });
+      if (help) setErrorHelp(help);
+      else pushToast(`No help for ${code} yet`, "default");
+    } catch (err) {
+      pushToast(buildDiagnostic(err, "Help could not be loaded").title, "danger");
+    }
+  }
+
   const phase = workflowPhaseName(workflow.phase);
   const hint = primaryActionLabel(workflow.primaryLabel || "START");
   const streamText = phase === "recording" || phase === "transcribing" ? liveTranscript : "";
@@ -308,6 +397,26 @@ export function MainScreen({
         </button>
 
         <div className="mainHint">{statusLabel}</div>
+
+        {phase === "rewriting" && workflow.taskId ? (
+          <button
+            type="button"
+            className="targetLock"
+            onClick={() => void skipRewrite()}
+            title="Stop the text improvement and keep the transcript"
+          >
+            Skip rewrite
+          </button>
+        ) : null}
+
+        <button
+          type="button"
+          className={`targetLock ${targetLock ? "isLocked" : ""}`}
+          onClick={() => void toggleTargetLock()}
+          title={targetLock ? "Unlock the export target" : "Always paste into the last active window"}
+        >
+          {targetLock ? `Locked: ${targetLockLabel(targetLock)}` : "Lock target"}
+        </button>
       </div>
 
       <div className="resultSheet">
@@ -331,8 +440,43 @@ export function MainScreen({
           aria-hidden={!workflow.diagnosticLine}
         >
           {diagnosticMessage || ""}
+          {workflow.diagnosticLine && workflow.diagnosticCode ? (
+            <button
+              type="button"
+              className="mainDiagHelp"
+              onClick={() => void openErrorHelp(workflow.diagnosticCode || "")}
+            >
+              Help
+            </button>
+          ) : null}
         </div>
       </div>
+
+      <PixelDialog
+        open={errorHelp !== null}
+        title={errorHelp?.title || ""}
+        onClose={() => setErrorHelp(null)}
+        actions={<PixelButton onClick={() => setErrorHelp(null)}>Close</PixelButton>}
+      >
+        {errorHelp ? (
+          <div className="stack">
+            <div>{errorHelp.summary}</div>
+            <div className="muted">Likely causes</div>
+            <ul>
+              {errorHelp.causes.map((cause) => (
+                <li key={cause}>{cause}</li>
+              ))}
+            </ul>
+            <div className="muted">What to do</div>
+            <ol>
+              {errorHelp.remediation.map((step) => (
+                <li key={step}>{step}</li>
+              ))}
+            </ol>
+            <div className="muted">{errorHelp.code}</div>
+          </div>
+        ) : null}
+      </PixelDialog>
     </div>
   );
 }
@@ -382,6 +526,13 @@ function metricsPayload(payload: unknown): TranscriptionMetrics | null {
   };
 }
 
+function targetLockLabel(lock: ExportTargetLock): string {
+  const title = optionalString(lock.title);
+  if (title) return title.length > 28 ? `${title.slice(0, 27)}…` : title;
+  const image = optionalString(lock.processImage);
+  return image ? image.split(/[\\/]/).pop() || image : "window";
+}
+
 function optionalString(value: unknown): string | null {
   return typeof value === "string" && value.trim() ? value : null;
 }
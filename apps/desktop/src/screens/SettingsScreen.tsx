@@ -4,14 +4,33 @@ import { defaultTauriGateway } from "../infra/runtimePorts";
 import type {
   ApiCheckResult,
   ApiKeyStatus,
+  AuditEntry,
   AudioCaptureDevice,
+  AudioDevicesChanged,
+  BackupResult,
+  BugReportResult,
+  DailySummaryResult,
+  MicTestResult,
+  NoiseProfile,
+  PostprocessorConfig,
+  RecordDeviceOverride,
+  RestoreResult,
+  RewritePreview,
   Settings,
+  SettingsFieldError,
+  HotkeyBackendStatus,
+  WarmPoolStatus,
+  PowerStatus,
+  PermissionKind,
+  PermissionStatus,
+  WorkspaceInfo,
 } from "../types";
 import { PixelButton } from "../ui/PixelButton";
 import { PixelDialog } from "../ui/PixelDialog";
 import { PixelInput, PixelTextarea } from "../ui/PixelInput";
 import { PixelSelect, type PixelSelectOption } from "../ui/PixelSelect";
 import { PixelToggle } from "../ui/PixelToggle";
+import { LevelMeter, useAudioLevel } from "../ui/LevelMeter";
 import { IconGear } from "../ui/icons";
 
 type Props = {
@@ -31,11 +50,32 @@ const REASONING: PixelSelectOption[] = [
   { value: "xhigh", label: "xhigh" },
 ];
 
+const OVERLAY_STATUS_LANGUAGES: PixelSelectOption[] = [
+  { value: "en", label: "English" },
+  { value: "zh", label: "中文" },
+];
+
+const REWRITE_OUTPUT_FORMATS: PixelSelectOption[] = [
+  { value: "text", label: "Plain text" },
+  { value: "json_schema", label: "JSON (schema)" },
+];
+
 const ASR_PROVIDERS: PixelSelectOption[] = [
   { value: "doubao", label: "doubao streaming" },
   { value: "remote", label: "remote (cloud)" },
 ];
 
+const LOW_POWER_MODES: PixelSelectOption[] = [
+  { value: "off", label: "Off" },
+  { value: "auto", label: "On battery" },
+  { value: "on", label: "Always" },
+];
+
+const LOW_POWER_ASR_PROVIDERS: PixelSelectOption[] = [
+  { value: "", label: "Same as ASR" },
+  ...ASR_PROVIDERS,
+];
+
 const RECORD_INPUT_STRATEGIES: PixelSelectOption[] = [
   { value: "follow_default", label: "follow system default" },
   { value: "fixed_device", label: "fixed specific device" },
@@ -47,6 +87,23 @@ const RECORD_DEFAULT_ROLES: PixelSelectOption[] = [
   { value: "console", label: "console (eConsole)" },
 ];
 
+const RECORD_BACKENDS: PixelSelectOption[] = [
+  { value: "native", label: "Native recorder" },
+  { value: "ffmpeg", label: "ffmpeg (dshow)" },
+];
+
+const RECORDING_FORMATS: PixelSelectOption[] = [
+  { value: "wav", label: "WAV (original)" },
+  { value: "flac", label: "FLAC (lossless)" },
+  { value: "opus", label: "Opus (smallest)" },
+];
+
+const TIME_FORMATS: PixelSelectOption[] = [
+  { value: "24h", label: "2026-03-29 14:05" },
+  { value: "12h", label: "2026-03-29 2:05 PM" },
+  { value: "iso", label: "ISO 8601 with offset" },
+];
+
 const PRIMARY_HOTKEYS: PixelSelectOption[] = [
   { value: "Alt", label: "Alt" },
   { value: "Ctrl", label: "Ctrl" },
@@ -71,11 +128,21 @@ type SettingsPanelId =
   | "preprocess"
   | "llm"
   | "llmKey"
+  | "proxy"
   | "rewrite"
   | "context"
   | "glossary"
+  | "postprocessors"
   | "export"
   | "hotkeys"
+  | "audit"
+  | "keepRecordings"
+  | "markdownNotes"
+  | "webhook"
+  | "mqtt"
+  | "dailySummary"
+  | "workspace"
+  | "permissions"
   | "history";
 
 type EffectiveSettingsValues = {
@@ -174,6 +241,7 @@ function SettingsLine({
 function sensitiveSettingDisplay(status: ApiKeyStatus | null): string {
   if (!status?.configured) return "";
   const source = status.source.trim();
+  if (source === "encrypted_file") return "Configured via encrypted file";
   return source ? `Configured via ${source}` : "Configured";
 }
 
@@ -192,49 +260,160 @@ export function SettingsScreen({
   const [remoteAsrUrl, setRemoteAsrUrl] = useState("https://api.server/transcribe");
   const [remoteAsrModel, setRemoteAsrModel] = useState("");
   const [remoteAsrConcurrency, setRemoteAsrConcurrency] = useState("4");
+  const [remoteLivePreview, setRemoteLivePreview] = useState(false);
+  const [warmPoolEnabled, setWarmPoolEnabled] = useState(false);
+  const [warmPoolModels, setWarmPoolModels] = useState("");
+  const [warmPoolMaxModels, setWarmPoolMaxModels] = useState("2");
+  const [warmPoolVramBudgetMb, setWarmPoolVramBudgetMb] = useState("0");
+  const [warmPoolModelVram, setWarmPoolModelVram] = useState("");
+  const [warmPoolKeepaliveSecs, setWarmPoolKeepaliveSecs] = useState("240");
+  const [warmPoolStatus, setWarmPoolStatus] = useState<WarmPoolStatus | null>(null);
   const [remoteAsrKeyDraft, setRemoteAsrKeyDraft] = useState("");
   const [doubaoAppKeyDraft, setDoubaoAppKeyDraft] = useState("");
   const [doubaoAccessKeyDraft, setDoubaoAccessKeyDraft] = useState("");
+  const [proxyUrl, setProxyUrl] = useState("");
+  const [proxyNoProxy, setProxyNoProxy] = useState("");
+  const [tlsCaBundlePath, setTlsCaBundlePath] = useState("");
+  const [tlsPinnedSha256, setTlsPinnedSha256] = useState("");
+  const [httpConnectTimeoutMs, setHttpConnectTimeoutMs] = useState("10000");
+  const [httpRequestTimeoutMs, setHttpRequestTimeoutMs] = useState("180000");
+  const [offlineMode, setOfflineMode] = useState(false);
+  const [proxyUsernameDraft, setProxyUsernameDraft] = useState("");
+  const [proxyPasswordDraft, setProxyPasswordDraft] = useState("");
   const [asrPreprocessTrimEnabled, setAsrPreprocessTrimEnabled] = useState(false);
+  const [asrPreprocessWarmSpare, setAsrPreprocessWarmSpare] = useState(true);
+  const [asrPreprocessNativeWav, setAsrPreprocessNativeWav] = useState(false);
   const [asrPreprocessThresholdDb, setAsrPreprocessThresholdDb] = useState("-50");
   const [asrPreprocessStartMs, setAsrPreprocessStartMs] = useState("300");
   const [asrPreprocessEndMs, setAsrPreprocessEndMs] = useState("300");
+  const [asrPreprocessGainDb, setAsrPreprocessGainDb] = useState("0");
+  const [noiseProfile, setNoiseProfile] = useState<NoiseProfile | null>(null);
+  const [noiseCalibrationPending, setNoiseCalibrationPending] = useState(false);
+  const [micTestResult, setMicTestResult] = useState<MicTestResult | null>(null);
+  const audioLevel = useAudioLevel();
+  const [micTestPending, setMicTestPending] = useState(false);
+  const [confirmBugReport, setConfirmBugReport] = useState(false);
+  const [bugReportNote, setBugReportNote] = useState("");
+  const [bugReportPending, setBugReportPending] = useState(false);
+  const [bugReportResult, setBugReportResult] = useState<BugReportResult | null>(null);
   const [llmBaseUrl, setLlmBaseUrl] = useState("");
   const [llmModel, setLlmModel] = useState("");
   const [reasoning, setReasoning] = useState("default");
+  const [llmCaptureEnabled, setLlmCaptureEnabled] = useState(false);
+  const [llmCaptureMaxEntries, setLlmCaptureMaxEntries] = useState(20);
+  const [gpuCoordinationEnabled, setGpuCoordinationEnabled] = useState(false);
+  const [gpuAsrUnloadUrl, setGpuAsrUnloadUrl] = useState("");
+  const [gpuLlmUnloadUrl, setGpuLlmUnloadUrl] = useState("");
   const [llmPrompt, setLlmPrompt] = useState("");
+  const [rewritePreamble, setRewritePreamble] = useState("");
+  const [rewriteOutputFormat, setRewriteOutputFormat] = useState("text");
+  const [rewriteOutputSchema, setRewriteOutputSchema] = useState("");
+  const [rewritePreview, setRewritePreview] = useState<RewritePreview | null>(null);
   const [rewriteEnabled, setRewriteEnabled] = useState(false);
+  const [maxBackgroundRewrites, setMaxBackgroundRewrites] = useState("0");
+  const [chunkThresholdTokens, setChunkThresholdTokens] = useState("6000");
+  const [chunkTokens, setChunkTokens] = useState("2000");
+  const [rewriteTokenCap, setRewriteTokenCap] = useState("0");
+  const [rewriteCacheEnabled, setRewriteCacheEnabled] = useState(true);
   const [rewriteGlossaryDraft, setRewriteGlossaryDraft] = useState("");
+  const [postprocessorsDraft, setPostprocessorsDraft] = useState("[]");
   const [autoPasteEnabled, setAutoPasteEnabled] = useState(true);
+  const [elevatedHelperEnabled, setElevatedHelperEnabled] = useState(false);
+  const [clipboardAutoClearSecs, setClipboardAutoClearSecs] = useState(0);
+  const [auditEnabled, setAuditEnabled] = useState(false);
+  const [auditIncludeText, setAuditIncludeText] = useState(false);
+  const [auditRetentionDays, setAuditRetentionDays] = useState("90");
+  const [auditMaxEntries, setAuditMaxEntries] = useState("10000");
+  const [auditEntries, setAuditEntries] = useState<AuditEntry[]>([]);
+  const [keepRecordings, setKeepRecordings] = useState(false);
+  const [recordingFormat, setRecordingFormat] = useState("wav");
+  const [timeFormat, setTimeFormat] = useState("24h");
+  const [markdownNotesEnabled, setMarkdownNotesEnabled] = useState(false);
+  const [markdownNotesFolder, setMarkdownNotesFolder] = useState("");
+  const [markdownNotesEntryTemplate, setMarkdownNotesEntryTemplate] = useState("");
+  const [webhookEnabled, setWebhookEnabled] = useState(false);
+  const [webhookUrl, setWebhookUrl] = useState("");
+  const [webhookFields, setWebhookFields] = useState("");
+  const [webhookSecretDraft, setWebhookSecretDraft] = useState("");
+  const [webhookSecretStatus, setWebhookSecretStatus] = useState<ApiKeyStatus | null>(null);
+  const [mqttEnabled, setMqttEnabled] = useState(false);
+  const [mqttBrokerHost, setMqttBrokerHost] = useState("");
+  const [mqttBrokerPort, setMqttBrokerPort] = useState("");
+  const [mqttTls, setMqttTls] = useState(false);
+  const [mqttTopicPrefix, setMqttTopicPrefix] = useState("");
+  const [mqttUsername, setMqttUsername] = useState("");
+  const [dailySummaryEnabled, setDailySummaryEnabled] = useState(false);
+  const [dailySummaryTime, setDailySummaryTime] = useState("");
+  const [dailySummaryPrompt, setDailySummaryPrompt] = useState("");
+  const [dailySummaryPending, setDailySummaryPending] = useState(false);
+  const [workspaces, setWorkspaces] = useState<WorkspaceInfo[]>([]);
+  const [workspaceDraft, setWorkspaceDraft] = useState("");
+  const [permissions, setPermissions] = useState<PermissionStatus[]>([]);
+  const [hotkeyBackend, setHotkeyBackend] = useState<HotkeyBackendStatus | null>(null);
+  const [mqttPasswordDraft, setMqttPasswordDraft] = useState("");
+  const [mqttPasswordStatus, setMqttPasswordStatus] = useState<ApiKeyStatus | null>(null);
+  const [fieldErrors, setFieldErrors] = useState<SettingsFieldError[]>([]);
   const [recordInputStrategy, setRecordInputStrategy] = useState("follow_default");
   const [recordFollowDefaultRole, setRecordFollowDefaultRole] = useState("communications");
   const [recordFixedEndpointId, setRecordFixedEndpointId] = useState("");
   const [recordFixedFriendlyName, setRecordFixedFriendlyName] = useState("");
   const [audioCaptureDevices, setAudioCaptureDevices] = useState<AudioCaptureDevice[]>([]);
+  const [recordDevicePreference, setRecordDevicePreference] = useState<string[]>([]);
+  const [recordAvoidLowQualityProfile, setRecordAvoidLowQualityProfile] = useState(false);
+  const [recordBackend, setRecordBackend] = useState("native");
+  const [recordVadEnabled, setRecordVadEnabled] = useState(false);
+  const [recordVadSilenceMs, setRecordVadSilenceMs] = useState("1500");
+  const [recordDeviceOverrides, setRecordDeviceOverrides] = useState<
+    Record<string, RecordDeviceOverride>
+  >({});
+  const [preferenceDragIndex, setPreferenceDragIndex] = useState<number | null>(null);
+  const [overrideEndpointId, setOverrideEndpointId] = useState("");
+  const [overrideGainDb, setOverrideGainDb] = useState("0");
+  const [overrideExtraArgs, setOverrideExtraArgs] = useState("");
 
   const [hotkeysEnabled, setHotkeysEnabled] = useState(true);
   const [hotkeyPrimary, setHotkeyPrimary] = useState("Alt");
+  const [hotkeyDebounceMs, setHotkeyDebounceMs] = useState(250);
+  const [hotkeyAppAllowlistDraft, setHotkeyAppAllowlistDraft] = useState("");
   const [hotkeysShowOverlay, setHotkeysShowOverlay] = useState(true);
+  const [a11yAnnouncements, setA11yAnnouncements] = useState(false);
+  const [captureIndicatorEnforced, setCaptureIndicatorEnforced] = useState(false);
+  const [screenShareGuard, setScreenShareGuard] = useState(true);
+  const [lowPowerMode, setLowPowerMode] = useState("off");
+  const [lowPowerAsrProvider, setLowPowerAsrProvider] = useState("");
+  const [lowPowerRemoteModel, setLowPowerRemoteModel] = useState("");
+  const [powerStatus, setPowerStatus] = useState<PowerStatus | null>(null);
+  const [screenShareProcessesDraft, setScreenShareProcessesDraft] = useState("");
   const [overlayBackgroundOpacity, setOverlayBackgroundOpacity] = useState(0.78);
   const [overlayFontSizePx, setOverlayFontSizePx] = useState(32);
   const [overlayWidthPx, setOverlayWidthPx] = useState(960);
   const [overlayHeightPx, setOverlayHeightPx] = useState(160);
+  const [overlayFontScale, setOverlayFontScale] = useState(1);
+  const [overlayStatusLanguage, setOverlayStatusLanguage] = useState("en");
   const [contextIncludeHistory, setContextIncludeHistory] = useState(true);
+  const [contextHistorySameApp, setContextHistorySameApp] = useState(false);
+  const [contextHistorySummarize, setContextHistorySummarize] = useState(false);
   const [contextIncludeClipboard, setContextIncludeClipboard] = useState(true);
   const [contextIncludePrevWindowMeta, setContextIncludePrevWindowMeta] = useState(true);
   const [contextIncludePrevWindowScreenshot, setContextIncludePrevWindowScreenshot] =
     useState(true);
+  const [contextInjectionGuardEnabled, setContextInjectionGuardEnabled] = useState(true);
   const [rewriteIncludeGlossary, setRewriteIncludeGlossary] = useState(true);
 
   const [keyDraft, setKeyDraft] = useState("");
   const [llmKeyStatus, setLlmKeyStatus] = useState<ApiKeyStatus | null>(null);
   const [remoteAsrKeyStatus, setRemoteAsrKeyStatus] = useState<ApiKeyStatus | null>(null);
   const [doubaoCredentialsStatus, setDoubaoCredentialsStatus] = useState<ApiKeyStatus | null>(null);
+  const [proxyCredentialsStatus, setProxyCredentialsStatus] = useState<ApiKeyStatus | null>(null);
 
   const [confirmClear, setConfirmClear] = useState(false);
+  const [backupPath, setBackupPath] = useState("");
+  const [backupPending, setBackupPending] = useState(false);
+  const [confirmRestore, setConfirmRestore] = useState(false);
   const [llmCheckPending, setLlmCheckPending] = useState(false);
   const [remoteAsrCheckPending, setRemoteAsrCheckPending] = useState(false);
   const [doubaoCheckPending, setDoubaoCheckPending] = useState(false);
+  const [proxyCheckPending, setProxyCheckPending] = useState(false);
   const [expandedSettingsPanels, setExpandedSettingsPanels] = useState<SettingsPanelId[]>([]);
 
   useEffect(() => {
@@ -251,7 +430,20 @@ export function SettingsScreen({
       const normalized = Number.isFinite(raw) ? Math.max(1, Math.min(16, Math.round(raw))) : 4;
       setRemoteAsrConcurrency(String(normalized));
     }
+    setRemoteLivePreview(settings.remote_asr_live_preview ?? false);
+    setWarmPoolEnabled(settings.asr_warm_pool_enabled ?? false);
+    setWarmPoolModels((settings.asr_warm_pool_models ?? []).join(", "));
+    setWarmPoolMaxModels(String(settings.asr_warm_pool_max_models ?? 2));
+    setWarmPoolVramBudgetMb(String(settings.asr_warm_pool_vram_budget_mb ?? 0));
+    setWarmPoolModelVram(
+      Object.entries(settings.asr_warm_pool_model_vram_mb ?? {})
+        .map(([model, mb]) => `${model}=${mb}`)
+        .join(", "),
+    );
+    setWarmPoolKeepaliveSecs(String(settings.asr_warm_pool_keepalive_secs ?? 240));
     setAsrPreprocessTrimEnabled(settings.asr_preprocess_silence_trim_enabled ?? false);
+    setAsrPreprocessWarmSpare(settings.asr_preprocess_warm_spare_enabled ?? true);
+    setAsrPreprocessNativeWav(settings.asr_preprocess_native_wav_enabled ?? false);
     setAsrPreprocessThresholdDb(
       String(
         settings.asr_preprocess_silence_threshold_db ??
@@ -265,19 +457,69 @@ export function SettingsScreen({
       ),
     );
     setAsrPreprocessEndMs(String(settings.asr_preprocess_silence_end_ms ?? 300));
+    setAsrPreprocessGainDb(String(settings.asr_preprocess_gain_db ?? 0));
+    setProxyUrl(settings.proxy_url ?? "");
+    setProxyNoProxy((settings.proxy_no_proxy || []).join(", "));
+    setTlsCaBundlePath(settings.tls_ca_bundle_path ?? "");
+    setTlsPinnedSha256(settings.tls_pinned_sha256 ?? "");
+    setHttpConnectTimeoutMs(String(settings.http_connect_timeout_ms ?? 10000));
+    setHttpRequestTimeoutMs(String(settings.http_request_timeout_ms ?? 180000));
+    setOfflineMode(settings.offline_mode ?? false);
     setLlmBaseUrl(settings.llm_base_url ?? "");
     setLlmModel(settings.llm_model ?? "");
     setReasoning(settings.llm_reasoning_effort ?? "default");
+    setLlmCaptureEnabled(settings.llm_capture_enabled === true);
+    setLlmCaptureMaxEntries(clampNumber(settings.llm_capture_max_entries, 20, 1, 200));
+    setGpuCoordinationEnabled(settings.gpu_coordination_enabled === true);
+    setGpuAsrUnloadUrl(settings.gpu_asr_unload_url ?? "");
+    setGpuLlmUnloadUrl(settings.gpu_llm_unload_url ?? "");
     setLlmPrompt(settings.llm_prompt ?? "");
+    setRewritePreamble(settings.rewrite_system_preamble ?? "");
+    setRewriteOutputFormat(settings.rewrite_output_format ?? "text");
+    setRewriteOutputSchema(settings.rewrite_output_json_schema ?? "");
 
     if (typeof settings.rewrite_enabled !== "boolean") {
       pushToast("Settings need attention", "danger");
       return;
     }
     setRewriteEnabled(settings.rewrite_enabled);
+    {
+      const raw = Number(settings.pipeline_max_background_rewrites ?? 0);
+      const normalized = Number.isFinite(raw) ? Math.max(0, Math.min(4, Math.round(raw))) : 0;
+      setMaxBackgroundRewrites(String(normalized));
+    }
+    setChunkThresholdTokens(String(settings.rewrite_chunk_threshold_tokens ?? 6000));
+    setChunkTokens(String(settings.rewrite_chunk_tokens ?? 2000));
+    setRewriteTokenCap(String(settings.rewrite_token_cap ?? 0));
+    setRewriteCacheEnabled(settings.rewrite_cache_enabled ?? true);
     setRewriteGlossaryDraft((settings.rewrite_glossary || []).join("\n"));
     setRewriteIncludeGlossary(settings.rewrite_include_glossary ?? true);
+    setPostprocessorsDraft(JSON.stringify(settings.postprocessors || [], null, 2));
     setAutoPasteEnabled(settings.auto_paste_enabled ?? true);
+    setClipboardAutoClearSecs(clampNumber(settings.clipboard_auto_clear_secs, 0, 0, 600));
+    setElevatedHelperEnabled(settings.export_elevated_helper_enabled ?? false);
+    setAuditEnabled(settings.audit_log_enabled ?? false);
+    setAuditIncludeText(settings.audit_log_include_text ?? false);
+    setKeepRecordings(settings.keep_recordings ?? false);
+    setRecordingFormat(settings.recording_format ?? "wav");
+    setTimeFormat(settings.time_format ?? "24h");
+    setAuditRetentionDays(String(settings.audit_log_retention_days ?? 90));
+    setAuditMaxEntries(String(settings.audit_log_max_entries ?? 10000));
+    setMarkdownNotesEnabled(settings.markdown_notes_enabled ?? false);
+    setMarkdownNotesFolder(settings.markdown_notes_folder ?? "");
+    setMarkdownNotesEntryTemplate(settings.markdown_notes_entry_template ?? "");
+    setWebhookEnabled(settings.webhook_enabled ?? false);
+    setWebhookUrl(settings.webhook_url ?? "");
+    setWebhookFields((settings.webhook_fields || []).join(", "));
+    setMqttEnabled(settings.mqtt_enabled ?? false);
+    setMqttBrokerHost(settings.mqtt_broker_host ?? "");
+    setMqttBrokerPort(settings.mqtt_broker_port ? String(settings.mqtt_broker_port) : "");
+    setMqttTls(settings.mqtt_tls ?? false);
+    setMqttTopicPrefix(settings.mqtt_topic_prefix ?? "");
+    setMqttUsername(settings.mqtt_username ?? "");
+    setDailySummaryEnabled(settings.daily_summary_enabled ?? false);
+    setDailySummaryTime(settings.daily_summary_time ?? "");
+    setDailySummaryPrompt(settings.daily_summary_prompt ?? "");
     setRecordInputStrategy(
       settings.record_input_strategy === "fixed_device"
         ? "fixed_device"
@@ -290,6 +532,12 @@ export function SettingsScreen({
     );
     setRecordFixedEndpointId(settings.record_fixed_endpoint_id ?? "");
     setRecordFixedFriendlyName(settings.record_fixed_friendly_name ?? "");
+    setRecordDevicePreference(settings.record_device_preference ?? []);
+    setRecordAvoidLowQualityProfile(settings.record_avoid_low_quality_profile === true);
+    setRecordBackend(settings.record_backend ?? "native");
+    setRecordVadEnabled(settings.record_vad_enabled === true);
+    setRecordVadSilenceMs(String(settings.record_vad_silence_ms ?? 1500));
+    setRecordDeviceOverrides(settings.record_device_overrides ?? {});
 
     if (typeof settings.hotkeys_enabled !== "boolean") {
       pushToast("Settings need attention", "danger");
@@ -301,17 +549,31 @@ export function SettingsScreen({
     }
     setHotkeysEnabled(settings.hotkeys_enabled);
     setHotkeyPrimary(normalizePrimaryHotkey(settings.hotkey_primary));
+    setHotkeyDebounceMs(clampNumber(settings.hotkey_debounce_ms, 250, 0, 2000));
+    setHotkeyAppAllowlistDraft((settings.hotkey_app_allowlist || []).join("\n"));
     setHotkeysShowOverlay(settings.hotkeys_show_overlay);
+    setA11yAnnouncements(settings.a11y_announcements === true);
+    setCaptureIndicatorEnforced(settings.capture_indicator_enforced === true);
+    setScreenShareGuard(settings.screen_share_guard_enabled ?? true);
+    setScreenShareProcessesDraft((settings.screen_share_extra_processes || []).join("\n"));
+    setLowPowerMode(settings.low_power_mode ?? "off");
+    setLowPowerAsrProvider(settings.low_power_asr_provider ?? "");
+    setLowPowerRemoteModel(settings.low_power_remote_asr_model ?? "");
     setOverlayBackgroundOpacity(
       clampNumber(settings.overlay_background_opacity, 0.78, 0.35, 0.95),
     );
     setOverlayFontSizePx(clampNumber(settings.overlay_font_size_px, 32, 18, 56));
     setOverlayWidthPx(clampNumber(settings.overlay_width_px, 960, 360, 1600));
     setOverlayHeightPx(clampNumber(settings.overlay_height_px, 160, 72, 360));
+    setOverlayFontScale(clampNumber(settings.overlay_font_scale, 1, 0.5, 2));
+    setOverlayStatusLanguage(settings.overlay_status_language === "zh" ? "zh" : "en");
 
     setContextIncludeHistory(settings.context_include_history ?? true);
+    setContextHistorySameApp(settings.context_history_same_app ?? false);
+    setContextHistorySummarize(settings.context_history_summarize ?? false);
     setContextIncludeClipboard(settings.context_include_clipboard ?? true);
     setContextIncludePrevWindowMeta(settings.context_include_prev_window_meta ?? true);
+    setContextInjectionGuardEnabled(settings.context_injection_guard_enabled ?? true);
     setContextIncludePrevWindowScreenshot(
       settings.context_include_prev_window_screenshot ?? true,
     );
@@ -337,6 +599,40 @@ export function SettingsScreen({
     // eslint-disable-next-line react-hooks/exhaustive-deps
   }, [settings]);
 
+  useEffect(() => {
+    void refreshWorkspaces();
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, [settings?.active_workspace]);
+
+  useEffect(() => {
+    void refreshPermissions();
+  }, []);
+
+  useEffect(() => {
+    (async () => {
+      try {
+        setWarmPoolStatus((await defaultTauriGateway.invoke("asr_warm_pool_status")) as WarmPoolStatus);
+      } catch {
+        setWarmPoolStatus(null);
+      }
+      try {
+        setPowerStatus((await defaultTauriGateway.invoke("power_status")) as PowerStatus);
+      } catch {
+        setPowerStatus(null);
+      }
+    })();
+  }, [settings]);
+
+  useEffect(() => {
+    (async () => {
+      try {
+        setHotkeyBackend((await defaultTauriGateway.invoke("hotkey_backend_status")) as HotkeyBackendStatus);
+      } catch {
+        setHotkeyBackend(null);
+      }
+    })();
+  }, []);
+
   useEffect(() => {
     (async () => {
       await refreshAudioCaptureDevices();
@@ -344,6 +640,29 @@ export function SettingsScreen({
     // eslint-disable-next-line react-hooks/exhaustive-deps
   }, []);
 
+  useEffect(() => {
+    let cancelled = false;
+    let unlisten: (() => void) | null = null;
+    (async () => {
+      const fn = await defaultTauriGateway.listen<AudioDevicesChanged>(
+        "tv_audio_devices_changed",
+        async () => {
+          await refreshAudioCaptureDevices();
+        },
+      );
+      if (cancelled) {
+        fn();
+        return;
+      }
+      unlisten = fn;
+    })().catch(() => {});
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, []);
+
   const captureDeviceOptions: PixelSelectOption[] = useMemo(() => {
     return audioCaptureDevices.map((v) => {
       let label = v.friendly_name;
@@ -357,6 +676,27 @@ export function SettingsScreen({
     });
   }, [audioCaptureDevices]);
 
+  // Preferred endpoints first (in the saved order, even if unplugged), then the
+  // remaining active endpoints so they can be dragged into the list.
+  const preferenceRows = useMemo(() => {
+    const known = new Map(audioCaptureDevices.map((v) => [v.endpoint_id, v.friendly_name]));
+    const preferred = recordDevicePreference.map((id) => ({
+      endpoint_id: id,
+      friendly_name: known.get(id) ?? `${id} (not connected)`,
+      preferred: true,
+    }));
+    const rest = audioCaptureDevices
+      .filter((v) => !recordDevicePreference.includes(v.endpoint_id))
+      .map((v) => ({ endpoint_id: v.endpoint_id, friendly_name: v.friendly_name, preferred: false }));
+    return [...preferred, ...rest];
+  }, [audioCaptureDevices, recordDevicePreference]);
+
+  useEffect(() => {
+    const saved = recordDeviceOverrides[overrideEndpointId];
+    setOverrideGainDb(String(saved?.gain_db ?? 0));
+    setOverrideExtraArgs((saved?.extra_ffmpeg_args ?? []).join(" "));
+  }, [overrideEndpointId, recordDeviceOverrides]);
+
   useEffect(() => {
     const found = audioCaptureDevices.find((v) => v.endpoint_id === recordFixedEndpointId);
     if (!found) return;
@@ -380,14 +720,20 @@ export function SettingsScreen({
 
   async function refreshSensitiveSettingStatuses() {
     try {
-      const [llmStatus, remoteStatus, doubaoStatus] = await Promise.all([
+      const [llmStatus, remoteStatus, doubaoStatus, proxyStatus, webhookStatus, mqttStatus] = await Promise.all([
         defaultTauriGateway.invoke("llm_api_key_status") as Promise<ApiKeyStatus>,
         defaultTauriGateway.invoke("remote_asr_api_key_status") as Promise<ApiKeyStatus>,
         defaultTauriGateway.invoke("doubao_asr_credentials_status") as Promise<ApiKeyStatus>,
+        defaultTauriGateway.invoke("proxy_credentials_status") as Promise<ApiKeyStatus>,
+        defaultTauriGateway.invoke("webhook_secret_status") as Promise<ApiKeyStatus>,
+        defaultTauriGateway.invoke("mqtt_password_status") as Promise<ApiKeyStatus>,
       ]);
       setLlmKeyStatus(llmStatus);
       setRemoteAsrKeyStatus(remoteStatus);
       setDoubaoCredentialsStatus(doubaoStatus);
+      setProxyCredentialsStatus(proxyStatus);
+      setWebhookSecretStatus(webhookStatus);
+      setMqttPasswordStatus(mqttStatus);
     } catch {
     }
   }
@@ -410,6 +756,15 @@ export function SettingsScreen({
     patch: Record<string, unknown>,
     successMessage = "SAVED",
   ): Promise<boolean> {
+    // A failed dry run falls through to the save, which reports its own error.
+    const errors = (await defaultTauriGateway
+      .invoke("validate_settings", { patch })
+      .catch(() => [])) as SettingsFieldError[];
+    setFieldErrors(errors);
+    if (errors.length > 0) {
+      pushToast(`INVALID ${errors[0].field.toUpperCase()}`, "danger");
+      return false;
+    }
     return runToastAction(
       async () => {
         await savePatch(patch);
@@ -430,11 +785,34 @@ export function SettingsScreen({
       return;
     }
     const normalizedConcurrency = Math.max(1, Math.min(16, Math.round(concurrencyNum)));
+    const poolModels = warmPoolModels
+      .split(",")
+      .map((v) => v.trim())
+      .filter((v) => v.length > 0);
+    const modelVram: Record<string, number> = {};
+    for (const entry of warmPoolModelVram.split(",")) {
+      const [model, mb] = entry.split("=").map((v) => v.trim());
+      if (!model) {
+        continue;
+      }
+      if (!Number.isFinite(Number(mb)) || Number(mb) < 0) {
+        pushToast(`INVALID VRAM ESTIMATE FOR ${model.toUpperCase()}`, "danger");
+        return;
+      }
+      modelVram[model] = Math.round(Number(mb));
+    }
     const saved = await persistSettingsPatch({
       asr_provider: provider,
       remote_asr_url: remoteAsrUrl.trim() ? remoteAsrUrl.trim() : null,
       remote_asr_model: remoteAsrModel.trim() ? remoteAsrModel.trim() : null,
       remote_asr_concurrency: normalizedConcurrency,
+      remote_asr_live_preview: remoteLivePreview,
+      asr_warm_pool_enabled: warmPoolEnabled,
+      asr_warm_pool_models: poolModels.length > 0 ? poolModels : null,
+      asr_warm_pool_max_models: Math.round(Number(warmPoolMaxModels) || 2),
+      asr_warm_pool_vram_budget_mb: Math.max(0, Math.round(Number(warmPoolVramBudgetMb) || 0)),
+      asr_warm_pool_model_vram_mb: Object.keys(modelVram).length > 0 ? modelVram : null,
+      asr_warm_pool_keepalive_secs: Math.round(Number(warmPoolKeepaliveSecs) || 240),
     });
     if (saved) {
       setRemoteAsrConcurrency(String(normalizedConcurrency));
@@ -465,6 +843,10 @@ export function SettingsScreen({
         strategy === "fixed_device"
           ? (selected?.friendly_name || recordFixedFriendlyName || "").trim() || null
           : null,
+      record_avoid_low_quality_profile: recordAvoidLowQualityProfile,
+      record_backend: recordBackend === "ffmpeg" ? "ffmpeg" : "native",
+      record_vad_enabled: recordVadEnabled,
+      record_vad_silence_ms: Math.round(Number(recordVadSilenceMs) || 1500),
     });
     if (saved) {
       if (selected) {
@@ -474,11 +856,66 @@ export function SettingsScreen({
     }
   }
 
+  function movePreference(endpointId: string, targetIndex: number) {
+    setRecordDevicePreference((cur) => {
+      const next = cur.filter((v) => v !== endpointId);
+      next.splice(Math.max(0, Math.min(targetIndex, next.length)), 0, endpointId);
+      return next;
+    });
+  }
+
+  function removePreference(endpointId: string) {
+    setRecordDevicePreference((cur) => cur.filter((v) => v !== endpointId));
+  }
+
+  async function saveDevicePreference() {
+    await persistSettingsPatch(
+      { record_device_preference: recordDevicePreference.length ? recordDevicePreference : null },
+      "DEVICE ORDER SAVED",
+    );
+  }
+
+  async function saveDeviceOverride() {
+    const endpointId = overrideEndpointId.trim();
+    if (!endpointId) {
+      pushToast("SELECT A DEVICE FIRST", "danger");
+      return;
+    }
+    const gainDb = Number(overrideGainDb);
+    if (!Number.isFinite(gainDb)) {
+      pushToast("DEVICE GAIN MUST BE A NUMBER", "danger");
+      return;
+    }
+    const extraArgs = overrideExtraArgs.split(/\s+/).filter((v) => v.length > 0);
+    const next = { ...recordDeviceOverrides };
+    if (gainDb === 0 && extraArgs.length === 0) {
+      delete next[endpointId];
+    } else {
+      next[endpointId] = {
+        gain_db: gainDb === 0 ? null : Math.max(-20, Math.min(20, gainDb)),
+        extra_ffmpeg_args: extraArgs.length ? extraArgs : null,
+      };
+    }
+    const saved = await persistSettingsPatch(
+      { record_device_overrides: Object.keys(next).length ? next : null },
+      "DEVICE SETTINGS SAVED",
+    );
+    if (saved) {
+      setRecordDeviceOverrides(next);
+    }
+  }
+
   async function savePreprocessConfig() {
     const thresholdDb = Number(asrPreprocessThresholdDb);
     const trimStartMs = Number(asrPreprocessStartMs);
     const trimEndMs = Number(asrPreprocessEndMs);
-    if (!Number.isFinite(thresholdDb) || !Number.isFinite(trimStartMs) || !Number.isFinite(trimEndMs)) {
+    const gainDb = Number(asrPreprocessGainDb);
+    if (
+      !Number.isFinite(thresholdDb) ||
+      !Number.isFinite(trimStartMs) ||
+      !Number.isFinite(trimEndMs) ||
+      !Number.isFinite(gainDb)
+    ) {
       pushToast("INVALID PREPROCESS INPUT", "danger");
       return;
     }
@@ -499,14 +936,93 @@ export function SettingsScreen({
       asr_preprocess_silence_end_ms: Number.isInteger(trimEndMs)
         ? trimEndMs
         : Math.round(trimEndMs),
+      asr_preprocess_gain_db: Math.max(-20, Math.min(20, gainDb)),
+      asr_preprocess_warm_spare_enabled: asrPreprocessWarmSpare,
+      asr_preprocess_native_wav_enabled: asrPreprocessNativeWav,
     });
   }
 
+  async function calibrateNoiseProfile() {
+    if (noiseCalibrationPending) return;
+    setNoiseCalibrationPending(true);
+    setNoiseProfile(null);
+    try {
+      const profile = (await defaultTauriGateway.invoke(
+        "calibrate_noise_profile",
+      )) as NoiseProfile;
+      setNoiseProfile(profile);
+    } catch {
+      pushToast("NOISE CALIBRATION FAILED", "danger");
+    } finally {
+      setNoiseCalibrationPending(false);
+    }
+  }
+
+  async function runMicTest() {
+    if (micTestPending) return;
+    setMicTestPending(true);
+    setMicTestResult(null);
+    try {
+      const result = (await defaultTauriGateway.invoke("mic_test")) as MicTestResult;
+      setMicTestResult(result);
+      const ready = result.level === "ok" && !!result.transcript;
+      pushToast(ready ? "MIC READY" : "MIC NEEDS ATTENTION", ready ? "ok" : "danger");
+    } catch {
+      pushToast("MIC TEST FAILED", "danger");
+    } finally {
+      setMicTestPending(false);
+    }
+  }
+
+  // Only called from the consent dialog; the backend refuses without consent.
+  async function recordBugReport() {
+    if (bugReportPending) return;
+    setConfirmBugReport(false);
+    setBugReportPending(true);
+    setBugReportResult(null);
+    try {
+      const result = (await defaultTauriGateway.invoke("record_bug_report_sample", {
+        consent: true,
+        note: bugReportNote.trim() || null,
+      })) as BugReportResult;
+      setBugReportResult(result);
+      setBugReportNote("");
+      pushToast("BUG REPORT SAVED", "ok");
+    } catch {
+      pushToast("BUG REPORT FAILED", "danger");
+    } finally {
+      setBugReportPending(false);
+    }
+  }
+
+  async function applyNoiseProfile() {
+    if (!noiseProfile) return;
+    const saved = await persistSettingsPatch(
+      {
+        asr_preprocess_silence_trim_enabled: true,
+        asr_preprocess_silence_threshold_db: noiseProfile.suggested_silence_threshold_db,
+        asr_preprocess_gain_db: noiseProfile.suggested_gain_db,
+      },
+      "CALIBRATION APPLIED",
+    );
+    if (saved) {
+      setAsrPreprocessTrimEnabled(true);
+      setAsrPreprocessThresholdDb(String(noiseProfile.suggested_silence_threshold_db));
+      setAsrPreprocessGainDb(String(noiseProfile.suggested_gain_db));
+      setNoiseProfile(null);
+    }
+  }
+
   async function saveLlm() {
     await persistSettingsPatch({
       llm_base_url: llmBaseUrl.trim() ? llmBaseUrl.trim() : null,
       llm_model: llmModel.trim() ? llmModel.trim() : null,
       llm_reasoning_effort: reasoning === "default" ? null : reasoning,
+      llm_capture_enabled: llmCaptureEnabled,
+      llm_capture_max_entries: Math.round(llmCaptureMaxEntries),
+      gpu_coordination_enabled: gpuCoordinationEnabled,
+      gpu_asr_unload_url: gpuAsrUnloadUrl.trim() ? gpuAsrUnloadUrl.trim() : null,
+      gpu_llm_unload_url: gpuLlmUnloadUrl.trim() ? gpuLlmUnloadUrl.trim() : null,
     });
   }
 
@@ -515,11 +1031,59 @@ export function SettingsScreen({
       pushToast("LLM PROMPT REQUIRED", "danger");
       return;
     }
-    await persistSettingsPatch({
+    const backgroundNum = Number(maxBackgroundRewrites);
+    if (!Number.isFinite(backgroundNum)) {
+      pushToast("BACKGROUND REWRITES MUST BE A NUMBER", "danger");
+      return;
+    }
+    const normalizedBackground = Math.max(0, Math.min(4, Math.round(backgroundNum)));
+    const thresholdNum = Number(chunkThresholdTokens);
+    const chunkNum = Number(chunkTokens);
+    if (!Number.isFinite(thresholdNum) || !Number.isFinite(chunkNum)) {
+      pushToast("CHUNK SIZES MUST BE NUMBERS", "danger");
+      return;
+    }
+    const tokenCapNum = Number(rewriteTokenCap);
+    if (!Number.isFinite(tokenCapNum)) {
+      pushToast("TOKEN CAP MUST BE A NUMBER", "danger");
+      return;
+    }
+    if (rewriteOutputFormat === "json_schema" && rewriteOutputSchema.trim()) {
+      try {
+        JSON.parse(rewriteOutputSchema);
+      } catch {
+        pushToast("OUTPUT SCHEMA MUST BE JSON", "danger");
+        return;
+      }
+    }
+    const saved = await persistSettingsPatch({
       rewrite_enabled: rewriteEnabled,
       llm_prompt: llmPrompt,
+      rewrite_system_preamble: rewritePreamble.trim() ? rewritePreamble : null,
+      rewrite_output_format: rewriteOutputFormat,
+      rewrite_output_json_schema: rewriteOutputSchema.trim() ? rewriteOutputSchema : null,
       rewrite_include_glossary: rewriteIncludeGlossary,
+      pipeline_max_background_rewrites: normalizedBackground,
+      rewrite_chunk_threshold_tokens: Math.max(0, Math.round(thresholdNum)),
+      rewrite_chunk_tokens: Math.max(0, Math.round(chunkNum)),
+      rewrite_token_cap: Math.max(0, Math.round(tokenCapNum)),
+      rewrite_cache_enabled: rewriteCacheEnabled,
     });
+    if (saved) {
+      setMaxBackgroundRewrites(String(normalizedBackground));
+    }
+  }
+
+  async function previewRewritePrompt() {
+    try {
+      setRewritePreview(
+        (await defaultTauriGateway.invoke("rewrite_preview", {
+          text: "（示例转录）",
+        })) as RewritePreview,
+      );
+    } catch {
+      pushToast("PREVIEW FAILED", "danger");
+    }
   }
 
   async function saveGlossary() {
@@ -530,18 +1094,36 @@ export function SettingsScreen({
     await persistSettingsPatch({ rewrite_glossary: items }, "GLOSSARY SAVED");
   }
 
+  async function savePostprocessors() {
+    let items: PostprocessorConfig[];
+    try {
+      const parsed = JSON.parse(postprocessorsDraft.trim() || "[]");
+      if (!Array.isArray(parsed)) throw new Error("not an array");
+      items = parsed;
+    } catch {
+      pushToast("POST-PROCESSORS MUST BE A JSON ARRAY", "danger");
+      return;
+    }
+    await persistSettingsPatch({ postprocessors: items }, "POST-PROCESSORS SAVED");
+  }
+
   async function saveContextConfig() {
     await persistSettingsPatch({
       context_include_history: contextIncludeHistory,
+      context_history_same_app: contextHistorySameApp,
+      context_history_summarize: contextHistorySummarize,
       context_include_clipboard: contextIncludeClipboard,
       context_include_prev_window_meta: contextIncludePrevWindowMeta,
       context_include_prev_window_screenshot: contextIncludePrevWindowScreenshot,
+      context_injection_guard_enabled: contextInjectionGuardEnabled,
     });
   }
 
   async function saveExportConfig() {
     await persistSettingsPatch({
       auto_paste_enabled: autoPasteEnabled,
+      export_elevated_helper_enabled: elevatedHelperEnabled,
+      clipboard_auto_clear_secs: Math.round(clipboardAutoClearSecs),
     });
   }
 
@@ -549,11 +1131,31 @@ export function SettingsScreen({
     await persistSettingsPatch({
       hotkeys_enabled: hotkeysEnabled,
       hotkey_primary: normalizePrimaryHotkey(hotkeyPrimary),
+      hotkey_debounce_ms: Math.round(hotkeyDebounceMs),
+      hotkey_app_allowlist: hotkeyAppAllowlistDraft
+        .split("\n")
+        .map((x) => x.trim())
+        .filter((x) => x.length > 0),
       hotkeys_show_overlay: hotkeysShowOverlay,
+      a11y_announcements: a11yAnnouncements,
+      capture_indicator_enforced: captureIndicatorEnforced,
+      screen_share_guard_enabled: screenShareGuard,
+      screen_share_extra_processes: screenShareProcessesDraft
+        .split("\n")
+        .map((x) => x.trim())
+        .filter((x) => x.length > 0),
+      low_power_mode: lowPowerMode === "auto" || lowPowerMode === "on" ? lowPowerMode : "off",
+      low_power_asr_provider:
+        lowPowerAsrProvider === "doubao" || lowPowerAsrProvider === "remote"
+          ? lowPowerAsrProvider
+          : null,
+      low_power_remote_asr_model: lowPowerRemoteModel.trim() ? lowPowerRemoteModel.trim() : null,
       overlay_background_opacity: overlayBackgroundOpacity,
       overlay_font_size_px: Math.round(overlayFontSizePx),
       overlay_width_px: Math.round(overlayWidthPx),
       overlay_height_px: Math.round(overlayHeightPx),
+      overlay_font_scale: overlayFontScale,
+      overlay_status_language: overlayStatusLanguage,
     });
   }
 
@@ -677,116 +1279,506 @@ export function SettingsScreen({
     }
   }
 
-  async function clearHistory() {
+  function proxyNoProxyList(): string[] {
+    return proxyNoProxy
+      .split(/[,\n]/)
+      .map((v) => v.trim())
+      .filter(Boolean);
+  }
+
+  async function saveNetwork() {
+    const url = proxyUrl.trim();
+    if (url && !/^(https?|socks5h?):\/\//i.test(url)) {
+      pushToast("PROXY URL MUST START WITH http(s):// OR socks5://", "danger");
+      return;
+    }
+    const pin = tlsPinnedSha256.replace(/[\s:]/g, "");
+    if (pin && !/^[0-9a-fA-F]{64}$/.test(pin)) {
+      pushToast("PINNED SHA-256 MUST BE 64 HEX CHARACTERS", "danger");
+      return;
+    }
+    const connectMs = Number(httpConnectTimeoutMs);
+    const requestMs = Number(httpRequestTimeoutMs);
+    if (!Number.isFinite(connectMs) || !Number.isFinite(requestMs)) {
+      pushToast("HTTP TIMEOUTS MUST BE NUMBERS", "danger");
+      return;
+    }
+    const noProxy = proxyNoProxyList();
+    await persistSettingsPatch({
+      http_connect_timeout_ms: Math.max(1000, Math.round(connectMs)),
+      http_request_timeout_ms: Math.max(0, Math.round(requestMs)),
+      offline_mode: offlineMode,
+      proxy_url: url || null,
+      proxy_no_proxy: noProxy.length ? noProxy : null,
+      tls_ca_bundle_path: tlsCaBundlePath.trim() || null,
+      tls_pinned_sha256: tlsPinnedSha256.trim() || null,
+    });
+  }
+
+  async function setProxyCredentials() {
+    const username = proxyUsernameDraft.trim();
+    if (!username) return;
     try {
-      await defaultTauriGateway.invoke("history_clear");
-      pushToast("HISTORY CLEARED", "ok");
-      onHistoryCleared();
+      await defaultTauriGateway.invoke("set_proxy_credentials", {
+        username,
+        password: proxyPasswordDraft,
+      });
+      setProxyUsernameDraft("");
+      setProxyPasswordDraft("");
+      await refreshSensitiveSettingStatuses();
+      pushToast("PROXY CREDENTIALS SAVED", "ok");
     } catch {
-      pushToast("CLEAR FAILED", "danger");
+      pushToast("PROXY CREDENTIALS SAVE FAILED", "danger");
+    }
+  }
+
+  async function clearProxyCredentials() {
+    try {
+      await defaultTauriGateway.invoke("clear_proxy_credentials");
+      setProxyCredentialsStatus(null);
+      await refreshSensitiveSettingStatuses();
+      pushToast("PROXY CREDENTIALS CLEARED", "ok");
+    } catch {
+      pushToast("PROXY CREDENTIALS CLEAR FAILED", "danger");
+    }
+  }
+
+  async function testProxy() {
+    if (proxyCheckPending) return;
+    setProxyCheckPending(true);
+    try {
+      const result = (await defaultTauriGateway.invoke("test_proxy", {
+        proxyUrl,
+        noProxy: proxyNoProxyList(),
+      })) as ApiCheckResult;
+      pushToast(result.message, result.ok ? "ok" : "danger");
+    } catch {
+      pushToast("Proxy check failed. Try again after checking the settings.", "danger");
     } finally {
-      setConfirmClear(false);
+      setProxyCheckPending(false);
     }
   }
 
-  const asrStatusText = useMemo(() => {
-    if (asrProvider === "doubao") {
-      return "Doubao streaming  wss://openspeech.bytedance.com/api/v3/sauc/bigmodel_async";
+  async function saveAuditConfig() {
+    const days = Number(auditRetentionDays);
+    const maxEntries = Number(auditMaxEntries);
+    if (!Number.isFinite(days) || !Number.isFinite(maxEntries)) {
+      pushToast("AUDIT LIMITS MUST BE NUMBERS", "danger");
+      return;
     }
-    return `Remote ${remoteAsrUrl.trim() || "https://api.server/transcribe"}`;
-  }, [asrProvider, remoteAsrUrl]);
+    await persistSettingsPatch({
+      audit_log_enabled: auditEnabled,
+      audit_log_include_text: auditIncludeText,
+      audit_log_retention_days: Math.max(0, Math.round(days)),
+      audit_log_max_entries: Math.max(0, Math.round(maxEntries)),
+    });
+  }
 
-  const llmKeyDisplay = sensitiveSettingDisplay(llmKeyStatus);
-  const remoteAsrKeyDisplay = sensitiveSettingDisplay(remoteAsrKeyStatus);
-  const doubaoCredentialsDisplay = sensitiveSettingDisplay(doubaoCredentialsStatus);
+  async function saveRecordingRetentionConfig() {
+    await persistSettingsPatch(
+      {
+        keep_recordings: keepRecordings,
+        recording_format: recordingFormat,
+      },
+      "RECORDING RETENTION SAVED",
+    );
+  }
 
-  function toggleSettingsPanel(panel: SettingsPanelId) {
-    setExpandedSettingsPanels((current) =>
-      current.includes(panel)
-        ? current.filter((value) => value !== panel)
-        : [...current, panel],
+  async function saveMarkdownNotesConfig() {
+    await persistSettingsPatch(
+      {
+        markdown_notes_enabled: markdownNotesEnabled,
+        markdown_notes_folder: markdownNotesFolder.trim() || null,
+        markdown_notes_entry_template: markdownNotesEntryTemplate.trim() ? markdownNotesEntryTemplate : null,
+      },
+      "NOTES SINK SAVED",
     );
   }
 
-  return (
-    <div className="pageSurface settingsSurface">
-      <div className="pageHeader settingsHeader">
-        <div className="sectionTitle">settings</div>
-        <div className="ok">Saved</div>
-      </div>
-      <div className="settingsGrid">
-        <div className="settingsColumn">
-          <div className="card">
-          <SettingsLine
-            title="Speech recognition"
-            detail={asrStatusText}
-            panel="asr"
-            expandedPanels={expandedSettingsPanels}
-            onTogglePanel={toggleSettingsPanel}
-            control={<PixelSelect value={asrProvider} onChange={setAsrProvider} options={ASR_PROVIDERS} />}
-          >
-            <div className="stack">
-              {asrProvider === "doubao" ? (
-                <>
-                  <PixelInput
-                    value={doubaoAppKeyDraft || doubaoCredentialsDisplay}
-                    onChange={setDoubaoAppKeyDraft}
-                    placeholder="App Key not configured"
-                    readOnly={!doubaoAppKeyDraft && !!doubaoCredentialsDisplay}
-                  />
-                  <PixelInput
-                    value={doubaoAccessKeyDraft || doubaoCredentialsDisplay}
-                    onChange={setDoubaoAccessKeyDraft}
-                    placeholder="Access Key not configured"
-                    readOnly={!doubaoAccessKeyDraft && !!doubaoCredentialsDisplay}
-                  />
-                  <div className="row" style={{ justifyContent: "flex-end" }}>
-                    <PixelButton
-                      onClick={setDoubaoAsrCredentials}
-                      tone="accent"
-                      disabled={!doubaoAppKeyDraft.trim() || !doubaoAccessKeyDraft.trim()}
-                    >
-                      Save key
-                    </PixelButton>
-                    <PixelButton onClick={clearDoubaoAsrCredentials} tone="danger">
-                      Clear key
-                    </PixelButton>
-                    <PixelButton onClick={checkDoubaoAsrCredentials} disabled={doubaoCheckPending}>
-                      {doubaoCheckPending ? "Checking" : "Check key"}
-                    </PixelButton>
-                  </div>
-                </>
-              ) : (
-                <>
-                  <PixelInput
-                    value={remoteAsrUrl}
-                    onChange={setRemoteAsrUrl}
-                    placeholder="remote ASR URL (e.g. https://api.server/transcribe)"
-                  />
-                  <PixelInput
-                    value={remoteAsrModel}
-                    onChange={setRemoteAsrModel}
-                    placeholder="remote model name (optional)"
-                  />
-                  <PixelInput
-                    value={remoteAsrConcurrency}
-                    onChange={setRemoteAsrConcurrency}
-                    placeholder="remote slicing concurrency (1-16)"
-                  />
-                  <PixelInput
-                    value={remoteAsrKeyDraft || remoteAsrKeyDisplay}
-                    onChange={setRemoteAsrKeyDraft}
-                    placeholder="Remote ASR key not configured"
-                    readOnly={!remoteAsrKeyDraft && !!remoteAsrKeyDisplay}
-                  />
-                  <div className="row" style={{ justifyContent: "flex-end" }}>
-                    <PixelButton
-                      onClick={setRemoteAsrApiKey}
-                      tone="accent"
-                      disabled={!remoteAsrKeyDraft.trim()}
-                    >
-                      Save key
-                    </PixelButton>
+  async function saveWebhookConfig() {
+    const fields = webhookFields
+      .split(",")
+      .map((x) => x.trim())
+      .filter((x) => x.length > 0);
+    await persistSettingsPatch(
+      {
+        webhook_enabled: webhookEnabled,
+        webhook_url: webhookUrl.trim() || null,
+        webhook_fields: fields.length > 0 ? fields : null,
+      },
+      "WEBHOOK SAVED",
+    );
+  }
+
+  async function setWebhookSecret() {
+    const secret = webhookSecretDraft.trim();
+    if (!secret) return;
+    try {
+      await defaultTauriGateway.invoke("set_webhook_secret", { secret });
+      setWebhookSecretDraft("");
+      await refreshSensitiveSettingStatuses();
+      pushToast("WEBHOOK SECRET SAVED", "ok");
+    } catch {
+      pushToast("WEBHOOK SECRET SAVE FAILED", "danger");
+    }
+  }
+
+  async function clearWebhookSecret() {
+    try {
+      await defaultTauriGateway.invoke("clear_webhook_secret");
+      setWebhookSecretStatus(null);
+      await refreshSensitiveSettingStatuses();
+      pushToast("WEBHOOK SECRET CLEARED", "ok");
+    } catch {
+      pushToast("WEBHOOK SECRET CLEAR FAILED", "danger");
+    }
+  }
+
+  async function saveMqttConfig() {
+    const port = Number(mqttBrokerPort.trim());
+    await persistSettingsPatch(
+      {
+        mqtt_enabled: mqttEnabled,
+        mqtt_broker_host: mqttBrokerHost.trim() || null,
+        mqtt_broker_port: mqttBrokerPort.trim() && Number.isFinite(port) ? port : null,
+        mqtt_tls: mqttTls,
+        mqtt_topic_prefix: mqttTopicPrefix.trim() || null,
+        mqtt_username: mqttUsername.trim() || null,
+      },
+      "MQTT SAVED",
+    );
+  }
+
+  async function saveDailySummaryConfig() {
+    await persistSettingsPatch(
+      {
+        daily_summary_enabled: dailySummaryEnabled,
+        daily_summary_time: dailySummaryTime.trim() || null,
+        daily_summary_prompt: dailySummaryPrompt.trim() ? dailySummaryPrompt : null,
+      },
+      "DAILY SUMMARY SAVED",
+    );
+  }
+
+  async function runDailySummaryNow() {
+    if (dailySummaryPending) return;
+    setDailySummaryPending(true);
+    try {
+      const result = (await defaultTauriGateway.invoke("daily_summary_run", {
+        date: null,
+      })) as DailySummaryResult;
+      pushToast(result.path ? "DAILY SUMMARY WRITTEN" : "NO DICTATIONS TODAY", "ok");
+    } catch {
+      pushToast("DAILY SUMMARY FAILED", "danger");
+    } finally {
+      setDailySummaryPending(false);
+    }
+  }
+
+  async function refreshWorkspaces() {
+    try {
+      setWorkspaces((await defaultTauriGateway.invoke("list_workspaces")) as WorkspaceInfo[]);
+    } catch {
+      setWorkspaces([]);
+    }
+  }
+
+  async function switchWorkspace(name: string) {
+    const target = name.trim();
+    if (!target) return;
+    try {
+      await defaultTauriGateway.invoke("switch_workspace", { name: target });
+      setWorkspaceDraft("");
+      await refreshWorkspaces();
+      pushToast(`WORKSPACE: ${target.toUpperCase()}`, "ok");
+    } catch {
+      pushToast("WORKSPACE SWITCH FAILED", "danger");
+    }
+  }
+
+  async function refreshPermissions() {
+    try {
+      setPermissions((await defaultTauriGateway.invoke("permissions_status")) as PermissionStatus[]);
+    } catch {
+      setPermissions([]);
+    }
+  }
+
+  // The microphone prompt only appears when a recording starts, so its
+  // request step runs the mic test.
+  async function requestPermission(kind: PermissionKind) {
+    try {
+      if (kind === "microphone") {
+        await runMicTest();
+      } else {
+        await defaultTauriGateway.invoke("request_permission", { kind });
+      }
+    } catch {
+      pushToast("PERMISSION REQUEST FAILED", "danger");
+    }
+    await refreshPermissions();
+  }
+
+  async function openPermissionSettings(kind: PermissionKind) {
+    try {
+      await defaultTauriGateway.invoke("open_permission_settings", { kind });
+    } catch {
+      pushToast("OPEN SETTINGS FAILED", "danger");
+    }
+    await refreshPermissions();
+  }
+
+  async function setMqttPassword() {
+    if (!mqttPasswordDraft) return;
+    try {
+      await defaultTauriGateway.invoke("set_mqtt_password", { password: mqttPasswordDraft });
+      setMqttPasswordDraft("");
+      await refreshSensitiveSettingStatuses();
+      pushToast("MQTT PASSWORD SAVED", "ok");
+    } catch {
+      pushToast("MQTT PASSWORD SAVE FAILED", "danger");
+    }
+  }
+
+  async function clearMqttPassword() {
+    try {
+      await defaultTauriGateway.invoke("clear_mqtt_password");
+      setMqttPasswordStatus(null);
+      await refreshSensitiveSettingStatuses();
+      pushToast("MQTT PASSWORD CLEARED", "ok");
+    } catch {
+      pushToast("MQTT PASSWORD CLEAR FAILED", "danger");
+    }
+  }
+
+  async function loadAuditEntries() {
+    try {
+      setAuditEntries((await defaultTauriGateway.invoke("audit_list", { limit: 20 })) as AuditEntry[]);
+    } catch {
+      pushToast("AUDIT LOG UNAVAILABLE", "danger");
+    }
+  }
+
+  async function clearAuditLog() {
+    try {
+      await defaultTauriGateway.invoke("audit_clear");
+      setAuditEntries([]);
+      pushToast("AUDIT LOG CLEARED", "ok");
+    } catch {
+      pushToast("CLEAR FAILED", "danger");
+    }
+  }
+
+  async function backupData() {
+    if (backupPending || !backupPath.trim()) return;
+    setBackupPending(true);
+    try {
+      const result = (await defaultTauriGateway.invoke("backup_data", {
+        path: backupPath.trim(),
+      })) as BackupResult;
+      pushToast(`BACKED UP ${result.manifest.workspaces.length} WORKSPACE(S)`, "ok");
+    } catch {
+      pushToast("BACKUP FAILED", "danger");
+    } finally {
+      setBackupPending(false);
+    }
+  }
+
+  async function restoreData() {
+    setConfirmRestore(false);
+    if (backupPending || !backupPath.trim()) return;
+    setBackupPending(true);
+    try {
+      const result = (await defaultTauriGateway.invoke("restore_data", {
+        path: backupPath.trim(),
+      })) as RestoreResult;
+      pushToast(`RESTORED; PREVIOUS DATA IN ${result.pre_restore_backup}`, "ok");
+      onHistoryCleared();
+    } catch {
+      pushToast("RESTORE FAILED", "danger");
+    } finally {
+      setBackupPending(false);
+    }
+  }
+
+  async function clearHistory() {
+    try {
+      await defaultTauriGateway.invoke("history_clear");
+      pushToast("HISTORY CLEARED", "ok");
+      onHistoryCleared();
+    } catch {
+      pushToast("CLEAR FAILED", "danger");
+    } finally {
+      setConfirmClear(false);
+    }
+  }
+
+  const asrStatusText = useMemo(() => {
+    if (asrProvider === "doubao") {
+      return "Doubao streaming  wss://openspeech.bytedance.com/api/v3/sauc/bigmodel_async";
+    }
+    return `Remote ${remoteAsrUrl.trim() || "https://api.server/transcribe"}`;
+  }, [asrProvider, remoteAsrUrl]);
+
+  const llmKeyDisplay = sensitiveSettingDisplay(llmKeyStatus);
+  const remoteAsrKeyDisplay = sensitiveSettingDisplay(remoteAsrKeyStatus);
+  const doubaoCredentialsDisplay = sensitiveSettingDisplay(doubaoCredentialsStatus);
+  const proxyCredentialsDisplay = sensitiveSettingDisplay(proxyCredentialsStatus);
+  const webhookSecretDisplay = sensitiveSettingDisplay(webhookSecretStatus);
+  const mqttPasswordDisplay = sensitiveSettingDisplay(mqttPasswordStatus);
+
+  function toggleSettingsPanel(panel: SettingsPanelId) {
+    setExpandedSettingsPanels((current) =>
+      current.includes(panel)
+        ? current.filter((value) => value !== panel)
+        : [...current, panel],
+    );
+  }
+
+  return (
+    <div className="pageSurface settingsSurface">
+      <div className="pageHeader settingsHeader">
+        <div className="sectionTitle">settings</div>
+        <div className="ok">Saved</div>
+      </div>
+      {fieldErrors.length > 0 ? (
+        <div className="card">
+          <div className="stack">
+            {fieldErrors.map((e) => (
+              <div key={`${e.field}:${e.code}`} className="danger">
+                {e.field}: {e.message} ({e.code})
+              </div>
+            ))}
+          </div>
+        </div>
+      ) : null}
+      <div className="settingsGrid">
+        <div className="settingsColumn">
+          <div className="card">
+          <SettingsLine
+            title="Speech recognition"
+            detail={asrStatusText}
+            panel="asr"
+            expandedPanels={expandedSettingsPanels}
+            onTogglePanel={toggleSettingsPanel}
+            control={<PixelSelect value={asrProvider} onChange={setAsrProvider} options={ASR_PROVIDERS} />}
+          >
+            <div className="stack">
+              {asrProvider === "doubao" ? (
+                <>
+                  <PixelInput
+                    value={doubaoAppKeyDraft || doubaoCredentialsDisplay}
+                    onChange={setDoubaoAppKeyDraft}
+                    placeholder="App Key not configured"
+                    readOnly={!doubaoAppKeyDraft && !!doubaoCredentialsDisplay}
+                  />
+                  <PixelInput
+                    value={doubaoAccessKeyDraft || doubaoCredentialsDisplay}
+                    onChange={setDoubaoAccessKeyDraft}
+                    placeholder="Access Key not configured"
+                    readOnly={!doubaoAccessKeyDraft && !!doubaoCredentialsDisplay}
+                  />
+                  <div className="row" style={{ justifyContent: "flex-end" }}>
+                    <PixelButton
+                      onClick={setDoubaoAsrCredentials}
+                      tone="accent"
+                      disabled={!doubaoAppKeyDraft.trim() || !doubaoAccessKeyDraft.trim()}
+                    >
+                      Save key
+                    </PixelButton>
+                    <PixelButton onClick={clearDoubaoAsrCredentials} tone="danger">
+                      Clear key
+                    </PixelButton>
+                    <PixelButton onClick={checkDoubaoAsrCredentials} disabled={doubaoCheckPending}>
+                      {doubaoCheckPending ? "Checking" : "Check key"}
+                    </PixelButton>
+                  </div>
+                </>
+              ) : (
+                <>
+                  <PixelInput
+                    value={remoteAsrUrl}
+                    onChange={setRemoteAsrUrl}
+                    placeholder="remote ASR URL (e.g. https://api.server/transcribe)"
+                  />
+                  <PixelInput
+                    value={remoteAsrModel}
+                    onChange={setRemoteAsrModel}
+                    placeholder="remote model name (optional)"
+                  />
+                  <PixelInput
+                    value={remoteAsrConcurrency}
+                    onChange={setRemoteAsrConcurrency}
+                    placeholder="remote slicing concurrency (1-16)"
+                  />
+                  <div className="settingsInlineToggle">
+                    <span>Live Preview While Recording</span>
+                    <PixelToggle
+                      value={remoteLivePreview}
+                      onChange={setRemoteLivePreview}
+                      label="remote live preview"
+                    />
+                  </div>
+                  <div className="settingsInlineToggle">
+                    <span>Keep Models Warm</span>
+                    <PixelToggle
+                      value={warmPoolEnabled}
+                      onChange={setWarmPoolEnabled}
+                      label="asr warm pool"
+                    />
+                  </div>
+                  {warmPoolEnabled ? (
+                    <>
+                      <PixelInput
+                        value={warmPoolModels}
+                        onChange={setWarmPoolModels}
+                        placeholder="extra models to keep warm (comma separated)"
+                      />
+                      <PixelInput
+                        value={warmPoolMaxModels}
+                        onChange={setWarmPoolMaxModels}
+                        placeholder="max warm models (1-4)"
+                      />
+                      <PixelInput
+                        value={warmPoolVramBudgetMb}
+                        onChange={setWarmPoolVramBudgetMb}
+                        placeholder="VRAM budget MB (0 = no budget)"
+                      />
+                      <PixelInput
+                        value={warmPoolModelVram}
+                        onChange={setWarmPoolModelVram}
+                        placeholder="per-model VRAM MB (e.g. large-v3=4000, small=500)"
+                      />
+                      <PixelInput
+                        value={warmPoolKeepaliveSecs}
+                        onChange={setWarmPoolKeepaliveSecs}
+                        placeholder="keepalive seconds (min 30)"
+                      />
+                      {warmPoolStatus?.enabled ? (
+                        <div className="muted">
+                          {warmPoolStatus.paused
+                            ? `暂停：${warmPoolStatus.paused}`
+                            : `常驻 ${warmPoolStatus.plan.members
+                                .map((m) => m.model || "default")
+                                .join(", ")} · 约 ${warmPoolStatus.plan.vram_mb} MB`}
+                        </div>
+                      ) : null}
+                    </>
+                  ) : null}
+                  <PixelInput
+                    value={remoteAsrKeyDraft || remoteAsrKeyDisplay}
+                    onChange={setRemoteAsrKeyDraft}
+                    placeholder="Remote ASR key not configured"
+                    readOnly={!remoteAsrKeyDraft && !!remoteAsrKeyDisplay}
+                  />
+                  <div className="row" style={{ justifyContent: "flex-end" }}>
+                    <PixelButton
+                      onClick={setRemoteAsrApiKey}
+                      tone="accent"
+                      disabled={!remoteAsrKeyDraft.trim()}
+                    >
+                      Save key
+                    </PixelButton>
                     <PixelButton onClick={clearRemoteAsrApiKey} tone="danger">
                       Clear key
                     </PixelButton>
@@ -842,7 +1834,133 @@ export function SettingsScreen({
               {audioCaptureDevices.length === 0 ? (
                 <div className="muted">No active capture endpoints detected.</div>
               ) : null}
+              <div className="row">
+                <span className="muted" style={{ flex: 1 }}>
+                  Avoid Bluetooth hands-free (8 kHz) capture
+                </span>
+                <PixelToggle
+                  value={recordAvoidLowQualityProfile}
+                  onChange={setRecordAvoidLowQualityProfile}
+                  label="avoid hands-free profile"
+                />
+              </div>
+              <div className="row">
+                <span className="muted" style={{ flex: 1 }}>
+                  Recorder (native falls back to ffmpeg if the device will not open)
+                </span>
+                <PixelSelect value={recordBackend} onChange={setRecordBackend} options={RECORD_BACKENDS} />
+              </div>
+              <div className="row">
+                <span className="muted" style={{ flex: 1 }}>
+                  Stop recording automatically after silence
+                </span>
+                <PixelToggle value={recordVadEnabled} onChange={setRecordVadEnabled} label="auto-stop on silence" />
+              </div>
+              {recordVadEnabled ? (
+                <PixelInput
+                  value={recordVadSilenceMs}
+                  onChange={setRecordVadSilenceMs}
+                  placeholder="trailing silence ms (300-60000)"
+                />
+              ) : null}
+              {preferenceRows.length > 0 ? (
+                <div className="stack">
+                  <div className="muted">
+                    Preferred order (drag to sort, tried before auto select)
+                  </div>
+                  {preferenceRows.map((row, index) => (
+                    <div
+                      key={row.endpoint_id}
+                      className="row"
+                      draggable
+                      onDragStart={() => setPreferenceDragIndex(index)}
+                      onDragOver={(event) => event.preventDefault()}
+                      onDrop={() => {
+                        if (preferenceDragIndex === null) return;
+                        const dragged = preferenceRows[preferenceDragIndex];
+                        setPreferenceDragIndex(null);
+                        if (dragged) movePreference(dragged.endpoint_id, index);
+                      }}
+                      onDragEnd={() => setPreferenceDragIndex(null)}
+                      style={{ cursor: "grab", opacity: row.preferred ? 1 : 0.6 }}
+                    >
+                      <span style={{ flex: 1 }}>
+                        {row.preferred ? `${index + 1}. ` : ""}
+                        {row.friendly_name}
+                      </span>
+                      {row.preferred ? (
+                        <PixelButton onClick={() => removePreference(row.endpoint_id)}>
+                          Remove
+                        </PixelButton>
+                      ) : (
+                        <PixelButton
+                          onClick={() =>
+                            movePreference(row.endpoint_id, recordDevicePreference.length)
+                          }
+                        >
+                          Prefer
+                        </PixelButton>
+                      )}
+                    </div>
+                  ))}
+                  <div className="row" style={{ justifyContent: "flex-end" }}>
+                    <PixelButton onClick={saveDevicePreference}>Save order</PixelButton>
+                  </div>
+                </div>
+              ) : null}
+              <PixelSelect
+                value={overrideEndpointId}
+                onChange={setOverrideEndpointId}
+                options={captureDeviceOptions}
+                placeholder="per-device settings"
+              />
+              {overrideEndpointId ? (
+                <>
+                  <PixelInput
+                    value={overrideGainDb}
+                    onChange={setOverrideGainDb}
+                    placeholder="device gain dB"
+                  />
+                  <PixelInput
+                    value={overrideExtraArgs}
+                    onChange={setOverrideExtraArgs}
+                    placeholder="extra ffmpeg input args, e.g. -audio_buffer_size 50"
+                  />
+                  <div className="row" style={{ justifyContent: "flex-end" }}>
+                    <PixelButton onClick={saveDeviceOverride}>Save device settings</PixelButton>
+                  </div>
+                </>
+              ) : null}
+              {audioLevel ? (
+                <div className="row">
+                  <span className="muted">Live input</span>
+                  <LevelMeter level={audioLevel} showLabel />
+                </div>
+              ) : null}
+              {micTestResult ? (
+                <div className="muted">
+                  level {micTestResult.level} · peak {micTestResult.peak_db} dB · rms{" "}
+                  {micTestResult.rms_db} dB ·{" "}
+                  {micTestResult.transcript
+                    ? `"${micTestResult.transcript}" (${micTestResult.provider}, ${micTestResult.asr_ms} ms)`
+                    : micTestResult.asr_error_code || "no transcript"}
+                </div>
+              ) : null}
+              {bugReportResult ? (
+                <div className="muted">
+                  bug report {Math.round(bugReportResult.bytes / 1024)} KB · {bugReportResult.path}
+                </div>
+              ) : null}
               <div className="row" style={{ justifyContent: "flex-end" }}>
+                <PixelButton onClick={runMicTest} disabled={micTestPending}>
+                  {micTestPending ? "Recording 3s..." : "Test mic"}
+                </PixelButton>
+                <PixelButton
+                  onClick={() => setConfirmBugReport(true)}
+                  disabled={bugReportPending || micTestPending}
+                >
+                  {bugReportPending ? "Recording 8s..." : "Bug report"}
+                </PixelButton>
                 <PixelButton onClick={refreshAudioCaptureDevices}>Refresh</PixelButton>
                 <PixelButton onClick={saveRecordingInput} tone="accent">
                   Save
@@ -881,42 +1999,748 @@ export function SettingsScreen({
                   onChange={setAsrPreprocessStartMs}
                   placeholder="300"
                 />
-              </div>
-              <div className="settingsField">
-                <div className="muted">末段静音 (ms)</div>
+              </div>
+              <div className="settingsField">
+                <div className="muted">末段静音 (ms)</div>
+                <PixelInput
+                  value={asrPreprocessEndMs}
+                  onChange={setAsrPreprocessEndMs}
+                  placeholder="300"
+                />
+              </div>
+              <div className="settingsField">
+                <div className="muted">增益 (dB)</div>
+                <PixelInput
+                  value={asrPreprocessGainDb}
+                  onChange={setAsrPreprocessGainDb}
+                  placeholder="0"
+                />
+              </div>
+              <div className="settingsInlineToggle">
+                <span>Keep ffmpeg Warm</span>
+                <PixelToggle
+                  value={asrPreprocessWarmSpare}
+                  onChange={setAsrPreprocessWarmSpare}
+                  label="warm ffmpeg"
+                />
+              </div>
+              <div className="settingsInlineToggle">
+                <span>Native WAV Preprocessing</span>
+                <PixelToggle
+                  value={asrPreprocessNativeWav}
+                  onChange={setAsrPreprocessNativeWav}
+                  label="native wav preprocessing"
+                />
+              </div>
+              {noiseProfile ? (
+                <div className="muted">
+                  底噪 {noiseProfile.noise_floor_db} dB · 峰值 {noiseProfile.peak_db} dB → 建议阈值{" "}
+                  {noiseProfile.suggested_silence_threshold_db} dB，增益{" "}
+                  {noiseProfile.suggested_gain_db} dB
+                </div>
+              ) : null}
+              <div className="row" style={{ justifyContent: "flex-end" }}>
+                <PixelButton onClick={calibrateNoiseProfile} disabled={noiseCalibrationPending}>
+                  {noiseCalibrationPending ? "Listening 3s..." : "Calibrate"}
+                </PixelButton>
+                {noiseProfile ? (
+                  <PixelButton onClick={applyNoiseProfile}>Apply</PixelButton>
+                ) : null}
+                <PixelButton onClick={savePreprocessConfig} tone="accent">
+                  Save
+                </PixelButton>
+              </div>
+            </div>
+          </SettingsLine>
+          </div>
+
+          <div className="card">
+            <SettingsLine
+              title="Rewrite"
+              detail={rewriteEnabled ? "On" : "Off"}
+              panel="rewrite"
+              expandedPanels={expandedSettingsPanels}
+              onTogglePanel={toggleSettingsPanel}
+              control={<PixelToggle value={rewriteEnabled} onChange={setRewriteEnabled} label="rewrite" />}
+            >
+              <div className="stack">
+                <PixelTextarea
+                  value={llmPrompt}
+                  onChange={setLlmPrompt}
+                  placeholder="LLM prompt..."
+                  rows={10}
+                />
+                <PixelTextarea
+                  value={rewritePreamble}
+                  onChange={setRewritePreamble}
+                  placeholder="Preamble for every rewrite (house style, language, terms to keep)..."
+                  rows={4}
+                />
+                <PixelSelect
+                  value={rewriteOutputFormat}
+                  onChange={setRewriteOutputFormat}
+                  options={REWRITE_OUTPUT_FORMATS}
+                />
+                {rewriteOutputFormat === "json_schema" ? (
+                  <PixelTextarea
+                    value={rewriteOutputSchema}
+                    onChange={setRewriteOutputSchema}
+                    placeholder={'JSON Schema (optional), e.g. {"type":"object","required":["title"]}'}
+                    rows={6}
+                  />
+                ) : null}
+                {rewritePreview ? (
+                  <div className="muted" style={{ whiteSpace: "pre-wrap" }}>
+                    {rewritePreview.system_prompt}
+                  </div>
+                ) : null}
+                <PixelInput
+                  value={maxBackgroundRewrites}
+                  onChange={setMaxBackgroundRewrites}
+                  placeholder="background rewrites while recording next (0-4, 0 = off)"
+                />
+                <PixelInput
+                  value={chunkThresholdTokens}
+                  onChange={setChunkThresholdTokens}
+                  placeholder="split long text above N tokens (0 = off)"
+                />
+                <PixelInput
+                  value={chunkTokens}
+                  onChange={setChunkTokens}
+                  placeholder="tokens per chunk (min 200)"
+                />
+                <PixelInput
+                  value={rewriteTokenCap}
+                  onChange={setRewriteTokenCap}
+                  placeholder="max tokens per request, drops context to fit (0 = off, min 500)"
+                />
+                <div className="settingsInlineToggle">
+                  <span>Reuse Identical Rewrites</span>
+                  <PixelToggle
+                    value={rewriteCacheEnabled}
+                    onChange={setRewriteCacheEnabled}
+                    label="rewrite cache"
+                  />
+                </div>
+                <div className="row" style={{ justifyContent: "flex-end" }}>
+                  <PixelButton onClick={previewRewritePrompt}>Preview</PixelButton>
+                  <PixelButton onClick={saveRewrite} tone="accent">
+                    Save
+                  </PixelButton>
+                </div>
+              </div>
+            </SettingsLine>
+          </div>
+
+          <div className="card">
+            <SettingsLine
+              title="Glossary"
+              detail="One term per line"
+              panel="glossary"
+              expandedPanels={expandedSettingsPanels}
+              onTogglePanel={toggleSettingsPanel}
+              control={
+                <PixelToggle
+                  value={rewriteIncludeGlossary}
+                  onChange={setRewriteIncludeGlossary}
+                  label="rewrite glossary"
+                />
+              }
+            >
+              <div className="stack">
+                <div className="muted">
+                  每行一个词；空行会自动忽略。用于 rewrite 阶段作为上下文词汇或术语约束模型遵循。
+                </div>
+                <PixelTextarea
+                  value={rewriteGlossaryDraft}
+                  onChange={setRewriteGlossaryDraft}
+                  placeholder={"比如：QPSK\nTypeScript\nOAuth"}
+                  rows={8}
+                />
+                <div className="row" style={{ justifyContent: "flex-end" }}>
+                  <PixelButton onClick={saveGlossary} tone="accent">
+                    Save
+                  </PixelButton>
+                </div>
+              </div>
+            </SettingsLine>
+          </div>
+
+          <div className="card">
+            <SettingsLine
+              title="Post-processors"
+              detail={postprocessorsSummary(postprocessorsDraft)}
+              panel="postprocessors"
+              expandedPanels={expandedSettingsPanels}
+              onTogglePanel={toggleSettingsPanel}
+            >
+              <div className="stack">
+                <div className="muted">
+                  在大小写与数字规范化之后按顺序执行。command 通过 stdin 接收 JSON（text/mode/task_id），从 stdout 返回
+                  {'{"text": "..."}'}；wasm 模块需导出 memory、alloc 与 postprocess，且不能导入任何宿主函数。失败或超时的插件会被跳过。
+                </div>
+                <PixelTextarea
+                  value={postprocessorsDraft}
+                  onChange={setPostprocessorsDraft}
+                  placeholder={'[\n  { "id": "snippets", "kind": "command", "path": "C:\\\\tools\\\\snippets.exe", "timeout_ms": 3000 }\n]'}
+                  rows={8}
+                />
+                <div className="row" style={{ justifyContent: "flex-end" }}>
+                  <PixelButton onClick={savePostprocessors} tone="accent">
+                    Save
+                  </PixelButton>
+                </div>
+              </div>
+            </SettingsLine>
+          </div>
+
+          <div className="card">
+            <SettingsLine
+              title="Hotkeys"
+              detail={hotkeysEnabled ? "On" : "Off"}
+              panel="hotkeys"
+              expandedPanels={expandedSettingsPanels}
+              onTogglePanel={toggleSettingsPanel}
+              control={<PixelToggle value={hotkeysEnabled} onChange={setHotkeysEnabled} label="hotkeys" />}
+            >
+              <div className="stack">
+                <div className="hotkeyGuide">
+                  <div><span>{hotkeyPrimary}</span><span>short press starts or stops recording</span></div>
+                </div>
+                {hotkeyBackend ? (
+                  <div className="muted">
+                    {hotkeyBackendLabel(hotkeyBackend)}
+                    {hotkeyBackend.available ? "" : ` · ${hotkeyBackend.reason_code ?? "unavailable"}`}
+                  </div>
+                ) : null}
+                <div className="stack">
+                  <div className="muted">Primary Key</div>
+                  <PixelSelect
+                    value={hotkeyPrimary}
+                    onChange={setHotkeyPrimary}
+                    options={PRIMARY_HOTKEYS}
+                  />
+                </div>
+                <SliderField
+                  label="Repeat Press Guard"
+                  min={0}
+                  max={2000}
+                  step={50}
+                  value={hotkeyDebounceMs}
+                  suffix="ms"
+                  onChange={setHotkeyDebounceMs}
+                />
+                <div className="stack">
+                  <div className="muted">
+                    Only In These Apps (one process name per line, e.g. code.exe; empty = any app)
+                  </div>
+                  <PixelTextarea
+                    value={hotkeyAppAllowlistDraft}
+                    onChange={setHotkeyAppAllowlistDraft}
+                    placeholder={"code.exe\noutlook.exe"}
+                    rows={3}
+                  />
+                </div>
+                <div className="settingsInlineToggle">
+                  <span>Overlay</span>
+                  <PixelToggle
+                    value={hotkeysShowOverlay}
+                    onChange={setHotkeysShowOverlay}
+                    label="overlay"
+                  />
+                </div>
+                <div className="settingsInlineToggle">
+                  <span>Screen Reader Announcements</span>
+                  <PixelToggle
+                    value={a11yAnnouncements}
+                    onChange={setA11yAnnouncements}
+                    label="screen reader announcements"
+                  />
+                </div>
+                <div className="settingsInlineToggle">
+                  <span>Always Show Capture Light</span>
+                  <PixelToggle
+                    value={captureIndicatorEnforced}
+                    onChange={setCaptureIndicatorEnforced}
+                    label="always show capture light"
+                  />
+                </div>
+                <div className="settingsInlineToggle">
+                  <span>Hide While Screen Sharing</span>
+                  <PixelToggle
+                    value={screenShareGuard}
+                    onChange={setScreenShareGuard}
+                    label="hide while screen sharing"
+                  />
+                </div>
+                {screenShareGuard ? (
+                  <div className="stack">
+                    <div className="muted">
+                      Also treat these processes as sharing (one per line; Zoom is built in)
+                    </div>
+                    <PixelTextarea
+                      value={screenShareProcessesDraft}
+                      onChange={setScreenShareProcessesDraft}
+                      placeholder="presenter.exe"
+                      rows={2}
+                    />
+                  </div>
+                ) : null}
+                <div className="settingsInlineToggle">
+                  <span>Low Power Mode</span>
+                  <PixelSelect
+                    value={lowPowerMode}
+                    onChange={setLowPowerMode}
+                    options={LOW_POWER_MODES}
+                  />
+                </div>
+                {lowPowerMode !== "off" ? (
+                  <div className="stack">
+                    <div className="muted">
+                      Skips screenshots and polls the foreground window less often
+                      {powerStatus?.active
+                        ? ` · active (${powerStatus.active})`
+                        : powerStatus?.on_battery === false
+                          ? " · on AC power"
+                          : ""}
+                    </div>
+                    <div className="settingsInlineToggle">
+                      <span>Low Power ASR</span>
+                      <PixelSelect
+                        value={lowPowerAsrProvider}
+                        onChange={setLowPowerAsrProvider}
+                        options={LOW_POWER_ASR_PROVIDERS}
+                      />
+                    </div>
+                    <PixelInput
+                      value={lowPowerRemoteModel}
+                      onChange={setLowPowerRemoteModel}
+                      placeholder="smaller remote model while on low power (optional)"
+                    />
+                  </div>
+                ) : null}
+                <SliderField
+                  label="Background Depth"
+                  min={0.35}
+                  max={0.95}
+                  step={0.01}
+                  value={overlayBackgroundOpacity}
+                  onChange={setOverlayBackgroundOpacity}
+                />
+                <SliderField
+                  label="Font Size"
+                  min={18}
+                  max={56}
+                  step={1}
+                  value={overlayFontSizePx}
+                  suffix="px"
+                  onChange={setOverlayFontSizePx}
+                />
+                <SliderField
+                  label="Width"
+                  min={360}
+                  max={1600}
+                  step={10}
+                  value={overlayWidthPx}
+                  suffix="px"
+                  onChange={setOverlayWidthPx}
+                />
+                <SliderField
+                  label="Height"
+                  min={72}
+                  max={360}
+                  step={4}
+                  value={overlayHeightPx}
+                  suffix="px"
+                  onChange={setOverlayHeightPx}
+                />
+                <SliderField
+                  label="Font Scale"
+                  min={0.5}
+                  max={2}
+                  step={0.05}
+                  value={overlayFontScale}
+                  suffix="x"
+                  onChange={setOverlayFontScale}
+                />
+                <div className="settingsInlineToggle">
+                  <span>Status Labels</span>
+                  <PixelSelect
+                    value={overlayStatusLanguage}
+                    onChange={setOverlayStatusLanguage}
+                    options={OVERLAY_STATUS_LANGUAGES}
+                  />
+                </div>
+                <div className="row" style={{ justifyContent: "flex-end" }}>
+                  <PixelButton onClick={saveHotkeys} tone="accent">
+                    Save
+                  </PixelButton>
+                </div>
+              </div>
+            </SettingsLine>
+          </div>
+        </div>
+
+        <div className="settingsColumn">
+          <div className="card">
+            <SettingsLine
+              title="Language model"
+              detail={llmModel.trim() || "Model settings"}
+              panel="llm"
+              expandedPanels={expandedSettingsPanels}
+              onTogglePanel={toggleSettingsPanel}
+              control={<PixelSelect value={reasoning} onChange={setReasoning} options={REASONING} />}
+            >
+              <div className="stack">
+                <PixelInput
+                  value={llmBaseUrl}
+                  onChange={setLlmBaseUrl}
+                  placeholder="API Base URL (e.g. https://api.openai.com/v1)"
+                />
+                <PixelInput value={llmModel} onChange={setLlmModel} placeholder="Model" />
+                <div className="settingsInlineToggle">
+                  <span>Capture Requests for Debugging</span>
+                  <PixelToggle
+                    value={llmCaptureEnabled}
+                    onChange={setLlmCaptureEnabled}
+                    label="capture llm requests"
+                  />
+                </div>
+                {llmCaptureEnabled ? (
+                  <SliderField
+                    label="Captures Kept"
+                    min={1}
+                    max={200}
+                    step={1}
+                    value={llmCaptureMaxEntries}
+                    onChange={setLlmCaptureMaxEntries}
+                  />
+                ) : null}
+                <div className="settingsInlineToggle">
+                  <span>Share Local GPU with ASR</span>
+                  <PixelToggle
+                    value={gpuCoordinationEnabled}
+                    onChange={setGpuCoordinationEnabled}
+                    label="gpu coordination"
+                  />
+                </div>
+                {gpuCoordinationEnabled ? (
+                  <>
+                    <PixelInput
+                      value={gpuAsrUnloadUrl}
+                      onChange={setGpuAsrUnloadUrl}
+                      placeholder="ASR unload URL (optional)"
+                    />
+                    <PixelInput
+                      value={gpuLlmUnloadUrl}
+                      onChange={setGpuLlmUnloadUrl}
+                      placeholder="LLM unload URL (optional)"
+                    />
+                  </>
+                ) : null}
+                <div className="row" style={{ justifyContent: "flex-end" }}>
+                  <PixelButton onClick={saveLlm} tone="accent">
+                    Save
+                  </PixelButton>
+                </div>
+              </div>
+            </SettingsLine>
+          </div>
+
+          <div className="card">
+            <SettingsLine
+              title="API key"
+              detail="Stored in keyring, encrypted file, or environment"
+              panel="llmKey"
+              expandedPanels={expandedSettingsPanels}
+              onTogglePanel={toggleSettingsPanel}
+            >
+              <div className="stack">
+                <PixelInput
+                  value={keyDraft || llmKeyDisplay}
+                  onChange={setKeyDraft}
+                  placeholder="LLM API key not configured"
+                  readOnly={!keyDraft && !!llmKeyDisplay}
+                />
+                <div className="row" style={{ justifyContent: "flex-end" }}>
+                  <PixelButton onClick={setApiKey} tone="accent" disabled={!keyDraft.trim()}>
+                    Save
+                  </PixelButton>
+                  <PixelButton onClick={clearApiKey} tone="danger">
+                    Clear
+                  </PixelButton>
+                  <PixelButton onClick={checkApiKey} disabled={llmCheckPending}>
+                    {llmCheckPending ? "Checking" : "Check"}
+                  </PixelButton>
+                </div>
+              </div>
+            </SettingsLine>
+          </div>
+
+          <div className="card">
+            <SettingsLine
+              title="Network"
+              detail={offlineMode ? "Offline mode: all network calls disabled" : proxyUrl.trim() || "Direct connection"}
+              panel="proxy"
+              expandedPanels={expandedSettingsPanels}
+              onTogglePanel={toggleSettingsPanel}
+            >
+              <div className="stack">
+                <div className="settingsInlineToggle">
+                  <span>Offline Mode</span>
+                  <PixelToggle value={offlineMode} onChange={setOfflineMode} label="offline" />
+                </div>
+                <PixelInput
+                  value={proxyUrl}
+                  onChange={setProxyUrl}
+                  placeholder="proxy URL (http://host:port or socks5://host:port)"
+                />
+                <PixelInput
+                  value={proxyNoProxy}
+                  onChange={setProxyNoProxy}
+                  placeholder="no proxy for (comma separated, e.g. localhost, *.corp.example)"
+                />
+                <PixelInput
+                  value={tlsCaBundlePath}
+                  onChange={setTlsCaBundlePath}
+                  placeholder="custom root CA bundle (PEM file path, optional)"
+                />
+                <PixelInput
+                  value={tlsPinnedSha256}
+                  onChange={setTlsPinnedSha256}
+                  placeholder="pinned server certificate SHA-256 (optional)"
+                />
+                <PixelInput
+                  value={httpConnectTimeoutMs}
+                  onChange={setHttpConnectTimeoutMs}
+                  placeholder="connect timeout ms (default 10000)"
+                />
+                <PixelInput
+                  value={httpRequestTimeoutMs}
+                  onChange={setHttpRequestTimeoutMs}
+                  placeholder="request timeout ms (0 = no limit, default 180000)"
+                />
+                <div className="row" style={{ justifyContent: "flex-end" }}>
+                  <PixelButton onClick={saveNetwork} tone="accent">
+                    Save
+                  </PixelButton>
+                  <PixelButton onClick={testProxy} disabled={proxyCheckPending || offlineMode || !proxyUrl.trim()}>
+                    {proxyCheckPending ? "Testing" : "Test"}
+                  </PixelButton>
+                </div>
+                <PixelInput
+                  value={proxyUsernameDraft || proxyCredentialsDisplay}
+                  onChange={setProxyUsernameDraft}
+                  placeholder="proxy username (optional)"
+                  readOnly={!proxyUsernameDraft && !!proxyCredentialsDisplay}
+                />
+                <PixelInput
+                  value={proxyPasswordDraft}
+                  onChange={setProxyPasswordDraft}
+                  placeholder="proxy password"
+                />
+                <div className="row" style={{ justifyContent: "flex-end" }}>
+                  <PixelButton
+                    onClick={setProxyCredentials}
+                    tone="accent"
+                    disabled={!proxyUsernameDraft.trim()}
+                  >
+                    Save credentials
+                  </PixelButton>
+                  <PixelButton onClick={clearProxyCredentials} tone="danger">
+                    Clear credentials
+                  </PixelButton>
+                </div>
+              </div>
+            </SettingsLine>
+          </div>
+
+          <div className="card">
+            <SettingsLine
+              title="Improvement context"
+              detail="Inputs available to rewriting"
+              panel="context"
+              expandedPanels={expandedSettingsPanels}
+              onTogglePanel={toggleSettingsPanel}
+            >
+              <div className="stack">
+                <div className="settingsInlineToggle">
+                  <span>Recent dictated text</span>
+                  <PixelToggle
+                    value={contextIncludeHistory}
+                    onChange={setContextIncludeHistory}
+                    label="recent dictated text"
+                  />
+                </div>
+                <div className="settingsInlineToggle">
+                  <span>Only text dictated into the same app</span>
+                  <PixelToggle
+                    value={contextHistorySameApp}
+                    onChange={setContextHistorySameApp}
+                    label="only text dictated into the same app"
+                  />
+                </div>
+                <div className="settingsInlineToggle">
+                  <span>Condense older text instead of cutting it</span>
+                  <PixelToggle
+                    value={contextHistorySummarize}
+                    onChange={setContextHistorySummarize}
+                    label="condense older text instead of cutting it"
+                  />
+                </div>
+                <div className="settingsInlineToggle">
+                  <span>Clipboard text</span>
+                  <PixelToggle
+                    value={contextIncludeClipboard}
+                    onChange={setContextIncludeClipboard}
+                    label="clipboard text"
+                  />
+                </div>
+                <div className="settingsInlineToggle">
+                  <span>Current app name and title</span>
+                  <PixelToggle
+                    value={contextIncludePrevWindowMeta}
+                    onChange={setContextIncludePrevWindowMeta}
+                    label="current app name and title"
+                  />
+                </div>
+                <div className="settingsInlineToggle">
+                  <span>Current screen image</span>
+                  <PixelToggle
+                    value={contextIncludePrevWindowScreenshot}
+                    onChange={setContextIncludePrevWindowScreenshot}
+                    label="current screen image"
+                  />
+                </div>
+                <div className="settingsInlineToggle">
+                  <span>Treat context as data only</span>
+                  <PixelToggle
+                    value={contextInjectionGuardEnabled}
+                    onChange={setContextInjectionGuardEnabled}
+                    label="treat context as data only"
+                  />
+                </div>
+                <div className="row" style={{ justifyContent: "flex-end" }}>
+                  <PixelButton onClick={saveContextConfig} tone="accent">
+                    Save
+                  </PixelButton>
+                </div>
+              </div>
+            </SettingsLine>
+          </div>
+
+          <div className="card">
+            <SettingsLine
+              title="Export"
+              detail={autoPasteEnabled ? "Auto paste on" : "Auto paste off"}
+              panel="export"
+              expandedPanels={expandedSettingsPanels}
+              onTogglePanel={toggleSettingsPanel}
+              control={
+                <PixelToggle
+                  value={autoPasteEnabled}
+                  onChange={setAutoPasteEnabled}
+                  label="auto paste"
+                />
+              }
+            >
+              <div className="stack">
+                <div className="muted">Use platform APIs to paste automatically.</div>
+                <div className="row">
+                  <span className="muted" style={{ flex: 1 }}>
+                    Paste into apps running as administrator (asks for UAC consent each time)
+                  </span>
+                  <PixelToggle
+                    value={elevatedHelperEnabled}
+                    onChange={setElevatedHelperEnabled}
+                    label="elevated helper"
+                  />
+                </div>
+                {!autoPasteEnabled ? (
+                  <SliderField
+                    label="Clear Clipboard After (0 = never)"
+                    min={0}
+                    max={600}
+                    step={5}
+                    value={clipboardAutoClearSecs}
+                    suffix="s"
+                    onChange={setClipboardAutoClearSecs}
+                  />
+                ) : null}
+                <div className="row" style={{ justifyContent: "flex-end" }}>
+                  <PixelButton onClick={saveExportConfig} tone="accent">
+                    Save
+                  </PixelButton>
+                </div>
+              </div>
+            </SettingsLine>
+          </div>
+
+          <div className="card">
+            <SettingsLine
+              title="Export audit"
+              detail={auditEnabled ? "Logging exports" : "Off"}
+              panel="audit"
+              expandedPanels={expandedSettingsPanels}
+              onTogglePanel={toggleSettingsPanel}
+              control={<PixelToggle value={auditEnabled} onChange={setAuditEnabled} label="audit log" />}
+            >
+              <div className="stack">
+                <div className="muted">
+                  Records when text was exported and to which app. Only a hash and length are kept unless full text is on.
+                </div>
+                <div className="row">
+                  <span className="muted" style={{ flex: 1 }}>
+                    Store full exported text
+                  </span>
+                  <PixelToggle value={auditIncludeText} onChange={setAuditIncludeText} label="store full text" />
+                </div>
+                <PixelInput
+                  value={auditRetentionDays}
+                  onChange={setAuditRetentionDays}
+                  placeholder="keep entries for N days (0 = no limit)"
+                />
                 <PixelInput
-                  value={asrPreprocessEndMs}
-                  onChange={setAsrPreprocessEndMs}
-                  placeholder="300"
+                  value={auditMaxEntries}
+                  onChange={setAuditMaxEntries}
+                  placeholder="keep at most N entries (0 = no limit)"
                 />
+                {auditEntries.map((entry) => (
+                  <div key={entry.id} className="muted">
+                    {new Date(entry.ts_ms).toLocaleString()}  {entry.export_mode}  {entry.process_image || "unknown app"}
+                    {entry.window_title ? ` — ${entry.window_title}` : ""}  {entry.char_count} chars
+                  </div>
+                ))}
+                <div className="row" style={{ justifyContent: "flex-end" }}>
+                  <PixelButton onClick={loadAuditEntries}>Show recent</PixelButton>
+                  <PixelButton onClick={clearAuditLog} tone="danger">
+                    Clear log
+                  </PixelButton>
+                  <PixelButton onClick={saveAuditConfig} tone="accent">
+                    Save
+                  </PixelButton>
+                </div>
               </div>
-              <div className="row" style={{ justifyContent: "flex-end" }}>
-                <PixelButton onClick={savePreprocessConfig} tone="accent">
-                  Save
-                </PixelButton>
-              </div>
-            </div>
-          </SettingsLine>
+            </SettingsLine>
           </div>
 
           <div className="card">
             <SettingsLine
-              title="Rewrite"
-              detail={rewriteEnabled ? "On" : "Off"}
-              panel="rewrite"
+              title="Keep recordings"
+              detail={keepRecordings ? `Kept as ${recordingFormat.toUpperCase()}` : "Off"}
+              panel="keepRecordings"
               expandedPanels={expandedSettingsPanels}
               onTogglePanel={toggleSettingsPanel}
-              control={<PixelToggle value={rewriteEnabled} onChange={setRewriteEnabled} label="rewrite" />}
+              control={<PixelToggle value={keepRecordings} onChange={setKeepRecordings} label="keep recordings" />}
             >
               <div className="stack">
-                <PixelTextarea
-                  value={llmPrompt}
-                  onChange={setLlmPrompt}
-                  placeholder="LLM prompt..."
-                  rows={10}
-                />
+                <div className="muted">
+                  Keeps each task's audio after transcription. FLAC and Opus are converted in the background; if that
+                  fails the WAV is kept instead.
+                </div>
+                <PixelSelect value={recordingFormat} onChange={setRecordingFormat} options={RECORDING_FORMATS} />
                 <div className="row" style={{ justifyContent: "flex-end" }}>
-                  <PixelButton onClick={saveRewrite} tone="accent">
+                  <PixelButton onClick={saveRecordingRetentionConfig} tone="accent">
                     Save
                   </PixelButton>
                 </div>
@@ -926,31 +2750,33 @@ export function SettingsScreen({
 
           <div className="card">
             <SettingsLine
-              title="Glossary"
-              detail="One term per line"
-              panel="glossary"
+              title="Markdown notes"
+              detail={markdownNotesEnabled ? "Appending to daily note" : "Off"}
+              panel="markdownNotes"
               expandedPanels={expandedSettingsPanels}
               onTogglePanel={toggleSettingsPanel}
               control={
-                <PixelToggle
-                  value={rewriteIncludeGlossary}
-                  onChange={setRewriteIncludeGlossary}
-                  label="rewrite glossary"
-                />
+                <PixelToggle value={markdownNotesEnabled} onChange={setMarkdownNotesEnabled} label="markdown notes" />
               }
             >
               <div className="stack">
                 <div className="muted">
-                  每行一个词；空行会自动忽略。用于 rewrite 阶段作为上下文词汇或术语约束模型遵循。
+                  每次插入完成后把文本追加到文件夹中的当日笔记（YYYY-MM-DD.md，如 Obsidian vault 的 Daily 目录）。可用占位符：
+                  {"{{date}} {{time}} {{text}} {{template}} {{task_id}}"}；留空使用默认的「## 时间」标题格式。
                 </div>
+                <PixelInput
+                  value={markdownNotesFolder}
+                  onChange={setMarkdownNotesFolder}
+                  placeholder="notes folder, e.g. D:\Vault\Daily"
+                />
                 <PixelTextarea
-                  value={rewriteGlossaryDraft}
-                  onChange={setRewriteGlossaryDraft}
-                  placeholder={"比如：QPSK\nTypeScript\nOAuth"}
-                  rows={8}
+                  value={markdownNotesEntryTemplate}
+                  onChange={setMarkdownNotesEntryTemplate}
+                  placeholder={"## {{time}}\n\n{{text}}"}
+                  rows={4}
                 />
                 <div className="row" style={{ justifyContent: "flex-end" }}>
-                  <PixelButton onClick={saveGlossary} tone="accent">
+                  <PixelButton onClick={saveMarkdownNotesConfig} tone="accent">
                     Save
                   </PixelButton>
                 </div>
@@ -960,98 +2786,85 @@ export function SettingsScreen({
 
           <div className="card">
             <SettingsLine
-              title="Hotkeys"
-              detail={hotkeysEnabled ? "On" : "Off"}
-              panel="hotkeys"
+              title="Webhook"
+              detail={webhookEnabled ? "Posting completed tasks" : "Off"}
+              panel="webhook"
               expandedPanels={expandedSettingsPanels}
               onTogglePanel={toggleSettingsPanel}
-              control={<PixelToggle value={hotkeysEnabled} onChange={setHotkeysEnabled} label="hotkeys" />}
+              control={<PixelToggle value={webhookEnabled} onChange={setWebhookEnabled} label="webhook" />}
             >
               <div className="stack">
-                <div className="hotkeyGuide">
-                  <div><span>{hotkeyPrimary}</span><span>short press starts or stops recording</span></div>
-                </div>
-                <div className="stack">
-                  <div className="muted">Primary Key</div>
-                  <PixelSelect
-                    value={hotkeyPrimary}
-                    onChange={setHotkeyPrimary}
-                    options={PRIMARY_HOTKEYS}
-                  />
-                </div>
-                <div className="settingsInlineToggle">
-                  <span>Overlay</span>
-                  <PixelToggle
-                    value={hotkeysShowOverlay}
-                    onChange={setHotkeysShowOverlay}
-                    label="overlay"
-                  />
+                <div className="muted">
+                  插入完成并写入历史后，向该地址 POST 一条 task_done JSON（可接 n8n、Zapier 或自建服务）。失败时按 1s/2s/4s 退避重试，投递结果记录在 metrics。设置密钥后附带
+                  X-TypeVoice-Signature（对「时间戳.正文」做 HMAC-SHA256）。
                 </div>
-                <SliderField
-                  label="Background Depth"
-                  min={0.35}
-                  max={0.95}
-                  step={0.01}
-                  value={overlayBackgroundOpacity}
-                  onChange={setOverlayBackgroundOpacity}
-                />
-                <SliderField
-                  label="Font Size"
-                  min={18}
-                  max={56}
-                  step={1}
-                  value={overlayFontSizePx}
-                  suffix="px"
-                  onChange={setOverlayFontSizePx}
-                />
-                <SliderField
-                  label="Width"
-                  min={360}
-                  max={1600}
-                  step={10}
-                  value={overlayWidthPx}
-                  suffix="px"
-                  onChange={setOverlayWidthPx}
-                />
-                <SliderField
-                  label="Height"
-                  min={72}
-                  max={360}
-                  step={4}
-                  value={overlayHeightPx}
-                  suffix="px"
-                  onChange={setOverlayHeightPx}
+                <PixelInput value={webhookUrl} onChange={setWebhookUrl} placeholder="https://hooks.example.com/typevoice" />
+                <PixelInput
+                  value={webhookFields}
+                  onChange={setWebhookFields}
+                  placeholder="fields, comma separated (empty = all): final_text, asr_text, template_id, rtf"
                 />
                 <div className="row" style={{ justifyContent: "flex-end" }}>
-                  <PixelButton onClick={saveHotkeys} tone="accent">
+                  <PixelButton onClick={saveWebhookConfig} tone="accent">
                     Save
                   </PixelButton>
                 </div>
+                <PixelInput
+                  value={webhookSecretDraft || webhookSecretDisplay}
+                  onChange={setWebhookSecretDraft}
+                  placeholder="signing secret (optional)"
+                  readOnly={!webhookSecretDraft && !!webhookSecretDisplay}
+                />
+                <div className="row" style={{ justifyContent: "flex-end" }}>
+                  <PixelButton onClick={setWebhookSecret} tone="accent" disabled={!webhookSecretDraft.trim()}>
+                    Save secret
+                  </PixelButton>
+                  <PixelButton onClick={clearWebhookSecret} tone="danger">
+                    Clear secret
+                  </PixelButton>
+                </div>
               </div>
             </SettingsLine>
           </div>
-        </div>
 
-        <div className="settingsColumn">
           <div className="card">
             <SettingsLine
-              title="Language model"
-              detail={llmModel.trim() || "Model settings"}
-              panel="llm"
+              title="MQTT"
+              detail={mqttEnabled ? mqttBrokerHost.trim() || "No broker" : "Off"}
+              panel="mqtt"
               expandedPanels={expandedSettingsPanels}
               onTogglePanel={toggleSettingsPanel}
-              control={<PixelSelect value={reasoning} onChange={setReasoning} options={REASONING} />}
+              control={<PixelToggle value={mqttEnabled} onChange={setMqttEnabled} label="mqtt" />}
             >
               <div className="stack">
+                <div className="muted">
+                  向 MQTT broker 发布录音状态，供灯光或忙碌指示器使用：前缀下的 status（online/offline）、state（当前阶段）、recording（ON/OFF）为保留消息，task
+                  为每次完成或失败的 JSON，不含转写文本。
+                </div>
+                <PixelInput value={mqttBrokerHost} onChange={setMqttBrokerHost} placeholder="broker host, e.g. homeassistant.local" />
+                <div className="row">
+                  <PixelInput value={mqttBrokerPort} onChange={setMqttBrokerPort} placeholder={mqttTls ? "port (8883)" : "port (1883)"} />
+                  <PixelToggle value={mqttTls} onChange={setMqttTls} label="tls" />
+                </div>
+                <PixelInput value={mqttTopicPrefix} onChange={setMqttTopicPrefix} placeholder="topic prefix (typevoice)" />
+                <PixelInput value={mqttUsername} onChange={setMqttUsername} placeholder="username (optional)" />
+                <div className="row" style={{ justifyContent: "flex-end" }}>
+                  <PixelButton onClick={saveMqttConfig} tone="accent">
+                    Save
+                  </PixelButton>
+                </div>
                 <PixelInput
-                  value={llmBaseUrl}
-                  onChange={setLlmBaseUrl}
-                  placeholder="API Base URL (e.g. https://api.openai.com/v1)"
+                  value={mqttPasswordDraft || mqttPasswordDisplay}
+                  onChange={setMqttPasswordDraft}
+                  placeholder="password (optional)"
+                  readOnly={!mqttPasswordDraft && !!mqttPasswordDisplay}
                 />
-                <PixelInput value={llmModel} onChange={setLlmModel} placeholder="Model" />
                 <div className="row" style={{ justifyContent: "flex-end" }}>
-                  <PixelButton onClick={saveLlm} tone="accent">
-                    Save
+                  <PixelButton onClick={setMqttPassword} tone="accent" disabled={!mqttPasswordDraft}>
+                    Save password
+                  </PixelButton>
+                  <PixelButton onClick={clearMqttPassword} tone="danger">
+                    Clear password
                   </PixelButton>
                 </div>
               </div>
@@ -1060,28 +2873,32 @@ export function SettingsScreen({
 
           <div className="card">
             <SettingsLine
-              title="API key"
-              detail="Stored in keyring or environment"
-              panel="llmKey"
+              title="Daily summary"
+              detail={dailySummaryEnabled ? `Digest at ${dailySummaryTime.trim() || "18:00"}` : "Off"}
+              panel="dailySummary"
               expandedPanels={expandedSettingsPanels}
               onTogglePanel={toggleSettingsPanel}
+              control={
+                <PixelToggle value={dailySummaryEnabled} onChange={setDailySummaryEnabled} label="daily summary" />
+              }
             >
               <div className="stack">
-                <PixelInput
-                  value={keyDraft || llmKeyDisplay}
-                  onChange={setKeyDraft}
-                  placeholder="LLM API key not configured"
-                  readOnly={!keyDraft && !!llmKeyDisplay}
+                <div className="muted">
+                  每天到设定时间后用 LLM 汇总当天的听写记录，写入数据目录下的 summaries/YYYY-MM-DD.md。应用未运行的日子不会补写；提示词留空使用默认摘要指令。
+                </div>
+                <PixelInput value={dailySummaryTime} onChange={setDailySummaryTime} placeholder="time (18:00)" />
+                <PixelTextarea
+                  value={dailySummaryPrompt}
+                  onChange={setDailySummaryPrompt}
+                  placeholder="summary prompt (optional)"
+                  rows={4}
                 />
                 <div className="row" style={{ justifyContent: "flex-end" }}>
-                  <PixelButton onClick={setApiKey} tone="accent" disabled={!keyDraft.trim()}>
-                    Save
-                  </PixelButton>
-                  <PixelButton onClick={clearApiKey} tone="danger">
-                    Clear
+                  <PixelButton onClick={runDailySummaryNow} disabled={dailySummaryPending}>
+                    {dailySummaryPending ? "Running" : "Run now"}
                   </PixelButton>
-                  <PixelButton onClick={checkApiKey} disabled={llmCheckPending}>
-                    {llmCheckPending ? "Checking" : "Check"}
+                  <PixelButton onClick={saveDailySummaryConfig} tone="accent">
+                    Save
                   </PixelButton>
                 </div>
               </div>
@@ -1090,48 +2907,32 @@ export function SettingsScreen({
 
           <div className="card">
             <SettingsLine
-              title="Improvement context"
-              detail="Inputs available to rewriting"
-              panel="context"
+              title="Workspace"
+              detail={settings?.active_workspace || "default"}
+              panel="workspace"
               expandedPanels={expandedSettingsPanels}
               onTogglePanel={toggleSettingsPanel}
             >
               <div className="stack">
-                <div className="settingsInlineToggle">
-                  <span>Recent dictated text</span>
-                  <PixelToggle
-                    value={contextIncludeHistory}
-                    onChange={setContextIncludeHistory}
-                    label="recent dictated text"
-                  />
-                </div>
-                <div className="settingsInlineToggle">
-                  <span>Clipboard text</span>
-                  <PixelToggle
-                    value={contextIncludeClipboard}
-                    onChange={setContextIncludeClipboard}
-                    label="clipboard text"
-                  />
-                </div>
-                <div className="settingsInlineToggle">
-                  <span>Current app name and title</span>
-                  <PixelToggle
-                    value={contextIncludePrevWindowMeta}
-                    onChange={setContextIncludePrevWindowMeta}
-                    label="current app name and title"
-                  />
+                <div className="muted">
+                  每个工作区有独立的历史记录与模板（改写提示词及按模板区分的输出/导出/笔记格式）。新工作区从当前模板复制；default 使用数据目录根下的历史。
                 </div>
-                <div className="settingsInlineToggle">
-                  <span>Current screen image</span>
-                  <PixelToggle
-                    value={contextIncludePrevWindowScreenshot}
-                    onChange={setContextIncludePrevWindowScreenshot}
-                    label="current screen image"
-                  />
+                <div className="row">
+                  {workspaces.map((w) => (
+                    <PixelButton
+                      key={w.name}
+                      onClick={() => switchWorkspace(w.name)}
+                      tone={w.active ? "accent" : "default"}
+                      disabled={w.active}
+                    >
+                      {w.name}
+                    </PixelButton>
+                  ))}
                 </div>
+                <PixelInput value={workspaceDraft} onChange={setWorkspaceDraft} placeholder="new workspace, e.g. client-a" />
                 <div className="row" style={{ justifyContent: "flex-end" }}>
-                  <PixelButton onClick={saveContextConfig} tone="accent">
-                    Save
+                  <PixelButton onClick={() => switchWorkspace(workspaceDraft)} tone="accent" disabled={!workspaceDraft.trim()}>
+                    Create & switch
                   </PixelButton>
                 </div>
               </div>
@@ -1140,25 +2941,40 @@ export function SettingsScreen({
 
           <div className="card">
             <SettingsLine
-              title="Export"
-              detail={autoPasteEnabled ? "Auto paste on" : "Auto paste off"}
-              panel="export"
+              title="Permissions"
+              detail={permissionsSummary(permissions)}
+              panel="permissions"
               expandedPanels={expandedSettingsPanels}
               onTogglePanel={toggleSettingsPanel}
-              control={
-                <PixelToggle
-                  value={autoPasteEnabled}
-                  onChange={setAutoPasteEnabled}
-                  label="auto paste"
-                />
-              }
             >
               <div className="stack">
-                <div className="muted">Use platform APIs to paste automatically.</div>
+                <div className="muted">
+                  macOS 需要分别授权麦克风（录音）、辅助功能（自动粘贴与快捷键）和屏幕录制（上下文截图）。屏幕录制在系统设置中开启后需重启应用。
+                </div>
+                {permissions.map((p) => (
+                  <div key={p.kind} className="row" style={{ justifyContent: "space-between" }}>
+                    <div>
+                      <div>{PERMISSION_LABELS[p.kind]}</div>
+                      <div className="muted">
+                        {p.state.replace("_", " ")}
+                        {p.step !== "done" ? ` · ${p.denied_code}` : ""}
+                      </div>
+                    </div>
+                    {p.step === "request" ? (
+                      <PixelButton onClick={() => requestPermission(p.kind)} tone="accent">
+                        Request
+                      </PixelButton>
+                    ) : p.step === "open_settings" ? (
+                      <PixelButton onClick={() => openPermissionSettings(p.kind)} tone="accent">
+                        Open Settings
+                      </PixelButton>
+                    ) : p.step === "restart" ? (
+                      <div className="muted">Restart TypeVoice</div>
+                    ) : null}
+                  </div>
+                ))}
                 <div className="row" style={{ justifyContent: "flex-end" }}>
-                  <PixelButton onClick={saveExportConfig} tone="accent">
-                    Save
-                  </PixelButton>
+                  <PixelButton onClick={() => refreshPermissions()}>Recheck</PixelButton>
                 </div>
               </div>
             </SettingsLine>
@@ -1172,7 +2988,34 @@ export function SettingsScreen({
               expandedPanels={expandedSettingsPanels}
               onTogglePanel={toggleSettingsPanel}
             >
+              <div className="stack">
+                <div className="muted">Times in history and history exports, in this computer's time zone.</div>
+                <PixelSelect
+                  value={timeFormat}
+                  onChange={(v) => {
+                    setTimeFormat(v);
+                    void persistSettingsPatch({ time_format: v }, "TIME FORMAT SAVED");
+                  }}
+                  options={TIME_FORMATS}
+                />
+              </div>
+              <div className="stack">
+                <div className="muted">
+                  Backup writes settings, templates and the history of every workspace to a new folder; restore
+                  replaces them from such a folder. API keys are not included.
+                </div>
+                <PixelInput value={backupPath} onChange={setBackupPath} placeholder="backup folder path" />
+              </div>
               <div className="row" style={{ justifyContent: "flex-end" }}>
+                <PixelButton onClick={backupData} disabled={backupPending || !backupPath.trim()}>
+                  Back up
+                </PixelButton>
+                <PixelButton
+                  onClick={() => setConfirmRestore(true)}
+                  disabled={backupPending || !backupPath.trim()}
+                >
+                  Restore
+                </PixelButton>
                 <PixelButton onClick={() => setConfirmClear(true)} tone="danger">
                   Clear all
                 </PixelButton>
@@ -1200,10 +3043,91 @@ export function SettingsScreen({
           <div className="muted">This action cannot be undone.</div>
         </div>
       </PixelDialog>
+
+      <PixelDialog
+        open={confirmRestore}
+        title="Restore data"
+        onClose={() => setConfirmRestore(false)}
+        actions={
+          <>
+            <PixelButton onClick={() => setConfirmRestore(false)}>Cancel</PixelButton>
+            <PixelButton onClick={restoreData} tone="danger">
+              Restore
+            </PixelButton>
+          </>
+        }
+      >
+        <div className="stack">
+          <div>Settings, templates and history will be replaced from {backupPath.trim()}.</div>
+          <div className="muted">The current data is backed up under backups/ in the data folder first.</div>
+        </div>
+      </PixelDialog>
+
+      <PixelDialog
+        open={confirmBugReport}
+        title="Record bug report"
+        onClose={() => setConfirmBugReport(false)}
+        actions={
+          <>
+            <PixelButton onClick={() => setConfirmBugReport(false)}>Cancel</PixelButton>
+            <PixelButton onClick={recordBugReport} tone="accent">
+              Record 8s
+            </PixelButton>
+          </>
+        }
+      >
+        <div className="stack">
+          <div>
+            Records 8 seconds from the microphone and runs it through transcription and rewrite
+            with full debug capture.
+          </div>
+          <div className="muted">
+            The bundle holds the recording, the ASR response, the rewrite prompt and reply, the
+            task's trace events and a settings summary. It stays on this computer until you
+            share it.
+          </div>
+          <PixelInput
+            value={bugReportNote}
+            onChange={setBugReportNote}
+            placeholder="what went wrong (optional)"
+          />
+        </div>
+      </PixelDialog>
     </div>
   );
 }
 
+function hotkeyBackendLabel(status: HotkeyBackendStatus): string {
+  if (status.backend === "xdg_portal") {
+    return "Wayland: shortcut is registered through the desktop portal; confirm or rebind it in the system dialog.";
+  }
+  if (status.backend === "windows_hook") return "Windows keyboard hook";
+  return "Global hotkeys are unavailable on this system";
+}
+
+const PERMISSION_LABELS: Record<PermissionKind, string> = {
+  microphone: "Microphone",
+  accessibility: "Accessibility",
+  screen_recording: "Screen Recording",
+};
+
+function permissionsSummary(list: PermissionStatus[]): string {
+  const pending = list.filter((p) => p.step !== "done").length;
+  if (!list.length) return "Unknown";
+  return pending ? `${pending} need attention` : "All set";
+}
+
+function postprocessorsSummary(draft: string): string {
+  try {
+    const parsed = JSON.parse(draft);
+    if (!Array.isArray(parsed)) return "Invalid";
+    const enabled = parsed.filter((p) => p && p.enabled !== false).length;
+    return enabled === 0 ? "None" : `${enabled} enabled`;
+  } catch {
+    return "Invalid";
+  }
+}
+
 function normalizePrimaryHotkey(value: string | null | undefined): string {
   const raw = (value || "").trim();
   const found = PRIMARY_HOTKEYS.find((item) => item.value.toLowerCase() === raw.toLowerCase());
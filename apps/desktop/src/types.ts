@@ -9,14 +9,44 @@ export type TaskEvent = {
   step_id?: string | null;
 };
 
+export type UiEventKind =
+  | "transcription.stage"
+  | "transcription.partial"
+  | "transcription.completed"
+  | "transcription.empty"
+  | "rewrite.completed"
+  | "insertion.completed"
+  | "diagnostic.error"
+  | "diagnostic.warning"
+  | "workflow.state"
+  | "workflow.task.failed"
+  | "workflow.task.cancelled"
+  | "audio.level";
+
+export type TaskStage =
+  | "Record"
+  | "Transcribe"
+  | "Rewrite"
+  | "RewriteBudget"
+  | "RewriteChunk"
+  | "Insert"
+  | "Retain"
+  | "Sink"
+  | "Persist"
+  | "Workflow";
+
+export type UiEventStatus = "started" | "completed" | "failed" | "cancelled" | "recording";
+
 export type UiEvent = {
-  kind: string;
+  kind: UiEventKind;
+  // Bumped when a payload shape changes incompatibly.
+  version: number;
   effect?: "displayOnly" | "stateChanging" | null;
   eventId?: string | null;
   sequence?: number | null;
   taskId?: string | null;
-  stage?: string | null;
-  status?: "started" | "completed" | "failed" | "cancelled" | "recording" | null;
+  stage?: TaskStage | null;
+  status?: UiEventStatus | null;
   message: string;
   elapsedMs?: number | null;
   errorCode?: string | null;
@@ -77,6 +107,8 @@ export type RewriteResult = {
   transcriptId: string;
   finalText: string;
   rewriteMs: number;
+  outputErrorCode?: string | null;
+  templateId?: string | null;
 };
 
 export type InsertResult = {
@@ -87,12 +119,20 @@ export type InsertResult = {
   errorMessage?: string | null;
 };
 
+export type ExportTargetLock = {
+  hwnd: number;
+  title?: string | null;
+  processImage?: string | null;
+  lockedAtMs: number;
+};
+
 export type WorkflowCommand = "primary" | "rewriteLast" | "insertLast" | "copyLast" | "cancel";
 
 export type WorkflowView = {
   phase: string;
   taskId?: string | null;
   recordingSessionId?: string | null;
+  backgroundTaskIds: string[];
   lastTranscriptId?: string | null;
   lastAsrText: string;
   lastText: string;
@@ -104,6 +144,7 @@ export type WorkflowView = {
   canRewrite: boolean;
   canInsert: boolean;
   canCopy: boolean;
+  replayedTaskId?: string | null;
 };
 
 export type TaskDone = {
@@ -126,43 +167,117 @@ export type ExportTextResult = {
   error_message?: string | null;
 };
 
+export type RecordDeviceOverride = {
+  gain_db?: number | null;
+  extra_ffmpeg_args?: string[] | null;
+};
+
+export type TemplateContextSections = {
+  history?: boolean | null;
+  clipboard?: boolean | null;
+  prev_window_meta?: boolean | null;
+  screenshot?: boolean | null;
+};
+
 export type Settings = {
   asr_provider?: string | null;
   remote_asr_url?: string | null;
   remote_asr_model?: string | null;
   remote_asr_concurrency?: number | null;
+  asr_warm_pool_enabled?: boolean | null;
+  asr_warm_pool_models?: string[] | null;
+  asr_warm_pool_max_models?: number | null;
+  asr_warm_pool_vram_budget_mb?: number | null;
+  asr_warm_pool_model_vram_mb?: Record<string, number> | null;
+  asr_warm_pool_keepalive_secs?: number | null;
+  remote_asr_live_preview?: boolean | null;
   asr_preprocess_silence_trim_enabled?: boolean | null;
   asr_preprocess_silence_threshold_db?: number | null;
   asr_preprocess_silence_start_ms?: number | null;
   asr_preprocess_silence_end_ms?: number | null;
+  asr_preprocess_gain_db?: number | null;
+  asr_preprocess_warm_spare_enabled?: boolean | null;
+  asr_preprocess_native_wav_enabled?: boolean | null;
+  proxy_url?: string | null;
+  proxy_no_proxy?: string[] | null;
+  tls_ca_bundle_path?: string | null;
+  tls_pinned_sha256?: string | null;
+  http_connect_timeout_ms?: number | null;
+  http_request_timeout_ms?: number | null;
+  offline_mode?: boolean | null;
   llm_base_url?: string | null;
   llm_model?: string | null;
   llm_reasoning_effort?: string | null;
+  llm_capture_enabled?: boolean | null;
+  llm_capture_max_entries?: number | null;
+  gpu_coordination_enabled?: boolean | null;
+  gpu_asr_unload_url?: string | null;
+  gpu_llm_unload_url?: string | null;
   llm_prompt?: string | null;
+  rewrite_system_preamble?: string | null;
+  rewrite_template_id?: string | null;
+  rewrite_output_format?: "text" | "json_schema" | null;
+  rewrite_output_format_by_template?: Record<string, string> | null;
+  rewrite_output_json_schema?: string | null;
+  rewrite_output_json_schema_by_template?: Record<string, string> | null;
   record_input_spec?: string | null;
   record_input_strategy?: string | null;
   record_follow_default_role?: string | null;
   record_fixed_endpoint_id?: string | null;
   record_fixed_friendly_name?: string | null;
+  record_device_preference?: string[] | null;
+  record_device_overrides?: Record<string, RecordDeviceOverride> | null;
+  record_avoid_low_quality_profile?: boolean | null;
+  record_backend?: "native" | "ffmpeg" | null;
+  record_vad_enabled?: boolean | null;
+  record_vad_silence_ms?: number | null;
   record_last_working_endpoint_id?: string | null;
   record_last_working_friendly_name?: string | null;
   record_last_working_dshow_spec?: string | null;
   record_last_working_ts_ms?: number | null;
   rewrite_enabled?: boolean | null;
   rewrite_glossary?: string[] | null;
+  pipeline_max_background_rewrites?: number | null;
+  task_stall_timeout_ms?: number | null;
+  rewrite_chunk_threshold_tokens?: number | null;
+  rewrite_chunk_tokens?: number | null;
+  rewrite_cache_enabled?: boolean | null;
+  rewrite_token_cap?: number | null;
   auto_paste_enabled?: boolean | null;
+  export_elevated_helper_enabled?: boolean | null;
+  clipboard_auto_clear_secs?: number | null;
+  export_format?: string | null;
+  export_format_by_template?: Record<string, string> | null;
+  export_targets_by_template?: Record<string, string[]> | null;
+  audit_log_enabled?: boolean | null;
+  audit_log_include_text?: boolean | null;
+  keep_recordings?: boolean | null;
+  recording_format?: string | null;
+  time_format?: "24h" | "12h" | "iso" | null;
+  audit_log_retention_days?: number | null;
+  audit_log_max_entries?: number | null;
+  text_casing?: string | null;
+  text_casing_by_mode?: Record<string, string> | null;
+  text_normalize_enabled?: boolean | null;
+  text_normalize_locales?: string[] | null;
   rewrite_include_glossary?: boolean | null;
 
   context_include_history?: boolean | null;
   context_history_n?: number | null;
   context_history_window_ms?: number | null;
+  context_history_same_app?: boolean | null;
+  context_history_summarize?: boolean | null;
   context_include_clipboard?: boolean | null;
   context_include_prev_window_screenshot?: boolean | null;
+  context_sections_by_template?: Record<string, TemplateContextSections> | null;
   context_include_prev_window_meta?: boolean | null;
+  context_injection_guard_enabled?: boolean | null;
   llm_supports_vision?: boolean | null;
 
   hotkeys_enabled?: boolean | null;
   hotkey_primary?: string | null;
+  hotkey_debounce_ms?: number | null;
+  hotkey_app_allowlist?: string[] | null;
   hotkeys_show_overlay?: boolean | null;
   overlay_background_opacity?: number | null;
   overlay_font_size_px?: number | null;
@@ -170,8 +285,83 @@ export type Settings = {
   overlay_height_px?: number | null;
   overlay_position_x?: number | null;
   overlay_position_y?: number | null;
+  overlay_status_colors?: Record<string, string> | null;
+  overlay_font_scale?: number | null;
+  overlay_status_language?: string | null;
+
+  captions_enabled?: boolean | null;
+  a11y_announcements?: boolean | null;
+  capture_indicator_enforced?: boolean | null;
+  screen_share_guard_enabled?: boolean | null;
+  screen_share_extra_processes?: string[] | null;
+  low_power_mode?: "auto" | "on" | "off" | null;
+  low_power_asr_provider?: "doubao" | "remote" | null;
+  low_power_remote_asr_model?: string | null;
+  captions_font_size_px?: number | null;
+  captions_background_opacity?: number | null;
+  captions_monitor_index?: number | null;
+
+  wake_word_enabled?: boolean | null;
+  wake_word_phrase?: string | null;
+  wake_word_sensitivity?: number | null;
+  wake_word_detector_command?: string | null;
+
+  postprocessors?: PostprocessorConfig[] | null;
+
+  markdown_notes_enabled?: boolean | null;
+  markdown_notes_folder?: string | null;
+  markdown_notes_entry_template?: string | null;
+  markdown_notes_entry_template_by_template?: Record<string, string> | null;
+
+  webhook_enabled?: boolean | null;
+  webhook_url?: string | null;
+  webhook_fields?: string[] | null;
+
+  mqtt_enabled?: boolean | null;
+  mqtt_broker_host?: string | null;
+  mqtt_broker_port?: number | null;
+  mqtt_tls?: boolean | null;
+  mqtt_topic_prefix?: string | null;
+  mqtt_username?: string | null;
+
+  daily_summary_enabled?: boolean | null;
+  daily_summary_time?: string | null;
+  daily_summary_prompt?: string | null;
+
+  // Changed only through switch_workspace.
+  active_workspace?: string | null;
 };
 
+export type PostprocessorConfig = {
+  id: string;
+  kind: "command" | "wasm";
+  path: string;
+  args?: string[] | null;
+  enabled?: boolean | null;
+  timeout_ms?: number | null;
+};
+
+export type SettingsFieldError = {
+  field: string;
+  code: string;
+  message: string;
+};
+
+export type FormattingProfile = {
+  id: string;
+  name: string;
+  enabled: boolean;
+  process_names: string[];
+  strip_trailing_newline: boolean;
+  wrap_columns?: number | null;
+  line_prefix?: string | null;
+  greeting?: string | null;
+  sign_off?: string | null;
+  terminal_paste?: TerminalPasteMode | null;
+};
+
+export type TerminalPasteMode = "clipboard_shortcut" | "bracketed_paste" | "typed";
+
 export type OverlayConfig = {
   background_opacity: number;
   font_size_px: number;
@@ -181,6 +371,281 @@ export type OverlayConfig = {
   position_y?: number | null;
 };
 
+export type OverlayStatusStyle = {
+  label: string;
+  color: string;
+};
+
+export type OverlayTheme = {
+  background_opacity: number;
+  font_scale: number;
+  language: string;
+  statuses: Record<string, OverlayStatusStyle>;
+};
+
+export type LlmCapture = {
+  ts_ms: number;
+  task_id: string;
+  url: string;
+  model: string;
+  request: unknown;
+  status?: number | null;
+  response?: string | null;
+  error?: string | null;
+  response_truncated: boolean;
+};
+
+export type CaptureSource =
+  | "recorder"
+  | "mic_test"
+  | "noise_calibration"
+  | "wake_word"
+  | "bug_report";
+
+export type CaptureIndicatorState = {
+  sources: CaptureSource[];
+};
+
+export type CaptionsConfig = {
+  enabled: boolean;
+  font_size_px: number;
+  background_opacity: number;
+  monitor_index?: number | null;
+};
+
+export type CaptionsState = {
+  visible: boolean;
+  text: string;
+  ts_ms: number;
+};
+
+export type CaptionsMonitor = {
+  index: number;
+  name?: string | null;
+  width: number;
+  height: number;
+  scale_factor: number;
+};
+
+export type WakeWordStatus = {
+  state: "off" | "listening" | "triggered" | "error";
+  phrase?: string | null;
+  detail?: string | null;
+  ts_ms: number;
+};
+
+export type NoiseProfile = {
+  duration_ms: number;
+  noise_floor_db: number;
+  rms_db: number;
+  peak_db: number;
+  suggested_silence_threshold_db: number;
+  suggested_gain_db: number;
+};
+
+export type BugReportResult = {
+  task_id: string;
+  path: string;
+  bytes: number;
+  transcript?: string | null;
+  asr_error_code?: string | null;
+  rewrite_error_code?: string | null;
+};
+
+export type MicTestResult = {
+  input_spec: string;
+  duration_ms: number;
+  rms_db: number;
+  peak_db: number;
+  noise_floor_db: number;
+  level: "ok" | "silent" | "clipping";
+  provider: string;
+  transcript?: string | null;
+  asr_ms?: number | null;
+  asr_error_code?: string | null;
+  asr_error_message?: string | null;
+};
+
+export type AlignedSegment = {
+  changed: boolean;
+  a: string;
+  b: string;
+};
+
+export type AsrCompareRun = {
+  profile: string;
+  model_id?: string | null;
+  text?: string | null;
+  rtf?: number | null;
+  asr_ms?: number | null;
+  error_code?: string | null;
+  error_message?: string | null;
+};
+
+export type AsrCompareResult = {
+  source_id: string;
+  audio_seconds: number;
+  a: AsrCompareRun;
+  b: AsrCompareRun;
+  diff: AlignedSegment[];
+};
+
+export type RewriteVariant = {
+  templateId?: string | null;
+  baseUrl?: string | null;
+  model?: string | null;
+  reasoningEffort?: string | null;
+};
+
+export type RewriteCompareRun = {
+  template_id?: string | null;
+  model?: string | null;
+  text?: string | null;
+  rewrite_ms?: number | null;
+  prompt_tokens?: number | null;
+  completion_tokens?: number | null;
+  tokens_estimated: boolean;
+  output_error_code?: string | null;
+  error_code?: string | null;
+  error_message?: string | null;
+};
+
+export type RewriteCompareResult = {
+  task_id: string;
+  asr_text: string;
+  runs: RewriteCompareRun[];
+  artifact?: string | null;
+};
+
+export type DataDirLockOwner = {
+  instance_id: string;
+  pid: number;
+  user: string;
+  host: string;
+  acquired_at_ms: number;
+  heartbeat_ms: number;
+};
+
+export type SettingsConflict = {
+  kind: "merged" | "overwritten" | "invalid";
+  message?: string | null;
+};
+
+export type CrashReport = {
+  count: number;
+  first_ts_ms: number;
+  last_ts_ms: number;
+  message: string;
+  log_path: string;
+};
+
+export type BackupManifest = {
+  format: string;
+  format_version: number;
+  app_version: string;
+  created_at_ms: number;
+  settings: boolean;
+  workspaces: { name: string; history: boolean; templates: boolean }[];
+};
+
+export type BackupResult = {
+  path: string;
+  manifest: BackupManifest;
+};
+
+export type RestoreResult = {
+  settings: boolean;
+  workspaces: string[];
+  pre_restore_backup: string;
+};
+
+export type QuarantinedFile = {
+  kind: "settings" | "templates" | "history";
+  path: string;
+  quarantined_to: string;
+  reason: string;
+};
+
+export type RecoveryReport = {
+  ts_ms: number;
+  corrupt_dir: string;
+  files: QuarantinedFile[];
+};
+
+export type DataDirLockStatus = {
+  held: boolean;
+  owner?: DataDirLockOwner | null;
+  error?: string | null;
+};
+
+export type AudioLevel = {
+  version: number;
+  recordingId: string;
+  rmsDbfs: number;
+  peakDbfs: number;
+  tsMs: number;
+};
+
+export type RecordingAutoStopped = {
+  taskId: string;
+  recordingSessionId: string;
+  silenceMs: number;
+};
+
+export type PowerStatus = {
+  mode: "auto" | "on" | "off";
+  on_battery?: boolean | null;
+  active?: "manual" | "battery" | null;
+};
+
+export type WarmPoolMember = {
+  model: string;
+  vram_mb: number;
+  reason: "active" | "recent" | "configured";
+};
+
+export type WarmPoolStatus = {
+  enabled: boolean;
+  paused?: string | null;
+  plan: {
+    members: WarmPoolMember[];
+    skipped: { model: string; vram_mb: number; reason: "max_models" | "vram_budget" }[];
+    vram_mb: number;
+  };
+  warmed: Record<
+    string,
+    { at_ms: number; source: string; elapsed_ms?: number | null; error?: string | null }
+  >;
+};
+
+export type HotkeyBackendStatus = {
+  backend: "windows_hook" | "xdg_portal" | "none";
+  available: boolean;
+  reason?: string | null;
+  reason_code?: string | null;
+};
+
+export type PermissionKind = "microphone" | "accessibility" | "screen_recording";
+
+export type PermissionStatus = {
+  kind: PermissionKind;
+  state: "granted" | "denied" | "not_determined" | "not_required";
+  step: "done" | "request" | "open_settings" | "restart";
+  denied_code: string;
+  affected_codes: string[];
+};
+
+export type WorkspaceInfo = {
+  name: string;
+  active: boolean;
+};
+
+export type DailySummaryResult = {
+  date: string;
+  dictations: number;
+  path?: string | null;
+};
+
 export type AudioCaptureDevice = {
   endpoint_id: string;
   friendly_name: string;
@@ -188,6 +653,15 @@ export type AudioCaptureDevice = {
   is_default_console: boolean;
 };
 
+export type AudioDevicesChanged = {
+  event_type: string;
+  endpoint_id: string;
+  flow?: string | null;
+  role?: string | null;
+  cache_invalidated: boolean;
+  ts_ms: number;
+};
+
 export type ApiKeyStatus = {
   configured: boolean;
   source: string;
@@ -208,6 +682,25 @@ export type RuntimeToolchainStatus = {
   expected_version: string;
 };
 
+export type AuditEntry = {
+  id: number;
+  ts_ms: number;
+  task_id?: string | null;
+  process_image?: string | null;
+  window_title?: string | null;
+  export_mode: "copy" | "paste" | "paste_failed";
+  export_format: string;
+  text_sha256: string;
+  char_count: number;
+  text?: string | null;
+};
+
+export type RewritePreview = {
+  system_prompt: string;
+  user_text: string;
+  has_preamble: boolean;
+};
+
 export type HistoryItem = {
   task_id: string;
   created_at_ms: number;
@@ -220,4 +713,93 @@ export type HistoryItem = {
   device_used: string;
   preprocess_ms: number;
   asr_ms: number;
+  target_app?: string | null;
+  target_title_hash?: string | null;
+  tags?: string[];
+  note?: string;
+  // Local time with the offset in force at that instant; history_list only.
+  created_at_iso?: string;
+  created_at_display?: string;
+};
+
+export type HistoryFilter = {
+  from_ms?: number | null;
+  to_ms?: number | null;
+  tag?: string | null;
+  target_app?: string | null;
+  template_id?: string | null;
+};
+
+export type HistoryBulkAction =
+  | { action: "delete" }
+  | { action: "export"; path: string }
+  | { action: "tag"; add?: string[]; remove?: string[] };
+
+export type HistoryBulkProgress = {
+  action: "delete" | "export" | "tag";
+  done: number;
+  total: number;
+};
+
+export type HistoryBulkResult = {
+  action: "delete" | "export" | "tag";
+  matched: number;
+  affected: number;
+};
+
+export type DroppedRecords = {
+  trace: number;
+  trace_payload: number;
+  metrics: number;
+  queue_capacity: number;
+};
+
+export type ErrorExplanation = {
+  code: string;
+  matched: string;
+  title: string;
+  summary: string;
+  causes: string[];
+  remediation: string[];
+};
+
+export type CorrectionSuggestion = {
+  heard: string;
+  corrected: string;
+  occurrences: number;
+  last_seen_ms: number;
+  in_glossary: boolean;
+};
+
+export type AccuracyBucket = {
+  week_start_ms: number;
+  tasks: number;
+  wer: number;
+  cer: number;
+};
+
+// ASR error rates against manual corrections, per model and microphone.
+export type AccuracyGroup = {
+  asr_model: string;
+  microphone: string;
+  tasks: number;
+  wer: number;
+  cer: number;
+  first_ms: number;
+  last_ms: number;
+  weekly: AccuracyBucket[];
+};
+
+// Debug lineage written under debug/<task_id>/ while TYPEVOICE_DEBUG_VERBOSE is on.
+export type TaskArtifact = {
+  name: string;
+  bytes: number;
+  modified_ms: number;
+};
+
+export type TaskArtifactContent = {
+  name: string;
+  bytes: number;
+  text: string | null;
+  truncated: boolean;
 };
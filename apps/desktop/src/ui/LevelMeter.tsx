@@ -0,0 +1,65 @@
+import { useEffect, useState } from "react";
+import { defaultTauriGateway } from "../infra/runtimePorts";
+import type { AudioLevel } from "../types";
+
+// The bar spans -60..0 dBFS; quieter input reads as empty.
+const METER_FLOOR_DBFS = -60;
+
+function meterPercent(dbfs: number): number {
+  const clamped = Math.min(0, Math.max(METER_FLOOR_DBFS, dbfs));
+  return Math.round(((clamped - METER_FLOOR_DBFS) / -METER_FLOOR_DBFS) * 100);
+}
+
+// Latest level from the active recording, or null when nothing is recording.
+export function useAudioLevel(): AudioLevel | null {
+  const [level, setLevel] = useState<AudioLevel | null>(null);
+  useEffect(() => {
+    let cancelled = false;
+    let unlisten: (() => void) | null = null;
+    void (async () => {
+      const stop = await defaultTauriGateway.listen<AudioLevel>("audio_level", (next) => {
+        if (cancelled) return;
+        // The recorder ends on a silent frame; hide the meter then.
+        setLevel(next.peakDbfs <= -100 ? null : next);
+      });
+      if (cancelled) {
+        stop();
+      } else {
+        unlisten = stop;
+      }
+    })();
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, []);
+  return level;
+}
+
+type Props = {
+  level: AudioLevel;
+  showLabel?: boolean;
+};
+
+export function LevelMeter({ level, showLabel }: Props) {
+  return (
+    <div
+      className="levelMeter"
+      role="meter"
+      aria-label="input level"
+      aria-valuemin={METER_FLOOR_DBFS}
+      aria-valuemax={0}
+      aria-valuenow={Math.round(level.rmsDbfs)}
+    >
+      <div className="levelMeterTrack">
+        <span className="levelMeterRms" style={{ width: `${meterPercent(level.rmsDbfs)}%` }} />
+        <span className="levelMeterPeak" style={{ left: `${meterPercent(level.peakDbfs)}%` }} />
+      </div>
+      {showLabel ? (
+        <span className="levelMeterLabel">
+          {level.rmsDbfs.toFixed(1)} dBFS · peak {level.peakDbfs.toFixed(1)}
+        </span>
+      ) : null}
+    </div>
+  );
+}
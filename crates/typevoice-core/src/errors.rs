@@ -0,0 +1,1250 @@
+use serde::Serialize;
+
+// Help text for the `E_*` codes the backend emits. Codes ending in `_` cover a
+// family (numbered HTTP statuses, codes built at runtime, per-field command
+// failures); an exact entry always wins over its family.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorExplanation {
+    pub code: String,
+    // Table entry that answered: the code itself or its family prefix.
+    pub matched: &'static str,
+    pub title: &'static str,
+    pub summary: &'static str,
+    pub causes: &'static [&'static str],
+    pub remediation: &'static [&'static str],
+}
+
+struct Entry {
+    code: &'static str,
+    title: &'static str,
+    summary: &'static str,
+    causes: &'static [&'static str],
+    remediation: &'static [&'static str],
+}
+
+const fn entry(
+    code: &'static str,
+    title: &'static str,
+    summary: &'static str,
+    causes: &'static [&'static str],
+    remediation: &'static [&'static str],
+) -> Entry {
+    Entry {
+        code,
+        title,
+        summary,
+        causes,
+        remediation,
+    }
+}
+
+const SEE_TRACE: &str =
+    "If it keeps happening, attach trace.jsonl from the data directory to a bug report.";
+
+static TABLE: &[Entry] = &[
+    // Audio tools and input.
+    entry(
+        "E_TOOLCHAIN_",
+        "Local audio tools need repair",
+        "FFmpeg or FFprobe failed the startup check, so recordings cannot be prepared for speech recognition.",
+        &[
+            "The bundled FFmpeg / FFprobe binaries are missing or were removed by antivirus software.",
+            "The binaries do not match the expected version or checksum.",
+            "The platform has no bundled toolchain.",
+        ],
+        &[
+            "Reinstall TypeVoice or restore the toolchain folder next to the app.",
+            "Allow the FFmpeg binaries in your antivirus software.",
+            "A failed check is retried automatically, so no restart is needed.",
+        ],
+    ),
+    entry(
+        "E_TOOLCHAIN_NOT_READY",
+        "Local audio tools are not ready",
+        "A task was started before the FFmpeg / FFprobe check passed.",
+        &[
+            "The startup check is still running.",
+            "The last check failed and has not been re-run yet.",
+        ],
+        &[
+            "Wait a few seconds and try again.",
+            "If it persists, reinstall TypeVoice to restore the audio tools.",
+        ],
+    ),
+    entry(
+        "E_FFMPEG_",
+        "Audio conversion failed",
+        "FFmpeg could not convert the recording into the format speech recognition expects.",
+        &[
+            "FFmpeg is missing or not executable.",
+            "The recording is empty, truncated or in an unsupported format.",
+            "The disk is full or the data directory is not writable.",
+        ],
+        &[
+            "Check that the local audio tools pass the runtime check in Settings.",
+            "Record again; if an imported file fails, convert it to WAV first.",
+            "Free disk space in the data directory.",
+        ],
+    ),
+    entry(
+        "E_FFPROBE_",
+        "Audio could not be inspected",
+        "FFprobe could not read the recording before processing.",
+        &[
+            "FFprobe is missing or not executable.",
+            "The audio file is damaged or not an audio file.",
+        ],
+        &[
+            "Check that the local audio tools pass the runtime check in Settings.",
+            "Record again or use a different file.",
+        ],
+    ),
+    entry(
+        "E_INPUT_INVALID",
+        "Recorded audio could not be read",
+        "The audio has no usable stream, or its length or format is outside what TypeVoice accepts.",
+        &[
+            "The recording stopped before any audio was captured.",
+            "An imported file has no audio track or is damaged.",
+        ],
+        &[
+            "Check the selected microphone and record again.",
+            "Convert imported files to WAV or FLAC and retry.",
+        ],
+    ),
+    entry(
+        "E_PREPROCESS_FAILED",
+        "Audio preparation failed",
+        "The recording could not be resampled and normalized before speech recognition.",
+        &[
+            "FFmpeg failed on this recording.",
+            "The data directory is not writable.",
+        ],
+        &[
+            "Record again.",
+            "Check the local audio tools and free disk space.",
+        ],
+    ),
+    entry(
+        "E_PATH_UTF8",
+        "Unsupported file path",
+        "A file path contains characters the audio tools cannot be given.",
+        &["The data directory or an imported file lives under a path that is not valid UTF-8."],
+        &["Move the data directory or the file to a path with plain characters."],
+    ),
+    entry(
+        "E_RECORDING_TRANSCODE_FAILED",
+        "Kept recording stays as WAV",
+        "A kept recording could not be converted to FLAC or Opus, so the WAV file was kept instead.",
+        &[
+            "The bundled FFmpeg has no FLAC or Opus encoder.",
+            "The disk is full.",
+        ],
+        &[
+            "Nothing is lost; the history entry points at the WAV file.",
+            "Set the recording format to WAV in Settings to skip conversion.",
+        ],
+    ),
+    // Recording.
+    entry(
+        "E_RECORD_",
+        "Recording could not start",
+        "The microphone could not be opened or read.",
+        &[
+            "The selected microphone is unplugged or used exclusively by another app.",
+            "Microphone access is turned off for TypeVoice.",
+            "The saved input device no longer exists.",
+        ],
+        &[
+            "Check the microphone selection in Settings > Recording input.",
+            "Close apps that hold the microphone exclusively.",
+            "Allow microphone access in Settings > Permissions.",
+        ],
+    ),
+    entry(
+        "E_RECORD_INPUT_",
+        "Microphone could not be selected",
+        "TypeVoice could not resolve which input device to record from.",
+        &[
+            "The fixed device was removed or renamed.",
+            "The system audio service did not answer the device query.",
+        ],
+        &[
+            "Pick the microphone again in Settings > Recording input, or switch to the default device.",
+            "Reconnect the device and retry.",
+        ],
+    ),
+    entry(
+        "E_CAPTURE_INDICATOR_UNAVAILABLE",
+        "Capture indicator could not be shown",
+        "The always-visible capture light is required, so the microphone was not opened without it.",
+        &["The indicator window failed to open or was closed by the system."],
+        &[
+            "Restart TypeVoice.",
+            "Turn off \"Always Show Capture Light\" in Settings > Hotkeys to record without it.",
+        ],
+    ),
+    entry(
+        "E_RECORD_ALREADY_ACTIVE",
+        "An action is already running",
+        "A recording is already in progress.",
+        &["A second start was requested while recording."],
+        &["Stop the current recording first."],
+    ),
+    entry(
+        "E_RECORD_BUSY",
+        "Recorder is busy",
+        "The recorder is still finishing the previous recording.",
+        &["A new recording was requested right after stopping the last one."],
+        &["Wait a moment and try again."],
+    ),
+    entry(
+        "E_RECORD_NOT_ACTIVE",
+        "No recording in progress",
+        "A stop was requested but nothing was recording.",
+        &["The recording already ended or was cancelled."],
+        &["Start a new recording."],
+    ),
+    entry(
+        "E_RECORD_UNSUPPORTED",
+        "Recording is unavailable on this system",
+        "This platform has no supported audio capture backend.",
+        &["TypeVoice is running on a platform without native recording support."],
+        &["Use a supported platform, or transcribe existing audio files instead."],
+    ),
+    entry(
+        "E_RECORD_LOW_QUALITY_PROFILE",
+        "Microphone uses a low-quality profile",
+        "The input device is in a narrowband mode (typically a Bluetooth headset in hands-free mode), which hurts recognition.",
+        &["A Bluetooth headset switched to its headset/hands-free profile."],
+        &[
+            "Use a wired or built-in microphone.",
+            "Switch the headset back to its high-quality profile in the system sound settings.",
+        ],
+    ),
+    entry(
+        "E_RECORD_NATIVE_FALLBACK",
+        "Recording through ffmpeg instead",
+        "The built-in recorder could not open the microphone, so this recording uses the ffmpeg recorder.",
+        &["The device rejected its default format, or the audio driver does not support shared-mode capture."],
+        &["Set the recorder to ffmpeg in Settings > Recording input to skip the native attempt."],
+    ),
+    entry(
+        "E_MIC_TEST_",
+        "Microphone test failed",
+        "The microphone test did not capture usable audio.",
+        &[
+            "The microphone is muted or the input level is too low.",
+            "The wrong input device is selected.",
+        ],
+        &[
+            "Unmute the microphone and raise the input level.",
+            "Pick another device in Settings > Recording input and test again.",
+        ],
+    ),
+    entry(
+        "E_BUG_REPORT_CONSENT_REQUIRED",
+        "Bug report needs consent",
+        "A bug report sample was requested without confirming what it records.",
+        &["The consent dialog was skipped or declined."],
+        &["Start the bug report again and confirm the dialog."],
+    ),
+    entry(
+        "E_BUG_REPORT_",
+        "Bug report sample failed",
+        "The bug report sample could not be recorded or packaged.",
+        &[
+            "Guest mode is on, so no recording or payload may be kept.",
+            "The data folder is not writable.",
+        ],
+        &[
+            "Turn guest mode off before recording a bug report.",
+            "Check free disk space and folder permissions, then try again.",
+        ],
+    ),
+    entry(
+        "E_NOISE_CALIBRATION_",
+        "Noise calibration failed",
+        "No room noise could be sampled for the noise profile.",
+        &["The microphone returned no audio during calibration."],
+        &["Check the microphone and run calibration again in a quiet moment."],
+    ),
+    entry(
+        "E_AUDIO_DEVICE_",
+        "Device change watcher unavailable",
+        "TypeVoice could not subscribe to microphone plug/unplug notifications.",
+        &["The system audio service refused the registration."],
+        &["Recording still works; restart TypeVoice to pick up device changes."],
+    ),
+    entry(
+        "E_WAKE_WORD_",
+        "Wake word is unavailable",
+        "Listening for the wake word could not start or stopped unexpectedly.",
+        &[
+            "No wake word detector is installed or configured.",
+            "The microphone could not be opened for listening.",
+            "This platform does not support wake word listening.",
+        ],
+        &[
+            "Check the wake word settings and the detector path.",
+            "Check the microphone, then turn wake word off and on again.",
+        ],
+    ),
+    // Speech recognition.
+    entry(
+        "E_ASR_",
+        "Speech recognition failed",
+        "The recording could not be turned into text.",
+        &[
+            "The speech recognition service is misconfigured or unreachable.",
+            "The recording contained no speech.",
+        ],
+        &[
+            "Check speech recognition settings and try again.",
+            SEE_TRACE,
+        ],
+    ),
+    entry(
+        "E_ASR_EMPTY_TEXT",
+        "No speech detected",
+        "Speech recognition returned no text for this recording.",
+        &[
+            "The recording was silent or too short.",
+            "The wrong microphone is selected or it is muted.",
+        ],
+        &[
+            "Record again and speak after the recording indicator appears.",
+            "Check the selected microphone.",
+        ],
+    ),
+    entry(
+        "E_ASR_COMPARE_",
+        "Model comparison could not run",
+        "The ASR comparison needs the task's audio and at least one valid profile.",
+        &[
+            "The task's audio was not kept, so there is nothing to re-run.",
+            "A comparison profile is missing its provider or model.",
+        ],
+        &[
+            "Turn on Keep recordings in Settings for future tasks.",
+            "Fix or remove the invalid comparison profile.",
+        ],
+    ),
+    entry(
+        "E_REMOTE_ASR_",
+        "Speech recognition could not start",
+        "The remote speech recognition service did not return a transcript.",
+        &[
+            "The service URL, model or API key is wrong.",
+            "The network or a proxy blocks the request.",
+        ],
+        &[
+            "Check the remote ASR settings and use Check key.",
+            SEE_TRACE,
+        ],
+    ),
+    entry(
+        "E_REMOTE_ASR_API_KEY_MISSING",
+        "Remote ASR API key missing",
+        "Remote speech recognition is selected but no API key is stored.",
+        &["The API key was never set or was cleared."],
+        &["Enter the remote ASR API key in Settings > Speech recognition."],
+    ),
+    entry(
+        "E_REMOTE_ASR_HTTP_STATUS_",
+        "Speech recognition service returned an error",
+        "The remote speech recognition service rejected the request.",
+        &[
+            "The service is down or overloaded (5xx).",
+            "The request was rejected by the service or a proxy (4xx).",
+        ],
+        &[
+            "Try again after the service recovers.",
+            "Check the service URL and model name.",
+        ],
+    ),
+    entry(
+        "E_REMOTE_ASR_HTTP_STATUS_401",
+        "Speech recognition credentials were rejected",
+        "The remote speech recognition service did not accept the API key.",
+        &["The API key is wrong, expired or revoked."],
+        &["Enter a valid API key in Settings and use Check key to verify it."],
+    ),
+    entry(
+        "E_REMOTE_ASR_HTTP_STATUS_403",
+        "Speech recognition access denied",
+        "The API key is valid but not allowed to use this model or endpoint.",
+        &["The account lacks permission for the configured model."],
+        &["Check the account permissions, or choose a model the key can use."],
+    ),
+    entry(
+        "E_REMOTE_ASR_HTTP_STATUS_404",
+        "Speech recognition endpoint not found",
+        "The service URL or model does not exist.",
+        &["The base URL has a typo or the wrong path.", "The model name is wrong."],
+        &["Check the remote ASR base URL and model in Settings."],
+    ),
+    entry(
+        "E_REMOTE_ASR_HTTP_STATUS_429",
+        "Speech recognition service is rate limited",
+        "Too many requests were sent to the speech recognition service.",
+        &["The account's request or usage quota is exhausted."],
+        &["Wait for the rate limit to reset, or raise the quota with the provider."],
+    ),
+    entry(
+        "E_REMOTE_ASR_HTTP_SEND",
+        "Speech recognition service is unreachable",
+        "The request to the speech recognition service could not be sent.",
+        &[
+            "No network connection.",
+            "The base URL is wrong, or a proxy or firewall blocks it.",
+        ],
+        &[
+            "Check the network connection and the ASR service URL.",
+            "Check the proxy settings and use Test proxy.",
+        ],
+    ),
+    entry(
+        "E_REMOTE_ASR_EMPTY_TEXT",
+        "No speech detected",
+        "The remote service returned an empty transcript.",
+        &["The recording was silent or too short."],
+        &["Record again and speak clearly into the microphone."],
+    ),
+    entry(
+        "E_REMOTE_ASR_WAV_",
+        "Recorded audio could not be read",
+        "The prepared WAV file could not be read for upload.",
+        &["The file is missing, damaged or in an unexpected sample format."],
+        &["Record again; check the local audio tools if it repeats."],
+    ),
+    entry(
+        "E_REMOTE_STREAMING_UNSUPPORTED",
+        "Live transcription is unavailable",
+        "The selected remote ASR provider does not support streaming.",
+        &["Streaming transcription is on while a batch-only provider is selected."],
+        &["Turn off streaming, or choose a provider that streams (for example Doubao)."],
+    ),
+    entry(
+        "E_DOUBAO_ASR_",
+        "Doubao speech recognition failed",
+        "The Doubao streaming session did not complete.",
+        &[
+            "The credentials are wrong or lack access to the resource.",
+            "The network dropped the WebSocket connection.",
+        ],
+        &[
+            "Check the Doubao App Key and Access Key and use Check key.",
+            "Check the network connection and try again.",
+        ],
+    ),
+    entry(
+        "E_DOUBAO_ASR_CREDENTIALS_MISSING",
+        "Doubao ASR credentials missing",
+        "Doubao is selected but its App Key or Access Key is not stored.",
+        &["The credentials were never set or were cleared."],
+        &["Configure the Doubao ASR App Key and Access Key in Settings."],
+    ),
+    entry(
+        "E_DOUBAO_ASR_APP_KEY_MISSING",
+        "Doubao ASR credentials missing",
+        "The Doubao App Key is not stored.",
+        &["The App Key was never set or was cleared."],
+        &["Configure the Doubao ASR App Key in Settings."],
+    ),
+    entry(
+        "E_DOUBAO_ASR_ACCESS_KEY_MISSING",
+        "Doubao ASR credentials missing",
+        "The Doubao Access Key is not stored.",
+        &["The Access Key was never set or was cleared."],
+        &["Configure the Doubao ASR Access Key in Settings."],
+    ),
+    entry(
+        "E_DOUBAO_ASR_FINISH_TIMEOUT",
+        "Doubao did not finish in time",
+        "The final transcript did not arrive after the recording stopped.",
+        &["The service or network is slow."],
+        &["Try again; a shorter recording finishes faster."],
+    ),
+    entry(
+        "E_DOUBAO_FIXTURE_UNSUPPORTED",
+        "Test fixture not supported",
+        "A Doubao replay fixture uses a feature the fixture player does not handle.",
+        &["The fixture was recorded by a newer build."],
+        &["Re-record the fixture with this build."],
+    ),
+    entry(
+        "E_STREAMING_",
+        "Live transcription failed",
+        "The streaming transcription session could not start or keep up.",
+        &[
+            "The streaming provider is misconfigured.",
+            "The transcription worker stopped unexpectedly.",
+        ],
+        &[
+            "Check speech recognition settings and try again.",
+            "Turn off streaming to fall back to transcribing after recording.",
+        ],
+    ),
+    entry(
+        "E_CANCELLED",
+        "Cancelled",
+        "The task was cancelled before it finished.",
+        &["The user cancelled, or a newer task replaced this one."],
+        &["No action needed; start again when ready."],
+    ),
+    // Text improvement.
+    entry(
+        "E_LLM_",
+        "Text improvement failed",
+        "The language model request did not return usable text.",
+        &[
+            "The LLM base URL, model or API key is wrong.",
+            "The network or a proxy blocks the request.",
+        ],
+        &[
+            "Check the LLM settings and use Check under API key.",
+            SEE_TRACE,
+        ],
+    ),
+    entry(
+        "E_LLM_API_KEY",
+        "LLM API key missing",
+        "Text improvement needs an API key that is not stored.",
+        &["The API key was never set or was cleared."],
+        &["Enter the LLM API key in Settings > API key."],
+    ),
+    entry(
+        "E_LLM_CONFIG_",
+        "LLM settings incomplete",
+        "The LLM base URL or model is not set.",
+        &["Required LLM fields are empty."],
+        &["Fill in the base URL and model in Settings > Language model."],
+    ),
+    entry(
+        "E_LLM_HTTP_SEND",
+        "LLM service is unreachable",
+        "The request to the language model could not be sent.",
+        &["No network connection, a wrong base URL, or a blocking proxy."],
+        &[
+            "Check the network connection and the LLM base URL.",
+            "Check the proxy settings and use Test proxy.",
+        ],
+    ),
+    entry(
+        "E_LLM_CHECK_HTTP_STATUS_",
+        "LLM check failed",
+        "The language model service rejected the test request.",
+        &["The service returned an error status."],
+        &["Check the base URL and model, then check again."],
+    ),
+    entry(
+        "E_LLM_CHECK_HTTP_STATUS_401",
+        "LLM credentials were rejected",
+        "The language model service did not accept the API key.",
+        &["The API key is wrong, expired or revoked."],
+        &["Enter a valid API key in Settings and check again."],
+    ),
+    entry(
+        "E_LLM_CHECK_HTTP_STATUS_403",
+        "LLM access denied",
+        "The API key is not allowed to use this model.",
+        &["The account lacks permission for the configured model."],
+        &["Choose a model the key can use, or update the account permissions."],
+    ),
+    entry(
+        "E_LLM_CHECK_HTTP_STATUS_404",
+        "LLM endpoint not found",
+        "The base URL or model does not exist.",
+        &["The base URL path or model name is wrong."],
+        &["Check the LLM base URL and model in Settings."],
+    ),
+    entry(
+        "E_LLM_CHECK_HTTP_STATUS_429",
+        "LLM service is rate limited",
+        "Too many requests were sent to the language model service.",
+        &["The account's request or usage quota is exhausted."],
+        &["Wait for the rate limit to reset, or raise the quota with the provider."],
+    ),
+    entry(
+        "E_LLM_OUTPUT_INVALID",
+        "Structured output did not match",
+        "The model's reply did not match the configured JSON schema; the raw reply was kept.",
+        &[
+            "The model ignored the output format instructions.",
+            "The schema is stricter than the prompt asks for.",
+        ],
+        &[
+            "Mention the required fields in the prompt, or relax the schema.",
+            "Switch the output format back to text.",
+        ],
+    ),
+    entry(
+        "E_REWRITE_",
+        "Text improvement failed",
+        "The transcript could not be rewritten; the original text is kept.",
+        &[
+            "The LLM request failed.",
+            "There was no text to rewrite.",
+        ],
+        &["Check text improvement settings and try again."],
+    ),
+    entry(
+        "E_REWRITE_DISABLED",
+        "Text improvement is off",
+        "A rewrite was requested while rewriting is turned off.",
+        &["Rewrite is disabled in Settings."],
+        &["Turn on rewrite in Settings > Rewrite."],
+    ),
+    entry(
+        "E_REWRITE_TOKEN_BUDGET_EXCEEDED",
+        "Rewrite request too large",
+        "Even after dropping the screenshot, clipboard and history, the rewrite request is over the token cap.",
+        &["The transcript and prompt alone are larger than the cap."],
+        &[
+            "Raise the token cap in Settings > Rewrite, or set it to 0.",
+            "Lower the chunk size so long transcripts are split.",
+        ],
+    ),
+    entry(
+        "E_REWRITE_CACHE",
+        "Rewrite cache unavailable",
+        "The rewrite cache could not be read; the model was asked instead.",
+        &["rewrite_cache.json is damaged or not readable."],
+        &["No action needed; the cache is rebuilt on the next successful rewrite."],
+    ),
+    entry(
+        "E_POSTPROCESS_",
+        "Post-processing plugin failed",
+        "A post-processing plugin did not return valid text, so its step was skipped.",
+        &[
+            "The plugin crashed, timed out or wrote too much output.",
+            "A WASM plugin imports functions the host does not provide.",
+        ],
+        &[
+            "Check the plugin path and run it by hand with sample input.",
+            "Disable the plugin in Settings until it is fixed.",
+        ],
+    ),
+    // Pasting and export.
+    entry(
+        "E_EXPORT_",
+        "Text could not be pasted",
+        "The text could not be delivered to the target app; it is left on the clipboard when possible.",
+        &[
+            "The target window closed or lost focus.",
+            "The target app does not accept pasted text.",
+        ],
+        &[
+            "Select the target app and try again.",
+            "Paste manually from the clipboard.",
+        ],
+    ),
+    entry(
+        "E_EXPORT_IME_COMPOSING",
+        "Input method is still composing",
+        "The target has an unfinished input method composition, so pasting would corrupt it.",
+        &["An IME candidate window is open in the target app."],
+        &["Finish or cancel the input method composition, then paste again."],
+    ),
+    entry(
+        "E_EXPORT_TARGET_ELEVATED",
+        "Target app runs as administrator",
+        "Windows blocks a normal app from pasting into an elevated one.",
+        &["The target app was started with Run as administrator."],
+        &[
+            "Run TypeVoice as administrator, or turn on the elevated paste helper in Settings > Export.",
+            "The text is on the clipboard; paste it manually.",
+        ],
+    ),
+    entry(
+        "E_EXPORT_ELEVATION_DECLINED",
+        "Administrator paste was declined",
+        "The UAC prompt for the elevated paste helper was declined.",
+        &["The prompt was cancelled or timed out."],
+        &["Accept the prompt next time, or paste from the clipboard."],
+    ),
+    entry(
+        "E_EXPORT_TARGET_LOCK_LOST",
+        "Locked target window was closed",
+        "The window that was locked as the paste target no longer exists.",
+        &["The locked window was closed or its app restarted."],
+        &["Lock a new target window, or paste from the clipboard."],
+    ),
+    entry(
+        "E_EXPORT_TARGET_LOCK_FOCUS_FAILED",
+        "Locked target window could not be activated",
+        "Windows refused to bring the locked window to the front.",
+        &["Another app holds the foreground, or the window is minimized."],
+        &["Bring the locked window to the front, or unlock the target."],
+    ),
+    entry(
+        "E_EXPORT_TARGET_NOT_EDITABLE",
+        "Target is not a text field",
+        "The focused element does not accept typed text.",
+        &["Focus was on a button, list or read-only area."],
+        &["Click into a text field and try again."],
+    ),
+    entry(
+        "E_EXPORT_TARGET_NOT_ALLOWED",
+        "Template blocks copying",
+        "The template's export policy allows neither the clipboard nor pasting.",
+        &["export_targets_by_template lists no clipboard or paste target for this template."],
+        &["Add clipboard or paste to the template's export targets, or use another template."],
+    ),
+    entry(
+        "E_INSERT_",
+        "Text could not be pasted",
+        "The insert request was missing its text or transcript.",
+        &["There is no transcript to insert yet."],
+        &["Record or select a transcript, then insert again."],
+    ),
+    entry(
+        "E_OVERLAY_",
+        "Overlay could not paste",
+        "The overlay's target window is no longer available.",
+        &["The window that was active when recording started has closed."],
+        &["Select the target app and try again."],
+    ),
+    entry(
+        "E_CLIPBOARD",
+        "Clipboard unavailable",
+        "The clipboard could not be read or written.",
+        &["Another app is holding the clipboard open."],
+        &["Try again in a moment."],
+    ),
+    entry(
+        "E_SINK_",
+        "Markdown export failed",
+        "The transcript could not be appended to the Markdown folder.",
+        &[
+            "The Markdown folder is not set or was moved.",
+            "The folder is not writable.",
+        ],
+        &["Choose an existing, writable folder in Settings > Export."],
+    ),
+    entry(
+        "E_WEBHOOK_",
+        "Webhook delivery failed",
+        "The transcript could not be posted to the configured webhook.",
+        &[
+            "The webhook URL is unreachable or returned an error.",
+            "The signing secret is missing.",
+        ],
+        &[
+            "Check the webhook URL and secret in Settings > Webhook.",
+            "Deliveries are retried; check the receiving service's logs.",
+        ],
+    ),
+    entry(
+        "E_WEBHOOK_HTTP_STATUS_410",
+        "Webhook endpoint is gone",
+        "The receiver answered 410 Gone, so deliveries to it stop.",
+        &["The webhook endpoint was deleted on the receiving side."],
+        &["Update or remove the webhook URL in Settings."],
+    ),
+    entry(
+        "E_MQTT_",
+        "MQTT publishing failed",
+        "Transcripts could not be published to the MQTT broker.",
+        &[
+            "The broker address or port is wrong, or the broker is down.",
+            "The MQTT password is missing or rejected.",
+        ],
+        &["Check the broker settings and password in Settings > MQTT."],
+    ),
+    // Context capture and permissions.
+    entry(
+        "E_CONTEXT_",
+        "App context is unavailable",
+        "Window, clipboard or screenshot context could not be captured; the task continued without it.",
+        &[
+            "Screen recording or accessibility access is off.",
+            "The foreground window closed during capture.",
+        ],
+        &["Grant the missing permission in Settings > Permissions, or turn off the context source."],
+    ),
+    entry(
+        "E_SCREENSHOT",
+        "Screenshot unavailable",
+        "The window screenshot for context could not be captured.",
+        &["Screen recording access is off, or the window is protected."],
+        &["Grant screen recording access in Settings > Permissions, or turn off screenshot context."],
+    ),
+    entry(
+        "E_PERMISSION_",
+        "Permission is off",
+        "A system permission TypeVoice needs is not granted.",
+        &["The permission was denied or revoked in system settings."],
+        &[
+            "Grant the permission in Settings > Permissions.",
+            "Restart TypeVoice after granting it if the status does not update.",
+        ],
+    ),
+    entry(
+        "E_PERMISSION_MICROPHONE_DENIED",
+        "Microphone access is off",
+        "The system blocks TypeVoice from using the microphone.",
+        &["Microphone access was denied for TypeVoice."],
+        &["Allow microphone access in Settings > Permissions or the system privacy settings."],
+    ),
+    entry(
+        "E_PERMISSION_ACCESSIBILITY_DENIED",
+        "Accessibility access is off",
+        "Without accessibility access TypeVoice cannot paste into other apps.",
+        &["Accessibility access was denied for TypeVoice."],
+        &["Allow accessibility access in Settings > Permissions or the system privacy settings."],
+    ),
+    entry(
+        "E_PERMISSION_SCREEN_RECORDING_DENIED",
+        "Screen recording access is off",
+        "Without screen recording access, window titles and screenshots are not captured.",
+        &["Screen recording access was denied for TypeVoice."],
+        &["Allow screen recording in Settings > Permissions, or turn off screenshot context."],
+    ),
+    entry(
+        "E_PERMISSION_SETTINGS_",
+        "System settings could not be opened",
+        "The system permission page could not be opened from TypeVoice.",
+        &["This platform has no direct link to the permission page."],
+        &["Open the system privacy settings by hand."],
+    ),
+    // Hotkeys.
+    entry(
+        "E_HOTKEY_",
+        "Keyboard shortcut could not run",
+        "The global shortcut could not be registered or its task could not start.",
+        &[
+            "Another app already uses the same shortcut.",
+            "The desktop environment does not allow global shortcuts.",
+        ],
+        &[
+            "Choose a different shortcut in Settings > Hotkeys.",
+            "On Wayland, allow the shortcut in the portal dialog.",
+        ],
+    ),
+    entry(
+        "E_HOTKEY_PORTAL_DENIED",
+        "Shortcut permission was denied",
+        "The desktop portal refused to bind the global shortcut.",
+        &["The portal dialog was declined."],
+        &["Re-apply the shortcut in Settings > Hotkeys and accept the portal dialog."],
+    ),
+    entry(
+        "E_HOTKEY_TASK_OPEN",
+        "Shortcut could not start a task",
+        "The shortcut fired but the dictation task could not be opened.",
+        &["Another task is still running.", "The audio tools are not ready."],
+        &["Wait for the current action to finish and try again."],
+    ),
+    entry(
+        "E_HK_",
+        "Keyboard shortcuts unavailable",
+        "The global shortcut listener could not start.",
+        &["The hotkey settings are invalid.", "The system refused the listener."],
+        &["Check the shortcuts in Settings > Hotkeys and restart TypeVoice."],
+    ),
+    // Workflow.
+    entry(
+        "E_WORKFLOW_",
+        "Action not available right now",
+        "The requested action does not fit the current state (for example pasting while still recording).",
+        &["A button or shortcut was used while another step was running."],
+        &["Wait for the current step to finish, then try again."],
+    ),
+    entry(
+        "E_WORKFLOW_BUSY",
+        "An action is already running",
+        "Another dictation is recording or transcribing.",
+        &["A new dictation was started before the previous one finished."],
+        &["Wait for the estimated time shown, then try again."],
+    ),
+    entry(
+        "E_WORKFLOW_BACKGROUND_BUSY",
+        "Too many rewrites in progress",
+        "The background rewrite slots are full.",
+        &["Several rewrites are still waiting for the language model."],
+        &["Wait for a rewrite to finish, then try again."],
+    ),
+    entry(
+        "E_TASK_",
+        "An action is already running",
+        "The task could not start or was replaced by a newer one.",
+        &[
+            "Another task is still active.",
+            "The task stopped reporting progress and was abandoned.",
+        ],
+        &["Wait for the current action to finish, or cancel it, and try again."],
+    ),
+    entry(
+        "E_TASK_STALLED",
+        "Task stopped responding",
+        "A task made no progress for too long and was stopped.",
+        &["A provider request hung.", "The transcription worker stalled."],
+        &["Try again.", SEE_TRACE],
+    ),
+    entry(
+        "E_PIPELINE_GRAPH",
+        "Processing pipeline is invalid",
+        "The configured processing stages do not form a valid pipeline.",
+        &["A stage depends on a stage that is disabled or missing."],
+        &["Reset the pipeline-related settings to their defaults.", SEE_TRACE],
+    ),
+    // Settings, secrets and storage.
+    entry(
+        "E_SETTINGS_",
+        "Settings need attention",
+        "A setting has a value TypeVoice cannot use.",
+        &[
+            "A value is out of range or not one of the allowed options.",
+            "settings.json was edited by hand and is not valid.",
+        ],
+        &[
+            "Open Settings; invalid fields are marked with the reason.",
+            "Restore the field to its default value.",
+        ],
+    ),
+    entry(
+        "E_SETTINGS_JSON_INVALID",
+        "Settings file is damaged",
+        "settings.json is not valid JSON.",
+        &["The file was edited by hand or cut off while saving."],
+        &["Fix the JSON by hand, or remove settings.json to start from defaults."],
+    ),
+    entry(
+        "E_SETTINGS_WRITE",
+        "Settings could not be saved",
+        "settings.json could not be written.",
+        &[
+            "The data directory is read-only or the disk is full.",
+            "Guest mode or another instance holds the data directory.",
+        ],
+        &["Free disk space and check write access to the data directory."],
+    ),
+    entry(
+        "E_SECRETS_",
+        "Stored keys could not be used",
+        "The encrypted key store could not be read or written.",
+        &[
+            "The secrets file is damaged or from a newer version.",
+            "The passphrase is wrong or not entered.",
+        ],
+        &[
+            "Set TYPEVOICE_SECRETS_PASSPHRASE to the key store passphrase before starting TypeVoice.",
+            "Re-enter the API keys in Settings to rebuild the store.",
+        ],
+    ),
+    entry(
+        "E_SECRETS_PASSPHRASE_REQUIRED",
+        "Passphrase required",
+        "The key store is protected by a passphrase that has not been entered.",
+        &["The app restarted and the passphrase is not cached."],
+        &["Set TYPEVOICE_SECRETS_PASSPHRASE before starting TypeVoice."],
+    ),
+    entry(
+        "E_SECRETS_DECRYPT",
+        "Stored keys could not be decrypted",
+        "The key store did not decrypt with the given passphrase or device key.",
+        &["The passphrase is wrong.", "The store was copied from another device."],
+        &["Set the correct TYPEVOICE_SECRETS_PASSPHRASE, or re-enter the API keys."],
+    ),
+    entry(
+        "E_PROXY_",
+        "Proxy settings are incomplete",
+        "The proxy is enabled but its address or credentials are not usable.",
+        &["The proxy URL is invalid.", "A password is stored without a username."],
+        &["Check the proxy settings and use Test proxy."],
+    ),
+    entry(
+        "E_TLS_",
+        "Server certificate is not trusted",
+        "The HTTPS connection failed certificate checks.",
+        &[
+            "A corporate proxy re-signs HTTPS traffic with an internal CA.",
+            "The pinned certificate no longer matches the server.",
+        ],
+        &["Add the internal CA bundle or update the pinned certificate in Settings."],
+    ),
+    entry(
+        "E_HTTP_CLIENT_BUILD",
+        "Network client could not be created",
+        "The HTTP client could not be configured from the network settings.",
+        &["The CA bundle file is unreadable or the proxy URL is invalid."],
+        &["Check the proxy and certificate settings."],
+    ),
+    entry(
+        "E_GPU_UNLOAD_FAILED",
+        "Idle model was not unloaded",
+        "The unload URL for the idle GPU backend did not succeed; the request ran anyway.",
+        &["The local ASR or LLM server is down, or the unload URL is wrong."],
+        &["Check gpu_asr_unload_url / gpu_llm_unload_url, or clear them."],
+    ),
+    entry(
+        "E_OFFLINE_MODE",
+        "Offline mode is on",
+        "Network services are blocked while offline mode is on.",
+        &["Offline mode was turned on in Settings."],
+        &["Turn off offline mode in Settings to use network services."],
+    ),
+    entry(
+        "E_HISTORY_",
+        "History could not be saved or read",
+        "The history database could not be opened or updated.",
+        &[
+            "The data directory is read-only, the disk is full or the file is locked.",
+            "history.sqlite3 is damaged.",
+        ],
+        &[
+            "Free disk space and check write access to the data directory.",
+            "Failed writes are kept in history_journal.jsonl and replayed on the next start.",
+        ],
+    ),
+    entry(
+        "E_HISTORY_BULK_EMPTY_FILTER",
+        "Bulk delete needs a filter",
+        "A bulk delete was requested without a date range, tag, app or template.",
+        &["Every filter field was left empty."],
+        &[
+            "Set at least one filter, or use Clear history to delete everything.",
+        ],
+    ),
+    entry(
+        "E_HISTORY_NOT_FOUND",
+        "History entry not found",
+        "The history entry for this task does not exist.",
+        &["The entry was deleted or history was cleared."],
+        &["Refresh the history list."],
+    ),
+    entry(
+        "E_AUDIT_",
+        "Export audit log unavailable",
+        "The export audit log could not be read or written.",
+        &["The data directory is read-only or the database is damaged."],
+        &["Check write access to the data directory; clearing the audit log recreates it."],
+    ),
+    entry(
+        "E_ARTIFACT_",
+        "Debug artifact unavailable",
+        "The requested task artifact does not exist or could not be read.",
+        &["Verbose debug capture was off for that task, or its files were cleaned up."],
+        &["Set TYPEVOICE_DEBUG_VERBOSE=1 and reproduce the task."],
+    ),
+    entry(
+        "E_FORMATTING_PROFILES_",
+        "Formatting profiles unavailable",
+        "formatting_profiles.json could not be read or written.",
+        &["The file is damaged or the data directory is read-only."],
+        &["Fix or remove formatting_profiles.json, then re-create the profiles."],
+    ),
+    entry(
+        "E_FORMATTING_PROFILE_",
+        "Formatting profile is invalid",
+        "The profile is missing its id or process name, or its wrapper is invalid.",
+        &["A required field was left empty."],
+        &["Fill in the profile id and process name, and check the wrap template."],
+    ),
+    entry(
+        "E_DAILY_SUMMARY_",
+        "Daily summary failed",
+        "The daily summary could not be produced or saved.",
+        &["The date is not in YYYY-MM-DD form.", "The output folder is not writable."],
+        &["Check the date and the summary folder setting."],
+    ),
+    entry(
+        "E_WORKSPACE_",
+        "Workspace could not be switched",
+        "The workspace name is invalid or its files could not be written.",
+        &["The name contains path characters.", "The data directory is read-only."],
+        &["Use a plain workspace name and check write access to the data directory."],
+    ),
+    entry(
+        "E_DATA_DIR",
+        "Data directory unavailable",
+        "The TypeVoice data directory could not be located or created.",
+        &["TYPEVOICE_DATA_DIR points to a missing or read-only location."],
+        &["Fix TYPEVOICE_DATA_DIR or remove it to use the default location."],
+    ),
+    entry(
+        "E_BACKUP_VERSION_UNSUPPORTED",
+        "Backup is from a newer version",
+        "The backup was written in a format this version of TypeVoice cannot restore.",
+        &["The backup was made by a newer TypeVoice."],
+        &["Update TypeVoice, then restore the backup again."],
+    ),
+    entry(
+        "E_BACKUP_",
+        "Backup or restore failed",
+        "The data backup could not be written, or the backup to restore is incomplete or damaged.",
+        &[
+            "The backup folder already exists and is not empty.",
+            "The folder is not a TypeVoice backup, or a file in it is damaged.",
+            "The data folder is read-only (guest mode or held by another instance).",
+        ],
+        &[
+            "Back up to a new, empty folder.",
+            "Restore from a folder that contains backup.json.",
+            "Nothing was replaced if the backup failed its checks; the data from before a restore is kept under backups/.",
+        ],
+    ),
+    entry(
+        "E_DATA_INTEGRITY_CHECK",
+        "Data files could not be checked",
+        "The startup check could not read or move some data files, so they were left in place.",
+        &[
+            "Another program has the file open.",
+            "The data folder is not writable.",
+        ],
+        &[
+            "Close other programs using the data folder and restart TypeVoice.",
+            "See the trace log for the files involved.",
+        ],
+    ),
+    entry(
+        "E_DATA_DIR_",
+        "Data directory is in use",
+        "Another TypeVoice instance holds the data directory, so this one is read-only.",
+        &["TypeVoice is already running, possibly in another session."],
+        &[
+            "Close the other instance, or use Take over in the data directory banner.",
+            "Takeover waits for the other instance's next heartbeat, up to about a minute.",
+        ],
+    ),
+    entry(
+        "E_GUEST_MODE_READ_ONLY",
+        "Guest mode is read-only",
+        "Guest mode does not write settings, history or recordings.",
+        &["TypeVoice was started in guest mode."],
+        &["Restart without guest mode to save changes."],
+    ),
+    entry(
+        "E_CMD_",
+        "Command failed",
+        "A request from the window to the backend failed; the text after the code names the underlying error.",
+        &["See the underlying error code in the message."],
+        &["Look up the underlying code for specific steps.", SEE_TRACE],
+    ),
+    entry(
+        "E_INTERNAL",
+        "Internal error",
+        "A background worker stopped unexpectedly.",
+        &["A bug in TypeVoice."],
+        &["Try again.", SEE_TRACE],
+    ),
+    // Diagnostics-only codes.
+    entry(
+        "E_UI_",
+        "Window reported a problem",
+        "The window logged a diagnostic to the backend trace.",
+        &["An error was shown in the window."],
+        &["Check the error shown in the window for specific steps."],
+    ),
+    entry(
+        "E_TRACE_",
+        "Trace diagnostic",
+        "A trace event recorded an error or a slow step for later diagnosis.",
+        &["An inner step failed or exceeded its time budget."],
+        &[SEE_TRACE],
+    ),
+];
+
+// Accepts a bare code or a whole error line ("E_FOO: message"); the first
+// `E_*` token is looked up.
+pub fn explain(raw: &str) -> Option<ErrorExplanation> {
+    let code = extract_code(raw)?;
+    let entry = TABLE.iter().find(|e| e.code == code).or_else(|| {
+        TABLE
+            .iter()
+            .filter(|e| e.code.ends_with('_') && code.starts_with(e.code))
+            .max_by_key(|e| e.code.len())
+    })?;
+    Some(ErrorExplanation {
+        code,
+        matched: entry.code,
+        title: entry.title,
+        summary: entry.summary,
+        causes: entry.causes,
+        remediation: entry.remediation,
+    })
+}
+
+fn extract_code(raw: &str) -> Option<String> {
+    let upper = raw.trim().to_ascii_uppercase();
+    let start = upper.find("E_")?;
+    let code: String = upper[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || *c == '_')
+        .collect();
+    (code.len() > 2).then_some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    // Codes only raised by tests or test doubles.
+    const TEST_ONLY: &[&str] = &[
+        "E_TEST",
+        "E_TEST_SCRIPT_EXHAUSTED",
+        "E_PORT_DEFAULT",
+        "E_PORT_REAL",
+    ];
+
+    #[test]
+    fn exact_entries_win_over_their_family() {
+        let e = explain("E_REMOTE_ASR_HTTP_STATUS_401").expect("known");
+        assert_eq!(e.matched, "E_REMOTE_ASR_HTTP_STATUS_401");
+        let e = explain("e_remote_asr_http_status_503: upstream down").expect("known");
+        assert_eq!(e.code, "E_REMOTE_ASR_HTTP_STATUS_503");
+        assert_eq!(e.matched, "E_REMOTE_ASR_HTTP_STATUS_");
+        assert!(explain("E_NOT_A_REAL_CODE").is_none());
+        assert!(explain("no code here").is_none());
+    }
+
+    #[test]
+    fn every_emitted_code_has_an_entry() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+        let mut missing = Vec::new();
+        for dir in ["crates", "apps/desktop/src-tauri/src"] {
+            scan(&root.join(dir), &mut |code| {
+                if !TEST_ONLY.contains(&code) && explain(code).is_none() {
+                    missing.push(code.to_string());
+                }
+            });
+        }
+        missing.sort();
+        missing.dedup();
+        assert!(missing.is_empty(), "no explanation for {missing:?}");
+    }
+
+    fn scan(dir: &Path, found: &mut dyn FnMut(&str)) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                if path.file_name().is_some_and(|n| n != "target") {
+                    scan(&path, found);
+                }
+                continue;
+            }
+            // This file's own test uses made-up codes.
+            if path.extension().is_none_or(|e| e != "rs")
+                || path.ends_with("typevoice-core/src/errors.rs")
+            {
+                continue;
+            }
+            let text = std::fs::read_to_string(&path).unwrap_or_default();
+            for (i, _) in text.match_indices("\"E_") {
+                let code: String = text[i + 1..]
+                    .chars()
+                    .take_while(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || *c == '_')
+                    .collect();
+                if code.len() > 2 {
+                    found(&code);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,368 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    PlainText,
+    MarkdownHtml,
+}
+
+impl ExportFormat {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "plain" | "plain_text" | "text" => Some(Self::PlainText),
+            "markdown_html" | "markdown" | "html" | "rich" => Some(Self::MarkdownHtml),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::PlainText => "plain",
+            Self::MarkdownHtml => "markdown_html",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipboardPayload {
+    pub plain: String,
+    pub html: Option<String>,
+}
+
+pub fn build_clipboard_payload(text: &str, format: ExportFormat) -> ClipboardPayload {
+    let html = match format {
+        ExportFormat::PlainText => None,
+        ExportFormat::MarkdownHtml if contains_markdown(text) => Some(markdown_to_html(text)),
+        ExportFormat::MarkdownHtml => None,
+    };
+    ClipboardPayload {
+        plain: text.to_string(),
+        html,
+    }
+}
+
+pub fn contains_markdown(text: &str) -> bool {
+    for line in text.lines() {
+        let t = line.trim_start();
+        if heading_level(t).is_some()
+            || unordered_item(t).is_some()
+            || ordered_item(t).is_some()
+            || t.starts_with("> ")
+            || t.starts_with("```")
+        {
+            return true;
+        }
+        if t.contains("**") || t.contains('`') || (t.contains("](") && t.contains('[')) {
+            return true;
+        }
+    }
+    false
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ListKind {
+    Unordered,
+    Ordered,
+}
+
+impl ListKind {
+    fn tag(self) -> &'static str {
+        match self {
+            Self::Unordered => "ul",
+            Self::Ordered => "ol",
+        }
+    }
+}
+
+// Covers the Markdown subset rewrite prompts produce: headings, lists, quotes,
+// fenced code, emphasis, inline code and links.
+pub fn markdown_to_html(markdown: &str) -> String {
+    let mut out = String::new();
+    let mut paragraph: Vec<String> = Vec::new();
+    let mut list: Option<ListKind> = None;
+    let mut in_code = false;
+
+    for line in markdown.lines() {
+        if in_code {
+            if line.trim_start().starts_with("```") {
+                out.push_str("</code></pre>\n");
+                in_code = false;
+            } else {
+                out.push_str(&escape_html(line));
+                out.push('\n');
+            }
+            continue;
+        }
+
+        let t = line.trim();
+        if t.starts_with("```") {
+            flush_paragraph(&mut out, &mut paragraph);
+            close_list(&mut out, &mut list);
+            out.push_str("<pre><code>");
+            in_code = true;
+            continue;
+        }
+        if t.is_empty() {
+            flush_paragraph(&mut out, &mut paragraph);
+            close_list(&mut out, &mut list);
+            continue;
+        }
+        if let Some((level, body)) = heading_level(t) {
+            flush_paragraph(&mut out, &mut paragraph);
+            close_list(&mut out, &mut list);
+            out.push_str(&format!("<h{level}>{}</h{level}>\n", render_inline(body)));
+            continue;
+        }
+        if let Some(body) = t.strip_prefix("> ") {
+            flush_paragraph(&mut out, &mut paragraph);
+            close_list(&mut out, &mut list);
+            out.push_str(&format!(
+                "<blockquote>{}</blockquote>\n",
+                render_inline(body)
+            ));
+            continue;
+        }
+        let item = unordered_item(t)
+            .map(|body| (ListKind::Unordered, body))
+            .or_else(|| ordered_item(t).map(|body| (ListKind::Ordered, body)));
+        if let Some((kind, body)) = item {
+            flush_paragraph(&mut out, &mut paragraph);
+            if list != Some(kind) {
+                close_list(&mut out, &mut list);
+                out.push_str(&format!("<{}>\n", kind.tag()));
+                list = Some(kind);
+            }
+            out.push_str(&format!("<li>{}</li>\n", render_inline(body)));
+            continue;
+        }
+        close_list(&mut out, &mut list);
+        paragraph.push(render_inline(t));
+    }
+
+    if in_code {
+        out.push_str("</code></pre>\n");
+    }
+    flush_paragraph(&mut out, &mut paragraph);
+    close_list(&mut out, &mut list);
+    out.trim_end().to_string()
+}
+
+fn flush_paragraph(out: &mut String, paragraph: &mut Vec<String>) {
+    if paragraph.is_empty() {
+        return;
+    }
+    out.push_str("<p>");
+    out.push_str(&paragraph.join("<br>"));
+    out.push_str("</p>\n");
+    paragraph.clear();
+}
+
+fn close_list(out: &mut String, list: &mut Option<ListKind>) {
+    if let Some(kind) = list.take() {
+        out.push_str(&format!("</{}>\n", kind.tag()));
+    }
+}
+
+fn heading_level(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    let rest = &line[level..];
+    let body = rest.strip_prefix(' ')?;
+    Some((level, body.trim()))
+}
+
+fn unordered_item(line: &str) -> Option<&str> {
+    ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))
+        .map(str::trim)
+}
+
+fn ordered_item(line: &str) -> Option<&str> {
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 {
+        return None;
+    }
+    let rest = &line[digits..];
+    rest.strip_prefix(". ")
+        .or_else(|| rest.strip_prefix(") "))
+        .map(str::trim)
+}
+
+fn render_inline(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::new();
+    let mut i = 0usize;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '`' {
+            if let Some(end) = find_char(&chars, i + 1, '`') {
+                let code: String = chars[i + 1..end].iter().collect();
+                out.push_str("<code>");
+                out.push_str(&escape_html(&code));
+                out.push_str("</code>");
+                i = end + 1;
+                continue;
+            }
+        }
+        if c == '*' && chars.get(i + 1) == Some(&'*') {
+            if let Some(end) = find_pair(&chars, i + 2, '*') {
+                let inner: String = chars[i + 2..end].iter().collect();
+                out.push_str("<strong>");
+                out.push_str(&render_inline(&inner));
+                out.push_str("</strong>");
+                i = end + 2;
+                continue;
+            }
+        }
+        let opens_word = i == 0 || !chars[i - 1].is_alphanumeric();
+        if (c == '*' || (c == '_' && opens_word))
+            && chars.get(i + 1).is_some_and(|n| !n.is_whitespace())
+        {
+            if let Some(end) = find_char(&chars, i + 1, c) {
+                if end > i + 1 {
+                    let inner: String = chars[i + 1..end].iter().collect();
+                    out.push_str("<em>");
+                    out.push_str(&render_inline(&inner));
+                    out.push_str("</em>");
+                    i = end + 1;
+                    continue;
+                }
+            }
+        }
+        if c == '[' {
+            if let Some((label, url, next)) = parse_link(&chars, i) {
+                out.push_str(&format!(
+                    "<a href=\"{}\">{}</a>",
+                    escape_html(&url),
+                    render_inline(&label)
+                ));
+                i = next;
+                continue;
+            }
+        }
+        push_escaped(&mut out, c);
+        i += 1;
+    }
+    out
+}
+
+fn find_char(chars: &[char], from: usize, needle: char) -> Option<usize> {
+    (from..chars.len()).find(|&j| chars[j] == needle)
+}
+
+fn find_pair(chars: &[char], from: usize, needle: char) -> Option<usize> {
+    (from..chars.len().saturating_sub(1)).find(|&j| chars[j] == needle && chars[j + 1] == needle)
+}
+
+fn parse_link(chars: &[char], start: usize) -> Option<(String, String, usize)> {
+    let close = find_char(chars, start + 1, ']')?;
+    if chars.get(close + 1) != Some(&'(') {
+        return None;
+    }
+    let end = find_char(chars, close + 2, ')')?;
+    let label: String = chars[start + 1..close].iter().collect();
+    let url: String = chars[close + 2..end].iter().collect();
+    let url = url.trim();
+    if !is_safe_link(url) {
+        return None;
+    }
+    Some((label, url.to_string(), end + 1))
+}
+
+// Only web and mail links become anchors; `javascript:`, `data:` and the like
+// stay literal text. Embedded whitespace is refused since browsers strip it
+// when reading the scheme.
+fn is_safe_link(url: &str) -> bool {
+    if url.is_empty() || url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return false;
+    }
+    let lower = url.to_ascii_lowercase();
+    ["http://", "https://", "mailto:"]
+        .iter()
+        .any(|scheme| lower.starts_with(scheme))
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        push_escaped(&mut out, c);
+    }
+    out
+}
+
+fn push_escaped(out: &mut String, c: char) {
+    match c {
+        '&' => out.push_str("&amp;"),
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        '"' => out.push_str("&quot;"),
+        '\'' => out.push_str("&#39;"),
+        _ => out.push(c),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_to_html_renders_blocks_and_inline_marks() {
+        let html = markdown_to_html(
+            "# Notes\n\n- **ship** it\n- check `cfg`\n\n1. first\n2. [docs](https://x/y)\n\nplain <b> & text",
+        );
+
+        assert!(html.contains("<h1>Notes</h1>"));
+        assert!(html.contains(
+            "<ul>\n<li><strong>ship</strong> it</li>\n<li>check <code>cfg</code></li>\n</ul>"
+        ));
+        assert!(
+            html.contains("<ol>\n<li>first</li>\n<li><a href=\"https://x/y\">docs</a></li>\n</ol>")
+        );
+        assert!(html.contains("<p>plain &lt;b&gt; &amp; text</p>"));
+    }
+
+    #[test]
+    fn only_web_and_mail_links_become_anchors() {
+        assert_eq!(
+            markdown_to_html("[mail](MAILTO:a@b.c)"),
+            "<p><a href=\"MAILTO:a@b.c\">mail</a></p>"
+        );
+        for url in [
+            "javascript:alert(1)",
+            "data:text/html,x",
+            "JaVaScRiPt:x",
+            "/local",
+        ] {
+            let html = markdown_to_html(&format!("[x]({url})"));
+            assert!(!html.contains("<a "), "{url} -> {html}");
+            assert!(html.contains("[x]("), "{url} -> {html}");
+        }
+    }
+
+    #[test]
+    fn plain_text_format_never_attaches_html() {
+        let payload = build_clipboard_payload("# title", ExportFormat::PlainText);
+        assert_eq!(payload.plain, "# title");
+        assert_eq!(payload.html, None);
+
+        let payload = build_clipboard_payload("just a sentence.", ExportFormat::MarkdownHtml);
+        assert_eq!(payload.html, None);
+
+        let payload = build_clipboard_payload("- a\n- b", ExportFormat::MarkdownHtml);
+        assert_eq!(
+            payload.html.as_deref(),
+            Some("<ul>\n<li>a</li>\n<li>b</li>\n</ul>")
+        );
+    }
+
+    #[test]
+    fn export_format_parse_accepts_aliases() {
+        assert_eq!(
+            ExportFormat::parse(" Markdown "),
+            Some(ExportFormat::MarkdownHtml)
+        );
+        assert_eq!(ExportFormat::parse("plain"), Some(ExportFormat::PlainText));
+        assert_eq!(ExportFormat::parse("docx"), None);
+    }
+}
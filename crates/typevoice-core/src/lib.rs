@@ -1,2 +1,13 @@
 pub mod context_pack;
+pub mod errors;
+pub mod export_format;
+pub mod noise_profile;
 pub mod ports;
+pub mod stage_graph;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+pub mod text_casing;
+pub mod text_chunks;
+pub mod text_normalize;
+pub mod token_budget;
+pub mod wav_preprocess;
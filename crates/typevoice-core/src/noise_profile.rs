@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+
+const WINDOW_MS: usize = 50;
+const MIN_DB: f64 = -96.0;
+// Silence threshold sits this far above the measured floor so room tone is
+// trimmed but quiet speech onsets survive.
+const THRESHOLD_MARGIN_DB: f64 = 6.0;
+const THRESHOLD_MIN_DB: f64 = -70.0;
+const THRESHOLD_MAX_DB: f64 = -20.0;
+// A healthy ambient floor for dictation; outside it the input gain is nudged
+// back toward the nearest edge.
+const FLOOR_TARGET_LOW_DB: f64 = -75.0;
+const FLOOR_TARGET_HIGH_DB: f64 = -45.0;
+const GAIN_MIN_DB: f64 = -12.0;
+const GAIN_MAX_DB: f64 = 12.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoiseProfile {
+    pub duration_ms: u64,
+    pub noise_floor_db: f64,
+    pub rms_db: f64,
+    pub peak_db: f64,
+    pub suggested_silence_threshold_db: f64,
+    pub suggested_gain_db: f64,
+}
+
+// Analyzes mono s16le PCM of ambient sound. The floor is the median of 50 ms
+// window levels, so a door slam or cough during calibration does not skew it.
+pub fn analyze_ambient_pcm(pcm: &[u8], sample_rate: u32) -> Option<NoiseProfile> {
+    let samples: Vec<f64> = pcm
+        .chunks_exact(2)
+        .map(|b| f64::from(i16::from_le_bytes([b[0], b[1]])) / 32768.0)
+        .collect();
+    let window = (sample_rate as usize * WINDOW_MS / 1000).max(1);
+    if sample_rate == 0 || samples.len() < window {
+        return None;
+    }
+
+    let mut window_db: Vec<f64> = samples
+        .chunks_exact(window)
+        .map(|w| to_db((w.iter().map(|v| v * v).sum::<f64>() / w.len() as f64).sqrt()))
+        .collect();
+    window_db.sort_by(f64::total_cmp);
+    let noise_floor_db = round1(window_db[window_db.len() / 2]);
+    let rms_db = round1(to_db(
+        (samples.iter().map(|v| v * v).sum::<f64>() / samples.len() as f64).sqrt(),
+    ));
+    let peak_db = round1(to_db(samples.iter().fold(0.0, |m: f64, v| m.max(v.abs()))));
+
+    let suggested_gain_db = suggest_gain_db(noise_floor_db);
+    let suggested_silence_threshold_db = round1(
+        (noise_floor_db + suggested_gain_db + THRESHOLD_MARGIN_DB)
+            .clamp(THRESHOLD_MIN_DB, THRESHOLD_MAX_DB),
+    );
+    Some(NoiseProfile {
+        duration_ms: (samples.len() as u64 * 1000) / u64::from(sample_rate),
+        noise_floor_db,
+        rms_db,
+        peak_db,
+        suggested_silence_threshold_db,
+        suggested_gain_db,
+    })
+}
+
+fn suggest_gain_db(noise_floor_db: f64) -> f64 {
+    let gain = if noise_floor_db < FLOOR_TARGET_LOW_DB {
+        FLOOR_TARGET_LOW_DB - noise_floor_db
+    } else if noise_floor_db > FLOOR_TARGET_HIGH_DB {
+        FLOOR_TARGET_HIGH_DB - noise_floor_db
+    } else {
+        0.0
+    };
+    // Half-dB steps keep the suggestion readable in the settings UI.
+    (gain.clamp(GAIN_MIN_DB, GAIN_MAX_DB) * 2.0).round() / 2.0
+}
+
+fn to_db(amplitude: f64) -> f64 {
+    if amplitude <= 0.0 {
+        return MIN_DB;
+    }
+    (20.0 * amplitude.log10()).max(MIN_DB)
+}
+
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone_pcm(amplitude: i16, samples: usize) -> Vec<u8> {
+        (0..samples)
+            .flat_map(|i| {
+                let v = if i % 2 == 0 { amplitude } else { -amplitude };
+                v.to_le_bytes()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn quiet_room_keeps_gain_and_sets_threshold_above_floor() {
+        // ~-50 dBFS square wave, with a short loud burst that the median ignores.
+        let mut pcm = tone_pcm(104, 16_000 * 3);
+        pcm.extend(tone_pcm(20_000, 800));
+        let p = analyze_ambient_pcm(&pcm, 16_000).expect("profile");
+
+        assert_eq!(p.duration_ms, 3050);
+        assert_eq!(p.noise_floor_db, -50.0);
+        assert_eq!(p.suggested_gain_db, 0.0);
+        assert_eq!(p.suggested_silence_threshold_db, -44.0);
+        assert!(p.peak_db > -5.0);
+    }
+
+    #[test]
+    fn gain_moves_floor_toward_target_and_threshold_is_clamped() {
+        let silent = analyze_ambient_pcm(&vec![0_u8; 32_000], 16_000).expect("profile");
+        assert_eq!(silent.noise_floor_db, MIN_DB);
+        assert_eq!(silent.suggested_gain_db, GAIN_MAX_DB);
+        assert_eq!(silent.suggested_silence_threshold_db, THRESHOLD_MIN_DB);
+
+        let noisy = analyze_ambient_pcm(&tone_pcm(3_277, 16_000), 16_000).expect("profile");
+        assert_eq!(noisy.noise_floor_db, -20.0);
+        assert_eq!(noisy.suggested_gain_db, -12.0);
+        assert_eq!(noisy.suggested_silence_threshold_db, -26.0);
+
+        assert!(analyze_ambient_pcm(&[0, 0, 0], 16_000).is_none());
+    }
+}
@@ -1,5 +1,9 @@
+use std::{future::Future, path::Path, pin::Pin};
+
 use serde::Serialize;
 
+use crate::context_pack::ContextSnapshot;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct PortError {
     pub code: String,
@@ -41,6 +45,40 @@ impl std::fmt::Display for PortError {
 impl std::error::Error for PortError {}
 
 pub type PortResult<T> = std::result::Result<T, PortError>;
+pub type PortFuture<'a, T> = Pin<Box<dyn Future<Output = PortResult<T>> + Send + 'a>>;
+
+// What an ASR provider returns for one preprocessed WAV.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AsrTranscript {
+    pub text: String,
+    pub rtf: f64,
+    pub device_used: String,
+    pub asr_ms: u128,
+    pub provider_elapsed_ms: i64,
+    pub audio_seconds: f64,
+    pub model_id: String,
+    pub model_version: Option<String>,
+    pub remote_slice_count: Option<usize>,
+    pub remote_concurrency_used: Option<usize>,
+}
+
+// The ASR call the transcription graph makes once audio is preprocessed. `O`
+// is the caller's option type, so core stays free of engine settings.
+pub trait AsrClient<O: ?Sized>: Send + Sync {
+    fn transcribe<'a>(
+        &'a self,
+        data_dir: &'a Path,
+        task_id: &'a str,
+        wav_path: &'a Path,
+        opts: &'a O,
+    ) -> PortFuture<'a, AsrTranscript>;
+}
+
+// Rewrite context capture. Best effort: sections that fail to capture are left
+// empty. `C` is the platform's capture config.
+pub trait ContextCollector<C: ?Sized>: Send + Sync {
+    fn collect(&self, data_dir: &Path, task_id: &str, cfg: &C) -> ContextSnapshot;
+}
 
 pub fn parse_error_code(message: &str) -> Option<String> {
     for token in message.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_')) {
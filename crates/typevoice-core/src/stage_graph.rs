@@ -0,0 +1,340 @@
+use std::{collections::HashMap, future::Future, pin::Pin, time::Instant};
+
+use crate::ports::{PortError, PortResult};
+
+// Stage bodies borrow the run context for the duration of the stage only.
+pub type StageFuture<'r> = Pin<Box<dyn Future<Output = PortResult<()>> + Send + 'r>>;
+pub type StageRun<C> = for<'r> fn(&'r mut C) -> StageFuture<'r>;
+
+pub struct Stage<C> {
+    pub id: &'static str,
+    pub deps: &'static [&'static str],
+    // None means always enabled.
+    pub enabled: Option<fn(&C) -> bool>,
+    pub run: StageRun<C>,
+}
+
+// Where stage timings come from; `test_support::ManualClock` makes them
+// deterministic.
+pub trait StageClock: Sync {
+    fn now_ms(&self) -> u128;
+}
+
+pub struct SystemClock {
+    origin: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl StageClock for SystemClock {
+    fn now_ms(&self) -> u128 {
+        self.origin.elapsed().as_millis()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StageEvent<'a> {
+    Started,
+    Completed { elapsed_ms: u128 },
+    Skipped,
+    Failed { error: &'a PortError },
+}
+
+// A small DAG of pipeline stages. Dependencies only order stages: a disabled
+// dependency is skipped and its dependents still run, while a failed stage
+// stops the run. Ties keep registration order, so the plan is deterministic.
+pub struct StageGraph<C> {
+    stages: Vec<Stage<C>>,
+}
+
+impl<C> Default for StageGraph<C> {
+    fn default() -> Self {
+        Self { stages: Vec::new() }
+    }
+}
+
+impl<C> StageGraph<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn stage(mut self, stage: Stage<C>) -> Self {
+        self.stages.push(stage);
+        self
+    }
+
+    // Topological order of every registered stage, enabled or not.
+    pub fn order(&self) -> PortResult<Vec<&'static str>> {
+        let mut index = HashMap::new();
+        for (i, s) in self.stages.iter().enumerate() {
+            if index.insert(s.id, i).is_some() {
+                return Err(graph_error(format!("duplicate stage: {}", s.id)));
+            }
+        }
+        let mut pending = vec![0usize; self.stages.len()];
+        for (i, s) in self.stages.iter().enumerate() {
+            for dep in s.deps {
+                if !index.contains_key(dep) {
+                    return Err(graph_error(format!(
+                        "stage {} depends on unknown {dep}",
+                        s.id
+                    )));
+                }
+            }
+            pending[i] = s.deps.len();
+        }
+        let mut done = vec![false; self.stages.len()];
+        let mut order = Vec::with_capacity(self.stages.len());
+        while order.len() < self.stages.len() {
+            let Some(next) = (0..self.stages.len()).find(|&i| !done[i] && pending[i] == 0) else {
+                let stuck: Vec<&str> = (0..self.stages.len())
+                    .filter(|&i| !done[i])
+                    .map(|i| self.stages[i].id)
+                    .collect();
+                return Err(graph_error(format!(
+                    "dependency cycle among: {}",
+                    stuck.join(", ")
+                )));
+            };
+            done[next] = true;
+            order.push(self.stages[next].id);
+            let id = self.stages[next].id;
+            for (i, s) in self.stages.iter().enumerate() {
+                pending[i] -= s.deps.iter().filter(|d| **d == id).count();
+            }
+        }
+        Ok(order)
+    }
+
+    // Stages that would run for this context, in execution order.
+    pub fn plan(&self, ctx: &C) -> PortResult<Vec<&'static str>> {
+        Ok(self
+            .order()?
+            .into_iter()
+            .filter(|id| self.get(id).is_some_and(|s| is_enabled(s, ctx)))
+            .collect())
+    }
+
+    pub async fn run(
+        &self,
+        ctx: &mut C,
+        on_event: impl FnMut(&'static str, StageEvent<'_>),
+    ) -> PortResult<()> {
+        self.run_with_clock(ctx, &SystemClock::default(), on_event)
+            .await
+    }
+
+    pub async fn run_with_clock(
+        &self,
+        ctx: &mut C,
+        clock: &dyn StageClock,
+        mut on_event: impl FnMut(&'static str, StageEvent<'_>),
+    ) -> PortResult<()> {
+        for id in self.order()? {
+            let Some(stage) = self.get(id) else {
+                continue;
+            };
+            if !is_enabled(stage, ctx) {
+                on_event(id, StageEvent::Skipped);
+                continue;
+            }
+            on_event(id, StageEvent::Started);
+            let t0 = clock.now_ms();
+            match (stage.run)(ctx).await {
+                Ok(()) => on_event(
+                    id,
+                    StageEvent::Completed {
+                        elapsed_ms: clock.now_ms().saturating_sub(t0),
+                    },
+                ),
+                Err(error) => {
+                    on_event(id, StageEvent::Failed { error: &error });
+                    return Err(error);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Option<&Stage<C>> {
+        self.stages.iter().find(|s| s.id == id)
+    }
+}
+
+fn is_enabled<C>(stage: &Stage<C>, ctx: &C) -> bool {
+    stage.enabled.is_none_or(|f| f(ctx))
+}
+
+fn graph_error(message: String) -> PortError {
+    PortError::new("E_PIPELINE_GRAPH", message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Stage, StageEvent, StageFuture, StageGraph};
+    use crate::ports::PortError;
+    use crate::test_support::block_on;
+
+    struct Run<'a> {
+        input: &'a str,
+        translate: bool,
+        log: Vec<String>,
+    }
+
+    fn transcribe<'r>(run: &'r mut Run<'_>) -> StageFuture<'r> {
+        Box::pin(async move {
+            run.log.push(format!("transcribe:{}", run.input));
+            Ok(())
+        })
+    }
+
+    fn translate<'r>(run: &'r mut Run<'_>) -> StageFuture<'r> {
+        Box::pin(async move {
+            run.log.push("translate".to_string());
+            Ok(())
+        })
+    }
+
+    fn persist<'r>(run: &'r mut Run<'_>) -> StageFuture<'r> {
+        Box::pin(async move {
+            run.log.push("persist".to_string());
+            Ok(())
+        })
+    }
+
+    fn fail<'r>(_run: &'r mut Run<'_>) -> StageFuture<'r> {
+        Box::pin(async move { Err(PortError::new("E_TEST", "boom")) })
+    }
+
+    fn graph<'a>() -> StageGraph<Run<'a>> {
+        // Registered out of order on purpose; deps decide the order.
+        StageGraph::new()
+            .stage(Stage {
+                id: "persist",
+                deps: &["transcribe", "translate"],
+                enabled: None,
+                run: persist,
+            })
+            .stage(Stage {
+                id: "translate",
+                deps: &["transcribe"],
+                enabled: Some(|r: &Run<'_>| r.translate),
+                run: translate,
+            })
+            .stage(Stage {
+                id: "transcribe",
+                deps: &[],
+                enabled: None,
+                run: transcribe,
+            })
+    }
+
+    #[test]
+    fn runs_in_dependency_order_and_skips_disabled_stages() {
+        let input = String::from("a.wav");
+        let g = graph();
+        assert_eq!(
+            g.order().expect("order"),
+            vec!["transcribe", "translate", "persist"]
+        );
+
+        let mut run = Run {
+            input: &input,
+            translate: false,
+            log: Vec::new(),
+        };
+        assert_eq!(g.plan(&run).expect("plan"), vec!["transcribe", "persist"]);
+        let mut events = Vec::new();
+        block_on(g.run(&mut run, |id, ev| {
+            let tag = match ev {
+                StageEvent::Started => "started",
+                StageEvent::Completed { .. } => "completed",
+                StageEvent::Skipped => "skipped",
+                StageEvent::Failed { .. } => "failed",
+            };
+            events.push(format!("{id}:{tag}"));
+        }))
+        .expect("run");
+        assert_eq!(run.log, vec!["transcribe:a.wav", "persist"]);
+        assert!(events.contains(&"translate:skipped".to_string()));
+
+        run.translate = true;
+        run.log.clear();
+        block_on(g.run(&mut run, |_, _| {})).expect("run");
+        assert_eq!(run.log, vec!["transcribe:a.wav", "translate", "persist"]);
+    }
+
+    #[test]
+    fn failed_stage_stops_the_run() {
+        let g: StageGraph<Run<'_>> = StageGraph::new()
+            .stage(Stage {
+                id: "transcribe",
+                deps: &[],
+                enabled: None,
+                run: transcribe,
+            })
+            .stage(Stage {
+                id: "persist",
+                deps: &["postprocess"],
+                enabled: None,
+                run: persist,
+            })
+            .stage(Stage {
+                id: "postprocess",
+                deps: &["transcribe"],
+                enabled: None,
+                run: fail,
+            });
+        let mut run = Run {
+            input: "x",
+            translate: false,
+            log: Vec::new(),
+        };
+        let mut failed = None;
+        let err = block_on(g.run(&mut run, |id, ev| {
+            if let StageEvent::Failed { error } = ev {
+                failed = Some((id, error.code.clone()));
+            }
+        }))
+        .expect_err("should fail");
+        assert_eq!(err.code, "E_TEST");
+        assert_eq!(failed, Some(("postprocess", "E_TEST".to_string())));
+        assert_eq!(run.log, vec!["transcribe:x"]);
+    }
+
+    #[test]
+    fn rejects_unknown_dependencies_and_cycles() {
+        let unknown: StageGraph<Run<'_>> = StageGraph::new().stage(Stage {
+            id: "rewrite",
+            deps: &["ocr"],
+            enabled: None,
+            run: persist,
+        });
+        assert_eq!(
+            unknown.order().expect_err("unknown").code,
+            "E_PIPELINE_GRAPH"
+        );
+
+        let cycle: StageGraph<Run<'_>> = StageGraph::new()
+            .stage(Stage {
+                id: "a",
+                deps: &["b"],
+                enabled: None,
+                run: persist,
+            })
+            .stage(Stage {
+                id: "b",
+                deps: &["a"],
+                enabled: None,
+                run: persist,
+            });
+        let err = cycle.order().expect_err("cycle");
+        assert!(err.message.contains("cycle"), "{}", err.message);
+    }
+}
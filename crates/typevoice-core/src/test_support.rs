@@ -0,0 +1,396 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::context_pack::ContextSnapshot;
+use crate::ports::{AsrClient, AsrTranscript, ContextCollector, PortError, PortFuture, PortResult};
+use crate::stage_graph::{StageClock, StageEvent, StageFuture};
+
+// Fixtures for driving a `StageGraph` end to end without FFmpeg, a network
+// provider or platform APIs: scripted stage outcomes, scripted ASR and context
+// seams, a clock that only moves when a stage says so, and an event log to
+// assert against. Enabled with the `test-support` feature.
+
+pub fn block_on<F: Future>(f: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("runtime")
+        .block_on(f)
+}
+
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    now_ms: AtomicU64,
+}
+
+impl ManualClock {
+    pub fn new(start_ms: u64) -> Self {
+        Self {
+            now_ms: AtomicU64::new(start_ms),
+        }
+    }
+
+    pub fn advance(&self, ms: u64) {
+        self.now_ms.fetch_add(ms, Ordering::SeqCst);
+    }
+}
+
+impl StageClock for ManualClock {
+    fn now_ms(&self) -> u128 {
+        u128::from(self.now_ms.load(Ordering::SeqCst))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptedStep {
+    pub cost_ms: u64,
+    pub result: PortResult<()>,
+}
+
+impl ScriptedStep {
+    pub fn ok(cost_ms: u64) -> Self {
+        Self {
+            cost_ms,
+            result: Ok(()),
+        }
+    }
+
+    pub fn fail(code: &str, cost_ms: u64) -> Self {
+        Self {
+            cost_ms,
+            result: Err(PortError::new(code, "scripted failure")),
+        }
+    }
+}
+
+// Run context for `scripted` stages. Each stage that runs takes the next step,
+// in execution order; `calls` counts them.
+pub struct ScriptedRun<'c> {
+    pub clock: &'c ManualClock,
+    pub steps: VecDeque<ScriptedStep>,
+    pub calls: usize,
+}
+
+impl<'c> ScriptedRun<'c> {
+    pub fn new(clock: &'c ManualClock, steps: impl IntoIterator<Item = ScriptedStep>) -> Self {
+        Self {
+            clock,
+            steps: steps.into_iter().collect(),
+            calls: 0,
+        }
+    }
+}
+
+pub fn scripted<'r>(run: &'r mut ScriptedRun<'_>) -> StageFuture<'r> {
+    Box::pin(async move {
+        run.calls += 1;
+        let step = run.steps.pop_front().ok_or_else(|| {
+            PortError::new(
+                "E_TEST_SCRIPT_EXHAUSTED",
+                format!("no scripted step for call {}", run.calls),
+            )
+        })?;
+        run.clock.advance(step.cost_ms);
+        step.result
+    })
+}
+
+// `AsrClient` that answers each call with the next scripted result and records
+// the task ids it was called for.
+#[derive(Debug, Default)]
+pub struct ScriptedAsr {
+    results: Mutex<VecDeque<PortResult<AsrTranscript>>>,
+    pub calls: Mutex<Vec<String>>,
+}
+
+impl ScriptedAsr {
+    pub fn new(results: impl IntoIterator<Item = PortResult<AsrTranscript>>) -> Self {
+        Self {
+            results: Mutex::new(results.into_iter().collect()),
+            calls: Mutex::default(),
+        }
+    }
+
+    pub fn text(text: &str, asr_ms: u128) -> PortResult<AsrTranscript> {
+        Ok(AsrTranscript {
+            text: text.to_string(),
+            device_used: "scripted".to_string(),
+            asr_ms,
+            ..Default::default()
+        })
+    }
+}
+
+impl<O: ?Sized + Sync> AsrClient<O> for ScriptedAsr {
+    fn transcribe<'a>(
+        &'a self,
+        _data_dir: &'a Path,
+        task_id: &'a str,
+        _wav_path: &'a Path,
+        _opts: &'a O,
+    ) -> PortFuture<'a, AsrTranscript> {
+        Box::pin(async move {
+            let mut calls = self.calls.lock().unwrap();
+            calls.push(task_id.to_string());
+            self.results.lock().unwrap().pop_front().unwrap_or_else(|| {
+                Err(PortError::new(
+                    "E_TEST_SCRIPT_EXHAUSTED",
+                    format!("no scripted transcript for call {}", calls.len()),
+                ))
+            })
+        })
+    }
+}
+
+// `ContextCollector` that hands out a fixed snapshot and records the task ids
+// it was collected for.
+#[derive(Debug, Default)]
+pub struct ScriptedContext {
+    pub snapshot: ContextSnapshot,
+    pub collected: Mutex<Vec<String>>,
+}
+
+impl ScriptedContext {
+    pub fn new(snapshot: ContextSnapshot) -> Self {
+        Self {
+            snapshot,
+            collected: Mutex::default(),
+        }
+    }
+}
+
+impl<C: ?Sized> ContextCollector<C> for ScriptedContext {
+    fn collect(&self, _data_dir: &Path, task_id: &str, _cfg: &C) -> ContextSnapshot {
+        self.collected.lock().unwrap().push(task_id.to_string());
+        self.snapshot.clone()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordedEvent {
+    Started(&'static str),
+    Completed(&'static str, u128),
+    Skipped(&'static str),
+    Failed(&'static str, String),
+}
+
+// In-memory sink for `StageGraph::run` events.
+#[derive(Debug, Default)]
+pub struct EventLog {
+    pub events: Vec<RecordedEvent>,
+}
+
+impl EventLog {
+    pub fn sink(&mut self) -> impl FnMut(&'static str, StageEvent<'_>) + '_ {
+        |id, ev| {
+            self.events.push(match ev {
+                StageEvent::Started => RecordedEvent::Started(id),
+                StageEvent::Completed { elapsed_ms } => RecordedEvent::Completed(id, elapsed_ms),
+                StageEvent::Skipped => RecordedEvent::Skipped(id),
+                StageEvent::Failed { error } => RecordedEvent::Failed(id, error.code.clone()),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context_pack::{self, ContextBudget, PreparedContext};
+    use crate::stage_graph::{Stage, StageGraph};
+
+    fn graph<'c>() -> StageGraph<ScriptedRun<'c>> {
+        StageGraph::new()
+            .stage(Stage {
+                id: "Preprocess",
+                deps: &[],
+                enabled: None,
+                run: scripted,
+            })
+            .stage(Stage {
+                id: "Transcribe",
+                deps: &["Preprocess"],
+                enabled: None,
+                run: scripted,
+            })
+            .stage(Stage {
+                id: "Translate",
+                deps: &["Transcribe"],
+                enabled: Some(|_: &ScriptedRun<'_>| false),
+                run: scripted,
+            })
+            .stage(Stage {
+                id: "Persist",
+                deps: &["Transcribe"],
+                enabled: None,
+                run: scripted,
+            })
+    }
+
+    #[test]
+    fn scripted_run_reports_deterministic_timings() {
+        let clock = ManualClock::new(1_000);
+        let mut run = ScriptedRun::new(
+            &clock,
+            [
+                ScriptedStep::ok(40),
+                ScriptedStep::ok(350),
+                ScriptedStep::ok(5),
+            ],
+        );
+        let mut log = EventLog::default();
+        block_on(graph().run_with_clock(&mut run, &clock, log.sink())).expect("run");
+        assert_eq!(
+            log.events,
+            vec![
+                RecordedEvent::Started("Preprocess"),
+                RecordedEvent::Completed("Preprocess", 40),
+                RecordedEvent::Started("Transcribe"),
+                RecordedEvent::Completed("Transcribe", 350),
+                RecordedEvent::Skipped("Translate"),
+                RecordedEvent::Started("Persist"),
+                RecordedEvent::Completed("Persist", 5),
+            ]
+        );
+        assert_eq!(clock.now_ms(), 1_395);
+    }
+
+    #[test]
+    fn scripted_failure_and_exhausted_script_stop_the_run() {
+        let clock = ManualClock::default();
+        let mut run = ScriptedRun::new(
+            &clock,
+            [ScriptedStep::ok(10), ScriptedStep::fail("E_ASR_FAILED", 20)],
+        );
+        let mut log = EventLog::default();
+        let err = block_on(graph().run_with_clock(&mut run, &clock, log.sink()))
+            .expect_err("scripted failure");
+        assert_eq!(err.code, "E_ASR_FAILED");
+        assert_eq!(
+            log.events.last(),
+            Some(&RecordedEvent::Failed("Transcribe", "E_ASR_FAILED".into()))
+        );
+
+        let mut run = ScriptedRun::new(&clock, [ScriptedStep::ok(10)]);
+        let err = block_on(graph().run_with_clock(&mut run, &clock, |_, _| {}))
+            .expect_err("script runs out");
+        assert_eq!(err.code, "E_TEST_SCRIPT_EXHAUSTED");
+        assert_eq!(run.calls, 2);
+    }
+
+    // Transcribe -> context -> prompt, the path a dictation takes between
+    // preprocessing and the LLM call, driven through the two seams.
+    struct PipelineRun<'c> {
+        asr: &'c dyn AsrClient<()>,
+        context: &'c dyn ContextCollector<()>,
+        clock: &'c ManualClock,
+        transcript: Option<AsrTranscript>,
+        snapshot: Option<ContextSnapshot>,
+        prepared: Option<PreparedContext>,
+    }
+
+    fn transcribe_stage<'r>(run: &'r mut PipelineRun<'_>) -> StageFuture<'r> {
+        Box::pin(async move {
+            let transcript = run
+                .asr
+                .transcribe(Path::new("data"), "task-1", Path::new("task-1.wav"), &())
+                .await?;
+            run.clock.advance(transcript.asr_ms as u64);
+            run.transcript = Some(transcript);
+            Ok(())
+        })
+    }
+
+    fn context_stage<'r>(run: &'r mut PipelineRun<'_>) -> StageFuture<'r> {
+        Box::pin(async move {
+            run.snapshot = Some(run.context.collect(Path::new("data"), "task-1", &()));
+            Ok(())
+        })
+    }
+
+    fn prompt_stage<'r>(run: &'r mut PipelineRun<'_>) -> StageFuture<'r> {
+        Box::pin(async move {
+            let (Some(transcript), Some(snapshot)) = (&run.transcript, &run.snapshot) else {
+                return Err(PortError::new("E_INTERNAL", "prompt before inputs"));
+            };
+            run.prepared = Some(context_pack::prepare(
+                &transcript.text,
+                snapshot,
+                &ContextBudget::default(),
+                true,
+            ));
+            Ok(())
+        })
+    }
+
+    fn pipeline<'c>() -> StageGraph<PipelineRun<'c>> {
+        StageGraph::new()
+            .stage(Stage {
+                id: "Transcribe",
+                deps: &[],
+                enabled: None,
+                run: transcribe_stage,
+            })
+            .stage(Stage {
+                id: "Context",
+                deps: &["Transcribe"],
+                enabled: None,
+                run: context_stage,
+            })
+            .stage(Stage {
+                id: "Prompt",
+                deps: &["Transcribe", "Context"],
+                enabled: None,
+                run: prompt_stage,
+            })
+    }
+
+    #[test]
+    fn pipeline_runs_end_to_end_on_scripted_seams() {
+        let clock = ManualClock::default();
+        let asr = ScriptedAsr::new([
+            ScriptedAsr::text("ship the release notes", 420),
+            Err(PortError::new("E_REMOTE_ASR_HTTP", "scripted outage")),
+        ]);
+        let context = ScriptedContext::new(ContextSnapshot {
+            clipboard_text: Some("v0.1.2 changelog".to_string()),
+            ..Default::default()
+        });
+
+        let mut run = PipelineRun {
+            asr: &asr,
+            context: &context,
+            clock: &clock,
+            transcript: None,
+            snapshot: None,
+            prepared: None,
+        };
+        let mut log = EventLog::default();
+        block_on(pipeline().run_with_clock(&mut run, &clock, log.sink())).expect("run");
+        let prepared = run.prepared.expect("prompt built");
+        assert!(prepared.user_text.contains("ship the release notes"));
+        assert!(prepared.user_text.contains("v0.1.2 changelog"));
+        assert!(log
+            .events
+            .contains(&RecordedEvent::Completed("Transcribe", 420)));
+        assert_eq!(*context.collected.lock().unwrap(), vec!["task-1"]);
+
+        let mut run = PipelineRun {
+            asr: &asr,
+            context: &context,
+            clock: &clock,
+            transcript: None,
+            snapshot: None,
+            prepared: None,
+        };
+        let err = block_on(pipeline().run_with_clock(&mut run, &clock, |_, _| {}))
+            .expect_err("asr failure stops the run");
+        assert_eq!(err.code, "E_REMOTE_ASR_HTTP");
+        assert!(run.prepared.is_none());
+        assert_eq!(context.collected.lock().unwrap().len(), 1);
+        assert_eq!(asr.calls.lock().unwrap().len(), 2);
+    }
+}
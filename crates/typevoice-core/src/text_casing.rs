@@ -0,0 +1,242 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CasingStyle {
+    Preserve,
+    Sentence,
+    Lower,
+    // Title case for heading lines only; other lines get sentence case.
+    Headings,
+}
+
+impl CasingStyle {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "preserve" | "none" | "off" => Some(Self::Preserve),
+            "sentence" | "sentence_case" => Some(Self::Sentence),
+            "lower" | "lowercase" => Some(Self::Lower),
+            "headings" | "title" | "title_case" => Some(Self::Headings),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Preserve => "preserve",
+            Self::Sentence => "sentence",
+            Self::Lower => "lower",
+            Self::Headings => "headings",
+        }
+    }
+}
+
+const TITLE_SMALL_WORDS: &[&str] = &[
+    "a", "an", "and", "as", "at", "but", "by", "for", "in", "nor", "of", "on", "or", "the", "to",
+    "via", "vs",
+];
+
+// A line this short with no sentence punctuation reads as a heading.
+const HEADING_MAX_WORDS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WordCase {
+    Sentence,
+    Lower,
+    Title,
+}
+
+// Only Latin words are recased. CJK runs pass through untouched, but CJK
+// sentence terminators still start a new sentence for the Latin text after them.
+pub fn apply_casing(text: &str, style: CasingStyle) -> String {
+    let shouting = is_shouting(text);
+    match style {
+        CasingStyle::Preserve => text.to_string(),
+        CasingStyle::Sentence => recase(text, WordCase::Sentence, shouting),
+        CasingStyle::Lower => recase(text, WordCase::Lower, shouting),
+        CasingStyle::Headings => text
+            .split('\n')
+            .map(|line| {
+                let case = if is_heading_line(line) {
+                    WordCase::Title
+                } else {
+                    WordCase::Sentence
+                };
+                recase(line, case, shouting)
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+fn is_heading_line(line: &str) -> bool {
+    let words = line.split_whitespace().count();
+    words > 0 && words <= HEADING_MAX_WORDS && !line.chars().any(is_sentence_terminator)
+}
+
+fn recase(text: &str, case: WordCase, shouting: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut sentence_start = true;
+    let mut first_in_line = true;
+    let mut word = String::new();
+    for c in text.chars() {
+        if is_word_char(c) {
+            word.push(c);
+            continue;
+        }
+        if !word.is_empty() {
+            out.push_str(&recase_word(
+                &word,
+                case,
+                sentence_start,
+                first_in_line,
+                shouting,
+            ));
+            word.clear();
+            sentence_start = false;
+            first_in_line = false;
+        }
+        if is_cjk(c) {
+            sentence_start = false;
+            first_in_line = false;
+        }
+        if is_sentence_terminator(c) {
+            sentence_start = true;
+        }
+        if c == '\n' {
+            sentence_start = true;
+            first_in_line = true;
+        }
+        out.push(c);
+    }
+    if !word.is_empty() {
+        out.push_str(&recase_word(
+            &word,
+            case,
+            sentence_start,
+            first_in_line,
+            shouting,
+        ));
+    }
+    out
+}
+
+// Sentence case only raises sentence starts and "I"; words already written
+// with capitals ("John", "iPhone") are kept unless the whole text is shouted.
+fn recase_word(
+    word: &str,
+    case: WordCase,
+    sentence_start: bool,
+    first_in_line: bool,
+    shouting: bool,
+) -> String {
+    if !shouting && is_acronym(word) {
+        return word.to_string();
+    }
+    let lower = word.to_lowercase();
+    let base = if shouting {
+        lower.clone()
+    } else {
+        word.to_string()
+    };
+    match case {
+        WordCase::Lower => lower,
+        WordCase::Sentence if lower == "i" || lower.starts_with("i'") => capitalize(&lower),
+        WordCase::Sentence if sentence_start && (shouting || base == lower) => capitalize(&lower),
+        WordCase::Sentence => base,
+        WordCase::Title if !first_in_line && TITLE_SMALL_WORDS.contains(&lower.as_str()) => lower,
+        WordCase::Title => capitalize(&lower),
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// ASR sometimes returns a whole utterance in capitals; acronym detection is
+// meaningless there, so everything folds.
+fn is_shouting(text: &str) -> bool {
+    let (upper, total) = text
+        .chars()
+        .filter(|c| c.is_alphabetic() && !is_cjk(*c))
+        .fold((0usize, 0usize), |(u, t), c| {
+            (u + usize::from(c.is_uppercase()), t + 1)
+        });
+    total >= 8 && upper * 10 >= total * 7
+}
+
+// "API", "GPU2" and similar tokens keep their casing; a lone capital letter
+// does not count.
+fn is_acronym(word: &str) -> bool {
+    let letters: Vec<char> = word.chars().filter(|c| c.is_alphabetic()).collect();
+    letters.len() >= 2 && letters.iter().all(|c| c.is_uppercase())
+}
+
+fn is_word_char(c: char) -> bool {
+    (c.is_alphanumeric() && !is_cjk(c)) || c == '\''
+}
+
+fn is_sentence_terminator(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '。' | '！' | '？')
+}
+
+pub(crate) fn is_cjk(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sentence_case_handles_mixed_cjk_and_latin() {
+        assert_eq!(
+            apply_casing(
+                "hello there. this is great! 我们用 Docker 部署。then ship the API",
+                CasingStyle::Sentence
+            ),
+            "Hello there. This is great! 我们用 Docker 部署。Then ship the API"
+        );
+        assert_eq!(
+            apply_casing("i met John in London with Docker", CasingStyle::Sentence),
+            "I met John in London with Docker"
+        );
+        assert_eq!(
+            apply_casing("iPhone sales. eBay too", CasingStyle::Sentence),
+            "iPhone sales. eBay too"
+        );
+        assert_eq!(
+            apply_casing("HELLO THERE. THIS IS THE API", CasingStyle::Sentence),
+            "Hello there. This is the api"
+        );
+        assert_eq!(
+            apply_casing("so i think i'm done", CasingStyle::Sentence),
+            "So I think I'm done"
+        );
+    }
+
+    #[test]
+    fn headings_and_lower_case_keep_acronyms() {
+        assert_eq!(
+            apply_casing("release notes for the GPU scheduler", CasingStyle::Headings),
+            "Release Notes for the GPU Scheduler"
+        );
+        assert_eq!(
+            apply_casing(
+                "release notes\nwe met john at the office. it went well",
+                CasingStyle::Headings
+            ),
+            "Release Notes\nWe met john at the office. It went well"
+        );
+        assert_eq!(CasingStyle::parse("title"), Some(CasingStyle::Headings));
+        assert_eq!(
+            apply_casing("Ship The NEW Build 中文", CasingStyle::Lower),
+            "ship the NEW build 中文"
+        );
+        assert_eq!(apply_casing("MiXeD", CasingStyle::Preserve), "MiXeD");
+    }
+}
@@ -0,0 +1,123 @@
+use crate::text_casing::is_cjk;
+
+// Rough token estimate for budgeting LLM calls: a CJK character is about one
+// token, other text about four characters per token. Counted in quarter tokens
+// so chunking can accumulate it per character.
+fn char_cost_quarters(c: char) -> usize {
+    if is_cjk(c) {
+        4
+    } else {
+        1
+    }
+}
+
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars()
+        .map(char_cost_quarters)
+        .sum::<usize>()
+        .div_ceil(4)
+}
+
+fn is_chunk_boundary(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | ';' | '\n' | '。' | '！' | '？' | '；')
+}
+
+// Sentences keep their terminator and any whitespace that follows it.
+fn sentences(text: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut start = 0usize;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if !is_chunk_boundary(c) {
+            continue;
+        }
+        let mut end = i + c.len_utf8();
+        while let Some(&(j, next)) = chars.peek() {
+            if !next.is_whitespace() {
+                break;
+            }
+            end = j + next.len_utf8();
+            chars.next();
+        }
+        out.push(&text[start..end]);
+        start = end;
+    }
+    if start < text.len() {
+        out.push(&text[start..]);
+    }
+    out
+}
+
+// Splits at sentence ends so each chunk stays within `max_tokens`; a single
+// run-on sentence over the budget is cut at character boundaries.
+pub fn split_by_tokens(text: &str, max_tokens: usize) -> Vec<String> {
+    let budget = max_tokens.max(1) * 4;
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut used = 0usize;
+    let mut flush = |current: &mut String, used: &mut usize| {
+        let chunk = current.trim();
+        if !chunk.is_empty() {
+            chunks.push(chunk.to_string());
+        }
+        current.clear();
+        *used = 0;
+    };
+    for sentence in sentences(text) {
+        let cost: usize = sentence.chars().map(char_cost_quarters).sum();
+        if used + cost <= budget {
+            current.push_str(sentence);
+            used += cost;
+            continue;
+        }
+        flush(&mut current, &mut used);
+        if cost <= budget {
+            current.push_str(sentence);
+            used = cost;
+            continue;
+        }
+        for c in sentence.chars() {
+            let c_cost = char_cost_quarters(c);
+            if used + c_cost > budget {
+                flush(&mut current, &mut used);
+            }
+            current.push(c);
+            used += c_cost;
+        }
+    }
+    flush(&mut current, &mut used);
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_tokens_weights_cjk_higher() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+        assert_eq!(estimate_tokens("你好"), 2);
+    }
+
+    #[test]
+    fn split_by_tokens_prefers_sentence_boundaries() {
+        let text = "第一句话。第二句话。 Third one here. 第四句";
+        let chunks = split_by_tokens(text, 10);
+        assert_eq!(
+            chunks,
+            vec!["第一句话。第二句话。", "Third one here. 第四句"]
+        );
+        assert!(chunks.iter().all(|c| estimate_tokens(c) <= 10));
+        assert_eq!(split_by_tokens(text, 1_000), vec![text.to_string()]);
+    }
+
+    #[test]
+    fn split_by_tokens_hard_cuts_run_on_sentences() {
+        let text = "一二三四五六七八九十";
+        let chunks = split_by_tokens(text, 4);
+        assert_eq!(chunks, vec!["一二三四", "五六七八", "九十"]);
+        assert!(split_by_tokens("   ", 4).is_empty());
+    }
+}
@@ -0,0 +1,719 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizeLocale {
+    Zh,
+    En,
+}
+
+impl NormalizeLocale {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "zh" | "zh-cn" | "zh_cn" | "zh-hans" | "cn" => Some(Self::Zh),
+            "en" | "en-us" | "en_us" | "en-gb" | "en_gb" => Some(Self::En),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Zh => "zh",
+            Self::En => "en",
+        }
+    }
+}
+
+struct CurrencyRule {
+    word: &'static str,
+    symbol: &'static str,
+}
+
+// "块" and "元" are also ordinary words ("一块去", "一元二次方程"), so on their own
+// they only count as money when the sentence says so.
+const ZH_BARE_CURRENCY: &[&str] = &["块", "元"];
+const ZH_MONEY_CUES: &[&str] = &[
+    "钱", "价", "花了", "付", "买", "卖", "一共", "总共", "共计", "费", "工资", "收入", "赚", "贵",
+    "便宜",
+];
+
+// Longest words first so "块钱" wins over "块".
+const ZH_CURRENCY: &[CurrencyRule] = &[
+    CurrencyRule {
+        word: "块钱",
+        symbol: "¥",
+    },
+    CurrencyRule {
+        word: "人民币",
+        symbol: "¥",
+    },
+    CurrencyRule {
+        word: "美元",
+        symbol: "$",
+    },
+    CurrencyRule {
+        word: "欧元",
+        symbol: "€",
+    },
+    CurrencyRule {
+        word: "英镑",
+        symbol: "£",
+    },
+    CurrencyRule {
+        word: "块",
+        symbol: "¥",
+    },
+    CurrencyRule {
+        word: "元",
+        symbol: "¥",
+    },
+];
+
+const EN_CURRENCY: &[CurrencyRule] = &[
+    CurrencyRule {
+        word: "dollars",
+        symbol: "$",
+    },
+    CurrencyRule {
+        word: "dollar",
+        symbol: "$",
+    },
+    CurrencyRule {
+        word: "bucks",
+        symbol: "$",
+    },
+    CurrencyRule {
+        word: "euros",
+        symbol: "€",
+    },
+    CurrencyRule {
+        word: "euro",
+        symbol: "€",
+    },
+    CurrencyRule {
+        word: "yuan",
+        symbol: "¥",
+    },
+    CurrencyRule {
+        word: "rmb",
+        symbol: "¥",
+    },
+];
+
+const EN_MONTHS: &[&str] = &[
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+// Only numbers in a recognizable context (money, dates, percentages) are
+// rewritten for Chinese; bare numerals such as "一起" or "万一" stay as spoken.
+// English number phrases of ten and above also become digits.
+pub fn normalize_spoken(text: &str, locales: &[NormalizeLocale]) -> String {
+    let mut out = text.to_string();
+    for locale in locales {
+        out = match locale {
+            NormalizeLocale::Zh => normalize_zh(&out),
+            NormalizeLocale::En => normalize_en(&out),
+        };
+    }
+    out
+}
+
+struct ZhNumber {
+    int: u64,
+    frac: Option<String>,
+    end: usize,
+}
+
+impl ZhNumber {
+    fn render(&self) -> String {
+        match &self.frac {
+            Some(frac) => format!("{}.{frac}", self.int),
+            None => self.int.to_string(),
+        }
+    }
+}
+
+fn normalize_zh(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0usize;
+    while i < chars.len() {
+        if starts_with_at(&chars, i, "百分之") {
+            if let Some(num) = parse_zh_number(&chars, i + 3) {
+                out.push_str(&format!("{}%", num.render()));
+                i = num.end;
+                continue;
+            }
+        }
+        if is_zh_number_char(chars[i]) && (i == 0 || !is_zh_number_char(chars[i - 1])) {
+            if let Some(num) = parse_zh_number(&chars, i) {
+                if let Some((rendered, next)) = zh_number_in_context(&chars, i, &num, &out) {
+                    out.push_str(&rendered);
+                    i = next;
+                } else {
+                    out.extend(&chars[i..num.end]);
+                    i = num.end;
+                }
+                continue;
+            }
+        }
+        out.push(chars[i]);
+        i += 1;
+    }
+    out
+}
+
+fn zh_number_in_context(
+    chars: &[char],
+    start: usize,
+    num: &ZhNumber,
+    out: &str,
+) -> Option<(String, usize)> {
+    let end = num.end;
+    for rule in ZH_CURRENCY {
+        if !starts_with_at(chars, end, rule.word) {
+            continue;
+        }
+        let bare = ZH_BARE_CURRENCY.contains(&rule.word);
+        if bare && !has_money_cue(chars, start, end) {
+            break;
+        }
+        let mut next = end + rule.word.chars().count();
+        let mut amount = num.render();
+        // "三块五" / "三块五毛钱": a trailing digit is the tenths place.
+        if num.frac.is_none() {
+            if let Some(d) = chars
+                .get(next)
+                .and_then(|c| zh_digit(*c))
+                .filter(|d| *d > 0)
+            {
+                let after = chars.get(next + 1).copied();
+                let has_jiao = matches!(after, Some('毛') | Some('角'));
+                if has_jiao || after.is_none_or(|c| !is_cjk_ideograph(c)) {
+                    amount = format!("{}.{d}", num.int);
+                    next += 1;
+                    if has_jiao {
+                        next += 1;
+                        if chars.get(next) == Some(&'钱') {
+                            next += 1;
+                        }
+                    }
+                }
+            }
+        }
+        // "一块儿", "一块去": the word runs on, so it is not an amount.
+        if bare && chars.get(next).is_some_and(|c| is_cjk_ideograph(*c)) {
+            break;
+        }
+        return Some((format!("{}{amount}", rule.symbol), next));
+    }
+    if num.frac.is_some() {
+        return None;
+    }
+    match chars.get(end) {
+        Some('年') if (1000..=2999).contains(&num.int) => {
+            Some((format!("{}年", num.int), end + 1))
+        }
+        Some('月') if (1..=12).contains(&num.int) => Some((format!("{}月", num.int), end + 1)),
+        Some('日') | Some('号') if (1..=31).contains(&num.int) && out.ends_with('月') => {
+            Some((format!("{}日", num.int), end + 1))
+        }
+        _ => None,
+    }
+}
+
+// Looks for a money word anywhere in the sentence around the number.
+fn has_money_cue(chars: &[char], start: usize, end: usize) -> bool {
+    let is_break = |c: &char| matches!(c, '。' | '！' | '？' | '；' | '!' | '?' | ';' | '\n');
+    let from = chars[..start]
+        .iter()
+        .rposition(is_break)
+        .map_or(0, |p| p + 1);
+    let to = chars[end..]
+        .iter()
+        .position(is_break)
+        .map_or(chars.len(), |p| end + p);
+    let sentence: String = chars[from..to].iter().collect();
+    ZH_MONEY_CUES.iter().any(|cue| sentence.contains(cue))
+}
+
+fn parse_zh_number(chars: &[char], start: usize) -> Option<ZhNumber> {
+    let mut end = start;
+    while end < chars.len() && is_zh_number_char(chars[end]) {
+        end += 1;
+    }
+    if end == start {
+        return None;
+    }
+    let run = &chars[start..end];
+    let int = if run.iter().all(char::is_ascii_digit) {
+        run.iter().collect::<String>().parse().ok()?
+    } else if run.iter().any(char::is_ascii_digit) {
+        return None;
+    } else if !run.iter().any(|c| zh_unit(*c).is_some()) {
+        run.iter()
+            .map(|c| zh_digit(*c).map(|d| char::from(b'0' + d as u8)))
+            .collect::<Option<String>>()?
+            .parse()
+            .ok()?
+    } else {
+        parse_zh_units(run)?
+    };
+    let mut frac = None;
+    if chars.get(end) == Some(&'点') {
+        let digits: String = chars[end + 1..]
+            .iter()
+            .map_while(|c| {
+                c.is_ascii_digit()
+                    .then_some(*c)
+                    .or_else(|| zh_digit(*c).map(|d| char::from(b'0' + d as u8)))
+            })
+            .collect();
+        if !digits.is_empty() {
+            end += 1 + digits.chars().count();
+            frac = Some(digits);
+        }
+    }
+    Some(ZhNumber { int, frac, end })
+}
+
+fn parse_zh_units(run: &[char]) -> Option<u64> {
+    let (mut total, mut high, mut section, mut number) = (0u64, 0u64, 0u64, 0u64);
+    let mut pending = false;
+    let mut last_unit = 0u64;
+    for &c in run {
+        if let Some(d) = zh_digit(c) {
+            number = d;
+            pending = true;
+            continue;
+        }
+        let unit = zh_unit(c)?;
+        match unit {
+            10 | 100 | 1000 => {
+                let n = match (pending, unit) {
+                    (true, _) => number,
+                    (false, 10) => 1,
+                    (false, _) => return None,
+                };
+                section = section.saturating_add(n.saturating_mul(unit));
+            }
+            10_000 => {
+                high = high.saturating_add(section.saturating_add(number).saturating_mul(unit));
+                section = 0;
+            }
+            _ => {
+                total = total
+                    .saturating_add(high)
+                    .saturating_add(section)
+                    .saturating_add(number)
+                    .saturating_mul(unit);
+                high = 0;
+                section = 0;
+            }
+        }
+        number = 0;
+        pending = false;
+        last_unit = unit;
+    }
+    // Colloquial "两百五" (250) / "一万五" (15000): a digit right after a unit
+    // of a hundred or more means the next lower place.
+    let n = run.len();
+    if pending && n >= 2 && zh_unit(run[n - 2]).is_some() && last_unit >= 100 {
+        number = number.saturating_mul(last_unit / 10);
+    }
+    Some(
+        total
+            .saturating_add(high)
+            .saturating_add(section)
+            .saturating_add(number),
+    )
+}
+
+fn zh_digit(c: char) -> Option<u64> {
+    Some(match c {
+        '零' | '〇' => 0,
+        '一' => 1,
+        '二' | '两' => 2,
+        '三' => 3,
+        '四' => 4,
+        '五' => 5,
+        '六' => 6,
+        '七' => 7,
+        '八' => 8,
+        '九' => 9,
+        _ => return None,
+    })
+}
+
+fn zh_unit(c: char) -> Option<u64> {
+    Some(match c {
+        '十' => 10,
+        '百' => 100,
+        '千' => 1000,
+        '万' => 10_000,
+        '亿' => 100_000_000,
+        _ => return None,
+    })
+}
+
+fn is_zh_number_char(c: char) -> bool {
+    c.is_ascii_digit() || zh_digit(c).is_some() || zh_unit(c).is_some()
+}
+
+fn is_cjk_ideograph(c: char) -> bool {
+    matches!(c as u32, 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF)
+}
+
+fn starts_with_at(chars: &[char], at: usize, word: &str) -> bool {
+    let mut idx = at;
+    for w in word.chars() {
+        if chars.get(idx) != Some(&w) {
+            return false;
+        }
+        idx += 1;
+    }
+    true
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EnKind {
+    None,
+    Unit,
+    Teen,
+    Tens,
+    Hundred,
+    Scale,
+}
+
+enum EnWord {
+    Zero,
+    Unit(u64),
+    Teen(u64),
+    Tens(u64),
+    Hundred,
+    Scale(u64),
+    Ordinal(u64),
+}
+
+struct EnNumber {
+    value: u64,
+    last: usize,
+    ordinal: bool,
+}
+
+fn normalize_en(text: &str) -> String {
+    let tokens = tokenize_en(text);
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0usize;
+    while i < tokens.len() {
+        if is_en_word(tokens[i]) {
+            if let Some((rendered, next)) = en_date(&tokens, i).or_else(|| en_number(&tokens, i)) {
+                out.push_str(&rendered);
+                i = next;
+                continue;
+            }
+        }
+        out.push_str(tokens[i]);
+        i += 1;
+    }
+    out
+}
+
+fn en_date(tokens: &[&str], i: usize) -> Option<(String, usize)> {
+    let month = EN_MONTHS
+        .iter()
+        .find(|m| m.eq_ignore_ascii_case(tokens[i]))?;
+    let day_idx = next_word(tokens, i)?;
+    let day = parse_en_number(tokens, day_idx, true)?;
+    if !(1..=31).contains(&day.value) {
+        return None;
+    }
+    // "may" is far more often the verb; only an ordinal makes it a date.
+    if *month == "May" && !day.ordinal {
+        return None;
+    }
+    Some((format!("{month} {}", day.value), day.last + 1))
+}
+
+fn en_number(tokens: &[&str], i: usize) -> Option<(String, usize)> {
+    let num = parse_en_number(tokens, i, false)?;
+    if let Some(k) = next_word(tokens, num.last) {
+        let word = tokens[k].to_ascii_lowercase();
+        if word == "percent" {
+            return Some((format!("{}%", num.value), k + 1));
+        }
+        if let Some(rule) = EN_CURRENCY.iter().find(|r| r.word == word) {
+            if let Some((cents, next)) = en_cents(tokens, k) {
+                return Some((format!("{}{}.{cents:02}", rule.symbol, num.value), next));
+            }
+            return Some((format!("{}{}", rule.symbol, num.value), k + 1));
+        }
+    }
+    if num.value >= 10 {
+        return Some((num.value.to_string(), num.last + 1));
+    }
+    None
+}
+
+fn en_cents(tokens: &[&str], currency_idx: usize) -> Option<(u64, usize)> {
+    let and_idx = next_word(tokens, currency_idx)?;
+    if !tokens[and_idx].eq_ignore_ascii_case("and") {
+        return None;
+    }
+    let num = parse_en_number(tokens, next_word(tokens, and_idx)?, false)?;
+    if num.value >= 100 {
+        return None;
+    }
+    let unit_idx = next_word(tokens, num.last)?;
+    let unit = tokens[unit_idx].to_ascii_lowercase();
+    (unit == "cents" || unit == "cent").then_some((num.value, unit_idx + 1))
+}
+
+fn parse_en_number(tokens: &[&str], start: usize, allow_ordinal: bool) -> Option<EnNumber> {
+    let (mut total, mut current) = (0u64, 0u64);
+    let mut last = EnKind::None;
+    let mut last_idx = None;
+    let mut ordinal = false;
+    let mut idx = start;
+    loop {
+        let word = tokens[idx].to_ascii_lowercase();
+        if word == "and" && matches!(last, EnKind::Hundred | EnKind::Scale) {
+            match next_word(tokens, idx) {
+                Some(n)
+                    if matches!(
+                        classify_en(&tokens[n].to_ascii_lowercase()),
+                        Some(EnWord::Unit(_) | EnWord::Teen(_) | EnWord::Tens(_))
+                    ) =>
+                {
+                    idx = n;
+                    continue;
+                }
+                _ => break,
+            }
+        }
+        match classify_en(&word) {
+            Some(EnWord::Zero) if last == EnKind::None => {
+                last_idx = Some(idx);
+                break;
+            }
+            Some(EnWord::Unit(n))
+                if matches!(
+                    last,
+                    EnKind::None | EnKind::Tens | EnKind::Hundred | EnKind::Scale
+                ) =>
+            {
+                current += n;
+                last = EnKind::Unit;
+            }
+            Some(EnWord::Teen(n))
+                if matches!(last, EnKind::None | EnKind::Hundred | EnKind::Scale) =>
+            {
+                current += n;
+                last = EnKind::Teen;
+            }
+            Some(EnWord::Tens(n))
+                if matches!(last, EnKind::None | EnKind::Hundred | EnKind::Scale) =>
+            {
+                current += n;
+                last = EnKind::Tens;
+            }
+            Some(EnWord::Hundred) if matches!(last, EnKind::Unit | EnKind::Teen) => {
+                current *= 100;
+                last = EnKind::Hundred;
+            }
+            Some(EnWord::Scale(s))
+                if matches!(
+                    last,
+                    EnKind::Unit | EnKind::Teen | EnKind::Tens | EnKind::Hundred
+                ) =>
+            {
+                total += current * s;
+                current = 0;
+                last = EnKind::Scale;
+            }
+            Some(EnWord::Ordinal(n))
+                if allow_ordinal && matches!(last, EnKind::None | EnKind::Tens) =>
+            {
+                current += n;
+                ordinal = true;
+                last_idx = Some(idx);
+                break;
+            }
+            _ => break,
+        }
+        last_idx = Some(idx);
+        match next_word(tokens, idx) {
+            Some(n) => idx = n,
+            None => break,
+        }
+    }
+    Some(EnNumber {
+        value: total + current,
+        last: last_idx?,
+        ordinal,
+    })
+}
+
+fn classify_en(word: &str) -> Option<EnWord> {
+    const UNITS: &[&str] = &[
+        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+    ];
+    const TEENS: &[&str] = &[
+        "ten",
+        "eleven",
+        "twelve",
+        "thirteen",
+        "fourteen",
+        "fifteen",
+        "sixteen",
+        "seventeen",
+        "eighteen",
+        "nineteen",
+    ];
+    const TENS: &[&str] = &[
+        "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+    ];
+    const ORDINALS: &[&str] = &[
+        "first",
+        "second",
+        "third",
+        "fourth",
+        "fifth",
+        "sixth",
+        "seventh",
+        "eighth",
+        "ninth",
+        "tenth",
+        "eleventh",
+        "twelfth",
+        "thirteenth",
+        "fourteenth",
+        "fifteenth",
+        "sixteenth",
+        "seventeenth",
+        "eighteenth",
+        "nineteenth",
+        "twentieth",
+    ];
+    if word == "zero" {
+        return Some(EnWord::Zero);
+    }
+    if let Some(p) = UNITS.iter().position(|w| *w == word) {
+        return Some(EnWord::Unit(p as u64 + 1));
+    }
+    if let Some(p) = TEENS.iter().position(|w| *w == word) {
+        return Some(EnWord::Teen(p as u64 + 10));
+    }
+    if let Some(p) = TENS.iter().position(|w| *w == word) {
+        return Some(EnWord::Tens((p as u64 + 2) * 10));
+    }
+    if let Some(p) = ORDINALS.iter().position(|w| *w == word) {
+        return Some(EnWord::Ordinal(p as u64 + 1));
+    }
+    match word {
+        "thirtieth" => Some(EnWord::Ordinal(30)),
+        "hundred" => Some(EnWord::Hundred),
+        "thousand" => Some(EnWord::Scale(1_000)),
+        "million" => Some(EnWord::Scale(1_000_000)),
+        _ => None,
+    }
+}
+
+fn tokenize_en(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0usize;
+    let mut in_word = None;
+    for (idx, c) in text.char_indices() {
+        let is_word = c.is_ascii_alphabetic();
+        if in_word.is_some_and(|w| w != is_word) {
+            tokens.push(&text[start..idx]);
+            start = idx;
+        }
+        in_word = Some(is_word);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+fn is_en_word(token: &str) -> bool {
+    token
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic())
+}
+
+fn next_word(tokens: &[&str], idx: usize) -> Option<usize> {
+    let sep = tokens.get(idx + 1)?;
+    if *sep != " " && *sep != "-" {
+        return None;
+    }
+    tokens
+        .get(idx + 2)
+        .filter(|t| is_en_word(t))
+        .map(|_| idx + 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zh_rules_convert_money_dates_and_percent_only() {
+        let zh = [NormalizeLocale::Zh];
+        assert_eq!(normalize_spoken("一共三百五十块", &zh), "一共¥350");
+        assert_eq!(normalize_spoken("三块五毛钱", &zh), "¥3.5");
+        assert_eq!(normalize_spoken("两百五十美元。", &zh), "$250。");
+        assert_eq!(
+            normalize_spoken("二零二六年三月五号开会", &zh),
+            "2026年3月5日开会"
+        );
+        assert_eq!(normalize_spoken("增长了百分之十二点五", &zh), "增长了12.5%");
+        assert_eq!(
+            normalize_spoken("我们一起去，万一下雨", &zh),
+            "我们一起去，万一下雨"
+        );
+        assert_eq!(normalize_spoken("三个月", &zh), "三个月");
+        assert_eq!(normalize_spoken("我们一块去吃饭", &zh), "我们一块去吃饭");
+        assert_eq!(normalize_spoken("一块儿走", &zh), "一块儿走");
+        assert_eq!(
+            normalize_spoken("这是一元二次方程", &zh),
+            "这是一元二次方程"
+        );
+        assert_eq!(normalize_spoken("这个花了五十块", &zh), "这个花了¥50");
+        assert_eq!(normalize_spoken("价格是十元", &zh), "价格是¥10");
+        assert_eq!(
+            normalize_spoken("花了十块，我们一块去", &zh),
+            "花了¥10，我们一块去"
+        );
+    }
+
+    #[test]
+    fn en_rules_convert_dates_money_and_large_numbers() {
+        let en = [NormalizeLocale::En];
+        assert_eq!(normalize_spoken("due march fifth", &en), "due March 5");
+        assert_eq!(
+            normalize_spoken("it costs twenty-five dollars and fifty cents", &en),
+            "it costs $25.50"
+        );
+        assert_eq!(
+            normalize_spoken("about one hundred and twenty three people", &en),
+            "about 123 people"
+        );
+        assert_eq!(normalize_spoken("fifteen percent off", &en), "15% off");
+        assert_eq!(
+            normalize_spoken("I may one day take one second", &en),
+            "I may one day take one second"
+        );
+        assert_eq!(normalize_spoken("May twenty first", &en), "May 21");
+    }
+}
@@ -0,0 +1,199 @@
+use serde::Serialize;
+
+use crate::context_pack::{self, ContextBudget, ContextSnapshot, PreparedContext};
+use crate::text_chunks::estimate_tokens;
+
+// Rewrite requests go out as OpenAI-compatible chat completions, so images are
+// priced with its high-detail formula: fit within 2048x2048, scale the short
+// side down to 768, then 85 base tokens plus 170 per 512px tile.
+const IMAGE_BASE_TOKENS: usize = 85;
+const IMAGE_TILE_TOKENS: usize = 170;
+const IMAGE_TILE_PX: f64 = 512.0;
+const IMAGE_MAX_SIDE_PX: f64 = 2048.0;
+const IMAGE_SHORT_SIDE_PX: f64 = 768.0;
+
+pub fn estimate_image_tokens(width: u32, height: u32) -> usize {
+    if width == 0 || height == 0 {
+        return 0;
+    }
+    let (mut w, mut h) = (f64::from(width), f64::from(height));
+    let fit = (IMAGE_MAX_SIDE_PX / w.max(h)).min(1.0);
+    w *= fit;
+    h *= fit;
+    let short = (IMAGE_SHORT_SIDE_PX / w.min(h)).min(1.0);
+    w *= short;
+    h *= short;
+    let tiles = (w / IMAGE_TILE_PX).ceil() as usize * (h / IMAGE_TILE_PX).ceil() as usize;
+    IMAGE_BASE_TOKENS + IMAGE_TILE_TOKENS * tiles
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TokenEstimate {
+    pub system: usize,
+    pub transcript: usize,
+    pub context: usize,
+    pub image: usize,
+    pub total: usize,
+}
+
+// Fixed parts of the request that the cap cannot shrink. When the transcript
+// is rewritten in chunks only one chunk goes out per request, so
+// `transcript_limit` caps what the transcript counts for.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestShape {
+    pub system_tokens: usize,
+    pub transcript_limit: Option<usize>,
+}
+
+pub fn estimate(asr_text: &str, prepared: &PreparedContext, shape: RequestShape) -> TokenEstimate {
+    let full = estimate_tokens(asr_text.trim());
+    let transcript = shape.transcript_limit.map_or(full, |limit| full.min(limit));
+    let context = estimate_tokens(&prepared.user_text).saturating_sub(full);
+    let image = prepared
+        .screenshot
+        .as_ref()
+        .map_or(0, |sc| estimate_image_tokens(sc.width, sc.height));
+    TokenEstimate {
+        system: shape.system_tokens,
+        transcript,
+        context,
+        image,
+        total: shape.system_tokens + transcript + context + image,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DroppedSection {
+    pub section: &'static str,
+    pub tokens_before: usize,
+    pub tokens_after: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct BudgetedContext {
+    pub prepared: PreparedContext,
+    pub estimate: TokenEstimate,
+    pub dropped: Vec<DroppedSection>,
+}
+
+impl BudgetedContext {
+    pub fn over_cap(&self, cap: Option<usize>) -> bool {
+        cap.is_some_and(|cap| self.estimate.total > cap)
+    }
+}
+
+// Clears one section of the snapshot; false when it was already empty.
+type DropSection = fn(&mut ContextSnapshot) -> bool;
+
+// Prepares the context and, while the estimate is over `cap`, drops whole
+// sections in order of cost to usefulness: screenshot, clipboard, then
+// history. The transcript and window title are never dropped, so the result
+// can still be over the cap; the caller decides whether to send it.
+pub fn fit_to_cap(
+    asr_text: &str,
+    snap: &ContextSnapshot,
+    budget: &ContextBudget,
+    injection_guard: bool,
+    shape: RequestShape,
+    cap: Option<usize>,
+) -> BudgetedContext {
+    let mut snap = snap.clone();
+    let prepared = context_pack::prepare(asr_text, &snap, budget, injection_guard);
+    let mut out = BudgetedContext {
+        estimate: estimate(asr_text, &prepared, shape),
+        prepared,
+        dropped: Vec::new(),
+    };
+    let Some(cap) = cap else {
+        return out;
+    };
+    let steps: [(&'static str, DropSection); 3] = [
+        ("screenshot", |s| s.screenshot.take().is_some()),
+        ("clipboard", |s| s.clipboard_text.take().is_some()),
+        ("history", |s| {
+            !std::mem::take(&mut s.recent_history).is_empty()
+        }),
+    ];
+    for (section, drop_section) in steps {
+        if out.estimate.total <= cap {
+            break;
+        }
+        if !drop_section(&mut snap) {
+            continue;
+        }
+        let tokens_before = out.estimate.total;
+        out.prepared = context_pack::prepare(asr_text, &snap, budget, injection_guard);
+        out.estimate = estimate(asr_text, &out.prepared, shape);
+        out.dropped.push(DroppedSection {
+            section,
+            tokens_before,
+            tokens_after: out.estimate.total,
+        });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context_pack::{HistorySnippet, ScreenshotPng};
+
+    fn snapshot() -> ContextSnapshot {
+        ContextSnapshot {
+            recent_history: vec![HistorySnippet {
+                created_at_ms: 1,
+                asr_text: String::new(),
+                final_text: "earlier dictation ".repeat(20),
+                template_id: None,
+            }],
+            clipboard_text: Some("clipboard words ".repeat(30)),
+            prev_window: None,
+            screenshot: Some(ScreenshotPng {
+                png_bytes: vec![0; 4],
+                width: 1920,
+                height: 1080,
+                sha256_hex: String::new(),
+            }),
+        }
+    }
+
+    #[test]
+    fn image_tokens_follow_the_tile_formula() {
+        assert_eq!(estimate_image_tokens(0, 100), 0);
+        assert_eq!(estimate_image_tokens(512, 512), 85 + 170);
+        // 1920x1080 scales to 1366x768: 3x2 tiles.
+        assert_eq!(estimate_image_tokens(1920, 1080), 85 + 170 * 6);
+    }
+
+    #[test]
+    fn drops_screenshot_then_clipboard_then_history_until_under_cap() {
+        let budget = ContextBudget {
+            max_chars_per_history_item: 1000,
+            max_chars_clipboard: 1000,
+            ..Default::default()
+        };
+        let shape = RequestShape {
+            system_tokens: 100,
+            transcript_limit: None,
+        };
+        let snap = snapshot();
+        let uncapped = fit_to_cap("hello there", &snap, &budget, false, shape, None);
+        assert!(uncapped.dropped.is_empty());
+        assert_eq!(uncapped.estimate.image, 85 + 170 * 6);
+
+        let cap = uncapped.estimate.total - uncapped.estimate.image - 1;
+        let out = fit_to_cap("hello there", &snap, &budget, false, shape, Some(cap));
+        let sections: Vec<&str> = out.dropped.iter().map(|d| d.section).collect();
+        assert_eq!(sections, ["screenshot", "clipboard"]);
+        assert!(out.prepared.screenshot.is_none());
+        assert!(!out.prepared.user_text.contains("CLIPBOARD"));
+        assert!(out.prepared.user_text.contains("RECENT HISTORY"));
+        assert!(!out.over_cap(Some(cap)));
+        assert!(out.dropped[1].tokens_after < out.dropped[1].tokens_before);
+
+        let out = fit_to_cap("hello there", &snap, &budget, false, shape, Some(10));
+        assert_eq!(out.dropped.len(), 3);
+        assert!(out.over_cap(Some(10)));
+        assert!(!out.prepared.user_text.contains("### CONTEXT"));
+    }
+}
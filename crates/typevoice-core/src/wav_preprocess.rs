@@ -0,0 +1,290 @@
+// Pure-Rust version of the ffmpeg preprocess for WAV input: mono mixdown,
+// resample to 16 kHz, gain, silence trim, 16-bit PCM out. Anything it cannot
+// read is reported as unsupported so the caller can fall back to ffmpeg.
+//
+// Without gain or trimming, 16 kHz mono s16 input comes out sample-identical,
+// as it does from ffmpeg. Resampling is linear and silence trimming works on
+// 20 ms RMS frames, so other outputs are close to ffmpeg's but not bit-equal.
+
+pub const TARGET_SAMPLE_RATE: u32 = 16_000;
+const FRAME_MS: u64 = 20;
+
+const FORMAT_PCM: u16 = 1;
+const FORMAT_FLOAT: u16 = 3;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SilenceTrim {
+    pub threshold_db: f64,
+    // Sound must last this long before the leading trim stops.
+    pub start_ms: u64,
+    // Silence after the first sound is cut when it lasts longer than this.
+    pub end_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NativePreprocess {
+    pub gain_db: f64,
+    pub silence_trim: Option<SilenceTrim>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonoAudio {
+    pub sample_rate: u32,
+    pub samples: Vec<f32>,
+}
+
+// Why the native path cannot take this file; not a user-facing error.
+pub type Unsupported = &'static str;
+
+pub fn preprocess_wav(wav: &[u8], cfg: &NativePreprocess) -> Result<Vec<u8>, Unsupported> {
+    let audio = decode_mono(wav)?;
+    let mut samples = resample_linear(&audio.samples, audio.sample_rate, TARGET_SAMPLE_RATE);
+    apply_gain(&mut samples, cfg.gain_db);
+    if let Some(trim) = cfg.silence_trim.as_ref() {
+        samples = trim_silence(&samples, TARGET_SAMPLE_RATE, trim);
+    }
+    Ok(encode_wav_s16(&samples, TARGET_SAMPLE_RATE))
+}
+
+// Reads 16-bit PCM or 32-bit float WAV, averaging channels into one.
+pub fn decode_mono(wav: &[u8]) -> Result<MonoAudio, Unsupported> {
+    if wav.len() < 12 || &wav[0..4] != b"RIFF" || &wav[8..12] != b"WAVE" {
+        return Err("not a RIFF/WAVE file");
+    }
+    let mut fmt: Option<(u16, u16, u32, u16)> = None;
+    let mut data: Option<&[u8]> = None;
+    let mut offset = 12;
+    while offset + 8 <= wav.len() {
+        let id = &wav[offset..offset + 4];
+        let len = u32::from_le_bytes([
+            wav[offset + 4],
+            wav[offset + 5],
+            wav[offset + 6],
+            wav[offset + 7],
+        ]) as usize;
+        let body = offset + 8;
+        let end = body.saturating_add(len).min(wav.len());
+        let chunk = &wav[body..end];
+        if id == b"fmt " {
+            if chunk.len() < 16 {
+                return Err("fmt chunk too short");
+            }
+            let u16_at = |i: usize| u16::from_le_bytes([chunk[i], chunk[i + 1]]);
+            let mut format = u16_at(0);
+            if format == FORMAT_EXTENSIBLE {
+                // The sub-format GUID starts with the plain format tag.
+                if chunk.len() < 26 {
+                    return Err("extensible fmt chunk too short");
+                }
+                format = u16_at(24);
+            }
+            let rate = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+            fmt = Some((format, u16_at(2), rate, u16_at(14)));
+        } else if id == b"data" {
+            // Recorders that never finalized the header leave a bogus length;
+            // take what is actually there.
+            data = Some(chunk);
+            break;
+        }
+        let Some(next) = body.checked_add(len).and_then(|v| v.checked_add(len % 2)) else {
+            break;
+        };
+        offset = next;
+    }
+    let (format, channels, sample_rate, bits) = fmt.ok_or("fmt chunk missing")?;
+    let data = data.ok_or("data chunk missing")?;
+    if channels == 0 || sample_rate == 0 {
+        return Err("invalid channel count or sample rate");
+    }
+    let decode: fn(&[u8]) -> f32 = match (format, bits) {
+        (FORMAT_PCM, 16) => |b| f32::from(i16::from_le_bytes([b[0], b[1]])) / 32768.0,
+        (FORMAT_FLOAT, 32) => |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+        _ => return Err("only 16-bit PCM and 32-bit float WAV are supported"),
+    };
+    let width = usize::from(bits / 8);
+    let frame = width * usize::from(channels);
+    let samples = data
+        .chunks_exact(frame)
+        .map(|f| f.chunks_exact(width).map(decode).sum::<f32>() / f32::from(channels))
+        .collect();
+    Ok(MonoAudio {
+        sample_rate,
+        samples,
+    })
+}
+
+pub fn resample_linear(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let out_len = (samples.len() as u64 * u64::from(to) / u64::from(from)) as usize;
+    let step = f64::from(from) / f64::from(to);
+    let last = samples.len() - 1;
+    (0..out_len)
+        .map(|i| {
+            let pos = i as f64 * step;
+            let idx = (pos.floor() as usize).min(last);
+            let frac = (pos - idx as f64) as f32;
+            let next = samples[(idx + 1).min(last)];
+            samples[idx] + (next - samples[idx]) * frac
+        })
+        .collect()
+}
+
+pub fn apply_gain(samples: &mut [f32], gain_db: f64) {
+    if gain_db == 0.0 || !gain_db.is_finite() {
+        return;
+    }
+    let factor = 10f64.powf(gain_db / 20.0) as f32;
+    for s in samples.iter_mut() {
+        *s *= factor;
+    }
+}
+
+// Drops leading silence until `start_ms` of sound, then removes every later
+// silent stretch longer than `end_ms`, including the tail.
+pub fn trim_silence(samples: &[f32], sample_rate: u32, trim: &SilenceTrim) -> Vec<f32> {
+    let frame_len = (u64::from(sample_rate) * FRAME_MS / 1000).max(1) as usize;
+    let frames_for = |ms: u64| (ms.div_ceil(FRAME_MS) as usize).max(1);
+    let threshold = 10f64.powf(trim.threshold_db / 20.0);
+    let loud: Vec<bool> = samples
+        .chunks(frame_len)
+        .map(|f| {
+            let energy: f64 = f.iter().map(|s| f64::from(*s) * f64::from(*s)).sum();
+            (energy / f.len() as f64).sqrt() > threshold
+        })
+        .collect();
+
+    let start_frames = frames_for(trim.start_ms);
+    let Some(first) = (0..loud.len()).find(|&i| {
+        let end = (i + start_frames).min(loud.len());
+        loud[i..end].iter().all(|l| *l)
+    }) else {
+        return Vec::new();
+    };
+
+    let end_frames = frames_for(trim.end_ms);
+    let mut keep = vec![true; loud.len()];
+    keep[..first].fill(false);
+    let mut i = first;
+    while i < loud.len() {
+        if loud[i] {
+            i += 1;
+            continue;
+        }
+        let run_end = (i..loud.len()).find(|&j| loud[j]).unwrap_or(loud.len());
+        if run_end - i > end_frames {
+            keep[i..run_end].fill(false);
+        }
+        i = run_end;
+    }
+    samples
+        .chunks(frame_len)
+        .zip(keep)
+        .filter(|(_, k)| *k)
+        .flat_map(|(f, _)| f.iter().copied())
+        .collect()
+}
+
+pub fn encode_wav_s16(samples: &[f32], sample_rate: u32) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut out = Vec::with_capacity(44 + samples.len() * 2);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16_u32.to_le_bytes());
+    out.extend_from_slice(&FORMAT_PCM.to_le_bytes());
+    out.extend_from_slice(&1_u16.to_le_bytes());
+    out.extend_from_slice(&sample_rate.to_le_bytes());
+    out.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    out.extend_from_slice(&2_u16.to_le_bytes());
+    out.extend_from_slice(&16_u16.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    for s in samples {
+        let v = (f64::from(*s) * 32768.0).round().clamp(-32768.0, 32767.0) as i16;
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context_pack::sha256_hex;
+
+    fn pcm_wav(rate: u32, channels: u16, samples: &[i16]) -> Vec<u8> {
+        let data: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        let mut out = Vec::new();
+        out.extend_from_slice(b"RIFF");
+        out.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+        out.extend_from_slice(b"WAVEfmt ");
+        out.extend_from_slice(&16_u32.to_le_bytes());
+        out.extend_from_slice(&1_u16.to_le_bytes());
+        out.extend_from_slice(&channels.to_le_bytes());
+        out.extend_from_slice(&rate.to_le_bytes());
+        out.extend_from_slice(&(rate * 2 * u32::from(channels)).to_le_bytes());
+        out.extend_from_slice(&(2 * channels).to_le_bytes());
+        out.extend_from_slice(&16_u16.to_le_bytes());
+        out.extend_from_slice(b"data");
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        out.extend_from_slice(&data);
+        out
+    }
+
+    fn tone(len: usize, amp: i16) -> Vec<i16> {
+        (0..len)
+            .map(|i| if i % 2 == 0 { amp } else { -amp })
+            .collect()
+    }
+
+    #[test]
+    fn recorder_output_passes_through_unchanged() {
+        // What the backend recorder writes, and what ffmpeg's preprocess
+        // leaves sample-identical when no filter is set.
+        let samples: Vec<i16> = (0..1600).map(|i| ((i * 37) % 2000 - 1000) as i16).collect();
+        let wav = pcm_wav(16_000, 1, &samples);
+        let out = preprocess_wav(&wav, &NativePreprocess::default()).expect("native");
+        assert_eq!(sha256_hex(&out[44..]), sha256_hex(&wav[44..]));
+    }
+
+    #[test]
+    fn stereo_48k_is_mixed_down_and_resampled() {
+        let frames = 4800;
+        let interleaved: Vec<i16> = (0..frames).flat_map(|_| [1000, 3000]).collect();
+        let wav = pcm_wav(48_000, 2, &interleaved);
+        let audio = decode_mono(&wav).expect("decode");
+        assert_eq!(audio.samples.len(), frames);
+        let out = preprocess_wav(&wav, &NativePreprocess::default()).expect("native");
+        let pcm = &out[44..];
+        assert_eq!(pcm.len(), 1600 * 2);
+        assert_eq!(i16::from_le_bytes([pcm[0], pcm[1]]), 2000);
+        assert!(decode_mono(b"RIFF....WAVEjunk").is_err());
+    }
+
+    #[test]
+    fn silence_is_trimmed_at_the_edges_and_long_gaps() {
+        let rate = 16_000;
+        let ms = |v: usize| v * 16;
+        let mut samples = vec![0i16; ms(500)];
+        samples.extend(tone(ms(300), 8000));
+        samples.extend(vec![0; ms(100)]);
+        samples.extend(tone(ms(300), 8000));
+        samples.extend(vec![0; ms(800)]);
+        samples.extend(tone(ms(200), 8000));
+        samples.extend(vec![0; ms(600)]);
+        let wav = pcm_wav(rate, 1, &samples);
+        let cfg = NativePreprocess {
+            gain_db: 0.0,
+            silence_trim: Some(SilenceTrim {
+                threshold_db: -50.0,
+                start_ms: 100,
+                end_ms: 300,
+            }),
+        };
+        let out = preprocess_wav(&wav, &cfg).expect("native");
+        // Sound plus the short 100 ms gap survive; the 500/800/600 ms runs go.
+        assert_eq!((out.len() - 44) / 2, ms(300 + 100 + 300 + 200));
+    }
+}
@@ -0,0 +1,359 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+
+use crate::audio_capture::RecordingRegistry;
+use crate::corrections::{self, AlignedSegment};
+use crate::pcm::{pcm_bytes_for_ms, wav_pcm_data};
+use crate::ports::{PortError, PortResult};
+use crate::transcription::{resolve_asr_preprocess_config, ProviderKind};
+use crate::{asr_lanes, data_dir, obs, pipeline, remote_asr, settings, transcription_actor};
+
+// One ASR configuration to compare: a provider plus, for the remote
+// provider, the model name sent to the endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsrProfile {
+    pub provider: ProviderKind,
+    pub model: Option<String>,
+}
+
+impl AsrProfile {
+    // "doubao", "remote" (the configured remote model), "remote:<model>", or a
+    // bare remote model name.
+    pub fn parse(spec: &str) -> PortResult<Self> {
+        let spec = spec.trim();
+        if spec.is_empty() {
+            return Err(PortError::new(
+                "E_ASR_COMPARE_PROFILE_INVALID",
+                "ASR profile must not be empty",
+            ));
+        }
+        if spec.eq_ignore_ascii_case("doubao") {
+            return Ok(Self {
+                provider: ProviderKind::Doubao,
+                model: None,
+            });
+        }
+        if spec.eq_ignore_ascii_case("remote") {
+            return Ok(Self {
+                provider: ProviderKind::Remote,
+                model: None,
+            });
+        }
+        let model = spec
+            .strip_prefix("remote:")
+            .unwrap_or(spec)
+            .trim()
+            .to_string();
+        if model.is_empty() {
+            return Err(PortError::new(
+                "E_ASR_COMPARE_PROFILE_INVALID",
+                format!("ASR profile has no model: {spec}"),
+            ));
+        }
+        Ok(Self {
+            provider: ProviderKind::Remote,
+            model: Some(model),
+        })
+    }
+
+    fn label(&self) -> String {
+        match &self.model {
+            Some(model) => format!("{}:{model}", self.provider.as_str()),
+            None => self.provider.as_str().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AsrCompareRun {
+    pub profile: String,
+    pub model_id: Option<String>,
+    pub text: Option<String>,
+    pub rtf: Option<f64>,
+    pub asr_ms: Option<u128>,
+    pub error_code: Option<String>,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AsrCompareResult {
+    pub source_id: String,
+    pub audio_seconds: f64,
+    pub a: AsrCompareRun,
+    pub b: AsrCompareRun,
+    pub diff: Vec<AlignedSegment>,
+}
+
+// Raw recordings still need preprocessing; audio kept from a finished task as
+// WAV is already the ASR input.
+enum AudioSource {
+    Recording(PathBuf),
+    PreprocessedWav(PathBuf),
+}
+
+fn resolve_source(
+    recordings: &RecordingRegistry,
+    data_dir: &Path,
+    id: &str,
+) -> PortResult<AudioSource> {
+    if id.is_empty() || id.contains(['/', '\\']) || id.contains("..") {
+        return Err(PortError::new(
+            "E_ASR_COMPARE_AUDIO_UNAVAILABLE",
+            "invalid recording or task id",
+        ));
+    }
+    if let Some(path) = recordings.asset_path(id).filter(|p| p.exists()) {
+        return Ok(AudioSource::Recording(path));
+    }
+    // Kept task audio stored as FLAC/Opus goes back through preprocessing.
+    match pipeline::retained_recording(data_dir, id) {
+        Some(kept) if kept.extension().is_some_and(|e| e == "wav") => {
+            Ok(AudioSource::PreprocessedWav(kept))
+        }
+        Some(kept) => Ok(AudioSource::Recording(kept)),
+        None => Err(PortError::new(
+            "E_ASR_COMPARE_AUDIO_UNAVAILABLE",
+            "no audio is kept for this id; turn on keep_recordings to keep task audio",
+        )),
+    }
+}
+
+async fn preprocess_recording(
+    data_dir: &Path,
+    run_id: &str,
+    input: PathBuf,
+    s: &settings::Settings,
+) -> PortResult<PathBuf> {
+    let wav_path = pipeline::preprocess_to_temp_wav(data_dir, run_id)
+        .map_err(|e| PortError::from_message("E_PREPROCESS_FAILED", e.to_string()))?;
+    let cfg = resolve_asr_preprocess_config(s);
+    let (dir, id, out) = (data_dir.to_path_buf(), run_id.to_string(), wav_path.clone());
+    tokio::task::spawn_blocking(move || {
+        pipeline::preprocess_ffmpeg_cancellable(
+            &dir,
+            &id,
+            &input,
+            &out,
+            &CancellationToken::new(),
+            &Arc::new(Mutex::new(None)),
+            &cfg,
+        )
+    })
+    .await
+    .map_err(|e| PortError::new("E_INTERNAL", format!("asr_compare_join_failed:{e}")))?
+    .map_err(|e| PortError::from_message("E_PREPROCESS_FAILED", e.to_string()))?;
+    Ok(wav_path)
+}
+
+async fn run_profile(
+    data_dir: &Path,
+    run_id: &str,
+    wav_path: &Path,
+    pcm: &[u8],
+    audio_seconds: f64,
+    profile: &AsrProfile,
+    s: &settings::Settings,
+) -> AsrCompareRun {
+    let started = Instant::now();
+    let outcome: PortResult<(String, Option<String>, Option<f64>)> = match profile.provider {
+        ProviderKind::Remote => {
+            let cfg = remote_asr::RemoteAsrConfig {
+                url: settings::resolve_remote_asr_url(s),
+                model: profile
+                    .model
+                    .clone()
+                    .or_else(|| settings::resolve_remote_asr_model(s)),
+                concurrency: settings::resolve_remote_asr_concurrency(s),
+                lane: remote_asr::AsrLane::Batch,
+                resume: settings::resolve_remote_asr_resume_enabled(s),
+            };
+            remote_asr::transcribe_remote(
+                data_dir,
+                run_id,
+                wav_path,
+                &CancellationToken::new(),
+                &cfg,
+            )
+            .await
+            .map(|v| (v.text, Some(v.metrics.model_id), Some(v.metrics.rtf)))
+            .map_err(|e| PortError::new(&e.code, e.message))
+        }
+        ProviderKind::Doubao => {
+            let (id, pcm) = (run_id.to_string(), pcm.to_vec());
+            match tokio::task::spawn_blocking(move || {
+                transcription_actor::transcribe_doubao_once(&id, &pcm)
+            })
+            .await
+            {
+                Ok(Ok(text)) => Ok((text, Some("doubao".to_string()), None)),
+                Ok(Err(e)) => Err(PortError::from_message(
+                    "E_DOUBAO_ASR_FAILED",
+                    e.to_string(),
+                )),
+                Err(e) => Err(PortError::new(
+                    "E_INTERNAL",
+                    format!("asr_compare_join_failed:{e}"),
+                )),
+            }
+        }
+    };
+    let asr_ms = started.elapsed().as_millis();
+    match outcome {
+        Ok((text, model_id, rtf)) => AsrCompareRun {
+            profile: profile.label(),
+            model_id,
+            text: Some(text.trim().to_string()),
+            rtf: rtf
+                .or_else(|| (audio_seconds > 0.0).then(|| asr_ms as f64 / 1000.0 / audio_seconds)),
+            asr_ms: Some(asr_ms),
+            error_code: None,
+            error_message: None,
+        },
+        Err(e) => AsrCompareRun {
+            profile: profile.label(),
+            model_id: None,
+            text: None,
+            rtf: None,
+            asr_ms: None,
+            error_code: Some(e.code.clone()),
+            error_message: Some(e.message),
+        },
+    }
+}
+
+// Transcribes one recording with two ASR profiles, one after the other so a
+// local model never shares the GPU with the other run. Runs outside the
+// workflow and the active transcription task, and writes no history. A failed
+// profile is reported in its run; only missing audio fails the command.
+pub async fn compare_asr_models(
+    recordings: RecordingRegistry,
+    asset_or_task_id: &str,
+    model_a: &str,
+    model_b: &str,
+) -> PortResult<AsrCompareResult> {
+    let data_dir =
+        data_dir::data_dir().map_err(|e| PortError::from_message("E_DATA_DIR", e.to_string()))?;
+    let source_id = asset_or_task_id.trim().to_string();
+    let span = obs::Span::start(
+        &data_dir,
+        None,
+        "Cmd",
+        "CMD.compare_asr_models",
+        Some(serde_json::json!({"source_id": source_id})),
+    );
+    let prepared = async {
+        let s = settings::load_settings_strict(&data_dir)
+            .map_err(|e| PortError::from_message("E_SETTINGS_INVALID", e.to_string()))?;
+        let a = AsrProfile::parse(model_a)?;
+        let b = AsrProfile::parse(model_b)?;
+        let source = resolve_source(&recordings, &data_dir, &source_id)?;
+        Ok::<_, PortError>((s, a, b, source))
+    }
+    .await;
+    let (s, profile_a, profile_b, source) = match prepared {
+        Ok(v) => v,
+        Err(e) => {
+            span.err("logic", &e.code, &e.message, None);
+            return Err(e);
+        }
+    };
+
+    let run_id = format!("asr-compare-{}", uuid::Uuid::new_v4());
+    let (wav_path, temp) = match source {
+        AudioSource::PreprocessedWav(path) => (path, false),
+        AudioSource::Recording(input) => {
+            match preprocess_recording(&data_dir, &run_id, input, &s).await {
+                Ok(path) => (path, true),
+                Err(e) => {
+                    span.err("process", &e.code, &e.message, None);
+                    return Err(e);
+                }
+            }
+        }
+    };
+    let wav = std::fs::read(&wav_path).unwrap_or_default();
+    let pcm = wav_pcm_data(&wav).unwrap_or_default();
+    let audio_seconds = pcm.len() as f64 / pcm_bytes_for_ms(1000) as f64;
+
+    // Each profile is one batch unit: it waits for dictation to finish, and the
+    // first run's result is kept while the second one waits.
+    let mut held = asr_lanes::global().batch_turn().await;
+    let a = run_profile(
+        &data_dir,
+        &format!("{run_id}-a"),
+        &wav_path,
+        pcm,
+        audio_seconds,
+        &profile_a,
+        &s,
+    )
+    .await;
+    held += asr_lanes::global().batch_turn().await;
+    let b = run_profile(
+        &data_dir,
+        &format!("{run_id}-b"),
+        &wav_path,
+        pcm,
+        audio_seconds,
+        &profile_b,
+        &s,
+    )
+    .await;
+    if temp {
+        let _ = std::fs::remove_file(&wav_path);
+    }
+
+    let diff = corrections::align(
+        a.text.as_deref().unwrap_or_default(),
+        b.text.as_deref().unwrap_or_default(),
+    );
+    span.ok(Some(serde_json::json!({
+        "audio_seconds": audio_seconds,
+        "held_for_dictation_ms": held.as_millis() as u64,
+        "a_rtf": a.rtf,
+        "b_rtf": b.rtf,
+        "a_error_code": a.error_code,
+        "b_error_code": b.error_code,
+        "changed_segments": diff.iter().filter(|d| d.changed).count(),
+    })));
+    Ok(AsrCompareResult {
+        source_id,
+        audio_seconds,
+        a,
+        b,
+        diff,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_spec_selects_provider_and_model() {
+        assert_eq!(
+            AsrProfile::parse(" Doubao ").expect("doubao"),
+            AsrProfile {
+                provider: ProviderKind::Doubao,
+                model: None,
+            }
+        );
+        let remote = AsrProfile::parse("remote:whisper-small").expect("remote");
+        assert_eq!(remote.model.as_deref(), Some("whisper-small"));
+        assert_eq!(remote.label(), "remote:whisper-small");
+        assert_eq!(
+            AsrProfile::parse("whisper-large-v3")
+                .expect("bare")
+                .model
+                .as_deref(),
+            Some("whisper-large-v3")
+        );
+        assert_eq!(AsrProfile::parse("remote").expect("remote").model, None);
+        assert!(AsrProfile::parse("remote:").is_err());
+        assert!(AsrProfile::parse("  ").is_err());
+    }
+}
@@ -5,55 +5,91 @@ use std::{
     process::{Child, ChildStderr, ChildStdout, Stdio},
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc, Mutex,
+        Arc,
     },
     time::{Duration, Instant},
 };
 
+use crate::capture_indicator::{self, CaptureLease, CaptureSource};
+use crate::noise_profile::{self, NoiseProfile};
+use crate::obs::lock_metrics::TimedMutex;
 use crate::pcm::pcm_peak_abs;
+use crate::record_input;
 use crate::record_input_cache::RecordInputCacheState;
+use crate::recorder::{self, NativeRecorder};
 use crate::subprocess::CommandNoConsoleExt;
 use crate::transcription_actor::{StreamingSessionConfig, TranscriptionActor};
-use crate::ui_events::{UiEvent, UiEventMailbox};
-use crate::{data_dir, obs, pipeline};
-
-const STREAMING_FIRST_AUDIO_SEQUENCE: u64 = 2;
+use crate::ui_events::{TaskStage, UiEvent, UiEventMailbox};
+use crate::vad::{self, SilenceDetector};
+use crate::{data_dir, doubao_asr, obs, pipeline, settings};
+
+pub(crate) const STREAMING_FIRST_AUDIO_SEQUENCE: u64 = 2;
+const NOISE_CALIBRATION_MS: u64 = 3_000;
+
+// Per-device extra args are input options, so they go between `-f <format>`
+// and `-i`; the remembered gain is applied to both the wav file and the PCM stream.
+fn ffmpeg_record_args(
+    input_spec: &str,
+    output_path: &Path,
+    device_override: Option<&settings::RecordDeviceOverride>,
+) -> Vec<std::ffi::OsString> {
+    let extra_args = device_override
+        .and_then(|o| o.extra_ffmpeg_args.as_deref())
+        .unwrap_or_default();
+    let gain_filter = device_override
+        .and_then(|o| o.gain_db)
+        .map(|db| format!("volume={db:.1}dB"));
+    let output_args = |tail: &[&str]| {
+        let mut args: Vec<std::ffi::OsString> = Vec::new();
+        if let Some(filter) = gain_filter.as_deref() {
+            args.extend(["-af", filter].map(std::ffi::OsString::from));
+        }
+        args.extend(
+            ["-ac", "1", "-ar", "16000", "-c:a", "pcm_s16le"]
+                .into_iter()
+                .chain(tail.iter().copied())
+                .map(std::ffi::OsString::from),
+        );
+        args
+    };
+
+    let (format, input) = record_input::ffmpeg_input(input_spec);
+    let mut args: Vec<std::ffi::OsString> =
+        ["-y", "-hide_banner", "-loglevel", "error", "-f", format]
+            .into_iter()
+            .map(std::ffi::OsString::from)
+            .collect();
+    args.extend(extra_args.iter().map(std::ffi::OsString::from));
+    args.extend(["-i".into(), std::ffi::OsString::from(input)]);
+    args.extend(output_args(&[]));
+    args.push(output_path.as_os_str().to_os_string());
+    args.extend(output_args(&["-f", "s16le", "pipe:1"]));
+    args
+}
 
-fn ffmpeg_record_args(input_spec: &str, output_path: &Path) -> Vec<std::ffi::OsString> {
+fn ffmpeg_ambient_args(input_spec: &str, duration_ms: u64) -> Vec<String> {
+    let seconds = format!("{:.3}", duration_ms as f64 / 1000.0);
+    let (format, input) = record_input::ffmpeg_input(input_spec);
     [
-        "-y",
         "-hide_banner",
         "-loglevel",
         "error",
         "-f",
-        "dshow",
+        format,
         "-i",
-        input_spec,
+        input.as_str(),
+        "-t",
+        seconds.as_str(),
         "-ac",
         "1",
         "-ar",
         "16000",
-        "-c:a",
-        "pcm_s16le",
+        "-f",
+        "s16le",
+        "pipe:1",
     ]
     .into_iter()
-    .map(std::ffi::OsString::from)
-    .chain(std::iter::once(output_path.as_os_str().to_os_string()))
-    .chain(
-        [
-            "-ac",
-            "1",
-            "-ar",
-            "16000",
-            "-c:a",
-            "pcm_s16le",
-            "-f",
-            "s16le",
-            "pipe:1",
-        ]
-        .into_iter()
-        .map(std::ffi::OsString::from),
-    )
+    .map(str::to_string)
     .collect()
 }
 
@@ -64,7 +100,7 @@ pub struct CaptureError {
 }
 
 impl CaptureError {
-    fn new(code: &str, message: impl Into<String>) -> Self {
+    pub(crate) fn new(code: &str, message: impl Into<String>) -> Self {
         Self {
             code: code.to_string(),
             message: message.into(),
@@ -81,9 +117,12 @@ struct ActiveRecording {
     task_id: Option<String>,
     output_path: PathBuf,
     child: Option<Child>,
+    native: Option<NativeRecorder>,
     started_at: Instant,
     meter_join: Option<std::thread::JoinHandle<()>>,
     finish_on_eof: Arc<AtomicBool>,
+    // Released with the recording, whichever way it ends.
+    _indicator: Option<CaptureLease>,
 }
 
 #[derive(Debug, Clone)]
@@ -108,16 +147,19 @@ struct RegistryInner {
 
 #[derive(Clone)]
 pub struct RecordingRegistry {
-    inner: Arc<Mutex<RegistryInner>>,
+    inner: Arc<TimedMutex<RegistryInner>>,
 }
 
 impl RecordingRegistry {
     pub fn new() -> Self {
         Self {
-            inner: Arc::new(Mutex::new(RegistryInner {
-                active: None,
-                assets: HashMap::new(),
-            })),
+            inner: Arc::new(TimedMutex::new(
+                "RecordingRegistry.inner",
+                RegistryInner {
+                    active: None,
+                    assets: HashMap::new(),
+                },
+            )),
         }
     }
 
@@ -141,6 +183,14 @@ impl RecordingRegistry {
         }
     }
 
+    // Recording file of a finished asset that has not been consumed yet.
+    pub fn asset_path(&self, asset_id: &str) -> Option<PathBuf> {
+        let g = self.inner.lock().unwrap();
+        g.assets
+            .get(asset_id)
+            .map(|asset| asset.output_path.clone())
+    }
+
     pub fn take_asset(&self, asset_id: &str) -> Option<RecordedAsset> {
         let mut g = self.inner.lock().unwrap();
         g.assets.remove(asset_id)
@@ -163,14 +213,6 @@ impl RecordingRegistry {
             "CMD.record_transcribe_start",
             None,
         );
-        if !cfg!(windows) {
-            let err = CaptureError::new(
-                "E_RECORD_UNSUPPORTED",
-                "backend recording is only supported on Windows",
-            );
-            span.err("config", &err.code, &err.render(), None);
-            return Err(err);
-        }
         self.cleanup_expired_assets(Duration::from_secs(120));
         let stale_active = {
             let mut g = self.inner.lock().unwrap();
@@ -210,42 +252,65 @@ impl RecordingRegistry {
         };
         let resolved_input = cached_input.resolved.clone();
         let input_spec = resolved_input.spec.clone();
-        let ffmpeg = pipeline::ffmpeg_cmd()
-            .map_err(|e| CaptureError::new("E_FFMPEG_NOT_FOUND", e.to_string()))?;
-
-        let mut child = match std::process::Command::new(&ffmpeg)
-            .args(ffmpeg_record_args(
-                input_spec.as_str(),
-                output_path.as_path(),
-            ))
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .no_console()
-            .spawn()
-        {
-            Ok(child) => child,
-            Err(e) => {
-                let err = CaptureError::new(
-                    "E_RECORD_START_FAILED",
-                    format!("failed to start ffmpeg recorder: {e}"),
-                );
-                span.err("process", &err.code, &err.render(), None);
+        let loaded = settings::load_settings(&dir).ok();
+        let backend = loaded
+            .as_ref()
+            .map(settings::resolve_record_backend)
+            .unwrap_or(settings::DEFAULT_RECORD_BACKEND);
+        let silence_detector = loaded.as_ref().and_then(SilenceDetector::from_settings);
+        let vad_silence_ms = silence_detector.as_ref().map(SilenceDetector::silence_ms);
+        let indicator = match capture_indicator::begin(CaptureSource::Recorder) {
+            Ok(lease) => lease,
+            Err(err) => {
+                span.err("config", &err.code, &err.render(), None);
                 return Err(err);
             }
         };
 
-        let stdout = match child.stdout.take() {
-            Some(v) => v,
-            None => {
-                let err =
-                    CaptureError::new("E_RECORD_START_FAILED", "recorder stdout not available");
-                span.err("process", &err.code, &err.render(), None);
-                let _ = child.kill();
-                let _ = child.wait();
-                let _ = std::fs::remove_file(&output_path);
-                return Err(err);
+        // The native recorder is tried first when selected; a device it cannot
+        // open still gets the ffmpeg recorder, with a warning.
+        let mut native_fallback = None;
+        let native = match backend {
+            "native" => match recorder::start(
+                resolved_input.friendly_name.as_deref(),
+                Some(&output_path),
+                resolved_input
+                    .device_override
+                    .as_ref()
+                    .and_then(|o| o.gain_db),
+            ) {
+                Ok(v) => Some(v),
+                Err(err) => {
+                    native_fallback = Some(err);
+                    None
+                }
+            },
+            _ => None,
+        };
+        let (mut child, native, pcm_source) = match native {
+            Some((native, reader)) => {
+                (None, Some(native), Box::new(reader) as Box<dyn Read + Send>)
             }
+            None => match spawn_ffmpeg_recorder(
+                input_spec.as_str(),
+                output_path.as_path(),
+                resolved_input.device_override.as_ref(),
+            ) {
+                Ok((child, stdout)) => {
+                    (Some(child), None, Box::new(stdout) as Box<dyn Read + Send>)
+                }
+                Err(err) => {
+                    span.err(
+                        capture_error_kind(&err.code),
+                        &err.code,
+                        &err.render(),
+                        Some(serde_json::json!({
+                            "native_error": native_fallback.as_ref().map(CaptureError::render),
+                        })),
+                    );
+                    return Err(err);
+                }
+            },
         };
         let finish_on_eof = Arc::new(AtomicBool::new(false));
         let meter_join = spawn_meter_thread(
@@ -253,65 +318,106 @@ impl RecordingRegistry {
             transcriber.cloned(),
             task_id.clone(),
             session_id.clone(),
-            stdout,
+            pcm_source,
             streaming_config.map(|config| config.chunk_bytes),
             finish_on_eof.clone(),
+            silence_detector,
         );
 
-        std::thread::sleep(Duration::from_millis(120));
-        match child.try_wait() {
-            Ok(Some(status)) => {
-                let stderr_tail = child.stderr.as_mut().and_then(read_last_stderr_line);
-                let mut message = if status.success() {
-                    "recorder exited unexpectedly right after start".to_string()
-                } else {
-                    format!("recorder exited right after start with {status}")
-                };
-                if let Some(line) = stderr_tail.as_deref() {
-                    message.push_str("; stderr=");
-                    message.push_str(line);
+        if let Some(child) = child.as_mut() {
+            std::thread::sleep(Duration::from_millis(120));
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    let stderr_tail = child.stderr.as_mut().and_then(read_last_stderr_line);
+                    let mut message = if status.success() {
+                        "recorder exited unexpectedly right after start".to_string()
+                    } else {
+                        format!("recorder exited right after start with {status}")
+                    };
+                    if let Some(line) = stderr_tail.as_deref() {
+                        message.push_str("; stderr=");
+                        message.push_str(line);
+                    }
+                    let err = CaptureError::new("E_RECORD_START_FAILED", message);
+                    span.err("process", &err.code, &err.render(), None);
+                    let _ = std::fs::remove_file(&output_path);
+                    let _ = meter_join.join();
+                    return Err(err);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    let err = CaptureError::new(
+                        "E_RECORD_START_FAILED",
+                        format!("failed to probe recorder process: {e}"),
+                    );
+                    span.err("process", &err.code, &err.render(), None);
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    let _ = std::fs::remove_file(&output_path);
+                    let _ = meter_join.join();
+                    return Err(err);
                 }
-                let err = CaptureError::new("E_RECORD_START_FAILED", message);
-                span.err("process", &err.code, &err.render(), None);
-                let _ = std::fs::remove_file(&output_path);
-                let _ = meter_join.join();
-                return Err(err);
-            }
-            Ok(None) => {}
-            Err(e) => {
-                let err = CaptureError::new(
-                    "E_RECORD_START_FAILED",
-                    format!("failed to probe recorder process: {e}"),
-                );
-                span.err("process", &err.code, &err.render(), None);
-                let _ = child.kill();
-                let _ = child.wait();
-                let _ = std::fs::remove_file(&output_path);
-                let _ = meter_join.join();
-                return Err(err);
             }
         }
 
+        // Lets per-task stats (ASR accuracy) be broken down by microphone.
+        if let Some(task_id) = task_id.as_deref() {
+            let _ = obs::metrics::emit(
+                &dir,
+                obs::schema::MetricsRecord::RecordInput {
+                    ts_ms: obs::schema::now_ms(),
+                    task_id: task_id.to_string(),
+                    endpoint_id: resolved_input.endpoint_id.clone(),
+                    friendly_name: resolved_input.friendly_name.clone(),
+                },
+            );
+        }
+        if let Some(err) = native_fallback.as_ref() {
+            mailbox.send(UiEvent::warning(
+                task_id.clone(),
+                TaskStage::Record,
+                "E_RECORD_NATIVE_FALLBACK",
+                format!("native recorder unavailable, using ffmpeg: {}", err.message),
+            ));
+        }
+        if let Some(warning) = resolved_input.quality_warning.as_deref() {
+            mailbox.send(UiEvent::warning(
+                task_id.clone(),
+                TaskStage::Record,
+                "E_RECORD_LOW_QUALITY_PROFILE",
+                warning,
+            ));
+        }
+        let native_device = native.as_ref().map(|n| n.device_name.clone());
         {
             let mut g = self.inner.lock().unwrap();
             g.active = Some(ActiveRecording {
                 session_id: session_id.clone(),
                 task_id,
                 output_path: output_path.clone(),
-                child: Some(child),
+                child,
+                native,
                 started_at: Instant::now(),
                 meter_join: Some(meter_join),
                 finish_on_eof,
+                _indicator: Some(indicator),
             });
         }
         span.ok(Some(serde_json::json!({
             "session_id": session_id,
             "output_path": output_path,
             "record_input_spec": input_spec,
+            "record_backend": if native_device.is_some() { "native" } else { "ffmpeg" },
+            "record_vad_silence_ms": vad_silence_ms,
+            "record_native_device": native_device,
+            "record_native_error": native_fallback.as_ref().map(CaptureError::render),
             "record_input_strategy": resolved_input.strategy_used,
             "record_input_resolved_by": resolved_input.resolved_by,
             "record_input_endpoint_id": resolved_input.endpoint_id,
             "record_input_friendly_name": resolved_input.friendly_name,
+            "record_input_device_override": resolved_input.device_override,
+            "record_input_capture_format": resolved_input.capture_format,
+            "record_input_quality_warning": resolved_input.quality_warning,
             "record_input_resolution_log": resolved_input.resolution_log,
             "record_input_cache_reason": cached_input.reason,
             "record_input_cache_refreshed_ts_ms": cached_input.refreshed_at_ms,
@@ -319,6 +425,125 @@ impl RecordingRegistry {
         Ok(session_id)
     }
 
+    // Records a few seconds of room tone from the cached input and measures it.
+    pub fn calibrate_noise_profile(
+        &self,
+        record_input_cache: &RecordInputCacheState,
+    ) -> Result<NoiseProfile, CaptureError> {
+        let dir =
+            data_dir::data_dir().map_err(|e| CaptureError::new("E_DATA_DIR", e.to_string()))?;
+        let span = obs::Span::start(
+            &dir,
+            None,
+            "Cmd",
+            "CMD.calibrate_noise_profile",
+            Some(serde_json::json!({"duration_ms": NOISE_CALIBRATION_MS})),
+        );
+        let (pcm, input_spec) = match self.capture_pcm(
+            record_input_cache,
+            NOISE_CALIBRATION_MS,
+            CaptureSource::NoiseCalibration,
+        ) {
+            Ok(v) => v,
+            Err(err) => {
+                span.err(
+                    capture_error_kind(&err.code),
+                    &err.code,
+                    &err.render(),
+                    None,
+                );
+                return Err(err);
+            }
+        };
+        let Some(profile) = noise_profile::analyze_ambient_pcm(&pcm, doubao_asr::PCM_SAMPLE_RATE)
+        else {
+            let err = CaptureError::new(
+                "E_NOISE_CALIBRATION_EMPTY",
+                "recorder produced too little audio to measure",
+            );
+            span.err(
+                "logic",
+                &err.code,
+                &err.render(),
+                Some(serde_json::json!({"pcm_bytes": pcm.len()})),
+            );
+            return Err(err);
+        };
+        span.ok(Some(serde_json::json!({
+            "record_input_spec": input_spec,
+            "noise_floor_db": profile.noise_floor_db,
+            "peak_db": profile.peak_db,
+            "suggested_silence_threshold_db": profile.suggested_silence_threshold_db,
+            "suggested_gain_db": profile.suggested_gain_db,
+        })));
+        Ok(profile)
+    }
+
+    // Captures a fixed-length mono 16 kHz PCM sample from the cached input,
+    // outside the dictation session. Refused while a dictation recording is
+    // active so the two never contend for the device.
+    pub fn capture_pcm(
+        &self,
+        record_input_cache: &RecordInputCacheState,
+        duration_ms: u64,
+        source: CaptureSource,
+    ) -> Result<(Vec<u8>, String), CaptureError> {
+        if self.inner.lock().unwrap().active.is_some() {
+            return Err(CaptureError::new(
+                "E_RECORD_BUSY",
+                "cannot capture a sample while a recording is in progress",
+            ));
+        }
+        let Some(cached_input) = record_input_cache.get_last_ok() else {
+            return Err(CaptureError::new(
+                "E_RECORD_INPUT_CACHE_NOT_READY",
+                "record input cache is not ready; wait for cache refresh and retry",
+            ));
+        };
+        let resolved = cached_input.resolved;
+        let backend = data_dir::data_dir()
+            .and_then(|dir| settings::load_settings(&dir))
+            .map(|s| settings::resolve_record_backend(&s))
+            .unwrap_or(settings::DEFAULT_RECORD_BACKEND);
+        let _indicator = capture_indicator::begin(source)?;
+        // A sample the native recorder cannot take is retried through ffmpeg.
+        if backend == "native" {
+            if let Ok((pcm, device)) =
+                recorder::capture(resolved.friendly_name.as_deref(), duration_ms)
+            {
+                return Ok((pcm, format!("native:{device}")));
+            }
+        }
+        let input_spec = resolved.spec;
+        let ffmpeg = pipeline::ffmpeg_cmd()
+            .map_err(|e| CaptureError::new("E_FFMPEG_NOT_FOUND", e.to_string()))?;
+        let output = std::process::Command::new(&ffmpeg)
+            .args(ffmpeg_ambient_args(&input_spec, duration_ms))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .no_console()
+            .output()
+            .map_err(|e| {
+                CaptureError::new(
+                    "E_RECORD_START_FAILED",
+                    format!("failed to start ffmpeg recorder: {e}"),
+                )
+            })?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(CaptureError::new(
+                "E_RECORD_SAMPLE_FAILED",
+                format!(
+                    "recorder exited with {}; stderr={}",
+                    output.status,
+                    stderr.lines().last().unwrap_or("").trim()
+                ),
+            ));
+        }
+        Ok((output.stdout, input_spec))
+    }
+
     pub fn stop_recording(&self, session_id: &str) -> Result<RecordingStopOutcome, CaptureError> {
         let dir =
             data_dir::data_dir().map_err(|e| CaptureError::new("E_DATA_DIR", e.to_string()))?;
@@ -348,67 +573,31 @@ impl RecordingRegistry {
             return Ok(RecordingStopOutcome::Stale);
         }
 
-        let child = active
-            .child
-            .as_mut()
-            .ok_or_else(|| CaptureError::new("E_RECORD_STOP_FAILED", "recorder process missing"))?;
         active.finish_on_eof.store(true, Ordering::SeqCst);
-        if let Some(stdin) = child.stdin.as_mut() {
-            let _ = std::io::Write::write_all(stdin, b"q\n");
-            let _ = std::io::Write::flush(stdin);
-        }
-
-        let mut status = None;
-        for _ in 0..100 {
-            match child.try_wait() {
-                Ok(Some(s)) => {
-                    status = Some(s);
-                    break;
-                }
-                Ok(None) => std::thread::sleep(Duration::from_millis(20)),
-                Err(_) => break,
-            }
-        }
-        if status.is_none() {
-            let _ = child.kill();
-            status = child.wait().ok();
-        }
-        let status = match status {
-            Some(s) => s,
-            None => {
-                let stderr_tail = child.stderr.as_mut().and_then(read_last_stderr_line);
-                let mut message = "recorder process wait failed".to_string();
-                if let Some(line) = stderr_tail.as_deref() {
-                    message.push_str("; stderr=");
-                    message.push_str(line);
-                }
-                join_meter_thread(&mut active);
-                let err = CaptureError::new("E_RECORD_STOP_FAILED", message);
+        let finished = match (active.native.take(), active.child.as_mut()) {
+            (Some(native), _) => native.stop().map(Some),
+            (None, Some(child)) => finish_ffmpeg_child(child).map(|()| None),
+            (None, None) => Err(CaptureError::new(
+                "E_RECORD_STOP_FAILED",
+                "recorder process missing",
+            )),
+        };
+        // Both recorders end the PCM stream when they stop, so the meter thread
+        // has flushed its last chunk by the time this returns.
+        join_meter_thread(&mut active);
+        let native_summary = match finished {
+            Ok(v) => v,
+            Err(err) => {
+                let _ = std::fs::remove_file(&active.output_path);
                 span.err("process", &err.code, &err.render(), None);
                 return Err(err);
             }
         };
-        let stderr_tail = child.stderr.as_mut().and_then(read_last_stderr_line);
-        if !status.success() {
-            let mut message = format!("recorder exited with {status}");
-            if let Some(line) = stderr_tail.as_deref() {
-                message.push_str("; stderr=");
-                message.push_str(line);
-            }
-            join_meter_thread(&mut active);
-            let _ = std::fs::remove_file(&active.output_path);
-            let err = CaptureError::new("E_RECORD_STOP_FAILED", message);
-            span.err("process", &err.code, &err.render(), None);
-            return Err(err);
-        }
-
         if !active.output_path.exists() {
-            join_meter_thread(&mut active);
             let err = CaptureError::new("E_RECORD_OUTPUT_MISSING", "recorded file missing");
             span.err("io", &err.code, &err.render(), None);
             return Err(err);
         }
-        join_meter_thread(&mut active);
 
         let elapsed_ms = active.started_at.elapsed().as_millis();
         let asset = self.complete_session(
@@ -421,6 +610,7 @@ impl RecordingRegistry {
             "session_id": active.session_id,
             "recording_asset_id": asset.asset_id,
             "record_elapsed_ms": elapsed_ms,
+            "record_native": native_summary,
         })));
         Ok(RecordingStopOutcome::Completed(asset))
     }
@@ -458,6 +648,9 @@ impl RecordingRegistry {
                 return Ok(());
             }
         }
+        if let Some(native) = active.native.take() {
+            let _ = native.stop();
+        }
         if let Some(child) = active.child.as_mut() {
             if let Some(stdin) = child.stdin.as_mut() {
                 let _ = std::io::Write::write_all(stdin, b"q\n");
@@ -500,9 +693,11 @@ impl RecordingRegistry {
             task_id: None,
             output_path: PathBuf::new(),
             child: None,
+            native: None,
             started_at: Instant::now(),
             meter_join: None,
             finish_on_eof: Arc::new(AtomicBool::new(false)),
+            _indicator: None,
         });
         Ok(())
     }
@@ -544,12 +739,15 @@ fn spawn_meter_thread(
     transcriber: Option<TranscriptionActor>,
     task_id: Option<String>,
     recording_id: String,
-    mut stdout: ChildStdout,
+    mut stdout: Box<dyn Read + Send>,
     chunk_bytes: Option<usize>,
     finish_on_eof: Arc<AtomicBool>,
+    mut silence_detector: Option<SilenceDetector>,
 ) -> std::thread::JoinHandle<()> {
     std::thread::spawn(move || {
-        const WINDOW_SAMPLES: usize = 800;
+        // 100 ms at 16 kHz: one audio.level event per window.
+        const WINDOW_SAMPLES: usize = 1_600;
+        const WINDOW_MS: u64 = WINDOW_SAMPLES as u64 * 1_000 / doubao_asr::PCM_SAMPLE_RATE as u64;
         let mut read_buf = [0_u8; 4096];
         let mut chunk = Vec::with_capacity(chunk_bytes.unwrap_or(0).max(1));
         let mut sequence = STREAMING_FIRST_AUDIO_SEQUENCE;
@@ -599,7 +797,7 @@ fn spawn_meter_thread(
             if let Some(low) = carry_low_byte.take() {
                 if n > 0 {
                     let sample = i16::from_le_bytes([low, read_buf[0]]);
-                    accumulate_sample(
+                    let rms = accumulate_sample(
                         sample,
                         &mut sum_sq,
                         &mut max_abs,
@@ -608,13 +806,20 @@ fn spawn_meter_thread(
                         &mailbox,
                         &recording_id,
                     );
+                    check_silence(
+                        &mut silence_detector,
+                        rms,
+                        WINDOW_MS,
+                        &task_id,
+                        &recording_id,
+                    );
                     idx = 1;
                 }
             }
 
             while idx + 1 < n {
                 let sample = i16::from_le_bytes([read_buf[idx], read_buf[idx + 1]]);
-                accumulate_sample(
+                let rms = accumulate_sample(
                     sample,
                     &mut sum_sq,
                     &mut max_abs,
@@ -623,6 +828,13 @@ fn spawn_meter_thread(
                     &mailbox,
                     &recording_id,
                 );
+                check_silence(
+                    &mut silence_detector,
+                    rms,
+                    WINDOW_MS,
+                    &task_id,
+                    &recording_id,
+                );
                 idx += 2;
             }
 
@@ -696,7 +908,7 @@ fn accumulate_sample(
     window_samples: usize,
     mailbox: &UiEventMailbox,
     recording_id: &str,
-) {
+) -> Option<f64> {
     let sample_i32 = i32::from(sample);
     let normalized = f64::from(sample_i32) / 32768.0;
     *sum_sq += normalized * normalized;
@@ -709,6 +921,54 @@ fn accumulate_sample(
         *sum_sq = 0.0;
         *max_abs = 0;
         *sample_count = 0;
+        return Some(rms);
+    }
+    None
+}
+
+// Hands a tripped detector to the auto-stop listener; the meter keeps reading
+// until the stop closes the source.
+fn check_silence(
+    detector: &mut Option<SilenceDetector>,
+    rms: Option<f64>,
+    window_ms: u64,
+    task_id: &str,
+    recording_id: &str,
+) {
+    let (Some(vad), Some(rms)) = (detector.as_mut(), rms) else {
+        return;
+    };
+    if !vad.push(rms, window_ms) {
+        return;
+    }
+    if let Ok(dir) = data_dir::data_dir() {
+        obs::event(
+            &dir,
+            Some(task_id),
+            "Record",
+            "RECORD.vad_auto_stop",
+            "ok",
+            Some(serde_json::json!({
+                "recording_id": recording_id,
+                "silence_ms": vad.silence_ms(),
+            })),
+        );
+    }
+    vad::notify_auto_stop(vad::AutoStop {
+        task_id: task_id.to_string(),
+        recording_session_id: recording_id.to_string(),
+        silence_ms: vad.silence_ms(),
+    });
+}
+
+pub fn capture_error_kind(code: &str) -> &'static str {
+    match code {
+        "E_RECORD_UNSUPPORTED"
+        | "E_RECORD_INPUT_CACHE_NOT_READY"
+        | "E_FFMPEG_NOT_FOUND"
+        | "E_CAPTURE_INDICATOR_UNAVAILABLE" => "config",
+        "E_RECORD_BUSY" => "logic",
+        _ => "process",
     }
 }
 
@@ -719,6 +979,9 @@ fn join_meter_thread(active: &mut ActiveRecording) {
 }
 
 fn discard_active_recording(active: &mut ActiveRecording) {
+    if let Some(native) = active.native.take() {
+        let _ = native.stop();
+    }
     if let Some(child) = active.child.as_mut() {
         if let Some(stdin) = child.stdin.as_mut() {
             let _ = std::io::Write::write_all(stdin, b"q\n");
@@ -731,6 +994,77 @@ fn discard_active_recording(active: &mut ActiveRecording) {
     let _ = std::fs::remove_file(&active.output_path);
 }
 
+fn spawn_ffmpeg_recorder(
+    input_spec: &str,
+    output_path: &Path,
+    device_override: Option<&settings::RecordDeviceOverride>,
+) -> Result<(Child, ChildStdout), CaptureError> {
+    let ffmpeg = pipeline::ffmpeg_cmd()
+        .map_err(|e| CaptureError::new("E_FFMPEG_NOT_FOUND", e.to_string()))?;
+    let mut child = std::process::Command::new(&ffmpeg)
+        .args(ffmpeg_record_args(input_spec, output_path, device_override))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .no_console()
+        .spawn()
+        .map_err(|e| {
+            CaptureError::new(
+                "E_RECORD_START_FAILED",
+                format!("failed to start ffmpeg recorder: {e}"),
+            )
+        })?;
+    match child.stdout.take() {
+        Some(stdout) => Ok((child, stdout)),
+        None => {
+            let _ = child.kill();
+            let _ = child.wait();
+            let _ = std::fs::remove_file(output_path);
+            Err(CaptureError::new(
+                "E_RECORD_START_FAILED",
+                "recorder stdout not available",
+            ))
+        }
+    }
+}
+
+// Asks ffmpeg to finish the file ("q" on stdin) and kills it if it has not
+// exited within two seconds.
+fn finish_ffmpeg_child(child: &mut Child) -> Result<(), CaptureError> {
+    if let Some(stdin) = child.stdin.as_mut() {
+        let _ = std::io::Write::write_all(stdin, b"q\n");
+        let _ = std::io::Write::flush(stdin);
+    }
+    let mut status = None;
+    for _ in 0..100 {
+        match child.try_wait() {
+            Ok(Some(s)) => {
+                status = Some(s);
+                break;
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(20)),
+            Err(_) => break,
+        }
+    }
+    if status.is_none() {
+        let _ = child.kill();
+        status = child.wait().ok();
+    }
+    let stderr_tail = child.stderr.as_mut().and_then(read_last_stderr_line);
+    let failed = |mut message: String| {
+        if let Some(line) = stderr_tail.as_deref() {
+            message.push_str("; stderr=");
+            message.push_str(line);
+        }
+        Err(CaptureError::new("E_RECORD_STOP_FAILED", message))
+    };
+    match status {
+        Some(s) if s.success() => Ok(()),
+        Some(s) => failed(format!("recorder exited with {s}")),
+        None => failed("recorder process wait failed".to_string()),
+    }
+}
+
 fn read_last_stderr_line(stderr: &mut ChildStderr) -> Option<String> {
     let mut buf = String::new();
     if stderr.read_to_string(&mut buf).is_err() {
@@ -840,11 +1174,23 @@ mod tests {
         assert_eq!(STREAMING_FIRST_AUDIO_SEQUENCE, 2);
     }
 
+    #[test]
+    fn ffmpeg_ambient_args_limit_duration_and_stream_pcm() {
+        let args = ffmpeg_ambient_args("audio=Mic", 3_000);
+
+        assert_eq!(
+            args[args.iter().position(|v| v == "-t").unwrap() + 1],
+            "3.000"
+        );
+        assert_eq!(&args[args.len() - 3..], ["-f", "s16le", "pipe:1"]);
+    }
+
     #[test]
     fn ffmpeg_record_args_transcodes_file_and_stream_outputs() {
         let args = ffmpeg_record_args(
             "audio=@device_cm_{33D9A762-90C8-11D0-BD43-00A0C911CE86}\\wave_{52B28A7E-31C7-4BB2-AFB4-1529B7F2C7CD}",
             Path::new("sample.wav"),
+            None,
         )
         .into_iter()
         .map(|v| v.to_string_lossy().into_owned())
@@ -873,4 +1219,38 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn ffmpeg_record_args_apply_device_override() {
+        let device_override = settings::RecordDeviceOverride {
+            gain_db: Some(4.5),
+            extra_ffmpeg_args: Some(vec!["-audio_buffer_size".into(), "50".into()]),
+        };
+        let args = ffmpeg_record_args("audio=Mic", Path::new("sample.wav"), Some(&device_override))
+            .into_iter()
+            .map(|v| v.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+
+        let input_idx = args.iter().position(|v| v == "-i").expect("input flag");
+        assert_eq!(
+            &args[input_idx - 3..input_idx],
+            [
+                record_input::ffmpeg_input("audio=Mic").0,
+                "-audio_buffer_size",
+                "50"
+            ]
+        );
+        assert_eq!(
+            args.iter().filter(|v| v.as_str() == "volume=4.5dB").count(),
+            2
+        );
+        let output_idx = args
+            .iter()
+            .position(|v| v == "sample.wav")
+            .expect("wav output path exists");
+        assert_eq!(
+            &args[output_idx - 8..output_idx - 6],
+            ["-af", "volume=4.5dB"]
+        );
+    }
 }
@@ -0,0 +1,338 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use base64::Engine;
+use flate2::{write::GzEncoder, Compression};
+use serde::Serialize;
+
+use crate::audio_capture::{capture_error_kind, RecordingRegistry};
+use crate::capture_indicator::CaptureSource;
+use crate::mic_test::transcribe_sample;
+use crate::obs::{debug, schema::now_ms, trace};
+use crate::pcm::pcm_to_wav_bytes;
+use crate::ports::{PortError, PortResult};
+use crate::record_input_cache::RecordInputCacheState;
+use crate::rewrite::sanitize_rewrite_glossary;
+use crate::{data_dir, doubao_asr, guest_mode, llm, noise_profile, obs, settings};
+
+const SAMPLE_MS: u64 = 8_000;
+const BUNDLE_DIR: &str = "bug_reports";
+const BUNDLE_FORMAT: &str = "typevoice-bug-report/1";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BugReportResult {
+    pub task_id: String,
+    // The gzip-compressed JSON bundle to attach to the report.
+    pub path: String,
+    pub bytes: u64,
+    pub transcript: Option<String>,
+    pub asr_error_code: Option<String>,
+    pub rewrite_error_code: Option<String>,
+}
+
+pub fn bundle_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(BUNDLE_DIR)
+}
+
+// Records a short sample and runs it through ASR and, when enabled, the
+// rewrite with full debug capture forced on, then packages the audio, the
+// ASR response, the prompt and the task's trace events into one bundle. The
+// UI asks for consent first since the bundle holds the recording and the
+// prompt; nothing is sent anywhere, the user shares the file. Like the mic
+// test it leaves history and the active task alone, and no screen or
+// clipboard context is captured.
+pub async fn record_bug_report_sample(
+    recordings: RecordingRegistry,
+    record_input_cache: RecordInputCacheState,
+    consent: bool,
+    note: Option<String>,
+) -> PortResult<BugReportResult> {
+    let data_dir =
+        data_dir::data_dir().map_err(|e| PortError::from_message("E_DATA_DIR", e.to_string()))?;
+    let task_id = format!("bug-report-{}", uuid::Uuid::new_v4());
+    let span = obs::Span::start(
+        &data_dir,
+        Some(&task_id),
+        "Cmd",
+        "CMD.record_bug_report_sample",
+        Some(serde_json::json!({"duration_ms": SAMPLE_MS, "consent": consent})),
+    );
+    if !consent {
+        let err = PortError::new(
+            "E_BUG_REPORT_CONSENT_REQUIRED",
+            "recording a bug report sample needs explicit consent",
+        );
+        span.err("logic", &err.code, &err.message, None);
+        return Err(err);
+    }
+    if guest_mode::enabled() {
+        let err = PortError::new(
+            "E_BUG_REPORT_GUEST_MODE",
+            "guest mode keeps no recordings or payloads",
+        );
+        span.err("logic", &err.code, &err.message, None);
+        return Err(err);
+    }
+    let _verbose = debug::ForceVerbose::hold();
+
+    let captured = tokio::task::spawn_blocking(move || {
+        recordings.capture_pcm(&record_input_cache, SAMPLE_MS, CaptureSource::BugReport)
+    })
+    .await;
+    let (pcm, input_spec) = match captured {
+        Ok(Ok(v)) => v,
+        Ok(Err(e)) => {
+            span.err(capture_error_kind(&e.code), &e.code, &e.message, None);
+            return Err(PortError::new(&e.code, e.message));
+        }
+        Err(e) => {
+            let err = PortError::new("E_INTERNAL", format!("bug_report_join_failed:{e}"));
+            span.err("process", &err.code, &err.message, None);
+            return Err(err);
+        }
+    };
+    let profile = noise_profile::analyze_ambient_pcm(&pcm, doubao_asr::PCM_SAMPLE_RATE);
+    if let Err(e) =
+        debug::write_task_artifact(&data_dir, &task_id, "input.wav", &pcm_to_wav_bytes(&pcm))
+    {
+        let err = PortError::from_message("E_BUG_REPORT_WRITE", e.to_string());
+        span.err("io", &err.code, &err.message, None);
+        return Err(err);
+    }
+
+    let s = settings::load_settings_strict(&data_dir).ok();
+    let provider = s
+        .as_ref()
+        .map(settings::resolve_asr_provider)
+        .unwrap_or_else(|| settings::DEFAULT_ASR_PROVIDER.to_string());
+    let started = Instant::now();
+    let transcribed = transcribe_sample(&data_dir, &task_id, &provider, pcm).await;
+    let asr_ms = started.elapsed().as_millis();
+    let (transcript, asr_error) = match transcribed {
+        Ok(text) => (Some(text.trim().to_string()), None),
+        Err(e) => (None, Some(e)),
+    };
+    let rewrite = match (s.as_ref(), transcript.as_deref()) {
+        (Some(s), Some(text)) if !text.is_empty() => {
+            rewrite_sample(&data_dir, &task_id, s, text).await
+        }
+        _ => None,
+    };
+
+    let bundle = serde_json::json!({
+        "format": BUNDLE_FORMAT,
+        "created_at_ms": now_ms(),
+        "app_version": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "task_id": task_id,
+        "note": note.as_deref().map(str::trim).filter(|v| !v.is_empty()),
+        "input": {
+            "spec": input_spec,
+            "duration_ms": profile.as_ref().map(|p| p.duration_ms),
+            "rms_db": profile.as_ref().map(|p| p.rms_db),
+            "peak_db": profile.as_ref().map(|p| p.peak_db),
+            "noise_floor_db": profile.as_ref().map(|p| p.noise_floor_db),
+        },
+        "settings": s.as_ref().map(settings_summary),
+        "asr": {
+            "provider": provider,
+            "transcript": transcript,
+            "asr_ms": asr_ms,
+            "error_code": asr_error.as_ref().map(|e| e.code.clone()),
+            "error_message": asr_error.as_ref().map(|e| e.message.clone()),
+        },
+        "rewrite": rewrite.as_ref().map(|r| &r.summary),
+    });
+    // The task's own span is still open; everything before it is on disk once
+    // the writer has drained.
+    let _ = obs::flush(500);
+    let written = write_bundle(&data_dir, &task_id, bundle);
+    match written {
+        Ok((path, bytes)) => {
+            span.ok(Some(serde_json::json!({
+                "record_input_spec": input_spec,
+                "provider": provider,
+                "asr_error_code": asr_error.as_ref().map(|e| e.code.clone()),
+                "rewrite_error_code": rewrite.as_ref().and_then(|r| r.error_code.clone()),
+                "bundle_bytes": bytes,
+            })));
+            Ok(BugReportResult {
+                task_id,
+                path: path.display().to_string(),
+                bytes,
+                transcript,
+                asr_error_code: asr_error.map(|e| e.code),
+                rewrite_error_code: rewrite.and_then(|r| r.error_code),
+            })
+        }
+        Err(e) => {
+            let err = PortError::from_message("E_BUG_REPORT_WRITE", e.to_string());
+            span.err("io", &err.code, &err.message, None);
+            Err(err)
+        }
+    }
+}
+
+struct SampleRewrite {
+    summary: serde_json::Value,
+    error_code: Option<String>,
+}
+
+// The configured prompt and glossary without captured context, so the bundle
+// shows exactly what the model saw. Skipped when rewrite is off.
+async fn rewrite_sample(
+    data_dir: &Path,
+    task_id: &str,
+    s: &settings::Settings,
+    text: &str,
+) -> Option<SampleRewrite> {
+    if !s.rewrite_enabled.unwrap_or(false) || settings::resolve_offline_mode(s) {
+        return None;
+    }
+    let prompt = s
+        .llm_prompt
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())?;
+    let include_glossary = s.rewrite_include_glossary.unwrap_or(true);
+    let glossary = if include_glossary {
+        sanitize_rewrite_glossary(s.rewrite_glossary.clone())
+    } else {
+        Vec::new()
+    };
+    let policy = llm::RewriteContextPolicy {
+        include_glossary,
+        ..Default::default()
+    };
+    let started = Instant::now();
+    let result =
+        llm::rewrite_with_context(data_dir, task_id, prompt, text, None, &glossary, &policy).await;
+    let rewrite_ms = started.elapsed().as_millis();
+    let (final_text, error) = match result {
+        Ok(v) => (Some(v), None),
+        Err(e) => (
+            None,
+            Some(PortError::from_message("E_LLM_FAILED", e.to_string())),
+        ),
+    };
+    Some(SampleRewrite {
+        summary: serde_json::json!({
+            "prompt": prompt,
+            "glossary": glossary,
+            "final_text": final_text,
+            "rewrite_ms": rewrite_ms,
+            "error_code": error.as_ref().map(|e| e.code.clone()),
+            "error_message": error.as_ref().map(|e| e.message.clone()),
+        }),
+        error_code: error.map(|e| e.code),
+    })
+}
+
+// Only what shapes the pipeline; API keys live in the secret store and never
+// reach the settings file.
+fn settings_summary(s: &settings::Settings) -> serde_json::Value {
+    serde_json::json!({
+        "asr_provider": settings::resolve_asr_provider(s),
+        "remote_asr_model": settings::resolve_remote_asr_model(s),
+        "rewrite_enabled": s.rewrite_enabled,
+        "llm_model": s.llm_model,
+        "llm_reasoning_effort": s.llm_reasoning_effort,
+        "offline_mode": settings::resolve_offline_mode(s),
+    })
+}
+
+// Adds the task's debug artifacts (text inline, binary as base64) and trace
+// events, then writes the bundle as gzip-compressed JSON.
+fn write_bundle(
+    data_dir: &Path,
+    task_id: &str,
+    mut bundle: serde_json::Value,
+) -> anyhow::Result<(PathBuf, u64)> {
+    bundle["artifacts"] = serde_json::Value::Array(collect_artifacts(data_dir, task_id)?);
+    bundle["events"] = serde_json::Value::Array(trace::read_task_events(data_dir, task_id));
+    let dir = bundle_dir(data_dir);
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{task_id}.json.gz"));
+    let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+    enc.write_all(&serde_json::to_vec(&bundle)?)?;
+    let bytes = enc.finish()?;
+    std::fs::write(&path, &bytes)?;
+    Ok((path, bytes.len() as u64))
+}
+
+fn collect_artifacts(data_dir: &Path, task_id: &str) -> anyhow::Result<Vec<serde_json::Value>> {
+    let task_dir = debug::debug_task_dir(data_dir, task_id);
+    let mut out = Vec::new();
+    for artifact in debug::list_task_artifacts(data_dir, task_id)? {
+        let raw = std::fs::read(task_dir.join(&artifact.name))?;
+        let (encoding, content) = match String::from_utf8(raw) {
+            Ok(text) => ("utf8", text),
+            Err(e) => (
+                "base64",
+                base64::engine::general_purpose::STANDARD.encode(e.into_bytes()),
+            ),
+        };
+        out.push(serde_json::json!({
+            "name": artifact.name,
+            "bytes": artifact.bytes,
+            "encoding": encoding,
+            "content": content,
+        }));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn bundle_holds_artifacts_and_only_this_tasks_events() {
+        let td = tempfile::tempdir().expect("tempdir");
+        let dir = td.path();
+        debug::write_task_artifact(dir, "bug-report-1", "input.wav", &pcm_to_wav_bytes(&[1, 0]))
+            .expect("wav");
+        debug::write_task_artifact(dir, "bug-report-1", "prompt.txt", b"### system\nfix")
+            .expect("prompt");
+        let line = |task: &str| serde_json::json!({"task_id": task}).to_string() + "\n";
+        std::fs::write(
+            trace::trace_path(dir),
+            line("bug-report-1") + &line("other"),
+        )
+        .expect("trace");
+
+        let (path, bytes) = write_bundle(
+            dir,
+            "bug-report-1",
+            serde_json::json!({"format": BUNDLE_FORMAT}),
+        )
+        .expect("bundle");
+        assert_eq!(path, bundle_dir(dir).join("bug-report-1.json.gz"));
+        let gz = std::fs::read(&path).expect("read");
+        assert_eq!(gz.len() as u64, bytes);
+        let mut json = String::new();
+        GzDecoder::new(gz.as_slice())
+            .read_to_string(&mut json)
+            .expect("gunzip");
+        let bundle: serde_json::Value = serde_json::from_str(&json).expect("json");
+
+        assert_eq!(bundle["format"], BUNDLE_FORMAT);
+        assert_eq!(bundle["events"].as_array().map(Vec::len), Some(1));
+        let artifacts = bundle["artifacts"].as_array().expect("artifacts");
+        let by_name = |name: &str| {
+            artifacts
+                .iter()
+                .find(|a| a["name"] == name)
+                .unwrap_or_else(|| panic!("{name}"))
+        };
+        assert_eq!(by_name("prompt.txt")["content"], "### system\nfix");
+        assert_eq!(by_name("input.wav")["encoding"], "base64");
+        let wav = base64::engine::general_purpose::STANDARD
+            .decode(by_name("input.wav")["content"].as_str().unwrap())
+            .expect("b64");
+        assert_eq!(&wav[0..4], b"RIFF");
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+
+use serde::Serialize;
+
+use crate::audio_capture::CaptureError;
+
+// Every path that opens the microphone (dictation recorder, mic test, noise
+// calibration, wake-word listener, bug report sample) holds a CaptureLease for
+// as long as the device is open. With the indicator enforced, the lease is only
+// granted once the indicator is on screen, so no path records unseen; the
+// indicator goes away when the last lease is dropped.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureSource {
+    Recorder,
+    MicTest,
+    NoiseCalibration,
+    WakeWord,
+    BugReport,
+}
+
+pub trait IndicatorPresenter: Send + Sync {
+    // Called with the live sources whenever they change; empty hides it.
+    fn present(&self, sources: &[CaptureSource]) -> Result<(), String>;
+}
+
+#[derive(Default)]
+struct State {
+    enforced: bool,
+    next_id: u64,
+    active: BTreeMap<u64, CaptureSource>,
+}
+
+impl State {
+    fn sources(&self) -> Vec<CaptureSource> {
+        let mut sources: Vec<CaptureSource> = self.active.values().copied().collect();
+        sources.sort();
+        sources.dedup();
+        sources
+    }
+}
+
+fn state() -> &'static Mutex<State> {
+    static STATE: OnceLock<Mutex<State>> = OnceLock::new();
+    STATE.get_or_init(|| Mutex::new(State::default()))
+}
+
+static PRESENTER: OnceLock<Box<dyn IndicatorPresenter>> = OnceLock::new();
+
+pub fn set_presenter(presenter: impl IndicatorPresenter + 'static) {
+    let _ = PRESENTER.set(Box::new(presenter));
+}
+
+// Turning enforcement on mid-capture shows the indicator for the captures
+// already running; turning it off hides it.
+pub fn set_enforced(enforced: bool) -> Result<(), String> {
+    let mut g = state().lock().unwrap();
+    if g.enforced == enforced {
+        return Ok(());
+    }
+    g.enforced = enforced;
+    let sources = if enforced { g.sources() } else { Vec::new() };
+    present(&sources)
+}
+
+pub fn active_sources() -> Vec<CaptureSource> {
+    state().lock().unwrap().sources()
+}
+
+pub fn begin(source: CaptureSource) -> Result<CaptureLease, CaptureError> {
+    let mut g = state().lock().unwrap();
+    let id = g.next_id;
+    g.next_id += 1;
+    g.active.insert(id, source);
+    if g.enforced {
+        if let Err(e) = present(&g.sources()) {
+            g.active.remove(&id);
+            let _ = present(&g.sources());
+            return Err(CaptureError::new(
+                "E_CAPTURE_INDICATOR_UNAVAILABLE",
+                format!("capture indicator could not be shown: {e}"),
+            ));
+        }
+    }
+    Ok(CaptureLease { id })
+}
+
+fn present(sources: &[CaptureSource]) -> Result<(), String> {
+    match PRESENTER.get() {
+        Some(presenter) => presenter.present(sources),
+        None => Err("no indicator presenter installed".to_string()),
+    }
+}
+
+#[derive(Debug)]
+pub struct CaptureLease {
+    id: u64,
+}
+
+impl Drop for CaptureLease {
+    fn drop(&mut self) {
+        let mut g = state().lock().unwrap();
+        g.active.remove(&self.id);
+        if g.enforced {
+            let _ = present(&g.sources());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leases_track_live_sources() {
+        let recorder = begin(CaptureSource::Recorder).expect("not enforced");
+        let wake = begin(CaptureSource::WakeWord).expect("not enforced");
+        let sources = active_sources();
+        assert!(sources.contains(&CaptureSource::Recorder));
+        assert!(sources.contains(&CaptureSource::WakeWord));
+        drop(recorder);
+        drop(wake);
+        assert!(!active_sources().contains(&CaptureSource::WakeWord));
+    }
+}
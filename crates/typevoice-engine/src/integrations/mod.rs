@@ -0,0 +1,2 @@
+// Outbound integrations that follow the UI event stream.
+pub mod mqtt;
@@ -0,0 +1,430 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use rumqttc::{Client, Connection, Event, LastWill, MqttOptions, Outgoing, Packet, QoS, Transport};
+
+use crate::llm::ApiKeyStatus;
+use crate::obs;
+use crate::secret_store;
+use crate::settings::{self, MqttResolved};
+use crate::ui_events::{UiEvent, UiEventKind};
+
+const KEYRING_SERVICE: &str = "typevoice";
+const MQTT_PASSWORD_USER: &str = "mqtt_password";
+const CLIENT_CAPACITY: usize = 64;
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+// Bumped when the stored password changes so the next event reconnects.
+static CREDENTIALS_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+// Topics, relative to the configured prefix:
+//   status     "online" / "offline" (retained; "offline" is also the last will)
+//   state      workflow phase, e.g. "recording" (retained)
+//   recording  "ON" / "OFF" (retained), for busy lights
+//   task       JSON per finished stage or task; never carries dictated text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MqttMessage {
+    pub topic: String,
+    pub payload: String,
+    pub retain: bool,
+}
+
+// Lives inside the UI event actor. Publishing only queues onto the client, so a
+// slow or unreachable broker never delays frontend events.
+#[derive(Default)]
+pub struct MqttPublisher {
+    session: Option<Session>,
+    last_phase: Option<String>,
+}
+
+struct Session {
+    cfg: MqttResolved,
+    generation: u64,
+    client: Client,
+    // Latest payload per retained topic, replayed after every (re)connect.
+    retained: Arc<Mutex<BTreeMap<String, String>>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl MqttPublisher {
+    pub fn on_event(&mut self, event: &UiEvent) {
+        if !is_published_kind(event.kind) {
+            return;
+        }
+        // Settings are re-read per event, like the overlay and captions, so
+        // turning the publisher off or changing the broker applies right away.
+        let Some((dir, cfg)) = load_config() else {
+            self.stop();
+            if event.kind == UiEventKind::WorkflowState {
+                self.last_phase = Some(phase_of(event).to_string());
+            }
+            return;
+        };
+        let generation = CREDENTIALS_GENERATION.load(Ordering::Relaxed);
+        let current = self
+            .session
+            .as_ref()
+            .is_some_and(|s| s.cfg == cfg && s.generation == generation);
+        if !current {
+            self.stop();
+            self.session = Some(Session::start(
+                &dir,
+                cfg,
+                generation,
+                self.last_phase.as_deref(),
+            ));
+        }
+        let Some(session) = &self.session else {
+            return;
+        };
+        for msg in messages_for_event(&session.cfg.topic_prefix, event, &mut self.last_phase) {
+            session.publish(msg);
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Some(session) = self.session.take() {
+            session.stop();
+        }
+    }
+}
+
+impl Session {
+    fn start(
+        data_dir: &Path,
+        cfg: MqttResolved,
+        generation: u64,
+        last_phase: Option<&str>,
+    ) -> Self {
+        let client_id = format!(
+            "typevoice-{}",
+            &uuid::Uuid::new_v4().simple().to_string()[..8]
+        );
+        let status_topic = topic(&cfg.topic_prefix, "status");
+        let mut opts = MqttOptions::new(client_id, cfg.host.clone(), cfg.port);
+        opts.set_keep_alive(KEEP_ALIVE);
+        opts.set_last_will(LastWill::new(
+            status_topic.clone(),
+            "offline",
+            QoS::AtLeastOnce,
+            true,
+        ));
+        if cfg.tls {
+            opts.set_transport(Transport::tls_with_default_config());
+        }
+        if let Some(username) = &cfg.username {
+            opts.set_credentials(username, stored_password().unwrap_or_default());
+        }
+
+        let mut retained = BTreeMap::from([(status_topic, "online".to_string())]);
+        if let Some(phase) = last_phase {
+            for msg in phase_messages(&cfg.topic_prefix, phase, None) {
+                retained.insert(msg.topic, msg.payload);
+            }
+        }
+        let retained = Arc::new(Mutex::new(retained));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (client, connection) = Client::new(opts, CLIENT_CAPACITY);
+        let driver = Driver {
+            data_dir: data_dir.to_path_buf(),
+            broker: format!("{}:{}", cfg.host, cfg.port),
+            client: client.clone(),
+            retained: retained.clone(),
+            stop: stop.clone(),
+        };
+        let spawned = std::thread::Builder::new()
+            .name("mqtt_connection".to_string())
+            .spawn(move || driver.run(connection));
+        if let Err(e) = spawned {
+            obs::event_err(
+                data_dir,
+                obs::ErrorEvent {
+                    task_id: None,
+                    stage: "Integration",
+                    step_id: "MQTT.connect",
+                    kind: "internal",
+                    code: "E_MQTT_THREAD",
+                    ctx: None,
+                },
+                &e.to_string(),
+            );
+        }
+        Self {
+            cfg,
+            generation,
+            client,
+            retained,
+            stop,
+        }
+    }
+
+    fn publish(&self, msg: MqttMessage) {
+        if msg.retain {
+            self.retained
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(msg.topic.clone(), msg.payload.clone());
+        }
+        // A full queue (broker down for a while) drops the message; retained
+        // state is replayed on reconnect anyway.
+        let _ = self
+            .client
+            .try_publish(msg.topic, QoS::AtLeastOnce, msg.retain, msg.payload);
+    }
+
+    // A clean disconnect suppresses the last will, so publish "offline" first.
+    fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.client.try_publish(
+            topic(&self.cfg.topic_prefix, "status"),
+            QoS::AtLeastOnce,
+            true,
+            "offline",
+        );
+        let _ = self.client.try_disconnect();
+    }
+}
+
+struct Driver {
+    data_dir: PathBuf,
+    broker: String,
+    client: Client,
+    retained: Arc<Mutex<BTreeMap<String, String>>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Driver {
+    // Iterating the connection is what sends queued publishes and reconnects.
+    // Failures are logged once per outage, not on every retry.
+    fn run(self, mut connection: Connection) {
+        let mut failing = false;
+        for notification in connection.iter() {
+            match notification {
+                // Sent by Session::stop after the final "offline" publish.
+                Ok(Event::Outgoing(Outgoing::Disconnect)) => break,
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    failing = false;
+                    obs::event(
+                        &self.data_dir,
+                        None,
+                        "Integration",
+                        "MQTT.connect",
+                        "ok",
+                        Some(serde_json::json!({ "broker": self.broker })),
+                    );
+                    let retained = self
+                        .retained
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .clone();
+                    for (topic, payload) in retained {
+                        let _ = self
+                            .client
+                            .try_publish(topic, QoS::AtLeastOnce, true, payload);
+                    }
+                }
+                Ok(_) => {}
+                Err(_) if self.stop.load(Ordering::Relaxed) => break,
+                Err(e) => {
+                    if !failing {
+                        failing = true;
+                        obs::event_err(
+                            &self.data_dir,
+                            obs::ErrorEvent {
+                                task_id: None,
+                                stage: "Integration",
+                                step_id: "MQTT.connect",
+                                kind: "network",
+                                code: "E_MQTT_CONNECT",
+                                ctx: Some(serde_json::json!({ "broker": self.broker })),
+                            },
+                            &e.to_string(),
+                        );
+                    }
+                    std::thread::sleep(RECONNECT_DELAY);
+                }
+            }
+        }
+    }
+}
+
+fn is_published_kind(kind: UiEventKind) -> bool {
+    kind == UiEventKind::WorkflowState || task_event_kind(kind)
+}
+
+fn task_event_kind(kind: UiEventKind) -> bool {
+    matches!(
+        kind,
+        UiEventKind::TranscriptionCompleted
+            | UiEventKind::TranscriptionEmpty
+            | UiEventKind::RewriteCompleted
+            | UiEventKind::InsertionCompleted
+            | UiEventKind::WorkflowTaskFailed
+            | UiEventKind::WorkflowTaskCancelled
+    )
+}
+
+fn phase_of(event: &UiEvent) -> &str {
+    event
+        .payload
+        .as_ref()
+        .and_then(|p| p.get("phase"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("idle")
+}
+
+fn topic(prefix: &str, name: &str) -> String {
+    format!("{prefix}/{name}")
+}
+
+// `recording` is only republished when it flips, so busy lights do not
+// flicker through transcribing/rewriting.
+fn phase_messages(prefix: &str, phase: &str, previous: Option<&str>) -> Vec<MqttMessage> {
+    let mut out = vec![MqttMessage {
+        topic: topic(prefix, "state"),
+        payload: phase.to_string(),
+        retain: true,
+    }];
+    let recording = phase == "recording";
+    if previous.map(|p| p == "recording") != Some(recording) {
+        out.push(MqttMessage {
+            topic: topic(prefix, "recording"),
+            payload: if recording { "ON" } else { "OFF" }.to_string(),
+            retain: true,
+        });
+    }
+    out
+}
+
+pub fn messages_for_event(
+    prefix: &str,
+    event: &UiEvent,
+    last_phase: &mut Option<String>,
+) -> Vec<MqttMessage> {
+    if event.kind == UiEventKind::WorkflowState {
+        let phase = phase_of(event);
+        if last_phase.as_deref() == Some(phase) {
+            return Vec::new();
+        }
+        let out = phase_messages(prefix, phase, last_phase.as_deref());
+        *last_phase = Some(phase.to_string());
+        return out;
+    }
+    if !task_event_kind(event.kind) {
+        return Vec::new();
+    }
+    let payload = serde_json::json!({
+        "kind": event.kind,
+        "task_id": event.task_id,
+        "stage": event.stage,
+        "status": event.status,
+        "error_code": event.error_code,
+        "ts_ms": event.ts_ms,
+    });
+    vec![MqttMessage {
+        topic: topic(prefix, "task"),
+        payload: payload.to_string(),
+        retain: false,
+    }]
+}
+
+// Offline mode keeps the publisher disconnected like every other network path.
+fn load_config() -> Option<(PathBuf, MqttResolved)> {
+    let dir = crate::data_dir::data_dir().ok()?;
+    let s = settings::load_settings_strict(&dir).ok()?;
+    if settings::resolve_offline_mode(&s) {
+        return None;
+    }
+    let cfg = settings::resolve_mqtt(&s)?;
+    Some((dir, cfg))
+}
+
+pub fn set_password(password: &str) -> Result<()> {
+    if password.is_empty() {
+        return Err(anyhow::anyhow!(
+            "E_MQTT_PASSWORD_MISSING: MQTT password is required"
+        ));
+    }
+    secret_store::set(KEYRING_SERVICE, MQTT_PASSWORD_USER, password)?;
+    CREDENTIALS_GENERATION.fetch_add(1, Ordering::Relaxed);
+    Ok(())
+}
+
+pub fn clear_password() -> Result<()> {
+    secret_store::clear(KEYRING_SERVICE, MQTT_PASSWORD_USER)?;
+    CREDENTIALS_GENERATION.fetch_add(1, Ordering::Relaxed);
+    Ok(())
+}
+
+pub fn password_status() -> ApiKeyStatus {
+    secret_store::status(KEYRING_SERVICE, MQTT_PASSWORD_USER)
+}
+
+fn stored_password() -> Option<String> {
+    secret_store::get(KEYRING_SERVICE, MQTT_PASSWORD_USER)
+        .ok()
+        .and_then(|(v, _)| v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(phase: &str) -> UiEvent {
+        UiEvent::workflow_state(serde_json::json!({ "phase": phase }))
+    }
+
+    #[test]
+    fn phase_changes_publish_state_and_recording_flips_only() {
+        let mut last = None;
+        let got = messages_for_event("home/tv", &state("recording"), &mut last);
+        let pairs: Vec<(&str, &str)> = got
+            .iter()
+            .map(|m| (m.topic.as_str(), m.payload.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![("home/tv/state", "recording"), ("home/tv/recording", "ON")]
+        );
+        assert!(got.iter().all(|m| m.retain));
+
+        assert!(messages_for_event("home/tv", &state("recording"), &mut last).is_empty());
+        let got = messages_for_event("home/tv", &state("transcribing"), &mut last);
+        assert_eq!(got.len(), 2);
+        assert_eq!(got[1].payload, "OFF");
+        let got = messages_for_event("home/tv", &state("idle"), &mut last);
+        assert_eq!(got.len(), 1);
+        assert_eq!(got[0].topic, "home/tv/state");
+    }
+
+    #[test]
+    fn task_events_publish_metadata_without_text() {
+        let mut last = None;
+        let event = UiEvent::completed(
+            "task-1",
+            UiEventKind::InsertionCompleted,
+            "insertion completed",
+            serde_json::json!({ "text": "secret dictation" }),
+        );
+        let got = messages_for_event("typevoice", &event, &mut last);
+        assert_eq!(got.len(), 1);
+        assert_eq!(got[0].topic, "typevoice/task");
+        assert!(!got[0].retain);
+        let payload: serde_json::Value = serde_json::from_str(&got[0].payload).expect("json");
+        assert_eq!(payload["kind"], "insertion.completed");
+        assert_eq!(payload["task_id"], "task-1");
+        assert!(!got[0].payload.contains("secret dictation"));
+
+        let partial = UiEvent::partial("task-1", "a", "a", 1);
+        assert!(messages_for_event("typevoice", &partial, &mut last).is_empty());
+    }
+}
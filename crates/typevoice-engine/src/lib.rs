@@ -1,42 +1,91 @@
-pub use typevoice_core::{context_pack, ports};
+pub use typevoice_core::{
+    context_pack, errors, export_format, noise_profile, ports, stage_graph, text_casing,
+    text_chunks, text_normalize, token_budget, wav_preprocess,
+};
 pub use typevoice_observability::obs;
 #[cfg(windows)]
 pub use typevoice_platform::context_capture_windows;
 pub use typevoice_platform::{
-    audio_device_notifications_windows, audio_devices_windows, context_capture, export, insertion,
-    pipeline, record_input, record_input_cache, subprocess, toolchain,
+    audio_device_notifications_windows, audio_devices_windows, context_capture, export,
+    ffmpeg_spare, insertion, pipeline, postprocess_plugins, power, record_input,
+    record_input_cache, screen_share, sinks, subprocess, toolchain,
+};
+pub use typevoice_providers::{
+    asr_lanes, asr_warm_pool, doubao_asr, gpu_coordinator, http_client, llm, remote_asr,
+    secret_store, webhook,
+};
+pub use typevoice_storage::{
+    corrections, data_dir, formatting_profiles, guest_mode, history, history_writer, rewrite_cache,
+    settings, workspaces,
 };
-pub use typevoice_providers::{doubao_asr, llm, remote_asr};
-pub use typevoice_storage::{data_dir, history, settings};
 
+pub mod asr_compare;
 pub mod audio_capture;
+pub mod bug_report;
+pub mod capture_indicator;
+pub mod integrations;
+pub mod mic_test;
 mod pcm;
+pub mod pipeline_runtime;
+mod recorder;
 pub mod rewrite;
+pub mod rewrite_compare;
+pub mod scheduler;
+pub mod schemas;
 pub mod task_manager;
 pub mod transcription;
 pub mod transcription_actor;
 pub mod ui_events;
+pub mod vad;
 pub mod voice_tasks;
 pub mod voice_workflow;
 
 pub struct RuntimeState {
-    toolchain: std::sync::Mutex<toolchain::ToolchainStatus>,
+    // Last preflight result and when it was taken; None until the first check.
+    toolchain: std::sync::Mutex<(toolchain::ToolchainStatus, Option<std::time::Instant>)>,
+    // Held across a whole check so two callers never race on the ready transition.
+    preflight: std::sync::Mutex<()>,
 }
 
 impl RuntimeState {
     pub fn new() -> Self {
         Self {
-            toolchain: std::sync::Mutex::new(toolchain::ToolchainStatus::pending()),
+            toolchain: std::sync::Mutex::new((toolchain::ToolchainStatus::pending(), None)),
+            preflight: std::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn preflight_guard(&self) -> std::sync::MutexGuard<'_, ()> {
+        self.preflight.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // None while another check is running; that check's result is as fresh.
+    pub fn try_preflight_guard(&self) -> Option<std::sync::MutexGuard<'_, ()>> {
+        match self.preflight.try_lock() {
+            Ok(g) => Some(g),
+            Err(std::sync::TryLockError::Poisoned(e)) => Some(e.into_inner()),
+            Err(std::sync::TryLockError::WouldBlock) => None,
         }
     }
 
     pub fn set_toolchain(&self, st: toolchain::ToolchainStatus) {
         let mut g = self.toolchain.lock().unwrap();
-        *g = st;
+        *g = (st, Some(std::time::Instant::now()));
     }
 
     pub fn get_toolchain(&self) -> toolchain::ToolchainStatus {
-        self.toolchain.lock().unwrap().clone()
+        self.toolchain.lock().unwrap().0.clone()
+    }
+
+    // Any result older than `ttl` is checked again: ffmpeg may have been
+    // installed or removed since. Failures a recheck cannot fix are kept
+    // until an explicit refresh.
+    pub fn toolchain_needs_recheck(&self, ttl: std::time::Duration) -> bool {
+        let g = self.toolchain.lock().unwrap();
+        if !g.0.ready && toolchain::is_permanent_failure(g.0.code.as_deref()) {
+            return false;
+        }
+        g.1.is_none_or(|at| at.elapsed() >= ttl)
     }
 }
 
@@ -0,0 +1,204 @@
+use std::path::Path;
+use std::time::Instant;
+
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+
+use crate::audio_capture::{capture_error_kind, RecordingRegistry};
+use crate::capture_indicator::CaptureSource;
+use crate::pcm::pcm_to_wav_bytes;
+use crate::ports::{PortError, PortResult};
+use crate::record_input_cache::RecordInputCacheState;
+use crate::{
+    data_dir, doubao_asr, noise_profile, obs, pipeline, remote_asr, settings, transcription_actor,
+};
+
+const MIC_TEST_MS: u64 = 3_000;
+const SILENT_PEAK_DB: f64 = -50.0;
+const CLIPPING_PEAK_DB: f64 = -0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MicLevelVerdict {
+    Ok,
+    Silent,
+    Clipping,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MicTestResult {
+    pub input_spec: String,
+    pub duration_ms: u64,
+    pub rms_db: f64,
+    pub peak_db: f64,
+    pub noise_floor_db: f64,
+    pub level: MicLevelVerdict,
+    pub provider: String,
+    pub transcript: Option<String>,
+    pub asr_ms: Option<u128>,
+    pub asr_error_code: Option<String>,
+    pub asr_error_message: Option<String>,
+}
+
+// Records a short sample from the resolved input and runs it through the
+// configured ASR provider without touching the workflow, history or the
+// active transcription task. Capture failures fail the command; ASR failures
+// are reported in the result so the level stats are still shown.
+pub async fn run_mic_test(
+    recordings: RecordingRegistry,
+    record_input_cache: RecordInputCacheState,
+) -> PortResult<MicTestResult> {
+    let data_dir =
+        data_dir::data_dir().map_err(|e| PortError::from_message("E_DATA_DIR", e.to_string()))?;
+    let span = obs::Span::start(
+        &data_dir,
+        None,
+        "Cmd",
+        "CMD.mic_test",
+        Some(serde_json::json!({"duration_ms": MIC_TEST_MS})),
+    );
+    let captured = tokio::task::spawn_blocking(move || {
+        recordings.capture_pcm(&record_input_cache, MIC_TEST_MS, CaptureSource::MicTest)
+    })
+    .await;
+    let (pcm, input_spec) = match captured {
+        Ok(Ok(v)) => v,
+        Ok(Err(e)) => {
+            span.err(capture_error_kind(&e.code), &e.code, &e.message, None);
+            return Err(PortError::new(&e.code, e.message));
+        }
+        Err(e) => {
+            let err = PortError::new("E_INTERNAL", format!("mic_test_join_failed:{e}"));
+            span.err("process", &err.code, &err.message, None);
+            return Err(err);
+        }
+    };
+    let Some(profile) = noise_profile::analyze_ambient_pcm(&pcm, doubao_asr::PCM_SAMPLE_RATE)
+    else {
+        let err = PortError::new(
+            "E_MIC_TEST_EMPTY",
+            "recorder produced too little audio to measure",
+        );
+        span.err("logic", &err.code, &err.message, None);
+        return Err(err);
+    };
+    let level = level_verdict(profile.peak_db);
+    let provider = settings::load_settings_strict(&data_dir)
+        .map(|s| settings::resolve_asr_provider(&s))
+        .unwrap_or_else(|_| settings::DEFAULT_ASR_PROVIDER.to_string());
+
+    let task_id = format!("mic-test-{}", uuid::Uuid::new_v4());
+    let started = Instant::now();
+    let transcribed = if level == MicLevelVerdict::Silent {
+        Err(PortError::new(
+            "E_MIC_TEST_SILENT",
+            "no audio signal detected; transcription skipped",
+        ))
+    } else {
+        transcribe_sample(&data_dir, &task_id, &provider, pcm).await
+    };
+    let asr_ms = started.elapsed().as_millis();
+
+    let (transcript, asr_error) = match transcribed {
+        Ok(text) => (Some(text.trim().to_string()), None),
+        Err(e) => (None, Some(e)),
+    };
+    span.ok(Some(serde_json::json!({
+        "record_input_spec": input_spec,
+        "peak_db": profile.peak_db,
+        "rms_db": profile.rms_db,
+        "level": level,
+        "provider": provider,
+        "transcript_chars": transcript.as_ref().map(|v| v.chars().count()),
+        "asr_ms": asr_ms,
+        "asr_error_code": asr_error.as_ref().map(|e| e.code.clone()),
+    })));
+    Ok(MicTestResult {
+        input_spec,
+        duration_ms: profile.duration_ms,
+        rms_db: profile.rms_db,
+        peak_db: profile.peak_db,
+        noise_floor_db: profile.noise_floor_db,
+        level,
+        provider,
+        asr_ms: transcript.as_ref().map(|_| asr_ms),
+        transcript,
+        asr_error_code: asr_error.as_ref().map(|e| e.code.clone()),
+        asr_error_message: asr_error.map(|e| e.message),
+    })
+}
+
+fn level_verdict(peak_db: f64) -> MicLevelVerdict {
+    if peak_db < SILENT_PEAK_DB {
+        MicLevelVerdict::Silent
+    } else if peak_db >= CLIPPING_PEAK_DB {
+        MicLevelVerdict::Clipping
+    } else {
+        MicLevelVerdict::Ok
+    }
+}
+
+pub(crate) async fn transcribe_sample(
+    data_dir: &Path,
+    task_id: &str,
+    provider: &str,
+    pcm: Vec<u8>,
+) -> PortResult<String> {
+    if provider != "remote" {
+        let task_id = task_id.to_string();
+        return tokio::task::spawn_blocking(move || {
+            transcription_actor::transcribe_doubao_once(&task_id, &pcm)
+        })
+        .await
+        .map_err(|e| PortError::new("E_INTERNAL", format!("mic_test_asr_join_failed:{e}")))?
+        .map_err(|e| PortError::from_message("E_DOUBAO_ASR_FAILED", e.to_string()));
+    }
+
+    let s = settings::load_settings_strict(data_dir)
+        .map_err(|e| PortError::from_message("E_SETTINGS_INVALID", e.to_string()))?;
+    let cfg = remote_asr::RemoteAsrConfig {
+        url: settings::resolve_remote_asr_url(&s),
+        model: settings::resolve_remote_asr_model(&s),
+        concurrency: settings::resolve_remote_asr_concurrency(&s),
+        lane: remote_asr::AsrLane::Interactive,
+        resume: false,
+    };
+    let wav_path = pipeline::preprocess_to_temp_wav(data_dir, task_id)
+        .map_err(|e| PortError::from_message("E_MIC_TEST_WAV", e.to_string()))?;
+    std::fs::write(&wav_path, pcm_to_wav_bytes(&pcm))
+        .map_err(|e| PortError::new("E_MIC_TEST_WAV", e.to_string()))?;
+    let result = remote_asr::transcribe_remote(
+        data_dir,
+        task_id,
+        &wav_path,
+        &CancellationToken::new(),
+        &cfg,
+    )
+    .await;
+    let _ = std::fs::remove_file(&wav_path);
+    result
+        .map(|v| v.text)
+        .map_err(|e| PortError::new(&e.code, e.message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_verdict_flags_silent_and_clipping_inputs() {
+        assert_eq!(level_verdict(-72.0), MicLevelVerdict::Silent);
+        assert_eq!(level_verdict(-18.0), MicLevelVerdict::Ok);
+        assert_eq!(level_verdict(0.0), MicLevelVerdict::Clipping);
+    }
+
+    #[test]
+    fn wav_header_describes_mono_16k_pcm() {
+        let wav = pcm_to_wav_bytes(&[1, 0, 2, 0]);
+
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(wav[24..28].try_into().unwrap()), 16_000);
+        assert_eq!(u32::from_le_bytes(wav[40..44].try_into().unwrap()), 4);
+        assert_eq!(wav.len(), 48);
+    }
+}
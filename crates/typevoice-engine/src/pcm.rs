@@ -13,3 +13,58 @@ pub(crate) fn pcm_bytes_for_ms(ms: u64) -> usize {
         * u64::from(doubao_asr::PCM_BITS / 8);
     ((bytes_per_second * ms) / 1000) as usize
 }
+
+// Wraps raw mono s16le PCM in a canonical 44-byte WAV header.
+pub(crate) fn pcm_to_wav_bytes(pcm: &[u8]) -> Vec<u8> {
+    let channels = doubao_asr::PCM_CHANNELS;
+    let bits = doubao_asr::PCM_BITS;
+    let sample_rate = doubao_asr::PCM_SAMPLE_RATE;
+    let block_align = channels * (bits / 8);
+    let data_len = pcm.len() as u32;
+    let mut out = Vec::with_capacity(44 + pcm.len());
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16_u32.to_le_bytes());
+    out.extend_from_slice(&1_u16.to_le_bytes());
+    out.extend_from_slice(&channels.to_le_bytes());
+    out.extend_from_slice(&sample_rate.to_le_bytes());
+    out.extend_from_slice(&(sample_rate * u32::from(block_align)).to_le_bytes());
+    out.extend_from_slice(&block_align.to_le_bytes());
+    out.extend_from_slice(&bits.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    out.extend_from_slice(pcm);
+    out
+}
+
+// PCM payload of the `data` chunk of a RIFF/WAV file; the format is trusted to
+// be the mono s16le 16 kHz that preprocessing produces.
+pub(crate) fn wav_pcm_data(wav: &[u8]) -> Option<&[u8]> {
+    if wav.len() < 12 || &wav[0..4] != b"RIFF" || &wav[8..12] != b"WAVE" {
+        return None;
+    }
+    let mut offset = 12;
+    while offset + 8 <= wav.len() {
+        let id = &wav[offset..offset + 4];
+        let len = u32::from_le_bytes(wav[offset + 4..offset + 8].try_into().ok()?) as usize;
+        let body = offset + 8;
+        if id == b"data" {
+            return Some(&wav[body..body.saturating_add(len).min(wav.len())]);
+        }
+        offset = body.checked_add(len)?.checked_add(len % 2)?;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wav_pcm_data_round_trips_the_canonical_header() {
+        let pcm = [1u8, 0, 2, 0, 3, 0];
+        assert_eq!(wav_pcm_data(&pcm_to_wav_bytes(&pcm)), Some(&pcm[..]));
+        assert_eq!(wav_pcm_data(b"not a wav file"), None);
+    }
+}
@@ -0,0 +1,50 @@
+use std::sync::OnceLock;
+
+use tokio::runtime::{Builder, Handle, Runtime};
+
+const WORKER_THREADS: usize = 2;
+
+// The multi-thread runtime every pipeline session runs on, instead of one
+// current-thread runtime per task. It lives for the whole process: dropping a
+// runtime from inside Tauri's own async context at exit would panic.
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+fn runtime() -> &'static Runtime {
+    RUNTIME.get_or_init(|| {
+        Builder::new_multi_thread()
+            .worker_threads(WORKER_THREADS)
+            .thread_name("tv_pipeline")
+            .enable_all()
+            .build()
+            .expect("failed to build pipeline runtime")
+    })
+}
+
+// Managed app state. Engine code reaches the runtime through `handle()`, so
+// actor threads need no AppHandle; tests and tools start it on first use.
+#[derive(Clone)]
+pub struct PipelineRuntime {
+    handle: Handle,
+}
+
+impl PipelineRuntime {
+    pub fn new() -> Self {
+        Self {
+            handle: runtime().handle().clone(),
+        }
+    }
+
+    pub fn handle(&self) -> &Handle {
+        &self.handle
+    }
+}
+
+impl Default for PipelineRuntime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn handle() -> Handle {
+    runtime().handle().clone()
+}
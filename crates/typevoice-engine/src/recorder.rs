@@ -0,0 +1,552 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
+    sync::{mpsc, Arc, Mutex},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SizedSample};
+
+use crate::audio_capture::CaptureError;
+use crate::doubao_asr;
+use crate::pcm::{pcm_bytes_for_ms, pcm_to_wav_bytes};
+
+const START_TIMEOUT: Duration = Duration::from_secs(3);
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+// How long past the requested duration a fixed-length capture waits for the
+// device to deliver before giving up.
+const CAPTURE_GRACE: Duration = Duration::from_secs(2);
+const WAV_HEADER_LEN: usize = 44;
+
+// In-process capture through cpal: the device's own stream format is mixed
+// down to mono, resampled to 16 kHz s16le, written to the wav file and handed
+// to a `PcmReader`, the same shape ffmpeg produces on its stdout pipe.
+pub(crate) struct NativeRecorder {
+    stop_tx: mpsc::Sender<()>,
+    join: Option<JoinHandle<Result<CaptureSummary, CaptureError>>>,
+    pub device_name: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct CaptureSummary {
+    pub device_name: String,
+    pub input_sample_rate: u32,
+    pub input_channels: u16,
+    pub input_sample_format: String,
+    pub pcm_bytes: u64,
+    pub stream_errors: usize,
+}
+
+// Blocking reader over the converted PCM; reads 0 once the recorder stops.
+pub(crate) struct PcmReader {
+    rx: mpsc::Receiver<Vec<u8>>,
+    pending: Vec<u8>,
+    offset: usize,
+}
+
+impl Read for PcmReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.offset >= self.pending.len() {
+            match self.rx.recv() {
+                Ok(next) => {
+                    self.pending = next;
+                    self.offset = 0;
+                }
+                Err(_) => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.pending.len() - self.offset);
+        buf[..n].copy_from_slice(&self.pending[self.offset..self.offset + n]);
+        self.offset += n;
+        Ok(n)
+    }
+}
+
+// Opens `device_name` (the friendly name record input resolution picked) or
+// the default input when it is missing or not found. Returns once the stream
+// is running, or with the error that kept it from starting.
+pub(crate) fn start(
+    device_name: Option<&str>,
+    output_path: Option<&Path>,
+    gain_db: Option<f64>,
+) -> Result<(NativeRecorder, PcmReader), CaptureError> {
+    let wav = match output_path {
+        Some(path) => Some(WavWriter::create(path)?),
+        None => None,
+    };
+    let (pcm_tx, pcm_rx) = mpsc::channel();
+    let (stop_tx, stop_rx) = mpsc::channel();
+    let (ready_tx, ready_rx) = mpsc::channel();
+    let device_name = device_name.map(str::to_string);
+    let gain = gain_db
+        .map(|db| 10_f32.powf(db as f32 / 20.0))
+        .unwrap_or(1.0);
+    // cpal streams are not Send on every host, so the stream lives and dies on
+    // this thread.
+    let join = std::thread::spawn(move || {
+        run_capture(device_name.as_deref(), gain, wav, pcm_tx, stop_rx, ready_tx)
+    });
+    match ready_rx.recv_timeout(START_TIMEOUT) {
+        Ok(Ok(device_name)) => Ok((
+            NativeRecorder {
+                stop_tx,
+                join: Some(join),
+                device_name,
+            },
+            PcmReader {
+                rx: pcm_rx,
+                pending: Vec::new(),
+                offset: 0,
+            },
+        )),
+        Ok(Err(err)) => {
+            let _ = join.join();
+            if let Some(path) = output_path {
+                let _ = std::fs::remove_file(path);
+            }
+            Err(err)
+        }
+        Err(_) => {
+            let _ = stop_tx.send(());
+            if let Some(path) = output_path {
+                let _ = std::fs::remove_file(path);
+            }
+            Err(CaptureError::new(
+                "E_RECORD_START_FAILED",
+                "native recorder did not start in time",
+            ))
+        }
+    }
+}
+
+impl NativeRecorder {
+    // Stops the stream, flushes what it already delivered and finalizes the
+    // wav header; the paired `PcmReader` sees EOF after the last chunk.
+    pub(crate) fn stop(mut self) -> Result<CaptureSummary, CaptureError> {
+        let _ = self.stop_tx.send(());
+        match self.join.take().map(|join| join.join()) {
+            Some(Ok(result)) => result,
+            _ => Err(CaptureError::new(
+                "E_RECORD_STOP_FAILED",
+                "native recorder thread panicked",
+            )),
+        }
+    }
+}
+
+impl Drop for NativeRecorder {
+    fn drop(&mut self) {
+        let _ = self.stop_tx.send(());
+        if let Some(join) = self.join.take() {
+            let _ = join.join();
+        }
+    }
+}
+
+// Fixed-length mono 16 kHz PCM from the device, without a wav file or gain,
+// like the ffmpeg sample capture.
+pub(crate) fn capture(
+    device_name: Option<&str>,
+    duration_ms: u64,
+) -> Result<(Vec<u8>, String), CaptureError> {
+    let (recorder, reader) = start(device_name, None, None)?;
+    let want = pcm_bytes_for_ms(duration_ms);
+    let deadline = Instant::now() + Duration::from_millis(duration_ms) + CAPTURE_GRACE;
+    let mut pcm = Vec::with_capacity(want);
+    while pcm.len() < want {
+        let left = deadline.saturating_duration_since(Instant::now());
+        match reader.rx.recv_timeout(left) {
+            Ok(chunk) => pcm.extend_from_slice(&chunk),
+            Err(_) => break,
+        }
+    }
+    let device_name = recorder.device_name.clone();
+    let summary = recorder.stop()?;
+    if pcm.is_empty() && summary.stream_errors > 0 {
+        return Err(CaptureError::new(
+            "E_RECORD_SAMPLE_FAILED",
+            format!("input stream reported {} errors", summary.stream_errors),
+        ));
+    }
+    pcm.truncate(want);
+    Ok((pcm, device_name))
+}
+
+fn run_capture(
+    device_name: Option<&str>,
+    gain: f32,
+    mut wav: Option<WavWriter>,
+    pcm_tx: mpsc::Sender<Vec<u8>>,
+    stop_rx: mpsc::Receiver<()>,
+    ready_tx: mpsc::Sender<Result<String, CaptureError>>,
+) -> Result<CaptureSummary, CaptureError> {
+    let opened = open_stream(device_name, gain);
+    let (stream, chunks, errors, mut summary) = match opened {
+        Ok(v) => v,
+        Err(err) => {
+            let _ = ready_tx.send(Err(err.clone()));
+            return Err(err);
+        }
+    };
+    if let Err(e) = stream.play() {
+        let err = CaptureError::new(
+            "E_RECORD_START_FAILED",
+            format!("failed to start input stream: {e}"),
+        );
+        let _ = ready_tx.send(Err(err.clone()));
+        return Err(err);
+    }
+    let _ = ready_tx.send(Ok(summary.device_name.clone()));
+
+    let mut write_error = None;
+    let mut forward = |chunk: Vec<u8>, summary: &mut CaptureSummary| {
+        summary.pcm_bytes += chunk.len() as u64;
+        if let Some(w) = wav.as_mut() {
+            if let Err(e) = w.write(&chunk) {
+                write_error.get_or_insert(e);
+            }
+        }
+        // The reader going away only means nobody meters this recording.
+        let _ = pcm_tx.send(chunk);
+    };
+    loop {
+        match chunks.recv_timeout(POLL_INTERVAL) {
+            Ok(chunk) => forward(chunk, &mut summary),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+        match stop_rx.try_recv() {
+            Ok(()) | Err(mpsc::TryRecvError::Disconnected) => break,
+            Err(mpsc::TryRecvError::Empty) => {}
+        }
+    }
+    drop(stream);
+    while let Ok(chunk) = chunks.try_recv() {
+        forward(chunk, &mut summary);
+    }
+    drop(forward);
+    summary.stream_errors = *errors.lock().unwrap();
+    if let Some(e) = write_error {
+        return Err(e);
+    }
+    if let Some(w) = wav {
+        w.finish()?;
+    }
+    Ok(summary)
+}
+
+type OpenedStream = (
+    cpal::Stream,
+    mpsc::Receiver<Vec<u8>>,
+    Arc<Mutex<usize>>,
+    CaptureSummary,
+);
+
+fn open_stream(device_name: Option<&str>, gain: f32) -> Result<OpenedStream, CaptureError> {
+    let host = cpal::default_host();
+    let named = device_name.and_then(|want| {
+        host.input_devices()
+            .ok()?
+            .find(|d| d.name().map(|n| n == want).unwrap_or(false))
+    });
+    let device = named
+        .or_else(|| host.default_input_device())
+        .ok_or_else(|| {
+            CaptureError::new(
+                "E_RECORD_DEVICE_NOT_FOUND",
+                "no audio input device available",
+            )
+        })?;
+    let name = device.name().unwrap_or_else(|_| "unknown".to_string());
+    let config = device.default_input_config().map_err(|e| {
+        CaptureError::new(
+            "E_RECORD_START_FAILED",
+            format!("failed to query input format of {name}: {e}"),
+        )
+    })?;
+    let sample_format = config.sample_format();
+    let stream_config = config.config();
+    let summary = CaptureSummary {
+        device_name: name.clone(),
+        input_sample_rate: stream_config.sample_rate.0,
+        input_channels: stream_config.channels,
+        input_sample_format: format!("{sample_format:?}"),
+        pcm_bytes: 0,
+        stream_errors: 0,
+    };
+    let (tx, rx) = mpsc::channel();
+    let errors = Arc::new(Mutex::new(0_usize));
+    let stream = match sample_format {
+        cpal::SampleFormat::F32 => build_stream::<f32>(&device, &stream_config, gain, tx, &errors),
+        cpal::SampleFormat::I16 => build_stream::<i16>(&device, &stream_config, gain, tx, &errors),
+        cpal::SampleFormat::U16 => build_stream::<u16>(&device, &stream_config, gain, tx, &errors),
+        cpal::SampleFormat::I32 => build_stream::<i32>(&device, &stream_config, gain, tx, &errors),
+        other => {
+            return Err(CaptureError::new(
+                "E_RECORD_START_FAILED",
+                format!("unsupported input sample format {other:?} on {name}"),
+            ))
+        }
+    }
+    .map_err(|e| {
+        CaptureError::new(
+            "E_RECORD_START_FAILED",
+            format!("failed to open input stream on {name}: {e}"),
+        )
+    })?;
+    Ok((stream, rx, errors, summary))
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    gain: f32,
+    tx: mpsc::Sender<Vec<u8>>,
+    errors: &Arc<Mutex<usize>>,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = usize::from(config.channels.max(1));
+    let mut resampler = Resampler::new(config.sample_rate.0, doubao_asr::PCM_SAMPLE_RATE);
+    let mut mono = Vec::new();
+    let errors = errors.clone();
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            downmix(data, channels, &mut mono);
+            let mut out = Vec::with_capacity(mono.len() * 2);
+            resampler.push(&mono, gain, &mut out);
+            if !out.is_empty() {
+                let _ = tx.send(out);
+            }
+        },
+        move |_| *errors.lock().unwrap() += 1,
+        None,
+    )
+}
+
+fn downmix<T>(data: &[T], channels: usize, out: &mut Vec<f32>)
+where
+    T: Sample,
+    f32: FromSample<T>,
+{
+    out.clear();
+    out.extend(data.chunks(channels).map(|frame| {
+        frame.iter().map(|&s| s.to_sample::<f32>()).sum::<f32>() / frame.len() as f32
+    }));
+}
+
+// Linear-interpolating rate converter that keeps its phase across callbacks.
+// Downsampling low-passes first, like ffmpeg's resampler, so 8-24 kHz content
+// of a 48 kHz device does not fold into the speech band.
+struct Resampler {
+    step: f64,
+    // Position of the next output sample, where 0 is `last` and 1 the first
+    // sample of the next input block.
+    pos: f64,
+    last: f32,
+    filter: Option<LowPass>,
+}
+
+impl Resampler {
+    fn new(from_rate: u32, to_rate: u32) -> Self {
+        let step = f64::from(from_rate.max(1)) / f64::from(to_rate.max(1));
+        Self {
+            step,
+            pos: 1.0,
+            last: 0.0,
+            // 90% of the output Nyquist, in cycles per input sample.
+            filter: (step > 1.0).then(|| LowPass::new(0.45 / step)),
+        }
+    }
+
+    fn push(&mut self, input: &[f32], gain: f32, out: &mut Vec<u8>) {
+        let filtered = self.filter.as_mut().map(|f| f.apply(input));
+        let input = filtered.as_deref().unwrap_or(input);
+        let at = |i: usize| if i == 0 { self.last } else { input[i - 1] };
+        while self.pos <= input.len() as f64 {
+            let i = self.pos.floor() as usize;
+            let frac = (self.pos - i as f64) as f32;
+            let a = at(i);
+            let s = if frac > 0.0 {
+                a + (at(i + 1) - a) * frac
+            } else {
+                a
+            };
+            out.extend_from_slice(&to_i16(s * gain).to_le_bytes());
+            self.pos += self.step;
+        }
+        if let Some(last) = input.last() {
+            self.last = *last;
+            self.pos -= input.len() as f64;
+        }
+    }
+}
+
+const LOW_PASS_TAPS: usize = 63;
+
+// Blackman-windowed sinc FIR with unity DC gain; keeps its tail across blocks.
+struct LowPass {
+    taps: Vec<f32>,
+    history: Vec<f32>,
+}
+
+impl LowPass {
+    fn new(cutoff: f64) -> Self {
+        use std::f64::consts::PI;
+        let last = (LOW_PASS_TAPS - 1) as f64;
+        let taps: Vec<f64> = (0..LOW_PASS_TAPS)
+            .map(|i| {
+                let x = i as f64 - last / 2.0;
+                let sinc = if x == 0.0 {
+                    2.0 * cutoff
+                } else {
+                    (2.0 * PI * cutoff * x).sin() / (PI * x)
+                };
+                let w = 2.0 * PI * i as f64 / last;
+                sinc * (0.42 - 0.5 * w.cos() + 0.08 * (2.0 * w).cos())
+            })
+            .collect();
+        let sum: f64 = taps.iter().sum();
+        Self {
+            taps: taps.into_iter().map(|t| (t / sum) as f32).collect(),
+            history: vec![0.0; LOW_PASS_TAPS - 1],
+        }
+    }
+
+    fn apply(&mut self, input: &[f32]) -> Vec<f32> {
+        let mut buf = std::mem::take(&mut self.history);
+        buf.extend_from_slice(input);
+        let out = buf
+            .windows(self.taps.len())
+            .map(|w| w.iter().zip(&self.taps).map(|(s, t)| s * t).sum())
+            .collect();
+        self.history = buf.split_off(buf.len() - (self.taps.len() - 1));
+        out
+    }
+}
+
+fn to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)).round() as i16
+}
+
+// Writes the data chunk as it arrives and patches the RIFF sizes on finish.
+struct WavWriter {
+    file: BufWriter<File>,
+    data_len: u32,
+}
+
+impl WavWriter {
+    fn create(path: &Path) -> Result<Self, CaptureError> {
+        let mut file = File::create(path)
+            .map(BufWriter::new)
+            .map_err(|e| CaptureError::new("E_RECORD_START_FAILED", e.to_string()))?;
+        file.write_all(&pcm_to_wav_bytes(&[]))
+            .map_err(|e| CaptureError::new("E_RECORD_START_FAILED", e.to_string()))?;
+        Ok(Self { file, data_len: 0 })
+    }
+
+    fn write(&mut self, pcm: &[u8]) -> Result<(), CaptureError> {
+        self.data_len = self.data_len.saturating_add(pcm.len() as u32);
+        self.file
+            .write_all(pcm)
+            .map_err(|e| CaptureError::new("E_RECORD_WRITE_FAILED", e.to_string()))
+    }
+
+    fn finish(mut self) -> Result<(), CaptureError> {
+        let patch = |file: &mut BufWriter<File>, data_len: u32| -> std::io::Result<()> {
+            file.seek(SeekFrom::Start(4))?;
+            file.write_all(&(data_len + WAV_HEADER_LEN as u32 - 8).to_le_bytes())?;
+            file.seek(SeekFrom::Start(WAV_HEADER_LEN as u64 - 4))?;
+            file.write_all(&data_len.to_le_bytes())?;
+            file.flush()
+        };
+        patch(&mut self.file, self.data_len)
+            .map_err(|e| CaptureError::new("E_RECORD_WRITE_FAILED", e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(pcm: &[u8]) -> Vec<i16> {
+        pcm.chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect()
+    }
+
+    #[test]
+    fn resampler_keeps_rate_and_phase_across_blocks() {
+        let input: Vec<f32> = (0..480).map(|i| (i % 7) as f32 / 10.0).collect();
+        let mut same = Resampler::new(16_000, 16_000);
+        let mut out = Vec::new();
+        same.push(&input[..100], 1.0, &mut out);
+        same.push(&input[100..], 1.0, &mut out);
+        let expected: Vec<i16> = input.iter().map(|s| to_i16(*s)).collect();
+        assert_eq!(samples(&out), expected);
+
+        let mut down = Resampler::new(48_000, 16_000);
+        let mut whole = Vec::new();
+        down.push(&input, 1.0, &mut whole);
+        let mut down = Resampler::new(48_000, 16_000);
+        let mut split = Vec::new();
+        for block in input.chunks(37) {
+            down.push(block, 1.0, &mut split);
+        }
+        assert_eq!(samples(&whole).len(), 160);
+        assert_eq!(split, whole);
+    }
+
+    #[test]
+    fn downsampling_filters_out_content_above_the_new_nyquist() {
+        let tone = |hz: f64| -> Vec<f32> {
+            (0..4_800)
+                .map(|i| {
+                    (0.5 * (2.0 * std::f64::consts::PI * hz * i as f64 / 48_000.0).sin()) as f32
+                })
+                .collect()
+        };
+        // Peak after the filter has settled.
+        let peak = |input: &[f32]| -> f32 {
+            let mut out = Vec::new();
+            Resampler::new(48_000, 16_000).push(input, 1.0, &mut out);
+            samples(&out)[100..]
+                .iter()
+                .map(|s| f32::from(*s).abs() / f32::from(i16::MAX))
+                .fold(0.0, f32::max)
+        };
+        // 1 kHz speech-band tone passes; 20 kHz would alias to 4 kHz.
+        assert!((peak(&tone(1_000.0)) - 0.5).abs() < 0.02);
+        assert!(peak(&tone(20_000.0)) < 0.005);
+    }
+
+    #[test]
+    fn downmix_averages_frames_and_gain_clamps() {
+        let mut mono = Vec::new();
+        downmix(&[0.5_f32, -0.5, 1.0, 0.0], 2, &mut mono);
+        assert_eq!(mono, [0.0, 0.5]);
+        let mut out = Vec::new();
+        Resampler::new(16_000, 16_000).push(&[0.8], 2.0, &mut out);
+        assert_eq!(samples(&out), [i16::MAX]);
+    }
+
+    #[test]
+    fn wav_writer_patches_sizes_on_finish() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("out.wav");
+        let mut w = WavWriter::create(&path).expect("create");
+        w.write(&[1, 0, 2, 0]).expect("write");
+        w.write(&[3, 0]).expect("write");
+        w.finish().expect("finish");
+        assert_eq!(
+            std::fs::read(&path).expect("read"),
+            pcm_to_wav_bytes(&[1, 0, 2, 0, 3, 0])
+        );
+    }
+}
@@ -1,29 +1,104 @@
 use std::time::Instant;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
 
-use crate::ports::{PortError, PortResult};
-use crate::{context_capture, context_pack, data_dir, history, llm, settings, task_manager};
+use crate::obs::{debug, metrics, schema::MetricsRecord};
+use crate::ports::{ContextCollector, PortError, PortResult};
+use crate::{
+    context_capture, context_pack, data_dir, formatting_profiles, gpu_coordinator, guest_mode,
+    history, llm, obs, rewrite_cache, settings, text_chunks, token_budget, transcription,
+    workspaces,
+};
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct RewriteTextRequest {
     pub transcript_id: String,
     pub text: String,
+    #[serde(default)]
+    pub template_id: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct RewriteResult {
     pub transcript_id: String,
     pub final_text: String,
     pub rewrite_ms: u128,
+    // Set when structured output failed validation and the raw reply was kept.
+    #[serde(default)]
+    pub output_error_code: Option<String>,
+    // Template the text was rewritten with; later exports follow its settings.
+    #[serde(default)]
+    pub template_id: Option<String>,
 }
 
+// Estimated size of the request about to be sent, after the token cap has
+// dropped what it had to. Reported before the LLM is called.
+#[derive(Debug, Clone, Serialize)]
+pub struct RewriteTokenBudget {
+    pub estimate: token_budget::TokenEstimate,
+    pub cap: Option<usize>,
+    pub dropped: Vec<token_budget::DroppedSection>,
+}
+
+impl RewriteTokenBudget {
+    pub fn label(&self) -> String {
+        let mut out = format!("~{} tokens", self.estimate.total);
+        if let Some(cap) = self.cap {
+            out.push_str(&format!(" / cap {cap}"));
+        }
+        if !self.dropped.is_empty() {
+            let sections: Vec<&str> = self.dropped.iter().map(|d| d.section).collect();
+            out.push_str(&format!(", dropped {}", sections.join(", ")));
+        }
+        out
+    }
+}
+
+// Progress of a chunked rewrite; `merge` marks the final pass over all parts.
+#[derive(Debug, Clone, Copy)]
+pub struct RewriteChunkProgress {
+    pub index: usize, // 1-based
+    pub total: usize,
+    pub merge: bool,
+    pub done: bool,
+    pub elapsed_ms: Option<u128>,
+}
+
+impl RewriteChunkProgress {
+    pub fn label(&self) -> String {
+        if self.merge {
+            format!("merge {} parts", self.total)
+        } else {
+            format!("chunk {}/{}", self.index, self.total)
+        }
+    }
+}
+
+const CHUNK_MERGE_INSTRUCTION: &str = "The TRANSCRIPT is the concatenation of separately rewritten parts of one long recording. Merge them into one coherent result: smooth the seams, drop repetition introduced at part boundaries, and keep all content.";
+
+const CHUNK_PART_INSTRUCTION: &str = "The TRANSCRIPT is one part of a long recording. Rewrite only this part; do not add a greeting, summary or closing it does not contain.";
+
+struct LlmRewrite<'a> {
+    data_dir: &'a std::path::Path,
+    task_id: &'a str,
+    system_prompt: &'a str,
+    ctx: &'a context_pack::PreparedContext,
+    glossary: &'a [String],
+    policy: &'a llm::RewriteContextPolicy,
+}
+
+// `context` is the `TaskManager` in the app; tests pass a scripted collector.
 pub async fn rewrite_text(
-    task_state: &task_manager::TaskManager,
+    context: &dyn ContextCollector<context_capture::ContextConfig>,
     pre_captured_context: Option<context_pack::ContextSnapshot>,
     req: RewriteTextRequest,
+    token: &CancellationToken,
+    on_budget: &(dyn Fn(&RewriteTokenBudget) + Sync),
+    on_chunk: &(dyn Fn(RewriteChunkProgress) + Sync),
 ) -> PortResult<RewriteResult> {
     let data_dir =
         data_dir::data_dir().map_err(|e| PortError::from_message("E_DATA_DIR", e.to_string()))?;
@@ -45,6 +120,20 @@ pub async fn rewrite_text(
             "rewrite is disabled in settings",
         ));
     }
+    if settings::resolve_offline_mode(&s) {
+        obs::event(
+            &data_dir,
+            Some(task_id),
+            "Rewrite",
+            "REWRITE.skipped",
+            "ok",
+            Some(serde_json::json!({ "reason": "offline_mode", "network_free": true })),
+        );
+        return Err(PortError::new(
+            "E_OFFLINE_MODE",
+            "rewrite skipped: network access is disabled by offline_mode",
+        ));
+    }
     let llm_prompt = s
         .llm_prompt
         .as_deref()
@@ -52,84 +141,432 @@ pub async fn rewrite_text(
         .filter(|v| !v.is_empty())
         .map(ToOwned::to_owned)
         .ok_or_else(|| PortError::new("E_SETTINGS_LLM_PROMPT_MISSING", "llm_prompt is required"))?;
-    let ctx_cfg = context_capture::config_from_settings(&s);
+    let output_format = llm::OutputFormat::from_settings(&s, req.template_id.as_deref())
+        .map_err(|e| PortError::from_message("E_SETTINGS_INVALID", e.to_string()))?;
+    let global_ctx_cfg = context_capture::config_from_settings(&s);
+    let ctx_cfg = with_template_sections(
+        &global_ctx_cfg,
+        settings::resolve_template_context_sections(&s, req.template_id.as_deref()),
+    );
+    let postprocess = transcription::TextPostprocess::from_settings(&s, "rewrite");
+    let target_process = pre_captured_context
+        .as_ref()
+        .and_then(|c| c.prev_window.as_ref())
+        .and_then(|w| w.process_image.clone());
     let ctx_snap = rewrite_context(
-        task_state,
+        context,
         &data_dir,
         task_id,
         &ctx_cfg,
+        &global_ctx_cfg,
         pre_captured_context,
     );
-    let prepared = context_pack::prepare(&req.text, &ctx_snap, &ctx_cfg.budget);
+    let include_glossary = s.rewrite_include_glossary.unwrap_or(true);
+    let glossary = sanitize_rewrite_glossary(s.rewrite_glossary.clone());
+    let chunking = settings::resolve_rewrite_chunking(&s)
+        .filter(|c| text_chunks::estimate_tokens(&req.text) > c.threshold_tokens);
+    let token_cap = settings::resolve_rewrite_token_cap(&s);
+    let shape = token_budget::RequestShape {
+        system_tokens: text_chunks::estimate_tokens(&llm_prompt)
+            + settings::resolve_rewrite_system_preamble(&s)
+                .map_or(0, |p| text_chunks::estimate_tokens(&p))
+            + glossary
+                .iter()
+                .filter(|_| include_glossary)
+                .map(|g| text_chunks::estimate_tokens(g))
+                .sum::<usize>(),
+        transcript_limit: chunking.map(|c| c.chunk_tokens),
+    };
+    let budgeted = token_budget::fit_to_cap(
+        &req.text,
+        &ctx_snap,
+        &ctx_cfg.budget,
+        ctx_cfg.injection_guard,
+        shape,
+        token_cap,
+    );
+    let over_cap = budgeted.over_cap(token_cap);
+    let report = RewriteTokenBudget {
+        estimate: budgeted.estimate,
+        cap: token_cap,
+        dropped: budgeted.dropped,
+    };
+    obs::event(
+        &data_dir,
+        Some(task_id),
+        "Rewrite",
+        "REWRITE.token_budget",
+        if over_cap { "err" } else { "ok" },
+        serde_json::to_value(&report).ok(),
+    );
+    on_budget(&report);
+    if over_cap {
+        return Err(PortError::new(
+            "E_REWRITE_TOKEN_BUDGET_EXCEEDED",
+            format!(
+                "rewrite request needs about {} tokens after dropping context, over the cap of {}",
+                report.estimate.total,
+                token_cap.unwrap_or_default()
+            ),
+        ));
+    }
+    let prepared = budgeted.prepared;
+    write_context_artifacts(&data_dir, task_id, &llm_prompt, &ctx_snap, &prepared);
     let policy = llm::RewriteContextPolicy {
         include_history: ctx_cfg.include_history,
         include_clipboard: ctx_cfg.include_clipboard,
         include_prev_window_meta: ctx_cfg.include_prev_window_meta,
         include_prev_window_screenshot: ctx_cfg.include_prev_window_screenshot
             && prepared.screenshot.is_some(),
-        include_glossary: s.rewrite_include_glossary.unwrap_or(true),
+        include_glossary,
+        output_format,
+        endpoint: None,
+        cancel: token.clone(),
     };
-    let glossary = sanitize_rewrite_glossary(s.rewrite_glossary);
     let glossary_ref: &[String] = if policy.include_glossary {
         &glossary
     } else {
         &[]
     };
 
-    let started = Instant::now();
-    let final_text = match llm::rewrite_with_context(
-        &data_dir,
+    let call = LlmRewrite {
+        data_dir: &data_dir,
         task_id,
-        &llm_prompt,
-        &req.text,
-        Some(&prepared),
-        glossary_ref,
-        &policy,
-    )
-    .await
-    {
-        Ok(v) => v,
-        Err(e) => {
-            let err = PortError::from_message("E_LLM_FAILED", e.to_string());
-            return Err(err);
+        system_prompt: &llm_prompt,
+        ctx: &prepared,
+        glossary: glossary_ref,
+        policy: &policy,
+    };
+    let cache_key = settings::resolve_rewrite_cache_enabled(&s)
+        .then(|| rewrite_cache_key(&s, &req, &call, chunking));
+
+    let started = Instant::now();
+    let cached = cache_key
+        .as_deref()
+        .and_then(|key| cache_lookup(&data_dir, task_id, key));
+    let from_cache = cached.is_some();
+    // Held through every chunk and the merge so ASR cannot interleave.
+    let gpu_turn = if from_cache {
+        None
+    } else {
+        gpu_coordinator::acquire(
+            &data_dir,
+            Some(task_id),
+            gpu_coordinator::GpuBackend::Llm,
+            s.llm_base_url.as_deref().unwrap_or_default(),
+            token,
+        )
+        .await
+    };
+    let llm_result = match (cached, chunking) {
+        (Some(text), _) => Ok(text),
+        (None, Some(chunking)) => rewrite_in_chunks(&call, &req.text, chunking, on_chunk).await,
+        (None, None) => {
+            llm::rewrite_with_context(
+                &data_dir,
+                task_id,
+                &llm_prompt,
+                &req.text,
+                Some(&prepared),
+                glossary_ref,
+                &policy,
+            )
+            .await
+        }
+    };
+    drop(gpu_turn);
+    let (final_text, output_error_code) = match llm_result {
+        Ok(v) => (v, None),
+        Err(e) => match e.downcast::<llm::OutputInvalid>() {
+            Ok(invalid) => (invalid.raw_text, Some("E_LLM_OUTPUT_INVALID".to_string())),
+            Err(e) => {
+                let err = PortError::from_message("E_LLM_FAILED", e.to_string());
+                return Err(err);
+            }
+        },
+    };
+    // Only validated replies are cached; a kept raw reply should be retried.
+    if !from_cache && output_error_code.is_none() {
+        if let Some(key) = cache_key.as_deref() {
+            cache_store(&data_dir, task_id, key, &final_text);
         }
+    }
+    let target_process = target_process.or_else(|| {
+        ctx_snap
+            .prev_window
+            .as_ref()
+            .and_then(|w| w.process_image.clone())
+    });
+    // Casing and app profiles would corrupt structured output, so they only
+    // apply to plain text replies.
+    let final_text = if policy.output_format == llm::OutputFormat::Text {
+        let final_text = postprocess
+            .apply(&data_dir, task_id, "Rewrite", final_text, token)
+            .await?;
+        apply_formatting_profile(&data_dir, task_id, target_process.as_deref(), final_text)
+    } else {
+        final_text
     };
     let rewrite_ms = started.elapsed().as_millis();
-    history::update_final_text(
-        &data_dir.join("history.sqlite3"),
-        task_id,
-        &final_text,
-        None,
-    )
-    .map_err(|e| PortError::from_message("E_HISTORY_UPDATE", e.to_string()))?;
+    // Guest dictations have no history row to update.
+    if !guest_mode::enabled() {
+        history::update_final_text(
+            &workspaces::history_db_path(data_dir),
+            task_id,
+            &final_text,
+            req.template_id.as_deref(),
+        )
+        .map_err(|e| PortError::from_message("E_HISTORY_UPDATE", e.to_string()))?;
+    }
     let result = RewriteResult {
         transcript_id: task_id.to_string(),
         final_text,
         rewrite_ms,
+        output_error_code,
+        template_id: req.template_id.clone(),
     };
     Ok(result)
 }
 
-fn rewrite_context(
-    task_state: &task_manager::TaskManager,
+// Map-reduce for transcripts too long for one request: rewrite each chunk, then
+// merge the parts in a final pass.
+async fn rewrite_in_chunks(
+    call: &LlmRewrite<'_>,
+    text: &str,
+    chunking: settings::RewriteChunkingResolved,
+    on_chunk: &(dyn Fn(RewriteChunkProgress) + Sync),
+) -> anyhow::Result<String> {
+    let chunks = text_chunks::split_by_tokens(text, chunking.chunk_tokens);
+    let total = chunks.len();
+    obs::event(
+        call.data_dir,
+        Some(call.task_id),
+        "Rewrite",
+        "REWRITE.chunked",
+        "ok",
+        Some(serde_json::json!({
+            "chunks": total,
+            "estimated_tokens": text_chunks::estimate_tokens(text),
+            "threshold_tokens": chunking.threshold_tokens,
+            "chunk_tokens": chunking.chunk_tokens,
+        })),
+    );
+    // Parts are plain text without the screenshot; only the merge pass shapes
+    // the final reply.
+    let part_policy = llm::RewriteContextPolicy {
+        include_prev_window_screenshot: false,
+        output_format: llm::OutputFormat::Text,
+        ..call.policy.clone()
+    };
+    let part_prompt = format!("{}\n\n{CHUNK_PART_INSTRUCTION}", call.system_prompt);
+    let mut parts = Vec::with_capacity(total);
+    for (i, chunk) in chunks.iter().enumerate() {
+        let progress = RewriteChunkProgress {
+            index: i + 1,
+            total,
+            merge: false,
+            done: false,
+            elapsed_ms: None,
+        };
+        on_chunk(progress);
+        let started = Instant::now();
+        let part = llm::rewrite_with_context(
+            call.data_dir,
+            call.task_id,
+            &part_prompt,
+            chunk,
+            Some(call.ctx),
+            call.glossary,
+            &part_policy,
+        )
+        .await?;
+        on_chunk(RewriteChunkProgress {
+            done: true,
+            elapsed_ms: Some(started.elapsed().as_millis()),
+            ..progress
+        });
+        parts.push(part);
+    }
+    let joined = parts.join("\n\n");
+    // Structured output always needs the merge pass; plain text skips it when
+    // the parts together would overflow the context again.
+    if call.policy.output_format == llm::OutputFormat::Text
+        && text_chunks::estimate_tokens(&joined) > chunking.threshold_tokens
+    {
+        return Ok(joined);
+    }
+    let progress = RewriteChunkProgress {
+        index: total,
+        total,
+        merge: true,
+        done: false,
+        elapsed_ms: None,
+    };
+    on_chunk(progress);
+    let started = Instant::now();
+    let merge_prompt = format!("{}\n\n{CHUNK_MERGE_INSTRUCTION}", call.system_prompt);
+    let merge_policy = llm::RewriteContextPolicy {
+        include_glossary: call.policy.include_glossary,
+        output_format: call.policy.output_format.clone(),
+        cancel: call.policy.cancel.clone(),
+        ..Default::default()
+    };
+    let merged = llm::rewrite_with_context(
+        call.data_dir,
+        call.task_id,
+        &merge_prompt,
+        &joined,
+        None,
+        call.glossary,
+        &merge_policy,
+    )
+    .await?;
+    on_chunk(RewriteChunkProgress {
+        done: true,
+        elapsed_ms: Some(started.elapsed().as_millis()),
+        ..progress
+    });
+    Ok(merged)
+}
+
+// Everything that shapes the LLM request goes into the key, so a hit only
+// happens when the same call would be repeated.
+fn rewrite_cache_key(
+    s: &settings::Settings,
+    req: &RewriteTextRequest,
+    call: &LlmRewrite<'_>,
+    chunking: Option<settings::RewriteChunkingResolved>,
+) -> String {
+    let material = serde_json::json!({
+        "text": req.text,
+        "template_id": req.template_id,
+        "prompt": call.system_prompt,
+        "preamble": settings::resolve_rewrite_system_preamble(s),
+        "llm_base_url": s.llm_base_url,
+        "llm_model": s.llm_model,
+        "llm_reasoning_effort": s.llm_reasoning_effort,
+        "output_format": format!("{:?}", call.policy.output_format),
+        "policy": call.policy,
+        "glossary": call.glossary,
+        "context": call.ctx.user_text,
+        "screenshot": call.ctx.screenshot.as_ref().map(|p| p.sha256_hex.as_str()),
+        "chunking": chunking.map(|c| [c.threshold_tokens, c.chunk_tokens]),
+    });
+    context_pack::sha256_hex(material.to_string().as_bytes())
+}
+
+// Cache failures never block a rewrite; they are traced and treated as a miss.
+fn cache_lookup(data_dir: &std::path::Path, task_id: &str, key: &str) -> Option<String> {
+    match rewrite_cache::lookup(data_dir, key, obs::schema::now_ms()) {
+        Ok((text, stats)) => {
+            if text.is_some() {
+                obs::event(
+                    data_dir,
+                    Some(task_id),
+                    "Rewrite",
+                    "REWRITE.cache_hit",
+                    "ok",
+                    Some(serde_json::json!({ "entries": stats.entries })),
+                );
+            }
+            emit_cache_metric(data_dir, task_id, text.is_some(), stats);
+            text
+        }
+        Err(e) => {
+            cache_error(data_dir, task_id, "REWRITE.cache_lookup", &e);
+            None
+        }
+    }
+}
+
+fn cache_store(data_dir: &std::path::Path, task_id: &str, key: &str, text: &str) {
+    if let Err(e) = rewrite_cache::store(
+        data_dir,
+        key,
+        text,
+        rewrite_cache::DEFAULT_CAPACITY,
+        obs::schema::now_ms(),
+    ) {
+        cache_error(data_dir, task_id, "REWRITE.cache_store", &e);
+    }
+}
+
+fn cache_error(data_dir: &std::path::Path, task_id: &str, step_id: &str, e: &anyhow::Error) {
+    obs::event_err_anyhow(
+        data_dir,
+        obs::ErrorEvent {
+            task_id: Some(task_id),
+            stage: "Rewrite",
+            step_id,
+            kind: "io",
+            code: "E_REWRITE_CACHE",
+            ctx: None,
+        },
+        e,
+    );
+}
+
+fn emit_cache_metric(
+    data_dir: &std::path::Path,
+    task_id: &str,
+    hit: bool,
+    stats: rewrite_cache::CacheStats,
+) {
+    let _ = metrics::emit(
+        data_dir,
+        MetricsRecord::RewriteCache {
+            ts_ms: obs::schema::now_ms(),
+            task_id: task_id.to_string(),
+            hit,
+            entries: stats.entries,
+            hits: stats.hits,
+            misses: stats.misses,
+        },
+    );
+}
+
+// A template's section flags override the global ones; the policy sent with
+// the request is built from the merged config.
+fn with_template_sections(
+    global: &context_capture::ContextConfig,
+    sections: settings::TemplateContextSections,
+) -> context_capture::ContextConfig {
+    let mut cfg = global.clone();
+    cfg.include_history = sections.history.unwrap_or(cfg.include_history);
+    cfg.include_clipboard = sections.clipboard.unwrap_or(cfg.include_clipboard);
+    cfg.include_prev_window_meta = sections
+        .prev_window_meta
+        .unwrap_or(cfg.include_prev_window_meta);
+    cfg.include_prev_window_screenshot = sections
+        .screenshot
+        .unwrap_or(cfg.include_prev_window_screenshot);
+    cfg
+}
+
+// The hotkey-time snapshot was taken with the global flags, before the
+// template was known; window sections only the template turns on are captured
+// now instead.
+pub(crate) fn rewrite_context(
+    collector: &dyn ContextCollector<context_capture::ContextConfig>,
     data_dir: &std::path::Path,
     task_id: &str,
     ctx_cfg: &context_capture::ContextConfig,
+    global_ctx_cfg: &context_capture::ContextConfig,
     pre_captured_context: Option<context_pack::ContextSnapshot>,
 ) -> context_pack::ContextSnapshot {
     let mut capture_cfg = ctx_cfg.clone();
     let pre = pre_captured_context;
     if pre.is_some() {
-        capture_cfg.include_prev_window_screenshot = false;
-        capture_cfg.include_prev_window_meta = false;
+        capture_cfg.include_prev_window_screenshot &=
+            !global_ctx_cfg.include_prev_window_screenshot;
+        capture_cfg.include_prev_window_meta &= !global_ctx_cfg.include_prev_window_meta;
     }
-    let mut snap =
-        task_state.capture_snapshot_best_effort_with_config(data_dir, task_id, &capture_cfg);
+    let mut snap = collector.collect(data_dir, task_id, &capture_cfg);
     if let Some(pre) = pre {
-        if ctx_cfg.include_prev_window_meta {
+        if ctx_cfg.include_prev_window_meta && global_ctx_cfg.include_prev_window_meta {
             snap.prev_window = pre.prev_window;
         }
-        if ctx_cfg.include_prev_window_screenshot {
+        if ctx_cfg.include_prev_window_screenshot && global_ctx_cfg.include_prev_window_screenshot {
             snap.screenshot = pre.screenshot;
         }
     }
@@ -148,7 +585,96 @@ fn rewrite_context(
     snap
 }
 
-fn sanitize_rewrite_glossary(glossary: Option<Vec<String>>) -> Vec<String> {
+// Debug lineage for the task: what context was captured and the exact prompt
+// material built from it. Gated like the LLM request dump since both carry
+// clipboard and history text.
+fn write_context_artifacts(
+    data_dir: &std::path::Path,
+    task_id: &str,
+    system_prompt: &str,
+    snap: &context_pack::ContextSnapshot,
+    prepared: &context_pack::PreparedContext,
+) {
+    if !(debug::verbose_enabled() && debug::include_llm()) {
+        return;
+    }
+    let context = serde_json::json!({
+        "recent_history": snap
+            .recent_history
+            .iter()
+            .map(|h| serde_json::json!({
+                "created_at_ms": h.created_at_ms,
+                "asr_text": h.asr_text,
+                "final_text": h.final_text,
+                "template_id": h.template_id,
+            }))
+            .collect::<Vec<_>>(),
+        "clipboard_text": snap.clipboard_text,
+        "prev_window": snap.prev_window.as_ref().map(|w| serde_json::json!({
+            "title": w.title,
+            "process_image": w.process_image,
+        })),
+        "screenshot": snap.screenshot.as_ref().map(|s| serde_json::json!({
+            "width": s.width,
+            "height": s.height,
+            "sha256": s.sha256_hex,
+        })),
+        "injection_guard": prepared.injection_guard,
+        "stripped_injections": prepared.stripped_injections,
+    });
+    let bytes = serde_json::to_vec_pretty(&context).unwrap_or_default();
+    let _ = debug::write_payload_best_effort(data_dir, task_id, "context.json", bytes);
+    let prompt = format!(
+        "### system\n{system_prompt}\n\n### user\n{}\n",
+        prepared.user_text
+    );
+    let _ = debug::write_payload_best_effort(data_dir, task_id, "prompt.txt", prompt.into_bytes());
+}
+
+fn apply_formatting_profile(
+    data_dir: &std::path::Path,
+    task_id: &str,
+    process_image: Option<&str>,
+    text: String,
+) -> String {
+    let profiles = match formatting_profiles::load_profiles(data_dir) {
+        Ok(v) => v,
+        Err(e) => {
+            obs::event_err_anyhow(
+                data_dir,
+                obs::ErrorEvent {
+                    task_id: Some(task_id),
+                    stage: "Rewrite",
+                    step_id: "REWRITE.formatting_profile",
+                    kind: "config",
+                    code: "E_FORMATTING_PROFILES_LOAD",
+                    ctx: None,
+                },
+                &e,
+            );
+            return text;
+        }
+    };
+    let Some(profile) = formatting_profiles::match_profile(&profiles, process_image) else {
+        return text;
+    };
+    let formatted = formatting_profiles::apply_profile(profile, &text);
+    obs::event(
+        data_dir,
+        Some(task_id),
+        "Rewrite",
+        "REWRITE.formatting_profile",
+        "ok",
+        Some(serde_json::json!({
+            "profile_id": profile.id,
+            "chars_before": text.chars().count(),
+            "chars_after": formatted.chars().count(),
+        })),
+    );
+    formatted
+}
+
+pub(crate) fn sanitize_rewrite_glossary(glossary: Option<Vec<String>>) -> Vec<String> {
     let mut out = Vec::new();
     for item in glossary.unwrap_or_default() {
         let v = item.trim();
@@ -163,15 +689,94 @@ fn sanitize_rewrite_glossary(glossary: Option<Vec<String>>) -> Vec<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn template_sections_override_only_what_they_set() {
+        let global = context_capture::ContextConfig {
+            include_prev_window_screenshot: false,
+            ..Default::default()
+        };
+        let cfg = with_template_sections(
+            &global,
+            settings::TemplateContextSections {
+                history: Some(false),
+                screenshot: Some(true),
+                ..Default::default()
+            },
+        );
+        assert!(!cfg.include_history);
+        assert!(cfg.include_clipboard);
+        assert!(cfg.include_prev_window_meta);
+        assert!(cfg.include_prev_window_screenshot);
+    }
+
+    #[test]
+    fn rewrite_context_filters_collected_sections_and_keeps_hotkey_window() {
+        use crate::context_pack::{ContextSnapshot, PrevWindowInfo};
+        use typevoice_core::test_support::ScriptedContext;
+
+        let window = |title: &str| PrevWindowInfo {
+            title: Some(title.to_string()),
+            process_image: None,
+        };
+        let collector = ScriptedContext::new(ContextSnapshot {
+            clipboard_text: Some("clipboard".to_string()),
+            prev_window: Some(window("now")),
+            ..Default::default()
+        });
+        let global = context_capture::ContextConfig::default();
+        let cfg = context_capture::ContextConfig {
+            include_clipboard: false,
+            ..Default::default()
+        };
+        let pre = ContextSnapshot {
+            prev_window: Some(window("at hotkey")),
+            ..Default::default()
+        };
+
+        let snap = rewrite_context(
+            &collector,
+            std::path::Path::new("data"),
+            "task-1",
+            &cfg,
+            &global,
+            Some(pre),
+        );
+        assert_eq!(snap.clipboard_text, None);
+        assert_eq!(
+            snap.prev_window.and_then(|w| w.title).as_deref(),
+            Some("at hotkey")
+        );
+        assert_eq!(*collector.collected.lock().unwrap(), vec!["task-1"]);
+    }
+
     #[test]
     fn rewrite_result_keeps_transcript_identity() {
         let result = RewriteResult {
             transcript_id: "task-1".to_string(),
             final_text: "rewritten".to_string(),
             rewrite_ms: 15,
+            output_error_code: None,
+            template_id: None,
         };
 
         assert_eq!(result.transcript_id, "task-1");
         assert_eq!(result.final_text, "rewritten");
     }
+
+    #[test]
+    fn chunk_progress_labels_parts_and_merge() {
+        let progress = RewriteChunkProgress {
+            index: 2,
+            total: 3,
+            merge: false,
+            done: false,
+            elapsed_ms: None,
+        };
+        assert_eq!(progress.label(), "chunk 2/3");
+        let merge = RewriteChunkProgress {
+            merge: true,
+            ..progress
+        };
+        assert_eq!(merge.label(), "merge 3 parts");
+    }
 }
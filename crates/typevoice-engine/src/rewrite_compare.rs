@@ -0,0 +1,318 @@
+use std::path::Path;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::obs::{debug, schema::now_ms};
+use crate::ports::{PortError, PortResult};
+use crate::rewrite::sanitize_rewrite_glossary;
+use crate::{data_dir, history, llm, obs, settings, text_chunks, workspaces};
+
+const MIN_VARIANTS: usize = 2;
+const MAX_VARIANTS: usize = 4;
+
+// One rewrite configuration to try. Unset fields fall back to settings; the
+// template only selects the output format, the prompt is always `llm_prompt`.
+// Every endpoint is called with the configured API key.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RewriteVariant {
+    #[serde(default)]
+    pub template_id: Option<String>,
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub reasoning_effort: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RewriteCompareRun {
+    pub template_id: Option<String>,
+    pub model: Option<String>,
+    pub text: Option<String>,
+    pub rewrite_ms: Option<u128>,
+    pub prompt_tokens: Option<u64>,
+    pub completion_tokens: Option<u64>,
+    // Set when the endpoint sent no usage and the counts are estimates.
+    pub tokens_estimated: bool,
+    pub output_error_code: Option<String>,
+    pub error_code: Option<String>,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RewriteCompareResult {
+    pub task_id: String,
+    pub asr_text: String,
+    pub runs: Vec<RewriteCompareRun>,
+    // Name of the task artifact the comparison was saved as.
+    pub artifact: Option<String>,
+}
+
+fn non_empty(v: Option<&str>) -> Option<&str> {
+    v.map(str::trim).filter(|v| !v.is_empty())
+}
+
+// The endpoint for a variant, or None to use the configured one unchanged.
+fn variant_endpoint(
+    v: &RewriteVariant,
+    base: Option<&llm::LlmConfig>,
+) -> anyhow::Result<Option<llm::LlmConfig>> {
+    let base_url = non_empty(v.base_url.as_deref());
+    let model = non_empty(v.model.as_deref());
+    let effort = non_empty(v.reasoning_effort.as_deref());
+    if base_url.is_none() && model.is_none() && effort.is_none() {
+        return Ok(None);
+    }
+    let mut cfg = llm::config_from_values(
+        base_url
+            .or(base.map(|b| b.base_url.as_str()))
+            .unwrap_or_default(),
+        model.or(base.map(|b| b.model.as_str())).unwrap_or_default(),
+        effort.or(base.and_then(|b| b.reasoning_effort.as_deref())),
+    )?;
+    cfg.system_preamble = base.and_then(|b| b.system_preamble.clone());
+    Ok(Some(cfg))
+}
+
+async fn run_variant(
+    data_dir: &Path,
+    task_id: &str,
+    s: &settings::Settings,
+    system_prompt: &str,
+    asr_text: &str,
+    glossary: &[String],
+    variant: &RewriteVariant,
+) -> RewriteCompareRun {
+    let template_id = non_empty(variant.template_id.as_deref()).map(ToOwned::to_owned);
+    let base = llm::load_config(data_dir).ok();
+    let mut run = RewriteCompareRun {
+        template_id: template_id.clone(),
+        model: non_empty(variant.model.as_deref())
+            .map(ToOwned::to_owned)
+            .or_else(|| base.as_ref().map(|b| b.model.clone())),
+        text: None,
+        rewrite_ms: None,
+        prompt_tokens: None,
+        completion_tokens: None,
+        tokens_estimated: false,
+        output_error_code: None,
+        error_code: None,
+        error_message: None,
+    };
+    let prepared = llm::OutputFormat::from_settings(s, template_id.as_deref())
+        .and_then(|format| Ok((format, variant_endpoint(variant, base.as_ref())?)));
+    let (output_format, endpoint) = match prepared {
+        Ok(v) => v,
+        Err(e) => {
+            let err = PortError::from_message("E_SETTINGS_INVALID", e.to_string());
+            run.error_code = Some(err.code);
+            run.error_message = Some(err.message);
+            return run;
+        }
+    };
+    let include_glossary = s.rewrite_include_glossary.unwrap_or(true);
+    let policy = llm::RewriteContextPolicy {
+        include_glossary,
+        output_format,
+        endpoint,
+        ..Default::default()
+    };
+    let glossary: &[String] = if include_glossary { glossary } else { &[] };
+
+    let started = Instant::now();
+    let result = llm::rewrite_with_usage(
+        data_dir,
+        task_id,
+        system_prompt,
+        asr_text,
+        None,
+        glossary,
+        &policy,
+    )
+    .await;
+    let rewrite_ms = started.elapsed().as_millis();
+    let (text, usage) = match result {
+        Ok(v) => v,
+        Err(e) => match e.downcast::<llm::OutputInvalid>() {
+            Ok(invalid) => {
+                run.output_error_code = Some("E_LLM_OUTPUT_INVALID".to_string());
+                (invalid.raw_text, None)
+            }
+            Err(e) => {
+                let err = PortError::from_message("E_LLM_FAILED", e.to_string());
+                run.error_code = Some(err.code);
+                run.error_message = Some(err.message);
+                return run;
+            }
+        },
+    };
+    let usage = usage.unwrap_or_else(|| {
+        run.tokens_estimated = true;
+        llm::TokenUsage {
+            prompt_tokens: (text_chunks::estimate_tokens(system_prompt)
+                + text_chunks::estimate_tokens(asr_text)) as u64,
+            completion_tokens: text_chunks::estimate_tokens(&text) as u64,
+        }
+    });
+    run.prompt_tokens = Some(usage.prompt_tokens);
+    run.completion_tokens = Some(usage.completion_tokens);
+    run.rewrite_ms = Some(rewrite_ms);
+    run.text = Some(text);
+    run
+}
+
+fn check_variants(variants: &[RewriteVariant]) -> PortResult<()> {
+    if (MIN_VARIANTS..=MAX_VARIANTS).contains(&variants.len()) {
+        return Ok(());
+    }
+    Err(PortError::new(
+        "E_REWRITE_COMPARE_VARIANTS_INVALID",
+        format!(
+            "compare needs {MIN_VARIANTS} to {MAX_VARIANTS} variants, got {}",
+            variants.len()
+        ),
+    ))
+}
+
+// Settings, rewrite prompt and the stored transcript for a comparison.
+fn prepare(
+    data_dir: &Path,
+    task_id: &str,
+    variants: &[RewriteVariant],
+) -> PortResult<(settings::Settings, String, String)> {
+    check_variants(variants)?;
+    let s = settings::load_settings_strict(data_dir)
+        .map_err(|e| PortError::from_message("E_SETTINGS_INVALID", e.to_string()))?;
+    if settings::resolve_offline_mode(&s) {
+        return Err(PortError::new(
+            "E_OFFLINE_MODE",
+            "rewrite compare skipped: network access is disabled by offline_mode",
+        ));
+    }
+    let prompt = non_empty(s.llm_prompt.as_deref())
+        .map(ToOwned::to_owned)
+        .ok_or_else(|| PortError::new("E_SETTINGS_LLM_PROMPT_MISSING", "llm_prompt is required"))?;
+    let item = history::get(&workspaces::history_db_path(data_dir), task_id)
+        .map_err(|e| PortError::from_message("E_HISTORY_READ", e.to_string()))?
+        .ok_or_else(|| {
+            PortError::new("E_HISTORY_NOT_FOUND", format!("no history for {task_id}"))
+        })?;
+    if item.asr_text.trim().is_empty() {
+        return Err(PortError::new(
+            "E_REWRITE_EMPTY_TEXT",
+            "the task has no transcript",
+        ));
+    }
+    Ok((s, prompt, item.asr_text))
+}
+
+// Rewrites a task's stored transcript with each variant in parallel and saves
+// the side-by-side result as a task artifact. History, the rewrite cache and
+// the task's final text are left alone; context capture is skipped so every
+// variant sees the same input. A failed variant is reported in its run.
+pub async fn compare_rewrites(
+    task_id: &str,
+    variants: Vec<RewriteVariant>,
+) -> PortResult<RewriteCompareResult> {
+    let data_dir =
+        data_dir::data_dir().map_err(|e| PortError::from_message("E_DATA_DIR", e.to_string()))?;
+    let task_id = task_id.trim().to_string();
+    let span = obs::Span::start(
+        &data_dir,
+        Some(&task_id),
+        "Cmd",
+        "CMD.compare_rewrites",
+        Some(serde_json::json!({"variants": variants.len()})),
+    );
+    let (s, prompt, asr_text) = match prepare(&data_dir, &task_id, &variants) {
+        Ok(v) => v,
+        Err(e) => {
+            span.err("logic", &e.code, &e.message, None);
+            return Err(e);
+        }
+    };
+
+    let glossary = sanitize_rewrite_glossary(s.rewrite_glossary.clone());
+    let runs = futures_util::future::join_all(
+        variants
+            .iter()
+            .map(|v| run_variant(&data_dir, &task_id, &s, &prompt, &asr_text, &glossary, v)),
+    )
+    .await;
+    let mut result = RewriteCompareResult {
+        task_id,
+        asr_text,
+        runs,
+        artifact: None,
+    };
+    let name = format!("rewrite_compare_{}.json", now_ms());
+    let bytes = serde_json::to_vec_pretty(&result).unwrap_or_default();
+    match debug::write_task_artifact(&data_dir, &result.task_id, &name, &bytes) {
+        Ok(_) => result.artifact = Some(name),
+        Err(e) => obs::event(
+            &data_dir,
+            Some(&result.task_id),
+            "Cmd",
+            "CMD.compare_rewrites.artifact",
+            "err",
+            Some(serde_json::json!({"error": e.to_string()})),
+        ),
+    }
+    span.ok(Some(serde_json::json!({
+        "failed": result.runs.iter().filter(|r| r.error_code.is_some()).count(),
+        "rewrite_ms": result.runs.iter().map(|r| r.rewrite_ms).collect::<Vec<_>>(),
+        "artifact": result.artifact,
+    })));
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> llm::LlmConfig {
+        llm::LlmConfig {
+            base_url: "https://api.example.com/v1".to_string(),
+            model: "small".to_string(),
+            reasoning_effort: Some("low".to_string()),
+            system_preamble: Some("preamble".to_string()),
+        }
+    }
+
+    #[test]
+    fn variant_endpoint_overrides_only_the_given_fields() {
+        let template_only = RewriteVariant {
+            template_id: Some("email".to_string()),
+            ..Default::default()
+        };
+        assert!(variant_endpoint(&template_only, Some(&base()))
+            .expect("ok")
+            .is_none());
+
+        let model_only = RewriteVariant {
+            model: Some(" large ".to_string()),
+            ..Default::default()
+        };
+        let cfg = variant_endpoint(&model_only, Some(&base()))
+            .expect("ok")
+            .expect("endpoint");
+        assert_eq!(cfg.base_url, "https://api.example.com/v1");
+        assert_eq!(cfg.model, "large");
+        assert_eq!(cfg.reasoning_effort.as_deref(), Some("low"));
+        assert_eq!(cfg.system_preamble.as_deref(), Some("preamble"));
+
+        // Without a configured endpoint the variant must name one.
+        assert!(variant_endpoint(&model_only, None).is_err());
+    }
+
+    #[test]
+    fn variant_count_is_bounded() {
+        let variants = |n: usize| vec![RewriteVariant::default(); n];
+        assert!(check_variants(&variants(1)).is_err());
+        assert!(check_variants(&variants(2)).is_ok());
+        assert!(check_variants(&variants(5)).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use serde::Serialize;
+
+use crate::ports::{PortError, PortResult};
+use crate::settings::{self, DailySummaryResolved};
+use crate::{data_dir, guest_mode, history, llm, obs, workspaces};
+
+// Jobs fire on the first tick at or after their time, so this bounds the delay.
+const TICK: Duration = Duration::from_secs(60);
+const MAX_DAY_ITEMS: i64 = 500;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DailySummaryResult {
+    pub date: String,
+    pub dictations: usize,
+    // None when there was nothing dictated that day.
+    pub path: Option<String>,
+}
+
+pub fn summary_path(data_dir: &Path, date: NaiveDate) -> PathBuf {
+    data_dir
+        .join("summaries")
+        .join(format!("{}.md", date.format("%Y-%m-%d")))
+}
+
+// Today's date once the configured time has passed. Days the app was not
+// running are not caught up.
+fn due_date(now: DateTime<Local>, cfg: &DailySummaryResolved) -> Option<NaiveDate> {
+    let scheduled = now.date_naive().and_hms_opt(cfg.hour, cfg.minute, 0)?;
+    (now.naive_local() >= scheduled).then(|| now.date_naive())
+}
+
+fn local_midnight_ms(date: NaiveDate) -> Option<i64> {
+    Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+        .earliest()
+        .map(|t| t.timestamp_millis())
+}
+
+// The day's dictations, oldest first.
+fn day_items(data_dir: &Path, date: NaiveDate) -> PortResult<Vec<history::HistoryItem>> {
+    let bounds = local_midnight_ms(date).zip(date.succ_opt().and_then(local_midnight_ms));
+    let Some((start_ms, end_ms)) = bounds else {
+        return Ok(Vec::new());
+    };
+    let mut items = history::list(
+        &workspaces::history_db_path(data_dir),
+        MAX_DAY_ITEMS,
+        Some(end_ms),
+    )
+    .map_err(|e| PortError::from_message("E_HISTORY_READ", e.to_string()))?;
+    items.retain(|h| h.created_at_ms >= start_ms && !item_text(h).is_empty());
+    items.reverse();
+    Ok(items)
+}
+
+fn item_text(h: &history::HistoryItem) -> &str {
+    let text = h.final_text.trim();
+    if text.is_empty() {
+        h.asr_text.trim()
+    } else {
+        text
+    }
+}
+
+fn digest_input(items: &[history::HistoryItem]) -> String {
+    items
+        .iter()
+        .map(|h| {
+            let time = Local
+                .timestamp_millis_opt(h.created_at_ms)
+                .single()
+                .map(|t| t.format("%H:%M").to_string())
+                .unwrap_or_default();
+            format!("[{time}] {}", item_text(h))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn render_summary(date: NaiveDate, dictations: usize, digest: &str) -> String {
+    let date = date.format("%Y-%m-%d");
+    format!(
+        "---\ndate: {date}\nsource: typevoice\ndictations: {dictations}\ntags:\n  - daily-summary\n---\n\n# {date}\n\n{}\n",
+        digest.trim()
+    )
+}
+
+// Digests the dictations of `date` with the LLM and writes
+// `summaries/YYYY-MM-DD.md`, replacing an earlier digest of that day.
+pub async fn write_daily_summary(
+    data_dir: &Path,
+    s: &settings::Settings,
+    date: NaiveDate,
+    prompt: &str,
+) -> PortResult<DailySummaryResult> {
+    let task_id = format!("daily-summary-{}", date.format("%Y-%m-%d"));
+    let span = obs::Span::start(
+        data_dir,
+        Some(&task_id),
+        "Scheduler",
+        "SCHEDULER.daily_summary",
+        None,
+    );
+    let result = async {
+        guest_mode::check_writable()
+            .map_err(|e| PortError::from_message("E_GUEST_MODE_READ_ONLY", e.to_string()))?;
+        if settings::resolve_offline_mode(s) {
+            return Err(PortError::new(
+                "E_OFFLINE_MODE",
+                "daily summary skipped: network access is disabled by offline_mode",
+            ));
+        }
+        let items = day_items(data_dir, date)?;
+        let mut out = DailySummaryResult {
+            date: date.format("%Y-%m-%d").to_string(),
+            dictations: items.len(),
+            path: None,
+        };
+        if items.is_empty() {
+            return Ok(out);
+        }
+        let digest = llm::rewrite(data_dir, &task_id, prompt, &digest_input(&items))
+            .await
+            .map_err(|e| PortError::from_message("E_LLM_FAILED", e.to_string()))?;
+        let path = summary_path(data_dir, date);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| {
+                PortError::new(
+                    "E_DAILY_SUMMARY_WRITE",
+                    format!("create {}: {e}", dir.display()),
+                )
+            })?;
+        }
+        std::fs::write(&path, render_summary(date, items.len(), &digest)).map_err(|e| {
+            PortError::new(
+                "E_DAILY_SUMMARY_WRITE",
+                format!("write {}: {e}", path.display()),
+            )
+        })?;
+        out.path = Some(path.display().to_string());
+        Ok(out)
+    }
+    .await;
+    match &result {
+        Ok(r) => span.ok(Some(serde_json::json!({
+            "dictations": r.dictations,
+            "written": r.path.is_some(),
+        }))),
+        Err(e) => span.err("logic", &e.code, &e.message, None),
+    }
+    result
+}
+
+// Background loop started once at app setup. Settings are re-read every tick,
+// so enabling the digest or moving its time needs no restart. Each day is
+// attempted once per run; `daily_summary_run` retries by hand.
+pub async fn run() {
+    let mut attempted: Option<NaiveDate> = None;
+    loop {
+        tokio::time::sleep(TICK).await;
+        let Ok(dir) = data_dir::data_dir() else {
+            continue;
+        };
+        let Ok(s) = settings::load_settings_strict(&dir) else {
+            continue;
+        };
+        let Some(cfg) = settings::resolve_daily_summary(&s) else {
+            continue;
+        };
+        let Some(date) = due_date(Local::now(), &cfg) else {
+            continue;
+        };
+        if attempted == Some(date) || summary_path(&dir, date).exists() {
+            continue;
+        }
+        attempted = Some(date);
+        let _ = write_daily_summary(&dir, &s, date, &cfg.prompt).await;
+    }
+}
+
+// Manual trigger; `date` is "YYYY-MM-DD" and defaults to today. Works while the
+// scheduled digest is off.
+pub async fn run_daily_summary_now(date: Option<&str>) -> PortResult<DailySummaryResult> {
+    let dir =
+        data_dir::data_dir().map_err(|e| PortError::from_message("E_DATA_DIR", e.to_string()))?;
+    let date = match date.map(str::trim).filter(|v| !v.is_empty()) {
+        Some(v) => NaiveDate::parse_from_str(v, "%Y-%m-%d")
+            .map_err(|e| PortError::new("E_DAILY_SUMMARY_DATE_INVALID", format!("{v}: {e}")))?,
+        None => Local::now().date_naive(),
+    };
+    let s = settings::load_settings_strict(&dir)
+        .map_err(|e| PortError::from_message("E_SETTINGS_INVALID", e.to_string()))?;
+    let prompt = settings::resolve_daily_summary_prompt(&s);
+    write_daily_summary(&dir, &s, date, &prompt).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(created_at_ms: i64, asr_text: &str, final_text: &str) -> history::HistoryItem {
+        history::HistoryItem {
+            task_id: format!("t{created_at_ms}"),
+            created_at_ms,
+            asr_text: asr_text.to_string(),
+            rewritten_text: String::new(),
+            inserted_text: String::new(),
+            final_text: final_text.to_string(),
+            template_id: None,
+            rtf: 0.0,
+            device_used: String::new(),
+            preprocess_ms: 0,
+            asr_ms: 0,
+            target_app: None,
+            target_title_hash: None,
+            tags: Vec::new(),
+            note: String::new(),
+        }
+    }
+
+    #[test]
+    fn summary_is_due_from_the_configured_time_on() {
+        let cfg = DailySummaryResolved {
+            hour: 18,
+            minute: 0,
+            prompt: String::new(),
+        };
+        let at = |h: u32, m: u32| {
+            Local
+                .with_ymd_and_hms(2026, 3, 4, h, m, 0)
+                .single()
+                .unwrap()
+        };
+        assert_eq!(due_date(at(17, 59), &cfg), None);
+        assert_eq!(
+            due_date(at(18, 0), &cfg),
+            NaiveDate::from_ymd_opt(2026, 3, 4)
+        );
+        assert_eq!(
+            due_date(at(23, 30), &cfg),
+            NaiveDate::from_ymd_opt(2026, 3, 4)
+        );
+    }
+
+    #[test]
+    fn digest_input_prefers_final_text_and_keeps_order() {
+        let base = local_midnight_ms(NaiveDate::from_ymd_opt(2026, 3, 4).unwrap()).unwrap();
+        let items = vec![
+            item(base + 9 * 3_600_000, "ship it", "Ship it."),
+            item(base + 10 * 3_600_000 + 5 * 60_000, "call bob", ""),
+        ];
+        assert_eq!(digest_input(&items), "[09:00] Ship it.\n[10:05] call bob");
+
+        let md = render_summary(NaiveDate::from_ymd_opt(2026, 3, 4).unwrap(), 2, "\n- one\n");
+        assert!(md.starts_with("---\ndate: 2026-03-04\n"));
+        assert!(md.ends_with("# 2026-03-04\n\n- one\n"));
+    }
+}
@@ -0,0 +1,46 @@
+use schemars::{JsonSchema, SchemaGenerator};
+use serde_json::{Map, Value};
+
+use crate::insertion::{InsertResult, InsertTextRequest};
+use crate::rewrite::{RewriteResult, RewriteTextRequest};
+use crate::settings::{Settings, SettingsPatch};
+use crate::ui_events::UiEvent;
+use crate::voice_workflow::{WorkflowCommandRequest, WorkflowView};
+
+// JSON Schema (draft 2020-12) of every payload crossing the IPC boundary, keyed
+// by Rust type name, so the frontend and external integrators can validate
+// against the same definitions the backend serializes with.
+pub fn schema_dump() -> Value {
+    let mut out = Map::new();
+    add::<Settings>(&mut out, "Settings");
+    add::<SettingsPatch>(&mut out, "SettingsPatch");
+    add::<UiEvent>(&mut out, "UiEvent");
+    add::<WorkflowView>(&mut out, "WorkflowView");
+    add::<WorkflowCommandRequest>(&mut out, "WorkflowCommandRequest");
+    add::<RewriteTextRequest>(&mut out, "RewriteTextRequest");
+    add::<RewriteResult>(&mut out, "RewriteResult");
+    add::<InsertTextRequest>(&mut out, "InsertTextRequest");
+    add::<InsertResult>(&mut out, "InsertResult");
+    Value::Object(out)
+}
+
+fn add<T: JsonSchema>(out: &mut Map<String, Value>, name: &str) {
+    let schema = SchemaGenerator::default().into_root_schema_for::<T>();
+    out.insert(name.to_string(), schema.to_value());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::schema_dump;
+
+    #[test]
+    fn dump_uses_the_serialized_field_names() {
+        let dump = schema_dump();
+        let view = &dump["WorkflowView"]["properties"];
+        assert!(view.get("primaryDisabled").is_some());
+        assert!(view.get("primary_disabled").is_none());
+        let command = &dump["WorkflowCommandRequest"]["properties"]["command"];
+        assert!(command.is_object());
+        assert!(dump["Settings"]["properties"].get("asr_provider").is_some());
+    }
+}
@@ -1,20 +1,134 @@
+use std::collections::{HashMap, VecDeque};
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 
-use crate::{context_capture, context_pack};
+use crate::ports::ContextCollector;
+use crate::{context_capture, context_pack, obs};
 use anyhow::{anyhow, Result};
 
+// Client-supplied keys are remembered just long enough to absorb hotkey
+// double-fires and UI retries.
+const IDEMPOTENCY_KEY_TTL_MS: i64 = 60_000;
+const IDEMPOTENCY_KEY_CAPACITY: usize = 128;
+
 #[derive(Clone)]
 pub struct TaskManager {
     ctx: context_capture::ContextService,
+    idempotency_keys: Arc<Mutex<IdempotencyKeys>>,
+    heartbeats: TaskHeartbeats,
+}
+
+// Last progress time per task. Every UI event that names a task counts as a
+// beat, so a runner that dies without a terminal event simply stops beating
+// and the stall watchdog can tell it apart from a slow one.
+#[derive(Debug, Clone, Default)]
+pub struct TaskHeartbeats {
+    last_ms: Arc<Mutex<HashMap<String, i64>>>,
+}
+
+impl TaskHeartbeats {
+    pub fn beat(&self, task_id: &str) {
+        self.beat_at(task_id, obs::schema::now_ms());
+    }
+
+    fn beat_at(&self, task_id: &str, now_ms: i64) {
+        self.last_ms
+            .lock()
+            .unwrap()
+            .insert(task_id.to_string(), now_ms);
+    }
+
+    // Time since the task last made progress. A task seen for the first time
+    // starts its clock now.
+    pub fn idle_ms(&self, task_id: &str, now_ms: i64) -> i64 {
+        let mut last = self.last_ms.lock().unwrap();
+        let at = *last.entry(task_id.to_string()).or_insert(now_ms);
+        now_ms.saturating_sub(at)
+    }
+
+    // Forgets every task not in `live`, so finished tasks do not accumulate.
+    pub fn retain(&self, live: &[&str]) {
+        self.last_ms
+            .lock()
+            .unwrap()
+            .retain(|id, _| live.contains(&id.as_str()));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdempotencyClaim {
+    // The caller owns the key and must finish it once the command settles.
+    Claimed,
+    // Another caller holds the key and has not settled yet.
+    InFlight,
+    Done(String),
+}
+
+#[derive(Debug, Default)]
+struct IdempotencyKeys {
+    // (key, task_id, claimed_at_ms), oldest first. `None` marks an in-flight claim.
+    entries: VecDeque<(String, Option<String>, i64)>,
+}
+
+impl IdempotencyKeys {
+    // Looks up and reserves in one step so two concurrent callers cannot both miss.
+    fn claim(&mut self, key: &str, now_ms: i64) -> IdempotencyClaim {
+        self.entries
+            .retain(|(_, _, at)| now_ms.saturating_sub(*at) <= IDEMPOTENCY_KEY_TTL_MS);
+        if let Some((_, task_id, _)) = self.entries.iter().find(|(k, _, _)| k == key) {
+            return match task_id {
+                Some(task_id) => IdempotencyClaim::Done(task_id.clone()),
+                None => IdempotencyClaim::InFlight,
+            };
+        }
+        while self.entries.len() >= IDEMPOTENCY_KEY_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((key.to_string(), None, now_ms));
+        IdempotencyClaim::Claimed
+    }
+
+    // Records the settled task, or releases the key when the command produced none.
+    fn finish(&mut self, key: &str, task_id: Option<&str>, now_ms: i64) {
+        self.entries.retain(|(k, _, _)| k != key);
+        let Some(task_id) = task_id else {
+            return;
+        };
+        while self.entries.len() >= IDEMPOTENCY_KEY_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries
+            .push_back((key.to_string(), Some(task_id.to_string()), now_ms));
+    }
 }
 
 impl TaskManager {
     pub fn new() -> Self {
         Self {
             ctx: context_capture::ContextService::new(),
+            idempotency_keys: Arc::new(Mutex::new(IdempotencyKeys::default())),
+            heartbeats: TaskHeartbeats::default(),
         }
     }
 
+    pub fn heartbeats(&self) -> TaskHeartbeats {
+        self.heartbeats.clone()
+    }
+
+    pub fn claim_idempotency_key(&self, key: &str) -> IdempotencyClaim {
+        self.idempotency_keys
+            .lock()
+            .unwrap()
+            .claim(key, obs::schema::now_ms())
+    }
+
+    pub fn finish_idempotency_key(&self, key: &str, task_id: Option<&str>) {
+        self.idempotency_keys
+            .lock()
+            .unwrap()
+            .finish(key, task_id, obs::schema::now_ms());
+    }
+
     pub fn warmup_context_best_effort(&self) {
         self.ctx.warmup_best_effort();
     }
@@ -45,8 +159,85 @@ impl TaskManager {
     }
 }
 
+impl ContextCollector<context_capture::ContextConfig> for TaskManager {
+    fn collect(
+        &self,
+        data_dir: &Path,
+        task_id: &str,
+        cfg: &context_capture::ContextConfig,
+    ) -> context_pack::ContextSnapshot {
+        self.capture_snapshot_best_effort_with_config(data_dir, task_id, cfg)
+    }
+}
+
 impl Default for TaskManager {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        IdempotencyClaim, IdempotencyKeys, TaskHeartbeats, IDEMPOTENCY_KEY_CAPACITY,
+        IDEMPOTENCY_KEY_TTL_MS,
+    };
+
+    #[test]
+    fn idempotency_keys_expire_and_stay_bounded() {
+        let mut keys = IdempotencyKeys::default();
+        assert_eq!(keys.claim("hotkey-1", 1_000), IdempotencyClaim::Claimed);
+        keys.finish("hotkey-1", Some("task-1"), 1_000);
+        assert_eq!(
+            keys.claim("hotkey-1", 2_000),
+            IdempotencyClaim::Done("task-1".to_string())
+        );
+        assert_eq!(keys.claim("hotkey-2", 2_000), IdempotencyClaim::Claimed);
+        assert_eq!(
+            keys.claim("hotkey-1", 1_000 + IDEMPOTENCY_KEY_TTL_MS + 1),
+            IdempotencyClaim::Claimed
+        );
+
+        for i in 0..IDEMPOTENCY_KEY_CAPACITY + 5 {
+            keys.claim(&format!("k{i}"), 5_000);
+            keys.finish(&format!("k{i}"), Some(&format!("t{i}")), 5_000);
+        }
+        assert_eq!(keys.entries.len(), IDEMPOTENCY_KEY_CAPACITY);
+        assert_eq!(keys.claim("k0", 5_000), IdempotencyClaim::Claimed);
+        assert_eq!(
+            keys.claim(&format!("k{}", IDEMPOTENCY_KEY_CAPACITY + 4), 5_000),
+            IdempotencyClaim::Done(format!("t{}", IDEMPOTENCY_KEY_CAPACITY + 4))
+        );
+    }
+
+    #[test]
+    fn concurrent_claims_on_one_key_run_only_once() {
+        let mut keys = IdempotencyKeys::default();
+        assert_eq!(keys.claim("hotkey-1", 1_000), IdempotencyClaim::Claimed);
+        assert_eq!(keys.claim("hotkey-1", 1_001), IdempotencyClaim::InFlight);
+        keys.finish("hotkey-1", Some("task-1"), 1_002);
+        assert_eq!(
+            keys.claim("hotkey-1", 1_003),
+            IdempotencyClaim::Done("task-1".to_string())
+        );
+
+        // A failed command releases its key so a retry can run.
+        assert_eq!(keys.claim("hotkey-2", 1_000), IdempotencyClaim::Claimed);
+        keys.finish("hotkey-2", None, 1_001);
+        assert_eq!(keys.claim("hotkey-2", 1_002), IdempotencyClaim::Claimed);
+    }
+
+    #[test]
+    fn heartbeats_measure_idle_time_and_forget_finished_tasks() {
+        let hb = TaskHeartbeats::default();
+        assert_eq!(hb.idle_ms("t1", 1_000), 0);
+        assert_eq!(hb.idle_ms("t1", 4_000), 3_000);
+        hb.beat_at("t1", 5_000);
+        assert_eq!(hb.idle_ms("t1", 5_500), 500);
+
+        hb.beat_at("t2", 5_000);
+        hb.retain(&["t2"]);
+        assert_eq!(hb.idle_ms("t1", 9_000), 0);
+        assert_eq!(hb.idle_ms("t2", 9_000), 4_000);
+    }
+}
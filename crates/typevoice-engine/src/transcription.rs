@@ -10,9 +10,13 @@ use std::{
 use serde::{Deserialize, Serialize};
 use tokio_util::sync::CancellationToken;
 
-use crate::obs::{metrics, schema::MetricsRecord};
-use crate::ports::{PortError, PortResult};
-use crate::{data_dir, pipeline, remote_asr, settings};
+use crate::gpu_coordinator::{self, GpuBackend};
+use crate::obs::{self, metrics, schema::MetricsRecord};
+use crate::ports::{AsrClient, AsrTranscript, PortError, PortFuture, PortResult};
+use crate::stage_graph::{Stage, StageClock, StageEvent, StageFuture, StageGraph, SystemClock};
+use crate::text_casing::{self, CasingStyle};
+use crate::text_normalize::{self, NormalizeLocale};
+use crate::{data_dir, pipeline, postprocess_plugins, power, remote_asr, settings};
 
 #[cfg(windows)]
 use crate::subprocess::CommandNoConsoleExt;
@@ -51,7 +55,7 @@ impl ProviderKind {
         }
     }
 
-    fn as_str(self) -> &'static str {
+    pub(crate) fn as_str(self) -> &'static str {
         match self {
             Self::Remote => "remote",
             Self::Doubao => "doubao",
@@ -96,6 +100,152 @@ impl TranscriptionResult {
     }
 }
 
+// Post-processing shared by the dictation and rewrite paths: spoken-number
+// normalization first, then casing, then user post-processor plugins. Unknown
+// settings values and failing plugins are reported and skipped rather than
+// failing the task.
+#[derive(Debug, Clone)]
+pub struct TextPostprocess {
+    pub mode: String,
+    pub casing: String,
+    pub normalize_locales: Vec<String>,
+    pub plugins: Vec<settings::PostprocessorResolved>,
+}
+
+impl TextPostprocess {
+    pub fn from_settings(s: &settings::Settings, mode: &str) -> Self {
+        Self {
+            mode: mode.to_string(),
+            casing: settings::resolve_text_casing(s, mode, None),
+            normalize_locales: settings::resolve_text_normalize_locales(s),
+            plugins: settings::resolve_postprocessors(s),
+        }
+    }
+
+    // Plugins may block up to their timeouts, so the chain runs on the
+    // blocking pool and races `cancel`.
+    pub async fn apply(
+        &self,
+        data_dir: &Path,
+        task_id: &str,
+        stage: &str,
+        text: String,
+        cancel: &CancellationToken,
+    ) -> PortResult<String> {
+        let text = self.apply_normalize(data_dir, task_id, stage, text);
+        let text = self.apply_casing(data_dir, task_id, stage, text);
+        if self.plugins.is_empty() {
+            return Ok(text);
+        }
+        let chain = {
+            let (data_dir, task_id, stage) = (
+                data_dir.to_path_buf(),
+                task_id.to_string(),
+                stage.to_string(),
+            );
+            let (mode, plugins, token) = (self.mode.clone(), self.plugins.clone(), cancel.clone());
+            tokio::task::spawn_blocking(move || {
+                postprocess_plugins::run_chain(
+                    &data_dir, &task_id, &mode, &stage, &plugins, text, &token,
+                )
+            })
+        };
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                Err(PortError::new("E_CANCELLED", "post-processing was cancelled"))
+            }
+            joined = chain => {
+                joined.map_err(|e| PortError::new("E_POSTPROCESS_JOIN", e.to_string()))
+            }
+        }
+    }
+
+    fn apply_normalize(&self, data_dir: &Path, task_id: &str, stage: &str, text: String) -> String {
+        if self.normalize_locales.is_empty() {
+            return text;
+        }
+        let mut locales = Vec::new();
+        for raw in &self.normalize_locales {
+            match NormalizeLocale::parse(raw) {
+                Some(locale) => locales.push(locale),
+                None => report_postprocess_invalid(
+                    data_dir,
+                    task_id,
+                    stage,
+                    "TEXT.normalize",
+                    "E_SETTINGS_TEXT_NORMALIZE_LOCALE_INVALID",
+                    raw,
+                ),
+            }
+        }
+        let normalized = text_normalize::normalize_spoken(&text, &locales);
+        obs::event(
+            data_dir,
+            Some(task_id),
+            stage,
+            "TEXT.normalize",
+            "ok",
+            Some(serde_json::json!({
+                "locales": locales.iter().map(|l| l.as_str()).collect::<Vec<_>>(),
+                "changed": normalized != text,
+            })),
+        );
+        normalized
+    }
+
+    fn apply_casing(&self, data_dir: &Path, task_id: &str, stage: &str, text: String) -> String {
+        let Some(style) = CasingStyle::parse(&self.casing) else {
+            report_postprocess_invalid(
+                data_dir,
+                task_id,
+                stage,
+                "TEXT.casing",
+                "E_SETTINGS_TEXT_CASING_INVALID",
+                &self.casing,
+            );
+            return text;
+        };
+        if style == CasingStyle::Preserve {
+            return text;
+        }
+        let cased = text_casing::apply_casing(&text, style);
+        obs::event(
+            data_dir,
+            Some(task_id),
+            stage,
+            "TEXT.casing",
+            "ok",
+            Some(serde_json::json!({
+                "style": style.as_str(),
+                "changed": cased != text,
+            })),
+        );
+        cased
+    }
+}
+
+fn report_postprocess_invalid(
+    data_dir: &Path,
+    task_id: &str,
+    stage: &str,
+    step_id: &str,
+    code: &str,
+    value: &str,
+) {
+    obs::event_err(
+        data_dir,
+        obs::ErrorEvent {
+            task_id: Some(task_id),
+            stage,
+            step_id,
+            kind: "config",
+            code,
+            ctx: Some(serde_json::json!({ "value": value })),
+        },
+        "unknown text post-process setting",
+    );
+}
+
 #[derive(Debug, Clone)]
 pub struct TranscriptionInput {
     pub task_id: Option<String>,
@@ -105,12 +255,24 @@ pub struct TranscriptionInput {
 }
 
 #[derive(Debug, Clone)]
-struct TranscriptionOptions {
+pub struct TranscriptionOptions {
     provider: ProviderKind,
     remote_url: String,
     remote_model: Option<String>,
     remote_concurrency: usize,
+    remote_resume: bool,
     preprocess: pipeline::PreprocessConfig,
+    native_wav: bool,
+}
+
+// What a run may swap out: the ASR client, the clock stage timings come from
+// and an extra sink for stage events. `transcribe_audio` uses the service
+// itself, the system clock and no extra sink; tests pass the scripted
+// `test_support` seams to drive the real graph.
+pub struct TranscriptionSeams<'a> {
+    pub asr: &'a dyn AsrClient<TranscriptionOptions>,
+    pub clock: &'a dyn StageClock,
+    pub on_event: &'a mut (dyn FnMut(&'static str, StageEvent<'_>) + Send),
 }
 
 #[derive(Clone)]
@@ -156,7 +318,21 @@ impl TranscriptionService {
     ) -> PortResult<TranscriptionResult> {
         let data_dir = data_dir::data_dir()
             .map_err(|e| PortError::from_message("E_DATA_DIR", e.to_string()))?;
-        let opts = TranscriptionOptions::from_settings(&data_dir)?;
+        let seams = TranscriptionSeams {
+            asr: self,
+            clock: &SystemClock::default(),
+            on_event: &mut |_, _| {},
+        };
+        self.transcribe_audio_with(&data_dir, seams, input).await
+    }
+
+    pub async fn transcribe_audio_with(
+        &self,
+        data_dir: &Path,
+        seams: TranscriptionSeams<'_>,
+        input: TranscriptionInput,
+    ) -> PortResult<TranscriptionResult> {
+        let opts = TranscriptionOptions::from_settings(data_dir)?;
         let task_id = input
             .task_id
             .as_deref()
@@ -167,7 +343,7 @@ impl TranscriptionService {
         self.replace_active_task(task_id.clone());
 
         let result = self
-            .transcribe_audio_inner(&data_dir, task_id.clone(), input, opts)
+            .transcribe_audio_inner(data_dir, task_id.clone(), input, opts, seams)
             .await;
         if !self.clear_active(&task_id) {
             return Err(PortError::new("E_TASK_STALE", "stale transcription task"));
@@ -181,6 +357,7 @@ impl TranscriptionService {
         task_id: String,
         input: TranscriptionInput,
         opts: TranscriptionOptions,
+        seams: TranscriptionSeams<'_>,
     ) -> PortResult<TranscriptionResult> {
         emit_stage_metric(
             data_dir,
@@ -204,110 +381,57 @@ impl TranscriptionService {
             return Err(PortError::new("E_CANCELLED", "cancelled"));
         }
 
-        emit_stage_metric(
+        let TranscriptionSeams {
+            asr,
+            clock,
+            on_event,
+        } = seams;
+        let mut run = TranscriptionRun {
+            service: self,
+            asr,
             data_dir,
-            &task_id,
-            "Preprocess",
-            MetricStageStatus::Started,
-            if opts.preprocess.silence_trim_enabled {
-                "ffmpeg (silence_trim)"
-            } else {
-                "ffmpeg"
-            },
-            None,
-            None,
-        );
-        let wav_path = match pipeline::preprocess_to_temp_wav(data_dir, &task_id) {
-            Ok(path) => path,
-            Err(e) => {
-                let _ = pipeline::cleanup_input_audio_artifact(&input.input_path, data_dir);
-                return Err(PortError::from_message(
-                    "E_PREPROCESS_FAILED",
-                    e.to_string(),
-                ));
-            }
+            task_id: &task_id,
+            input: &input,
+            opts: &opts,
+            probe: None,
+            wav_path: None,
+            preprocess_ms: 0,
+            transcript: None,
         };
-        let preprocess_ms = match self
-            .run_preprocess(
-                data_dir,
-                &task_id,
-                &input.input_path,
-                &wav_path,
-                &opts.preprocess,
-            )
-            .await
-        {
-            Ok(ms) => ms,
-            Err(e) => {
-                let _ = pipeline::cleanup_audio_artifacts(&input.input_path, &wav_path, data_dir);
-                emit_stage_metric(
-                    data_dir,
-                    &task_id,
-                    "Preprocess",
-                    if e.code == "E_CANCELLED" {
-                        MetricStageStatus::Cancelled
-                    } else {
-                        MetricStageStatus::Failed
-                    },
-                    e.message.clone(),
-                    None,
-                    Some(&e.code),
-                );
-                return Err(e);
+        let graph_result = transcription_graph()
+            .run_with_clock(&mut run, clock, |stage, event| {
+                if let StageEvent::Failed { error } = &event {
+                    emit_stage_metric(
+                        data_dir,
+                        &task_id,
+                        stage,
+                        if error.code == "E_CANCELLED" {
+                            MetricStageStatus::Cancelled
+                        } else {
+                            MetricStageStatus::Failed
+                        },
+                        error.message.clone(),
+                        None,
+                        Some(&error.code),
+                    );
+                }
+                on_event(stage, event);
+            })
+            .await;
+        let _ = match run.wav_path.as_deref() {
+            Some(wav_path) => {
+                pipeline::cleanup_audio_artifacts(&input.input_path, wav_path, data_dir)
             }
+            None => pipeline::cleanup_input_audio_artifact(&input.input_path, data_dir),
         };
-        emit_stage_metric(
-            data_dir,
-            &task_id,
-            "Preprocess",
-            MetricStageStatus::Completed,
-            "ok",
-            Some(preprocess_ms),
-            None,
-        );
-
-        emit_stage_metric(
-            data_dir,
-            &task_id,
-            "Transcribe",
-            MetricStageStatus::Started,
-            format!("asr({})", opts.provider.as_str()),
-            None,
-            None,
-        );
-        let transcript = match self
-            .run_transcriber(data_dir, &task_id, &wav_path, &opts)
-            .await
-        {
-            Ok(v) => v,
-            Err(e) => {
-                let _ = pipeline::cleanup_audio_artifacts(&input.input_path, &wav_path, data_dir);
-                emit_stage_metric(
-                    data_dir,
-                    &task_id,
-                    "Transcribe",
-                    if e.code == "E_CANCELLED" {
-                        MetricStageStatus::Cancelled
-                    } else {
-                        MetricStageStatus::Failed
-                    },
-                    e.message.clone(),
-                    None,
-                    Some(&e.code),
-                );
-                return Err(e);
-            }
+        graph_result?;
+        let preprocess_ms = run.preprocess_ms;
+        let Some(transcript) = run.transcript.take() else {
+            return Err(PortError::new(
+                "E_INTERNAL",
+                "transcription graph finished without a transcript",
+            ));
         };
-        let _ = pipeline::cleanup_audio_artifacts(&input.input_path, &wav_path, data_dir);
-        emit_stage_metric(
-            data_dir,
-            &task_id,
-            "Transcribe",
-            MetricStageStatus::Completed,
-            format!("rtf={:.3}", transcript.rtf),
-            Some(transcript.asr_ms),
-            None,
-        );
 
         let metrics = TranscriptionMetrics {
             rtf: transcript.rtf,
@@ -323,6 +447,7 @@ impl TranscriptionService {
             &opts.preprocess,
             preprocess_ms,
             &transcript,
+            run.probe.as_ref(),
         );
         Ok(result)
     }
@@ -380,7 +505,7 @@ impl TranscriptionService {
         task_id: &str,
         wav_path: &Path,
         opts: &TranscriptionOptions,
-    ) -> PortResult<ProviderTranscript> {
+    ) -> PortResult<AsrTranscript> {
         if opts.provider == ProviderKind::Remote {
             self.run_remote_transcriber(data_dir, task_id, wav_path, opts)
                 .await
@@ -398,16 +523,26 @@ impl TranscriptionService {
         task_id: &str,
         wav_path: &Path,
         opts: &TranscriptionOptions,
-    ) -> PortResult<ProviderTranscript> {
+    ) -> PortResult<AsrTranscript> {
         let active = self.active_for_task(task_id)?;
         let cfg = remote_asr::RemoteAsrConfig {
             url: opts.remote_url.clone(),
             model: opts.remote_model.clone(),
             concurrency: opts.remote_concurrency,
+            lane: remote_asr::AsrLane::Interactive,
+            resume: opts.remote_resume,
         };
+        let _gpu = gpu_coordinator::acquire(
+            data_dir,
+            Some(task_id),
+            GpuBackend::Asr,
+            &cfg.url,
+            &active.token,
+        )
+        .await;
         match remote_asr::transcribe_remote(data_dir, task_id, wav_path, &active.token, &cfg).await
         {
-            Ok(v) => Ok(ProviderTranscript {
+            Ok(v) => Ok(AsrTranscript {
                 text: v.text,
                 rtf: v.metrics.rtf,
                 device_used: "remote".to_string(),
@@ -495,6 +630,18 @@ fn cancel_active_transcription(active: &ActiveTranscription, stale: bool) {
     }
 }
 
+impl AsrClient<TranscriptionOptions> for TranscriptionService {
+    fn transcribe<'a>(
+        &'a self,
+        data_dir: &'a Path,
+        task_id: &'a str,
+        wav_path: &'a Path,
+        opts: &'a TranscriptionOptions,
+    ) -> PortFuture<'a, AsrTranscript> {
+        Box::pin(self.run_transcriber(data_dir, task_id, wav_path, opts))
+    }
+}
+
 impl Default for TranscriptionService {
     fn default() -> Self {
         Self::new()
@@ -506,30 +653,185 @@ impl TranscriptionOptions {
         let s = settings::load_settings_strict(data_dir)
             .map_err(|e| PortError::from_message("E_SETTINGS_INVALID", e.to_string()))?;
         Ok(Self {
-            provider: ProviderKind::from_settings_value(&settings::resolve_asr_provider(&s)),
+            provider: ProviderKind::from_settings_value(&power::asr_provider(&s)),
             remote_url: settings::resolve_remote_asr_url(&s),
-            remote_model: settings::resolve_remote_asr_model(&s),
+            remote_model: power::remote_asr_model(&s),
             remote_concurrency: settings::resolve_remote_asr_concurrency(&s),
+            remote_resume: settings::resolve_remote_asr_resume_enabled(&s),
             preprocess: resolve_asr_preprocess_config(&s),
+            native_wav: settings::resolve_preprocess_native_wav_enabled(&s),
         })
     }
 }
 
-#[derive(Debug, Clone)]
-struct ProviderTranscript {
-    text: String,
-    rtf: f64,
-    device_used: String,
-    asr_ms: u128,
-    provider_elapsed_ms: i64,
-    audio_seconds: f64,
-    model_id: String,
-    model_version: Option<String>,
-    remote_slice_count: Option<usize>,
-    remote_concurrency_used: Option<usize>,
-}
-
-fn resolve_asr_preprocess_config(s: &settings::Settings) -> pipeline::PreprocessConfig {
+// Per-run state threaded through the transcription stage graph.
+struct TranscriptionRun<'a> {
+    service: &'a TranscriptionService,
+    asr: &'a dyn AsrClient<TranscriptionOptions>,
+    data_dir: &'a Path,
+    task_id: &'a str,
+    input: &'a TranscriptionInput,
+    opts: &'a TranscriptionOptions,
+    probe: Option<pipeline::InputProbe>,
+    wav_path: Option<PathBuf>,
+    preprocess_ms: u128,
+    transcript: Option<AsrTranscript>,
+}
+
+// Stages of a recorded-audio transcription, ending at the ASR transcript.
+// Post-processing and the history write run in `report_asr_completed`, which
+// the streaming path shares; rewrite and export wait for the user. Further
+// audio stages (translation, diarization) register here with their
+// dependencies and an enable condition read from the run; the temp audio is
+// cleaned up by the caller either way.
+fn transcription_graph<'a>() -> StageGraph<TranscriptionRun<'a>> {
+    StageGraph::new()
+        .stage(Stage {
+            id: "Probe",
+            deps: &[],
+            enabled: Some(probe_enabled),
+            run: probe_stage,
+        })
+        .stage(Stage {
+            id: "Preprocess",
+            deps: &["Probe"],
+            enabled: None,
+            run: preprocess_stage,
+        })
+        .stage(Stage {
+            id: "Transcribe",
+            deps: &["Preprocess"],
+            enabled: None,
+            run: transcribe_stage,
+        })
+}
+
+// A WAV headed for the in-process preprocessor is parsed there (and falls back
+// to FFmpeg if unreadable), so ffprobe only guards inputs FFmpeg decodes.
+fn probe_enabled(run: &TranscriptionRun<'_>) -> bool {
+    let wav = run
+        .input
+        .input_path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("wav"));
+    !(run.opts.native_wav && wav)
+}
+
+fn probe_stage<'r>(run: &'r mut TranscriptionRun<'_>) -> StageFuture<'r> {
+    Box::pin(async move {
+        emit_stage_metric(
+            run.data_dir,
+            run.task_id,
+            "Probe",
+            MetricStageStatus::Started,
+            "ffprobe",
+            None,
+            None,
+        );
+        let t0 = std::time::Instant::now();
+        let data_dir = run.data_dir.to_path_buf();
+        let task_id = run.task_id.to_string();
+        let input_path = run.input.input_path.clone();
+        let probe = tokio::task::spawn_blocking(move || {
+            pipeline::probe_input(&data_dir, &task_id, &input_path)
+        })
+        .await
+        .map_err(|e| PortError::new("E_INTERNAL", format!("probe_join_failed:{e}")))?
+        .map_err(|e| PortError::from_message("E_INPUT_INVALID", e.to_string()))?;
+        emit_stage_metric(
+            run.data_dir,
+            run.task_id,
+            "Probe",
+            MetricStageStatus::Completed,
+            match probe.duration_ms {
+                Some(ms) => format!("{}/{} {ms}ms", probe.format_name, probe.codec_name),
+                None => format!("{}/{}", probe.format_name, probe.codec_name),
+            },
+            Some(t0.elapsed().as_millis()),
+            None,
+        );
+        run.probe = Some(probe);
+        Ok(())
+    })
+}
+
+fn preprocess_stage<'r>(run: &'r mut TranscriptionRun<'_>) -> StageFuture<'r> {
+    Box::pin(async move {
+        emit_stage_metric(
+            run.data_dir,
+            run.task_id,
+            "Preprocess",
+            MetricStageStatus::Started,
+            if run.opts.preprocess.silence_trim_enabled {
+                "ffmpeg (silence_trim)"
+            } else {
+                "ffmpeg"
+            },
+            None,
+            None,
+        );
+        let wav_path = pipeline::preprocess_to_temp_wav(run.data_dir, run.task_id)
+            .map_err(|e| PortError::from_message("E_PREPROCESS_FAILED", e.to_string()))?;
+        run.wav_path = Some(wav_path.clone());
+        run.preprocess_ms = run
+            .service
+            .run_preprocess(
+                run.data_dir,
+                run.task_id,
+                &run.input.input_path,
+                &wav_path,
+                &run.opts.preprocess,
+            )
+            .await?;
+        emit_stage_metric(
+            run.data_dir,
+            run.task_id,
+            "Preprocess",
+            MetricStageStatus::Completed,
+            "ok",
+            Some(run.preprocess_ms),
+            None,
+        );
+        Ok(())
+    })
+}
+
+fn transcribe_stage<'r>(run: &'r mut TranscriptionRun<'_>) -> StageFuture<'r> {
+    Box::pin(async move {
+        let Some(wav_path) = run.wav_path.clone() else {
+            return Err(PortError::new(
+                "E_INTERNAL",
+                "transcribe stage ran without preprocessed audio",
+            ));
+        };
+        emit_stage_metric(
+            run.data_dir,
+            run.task_id,
+            "Transcribe",
+            MetricStageStatus::Started,
+            format!("asr({})", run.opts.provider.as_str()),
+            None,
+            None,
+        );
+        let transcript = run
+            .asr
+            .transcribe(run.data_dir, run.task_id, &wav_path, run.opts)
+            .await?;
+        emit_stage_metric(
+            run.data_dir,
+            run.task_id,
+            "Transcribe",
+            MetricStageStatus::Completed,
+            format!("rtf={:.3}", transcript.rtf),
+            Some(transcript.asr_ms),
+            None,
+        );
+        run.transcript = Some(transcript);
+        Ok(())
+    })
+}
+
+pub(crate) fn resolve_asr_preprocess_config(s: &settings::Settings) -> pipeline::PreprocessConfig {
     let mut cfg = pipeline::PreprocessConfig::default();
     if let Some(v) = s.asr_preprocess_silence_trim_enabled {
         cfg.silence_trim_enabled = v;
@@ -543,6 +845,9 @@ fn resolve_asr_preprocess_config(s: &settings::Settings) -> pipeline::Preprocess
     if let Some(v) = s.asr_preprocess_silence_end_ms {
         cfg.silence_trim_end_ms = v;
     }
+    if let Some(v) = s.asr_preprocess_gain_db {
+        cfg.gain_db = v;
+    }
     cfg
 }
 
@@ -576,7 +881,8 @@ fn emit_perf_metrics(
     provider: ProviderKind,
     preprocess_cfg: &pipeline::PreprocessConfig,
     preprocess_ms: u128,
-    transcript: &ProviderTranscript,
+    transcript: &AsrTranscript,
+    probe: Option<&pipeline::InputProbe>,
 ) {
     let overhead_ms_u128 = transcript
         .asr_ms
@@ -612,6 +918,9 @@ fn emit_perf_metrics(
             asr_preprocess_threshold_db: preprocess_cfg.silence_threshold_db,
             asr_preprocess_trim_start_ms: preprocess_cfg.silence_trim_start_ms,
             asr_preprocess_trim_end_ms: preprocess_cfg.silence_trim_end_ms,
+            input_format: probe.map(|p| p.format_name.clone()),
+            input_codec: probe.map(|p| p.codec_name.clone()),
+            input_duration_ms: probe.and_then(|p| p.duration_ms),
         },
     );
 }
@@ -718,6 +1027,85 @@ mod tests {
         assert!(!active.stale.load(Ordering::SeqCst));
     }
 
+    #[test]
+    fn recorded_audio_runs_the_real_graph_on_scripted_seams() {
+        use crate::context_pack::ContextSnapshot;
+        use typevoice_core::test_support::{
+            block_on, EventLog, ManualClock, RecordedEvent, ScriptedAsr, ScriptedContext,
+        };
+
+        let tmp = tempfile::tempdir().expect("tempdir");
+        settings::save_settings(
+            tmp.path(),
+            &settings::Settings {
+                asr_preprocess_native_wav_enabled: Some(true),
+                ..Default::default()
+            },
+        )
+        .expect("settings");
+        let input_path = tmp.path().join("recording.wav");
+        let samples: Vec<f32> = (0..16_000).map(|i| (i as f32 * 0.05).sin() * 0.3).collect();
+        std::fs::write(
+            &input_path,
+            crate::wav_preprocess::encode_wav_s16(&samples, 16_000),
+        )
+        .expect("input");
+
+        let service = TranscriptionService::new();
+        let asr = ScriptedAsr::new([ScriptedAsr::text("hello world", 40)]);
+        let clock = ManualClock::new(1_000);
+        let mut log = EventLog::default();
+        let mut sink = log.sink();
+        let result = block_on(service.transcribe_audio_with(
+            tmp.path(),
+            TranscriptionSeams {
+                asr: &asr,
+                clock: &clock,
+                on_event: &mut sink,
+            },
+            TranscriptionInput {
+                task_id: Some("task-1".to_string()),
+                input_path,
+                record_elapsed_ms: 1_000,
+                record_label: "Record (test)".to_string(),
+            },
+        ))
+        .expect("transcribed");
+        drop(sink);
+
+        assert_eq!(result.asr_text, "hello world");
+        assert_eq!(result.metrics.asr_ms, 40);
+        assert_eq!(*asr.calls.lock().unwrap(), vec!["task-1"]);
+        // The recorder WAV is parsed in process, so ffprobe is skipped.
+        assert_eq!(
+            log.events,
+            vec![
+                RecordedEvent::Skipped("Probe"),
+                RecordedEvent::Started("Preprocess"),
+                RecordedEvent::Completed("Preprocess", 0),
+                RecordedEvent::Started("Transcribe"),
+                RecordedEvent::Completed("Transcribe", 0),
+            ]
+        );
+
+        // The transcript then takes the rewrite path's context seam.
+        let collector = ScriptedContext::new(ContextSnapshot {
+            clipboard_text: Some("clipboard".to_string()),
+            ..Default::default()
+        });
+        let cfg = crate::context_capture::ContextConfig::default();
+        let snap = crate::rewrite::rewrite_context(
+            &collector,
+            tmp.path(),
+            &result.transcript_id,
+            &cfg,
+            &cfg,
+            None,
+        );
+        assert_eq!(snap.clipboard_text.as_deref(), Some("clipboard"));
+        assert_eq!(*collector.collected.lock().unwrap(), vec!["task-1"]);
+    }
+
     #[test]
     fn current_cancel_cancels_current_task_without_marking_stale() {
         let service = TranscriptionService::new();
@@ -6,16 +6,22 @@ use std::{
 
 use anyhow::{anyhow, Context, Result};
 use futures_util::{SinkExt, StreamExt};
+use tokio_util::sync::CancellationToken;
 
 use crate::{
-    data_dir, doubao_asr, obs,
+    asr_lanes, data_dir, doubao_asr, http_client,
+    obs::{self, debug},
     pcm::{pcm_bytes_for_ms, pcm_peak_abs},
+    pipeline_runtime, power, remote_asr,
     settings::{self, Settings},
     transcription::{TranscriptionMetrics, TranscriptionResult},
-    ui_events::{UiEvent, UiEventMailbox, UiEventStatus},
+    ui_events::{TaskStage, UiEvent, UiEventKind, UiEventMailbox, UiEventStatus},
 };
 
 const REMOTE_CHUNK_MS: u64 = 60_000;
+// Long enough for a few words per request, short enough that the preview
+// trails speech by a few seconds.
+const REMOTE_PREVIEW_CHUNK_MS: u64 = 3_000;
 const DOUBAO_CHUNK_MS: u64 = 200;
 const DOUBAO_FINISH_TIMEOUT_SECS: u64 = 20;
 
@@ -27,7 +33,7 @@ pub enum StreamingProviderKind {
 
 impl StreamingProviderKind {
     fn from_settings(s: &Settings) -> Self {
-        match settings::resolve_asr_provider(s).as_str() {
+        match power::asr_provider(s).as_str() {
             "remote" => Self::Remote,
             _ => Self::Doubao,
         }
@@ -46,6 +52,15 @@ pub struct StreamingSessionConfig {
     pub provider: StreamingProviderKind,
     pub chunk_ms: u64,
     pub chunk_bytes: usize,
+    // Set for a remote live preview: chunks are transcribed for display only
+    // and the session never produces the task's result.
+    pub remote_preview: Option<RemotePreviewTarget>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RemotePreviewTarget {
+    pub url: String,
+    pub model: Option<String>,
 }
 
 #[derive(Debug)]
@@ -160,15 +175,20 @@ impl TranscriptionActor {
                         }
                         ActorMessage::Cancel { task_id } => {
                             if let Some(mut active) = session.take() {
-                                if active.task_id == task_id {
+                                if active.task_id == task_id && active.is_preview() {
+                                    active.cancel();
+                                } else if active.task_id == task_id {
                                     active.cancel();
                                     mailbox.send(UiEvent::stage(
                                         &task_id,
-                                        "Transcribe",
+                                        TaskStage::Transcribe,
                                         UiEventStatus::Cancelled,
                                         "cancelled",
                                     ));
-                                    mailbox.send(UiEvent::state_cancelled(&task_id, "Transcribe"));
+                                    mailbox.send(UiEvent::state_cancelled(
+                                        &task_id,
+                                        TaskStage::Transcribe,
+                                    ));
                                 } else {
                                     session = Some(active);
                                 }
@@ -185,15 +205,25 @@ impl TranscriptionActor {
     pub fn session_config_for_current_settings(&self) -> Result<StreamingSessionConfig> {
         let dir = data_dir::data_dir()?;
         let s = settings::load_settings_strict(&dir)?;
+        // Both ASR providers are remote, so offline mode rejects the session up front.
+        http_client::ensure_online(&s)?;
         let provider = StreamingProviderKind::from_settings(&s);
+        let remote_preview = (provider == StreamingProviderKind::Remote
+            && settings::resolve_remote_asr_live_preview(&s))
+        .then(|| RemotePreviewTarget {
+            url: settings::resolve_remote_asr_url(&s),
+            model: power::remote_asr_model(&s),
+        });
         let chunk_ms = match provider {
             StreamingProviderKind::Doubao => DOUBAO_CHUNK_MS,
+            StreamingProviderKind::Remote if remote_preview.is_some() => REMOTE_PREVIEW_CHUNK_MS,
             StreamingProviderKind::Remote => REMOTE_CHUNK_MS,
         };
         Ok(StreamingSessionConfig {
             provider,
             chunk_ms,
             chunk_bytes: pcm_bytes_for_ms(chunk_ms),
+            remote_preview,
         })
     }
 
@@ -257,6 +287,9 @@ struct ActorSession {
     started_at: Instant,
     text: String,
     doubao: Option<DoubaoSessionHandle>,
+    preview: Option<RemotePreviewHandle>,
+    // Batch ASR work holds back while a dictation is streaming.
+    _lane: asr_lanes::InteractiveLane,
 }
 
 impl ActorSession {
@@ -273,18 +306,28 @@ impl ActorSession {
         } else {
             None
         };
-        mailbox.send(UiEvent::stage(
-            &task_id,
-            "Transcribe",
-            UiEventStatus::Started,
-            format!("asr({})", config.provider.as_str()),
-        ));
+        let preview = config
+            .remote_preview
+            .clone()
+            .map(|target| RemotePreviewHandle::start(task_id.clone(), target, mailbox.clone()));
+        // A preview runs alongside recording; the Transcribe stage starts
+        // with the batch request after stop.
+        if preview.is_none() {
+            mailbox.send(UiEvent::stage(
+                &task_id,
+                TaskStage::Transcribe,
+                UiEventStatus::Started,
+                format!("asr({})", config.provider.as_str()),
+            ));
+        }
         Ok(Self {
             task_id,
             config,
             started_at: Instant::now(),
             text: String::new(),
             doubao,
+            preview,
+            _lane: asr_lanes::global().interactive(),
         })
     }
 
@@ -300,12 +343,24 @@ impl ActorSession {
                 doubao.send_chunk(sequence, pcm, is_last)
             }
             StreamingProviderKind::Remote => {
-                Err(anyhow!("E_REMOTE_STREAMING_UNSUPPORTED: remote HTTP ASR does not support streaming actor mode"))
+                let Some(preview) = self.preview.as_ref() else {
+                    return Err(anyhow!("E_REMOTE_STREAMING_UNSUPPORTED: remote HTTP ASR does not support streaming actor mode"));
+                };
+                preview.push(pcm);
+                Ok(())
             }
         }
     }
 
+    fn is_preview(&self) -> bool {
+        self.preview.is_some()
+    }
+
     fn finish(&mut self, mailbox: &UiEventMailbox) -> Result<()> {
+        if let Some(preview) = self.preview.take() {
+            preview.stop();
+            return Ok(());
+        }
         if let Some(doubao) = self.doubao.take() {
             let text = doubao.finish()?;
             self.text = text;
@@ -313,7 +368,7 @@ impl ActorSession {
         if self.text.trim().is_empty() {
             mailbox.send(UiEvent::stage_with_elapsed(
                 &self.task_id,
-                "Transcribe",
+                TaskStage::Transcribe,
                 UiEventStatus::Completed,
                 "empty",
                 Some(self.started_at.elapsed().as_millis()),
@@ -335,7 +390,7 @@ impl ActorSession {
         );
         mailbox.send(UiEvent::stage_with_elapsed(
             &self.task_id,
-            "Transcribe",
+            TaskStage::Transcribe,
             UiEventStatus::Completed,
             "ok",
             Some(elapsed),
@@ -343,7 +398,7 @@ impl ActorSession {
         ));
         mailbox.send(UiEvent::completed(
             &self.task_id,
-            "transcription.completed",
+            UiEventKind::TranscriptionCompleted,
             "transcription completed",
             serde_json::to_value(&result).unwrap_or_default(),
         ));
@@ -354,12 +409,152 @@ impl ActorSession {
         if let Some(doubao) = self.doubao.take() {
             doubao.cancel();
         }
+        if let Some(preview) = self.preview.take() {
+            preview.stop();
+        }
+    }
+}
+
+// Feeds recording chunks to the remote server one request at a time. Partial
+// events stop as soon as the session is finished or cancelled, so a slow
+// request cannot overwrite the final text.
+struct RemotePreviewHandle {
+    tx: tokio::sync::mpsc::UnboundedSender<Vec<u8>>,
+    token: CancellationToken,
+}
+
+impl RemotePreviewHandle {
+    fn start(task_id: String, target: RemotePreviewTarget, mailbox: UiEventMailbox) -> Self {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let token = CancellationToken::new();
+        pipeline_runtime::handle().spawn(run_remote_preview(
+            task_id,
+            target,
+            mailbox,
+            rx,
+            token.clone(),
+        ));
+        Self { tx, token }
+    }
+
+    fn push(&self, pcm: Vec<u8>) {
+        // Silent chunks would only cost a request.
+        if pcm_peak_abs(&pcm) > 0 {
+            let _ = self.tx.send(pcm);
+        }
+    }
+
+    fn stop(self) {
+        self.token.cancel();
+    }
+}
+
+async fn run_remote_preview(
+    task_id: String,
+    target: RemotePreviewTarget,
+    mailbox: UiEventMailbox,
+    mut rx: tokio::sync::mpsc::UnboundedReceiver<Vec<u8>>,
+    token: CancellationToken,
+) {
+    let Ok(dir) = data_dir::data_dir() else {
+        return;
+    };
+    let mut text = String::new();
+    let mut sequence = 0_u64;
+    let mut failures = 0_usize;
+    loop {
+        let pcm = tokio::select! {
+            _ = token.cancelled() => break,
+            pcm = rx.recv() => match pcm {
+                Some(v) => v,
+                None => break,
+            },
+        };
+        let result = remote_asr::transcribe_pcm_chunk(
+            &dir,
+            &target.url,
+            target.model.as_deref(),
+            &pcm,
+            &token,
+        )
+        .await;
+        if token.is_cancelled() {
+            break;
+        }
+        match result {
+            Ok(delta) => {
+                let delta = delta.trim();
+                if delta.is_empty() {
+                    continue;
+                }
+                append_preview_text(&mut text, delta);
+                sequence += 1;
+                mailbox.send(UiEvent::partial(&task_id, delta, text.as_str(), sequence));
+            }
+            Err(e) => {
+                failures += 1;
+                obs::event(
+                    &dir,
+                    Some(&task_id),
+                    "Transcribe",
+                    "ASR.remote_preview_chunk",
+                    "err",
+                    Some(serde_json::json!({ "code": e.code, "message": e.message })),
+                );
+            }
+        }
+    }
+    obs::event(
+        &dir,
+        Some(&task_id),
+        "Transcribe",
+        "ASR.remote_preview_summary",
+        "ok",
+        Some(serde_json::json!({
+            "partials": sequence,
+            "failed_chunks": failures,
+            "text_chars": text.chars().count(),
+        })),
+    );
+}
+
+// Chunk texts are joined with a space only between two word characters, so
+// Latin words stay apart and CJK text runs on.
+fn append_preview_text(text: &mut String, delta: &str) {
+    let joins_words = text
+        .chars()
+        .last()
+        .zip(delta.chars().next())
+        .is_some_and(|(a, b)| a.is_ascii_alphanumeric() && b.is_ascii_alphanumeric());
+    if joins_words {
+        text.push(' ');
+    }
+    text.push_str(delta);
+}
+
+// Transcribes a short, already captured sample through a standalone Doubao
+// session. Blocks until the final text arrives; partial events are dropped.
+pub(crate) fn transcribe_doubao_once(task_id: &str, pcm: &[u8]) -> Result<String> {
+    let session = DoubaoSessionHandle::start(task_id.to_string(), UiEventMailbox::detached())?;
+    let chunk_bytes = pcm_bytes_for_ms(DOUBAO_CHUNK_MS).max(2);
+    let mut chunks = pcm.chunks(chunk_bytes).peekable();
+    let mut sequence = crate::audio_capture::STREAMING_FIRST_AUDIO_SEQUENCE;
+    if chunks.peek().is_none() {
+        session.send_chunk(sequence, Vec::new(), true)?;
     }
+    while let Some(chunk) = chunks.next() {
+        session.send_chunk(sequence, chunk.to_vec(), chunks.peek().is_none())?;
+        sequence += 1;
+    }
+    session.finish()
 }
 
+// One Doubao websocket session, running as a task on the shared pipeline
+// runtime. `finish` and `cancel` block the calling actor (or spawn_blocking)
+// thread until the task ends.
 struct DoubaoSessionHandle {
     tx: tokio::sync::mpsc::UnboundedSender<DoubaoCommand>,
-    join: Option<std::thread::JoinHandle<Result<String>>>,
+    join: Option<tokio::task::JoinHandle<Result<String>>>,
 }
 
 #[derive(Default)]
@@ -390,10 +585,8 @@ impl DoubaoSessionHandle {
     fn start(task_id: String, mailbox: UiEventMailbox) -> Result<Self> {
         let creds = doubao_asr::load_credentials()?;
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
-        let join = std::thread::Builder::new()
-            .name("doubao_asr_session".to_string())
-            .spawn(move || run_doubao_session(task_id, mailbox, creds, rx))
-            .map_err(|e| anyhow!("spawn doubao session failed: {e}"))?;
+        let join =
+            pipeline_runtime::handle().spawn(run_doubao_session(task_id, mailbox, creds, rx));
         Ok(Self {
             tx,
             join: Some(join),
@@ -413,9 +606,9 @@ impl DoubaoSessionHandle {
     fn finish(mut self) -> Result<String> {
         let _ = self.tx.send(DoubaoCommand::Finish);
         match self.join.take() {
-            Some(join) => join
-                .join()
-                .map_err(|_| anyhow!("doubao session thread panicked"))?,
+            Some(join) => pipeline_runtime::handle()
+                .block_on(join)
+                .map_err(|e| anyhow!("doubao session task failed: {e}"))?,
             None => Err(anyhow!("doubao session join missing")),
         }
     }
@@ -423,23 +616,19 @@ impl DoubaoSessionHandle {
     fn cancel(mut self) {
         let _ = self.tx.send(DoubaoCommand::Cancel);
         if let Some(join) = self.join.take() {
-            let _ = join.join();
+            let _ = pipeline_runtime::handle().block_on(join);
         }
     }
 }
 
-fn run_doubao_session(
+async fn run_doubao_session(
     task_id: String,
     mailbox: UiEventMailbox,
     creds: doubao_asr::DoubaoCredentials,
     mut rx: tokio::sync::mpsc::UnboundedReceiver<DoubaoCommand>,
 ) -> Result<String> {
     let task_id_for_trace = task_id.clone();
-    let rt = tokio::runtime::Builder::new_current_thread()
-        .enable_all()
-        .build()
-        .context("build doubao runtime failed")?;
-    let result = rt.block_on(async move {
+    let result = async move {
         let req = doubao_asr::build_websocket_request(&creds)?;
 
         let (ws, resp) = tokio_tungstenite::connect_async(req)
@@ -609,6 +798,23 @@ fn run_doubao_session(
                         }
                     }
                     if payload.is_last {
+                        if let Ok(dir) = data_dir::data_dir() {
+                            if debug::verbose_enabled() && debug::include_asr_segments() {
+                                let bytes =
+                                    serde_json::to_vec_pretty(&payload.value).unwrap_or_default();
+                                // File IO stays off the runtime's worker threads.
+                                let id = task_id.clone();
+                                let _ = tokio::task::spawn_blocking(move || {
+                                    debug::write_payload_best_effort(
+                                        &dir,
+                                        &id,
+                                        "asr_response.json",
+                                        bytes,
+                                    )
+                                })
+                                .await;
+                            }
+                        }
                         break;
                     }
                 }
@@ -649,7 +855,8 @@ fn run_doubao_session(
             );
         }
         Ok(final_text)
-    });
+    }
+    .await;
     if let Err(err) = &result {
         if let Ok(dir) = data_dir::data_dir() {
             obs::event_err_anyhow(
@@ -707,13 +914,18 @@ fn send_failed(mailbox: &UiEventMailbox, task_id: &str, code: &str, message: imp
     }
     mailbox.send(UiEvent::stage_with_elapsed(
         task_id,
-        "Transcribe",
+        TaskStage::Transcribe,
         UiEventStatus::Failed,
         message.clone(),
         None,
         Some(code.to_string()),
     ));
-    mailbox.send(UiEvent::state_failed(task_id, "Transcribe", code, message));
+    mailbox.send(UiEvent::state_failed(
+        task_id,
+        TaskStage::Transcribe,
+        code,
+        message,
+    ));
 }
 
 #[cfg(test)]
@@ -722,7 +934,7 @@ mod tests {
     use std::sync::mpsc;
     use std::time::Duration;
 
-    use crate::ui_events::{UiEvent, UiEventMailbox};
+    use crate::ui_events::{UiEvent, UiEventKind, UiEventMailbox};
 
     #[test]
     fn chunk_size_matches_pcm_duration() {
@@ -815,22 +1027,33 @@ mod tests {
         assert!(
             events
                 .iter()
-                .any(|event| event.kind == "transcription.empty"),
+                .any(|event| event.kind == UiEventKind::TranscriptionEmpty),
             "expected empty transcription event: {events:?}"
         );
         assert!(
             events
                 .iter()
-                .all(|event| event.status.as_deref() != Some("failed")),
+                .all(|event| event.status != Some(UiEventStatus::Failed)),
             "unexpected failed event: {events:?}"
         );
     }
 
+    #[test]
+    fn preview_text_spaces_only_between_words() {
+        let mut text = String::new();
+        append_preview_text(&mut text, "hello");
+        append_preview_text(&mut text, "world,");
+        append_preview_text(&mut text, "你好");
+        append_preview_text(&mut text, "世界");
+        assert_eq!(text, "hello world,你好世界");
+    }
+
     fn remote_streaming_config() -> StreamingSessionConfig {
         StreamingSessionConfig {
             provider: StreamingProviderKind::Remote,
             chunk_ms: 1,
             chunk_bytes: 2,
+            remote_preview: None,
         }
     }
 
@@ -849,7 +1072,7 @@ mod tests {
         assert!(
             events
                 .iter()
-                .all(|event| event.status.as_deref() != Some("failed")),
+                .all(|event| event.status != Some(UiEventStatus::Failed)),
             "unexpected failed event: {events:?}"
         );
     }
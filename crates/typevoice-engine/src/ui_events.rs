@@ -3,43 +3,169 @@ use std::sync::{
     mpsc,
 };
 
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Emitter, Manager};
-use typevoice_platform::overlay_layout;
+use typevoice_platform::{a11y_announce, overlay_layout, screen_share};
+
+use crate::integrations::mqtt::MqttPublisher;
+use crate::task_manager::TaskHeartbeats;
 
 pub const UI_EVENT_CHANNEL: &str = "ui_event";
+// Live text while recording, from doubao streaming or the remote preview.
+pub const TASK_PARTIAL_TRANSCRIPT_CHANNEL: &str = "task_partial_transcript";
+// Mic meter for the overlay and settings page while a recording is active.
+pub const AUDIO_LEVEL_CHANNEL: &str = "audio_level";
+// Digital silence has no finite dBFS; meters bottom out here instead.
+const DBFS_FLOOR: f64 = -100.0;
+
+// Payload shape of every event on the UI channels. Bumped only for
+// incompatible changes; new optional fields keep the version.
+pub const UI_EVENT_VERSION: u32 = 1;
 
 static EVENT_SEQUENCE: AtomicU64 = AtomicU64::new(1);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// The tags are the strings the frontend matched on before these were typed, so
+// older listeners keep working.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum UiEventKind {
+    #[serde(rename = "transcription.stage")]
+    TranscriptionStage,
+    #[serde(rename = "transcription.partial")]
+    TranscriptionPartial,
+    #[serde(rename = "transcription.completed")]
+    TranscriptionCompleted,
+    #[serde(rename = "transcription.empty")]
+    TranscriptionEmpty,
+    #[serde(rename = "rewrite.completed")]
+    RewriteCompleted,
+    #[serde(rename = "insertion.completed")]
+    InsertionCompleted,
+    #[serde(rename = "diagnostic.error")]
+    DiagnosticError,
+    #[serde(rename = "diagnostic.warning")]
+    DiagnosticWarning,
+    #[serde(rename = "workflow.state")]
+    WorkflowState,
+    #[serde(rename = "workflow.task.failed")]
+    WorkflowTaskFailed,
+    #[serde(rename = "workflow.task.cancelled")]
+    WorkflowTaskCancelled,
+    #[serde(rename = "audio.level")]
+    AudioLevel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum TaskStage {
+    Record,
+    Transcribe,
+    Rewrite,
+    RewriteBudget,
+    RewriteChunk,
+    Insert,
+    Retain,
+    Sink,
+    Persist,
+    Workflow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
 pub enum UiEventStatus {
     Started,
     Completed,
     Failed,
     Cancelled,
+    Recording,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum UiEventEffect {
+    DisplayOnly,
+    StateChanging,
+}
+
+impl UiEventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::TranscriptionStage => "transcription.stage",
+            Self::TranscriptionPartial => "transcription.partial",
+            Self::TranscriptionCompleted => "transcription.completed",
+            Self::TranscriptionEmpty => "transcription.empty",
+            Self::RewriteCompleted => "rewrite.completed",
+            Self::InsertionCompleted => "insertion.completed",
+            Self::DiagnosticError => "diagnostic.error",
+            Self::DiagnosticWarning => "diagnostic.warning",
+            Self::WorkflowState => "workflow.state",
+            Self::WorkflowTaskFailed => "workflow.task.failed",
+            Self::WorkflowTaskCancelled => "workflow.task.cancelled",
+            Self::AudioLevel => "audio.level",
+        }
+    }
+
+    // Shim for string tags arriving from older callers (frontend echoes, logs).
+    pub fn parse(tag: &str) -> Option<Self> {
+        ALL_KINDS.iter().copied().find(|kind| kind.as_str() == tag)
+    }
+}
+
+const ALL_KINDS: &[UiEventKind] = &[
+    UiEventKind::TranscriptionStage,
+    UiEventKind::TranscriptionPartial,
+    UiEventKind::TranscriptionCompleted,
+    UiEventKind::TranscriptionEmpty,
+    UiEventKind::RewriteCompleted,
+    UiEventKind::InsertionCompleted,
+    UiEventKind::DiagnosticError,
+    UiEventKind::DiagnosticWarning,
+    UiEventKind::WorkflowState,
+    UiEventKind::WorkflowTaskFailed,
+    UiEventKind::WorkflowTaskCancelled,
+    UiEventKind::AudioLevel,
+];
+
+impl TaskStage {
+    // Same spelling as the serialized tag; trace events use it as their stage.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Record => "Record",
+            Self::Transcribe => "Transcribe",
+            Self::Rewrite => "Rewrite",
+            Self::RewriteBudget => "RewriteBudget",
+            Self::RewriteChunk => "RewriteChunk",
+            Self::Insert => "Insert",
+            Self::Retain => "Retain",
+            Self::Sink => "Sink",
+            Self::Persist => "Persist",
+            Self::Workflow => "Workflow",
+        }
+    }
 }
 
 impl UiEventStatus {
-    fn as_str(self) -> &'static str {
+    pub fn as_str(self) -> &'static str {
         match self {
             Self::Started => "started",
             Self::Completed => "completed",
             Self::Failed => "failed",
             Self::Cancelled => "cancelled",
+            Self::Recording => "recording",
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UiEvent {
-    pub kind: String,
-    pub effect: String,
+    pub kind: UiEventKind,
+    pub version: u32,
+    pub effect: UiEventEffect,
     pub event_id: String,
     pub sequence: u64,
     pub task_id: Option<String>,
-    pub stage: Option<String>,
-    pub status: Option<String>,
+    pub stage: Option<TaskStage>,
+    pub status: Option<UiEventStatus>,
     pub message: String,
     pub elapsed_ms: Option<u128>,
     pub error_code: Option<String>,
@@ -49,16 +175,49 @@ pub struct UiEvent {
 
 #[derive(Debug, Clone, Serialize)]
 struct OverlayState {
+    version: u32,
     visible: bool,
-    status: String,
+    status: OverlayStatus,
     detail: Option<String>,
     ts_ms: i64,
 }
 
+// Serialized as the label the overlay shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+enum OverlayStatus {
+    #[serde(rename = "Ready")]
+    Ready,
+    #[serde(rename = "Listening")]
+    Listening,
+    #[serde(rename = "Creating text")]
+    CreatingText,
+    #[serde(rename = "Text ready")]
+    TextReady,
+    #[serde(rename = "Improving text")]
+    ImprovingText,
+    #[serde(rename = "Text improved")]
+    TextImproved,
+    #[serde(rename = "Pasting text")]
+    PastingText,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct Announcement {
+    text: String,
+    important: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaptionsState {
+    pub visible: bool,
+    pub text: String,
+    pub ts_ms: i64,
+}
+
 impl UiEvent {
     pub fn stage(
         task_id: impl Into<String>,
-        stage: impl Into<String>,
+        stage: TaskStage,
         status: UiEventStatus,
         message: impl Into<String>,
     ) -> Self {
@@ -67,20 +226,21 @@ impl UiEvent {
 
     pub fn stage_with_elapsed(
         task_id: impl Into<String>,
-        stage: impl Into<String>,
+        stage: TaskStage,
         status: UiEventStatus,
         message: impl Into<String>,
         elapsed_ms: Option<u128>,
         error_code: Option<String>,
     ) -> Self {
         Self {
-            kind: "transcription.stage".to_string(),
-            effect: "displayOnly".to_string(),
+            kind: UiEventKind::TranscriptionStage,
+            version: UI_EVENT_VERSION,
+            effect: UiEventEffect::DisplayOnly,
             event_id: new_event_id(),
             sequence: next_sequence(),
             task_id: Some(task_id.into()),
-            stage: Some(stage.into()),
-            status: Some(status.as_str().to_string()),
+            stage: Some(stage),
+            status: Some(status),
             message: message.into(),
             elapsed_ms,
             error_code,
@@ -95,13 +255,38 @@ impl UiEvent {
         message: impl Into<String>,
     ) -> Self {
         Self {
-            kind: "diagnostic.error".to_string(),
-            effect: "displayOnly".to_string(),
+            kind: UiEventKind::DiagnosticError,
+            version: UI_EVENT_VERSION,
+            effect: UiEventEffect::DisplayOnly,
             event_id: new_event_id(),
             sequence: next_sequence(),
             task_id: Some(task_id.into()),
             stage: None,
-            status: Some("failed".to_string()),
+            status: Some(UiEventStatus::Failed),
+            message: message.into(),
+            elapsed_ms: None,
+            error_code: Some(code.into()),
+            payload: None,
+            ts_ms: now_ms(),
+        }
+    }
+
+    // Non-fatal diagnostic; the task keeps running.
+    pub fn warning(
+        task_id: Option<String>,
+        stage: TaskStage,
+        code: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            kind: UiEventKind::DiagnosticWarning,
+            version: UI_EVENT_VERSION,
+            effect: UiEventEffect::DisplayOnly,
+            event_id: new_event_id(),
+            sequence: next_sequence(),
+            task_id,
+            stage: Some(stage),
+            status: None,
             message: message.into(),
             elapsed_ms: None,
             error_code: Some(code.into()),
@@ -112,17 +297,17 @@ impl UiEvent {
 
     pub fn completed(
         task_id: impl Into<String>,
-        kind: impl Into<String>,
+        kind: UiEventKind,
         message: impl Into<String>,
         payload: serde_json::Value,
     ) -> Self {
-        Self::completed_with_effect(task_id, kind, message, payload, "displayOnly")
+        Self::completed_with_effect(task_id, kind, message, payload, UiEventEffect::DisplayOnly)
     }
 
     pub fn transcription_empty(task_id: impl Into<String>) -> Self {
         Self::completed(
             task_id,
-            "transcription.empty",
+            UiEventKind::TranscriptionEmpty,
             "no speech detected",
             serde_json::json!({}),
         )
@@ -130,28 +315,29 @@ impl UiEvent {
 
     pub fn state_completed(
         task_id: impl Into<String>,
-        kind: impl Into<String>,
+        kind: UiEventKind,
         message: impl Into<String>,
         payload: serde_json::Value,
     ) -> Self {
-        Self::completed_with_effect(task_id, kind, message, payload, "displayOnly")
+        Self::completed_with_effect(task_id, kind, message, payload, UiEventEffect::DisplayOnly)
     }
 
     fn completed_with_effect(
         task_id: impl Into<String>,
-        kind: impl Into<String>,
+        kind: UiEventKind,
         message: impl Into<String>,
         payload: serde_json::Value,
-        effect: impl Into<String>,
+        effect: UiEventEffect,
     ) -> Self {
         Self {
-            kind: kind.into(),
-            effect: effect.into(),
+            kind,
+            version: UI_EVENT_VERSION,
+            effect,
             event_id: new_event_id(),
             sequence: next_sequence(),
             task_id: Some(task_id.into()),
             stage: None,
-            status: Some("completed".to_string()),
+            status: Some(UiEventStatus::Completed),
             message: message.into(),
             elapsed_ms: None,
             error_code: None,
@@ -162,12 +348,13 @@ impl UiEvent {
 
     pub fn workflow_state(payload: impl Serialize) -> Self {
         Self {
-            kind: "workflow.state".to_string(),
-            effect: "displayOnly".to_string(),
+            kind: UiEventKind::WorkflowState,
+            version: UI_EVENT_VERSION,
+            effect: UiEventEffect::DisplayOnly,
             event_id: new_event_id(),
             sequence: next_sequence(),
             task_id: None,
-            stage: Some("Workflow".to_string()),
+            stage: Some(TaskStage::Workflow),
             status: None,
             message: "workflow state".to_string(),
             elapsed_ms: None,
@@ -177,15 +364,17 @@ impl UiEvent {
         }
     }
 
+    // `rms` and `peak` are linear (0..=1); the payload carries both scales.
     pub fn audio_level(recording_id: impl Into<String>, rms: f64, peak: f64) -> Self {
         Self {
-            kind: "audio.level".to_string(),
-            effect: "displayOnly".to_string(),
+            kind: UiEventKind::AudioLevel,
+            version: UI_EVENT_VERSION,
+            effect: UiEventEffect::DisplayOnly,
             event_id: new_event_id(),
             sequence: next_sequence(),
             task_id: None,
-            stage: Some("Record".to_string()),
-            status: Some("recording".to_string()),
+            stage: Some(TaskStage::Record),
+            status: Some(UiEventStatus::Recording),
             message: "audio level".to_string(),
             elapsed_ms: None,
             error_code: None,
@@ -193,6 +382,8 @@ impl UiEvent {
                 "recordingId": recording_id.into(),
                 "rms": rms.clamp(0.0, 1.0),
                 "peak": peak.clamp(0.0, 1.0),
+                "rmsDbfs": to_dbfs(rms),
+                "peakDbfs": to_dbfs(peak),
             })),
             ts_ms: now_ms(),
         }
@@ -205,13 +396,14 @@ impl UiEvent {
         sequence: u64,
     ) -> Self {
         Self {
-            kind: "transcription.partial".to_string(),
-            effect: "displayOnly".to_string(),
+            kind: UiEventKind::TranscriptionPartial,
+            version: UI_EVENT_VERSION,
+            effect: UiEventEffect::DisplayOnly,
             event_id: new_event_id(),
             sequence: next_sequence(),
             task_id: Some(task_id.into()),
-            stage: Some("Transcribe".to_string()),
-            status: Some("recording".to_string()),
+            stage: Some(TaskStage::Transcribe),
+            status: Some(UiEventStatus::Recording),
             message: "partial transcription".to_string(),
             elapsed_ms: None,
             error_code: None,
@@ -226,18 +418,19 @@ impl UiEvent {
 
     pub fn state_failed(
         task_id: impl Into<String>,
-        stage: impl Into<String>,
+        stage: TaskStage,
         code: impl Into<String>,
         message: impl Into<String>,
     ) -> Self {
         Self {
-            kind: "workflow.task.failed".to_string(),
-            effect: "displayOnly".to_string(),
+            kind: UiEventKind::WorkflowTaskFailed,
+            version: UI_EVENT_VERSION,
+            effect: UiEventEffect::DisplayOnly,
             event_id: new_event_id(),
             sequence: next_sequence(),
             task_id: Some(task_id.into()),
-            stage: Some(stage.into()),
-            status: Some("failed".to_string()),
+            stage: Some(stage),
+            status: Some(UiEventStatus::Failed),
             message: message.into(),
             elapsed_ms: None,
             error_code: Some(code.into()),
@@ -246,15 +439,16 @@ impl UiEvent {
         }
     }
 
-    pub fn state_cancelled(task_id: impl Into<String>, stage: impl Into<String>) -> Self {
+    pub fn state_cancelled(task_id: impl Into<String>, stage: TaskStage) -> Self {
         Self {
-            kind: "workflow.task.cancelled".to_string(),
-            effect: "displayOnly".to_string(),
+            kind: UiEventKind::WorkflowTaskCancelled,
+            version: UI_EVENT_VERSION,
+            effect: UiEventEffect::DisplayOnly,
             event_id: new_event_id(),
             sequence: next_sequence(),
             task_id: Some(task_id.into()),
-            stage: Some(stage.into()),
-            status: Some("cancelled".to_string()),
+            stage: Some(stage),
+            status: Some(UiEventStatus::Cancelled),
             message: "cancelled".to_string(),
             elapsed_ms: None,
             error_code: None,
@@ -267,45 +461,242 @@ impl UiEvent {
 #[derive(Clone)]
 pub struct UiEventMailbox {
     tx: mpsc::Sender<UiEvent>,
+    heartbeats: TaskHeartbeats,
 }
 
 impl UiEventMailbox {
-    pub fn new(app: AppHandle) -> Self {
+    pub fn new(app: AppHandle, heartbeats: TaskHeartbeats) -> Self {
         let (tx, rx) = mpsc::channel::<UiEvent>();
         std::thread::Builder::new()
             .name("ui_event_actor".to_string())
             .spawn(move || {
+                let mut captions_active = false;
+                let mut announced_phase = String::new();
+                let mut mqtt = MqttPublisher::default();
                 while let Ok(event) = rx.recv() {
+                    mqtt.on_event(&event);
                     let overlay = overlay_state_from_event(&event);
+                    let captions =
+                        captions_state_from_event(&event, &mut captions_active, captions_enabled);
+                    let announcement = announcement_from_event(&event, &mut announced_phase);
+                    if let Some(partial) = partial_transcript_from_event(&event) {
+                        let _ = app.emit(TASK_PARTIAL_TRANSCRIPT_CHANNEL, partial);
+                    }
+                    if let Some(level) = audio_level_from_event(&event) {
+                        let _ = app.emit(AUDIO_LEVEL_CHANNEL, level);
+                    }
                     let _ = app.emit(UI_EVENT_CHANNEL, event);
                     if let Some(state) = overlay {
                         apply_overlay_state(&app, state);
                     }
+                    if let Some(state) = captions {
+                        apply_captions_state(&app, state);
+                    }
+                    if let Some(a) = announcement.filter(|_| announcements_enabled()) {
+                        announce(&app, a);
+                    }
                 }
             })
             .expect("failed to start ui event actor");
-        Self { tx }
+        Self { tx, heartbeats }
+    }
+
+    // For one-off work outside a workflow task (e.g. the settings mic test):
+    // events are dropped instead of reaching the frontend.
+    pub fn detached() -> Self {
+        let (tx, _rx) = mpsc::channel::<UiEvent>();
+        Self {
+            tx,
+            heartbeats: TaskHeartbeats::default(),
+        }
     }
 
     #[cfg(test)]
     pub fn for_test() -> (Self, mpsc::Receiver<UiEvent>) {
         let (tx, rx) = mpsc::channel::<UiEvent>();
-        (Self { tx }, rx)
+        (
+            Self {
+                tx,
+                heartbeats: TaskHeartbeats::default(),
+            },
+            rx,
+        )
     }
 
     pub fn send(&self, event: UiEvent) {
+        if let Some(task_id) = &event.task_id {
+            self.heartbeats.beat(task_id);
+        }
         let _ = self.tx.send(event);
     }
 }
 
+fn partial_transcript_from_event(event: &UiEvent) -> Option<serde_json::Value> {
+    if event.kind != UiEventKind::TranscriptionPartial {
+        return None;
+    }
+    let payload = event.payload.as_ref()?;
+    Some(serde_json::json!({
+        "version": UI_EVENT_VERSION,
+        "taskId": event.task_id,
+        "text": payload.get("text")?,
+        "textDelta": payload.get("textDelta")?,
+        "sequence": payload.get("sequence")?,
+        "tsMs": event.ts_ms,
+    }))
+}
+
+fn audio_level_from_event(event: &UiEvent) -> Option<serde_json::Value> {
+    if event.kind != UiEventKind::AudioLevel {
+        return None;
+    }
+    let payload = event.payload.as_ref()?;
+    Some(serde_json::json!({
+        "version": UI_EVENT_VERSION,
+        "recordingId": payload.get("recordingId")?,
+        "rmsDbfs": payload.get("rmsDbfs")?,
+        "peakDbfs": payload.get("peakDbfs")?,
+        "tsMs": event.ts_ms,
+    }))
+}
+
+fn to_dbfs(linear: f64) -> f64 {
+    let linear = linear.clamp(0.0, 1.0);
+    if linear <= 0.0 {
+        return DBFS_FLOOR;
+    }
+    (20.0 * linear.log10()).max(DBFS_FLOOR)
+}
+
+fn workflow_phase(event: &UiEvent) -> Option<&str> {
+    event.payload.as_ref()?.get("phase")?.as_str()
+}
+
+// Captions follow one recording: settings are read once when recording starts,
+// partial text is forwarded while active, and the window hides once the
+// workflow leaves recording/transcribing.
+fn captions_state_from_event(
+    event: &UiEvent,
+    active: &mut bool,
+    is_enabled: impl FnOnce() -> bool,
+) -> Option<CaptionsState> {
+    match event.kind {
+        UiEventKind::WorkflowState => match workflow_phase(event).unwrap_or("idle") {
+            "recording" if !*active => {
+                *active = is_enabled();
+                active.then(|| CaptionsState {
+                    visible: true,
+                    text: String::new(),
+                    ts_ms: now_ms(),
+                })
+            }
+            "recording" | "transcribing" => None,
+            _ if *active => {
+                *active = false;
+                Some(CaptionsState {
+                    visible: false,
+                    text: String::new(),
+                    ts_ms: now_ms(),
+                })
+            }
+            _ => None,
+        },
+        UiEventKind::TranscriptionPartial if *active => {
+            let text = event.payload.as_ref()?.get("text")?.as_str()?;
+            Some(CaptionsState {
+                visible: true,
+                text: text.to_string(),
+                ts_ms: now_ms(),
+            })
+        }
+        _ => None,
+    }
+}
+
+// Only the transitions a user needs to follow the hotkey flow without the
+// overlay: each phase is announced once, and every error.
+fn announcement_from_event(event: &UiEvent, last_phase: &mut String) -> Option<Announcement> {
+    if event.kind == UiEventKind::DiagnosticError {
+        return Some(Announcement {
+            text: format!("Error: {}", event.message),
+            important: true,
+        });
+    }
+    if event.kind != UiEventKind::WorkflowState {
+        return None;
+    }
+    let phase = workflow_phase(event).unwrap_or("idle");
+    if phase == last_phase {
+        return None;
+    }
+    let previous = std::mem::replace(last_phase, phase.to_string());
+    let text = match phase {
+        "recording" => "Recording started",
+        "transcribing" if previous == "recording" => "Recording stopped",
+        "transcribed" | "rewritten" => "Transcription ready",
+        _ => return None,
+    };
+    Some(Announcement {
+        text: text.to_string(),
+        important: false,
+    })
+}
+
+fn announcements_enabled() -> bool {
+    let Ok(dir) = crate::data_dir::data_dir() else {
+        return false;
+    };
+    let Ok(s) = crate::settings::load_settings_strict(&dir) else {
+        return false;
+    };
+    s.a11y_announcements.unwrap_or(false)
+}
+
+// The frontend mirrors the text into an aria-live region; Windows also gets a
+// UIA notification, which is read while another app has focus.
+fn announce(app: &AppHandle, a: Announcement) {
+    #[cfg(windows)]
+    let hwnd = app
+        .get_webview_window("main")
+        .and_then(|w| w.hwnd().ok())
+        .map(|h| h.0 as isize);
+    #[cfg(not(windows))]
+    let hwnd = None;
+    a11y_announce::announce(hwnd, &a.text, a.important);
+    let _ = app.emit("tv_a11y_announcement", a);
+}
+
+fn captions_enabled() -> bool {
+    let Ok(dir) = crate::data_dir::data_dir() else {
+        return false;
+    };
+    let Ok(s) = crate::settings::load_settings_strict(&dir) else {
+        return false;
+    };
+    crate::settings::resolve_captions_config(&s).enabled
+}
+
+pub fn apply_captions_state(app: &AppHandle, state: CaptionsState) {
+    if let Some(w) = app.get_webview_window("captions") {
+        if state.visible {
+            let _ = overlay_layout::apply_captions_layout(&w);
+            let _ = w.show();
+        } else {
+            let _ = w.hide();
+        }
+    }
+    let _ = app.emit("tv_captions_state", state);
+}
+
 fn overlay_state_from_event(event: &UiEvent) -> Option<OverlayState> {
-    if event.kind != "workflow.state" {
+    if event.kind != UiEventKind::WorkflowState {
         return None;
     }
     if !overlay_enabled() {
         return Some(OverlayState {
+            version: UI_EVENT_VERSION,
             visible: false,
-            status: "Ready".to_string(),
+            status: OverlayStatus::Ready,
             detail: None,
             ts_ms: now_ms(),
         });
@@ -321,15 +712,16 @@ fn overlay_state_from_event(event: &UiEvent) -> Option<OverlayState> {
         .filter(|v| !v.trim().is_empty())
         .map(ToOwned::to_owned);
     let (visible, status, detail) = match phase {
-        "recording" => (true, "Listening".to_string(), None),
-        "transcribing" => (true, "Creating text".to_string(), None),
-        "transcribed" => (true, "Text ready".to_string(), None),
-        "rewriting" => (true, "Improving text".to_string(), None),
-        "rewritten" => (true, "Text improved".to_string(), None),
-        "inserting" => (true, "Pasting text".to_string(), None),
-        _ => (false, "Ready".to_string(), diagnostic_code),
+        "recording" => (true, OverlayStatus::Listening, None),
+        "transcribing" => (true, OverlayStatus::CreatingText, None),
+        "transcribed" => (true, OverlayStatus::TextReady, None),
+        "rewriting" => (true, OverlayStatus::ImprovingText, None),
+        "rewritten" => (true, OverlayStatus::TextImproved, None),
+        "inserting" => (true, OverlayStatus::PastingText, None),
+        _ => (false, OverlayStatus::Ready, diagnostic_code),
     };
     Some(OverlayState {
+        version: UI_EVENT_VERSION,
         visible,
         status,
         detail,
@@ -349,7 +741,8 @@ fn overlay_enabled() -> bool {
 
 fn apply_overlay_state(app: &AppHandle, state: OverlayState) {
     if let Some(w) = app.get_webview_window("overlay") {
-        if state.visible {
+        // Kept off shared screens until a state update after the share ends.
+        if state.visible && screen_share::active().is_none() {
             let _ = overlay_layout::apply_overlay_layout(&w);
             let _ = w.show();
         } else {
@@ -382,22 +775,138 @@ mod tests {
     fn ui_events_use_single_frontend_channel() {
         let event = UiEvent::stage(
             "task-1",
-            "Transcribe",
+            TaskStage::Transcribe,
             UiEventStatus::Started,
             "asr(doubao)",
         );
 
         assert_eq!(UI_EVENT_CHANNEL, "ui_event");
-        assert_eq!(event.kind, "transcription.stage");
+        assert_eq!(event.kind, UiEventKind::TranscriptionStage);
         assert_eq!(event.task_id.as_deref(), Some("task-1"));
     }
 
+    #[test]
+    fn typed_fields_serialize_to_the_existing_tags() {
+        let event = UiEvent::state_failed("task-1", TaskStage::Rewrite, "E_LLM_FAILED", "boom");
+        let json = serde_json::to_value(&event).expect("serialize");
+
+        assert_eq!(json["kind"], "workflow.task.failed");
+        assert_eq!(json["version"], UI_EVENT_VERSION);
+        assert_eq!(json["effect"], "displayOnly");
+        assert_eq!(json["stage"], "Rewrite");
+        assert_eq!(json["status"], "failed");
+        for kind in ALL_KINDS {
+            assert_eq!(UiEventKind::parse(kind.as_str()), Some(*kind));
+        }
+        assert_eq!(UiEventKind::parse("unknown.kind"), None);
+    }
+
     #[test]
     fn error_events_keep_code_and_message() {
         let event = UiEvent::error("task-1", "E_ASR_FAILED", "asr failed");
 
-        assert_eq!(event.kind, "diagnostic.error");
+        assert_eq!(event.kind, UiEventKind::DiagnosticError);
         assert_eq!(event.error_code.as_deref(), Some("E_ASR_FAILED"));
         assert_eq!(event.message, "asr failed");
     }
+
+    #[test]
+    fn partial_transcript_mirrors_partial_events_only() {
+        let partial = UiEvent::partial("task-1", "world", "hello world", 2);
+        let mirrored = partial_transcript_from_event(&partial).expect("partial is mirrored");
+        assert_eq!(mirrored["taskId"], "task-1");
+        assert_eq!(mirrored["text"], "hello world");
+        assert_eq!(mirrored["sequence"], 2);
+        let stage = UiEvent::stage(
+            "task-1",
+            TaskStage::Transcribe,
+            UiEventStatus::Started,
+            "asr",
+        );
+        assert!(partial_transcript_from_event(&stage).is_none());
+    }
+
+    #[test]
+    fn audio_level_channel_carries_dbfs() {
+        let level = audio_level_from_event(&UiEvent::audio_level("rec-1", 0.5, 1.0))
+            .expect("level is mirrored");
+        assert_eq!(level["recordingId"], "rec-1");
+        assert!((level["rmsDbfs"].as_f64().unwrap() + 6.02).abs() < 0.01);
+        assert_eq!(level["peakDbfs"], 0.0);
+        let silent = audio_level_from_event(&UiEvent::audio_level("rec-1", 0.0, 0.0)).unwrap();
+        assert_eq!(silent["rmsDbfs"], DBFS_FLOOR);
+        assert!(audio_level_from_event(&UiEvent::partial("task-1", "a", "a", 1)).is_none());
+    }
+
+    #[test]
+    fn captions_follow_recording_and_partial_text() {
+        let state = |phase: &str| UiEvent {
+            kind: UiEventKind::WorkflowState,
+            payload: Some(serde_json::json!({ "phase": phase })),
+            ..UiEvent::error("task-1", "E", "m")
+        };
+        let mut active = false;
+
+        let partial = UiEvent::partial("task-1", "lo", "hello", 1);
+        assert_eq!(
+            captions_state_from_event(&partial, &mut active, || true),
+            None
+        );
+
+        let shown = captions_state_from_event(&state("recording"), &mut active, || true);
+        assert!(shown.is_some_and(|s| s.visible && s.text.is_empty()));
+        let text = captions_state_from_event(&partial, &mut active, || unreachable!());
+        assert_eq!(text.map(|s| s.text).as_deref(), Some("hello"));
+        assert_eq!(
+            captions_state_from_event(&state("transcribing"), &mut active, || unreachable!()),
+            None
+        );
+        let hidden = captions_state_from_event(&state("transcribed"), &mut active, || true);
+        assert!(hidden.is_some_and(|s| !s.visible));
+        assert!(!active);
+
+        assert_eq!(
+            captions_state_from_event(&state("recording"), &mut active, || false),
+            None
+        );
+        assert_eq!(
+            captions_state_from_event(&partial, &mut active, || true),
+            None
+        );
+    }
+
+    #[test]
+    fn announcements_cover_phase_changes_once_and_errors() {
+        let state = |phase: &str| UiEvent {
+            kind: UiEventKind::WorkflowState,
+            payload: Some(serde_json::json!({ "phase": phase })),
+            ..UiEvent::error("task-1", "E", "m")
+        };
+        let mut last = String::new();
+        let text =
+            |e: &UiEvent, last: &mut String| announcement_from_event(e, last).map(|a| a.text);
+
+        assert_eq!(
+            text(&state("recording"), &mut last).as_deref(),
+            Some("Recording started")
+        );
+        assert_eq!(text(&state("recording"), &mut last), None);
+        assert_eq!(
+            text(&state("transcribing"), &mut last).as_deref(),
+            Some("Recording stopped")
+        );
+        assert_eq!(
+            text(&state("transcribed"), &mut last).as_deref(),
+            Some("Transcription ready")
+        );
+        assert_eq!(text(&state("idle"), &mut last), None);
+
+        let err = announcement_from_event(
+            &UiEvent::error("task-1", "E_ASR_FAILED", "asr failed"),
+            &mut last,
+        )
+        .expect("error");
+        assert!(err.important);
+        assert_eq!(err.text, "Error: asr failed");
+    }
 }
@@ -0,0 +1,108 @@
+use std::sync::OnceLock;
+
+use serde::Serialize;
+
+use crate::{settings, transcription};
+
+// Ends a hands-free recording once speech has been heard and then the input
+// stays below the silence threshold for `silence_ms`. Fed one meter window at a
+// time; it trips at most once per recording.
+#[derive(Debug, Clone)]
+pub struct SilenceDetector {
+    threshold_rms: f64,
+    silence_ms: u64,
+    heard_speech: bool,
+    quiet_ms: u64,
+    tripped: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoStop {
+    pub task_id: String,
+    pub recording_session_id: String,
+    pub silence_ms: u64,
+}
+
+type AutoStopListener = Box<dyn Fn(AutoStop) + Send + Sync>;
+static AUTO_STOP_LISTENER: OnceLock<AutoStopListener> = OnceLock::new();
+
+impl SilenceDetector {
+    pub fn new(threshold_db: f64, silence_ms: u64) -> Self {
+        Self {
+            threshold_rms: 10_f64.powf(threshold_db / 20.0),
+            silence_ms,
+            heard_speech: false,
+            quiet_ms: 0,
+            tripped: false,
+        }
+    }
+
+    // None when auto-stop is off. Uses the same threshold as the preprocess
+    // silence trim, so a calibrated noise floor applies to both.
+    pub fn from_settings(s: &settings::Settings) -> Option<Self> {
+        let silence_ms = settings::resolve_record_vad_silence_ms(s)?;
+        let threshold_db = transcription::resolve_asr_preprocess_config(s).silence_threshold_db;
+        Some(Self::new(threshold_db, silence_ms))
+    }
+
+    pub fn silence_ms(&self) -> u64 {
+        self.silence_ms
+    }
+
+    // True exactly once, on the window that completes the trailing silence.
+    // Silence before the first word never counts.
+    pub fn push(&mut self, rms: f64, window_ms: u64) -> bool {
+        if self.tripped {
+            return false;
+        }
+        if rms >= self.threshold_rms {
+            self.heard_speech = true;
+            self.quiet_ms = 0;
+            return false;
+        }
+        if !self.heard_speech {
+            return false;
+        }
+        self.quiet_ms += window_ms;
+        if self.quiet_ms >= self.silence_ms {
+            self.tripped = true;
+        }
+        self.tripped
+    }
+}
+
+// Called from the recording meter thread when a detector trips; the desktop app
+// stops the matching recording. Only the first registration takes effect.
+pub fn set_auto_stop_listener(f: impl Fn(AutoStop) + Send + Sync + 'static) -> bool {
+    AUTO_STOP_LISTENER.set(Box::new(f)).is_ok()
+}
+
+pub(crate) fn notify_auto_stop(stop: AutoStop) {
+    if let Some(f) = AUTO_STOP_LISTENER.get() {
+        f(stop);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SilenceDetector;
+
+    #[test]
+    fn trips_once_after_speech_then_trailing_silence() {
+        let mut vad = SilenceDetector::new(-40.0, 150);
+        // Leading silence never stops the recording.
+        for _ in 0..10 {
+            assert!(!vad.push(0.0, 50));
+        }
+        assert!(!vad.push(0.2, 50));
+        assert!(!vad.push(0.0, 50));
+        assert!(!vad.push(0.0, 50));
+        // A word in between restarts the count.
+        assert!(!vad.push(0.2, 50));
+        assert!(!vad.push(0.001, 50));
+        assert!(!vad.push(0.001, 50));
+        assert!(vad.push(0.001, 50));
+        assert!(!vad.push(0.0, 50));
+    }
+}
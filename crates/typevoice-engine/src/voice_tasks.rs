@@ -1,145 +1,238 @@
+use std::future::Future;
+use std::time::Duration;
+
 use tauri::{Manager, Runtime};
 
+use crate::asr_lanes;
 use crate::audio_capture::{RecordingRegistry, RecordingStopOutcome};
 use crate::insertion;
 use crate::obs;
 use crate::rewrite;
-use crate::task_manager::TaskManager;
+use crate::settings;
+use crate::task_manager::{TaskHeartbeats, TaskManager};
 use crate::transcription::{TranscriptionInput, TranscriptionService};
-use crate::ui_events::{UiEvent, UiEventMailbox, UiEventStatus};
+use crate::ui_events::{TaskStage, UiEvent, UiEventKind, UiEventMailbox, UiEventStatus};
 use crate::voice_workflow::{
     VoiceWorkflow, WorkflowError, WorkflowInsertCompletedRequest, WorkflowRewriteCompletedRequest,
     WorkflowTaskFailedRequest, WorkflowTaskRequest,
 };
 use crate::RuntimeState;
 
+// While a runner is alive it beats for its task even between events, so a
+// long upload is not mistaken for a stall; a runner that panics or is dropped
+// goes quiet and the watchdog fails its task.
+const RUNNER_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+const STALL_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
 pub fn spawn<R: Runtime>(app: tauri::AppHandle<R>, task: WorkflowTaskRequest) {
+    let heartbeats = app.state::<TaskManager>().heartbeats();
+    let task_id = task.task_id().to_string();
     tauri::async_runtime::spawn(async move {
-        match task {
-            WorkflowTaskRequest::StopRecordTranscribe {
+        with_heartbeat(&heartbeats, &task_id, run(app, task)).await;
+    });
+}
+
+async fn with_heartbeat<F: Future>(
+    heartbeats: &TaskHeartbeats,
+    task_id: &str,
+    fut: F,
+) -> F::Output {
+    let mut fut = std::pin::pin!(fut);
+    let mut tick = tokio::time::interval(RUNNER_HEARTBEAT_INTERVAL);
+    loop {
+        tokio::select! {
+            out = &mut fut => return out,
+            _ = tick.tick() => heartbeats.beat(task_id),
+        }
+    }
+}
+
+// Gives up on tasks whose runner stopped beating; see
+// `VoiceWorkflow::fail_stalled_tasks`. The timeout is re-read every tick so a
+// settings change applies without a restart.
+pub fn spawn_stall_watchdog<R: Runtime>(app: tauri::AppHandle<R>) {
+    let _ = std::thread::Builder::new()
+        .name("task_stall_watchdog".to_string())
+        .spawn(move || loop {
+            std::thread::sleep(STALL_CHECK_INTERVAL);
+            let Some(stall_ms) = crate::data_dir::data_dir()
+                .ok()
+                .and_then(|dir| settings::load_settings_strict(&dir).ok())
+                .and_then(|s| settings::resolve_task_stall_timeout_ms(&s))
+            else {
+                continue;
+            };
+            let heartbeats = app.state::<TaskManager>().heartbeats();
+            app.state::<VoiceWorkflow>().fail_stalled_tasks(
+                &app.state::<UiEventMailbox>(),
+                &heartbeats,
+                stall_ms as i64,
+            );
+        });
+}
+
+async fn run<R: Runtime>(app: tauri::AppHandle<R>, task: WorkflowTaskRequest) {
+    match task {
+        WorkflowTaskRequest::StopRecordTranscribe {
+            task_id,
+            recording_session_id,
+        } => {
+            let runtime = app.state::<RuntimeState>();
+            let audio = app.state::<RecordingRegistry>();
+            let transcriber = app.state::<TranscriptionService>();
+            let mailbox = app.state::<UiEventMailbox>();
+            let _lane = asr_lanes::global().interactive();
+            run_stop_record_transcribe(
+                &runtime,
+                &audio,
+                &transcriber,
+                &mailbox,
                 task_id,
                 recording_session_id,
-            } => {
-                let runtime = app.state::<RuntimeState>();
-                let audio = app.state::<RecordingRegistry>();
-                let transcriber = app.state::<TranscriptionService>();
-                let mailbox = app.state::<UiEventMailbox>();
-                run_stop_record_transcribe(
-                    &runtime,
-                    &audio,
-                    &transcriber,
-                    &mailbox,
-                    task_id,
-                    recording_session_id,
-                )
-                .await;
-            }
-            WorkflowTaskRequest::Rewrite {
-                task_id,
+            )
+            .await;
+        }
+        WorkflowTaskRequest::Rewrite {
+            task_id,
+            pending_context,
+            req,
+            token,
+        } => {
+            let task_state = app.state::<TaskManager>();
+            let mailbox = app.state::<UiEventMailbox>();
+            let workflow = app.state::<VoiceWorkflow>();
+            mailbox.send(UiEvent::stage(
+                &task_id,
+                TaskStage::Rewrite,
+                UiEventStatus::Started,
+                "llm",
+            ));
+            match rewrite::rewrite_text(
+                &*task_state,
                 pending_context,
                 req,
-            } => {
-                let task_state = app.state::<TaskManager>();
-                let mailbox = app.state::<UiEventMailbox>();
-                let workflow = app.state::<VoiceWorkflow>();
-                mailbox.send(UiEvent::stage(
-                    &task_id,
-                    "Rewrite",
-                    UiEventStatus::Started,
-                    "llm",
-                ));
-                match rewrite::rewrite_text(&task_state, pending_context, req).await {
-                    Ok(result) => {
-                        mailbox.send(UiEvent::stage_with_elapsed(
-                            &task_id,
-                            "Rewrite",
-                            UiEventStatus::Completed,
-                            "ok",
-                            Some(result.rewrite_ms),
-                            None,
-                        ));
-                        if let Err(err) = workflow.report_rewrite_completed(
+                &token,
+                &|_| {},
+                &|_| {},
+            )
+            .await
+            {
+                Ok(result) => {
+                    mailbox.send(UiEvent::stage_with_elapsed(
+                        &task_id,
+                        TaskStage::Rewrite,
+                        UiEventStatus::Completed,
+                        "ok",
+                        Some(result.rewrite_ms),
+                        None,
+                    ));
+                    if let Err(err) = workflow.report_rewrite_completed(
+                        &mailbox,
+                        WorkflowRewriteCompletedRequest {
+                            transcript_id: result.transcript_id.clone(),
+                            text: result.final_text.clone(),
+                            rewrite_ms: result.rewrite_ms,
+                        },
+                    ) {
+                        send_failed(
                             &mailbox,
-                            WorkflowRewriteCompletedRequest {
-                                transcript_id: result.transcript_id.clone(),
-                                text: result.final_text.clone(),
-                                rewrite_ms: result.rewrite_ms,
-                            },
-                        ) {
-                            send_failed(&mailbox, &task_id, "Rewrite", &err.code, err.message);
-                            return;
-                        }
-                        mailbox.send(UiEvent::state_completed(
                             &task_id,
-                            "rewrite.completed",
-                            "rewrite completed",
-                            serde_json::to_value(&result).unwrap_or_default(),
-                        ));
-                    }
-                    Err(err) => {
-                        report_task_failed(
-                            &workflow,
-                            &mailbox,
-                            &task_id,
-                            "Rewrite",
+                            TaskStage::Rewrite,
                             &err.code,
-                            err.message.clone(),
+                            err.message,
                         );
-                        send_failed(&mailbox, &task_id, "Rewrite", &err.code, err.message);
+                        return;
                     }
+                    mailbox.send(UiEvent::state_completed(
+                        &task_id,
+                        UiEventKind::RewriteCompleted,
+                        "rewrite completed",
+                        serde_json::to_value(&result).unwrap_or_default(),
+                    ));
+                }
+                // Cancel already moved the workflow and sent the event.
+                Err(err) if err.code == "E_CANCELLED" => {}
+                Err(err) => {
+                    report_task_failed(
+                        &workflow,
+                        &mailbox,
+                        &task_id,
+                        TaskStage::Rewrite,
+                        &err.code,
+                        err.message.clone(),
+                    );
+                    send_failed(
+                        &mailbox,
+                        &task_id,
+                        TaskStage::Rewrite,
+                        &err.code,
+                        err.message,
+                    );
                 }
             }
-            WorkflowTaskRequest::Insert { task_id, req } => {
-                let mailbox = app.state::<UiEventMailbox>();
-                let workflow = app.state::<VoiceWorkflow>();
-                let transcript_id = req.transcript_id.clone().unwrap_or_else(|| task_id.clone());
-                let inserted_text = req.text.clone();
-                mailbox.send(UiEvent::stage(
-                    &task_id,
-                    "Insert",
-                    UiEventStatus::Started,
-                    "insert",
-                ));
-                match insertion::insert_text(req).await {
-                    Ok(result) => {
-                        mailbox.send(UiEvent::stage(
-                            &task_id,
-                            "Insert",
-                            UiEventStatus::Completed,
-                            "ok",
-                        ));
-                        if let Err(err) = workflow.report_insert_completed(
-                            &mailbox,
-                            WorkflowInsertCompletedRequest {
-                                transcript_id: transcript_id.clone(),
-                                text: inserted_text.clone(),
-                            },
-                        ) {
-                            send_failed(&mailbox, &task_id, "Insert", &err.code, err.message);
-                            return;
-                        }
-                        mailbox.send(UiEvent::state_completed(
-                            &task_id,
-                            "insertion.completed",
-                            "insertion completed",
-                            serde_json::to_value(&result).unwrap_or_default(),
-                        ));
-                    }
-                    Err(err) => {
-                        report_task_failed(
-                            &workflow,
+        }
+        WorkflowTaskRequest::Insert { task_id, req } => {
+            let mailbox = app.state::<UiEventMailbox>();
+            let workflow = app.state::<VoiceWorkflow>();
+            let transcript_id = req.transcript_id.clone().unwrap_or_else(|| task_id.clone());
+            let inserted_text = req.text.clone();
+            mailbox.send(UiEvent::stage(
+                &task_id,
+                TaskStage::Insert,
+                UiEventStatus::Started,
+                "insert",
+            ));
+            match insertion::insert_text(req).await {
+                Ok(result) => {
+                    mailbox.send(UiEvent::stage(
+                        &task_id,
+                        TaskStage::Insert,
+                        UiEventStatus::Completed,
+                        "ok",
+                    ));
+                    if let Err(err) = workflow.report_insert_completed(
+                        &mailbox,
+                        WorkflowInsertCompletedRequest {
+                            transcript_id: transcript_id.clone(),
+                            text: inserted_text.clone(),
+                        },
+                    ) {
+                        send_failed(
                             &mailbox,
                             &task_id,
-                            "Insert",
+                            TaskStage::Insert,
                             &err.code,
-                            err.message.clone(),
+                            err.message,
                         );
-                        send_failed(&mailbox, &task_id, "Insert", &err.code, err.message);
+                        return;
                     }
+                    mailbox.send(UiEvent::state_completed(
+                        &task_id,
+                        UiEventKind::InsertionCompleted,
+                        "insertion completed",
+                        serde_json::to_value(&result).unwrap_or_default(),
+                    ));
+                }
+                Err(err) => {
+                    report_task_failed(
+                        &workflow,
+                        &mailbox,
+                        &task_id,
+                        TaskStage::Insert,
+                        &err.code,
+                        err.message.clone(),
+                    );
+                    send_failed(
+                        &mailbox,
+                        &task_id,
+                        TaskStage::Insert,
+                        &err.code,
+                        err.message,
+                    );
                 }
             }
         }
-    });
+    }
 }
 
 async fn run_stop_record_transcribe(
@@ -154,14 +247,14 @@ async fn run_stop_record_transcribe(
         Ok(RecordingStopOutcome::Completed(asset)) => asset,
         Ok(RecordingStopOutcome::Stale) => return,
         Err(err) => {
-            send_failed(mailbox, &task_id, "Record", &err.code, err.message);
+            send_failed(mailbox, &task_id, TaskStage::Record, &err.code, err.message);
             return;
         }
     };
     let consumed = audio.take_asset(&asset.asset_id).unwrap_or(asset);
     mailbox.send(UiEvent::stage_with_elapsed(
         &task_id,
-        "Record",
+        TaskStage::Record,
         UiEventStatus::Completed,
         "ok",
         Some(consumed.record_elapsed_ms),
@@ -170,13 +263,19 @@ async fn run_stop_record_transcribe(
 
     if let Err(err) = ensure_runtime_ready(runtime) {
         let _ = std::fs::remove_file(&consumed.output_path);
-        send_failed(mailbox, &task_id, "Transcribe", &err.code, err.message);
+        send_failed(
+            mailbox,
+            &task_id,
+            TaskStage::Transcribe,
+            &err.code,
+            err.message,
+        );
         return;
     }
 
     mailbox.send(UiEvent::stage(
         &task_id,
-        "Transcribe",
+        TaskStage::Transcribe,
         UiEventStatus::Started,
         "asr",
     ));
@@ -193,7 +292,7 @@ async fn run_stop_record_transcribe(
             if result.asr_text.trim().is_empty() {
                 mailbox.send(UiEvent::stage_with_elapsed(
                     &result.transcript_id,
-                    "Transcribe",
+                    TaskStage::Transcribe,
                     UiEventStatus::Completed,
                     "empty",
                     Some(result.metrics.asr_ms),
@@ -204,7 +303,7 @@ async fn run_stop_record_transcribe(
             }
             mailbox.send(UiEvent::stage_with_elapsed(
                 &result.transcript_id,
-                "Transcribe",
+                TaskStage::Transcribe,
                 UiEventStatus::Completed,
                 "ok",
                 Some(result.metrics.asr_ms),
@@ -212,7 +311,7 @@ async fn run_stop_record_transcribe(
             ));
             mailbox.send(UiEvent::state_completed(
                 &result.transcript_id,
-                "transcription.completed",
+                UiEventKind::TranscriptionCompleted,
                 "transcription completed",
                 serde_json::to_value(&result).unwrap_or_default(),
             ));
@@ -221,25 +320,37 @@ async fn run_stop_record_transcribe(
         Err(err) if err.code == "E_CANCELLED" => {
             mailbox.send(UiEvent::stage(
                 &task_id,
-                "Transcribe",
+                TaskStage::Transcribe,
                 UiEventStatus::Cancelled,
                 "cancelled",
             ));
-            mailbox.send(UiEvent::state_cancelled(&task_id, "Transcribe"));
+            mailbox.send(UiEvent::state_cancelled(&task_id, TaskStage::Transcribe));
         }
         Err(err) => {
-            send_failed(mailbox, &task_id, "Transcribe", &err.code, err.message);
+            send_failed(
+                mailbox,
+                &task_id,
+                TaskStage::Transcribe,
+                &err.code,
+                err.message,
+            );
         }
     }
 }
 
-fn send_failed(mailbox: &UiEventMailbox, task_id: &str, stage: &str, code: &str, message: String) {
+fn send_failed(
+    mailbox: &UiEventMailbox,
+    task_id: &str,
+    stage: TaskStage,
+    code: &str,
+    message: String,
+) {
     if let Ok(dir) = crate::data_dir::data_dir() {
         obs::event_err(
             &dir,
             obs::ErrorEvent {
                 task_id: Some(task_id),
-                stage,
+                stage: stage.as_str(),
                 step_id: "TASK.failed",
                 kind: "task",
                 code,
@@ -263,7 +374,7 @@ fn report_task_failed(
     workflow: &VoiceWorkflow,
     mailbox: &UiEventMailbox,
     task_id: &str,
-    stage: &str,
+    stage: TaskStage,
     code: &str,
     message: String,
 ) {
@@ -273,10 +384,10 @@ fn report_task_failed(
         message,
     };
     match stage {
-        "Rewrite" => {
+        TaskStage::Rewrite => {
             let _ = workflow.report_rewrite_failed(mailbox, req);
         }
-        "Insert" => {
+        TaskStage::Insert => {
             let _ = workflow.report_insert_failed(mailbox, req);
         }
         _ => {}
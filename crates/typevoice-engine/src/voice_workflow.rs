@@ -1,24 +1,38 @@
-use std::{collections::HashMap, path::Path, sync::Mutex};
+use std::{collections::HashMap, path::Path};
 
 use crate::audio_capture::{RecordingRegistry, RecordingStopOutcome};
 use crate::context_capture;
-use crate::context_pack::ContextSnapshot;
+use crate::context_pack::{self, ContextSnapshot};
+use crate::export_format::ExportFormat;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
 
 use crate::insertion::{InsertResult, InsertTextRequest};
+use crate::obs::debug;
+use crate::obs::lock_metrics::TimedMutex;
 use crate::ports::PortError;
 use crate::record_input_cache::RecordInputCacheState;
 use crate::rewrite::{RewriteResult, RewriteTextRequest};
-use crate::task_manager::TaskManager;
+use crate::task_manager::{IdempotencyClaim, TaskHeartbeats, TaskManager};
 use crate::transcription::{
-    TranscriptionInput, TranscriptionMetrics, TranscriptionResult, TranscriptionService,
+    TextPostprocess, TranscriptionInput, TranscriptionMetrics, TranscriptionResult,
+    TranscriptionService,
 };
 use crate::transcription_actor::{StreamingProviderKind, TranscriptionActor};
-use crate::ui_events::{UiEvent, UiEventMailbox, UiEventStatus};
-use crate::{data_dir, export, history, insertion, pipeline, rewrite, RuntimeState};
+use crate::ui_events::{TaskStage, UiEvent, UiEventKind, UiEventMailbox, UiEventStatus};
+use crate::{
+    data_dir, export, formatting_profiles, guest_mode, history, history_writer, insertion,
+    pipeline, rewrite, settings, sinks, webhook, workspaces, RuntimeState,
+};
 
 pub type WorkflowResult<T> = Result<T, WorkflowError>;
 
+// A busy rejection never promises less than this; each finished task moves the
+// recent RTF this far toward its own.
+const READY_ESTIMATE_FLOOR_MS: u64 = 1_000;
+const RTF_SMOOTHING: f64 = 0.3;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WorkflowPhase {
     Idle,
@@ -48,14 +62,16 @@ impl WorkflowPhase {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct WorkflowCommandRequest {
     pub command: WorkflowCommand,
     pub task_id: Option<String>,
+    // Repeated `primary` with the same key inside the TTL returns the original task.
+    pub idempotency_key: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum WorkflowCommand {
     Primary,
@@ -75,6 +91,7 @@ pub enum WorkflowTaskRequest {
         task_id: String,
         pending_context: Option<ContextSnapshot>,
         req: RewriteTextRequest,
+        token: CancellationToken,
     },
     Insert {
         task_id: String,
@@ -82,6 +99,16 @@ pub enum WorkflowTaskRequest {
     },
 }
 
+impl WorkflowTaskRequest {
+    pub fn task_id(&self) -> &str {
+        match self {
+            Self::StopRecordTranscribe { task_id, .. }
+            | Self::Rewrite { task_id, .. }
+            | Self::Insert { task_id, .. } => task_id,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct WorkflowCommandOutcome {
     pub view: WorkflowView,
@@ -95,14 +122,16 @@ pub struct WorkflowCommandDeps<'a> {
     pub streaming_actor: &'a TranscriptionActor,
     pub mailbox: &'a UiEventMailbox,
     pub record_input_cache: &'a RecordInputCacheState,
+    pub task_state: &'a TaskManager,
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct WorkflowView {
     pub phase: String,
     pub task_id: Option<String>,
     pub recording_session_id: Option<String>,
+    pub background_task_ids: Vec<String>,
     pub last_transcript_id: Option<String>,
     pub last_asr_text: String,
     pub last_text: String,
@@ -114,6 +143,7 @@ pub struct WorkflowView {
     pub can_rewrite: bool,
     pub can_insert: bool,
     pub can_copy: bool,
+    pub replayed_task_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -150,6 +180,13 @@ pub struct WorkflowTaskFailedRequest {
     pub message: String,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowCancelStageRequest {
+    pub task_id: String,
+    pub stage: String,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WorkflowTextCommandRequest {
@@ -162,6 +199,8 @@ pub struct WorkflowRewriteCompletedRequest {
     pub transcript_id: String,
     pub text: String,
     pub rewrite_ms: u128,
+    #[serde(default)]
+    pub template_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -176,6 +215,8 @@ pub struct WorkflowError {
     pub code: String,
     pub message: String,
     pub raw: Option<String>,
+    // Set on busy rejections when the active task's end can be estimated.
+    pub retry_after_ms: Option<u64>,
 }
 
 impl WorkflowError {
@@ -185,11 +226,26 @@ impl WorkflowError {
             code: code.to_string(),
             raw: Some(message.clone()),
             message,
+            retry_after_ms: None,
         }
     }
 
+    fn with_retry_after(mut self, retry_after_ms: Option<u64>) -> Self {
+        self.retry_after_ms = retry_after_ms;
+        self
+    }
+
+    // The UI reads `retry_after_ms=` back out of the rendered string.
     pub fn render(&self) -> String {
-        format!("{}: {}", self.code, self.message)
+        match self.retry_after_ms {
+            Some(ms) => format!(
+                "{}: {} (ready in ~{}s, retry_after_ms={ms})",
+                self.code,
+                self.message,
+                ms.div_ceil(1000)
+            ),
+            None => format!("{}: {}", self.code, self.message),
+        }
     }
 
     fn from_port(err: PortError) -> Self {
@@ -198,6 +254,7 @@ impl WorkflowError {
             code,
             raw: Some(raw.unwrap_or_else(|| message.clone())),
             message,
+            retry_after_ms: None,
         }
     }
 
@@ -226,6 +283,8 @@ pub struct WorkflowSnapshot {
     pub rewrite: Option<RewriteResult>,
     pub last_created_at_ms: Option<i64>,
     pub last_error: Option<WorkflowError>,
+    pub background_rewrites: Vec<String>,
+    pub max_background_rewrites: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -239,6 +298,26 @@ struct WorkflowState {
     insert_previous_phase: Option<WorkflowPhase>,
     applied_event_views: HashMap<String, WorkflowView>,
     last_error: Option<WorkflowError>,
+    // Rewrites detached from the foreground session, keyed by task id.
+    background_rewrites: HashMap<String, BackgroundRewrite>,
+    max_background_rewrites: usize,
+    // Cancels the foreground rewrite's LLM requests; dropped when it detaches.
+    rewrite_token: Option<CancellationToken>,
+    // Feed the ready-in estimate for busy rejections.
+    recording_started_ms: Option<i64>,
+    transcribing_started_ms: Option<i64>,
+    recent_rtf: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+struct BackgroundRewrite {
+    detached_at_ms: i64,
+    // Still cancels the detached rewrite's LLM requests.
+    token: Option<CancellationToken>,
+    // The task's transcript, so a result that lands while the foreground is
+    // free can become the current text again.
+    transcription: Option<TranscriptionResult>,
+    created_at_ms: Option<i64>,
 }
 
 #[derive(Debug, Clone)]
@@ -253,6 +332,7 @@ struct WorkflowActionText {
     asr_text: String,
     final_text: String,
     created_at_ms: Option<i64>,
+    template_id: Option<String>,
 }
 
 impl WorkflowState {
@@ -267,7 +347,45 @@ impl WorkflowState {
             insert_previous_phase: None,
             applied_event_views: HashMap::new(),
             last_error: None,
+            background_rewrites: HashMap::new(),
+            max_background_rewrites: settings::DEFAULT_PIPELINE_MAX_BACKGROUND_REWRITES,
+            rewrite_token: None,
+            recording_started_ms: None,
+            transcribing_started_ms: None,
+            recent_rtf: None,
+        }
+    }
+
+    // When a transcribing task should be done: the recording's length times
+    // the recent ratio of post-recording wait to audio length, minus the time
+    // already spent. None until one task has finished, and outside
+    // Transcribing, where the slot frees on user action instead.
+    fn ready_estimate_ms(&self, now_ms: i64) -> Option<u64> {
+        if self.phase != WorkflowPhase::Transcribing {
+            return None;
         }
+        let (recorded, started) = (self.recording_started_ms?, self.transcribing_started_ms?);
+        let audio_ms = started.saturating_sub(recorded).max(0) as f64;
+        let expected_ms = audio_ms * self.recent_rtf?;
+        let remaining_ms = expected_ms - now_ms.saturating_sub(started) as f64;
+        Some(remaining_ms.max(READY_ESTIMATE_FLOOR_MS as f64) as u64)
+    }
+
+    fn note_transcription_finished(&mut self, now_ms: i64) {
+        let (Some(recorded), Some(started)) =
+            (self.recording_started_ms, self.transcribing_started_ms)
+        else {
+            return;
+        };
+        let audio_ms = started.saturating_sub(recorded);
+        if audio_ms <= 0 {
+            return;
+        }
+        let rtf = now_ms.saturating_sub(started).max(0) as f64 / audio_ms as f64;
+        self.recent_rtf = Some(match self.recent_rtf {
+            Some(prev) => prev * (1.0 - RTF_SMOOTHING) + rtf * RTF_SMOOTHING,
+            None => rtf,
+        });
     }
 
     fn snapshot(&self) -> WorkflowSnapshot {
@@ -278,18 +396,28 @@ impl WorkflowState {
             rewrite: self.rewrite.clone(),
             last_created_at_ms: self.last_created_at_ms,
             last_error: self.last_error.clone(),
+            background_rewrites: {
+                let mut ids: Vec<(i64, &String)> = self
+                    .background_rewrites
+                    .iter()
+                    .map(|(id, b)| (b.detached_at_ms, id))
+                    .collect();
+                ids.sort();
+                ids.into_iter().map(|(_, id)| id.clone()).collect()
+            },
+            max_background_rewrites: self.max_background_rewrites,
         }
     }
 }
 
 pub struct VoiceWorkflow {
-    state: Mutex<WorkflowState>,
+    state: TimedMutex<WorkflowState>,
 }
 
 impl VoiceWorkflow {
     pub fn new() -> Self {
         Self {
-            state: Mutex::new(WorkflowState::idle()),
+            state: TimedMutex::new("VoiceWorkflow.state", WorkflowState::idle()),
         }
     }
 
@@ -346,11 +474,30 @@ impl VoiceWorkflow {
         deps: WorkflowCommandDeps<'_>,
         req: WorkflowCommandRequest,
     ) -> WorkflowResult<WorkflowCommandOutcome> {
-        let result = match req.command {
-            WorkflowCommand::Primary => {
-                self.run_primary(&deps, normalize_optional_task_id(req.task_id)?)
-                    .await
+        let (idempotency_key, primary_task_id) = match req.command {
+            WorkflowCommand::Primary => (
+                normalize_optional_task_id(req.idempotency_key)?,
+                normalize_optional_task_id(req.task_id)?,
+            ),
+            _ => (None, None),
+        };
+        if let Some(key) = idempotency_key.as_deref() {
+            let replayed_task_id = match deps.task_state.claim_idempotency_key(key) {
+                IdempotencyClaim::Claimed => None,
+                IdempotencyClaim::Done(task_id) => Some(Some(task_id)),
+                // The first caller is still starting; report whatever it has settled so far.
+                IdempotencyClaim::InFlight => Some(self.active_task_id_best_effort()),
+            };
+            if let Some(task_id) = replayed_task_id {
+                log_idempotent_replay(task_id.as_deref(), key);
+                let mut view = self.view();
+                view.replayed_task_id = task_id;
+                return Ok(WorkflowCommandOutcome { view, task: None });
             }
+        }
+        let previous_task_id = self.active_task_id_best_effort();
+        let result = match req.command {
+            WorkflowCommand::Primary => self.run_primary(&deps, primary_task_id).await,
             WorkflowCommand::RewriteLast => self.run_rewrite_last().await,
             WorkflowCommand::InsertLast => self.run_insert_last().await,
             WorkflowCommand::CopyLast => self.run_copy_last().map(|()| None),
@@ -364,11 +511,19 @@ impl VoiceWorkflow {
 
         match result {
             Ok(task) => {
+                if let Some(key) = idempotency_key.as_deref() {
+                    let task_id = self.active_task_id_best_effort().or(previous_task_id);
+                    deps.task_state
+                        .finish_idempotency_key(key, task_id.as_deref());
+                }
                 let view = self.view();
                 self.emit_state(deps.mailbox);
                 Ok(WorkflowCommandOutcome { view, task })
             }
             Err(err) => {
+                if let Some(key) = idempotency_key.as_deref() {
+                    deps.task_state.finish_idempotency_key(key, None);
+                }
                 self.remember_error(err.clone());
                 self.emit_state(deps.mailbox);
                 Err(err)
@@ -377,13 +532,21 @@ impl VoiceWorkflow {
     }
 
     pub fn has_active_task(&self) -> bool {
-        matches!(
-            self.phase(),
-            WorkflowPhase::Recording
-                | WorkflowPhase::Transcribing
-                | WorkflowPhase::Rewriting
-                | WorkflowPhase::Inserting
-        )
+        let snapshot = self.snapshot();
+        match snapshot.phase {
+            WorkflowPhase::Recording | WorkflowPhase::Transcribing | WorkflowPhase::Inserting => {
+                true
+            }
+            // A running rewrite can move to the background to make room for the next take.
+            WorkflowPhase::Rewriting => {
+                snapshot.background_rewrites.len() >= snapshot.max_background_rewrites
+            }
+            WorkflowPhase::Idle
+            | WorkflowPhase::Transcribed
+            | WorkflowPhase::Rewritten
+            | WorkflowPhase::Cancelled
+            | WorkflowPhase::Failed => false,
+        }
     }
 
     pub fn active_task_id_best_effort(&self) -> Option<String> {
@@ -424,11 +587,32 @@ impl VoiceWorkflow {
                     self.prepare_stop_record_transcribe().map(Some)
                 }
             }
+            WorkflowPhase::Rewriting => {
+                // ASR resources are free again; let the rewrite finish on its own.
+                let detached = self.detach_rewrite_to_background(load_max_background_rewrites())?;
+                deps.mailbox.send(UiEvent::stage(
+                    &detached,
+                    TaskStage::Rewrite,
+                    UiEventStatus::Started,
+                    "background",
+                ));
+                self.start_record_transcribe(
+                    deps.runtime,
+                    deps.audio,
+                    deps.streaming_actor,
+                    deps.mailbox,
+                    deps.record_input_cache,
+                    task_id,
+                )?;
+                Ok(None)
+            }
             WorkflowPhase::Transcribing
             | WorkflowPhase::Transcribed
-            | WorkflowPhase::Rewriting
             | WorkflowPhase::Rewritten
-            | WorkflowPhase::Inserting => Err(primary_phase_error(snapshot.phase)),
+            | WorkflowPhase::Inserting => {
+                let ready = self.state.lock().unwrap().ready_estimate_ms(now_ms());
+                Err(primary_phase_error(snapshot.phase).with_retry_after(ready))
+            }
         }
     }
 
@@ -437,13 +621,16 @@ impl VoiceWorkflow {
         let req = RewriteTextRequest {
             transcript_id: current.transcript_id.clone(),
             text: current.final_text,
+            template_id: load_rewrite_template_id(),
         };
-        self.begin_rewrite(&current.transcript_id)?;
+        self.set_max_background_rewrites(load_max_background_rewrites());
+        let token = self.begin_rewrite(&current.transcript_id)?;
         let pending_context = self.take_pending_context(&current.transcript_id);
         Ok(Some(WorkflowTaskRequest::Rewrite {
             task_id: current.transcript_id,
             pending_context,
             req,
+            token,
         }))
     }
 
@@ -452,6 +639,7 @@ impl VoiceWorkflow {
         let req = InsertTextRequest {
             transcript_id: Some(current.transcript_id.clone()),
             text: current.final_text,
+            template_id: current.template_id,
         };
         self.begin_insert(&current.transcript_id)?;
         Ok(Some(WorkflowTaskRequest::Insert {
@@ -461,11 +649,39 @@ impl VoiceWorkflow {
     }
 
     fn run_copy_last(&self) -> WorkflowResult<()> {
-        let last = self.current_action_text()?;
-        export::copy_text_to_clipboard(&last.final_text)
+        let s = data_dir::data_dir()
+            .ok()
+            .and_then(|dir| settings::load_settings(&dir).ok())
+            .unwrap_or_default();
+        let (text, format) = self.copy_last_payload(&s)?;
+        export::copy_text_to_clipboard_as(&text, format)
             .map_err(|err| WorkflowError::new(&err.code, err.message))
     }
 
+    // Copy-last honours the same per-template export targets as insert.
+    fn copy_last_payload(&self, s: &settings::Settings) -> WorkflowResult<(String, ExportFormat)> {
+        let last = self.current_action_text()?;
+        let template_id = last.template_id.as_deref();
+        if !settings::export_target_allowed(s, template_id, settings::ExportTarget::Clipboard) {
+            return Err(WorkflowError::new(
+                "E_EXPORT_TARGET_NOT_ALLOWED",
+                "the template does not allow copying or pasting its text",
+            ));
+        }
+        Ok((
+            last.final_text,
+            insertion::resolve_export_format(s, template_id),
+        ))
+    }
+
+    // Template of the current text when `transcript_id` still names it.
+    pub fn text_template_id(&self, transcript_id: &str) -> Option<String> {
+        self.current_action_text()
+            .ok()
+            .filter(|current| current.transcript_id == transcript_id.trim())
+            .and_then(|current| current.template_id)
+    }
+
     fn run_cancel(
         &self,
         audio: &RecordingRegistry,
@@ -491,7 +707,7 @@ impl VoiceWorkflow {
         self.reserve_recording(&transcript_id)?;
         mailbox.send(UiEvent::stage(
             &transcript_id,
-            "Record",
+            TaskStage::Record,
             UiEventStatus::Started,
             "recording",
         ));
@@ -499,8 +715,13 @@ impl VoiceWorkflow {
         let streaming_config = match streaming_actor.session_config_for_current_settings() {
             Ok(v) => v,
             Err(e) => {
-                let workflow_err =
-                    WorkflowError::new("E_STREAMING_TRANSCRIBE_CONFIG", e.to_string());
+                let message = e.to_string();
+                let code = if message.starts_with("E_OFFLINE_MODE") {
+                    "E_OFFLINE_MODE"
+                } else {
+                    "E_STREAMING_TRANSCRIBE_CONFIG"
+                };
+                let workflow_err = WorkflowError::new(code, message);
                 self.mark_failed(workflow_err.clone());
                 return Err(workflow_err);
             }
@@ -515,11 +736,18 @@ impl VoiceWorkflow {
                 return Err(workflow_err);
             }
         }
+        // A remote live preview feeds the actor too, but the task still stops
+        // through the batch path; losing the preview never fails the recording.
+        let live_preview = streaming_config.remote_preview.is_some()
+            && streaming_actor
+                .start_session(&transcript_id, streaming_config.clone())
+                .is_ok();
+        let feed_actor = streaming_enabled || live_preview;
 
         match audio.start_recording(
             mailbox,
-            streaming_enabled.then_some(streaming_actor),
-            streaming_enabled.then_some(streaming_config),
+            feed_actor.then_some(streaming_actor),
+            feed_actor.then_some(streaming_config),
             record_input_cache,
             Some(transcript_id.clone()),
         ) {
@@ -538,7 +766,7 @@ impl VoiceWorkflow {
                 self.mark_failed(workflow_err.clone());
                 mailbox.send(UiEvent::stage_with_elapsed(
                     &transcript_id,
-                    "Record",
+                    TaskStage::Record,
                     UiEventStatus::Failed,
                     workflow_err.message.clone(),
                     None,
@@ -549,6 +777,34 @@ impl VoiceWorkflow {
         }
     }
 
+    // Stops the recording a VAD detector ended, the same way `primary` would. A
+    // recording that already stopped or was replaced is left alone.
+    pub fn auto_stop_recording(
+        &self,
+        audio: &RecordingRegistry,
+        mailbox: &UiEventMailbox,
+        recording_session_id: &str,
+    ) -> WorkflowResult<Option<WorkflowTaskRequest>> {
+        let snapshot = self.snapshot();
+        let session = snapshot
+            .session
+            .as_ref()
+            .filter(|session| {
+                snapshot.phase == WorkflowPhase::Recording
+                    && session.recording_session_id == recording_session_id
+            })
+            .ok_or_else(|| {
+                WorkflowError::new("E_WORKFLOW_AUTO_STOP_STALE", "recording already stopped")
+            })?;
+        if session.streaming_transcription {
+            self.stop_streaming_record_transcribe(audio, mailbox)?;
+            return Ok(None);
+        }
+        let task = self.prepare_stop_record_transcribe()?;
+        self.emit_state(mailbox);
+        Ok(Some(task))
+    }
+
     pub fn prepare_stop_record_transcribe(&self) -> WorkflowResult<WorkflowTaskRequest> {
         let session = self.begin_transcribing_current()?;
         Ok(WorkflowTaskRequest::StopRecordTranscribe {
@@ -572,7 +828,7 @@ impl VoiceWorkflow {
                 self.mark_failed(workflow_err.clone());
                 mailbox.send(UiEvent::stage_with_elapsed(
                     session.session_id,
-                    "Record",
+                    TaskStage::Record,
                     UiEventStatus::Failed,
                     workflow_err.message.clone(),
                     None,
@@ -587,7 +843,7 @@ impl VoiceWorkflow {
         }
         mailbox.send(UiEvent::stage_with_elapsed(
             &session.session_id,
-            "Record",
+            TaskStage::Record,
             UiEventStatus::Completed,
             "ok",
             Some(consumed.record_elapsed_ms),
@@ -613,7 +869,7 @@ impl VoiceWorkflow {
                 self.mark_failed(workflow_err.clone());
                 mailbox.send(UiEvent::stage_with_elapsed(
                     session.session_id,
-                    "Record",
+                    TaskStage::Record,
                     UiEventStatus::Failed,
                     workflow_err.message.clone(),
                     None,
@@ -625,7 +881,7 @@ impl VoiceWorkflow {
         let consumed = audio.take_asset(&asset.asset_id).unwrap_or(asset);
         mailbox.send(UiEvent::stage_with_elapsed(
             &session.session_id,
-            "Record",
+            TaskStage::Record,
             UiEventStatus::Completed,
             "ok",
             Some(consumed.record_elapsed_ms),
@@ -636,7 +892,7 @@ impl VoiceWorkflow {
             self.mark_failed(err.clone());
             mailbox.send(UiEvent::stage_with_elapsed(
                 session.session_id,
-                "Transcribe",
+                TaskStage::Transcribe,
                 UiEventStatus::Failed,
                 err.message.clone(),
                 None,
@@ -647,7 +903,7 @@ impl VoiceWorkflow {
 
         mailbox.send(UiEvent::stage(
             &session.session_id,
-            "Transcribe",
+            TaskStage::Transcribe,
             UiEventStatus::Started,
             "asr",
         ));
@@ -667,7 +923,7 @@ impl VoiceWorkflow {
                 self.emit_state(mailbox);
                 mailbox.send(UiEvent::stage(
                     &session.session_id,
-                    "Transcribe",
+                    TaskStage::Transcribe,
                     UiEventStatus::Completed,
                     "empty",
                 ));
@@ -679,7 +935,7 @@ impl VoiceWorkflow {
                 self.mark_failed(workflow_err.clone());
                 mailbox.send(UiEvent::stage_with_elapsed(
                     &session.session_id,
-                    "Transcribe",
+                    TaskStage::Transcribe,
                     UiEventStatus::Failed,
                     workflow_err.message.clone(),
                     None,
@@ -693,7 +949,7 @@ impl VoiceWorkflow {
             self.emit_state(mailbox);
             mailbox.send(UiEvent::stage_with_elapsed(
                 &result.transcript_id,
-                "Transcribe",
+                TaskStage::Transcribe,
                 UiEventStatus::Completed,
                 "empty",
                 Some(result.metrics.asr_ms),
@@ -704,7 +960,7 @@ impl VoiceWorkflow {
         }
         mailbox.send(UiEvent::stage_with_elapsed(
             &result.transcript_id,
-            "Transcribe",
+            TaskStage::Transcribe,
             UiEventStatus::Completed,
             "ok",
             Some(result.metrics.asr_ms),
@@ -712,14 +968,14 @@ impl VoiceWorkflow {
         ));
         mailbox.send(UiEvent::completed(
             &result.transcript_id,
-            "transcription.completed",
+            UiEventKind::TranscriptionCompleted,
             "transcription completed",
             serde_json::to_value(&result).unwrap_or_default(),
         ));
         Ok(Some(result))
     }
 
-    pub fn report_asr_completed(
+    pub async fn report_asr_completed(
         &self,
         mailbox: &UiEventMailbox,
         req: WorkflowAsrCompletedRequest,
@@ -739,10 +995,11 @@ impl VoiceWorkflow {
                 },
             );
         }
-        let result = TranscriptionResult::new(transcript_id, req.text, req.metrics);
+        let mut result = TranscriptionResult::new(transcript_id, req.text, req.metrics);
+        apply_dictation_postprocess(&mut result).await;
         let should_persist = self.report_completed_transcription_state(result.clone())?;
         if should_persist {
-            self.persist_transcription_result(&result)?;
+            self.persist_transcription_result(mailbox, &result);
         }
         let view = self.view();
         self.emit_state(mailbox);
@@ -835,7 +1092,7 @@ impl VoiceWorkflow {
                 let _ = streaming_actor.cancel_session(&session.session_id);
                 mailbox.send(UiEvent::stage(
                     session.session_id,
-                    "Record",
+                    TaskStage::Record,
                     UiEventStatus::Cancelled,
                     "cancelled",
                 ));
@@ -853,23 +1110,102 @@ impl VoiceWorkflow {
                 self.emit_state(mailbox);
                 mailbox.send(UiEvent::stage(
                     session.session_id,
-                    "Transcribe",
+                    TaskStage::Transcribe,
+                    UiEventStatus::Cancelled,
+                    "cancelled",
+                ));
+                Ok(())
+            }
+            WorkflowPhase::Rewriting => {
+                let task_id = self.cancel_current_rewrite()?;
+                self.emit_state(mailbox);
+                mailbox.send(UiEvent::stage(
+                    task_id,
+                    TaskStage::Rewrite,
                     UiEventStatus::Cancelled,
                     "cancelled",
                 ));
                 Ok(())
             }
+            WorkflowPhase::Inserting => Err(cancel_phase_error(snapshot.phase)),
+            // Nothing runs in the foreground; cancel reaches detached rewrites.
             WorkflowPhase::Idle
             | WorkflowPhase::Transcribed
             | WorkflowPhase::Rewritten
             | WorkflowPhase::Cancelled
-            | WorkflowPhase::Failed => Err(cancel_phase_error(snapshot.phase)),
-            WorkflowPhase::Rewriting | WorkflowPhase::Inserting => {
-                Err(cancel_phase_error(snapshot.phase))
+            | WorkflowPhase::Failed => {
+                let cancelled: Vec<String> = self
+                    .background_rewrite_ids()
+                    .into_iter()
+                    .filter(|id| self.cancel_background_rewrite(id))
+                    .collect();
+                if cancelled.is_empty() {
+                    return Err(cancel_phase_error(snapshot.phase));
+                }
+                self.emit_state(mailbox);
+                for task_id in cancelled {
+                    mailbox.send(UiEvent::stage(
+                        task_id,
+                        TaskStage::Rewrite,
+                        UiEventStatus::Cancelled,
+                        "background rewrite cancelled",
+                    ));
+                }
+                Ok(())
             }
         }
     }
 
+    // Cancels one stage and lets the task finish without it. Only the rewrite
+    // can be dropped this way: the transcript it would have replaced is kept.
+    pub fn cancel_stage(
+        &self,
+        mailbox: &UiEventMailbox,
+        req: WorkflowCancelStageRequest,
+    ) -> WorkflowResult<WorkflowView> {
+        let task_id = req.task_id.trim();
+        if task_id.is_empty() {
+            return Err(WorkflowError::new(
+                "E_WORKFLOW_TASK_ID_MISSING",
+                "task_id is required",
+            ));
+        }
+        if req.stage != "Rewrite" {
+            return Err(WorkflowError::new(
+                "E_WORKFLOW_CANCEL_STAGE_UNSUPPORTED",
+                format!("stage {} cannot be cancelled on its own", req.stage),
+            ));
+        }
+        if self.cancel_background_rewrite(task_id) {
+            let view = self.view();
+            self.emit_state(mailbox);
+            mailbox.send(UiEvent::stage(
+                task_id,
+                TaskStage::Rewrite,
+                UiEventStatus::Cancelled,
+                "background rewrite cancelled; transcript kept",
+            ));
+            return Ok(view);
+        }
+        let result = self.skip_current_rewrite(task_id)?;
+        self.persist_rewrite_result(mailbox, &result);
+        let view = self.view();
+        self.emit_state(mailbox);
+        mailbox.send(UiEvent::stage(
+            task_id,
+            TaskStage::Rewrite,
+            UiEventStatus::Cancelled,
+            "transcript kept",
+        ));
+        mailbox.send(UiEvent::completed(
+            task_id,
+            UiEventKind::RewriteCompleted,
+            "rewrite cancelled; transcript kept",
+            serde_json::to_value(&result).unwrap_or_default(),
+        ));
+        Ok(view)
+    }
+
     pub async fn rewrite_text(
         &self,
         mailbox: &UiEventMailbox,
@@ -883,23 +1219,64 @@ impl VoiceWorkflow {
                 "transcript_id is required",
             ));
         }
-        self.begin_rewrite(&transcript_id)?;
+        self.set_max_background_rewrites(load_max_background_rewrites());
+        let token = self.begin_rewrite(&transcript_id)?;
         self.emit_state(mailbox);
         let pending_context = self.take_pending_context(&transcript_id);
         mailbox.send(UiEvent::stage(
             &transcript_id,
-            "Rewrite",
+            TaskStage::Rewrite,
             UiEventStatus::Started,
             "llm",
         ));
-        let result = match rewrite::rewrite_text(task_state, pending_context, req).await {
+        let on_budget = |budget: &rewrite::RewriteTokenBudget| {
+            let mut event = UiEvent::stage(
+                &transcript_id,
+                TaskStage::RewriteBudget,
+                UiEventStatus::Completed,
+                budget.label(),
+            );
+            event.payload = serde_json::to_value(budget).ok();
+            mailbox.send(event);
+        };
+        let on_chunk = |progress: rewrite::RewriteChunkProgress| {
+            let status = if progress.done {
+                UiEventStatus::Completed
+            } else {
+                UiEventStatus::Started
+            };
+            mailbox.send(UiEvent::stage_with_elapsed(
+                &transcript_id,
+                TaskStage::RewriteChunk,
+                status,
+                progress.label(),
+                progress.elapsed_ms,
+                None,
+            ));
+        };
+        let result = match rewrite::rewrite_text(
+            task_state,
+            pending_context,
+            req,
+            &token,
+            &on_budget,
+            &on_chunk,
+        )
+        .await
+        {
             Ok(result) => result,
+            Err(err) if err.code == "E_CANCELLED" => {
+                // The cancel command already moved the workflow and sent the event.
+                return Err(WorkflowError::from_port(err));
+            }
             Err(err) => {
                 let workflow_err = WorkflowError::from_port(err);
-                self.mark_failed(workflow_err.clone());
+                if !self.finish_background_rewrite(&transcript_id) {
+                    self.mark_failed(workflow_err.clone());
+                }
                 mailbox.send(UiEvent::stage_with_elapsed(
                     &transcript_id,
-                    "Rewrite",
+                    TaskStage::Rewrite,
                     UiEventStatus::Failed,
                     workflow_err.message.clone(),
                     None,
@@ -909,19 +1286,19 @@ impl VoiceWorkflow {
             }
         };
         self.complete_rewrite(result.clone())?;
-        self.persist_rewrite_result(&result)?;
+        self.persist_rewrite_result(mailbox, &result);
         self.emit_state(mailbox);
         mailbox.send(UiEvent::stage_with_elapsed(
             &transcript_id,
-            "Rewrite",
+            TaskStage::Rewrite,
             UiEventStatus::Completed,
             "ok",
             Some(result.rewrite_ms),
-            None,
+            result.output_error_code.clone(),
         ));
         mailbox.send(UiEvent::completed(
             &transcript_id,
-            "rewrite.completed",
+            UiEventKind::RewriteCompleted,
             "rewrite completed",
             serde_json::to_value(&result).unwrap_or_default(),
         ));
@@ -960,9 +1337,11 @@ impl VoiceWorkflow {
             transcript_id,
             final_text: req.text,
             rewrite_ms: req.rewrite_ms,
+            output_error_code: None,
+            template_id: req.template_id,
         };
         self.complete_rewrite(result.clone())?;
-        self.persist_rewrite_result(&result)?;
+        self.persist_rewrite_result(mailbox, &result);
         let view = self.view();
         self.emit_state(mailbox);
         Ok(view)
@@ -973,6 +1352,12 @@ impl VoiceWorkflow {
         mailbox: &UiEventMailbox,
         req: WorkflowTaskFailedRequest,
     ) -> WorkflowResult<WorkflowView> {
+        if self.finish_background_rewrite(&req.transcript_id) {
+            // The foreground session has moved on; the task's own Failed event is enough.
+            let view = self.view();
+            self.emit_state(mailbox);
+            return Ok(view);
+        }
         self.ensure_rewriting_task(&req.transcript_id)?;
         let code = req.code.trim();
         if code.is_empty() {
@@ -998,7 +1383,7 @@ impl VoiceWorkflow {
     pub async fn insert_text_after_focus(
         &self,
         mailbox: &UiEventMailbox,
-        req: InsertTextRequest,
+        mut req: InsertTextRequest,
         target_hwnd: Option<isize>,
     ) -> WorkflowResult<InsertResult> {
         let transcript_id = req
@@ -1019,12 +1404,17 @@ impl VoiceWorkflow {
                 "text is required",
             ));
         }
+        // Callers that do not know the template insert the current text under
+        // the one it was rewritten with.
+        if req.template_id.is_none() {
+            req.template_id = self.text_template_id(&transcript_id);
+        }
         self.begin_insert(&transcript_id)?;
         self.emit_state(mailbox);
         let event_task_id = transcript_id.as_str();
         mailbox.send(UiEvent::stage(
             event_task_id,
-            "Insert",
+            TaskStage::Insert,
             UiEventStatus::Started,
             "insert",
         ));
@@ -1035,7 +1425,7 @@ impl VoiceWorkflow {
                 self.mark_failed(workflow_err.clone());
                 mailbox.send(UiEvent::stage_with_elapsed(
                     event_task_id,
-                    "Insert",
+                    TaskStage::Insert,
                     UiEventStatus::Failed,
                     workflow_err.message.clone(),
                     None,
@@ -1044,18 +1434,24 @@ impl VoiceWorkflow {
                 return Err(workflow_err);
             }
         };
-        self.persist_inserted_text(&transcript_id, &req.text)?;
+        self.persist_inserted_text(mailbox, &transcript_id, &req.text);
+        self.export_to_sinks(
+            mailbox,
+            &transcript_id,
+            &req.text,
+            req.template_id.as_deref(),
+        );
         self.complete_insert()?;
         self.emit_state(mailbox);
         mailbox.send(UiEvent::stage(
             event_task_id,
-            "Insert",
+            TaskStage::Insert,
             UiEventStatus::Completed,
             "ok",
         ));
         mailbox.send(UiEvent::completed(
             event_task_id,
-            "insertion.completed",
+            UiEventKind::InsertionCompleted,
             "insertion completed",
             serde_json::to_value(&result).unwrap_or_default(),
         ));
@@ -1091,7 +1487,9 @@ impl VoiceWorkflow {
             ));
         }
         self.ensure_inserting_task(&transcript_id)?;
-        self.persist_inserted_text(&transcript_id, &req.text)?;
+        self.persist_inserted_text(mailbox, &transcript_id, &req.text);
+        let template_id = self.text_template_id(&transcript_id);
+        self.export_to_sinks(mailbox, &transcript_id, &req.text, template_id.as_deref());
         self.complete_insert()?;
         let view = self.view();
         self.emit_state(mailbox);
@@ -1117,6 +1515,86 @@ impl VoiceWorkflow {
         Ok(view)
     }
 
+    // Run by the stall watchdog. A runner that dies without reporting back
+    // leaves the workflow in a busy phase forever; once its task has not beaten
+    // for `stall_ms`, the foreground task is failed and a detached rewrite is
+    // dropped, releasing the slot. Recording is left alone: its length is the
+    // user's call. Returns the ids that were given up on.
+    pub fn fail_stalled_tasks(
+        &self,
+        mailbox: &UiEventMailbox,
+        heartbeats: &TaskHeartbeats,
+        stall_ms: i64,
+    ) -> Vec<String> {
+        let now = now_ms();
+        let (phase, foreground, background) = {
+            let state = self.state.lock().unwrap();
+            let foreground = state
+                .session
+                .as_ref()
+                .map(|session| session.session_id.clone())
+                .filter(|_| stalled_phase_stage(state.phase).is_some());
+            let background: Vec<String> = state.background_rewrites.keys().cloned().collect();
+            (state.phase, foreground, background)
+        };
+        let mut live: Vec<&str> = background.iter().map(String::as_str).collect();
+        live.extend(foreground.as_deref());
+        heartbeats.retain(&live);
+
+        let mut stalled = Vec::new();
+        for task_id in background {
+            if heartbeats.idle_ms(&task_id, now) >= stall_ms
+                && self.cancel_background_rewrite(&task_id)
+            {
+                let err = stalled_task_error(stall_ms);
+                log_workflow_error(Some(&task_id), "WF.task_stalled", &err);
+                mailbox.send(UiEvent::state_failed(
+                    &task_id,
+                    TaskStage::Rewrite,
+                    &err.code,
+                    &err.message,
+                ));
+                stalled.push(task_id);
+            }
+        }
+
+        let Some(task_id) = foreground else {
+            return stalled;
+        };
+        if heartbeats.idle_ms(&task_id, now) < stall_ms {
+            return stalled;
+        }
+        let err = stalled_task_error(stall_ms);
+        {
+            let mut state = self.state.lock().unwrap();
+            // The task may have finished while the heartbeats were checked.
+            let same_task = state.phase == phase
+                && state
+                    .session
+                    .as_ref()
+                    .is_some_and(|session| session.session_id == task_id);
+            if !same_task {
+                return stalled;
+            }
+            if let Some(token) = state.rewrite_token.take() {
+                token.cancel();
+            }
+            state.phase = WorkflowPhase::Failed;
+            state.insert_previous_phase = None;
+            state.last_error = Some(err.clone());
+        }
+        log_workflow_error(Some(&task_id), "WF.task_stalled", &err);
+        self.emit_state(mailbox);
+        mailbox.send(UiEvent::state_failed(
+            &task_id,
+            stalled_phase_stage(phase).unwrap_or(TaskStage::Workflow),
+            &err.code,
+            &err.message,
+        ));
+        stalled.push(task_id);
+        stalled
+    }
+
     pub fn abort_pending_task(&self, task_id: &str) -> bool {
         self.take_pending_context(task_id).is_some()
     }
@@ -1130,10 +1608,12 @@ impl VoiceWorkflow {
     ) -> WorkflowResult<String> {
         self.cleanup_orphan_pending_contexts(60_000);
         if self.has_active_task() {
+            let ready = self.state.lock().unwrap().ready_estimate_ms(now_ms());
             return Err(WorkflowError::new(
                 "E_WORKFLOW_BUSY",
                 "workflow already has an active session",
-            ));
+            )
+            .with_retry_after(ready));
         }
         let task_id = uuid::Uuid::new_v4().to_string();
         if capture_required {
@@ -1164,6 +1644,8 @@ impl VoiceWorkflow {
             }
         });
         let last = last_result_from_snapshot(&snapshot);
+        let background_full =
+            snapshot.background_rewrites.len() >= snapshot.max_background_rewrites;
         let diagnostic_code = snapshot.last_error.as_ref().map(|err| err.code.clone());
         let diagnostic_line = snapshot
             .last_error
@@ -1190,6 +1672,7 @@ impl VoiceWorkflow {
             phase: phase.as_str().to_string(),
             task_id,
             recording_session_id,
+            background_task_ids: snapshot.background_rewrites.clone(),
             last_transcript_id: last.as_ref().map(|result| result.transcript_id.clone()),
             last_asr_text: last
                 .as_ref()
@@ -1203,17 +1686,21 @@ impl VoiceWorkflow {
             diagnostic_code,
             diagnostic_line,
             primary_label: primary_label(phase).to_string(),
-            primary_disabled: matches!(
-                phase,
+            primary_disabled: match phase {
+                WorkflowPhase::Rewriting => background_full,
                 WorkflowPhase::Transcribing
-                    | WorkflowPhase::Transcribed
-                    | WorkflowPhase::Rewriting
-                    | WorkflowPhase::Rewritten
-                    | WorkflowPhase::Inserting
-            ),
+                | WorkflowPhase::Transcribed
+                | WorkflowPhase::Rewritten
+                | WorkflowPhase::Inserting => true,
+                WorkflowPhase::Idle
+                | WorkflowPhase::Recording
+                | WorkflowPhase::Cancelled
+                | WorkflowPhase::Failed => false,
+            },
             can_rewrite: has_asr && !active,
             can_insert: has_text && !active,
             can_copy: has_text,
+            replayed_task_id: None,
         }
     }
 
@@ -1245,6 +1732,7 @@ impl VoiceWorkflow {
         Ok(RewriteTextRequest {
             transcript_id: current.transcript_id,
             text: req.text,
+            template_id: load_rewrite_template_id(),
         })
     }
 
@@ -1262,48 +1750,158 @@ impl VoiceWorkflow {
         Ok(InsertTextRequest {
             transcript_id: Some(current.transcript_id),
             text: req.text,
+            template_id: current.template_id,
         })
     }
 
-    fn persist_transcription_result(&self, result: &TranscriptionResult) -> WorkflowResult<()> {
-        let dir = data_dir::data_dir()
-            .map_err(|e| WorkflowError::from_message("E_DATA_DIR", e.to_string()))?;
-        history::append(
-            &dir.join("history.sqlite3"),
-            &history::HistoryItem {
+    // History writes run on the background writer; the text is already in the
+    // workflow state, so a failed write becomes a warning, not a task failure.
+    fn persist_history(&self, mailbox: &UiEventMailbox, op: history_writer::HistoryOp) {
+        persist_history_op(mailbox, op);
+    }
+
+    fn persist_transcription_result(&self, mailbox: &UiEventMailbox, result: &TranscriptionResult) {
+        let (target_app, target_title_hash) = self.pending_target(&result.transcript_id);
+        self.persist_history(
+            mailbox,
+            history_writer::HistoryOp::Append {
+                item: Box::new(history::HistoryItem {
+                    task_id: result.transcript_id.clone(),
+                    created_at_ms: now_ms(),
+                    asr_text: result.asr_text.clone(),
+                    rewritten_text: String::new(),
+                    inserted_text: String::new(),
+                    final_text: result.final_text.clone(),
+                    template_id: None,
+                    rtf: result.metrics.rtf,
+                    device_used: result.metrics.device_used.clone(),
+                    preprocess_ms: result.metrics.preprocess_ms as i64,
+                    asr_ms: result.metrics.asr_ms as i64,
+                    target_app,
+                    target_title_hash,
+                    tags: Vec::new(),
+                    note: String::new(),
+                }),
+            },
+        );
+        self.persist_retained_recording(mailbox, &result.transcript_id);
+    }
+
+    // Kept task audio is linked from its history row. Other formats than WAV
+    // are transcoded on a background thread queued behind the append; if that
+    // fails the WAV stays and the row points at it.
+    fn persist_retained_recording(&self, mailbox: &UiEventMailbox, task_id: &str) {
+        let Ok(dir) = data_dir::data_dir() else {
+            return;
+        };
+        let Some(kept) = pipeline::retained_recording(&dir, task_id) else {
+            return;
+        };
+        let format = settings::load_settings(&dir)
+            .map(|s| settings::resolve_recording_format(&s))
+            .unwrap_or(settings::DEFAULT_RECORDING_FORMAT);
+        if kept.extension().is_none_or(|e| e != "wav") || format == "wav" {
+            self.persist_history(
+                mailbox,
+                history_writer::HistoryOp::UpdateRecordingPath {
+                    task_id: task_id.to_string(),
+                    recording_path: kept.display().to_string(),
+                },
+            );
+            return;
+        }
+        let mailbox = mailbox.clone();
+        let task_id = task_id.to_string();
+        std::thread::spawn(move || {
+            let path = match pipeline::transcode_retained_recording(&dir, &task_id, &kept, format) {
+                Ok(path) => path,
+                Err(e) => {
+                    mailbox.send(UiEvent::warning(
+                        Some(task_id.clone()),
+                        TaskStage::Retain,
+                        "E_RECORDING_TRANSCODE_FAILED",
+                        format!("recording kept as WAV: {e}"),
+                    ));
+                    kept
+                }
+            };
+            persist_history_op(
+                &mailbox,
+                history_writer::HistoryOp::UpdateRecordingPath {
+                    task_id,
+                    recording_path: path.display().to_string(),
+                },
+            );
+        });
+    }
+
+    fn persist_rewrite_result(&self, mailbox: &UiEventMailbox, result: &RewriteResult) {
+        self.persist_history(
+            mailbox,
+            history_writer::HistoryOp::UpdateFinalText {
                 task_id: result.transcript_id.clone(),
-                created_at_ms: now_ms(),
-                asr_text: result.asr_text.clone(),
-                rewritten_text: String::new(),
-                inserted_text: String::new(),
-                final_text: result.asr_text.clone(),
-                template_id: None,
-                rtf: result.metrics.rtf,
-                device_used: result.metrics.device_used.clone(),
-                preprocess_ms: result.metrics.preprocess_ms as i64,
-                asr_ms: result.metrics.asr_ms as i64,
+                final_text: result.final_text.clone(),
+                template_id: result.template_id.clone(),
             },
-        )
-        .map_err(|e| WorkflowError::from_message("E_HISTORY_APPEND", e.to_string()))
+        );
     }
 
-    fn persist_rewrite_result(&self, result: &RewriteResult) -> WorkflowResult<()> {
-        let dir = data_dir::data_dir()
-            .map_err(|e| WorkflowError::from_message("E_DATA_DIR", e.to_string()))?;
-        history::update_final_text(
-            &dir.join("history.sqlite3"),
-            &result.transcript_id,
-            &result.final_text,
-            None,
-        )
-        .map_err(|e| WorkflowError::from_message("E_HISTORY_UPDATE", e.to_string()))
+    fn persist_inserted_text(&self, mailbox: &UiEventMailbox, transcript_id: &str, text: &str) {
+        self.persist_history(
+            mailbox,
+            history_writer::HistoryOp::UpdateInsertedText {
+                task_id: transcript_id.to_string(),
+                inserted_text: text.to_string(),
+            },
+        );
     }
 
-    fn persist_inserted_text(&self, transcript_id: &str, text: &str) -> WorkflowResult<()> {
-        let dir = data_dir::data_dir()
-            .map_err(|e| WorkflowError::from_message("E_DATA_DIR", e.to_string()))?;
-        history::update_inserted_text(&dir.join("history.sqlite3"), transcript_id, text)
-            .map_err(|e| WorkflowError::from_message("E_HISTORY_UPDATE", e.to_string()))
+    // Sinks run after the history write is queued, off the workflow thread so a
+    // slow or unplugged vault drive or webhook never delays the next dictation.
+    fn export_to_sinks(
+        &self,
+        mailbox: &UiEventMailbox,
+        transcript_id: &str,
+        text: &str,
+        template_id: Option<&str>,
+    ) {
+        let Ok(dir) = data_dir::data_dir() else {
+            return;
+        };
+        if debug::verbose_enabled() {
+            let _ = debug::write_payload_best_effort(
+                &dir,
+                transcript_id,
+                "final_text.txt",
+                text.as_bytes().to_vec(),
+            );
+        }
+        // A guest's text must not land in the owner's notes or automations.
+        if guest_mode::enabled() {
+            return;
+        }
+        let s = settings::load_settings(&dir).unwrap_or_default();
+        let allowed = |target| settings::export_target_allowed(&s, template_id, target);
+        if settings::resolve_webhook(&s).is_some() && allowed(settings::ExportTarget::Webhook) {
+            spawn_webhook_delivery(mailbox.clone(), dir.clone(), transcript_id.to_string());
+        }
+        if settings::resolve_markdown_notes(&s, template_id).is_none()
+            || !allowed(settings::ExportTarget::MarkdownNotes)
+        {
+            return;
+        }
+        let entry = sinks::CompletedDictation::now(transcript_id, text, template_id);
+        let mailbox = mailbox.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = sinks::markdown_notes::export(&dir, &s, &entry) {
+                mailbox.send(UiEvent::warning(
+                    Some(entry.task_id.clone()),
+                    TaskStage::Sink,
+                    e.code,
+                    format!("Markdown note not written: {}", e.message),
+                ));
+            }
+        });
     }
 
     fn remember_error(&self, err: WorkflowError) {
@@ -1432,6 +2030,7 @@ impl VoiceWorkflow {
             streaming_transcription: true,
         };
         state.phase = WorkflowPhase::Recording;
+        state.recording_started_ms = Some(now_ms());
         state.session = Some(session.clone());
         state.transcription = None;
         state.rewrite = None;
@@ -1446,9 +2045,11 @@ impl VoiceWorkflow {
             state.phase,
             WorkflowPhase::Idle | WorkflowPhase::Cancelled | WorkflowPhase::Failed
         ) {
-            return Err(primary_phase_error(state.phase));
+            let ready = state.ready_estimate_ms(now_ms());
+            return Err(primary_phase_error(state.phase).with_retry_after(ready));
         }
         state.phase = WorkflowPhase::Recording;
+        state.recording_started_ms = Some(now_ms());
         state.session = Some(WorkflowSession {
             session_id: transcript_id.to_string(),
             recording_session_id: String::new(),
@@ -1510,6 +2111,7 @@ impl VoiceWorkflow {
             ));
         }
         state.phase = WorkflowPhase::Transcribing;
+        state.transcribing_started_ms = Some(now_ms());
         Ok(session)
     }
 
@@ -1532,6 +2134,7 @@ impl VoiceWorkflow {
             ));
         }
         state.phase = WorkflowPhase::Transcribing;
+        state.transcribing_started_ms = Some(now_ms());
         Ok(session)
     }
 
@@ -1598,6 +2201,7 @@ impl VoiceWorkflow {
                 "transcription result does not match active session",
             ));
         }
+        state.note_transcription_finished(now_ms());
         state.phase = WorkflowPhase::Transcribed;
         state.transcription = Some(result);
         state.last_created_at_ms = Some(now_ms());
@@ -1679,7 +2283,7 @@ impl VoiceWorkflow {
         Ok(())
     }
 
-    fn begin_rewrite(&self, transcript_id: &str) -> WorkflowResult<()> {
+    fn begin_rewrite(&self, transcript_id: &str) -> WorkflowResult<CancellationToken> {
         let mut state = self.state.lock().unwrap();
         if !matches!(
             state.phase,
@@ -1711,42 +2315,206 @@ impl VoiceWorkflow {
         });
         state.insert_previous_phase = None;
         state.last_error = None;
-        Ok(())
+        let token = CancellationToken::new();
+        state.rewrite_token = Some(token.clone());
+        Ok(token)
     }
 
-    fn complete_rewrite(&self, result: RewriteResult) -> WorkflowResult<()> {
+    fn cancel_current_rewrite(&self) -> WorkflowResult<String> {
         let mut state = self.state.lock().unwrap();
         if state.phase != WorkflowPhase::Rewriting {
-            return Err(WorkflowError::new(
-                "E_WORKFLOW_INVALID_PHASE",
-                "workflow is not rewriting",
-            ));
+            return Err(cancel_phase_error(state.phase));
         }
         let session_id = state
             .session
             .as_ref()
-            .map(|session| session.session_id.as_str())
+            .map(|session| session.session_id.clone())
             .ok_or_else(|| WorkflowError::new("E_WORKFLOW_SESSION_MISSING", "session missing"))?;
-        if session_id != result.transcript_id {
-            return Err(WorkflowError::new(
-                "E_WORKFLOW_TRANSCRIPT_MISMATCH",
-                "rewrite result does not match workflow session",
-            ));
+        if let Some(token) = state.rewrite_token.take() {
+            token.cancel();
         }
-        state.phase = WorkflowPhase::Rewritten;
-        state.rewrite = Some(result);
+        state.phase = WorkflowPhase::Cancelled;
         state.insert_previous_phase = None;
         state.last_error = None;
-        Ok(())
+        Ok(session_id)
     }
 
-    fn begin_insert(&self, transcript_id: &str) -> WorkflowResult<()> {
+    // Stops the foreground rewrite and completes the task with the transcript
+    // text in its place.
+    fn skip_current_rewrite(&self, task_id: &str) -> WorkflowResult<RewriteResult> {
         let mut state = self.state.lock().unwrap();
-        if !matches!(
-            state.phase,
-            WorkflowPhase::Transcribed | WorkflowPhase::Rewritten
-        ) {
-            return Err(insert_phase_error(state.phase));
+        if state.phase != WorkflowPhase::Rewriting {
+            return Err(cancel_phase_error(state.phase));
+        }
+        if state
+            .session
+            .as_ref()
+            .map(|session| session.session_id.as_str())
+            != Some(task_id)
+        {
+            return Err(WorkflowError::new(
+                "E_WORKFLOW_TRANSCRIPT_MISMATCH",
+                "task is not the foreground rewrite",
+            ));
+        }
+        let transcription = state.transcription.as_ref().ok_or_else(|| {
+            WorkflowError::new("E_WORKFLOW_SESSION_MISSING", "transcript missing")
+        })?;
+        let final_text = if transcription.final_text.trim().is_empty() {
+            transcription.asr_text.clone()
+        } else {
+            transcription.final_text.clone()
+        };
+        if let Some(token) = state.rewrite_token.take() {
+            token.cancel();
+        }
+        let result = RewriteResult {
+            transcript_id: task_id.to_string(),
+            final_text,
+            rewrite_ms: 0,
+            output_error_code: None,
+            template_id: None,
+        };
+        state.phase = WorkflowPhase::Rewritten;
+        state.rewrite = Some(result.clone());
+        state.insert_previous_phase = None;
+        state.last_error = None;
+        Ok(result)
+    }
+
+    fn complete_rewrite(&self, result: RewriteResult) -> WorkflowResult<()> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(background) = state.background_rewrites.remove(&result.transcript_id) {
+            // Persisted by the caller. A busy or unread foreground is left
+            // alone and the result reaches the UI through its completed
+            // event; otherwise it becomes the current text to insert or copy.
+            let foreground_free = matches!(
+                state.phase,
+                WorkflowPhase::Idle | WorkflowPhase::Cancelled | WorkflowPhase::Failed
+            );
+            if let (true, Some(transcription)) = (foreground_free, background.transcription) {
+                state.phase = WorkflowPhase::Rewritten;
+                state.session = Some(WorkflowSession {
+                    session_id: result.transcript_id.clone(),
+                    recording_session_id: String::new(),
+                    streaming_transcription: false,
+                });
+                state.transcription = Some(transcription);
+                state.last_created_at_ms = background.created_at_ms;
+                state.rewrite = Some(result);
+                state.insert_previous_phase = None;
+                state.last_error = None;
+            }
+            return Ok(());
+        }
+        if state.phase != WorkflowPhase::Rewriting {
+            return Err(WorkflowError::new(
+                "E_WORKFLOW_INVALID_PHASE",
+                "workflow is not rewriting",
+            ));
+        }
+        let session_id = state
+            .session
+            .as_ref()
+            .map(|session| session.session_id.as_str())
+            .ok_or_else(|| WorkflowError::new("E_WORKFLOW_SESSION_MISSING", "session missing"))?;
+        if session_id != result.transcript_id {
+            return Err(WorkflowError::new(
+                "E_WORKFLOW_TRANSCRIPT_MISMATCH",
+                "rewrite result does not match workflow session",
+            ));
+        }
+        state.phase = WorkflowPhase::Rewritten;
+        // The raw reply is still usable, so invalid structured output is only a diagnostic.
+        state.last_error = result.output_error_code.as_deref().map(|code| {
+            WorkflowError::new(
+                code,
+                "rewrite output failed format validation; raw text kept",
+            )
+        });
+        state.rewrite = Some(result);
+        state.insert_previous_phase = None;
+        Ok(())
+    }
+
+    fn set_max_background_rewrites(&self, limit: usize) {
+        self.state.lock().unwrap().max_background_rewrites = limit;
+    }
+
+    fn detach_rewrite_to_background(&self, limit: usize) -> WorkflowResult<String> {
+        let mut state = self.state.lock().unwrap();
+        state.max_background_rewrites = limit;
+        if state.phase != WorkflowPhase::Rewriting || limit == 0 {
+            return Err(primary_phase_error(state.phase));
+        }
+        if state.background_rewrites.len() >= limit {
+            return Err(WorkflowError::new(
+                "E_WORKFLOW_BACKGROUND_BUSY",
+                format!("{limit} background rewrite(s) still running"),
+            ));
+        }
+        let session_id = state
+            .session
+            .as_ref()
+            .map(|session| session.session_id.clone())
+            .ok_or_else(|| WorkflowError::new("E_WORKFLOW_SESSION_MISSING", "session missing"))?;
+        // A detached rewrite keeps running and keeps its cancel token.
+        let background = BackgroundRewrite {
+            detached_at_ms: now_ms(),
+            token: state.rewrite_token.take(),
+            transcription: state.transcription.take(),
+            created_at_ms: state.last_created_at_ms.take(),
+        };
+        state
+            .background_rewrites
+            .insert(session_id.clone(), background);
+        state.phase = WorkflowPhase::Idle;
+        state.session = None;
+        state.rewrite = None;
+        state.insert_previous_phase = None;
+        state.last_error = None;
+        Ok(session_id)
+    }
+
+    // Stops a detached rewrite and frees its slot.
+    fn cancel_background_rewrite(&self, task_id: &str) -> bool {
+        let removed = self
+            .state
+            .lock()
+            .unwrap()
+            .background_rewrites
+            .remove(task_id.trim());
+        match removed {
+            Some(background) => {
+                if let Some(token) = background.token {
+                    token.cancel();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn background_rewrite_ids(&self) -> Vec<String> {
+        self.snapshot().background_rewrites
+    }
+
+    fn finish_background_rewrite(&self, task_id: &str) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .background_rewrites
+            .remove(task_id.trim())
+            .is_some()
+    }
+
+    fn begin_insert(&self, transcript_id: &str) -> WorkflowResult<()> {
+        let mut state = self.state.lock().unwrap();
+        if !matches!(
+            state.phase,
+            WorkflowPhase::Transcribed | WorkflowPhase::Rewritten
+        ) {
+            return Err(insert_phase_error(state.phase));
         }
         if let Some(session) = state.session.as_ref() {
             if !session.session_id.is_empty() && session.session_id != transcript_id {
@@ -1844,6 +2612,29 @@ impl VoiceWorkflow {
             .map(|ctx| ctx.snapshot)
     }
 
+    // The hotkey snapshot is still pending when the transcript is persisted;
+    // rewrite takes it later.
+    fn pending_target(&self, task_id: &str) -> (Option<String>, Option<String>) {
+        let state = self.state.lock().unwrap();
+        let Some(window) = state
+            .pending_contexts
+            .get(task_id)
+            .and_then(|ctx| ctx.snapshot.prev_window.as_ref())
+        else {
+            return (None, None);
+        };
+        (
+            window
+                .process_image
+                .as_deref()
+                .map(formatting_profiles::process_file_name),
+            window
+                .title
+                .as_deref()
+                .map(|title| context_pack::sha256_hex(title.as_bytes())),
+        )
+    }
+
     fn cleanup_orphan_pending_contexts(&self, max_age_ms: i64) {
         let now = now_ms();
         let mut state = self.state.lock().unwrap();
@@ -1884,7 +2675,7 @@ impl VoiceWorkflow {
     }
 
     #[cfg(test)]
-    fn begin_rewrite_for_test(&self, transcript_id: &str) -> WorkflowResult<()> {
+    fn begin_rewrite_for_test(&self, transcript_id: &str) -> WorkflowResult<CancellationToken> {
         self.begin_rewrite(transcript_id)
     }
 
@@ -1947,6 +2738,9 @@ fn user_facing_error_title(code: &str) -> &'static str {
     if code.starts_with("E_TOOLCHAIN_") {
         return "Local audio tools need repair";
     }
+    if code == "E_OFFLINE_MODE" {
+        return "Offline mode is on";
+    }
     if code.starts_with("E_RECORD_")
         || code.starts_with("E_STREAMING_TRANSCRIBE_")
         || code.starts_with("E_DOUBAO_ASR_")
@@ -1954,6 +2748,12 @@ fn user_facing_error_title(code: &str) -> &'static str {
     {
         return "Speech recognition could not start";
     }
+    if code == "E_LLM_OUTPUT_INVALID" {
+        return "Improved text is not in the expected format";
+    }
+    if code.starts_with("E_TLS_") {
+        return "Server certificate is not trusted";
+    }
     if code.starts_with("E_REWRITE_") || code.starts_with("HTTP_") {
         return "Text improvement failed";
     }
@@ -1963,18 +2763,27 @@ fn user_facing_error_title(code: &str) -> &'static str {
     {
         return "Text could not be pasted";
     }
-    if code == "E_TASK_ALREADY_ACTIVE" || code == "E_RECORD_ALREADY_ACTIVE" {
+    if code == "E_TASK_ALREADY_ACTIVE"
+        || code == "E_RECORD_ALREADY_ACTIVE"
+        || code == "E_WORKFLOW_BACKGROUND_BUSY"
+    {
         return "An action is already running";
     }
     if code.starts_with("E_SETTINGS_") {
         return "Settings need attention";
     }
+    if code == "E_TASK_STALLED" {
+        return "The task stopped responding";
+    }
     "Something went wrong"
 }
 
 fn user_facing_error_action(code: &str) -> &'static str {
     if code.starts_with("E_TOOLCHAIN_") {
-        return "Repair the local audio tools, then restart the app.";
+        return "Repair the local audio tools; they are checked again automatically.";
+    }
+    if code == "E_OFFLINE_MODE" {
+        return "Turn off offline mode in Settings to use network services.";
     }
     if code.starts_with("E_RECORD_")
         || code.starts_with("E_STREAMING_TRANSCRIBE_")
@@ -1983,6 +2792,12 @@ fn user_facing_error_action(code: &str) -> &'static str {
     {
         return "Check the selected microphone and speech recognition settings.";
     }
+    if code == "E_LLM_OUTPUT_INVALID" {
+        return "The raw reply was kept; check the output format and schema.";
+    }
+    if code.starts_with("E_TLS_") {
+        return "Add the internal CA bundle or pin the certificate in Settings.";
+    }
     if code.starts_with("E_REWRITE_") || code.starts_with("HTTP_") {
         return "Check text improvement settings and try again.";
     }
@@ -1992,9 +2807,15 @@ fn user_facing_error_action(code: &str) -> &'static str {
     {
         return "Select the target app and try again.";
     }
-    if code == "E_TASK_ALREADY_ACTIVE" || code == "E_RECORD_ALREADY_ACTIVE" {
+    if code == "E_TASK_ALREADY_ACTIVE"
+        || code == "E_RECORD_ALREADY_ACTIVE"
+        || code == "E_WORKFLOW_BACKGROUND_BUSY"
+    {
         return "Wait for the current action to finish.";
     }
+    if code == "E_TASK_STALLED" {
+        return "It was stopped; try again.";
+    }
     "Check settings and try again."
 }
 
@@ -2060,8 +2881,9 @@ fn cancel_phase_error(phase: WorkflowPhase) -> WorkflowError {
         WorkflowPhase::Rewritten => "E_WORKFLOW_CANCEL_REWRITTEN",
         WorkflowPhase::Cancelled => "E_WORKFLOW_CANCEL_CANCELLED",
         WorkflowPhase::Failed => "E_WORKFLOW_CANCEL_FAILED",
-        WorkflowPhase::Recording | WorkflowPhase::Transcribing => "E_WORKFLOW_CANCEL_ALLOWED",
-        WorkflowPhase::Rewriting => "E_WORKFLOW_CANCEL_REWRITING",
+        WorkflowPhase::Recording | WorkflowPhase::Transcribing | WorkflowPhase::Rewriting => {
+            "E_WORKFLOW_CANCEL_ALLOWED"
+        }
         WorkflowPhase::Inserting => "E_WORKFLOW_CANCEL_INSERTING",
     };
     WorkflowError::new(code, format!("workflow is {}", phase.as_str()))
@@ -2079,9 +2901,67 @@ fn last_result_from_snapshot(snapshot: &WorkflowSnapshot) -> Option<WorkflowActi
         asr_text: transcription.asr_text.clone(),
         final_text,
         created_at_ms: snapshot.last_created_at_ms,
+        template_id: snapshot
+            .rewrite
+            .as_ref()
+            .and_then(|result| result.template_id.clone()),
     })
 }
 
+const WEBHOOK_HISTORY_FLUSH_MS: u64 = 5_000;
+
+// The webhook payload is read back from history, so it waits for the queued
+// writes first and reflects exactly what was persisted.
+fn spawn_webhook_delivery(mailbox: UiEventMailbox, dir: std::path::PathBuf, task_id: String) {
+    tauri::async_runtime::spawn(async move {
+        let db_path = workspaces::history_db_path(&dir);
+        let lookup_id = task_id.clone();
+        let item = tauri::async_runtime::spawn_blocking(move || {
+            history_writer::flush(WEBHOOK_HISTORY_FLUSH_MS);
+            history::get(&db_path, &lookup_id)
+        })
+        .await;
+        let result = match item {
+            Ok(Ok(Some(item))) => webhook::deliver_task_done(&dir, &item).await.map(|_| ()),
+            Ok(Ok(None)) => Err(PortError::new(
+                "E_WEBHOOK_HISTORY_MISSING",
+                "history entry not found; nothing was sent",
+            )),
+            Ok(Err(e)) => Err(PortError::new("E_WEBHOOK_HISTORY_READ", e.to_string())),
+            Err(e) => Err(PortError::new("E_WEBHOOK_HISTORY_READ", e.to_string())),
+        };
+        if let Err(e) = result {
+            mailbox.send(UiEvent::warning(
+                Some(task_id),
+                TaskStage::Sink,
+                e.code,
+                format!("Webhook not delivered: {}", e.message),
+            ));
+        }
+    });
+}
+
+// Nothing cancels a finished dictation; plugin timeouts bound the wait.
+async fn apply_dictation_postprocess(result: &mut TranscriptionResult) {
+    let Ok(dir) = data_dir::data_dir() else {
+        return;
+    };
+    let s = settings::load_settings(&dir).unwrap_or_default();
+    let raw = result.final_text.clone();
+    if let Ok(text) = TextPostprocess::from_settings(&s, "dictation")
+        .apply(
+            &dir,
+            &result.transcript_id,
+            "Transcribe",
+            raw,
+            &CancellationToken::new(),
+        )
+        .await
+    {
+        result.final_text = text;
+    }
+}
+
 fn is_empty_asr_failure(code: &str, message: &str) -> bool {
     matches!(
         code,
@@ -2105,6 +2985,34 @@ fn normalize_optional_task_id(task_id: Option<String>) -> WorkflowResult<Option<
     Ok(Some(parsed.to_string()))
 }
 
+fn log_idempotent_replay(task_id: Option<&str>, key: &str) {
+    if let Ok(dir) = data_dir::data_dir() {
+        crate::obs::event(
+            &dir,
+            task_id,
+            "Workflow",
+            "workflow.idempotent_replay",
+            "ok",
+            Some(serde_json::json!({ "idempotency_key": key })),
+        );
+    }
+}
+
+fn load_rewrite_template_id() -> Option<String> {
+    data_dir::data_dir()
+        .ok()
+        .and_then(|dir| settings::load_settings(&dir).ok())
+        .and_then(|s| settings::resolve_rewrite_template_id(&s))
+}
+
+fn load_max_background_rewrites() -> usize {
+    data_dir::data_dir()
+        .ok()
+        .and_then(|dir| settings::load_settings(&dir).ok())
+        .map(|s| settings::resolve_pipeline_max_background_rewrites(&s))
+        .unwrap_or(settings::DEFAULT_PIPELINE_MAX_BACKGROUND_REWRITES)
+}
+
 fn now_ms() -> i64 {
     match std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
         Ok(dur) => dur.as_millis() as i64,
@@ -2112,6 +3020,60 @@ fn now_ms() -> i64 {
     }
 }
 
+// Also used by the retained-recording transcode, which finishes off the
+// workflow thread.
+fn persist_history_op(mailbox: &UiEventMailbox, op: history_writer::HistoryOp) {
+    let dir = match data_dir::data_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            mailbox.send(UiEvent::warning(
+                Some(op.task_id().to_string()),
+                TaskStage::Persist,
+                "E_DATA_DIR",
+                format!("history not saved: {e}"),
+            ));
+            return;
+        }
+    };
+    let mailbox = mailbox.clone();
+    history_writer::submit(&workspaces::history_db_path(&dir), op, move |w| {
+        // Only the first failure and the final outcome reach the UI; the raw
+        // error is in the HISTORY.persist_retry trace event.
+        let message = if w.code == "E_HISTORY_NOT_FOUND" {
+            "History entry no longer exists; update dropped"
+        } else if w.gave_up {
+            "History not saved yet; it will be retried on next start"
+        } else if w.attempt == 1 {
+            "History save failed; retrying in the background"
+        } else {
+            return;
+        };
+        mailbox.send(UiEvent::warning(
+            Some(w.task_id.clone()),
+            TaskStage::Persist,
+            w.code.clone(),
+            message,
+        ));
+    });
+}
+
+// Phases a runner drives on its own, and the stage name their events use.
+fn stalled_phase_stage(phase: WorkflowPhase) -> Option<TaskStage> {
+    match phase {
+        WorkflowPhase::Transcribing => Some(TaskStage::Transcribe),
+        WorkflowPhase::Rewriting => Some(TaskStage::Rewrite),
+        WorkflowPhase::Inserting => Some(TaskStage::Insert),
+        _ => None,
+    }
+}
+
+fn stalled_task_error(stall_ms: i64) -> WorkflowError {
+    WorkflowError::new(
+        "E_TASK_STALLED",
+        format!("no progress for {}s", stall_ms / 1000),
+    )
+}
+
 fn log_workflow_error(task_id: Option<&str>, step_id: &str, err: &WorkflowError) {
     if let Ok(dir) = data_dir::data_dir() {
         crate::obs::event_err(
@@ -2323,8 +3285,8 @@ mod tests {
         );
     }
 
-    #[test]
-    fn duplicate_completed_asr_report_returns_current_view() {
+    #[tokio::test]
+    async fn duplicate_completed_asr_report_returns_current_view() {
         let (mailbox, _rx) = UiEventMailbox::for_test();
         let workflow = VoiceWorkflow::new();
         workflow
@@ -2349,6 +3311,7 @@ mod tests {
                     metrics: metrics.clone(),
                 },
             )
+            .await
             .expect("first report completes");
         let view = workflow
             .report_asr_completed(
@@ -2359,6 +3322,7 @@ mod tests {
                     metrics,
                 },
             )
+            .await
             .expect("duplicate report is accepted");
 
         assert_eq!(view.phase, "transcribed");
@@ -2380,6 +3344,8 @@ mod tests {
                 transcript_id: "task-1".to_string(),
                 final_text: "final text".to_string(),
                 rewrite_ms: 30,
+                output_error_code: None,
+                template_id: None,
             })
             .expect("rewrite completes");
 
@@ -2398,6 +3364,356 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cancel_during_rewrite_cancels_only_the_foreground_request() {
+        let workflow = VoiceWorkflow::new();
+        workflow
+            .open_transcribed_session_for_test("task-1", "asr text")
+            .expect("transcribed");
+        let foreground = workflow
+            .begin_rewrite_for_test("task-1")
+            .expect("rewrite starts");
+
+        assert_eq!(
+            workflow.cancel_current_rewrite().expect("cancel accepted"),
+            "task-1"
+        );
+        assert!(foreground.is_cancelled());
+        assert_eq!(workflow.phase(), WorkflowPhase::Cancelled);
+
+        workflow
+            .open_transcribed_session_for_test("task-2", "asr text")
+            .expect("transcribed");
+        let detached = workflow
+            .begin_rewrite_for_test("task-2")
+            .expect("rewrite starts");
+        workflow
+            .detach_rewrite_to_background(1)
+            .expect("rewrite detaches");
+        let err = workflow
+            .cancel_current_rewrite()
+            .expect_err("nothing in the foreground");
+        assert_eq!(err.code, "E_WORKFLOW_CANCEL_IDLE");
+        assert!(!detached.is_cancelled());
+    }
+
+    #[test]
+    fn cancelling_the_rewrite_stage_keeps_the_transcript() {
+        let workflow = VoiceWorkflow::new();
+        workflow
+            .open_transcribed_session_for_test("task-1", "asr text")
+            .expect("transcribed");
+        let token = workflow
+            .begin_rewrite_for_test("task-1")
+            .expect("rewrite starts");
+
+        let err = workflow
+            .skip_current_rewrite("task-2")
+            .expect_err("other task");
+        assert_eq!(err.code, "E_WORKFLOW_TRANSCRIPT_MISMATCH");
+        assert!(!token.is_cancelled());
+
+        let result = workflow
+            .skip_current_rewrite("task-1")
+            .expect("rewrite skipped");
+        assert!(token.is_cancelled());
+        assert_eq!(result.final_text, "asr text");
+        assert_eq!(workflow.phase(), WorkflowPhase::Rewritten);
+        assert_eq!(
+            workflow.snapshot().rewrite.map(|r| r.final_text).as_deref(),
+            Some("asr text")
+        );
+
+        // The late E_CANCELLED from the LLM call must not undo the completion.
+        let err = workflow
+            .skip_current_rewrite("task-1")
+            .expect_err("already rewritten");
+        assert_eq!(err.code, "E_WORKFLOW_CANCEL_REWRITTEN");
+    }
+
+    #[test]
+    fn busy_rejection_estimates_when_transcription_frees_the_slot() {
+        let mut state = WorkflowState::idle();
+        state.phase = WorkflowPhase::Transcribing;
+        state.recording_started_ms = Some(0);
+        state.transcribing_started_ms = Some(8_000);
+        assert_eq!(state.ready_estimate_ms(8_000), None);
+
+        // 2s of waiting for 8s of audio.
+        state.note_transcription_finished(10_000);
+        assert_eq!(state.recent_rtf, Some(0.25));
+        assert_eq!(state.ready_estimate_ms(8_500), Some(1_500));
+        assert_eq!(
+            state.ready_estimate_ms(30_000),
+            Some(READY_ESTIMATE_FLOOR_MS)
+        );
+        state.phase = WorkflowPhase::Recording;
+        assert_eq!(state.ready_estimate_ms(8_500), None);
+
+        let err = primary_phase_error(WorkflowPhase::Transcribing).with_retry_after(Some(1_500));
+        assert!(err
+            .render()
+            .ends_with("(ready in ~2s, retry_after_ms=1500)"));
+        assert!(!primary_phase_error(WorkflowPhase::Transcribing)
+            .render()
+            .contains("retry_after_ms"));
+    }
+
+    #[test]
+    fn stalled_tasks_fail_and_release_their_slots() {
+        let (mailbox, rx) = UiEventMailbox::for_test();
+        let heartbeats = TaskHeartbeats::default();
+        let workflow = VoiceWorkflow::new();
+        workflow
+            .open_transcribed_session_for_test("task-1", "asr text")
+            .expect("transcribed");
+        workflow.begin_rewrite_for_test("task-1").expect("rewrite");
+        workflow
+            .detach_rewrite_to_background(1)
+            .expect("rewrite detaches");
+        workflow
+            .open_transcribed_session_for_test("task-2", "asr text")
+            .expect("transcribed");
+        let foreground = workflow.begin_rewrite_for_test("task-2").expect("rewrite");
+        assert!(workflow.has_active_task());
+
+        assert!(workflow
+            .fail_stalled_tasks(&mailbox, &heartbeats, 60_000)
+            .is_empty());
+        assert_eq!(workflow.phase(), WorkflowPhase::Rewriting);
+
+        let stalled = workflow.fail_stalled_tasks(&mailbox, &heartbeats, 0);
+        assert_eq!(stalled, vec!["task-1".to_string(), "task-2".to_string()]);
+        assert_eq!(workflow.phase(), WorkflowPhase::Failed);
+        assert!(foreground.is_cancelled());
+        assert!(!workflow.has_active_task());
+        assert!(workflow.snapshot().background_rewrites.is_empty());
+        let failed: Vec<String> = rx
+            .try_iter()
+            .filter(|e| e.kind == UiEventKind::WorkflowTaskFailed)
+            .filter_map(|e| e.error_code)
+            .collect();
+        assert_eq!(failed, vec!["E_TASK_STALLED", "E_TASK_STALLED"]);
+
+        // Nothing left to watch.
+        assert!(workflow
+            .fail_stalled_tasks(&mailbox, &heartbeats, 0)
+            .is_empty());
+    }
+
+    #[test]
+    fn rewrite_with_invalid_output_keeps_raw_text_and_reports_code() {
+        let workflow = VoiceWorkflow::new();
+        workflow
+            .open_transcribed_session_for_test("task-1", "asr text")
+            .expect("transcribed");
+        workflow
+            .begin_rewrite_for_test("task-1")
+            .expect("rewrite starts");
+        workflow
+            .complete_rewrite_for_test(crate::rewrite::RewriteResult {
+                transcript_id: "task-1".to_string(),
+                final_text: "{\"name\": ".to_string(),
+                rewrite_ms: 30,
+                output_error_code: Some("E_LLM_OUTPUT_INVALID".to_string()),
+                template_id: None,
+            })
+            .expect("rewrite completes");
+
+        let view = workflow.view();
+        assert_eq!(view.phase, "rewritten");
+        assert_eq!(view.last_text, "{\"name\": ");
+        assert!(view.can_insert);
+        assert_eq!(
+            view.diagnostic_code.as_deref(),
+            Some("E_LLM_OUTPUT_INVALID")
+        );
+        assert!(view.diagnostic_line.contains("raw reply was kept"));
+    }
+
+    #[test]
+    fn rewrite_detaches_to_background_and_frees_primary() {
+        let workflow = VoiceWorkflow::new();
+        workflow.set_max_background_rewrites(1);
+        workflow
+            .open_transcribed_session_for_test("task-1", "asr text")
+            .expect("transcribed");
+        workflow
+            .begin_rewrite_for_test("task-1")
+            .expect("rewrite starts");
+        assert!(!workflow.view().primary_disabled);
+        assert!(!workflow.has_active_task());
+
+        let detached = workflow
+            .detach_rewrite_to_background(1)
+            .expect("rewrite detaches");
+        assert_eq!(detached, "task-1");
+        assert_eq!(workflow.phase(), WorkflowPhase::Idle);
+        assert_eq!(workflow.view().background_task_ids, vec!["task-1"]);
+
+        workflow
+            .open_recording_for_test("task-2", "recording-2")
+            .expect("next recording starts");
+        workflow
+            .complete_rewrite_for_test(crate::rewrite::RewriteResult {
+                transcript_id: "task-1".to_string(),
+                final_text: "final text".to_string(),
+                rewrite_ms: 30,
+                output_error_code: None,
+                template_id: None,
+            })
+            .expect("background rewrite completes");
+
+        let view = workflow.view();
+        assert_eq!(view.phase, "recording");
+        assert_eq!(view.task_id.as_deref(), Some("task-2"));
+        assert!(view.background_task_ids.is_empty());
+        assert!(workflow.snapshot().rewrite.is_none());
+    }
+
+    #[test]
+    fn background_rewrites_are_off_by_default() {
+        let workflow = VoiceWorkflow::new();
+        workflow
+            .open_transcribed_session_for_test("task-1", "asr text")
+            .expect("transcribed");
+        workflow
+            .begin_rewrite_for_test("task-1")
+            .expect("rewrite starts");
+        assert!(workflow.view().primary_disabled);
+        assert!(workflow.has_active_task());
+    }
+
+    #[test]
+    fn background_result_becomes_current_text_when_foreground_is_free() {
+        let workflow = VoiceWorkflow::new();
+        workflow
+            .open_transcribed_session_for_test("task-1", "asr text")
+            .expect("transcribed");
+        workflow
+            .begin_rewrite_for_test("task-1")
+            .expect("rewrite starts");
+        workflow
+            .detach_rewrite_to_background(1)
+            .expect("rewrite detaches");
+        workflow
+            .complete_rewrite_for_test(crate::rewrite::RewriteResult {
+                transcript_id: "task-1".to_string(),
+                final_text: "final text".to_string(),
+                rewrite_ms: 30,
+                output_error_code: None,
+                template_id: None,
+            })
+            .expect("background rewrite completes");
+
+        let view = workflow.view();
+        assert_eq!(view.phase, "rewritten");
+        assert_eq!(view.task_id.as_deref(), Some("task-1"));
+        assert_eq!(view.last_text, "final text");
+        assert!(view.can_insert);
+        assert!(view.background_task_ids.is_empty());
+    }
+
+    #[test]
+    fn cancelling_a_background_rewrite_stops_its_requests() {
+        let workflow = VoiceWorkflow::new();
+        let (mailbox, _rx) = UiEventMailbox::for_test();
+        workflow
+            .open_transcribed_session_for_test("task-1", "asr text")
+            .expect("transcribed");
+        let token = workflow
+            .begin_rewrite_for_test("task-1")
+            .expect("rewrite starts");
+        workflow
+            .detach_rewrite_to_background(1)
+            .expect("rewrite detaches");
+        workflow
+            .open_recording_for_test("task-2", "recording-2")
+            .expect("next recording starts");
+
+        let view = workflow
+            .cancel_stage(
+                &mailbox,
+                WorkflowCancelStageRequest {
+                    task_id: "task-1".to_string(),
+                    stage: "Rewrite".to_string(),
+                },
+            )
+            .expect("background rewrite cancels");
+
+        assert!(token.is_cancelled());
+        assert_eq!(view.phase, "recording");
+        assert!(view.background_task_ids.is_empty());
+    }
+
+    #[test]
+    fn background_rewrites_are_bounded_by_limit() {
+        let workflow = VoiceWorkflow::new();
+        workflow
+            .open_transcribed_session_for_test("task-1", "asr text")
+            .expect("transcribed");
+        workflow
+            .begin_rewrite_for_test("task-1")
+            .expect("rewrite starts");
+
+        let err = workflow
+            .detach_rewrite_to_background(0)
+            .expect_err("disabled overlap keeps old behavior");
+        assert_eq!(err.code, "E_WORKFLOW_PRIMARY_REWRITING");
+        assert!(workflow.view().primary_disabled);
+
+        workflow
+            .detach_rewrite_to_background(1)
+            .expect("first rewrite detaches");
+        workflow
+            .open_transcribed_session_for_test("task-2", "asr text 2")
+            .expect("transcribed");
+        workflow
+            .begin_rewrite_for_test("task-2")
+            .expect("second rewrite starts");
+        assert!(workflow.view().primary_disabled);
+        assert!(workflow.has_active_task());
+
+        let err = workflow
+            .detach_rewrite_to_background(1)
+            .expect_err("limit reached");
+        assert_eq!(err.code, "E_WORKFLOW_BACKGROUND_BUSY");
+        assert_eq!(workflow.phase(), WorkflowPhase::Rewriting);
+    }
+
+    #[test]
+    fn background_rewrite_failure_leaves_foreground_untouched() {
+        let workflow = VoiceWorkflow::new();
+        let (mailbox, _rx) = UiEventMailbox::for_test();
+        workflow
+            .open_transcribed_session_for_test("task-1", "asr text")
+            .expect("transcribed");
+        workflow
+            .begin_rewrite_for_test("task-1")
+            .expect("rewrite starts");
+        workflow
+            .detach_rewrite_to_background(1)
+            .expect("rewrite detaches");
+        workflow
+            .open_recording_for_test("task-2", "recording-2")
+            .expect("next recording starts");
+
+        let view = workflow
+            .report_rewrite_failed(
+                &mailbox,
+                WorkflowTaskFailedRequest {
+                    transcript_id: "task-1".to_string(),
+                    code: "E_REWRITE_FAILED".to_string(),
+                    message: "llm down".to_string(),
+                },
+            )
+            .expect("background failure is accepted");
+
+        assert_eq!(view.phase, "recording");
+        assert_eq!(view.diagnostic_code, None);
+        assert!(view.background_task_ids.is_empty());
+    }
+
     #[test]
     fn insert_completes_session_to_idle() {
         let workflow = VoiceWorkflow::new();
@@ -2504,6 +3820,71 @@ mod tests {
         assert_eq!(req.text, "edited text");
     }
 
+    #[test]
+    fn rewritten_text_exports_under_its_template_policy() {
+        let workflow = VoiceWorkflow::new();
+        workflow
+            .open_transcribed_session_for_test("task-1", "asr text")
+            .expect("transcribed");
+        let s = settings::Settings {
+            export_targets_by_template: Some(
+                [("private".to_string(), vec!["webhook".to_string()])]
+                    .into_iter()
+                    .collect(),
+            ),
+            export_format_by_template: Some(
+                [("notes".to_string(), "markdown_html".to_string())]
+                    .into_iter()
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        // Plain dictations have no template and are unrestricted.
+        assert!(workflow.copy_last_payload(&s).is_ok());
+
+        workflow
+            .begin_rewrite_for_test("task-1")
+            .expect("rewrite starts");
+        workflow
+            .complete_rewrite_for_test(crate::rewrite::RewriteResult {
+                transcript_id: "task-1".to_string(),
+                final_text: "private note".to_string(),
+                rewrite_ms: 30,
+                output_error_code: None,
+                template_id: Some("private".to_string()),
+            })
+            .expect("rewrite completes");
+
+        let err = workflow.copy_last_payload(&s).expect_err("copy is blocked");
+        assert_eq!(err.code, "E_EXPORT_TARGET_NOT_ALLOWED");
+        let req = workflow
+            .current_insert_request(WorkflowTextCommandRequest {
+                text: "private note".to_string(),
+            })
+            .expect("insert request");
+        assert_eq!(req.template_id.as_deref(), Some("private"));
+        assert_eq!(
+            workflow.text_template_id("task-1").as_deref(),
+            Some("private")
+        );
+        assert_eq!(workflow.text_template_id("task-2"), None);
+
+        workflow
+            .begin_rewrite_for_test("task-1")
+            .expect("second rewrite starts");
+        workflow
+            .complete_rewrite_for_test(crate::rewrite::RewriteResult {
+                transcript_id: "task-1".to_string(),
+                final_text: "- note".to_string(),
+                rewrite_ms: 30,
+                output_error_code: None,
+                template_id: Some("notes".to_string()),
+            })
+            .expect("second rewrite completes");
+        let (_, format) = workflow.copy_last_payload(&s).expect("copy allowed");
+        assert_eq!(format, ExportFormat::MarkdownHtml);
+    }
+
     #[test]
     fn insert_current_request_uses_current_transcript_id() {
         let workflow = VoiceWorkflow::new();
@@ -2540,6 +3921,7 @@ mod tests {
                 task_id,
                 pending_context,
                 req,
+                ..
             } => {
                 assert_eq!(task_id, "task-1");
                 assert!(pending_context.is_none());
@@ -0,0 +1,59 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{anyhow, Result};
+
+// Guest mode lets a shared machine dictate without leaving anything behind:
+// history, templates and settings stay untouched and no audio, context or
+// debug payload is written. It lives here, below storage and every provider,
+// so each write path can consult the one flag.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub const CLI_FLAG: &str = "--guest";
+const ENV: &str = "TYPEVOICE_GUEST";
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn set_enabled(on: bool) {
+    ENABLED.store(on, Ordering::Relaxed);
+}
+
+// `--guest` on the command line, or TYPEVOICE_GUEST=1. Fixed for the life of
+// the process; there is no way to leave guest mode from the UI.
+pub fn requested<I: IntoIterator<Item = String>>(args: I) -> bool {
+    requested_by(args, std::env::var(ENV).ok().as_deref())
+}
+
+fn requested_by<I: IntoIterator<Item = String>>(args: I, env: Option<&str>) -> bool {
+    let env_on = env.is_some_and(|v| {
+        matches!(
+            v.trim().to_ascii_lowercase().as_str(),
+            "1" | "true" | "yes" | "on"
+        )
+    });
+    env_on || args.into_iter().any(|a| a == CLI_FLAG)
+}
+
+pub fn check_writable() -> Result<()> {
+    if enabled() {
+        return Err(anyhow!(
+            "E_GUEST_MODE_READ_ONLY: guest mode does not save history, templates or settings"
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requested_by_flag_or_env() {
+        let args = |v: &[&str]| v.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert!(requested_by(args(&["typevoice", "--guest"]), None));
+        assert!(!requested_by(args(&["typevoice", "--guests"]), None));
+        assert!(requested_by(args(&["typevoice"]), Some(" TRUE ")));
+        assert!(!requested_by(args(&["typevoice"]), Some("0")));
+    }
+}
@@ -1,2 +1,3 @@
+pub mod guest_mode;
 pub mod obs;
 pub mod safe_print;
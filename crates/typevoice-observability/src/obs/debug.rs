@@ -1,10 +1,16 @@
 use std::{
     fs,
     path::{Path, PathBuf},
-    sync::{Mutex, OnceLock},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, OnceLock,
+    },
     time::UNIX_EPOCH,
 };
 
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+
 use super::{metrics, schema};
 use crate::obs::schema::MetricsRecord;
 use typevoice_core::context_pack::sha256_hex;
@@ -34,17 +40,42 @@ fn env_usize(key: &str, default: usize) -> usize {
     }
 }
 
+static FORCED: AtomicUsize = AtomicUsize::new(0);
+
+// Turns full debug capture on for as long as it is held, for flows that exist
+// to collect it (a bug report sample). It is process-wide: another task running
+// at the same time is captured too.
+pub struct ForceVerbose(());
+
+impl ForceVerbose {
+    pub fn hold() -> Self {
+        FORCED.fetch_add(1, Ordering::SeqCst);
+        Self(())
+    }
+}
+
+impl Drop for ForceVerbose {
+    fn drop(&mut self) {
+        FORCED.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn forced() -> bool {
+    FORCED.load(Ordering::SeqCst) > 0
+}
+
+// Guest mode never keeps payloads, whatever the env says.
 pub fn verbose_enabled() -> bool {
-    env_bool("TYPEVOICE_DEBUG_VERBOSE")
+    (env_bool("TYPEVOICE_DEBUG_VERBOSE") || forced()) && !crate::guest_mode::enabled()
 }
 
 pub fn include_llm() -> bool {
-    env_bool("TYPEVOICE_DEBUG_INCLUDE_LLM")
+    env_bool("TYPEVOICE_DEBUG_INCLUDE_LLM") || forced()
 }
 
 pub fn include_asr_segments() -> bool {
     match std::env::var("TYPEVOICE_DEBUG_INCLUDE_ASR_SEGMENTS") {
-        Ok(_) => env_bool("TYPEVOICE_DEBUG_INCLUDE_ASR_SEGMENTS"),
+        Ok(_) => env_bool("TYPEVOICE_DEBUG_INCLUDE_ASR_SEGMENTS") || forced(),
         Err(_) => true,
     }
 }
@@ -73,6 +104,22 @@ pub fn debug_task_dir(data_dir: &Path, task_id: &str) -> PathBuf {
     debug_root(data_dir).join(task_id)
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskArtifact {
+    pub name: String,
+    pub bytes: u64,
+    pub modified_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskArtifactContent {
+    pub name: String,
+    pub bytes: u64,
+    // None for binary artifacts such as screenshots.
+    pub text: Option<String>,
+    pub truncated: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct PayloadInfo {
     pub path: PathBuf,
@@ -179,6 +226,119 @@ pub fn write_payload_binary_no_truncate_best_effort(
     })
 }
 
+// Artifacts are whatever the debug writers left in `debug/<task_id>/`; listing
+// and reading work regardless of the current debug env so earlier captures stay
+// inspectable.
+pub fn list_task_artifacts(data_dir: &Path, task_id: &str) -> Result<Vec<TaskArtifact>> {
+    check_component("task_id", task_id)?;
+    let dir = debug_task_dir(data_dir, task_id);
+    let entries = match fs::read_dir(&dir) {
+        Ok(v) => v,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(anyhow!("E_ARTIFACT_READ: read {}: {e}", dir.display())),
+    };
+    let mut out = Vec::new();
+    for ent in entries.flatten() {
+        let Ok(meta) = ent.metadata() else {
+            continue;
+        };
+        if !meta.is_file() {
+            continue;
+        }
+        let modified_ms = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        out.push(TaskArtifact {
+            name: ent.file_name().to_string_lossy().to_string(),
+            bytes: meta.len(),
+            modified_ms,
+        });
+    }
+    // Oldest first reads as the task's lineage.
+    out.sort_by(|a, b| {
+        a.modified_ms
+            .cmp(&b.modified_ms)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    Ok(out)
+}
+
+// Results the user asked for (not debug captures), so this is not gated on the
+// debug env.
+pub fn write_task_artifact(
+    data_dir: &Path,
+    task_id: &str,
+    name: &str,
+    bytes: &[u8],
+) -> Result<PathBuf> {
+    check_component("task_id", task_id)?;
+    check_component("name", name)?;
+    crate::guest_mode::check_writable()?;
+    let dir = debug_task_dir(data_dir, task_id);
+    fs::create_dir_all(&dir)
+        .map_err(|e| anyhow!("E_ARTIFACT_WRITE: create {}: {e}", dir.display()))?;
+    let path = dir.join(name);
+    fs::write(&path, bytes)
+        .map_err(|e| anyhow!("E_ARTIFACT_WRITE: write {}: {e}", path.display()))?;
+    Ok(path)
+}
+
+pub fn read_task_artifact(
+    data_dir: &Path,
+    task_id: &str,
+    name: &str,
+) -> Result<TaskArtifactContent> {
+    check_component("task_id", task_id)?;
+    check_component("name", name)?;
+    let path = debug_task_dir(data_dir, task_id).join(name);
+    let bytes = match fs::read(&path) {
+        Ok(v) => v,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(anyhow!(
+                "E_ARTIFACT_NOT_FOUND: no artifact {name} for task {task_id}"
+            ))
+        }
+        Err(e) => return Err(anyhow!("E_ARTIFACT_READ: read {}: {e}", path.display())),
+    };
+    let total = bytes.len() as u64;
+    let text = String::from_utf8(bytes).ok().map(|mut text| {
+        let max = max_payload_bytes();
+        if text.len() > max {
+            let mut end = max;
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            text.truncate(end);
+        }
+        text
+    });
+    let truncated = text.as_ref().is_some_and(|t| (t.len() as u64) < total);
+    Ok(TaskArtifactContent {
+        name: name.to_string(),
+        bytes: total,
+        text,
+        truncated,
+    })
+}
+
+// Both ids come from the frontend; only a single plain path component is allowed.
+fn check_component(field: &str, value: &str) -> Result<()> {
+    let ok = !value.is_empty()
+        && value != "."
+        && value != ".."
+        && !value.contains(['/', '\\', ':', '\0']);
+    if ok {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "E_ARTIFACT_NAME_INVALID: invalid {field}: {value:?}"
+        ))
+    }
+}
+
 pub fn emit_debug_event_best_effort(
     data_dir: &Path,
     event_type: &str,
@@ -220,7 +380,8 @@ pub fn prune_debug_dir_best_effort(data_dir: &Path) {
     let mut dirs: Vec<(std::time::SystemTime, PathBuf)> = Vec::new();
     for ent in entries.flatten() {
         let p = ent.path();
-        if !p.is_dir() {
+        // The LLM capture store keeps its own cap.
+        if !p.is_dir() || ent.file_name() == super::llm_capture::LLM_CAPTURE_DIR {
             continue;
         }
         let modified = ent
@@ -253,6 +414,50 @@ mod tests {
         LOCK.get_or_init(|| Mutex::new(()))
     }
 
+    #[test]
+    fn task_artifacts_are_listed_and_read_without_leaving_the_task_dir() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let dir = debug_task_dir(tmp.path(), "t1");
+        fs::create_dir_all(&dir).expect("mkdir");
+        fs::write(dir.join("final_text.txt"), "hello").expect("write");
+        fs::write(dir.join("screenshot.png"), [0x89, 0xff, 0x00]).expect("write");
+        fs::write(tmp.path().join("settings.json"), "{}").expect("write");
+
+        let names: Vec<String> = list_task_artifacts(tmp.path(), "t1")
+            .expect("list")
+            .into_iter()
+            .map(|a| a.name)
+            .collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&"final_text.txt".to_string()));
+        assert!(list_task_artifacts(tmp.path(), "missing")
+            .expect("list")
+            .is_empty());
+
+        let text = read_task_artifact(tmp.path(), "t1", "final_text.txt").expect("read");
+        assert_eq!(text.text.as_deref(), Some("hello"));
+        assert!(!text.truncated);
+        let bin = read_task_artifact(tmp.path(), "t1", "screenshot.png").expect("read");
+        assert_eq!((bin.text, bin.bytes), (None, 3));
+
+        for (task, name) in [("t1", "../../settings.json"), ("..", "settings.json")] {
+            let err = read_task_artifact(tmp.path(), task, name).expect_err("escape");
+            assert!(err.to_string().starts_with("E_ARTIFACT_NAME_INVALID"));
+        }
+        let err = read_task_artifact(tmp.path(), "t1", "nope.txt").expect_err("missing");
+        assert!(err.to_string().starts_with("E_ARTIFACT_NOT_FOUND"));
+
+        write_task_artifact(tmp.path(), "t2", "compare.json", b"{}").expect("write");
+        assert_eq!(
+            read_task_artifact(tmp.path(), "t2", "compare.json")
+                .expect("read")
+                .text
+                .as_deref(),
+            Some("{}")
+        );
+        assert!(write_task_artifact(tmp.path(), "t2", "../x.json", b"{}").is_err());
+    }
+
     #[test]
     fn llm_payload_debugging_requires_explicit_env() {
         let _guard = env_lock().lock().unwrap();
@@ -266,4 +471,20 @@ mod tests {
         assert!(!include_llm());
         std::env::remove_var("TYPEVOICE_DEBUG_INCLUDE_LLM");
     }
+
+    #[test]
+    fn force_verbose_lasts_while_any_guard_is_held() {
+        let _guard = env_lock().lock().unwrap();
+        std::env::remove_var("TYPEVOICE_DEBUG_VERBOSE");
+        std::env::remove_var("TYPEVOICE_DEBUG_INCLUDE_LLM");
+        assert!(!verbose_enabled());
+
+        let outer = ForceVerbose::hold();
+        let inner = ForceVerbose::hold();
+        assert!(verbose_enabled() && include_llm());
+        drop(inner);
+        assert!(verbose_enabled());
+        drop(outer);
+        assert!(!verbose_enabled() && !include_llm());
+    }
 }
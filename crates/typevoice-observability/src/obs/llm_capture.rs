@@ -0,0 +1,169 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, OnceLock,
+    },
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{debug, schema};
+
+// Opt-in (llm_capture_enabled) store of the last N LLM exchanges, for prompt
+// debugging with real context. Unlike the TYPEVOICE_DEBUG_INCLUDE_LLM payloads
+// it is not tied to a task directory: one file per exchange under
+// debug/llm_captures/, oldest removed first. The caller redacts secrets and
+// images before handing the request over.
+pub const LLM_CAPTURE_DIR: &str = "llm_captures";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmCapture {
+    pub ts_ms: i64,
+    pub task_id: String,
+    pub url: String,
+    pub model: String,
+    pub request: serde_json::Value,
+    // None when the request never got an HTTP response.
+    pub status: Option<u16>,
+    pub response: Option<String>,
+    pub error: Option<String>,
+    pub response_truncated: bool,
+}
+
+impl LlmCapture {
+    pub fn new(task_id: &str, url: &str, model: &str, request: serde_json::Value) -> Self {
+        Self {
+            ts_ms: schema::now_ms(),
+            task_id: task_id.to_string(),
+            url: url.to_string(),
+            model: model.to_string(),
+            request,
+            status: None,
+            response: None,
+            error: None,
+            response_truncated: false,
+        }
+    }
+}
+
+fn write_lock() -> &'static Mutex<()> {
+    static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| Mutex::new(()))
+}
+
+pub fn capture_dir(data_dir: &Path) -> PathBuf {
+    debug::debug_root(data_dir).join(LLM_CAPTURE_DIR)
+}
+
+pub fn record_best_effort(data_dir: &Path, mut capture: LlmCapture, max_entries: usize) {
+    if crate::guest_mode::enabled() || max_entries == 0 {
+        return;
+    }
+    if let Some(text) = capture.response.as_mut() {
+        let max = debug::max_payload_bytes();
+        if text.len() > max {
+            let mut end = max;
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            text.truncate(end);
+            capture.response_truncated = true;
+        }
+    }
+    let bytes = match serde_json::to_vec_pretty(&capture) {
+        Ok(v) => v,
+        Err(e) => {
+            crate::safe_eprintln!("llm_capture: serialize failed: {e}");
+            return;
+        }
+    };
+
+    static SEQ: AtomicU64 = AtomicU64::new(0);
+    let _guard = write_lock().lock().unwrap();
+    let dir = capture_dir(data_dir);
+    if let Err(e) = fs::create_dir_all(&dir) {
+        crate::safe_eprintln!("llm_capture: create_dir_all failed: {}: {e}", dir.display());
+        return;
+    }
+    // Zero-padded so name order is capture order; parallel rewrite variants of
+    // one task land in the same millisecond.
+    let name = format!(
+        "{:013}-{:06}.json",
+        capture.ts_ms.max(0),
+        SEQ.fetch_add(1, Ordering::Relaxed) % 1_000_000
+    );
+    let path = dir.join(name);
+    if let Err(e) = fs::write(&path, bytes) {
+        crate::safe_eprintln!("llm_capture: write failed: {}: {e}", path.display());
+        return;
+    }
+    let mut names = capture_files(&dir);
+    if names.len() > max_entries {
+        let excess = names.len() - max_entries;
+        for p in names.drain(..excess) {
+            let _ = fs::remove_file(p);
+        }
+    }
+}
+
+// Newest first.
+pub fn list(data_dir: &Path) -> Result<Vec<LlmCapture>> {
+    let dir = capture_dir(data_dir);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut out = Vec::new();
+    for path in capture_files(&dir).into_iter().rev() {
+        let bytes = fs::read(&path)
+            .map_err(|e| anyhow!("E_LLM_CAPTURE_READ: read {}: {e}", path.display()))?;
+        // A hand-edited or half-written file is skipped, not fatal.
+        if let Ok(capture) = serde_json::from_slice::<LlmCapture>(&bytes) {
+            out.push(capture);
+        }
+    }
+    Ok(out)
+}
+
+fn capture_files(dir: &Path) -> Vec<PathBuf> {
+    let mut names: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_newest_captures_up_to_the_cap() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        for i in 0..5 {
+            let mut c = LlmCapture::new(
+                &format!("t{i}"),
+                "https://llm.local/v1/chat/completions",
+                "m",
+                serde_json::json!({"i": i}),
+            );
+            c.ts_ms = 1_000 + i;
+            c.status = Some(200);
+            c.response = Some(format!("resp {i}"));
+            record_best_effort(tmp.path(), c, 3);
+        }
+        let listed = list(tmp.path()).expect("list");
+        let ids: Vec<&str> = listed.iter().map(|c| c.task_id.as_str()).collect();
+        assert_eq!(ids, ["t4", "t3", "t2"]);
+        assert_eq!(listed[0].response.as_deref(), Some("resp 4"));
+        assert!(list(&tmp.path().join("missing")).expect("list").is_empty());
+    }
+}
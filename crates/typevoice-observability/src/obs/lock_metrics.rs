@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::sync::{LockResult, Mutex, MutexGuard, PoisonError};
+#[cfg(feature = "lock-metrics")]
+use std::time::Instant;
+
+use super::metrics;
+use super::schema::{now_ms, MetricsRecord};
+
+// A Mutex that, with the `lock-metrics` feature, records how long callers
+// waited for it and how long they held it. Without the feature it is a plain
+// Mutex behind the same API, so call sites keep `.lock().unwrap()`.
+pub struct TimedMutex<T> {
+    #[cfg_attr(not(feature = "lock-metrics"), allow(dead_code))]
+    name: &'static str,
+    inner: Mutex<T>,
+}
+
+pub struct TimedGuard<'a, T> {
+    guard: MutexGuard<'a, T>,
+    #[cfg(feature = "lock-metrics")]
+    name: &'static str,
+    #[cfg(feature = "lock-metrics")]
+    acquired: Instant,
+}
+
+impl<T> TimedMutex<T> {
+    pub const fn new(name: &'static str, value: T) -> Self {
+        Self {
+            name,
+            inner: Mutex::new(value),
+        }
+    }
+
+    pub fn lock(&self) -> LockResult<TimedGuard<'_, T>> {
+        #[cfg(feature = "lock-metrics")]
+        {
+            // An uncontended lock is recorded without reading the clock twice.
+            let (result, waited_ns, contended) = match self.inner.try_lock() {
+                Ok(g) => (Ok(g), 0, false),
+                Err(std::sync::TryLockError::Poisoned(p)) => (Err(p), 0, false),
+                Err(std::sync::TryLockError::WouldBlock) => {
+                    let started = Instant::now();
+                    let r = self.inner.lock();
+                    (r, started.elapsed().as_nanos() as u64, true)
+                }
+            };
+            record_wait(self.name, waited_ns, contended);
+            let wrap = |guard| TimedGuard {
+                guard,
+                name: self.name,
+                acquired: Instant::now(),
+            };
+            match result {
+                Ok(g) => Ok(wrap(g)),
+                Err(p) => Err(PoisonError::new(wrap(p.into_inner()))),
+            }
+        }
+
+        #[cfg(not(feature = "lock-metrics"))]
+        {
+            match self.inner.lock() {
+                Ok(guard) => Ok(TimedGuard { guard }),
+                Err(p) => Err(PoisonError::new(TimedGuard {
+                    guard: p.into_inner(),
+                })),
+            }
+        }
+    }
+}
+
+impl<T> Deref for TimedGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for TimedGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+#[cfg(feature = "lock-metrics")]
+impl<T> Drop for TimedGuard<'_, T> {
+    fn drop(&mut self) {
+        record_hold(self.name, self.acquired.elapsed().as_nanos() as u64);
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LockStats {
+    pub acquisitions: u64,
+    pub contended: u64,
+    pub wait_total_ns: u64,
+    pub wait_max_ns: u64,
+    pub hold_total_ns: u64,
+    pub hold_max_ns: u64,
+}
+
+// Per lock name, since the last `report`.
+static STATS: Mutex<BTreeMap<&'static str, LockStats>> = Mutex::new(BTreeMap::new());
+static WINDOW_STARTED_MS: Mutex<Option<i64>> = Mutex::new(None);
+
+fn with_stats(name: &'static str, f: impl FnOnce(&mut LockStats)) {
+    let mut stats = STATS.lock().unwrap_or_else(|e| e.into_inner());
+    f(stats.entry(name).or_default());
+}
+
+#[cfg_attr(not(feature = "lock-metrics"), allow(dead_code))]
+fn record_wait(name: &'static str, waited_ns: u64, contended: bool) {
+    with_stats(name, |s| {
+        s.acquisitions += 1;
+        s.contended += u64::from(contended);
+        s.wait_total_ns += waited_ns;
+        s.wait_max_ns = s.wait_max_ns.max(waited_ns);
+    });
+}
+
+#[cfg_attr(not(feature = "lock-metrics"), allow(dead_code))]
+fn record_hold(name: &'static str, held_ns: u64) {
+    with_stats(name, |s| {
+        s.hold_total_ns += held_ns;
+        s.hold_max_ns = s.hold_max_ns.max(held_ns);
+    });
+}
+
+pub fn enabled() -> bool {
+    cfg!(feature = "lock-metrics")
+}
+
+// Drains the counters, so each report covers one window.
+pub fn take_stats() -> BTreeMap<&'static str, LockStats> {
+    std::mem::take(&mut *STATS.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+// One `lock_contention` metrics record per lock used in the window. Nothing is
+// written when the feature is off.
+pub fn report(data_dir: &Path) {
+    if !enabled() {
+        return;
+    }
+    let now = now_ms();
+    let started = WINDOW_STARTED_MS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .replace(now)
+        .unwrap_or(now);
+    for (lock, s) in take_stats() {
+        let _ = metrics::emit(
+            data_dir,
+            MetricsRecord::LockContention {
+                ts_ms: now,
+                lock: lock.to_string(),
+                window_ms: now.saturating_sub(started),
+                acquisitions: s.acquisitions,
+                contended: s.contended,
+                wait_total_us: s.wait_total_ns / 1_000,
+                wait_max_us: s.wait_max_ns / 1_000,
+                hold_total_us: s.hold_total_ns / 1_000,
+                hold_max_us: s.hold_max_ns / 1_000,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timed_mutex_behaves_like_a_mutex() {
+        let m = TimedMutex::new("test.lock", 1);
+        *m.lock().unwrap() += 1;
+        assert_eq!(*m.lock().unwrap(), 2);
+
+        let stats = take_stats();
+        if enabled() {
+            let s = stats.get("test.lock").expect("stats");
+            assert_eq!(s.acquisitions, 2);
+            assert_eq!(s.contended, 0);
+        } else {
+            assert!(stats.is_empty());
+        }
+    }
+}
@@ -5,7 +5,6 @@ use anyhow::Result;
 use super::schema::MetricsRecord;
 use super::writer;
 
-#[cfg_attr(not(test), allow(dead_code))]
 pub fn metrics_path(data_dir: &Path) -> PathBuf {
     data_dir.join("metrics.jsonl")
 }
@@ -13,3 +12,55 @@ pub fn metrics_path(data_dir: &Path) -> PathBuf {
 pub fn emit(data_dir: &Path, record: MetricsRecord) -> Result<()> {
     writer::emit_metrics_record(data_dir, &record)
 }
+
+pub fn read_all(data_dir: &Path) -> Vec<serde_json::Value> {
+    read_rotated(&metrics_path(data_dir))
+}
+
+// Every parseable record across a JSONL file and its rotated copies, oldest
+// first. Unreadable files and malformed lines are skipped.
+pub(crate) fn read_rotated(current: &Path) -> Vec<serde_json::Value> {
+    let current = current.to_path_buf();
+    let mut files = Vec::new();
+    for idx in 1.. {
+        let rotated = PathBuf::from(format!("{}.{idx}", current.display()));
+        if !rotated.exists() {
+            break;
+        }
+        files.push(rotated);
+    }
+    files.reverse();
+    files.push(current);
+
+    let mut out = Vec::new();
+    for path in files {
+        let Ok(raw) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        out.extend(
+            raw.lines()
+                .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok()),
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_all_returns_rotated_records_oldest_first() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let dir = tmp.path();
+        std::fs::write(dir.join("metrics.jsonl.2"), "{\"n\":1}\n").expect("write");
+        std::fs::write(dir.join("metrics.jsonl.1"), "{\"n\":2}\nnot json\n").expect("write");
+        std::fs::write(dir.join("metrics.jsonl"), "{\"n\":3}\n").expect("write");
+
+        let n: Vec<i64> = read_all(dir)
+            .iter()
+            .filter_map(|v| v["n"].as_i64())
+            .collect();
+        assert_eq!(n, vec![1, 2, 3]);
+    }
+}
@@ -1,4 +1,6 @@
 pub mod debug;
+pub mod llm_capture;
+pub mod lock_metrics;
 pub mod metrics;
 pub mod panic;
 pub mod schema;
@@ -7,6 +9,7 @@ pub mod trace;
 mod writer;
 
 pub use trace::{event, event_err, event_err_anyhow, ErrorEvent, Span};
+pub use writer::{dropped_records, DroppedRecords};
 
 const APP_DATA_DIR: &str = "com.typevoice.typevoice";
 const APP_DATA_SUBDIR: &str = "data";
@@ -1,14 +1,38 @@
-use std::{fs::OpenOptions, io::Write};
+use std::{fs::OpenOptions, io::Write, path::Path};
 
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 
 use super::schema::now_ms;
 use super::trace::redact_user_paths;
 
+const MAX_MESSAGE_CHARS: usize = 300;
+
 pub fn panic_trace_path(data_dir: &std::path::Path) -> std::path::PathBuf {
     data_dir.join("panic.jsonl")
 }
 
+// Panics newer than this have not been shown to the user yet.
+fn ack_path(data_dir: &Path) -> std::path::PathBuf {
+    data_dir.join("panic_ack.json")
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PanicAck {
+    acknowledged_ts_ms: i64,
+}
+
+// Panics recorded since the user last dismissed a crash notice.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CrashReport {
+    pub count: usize,
+    pub first_ts_ms: i64,
+    pub last_ts_ms: i64,
+    // First line of the newest panic message, already path-redacted.
+    pub message: String,
+    pub log_path: String,
+}
+
 pub fn install_best_effort() {
     std::panic::set_hook(Box::new(|info| {
         let bt = format!("{:?}", std::backtrace::Backtrace::force_capture());
@@ -31,3 +55,89 @@ pub fn install_best_effort() {
         }
     }));
 }
+
+pub fn unacknowledged(data_dir: &Path) -> Option<CrashReport> {
+    let raw = std::fs::read_to_string(panic_trace_path(data_dir)).ok()?;
+    let acked = std::fs::read(ack_path(data_dir))
+        .ok()
+        .and_then(|b| serde_json::from_slice::<PanicAck>(&b).ok())
+        .unwrap_or_default()
+        .acknowledged_ts_ms;
+    let mut report: Option<CrashReport> = None;
+    // A torn last line from a crash mid-write is skipped, not fatal.
+    for rec in raw
+        .lines()
+        .filter_map(|l| serde_json::from_str::<serde_json::Value>(l).ok())
+    {
+        let ts_ms = rec.get("ts_ms").and_then(|v| v.as_i64()).unwrap_or(0);
+        if ts_ms <= acked {
+            continue;
+        }
+        let message = rec
+            .get("message")
+            .and_then(|v| v.as_str())
+            .and_then(|m| m.lines().next())
+            .unwrap_or("")
+            .chars()
+            .take(MAX_MESSAGE_CHARS)
+            .collect();
+        let r = report.get_or_insert_with(|| CrashReport {
+            count: 0,
+            first_ts_ms: ts_ms,
+            last_ts_ms: ts_ms,
+            message: String::new(),
+            log_path: panic_trace_path(data_dir).display().to_string(),
+        });
+        r.count += 1;
+        r.first_ts_ms = r.first_ts_ms.min(ts_ms);
+        if ts_ms >= r.last_ts_ms {
+            r.last_ts_ms = ts_ms;
+            r.message = message;
+        }
+    }
+    report
+}
+
+// Takes the newest timestamp the user saw, so a panic logged after the notice
+// was built still shows next time.
+pub fn acknowledge(data_dir: &Path, up_to_ts_ms: i64) -> std::io::Result<()> {
+    let body = serde_json::to_vec(&PanicAck {
+        acknowledged_ts_ms: up_to_ts_ms,
+    })?;
+    std::fs::write(ack_path(data_dir), body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dismissed_panics_are_not_reported_again() {
+        let td = tempfile::tempdir().expect("tempdir");
+        let dir = td.path();
+        assert!(unacknowledged(dir).is_none());
+
+        let line =
+            |ts: i64, msg: &str| json!({"ts_ms": ts, "type": "panic", "message": msg}).to_string();
+        std::fs::write(
+            panic_trace_path(dir),
+            format!(
+                "{}\n{}\n{{\"ts_ms\": 9",
+                line(100, "panicked at src/a.rs:1:1:\nfirst"),
+                line(200, "panicked at src/b.rs:2:2:\nsecond")
+            ),
+        )
+        .expect("write");
+        let report = unacknowledged(dir).expect("report");
+        assert_eq!(
+            (report.count, report.first_ts_ms, report.last_ts_ms),
+            (2, 100, 200)
+        );
+        assert_eq!(report.message, "panicked at src/b.rs:2:2:");
+
+        acknowledge(dir, 100).expect("ack");
+        assert_eq!(unacknowledged(dir).expect("report").count, 1);
+        acknowledge(dir, report.last_ts_ms).expect("ack");
+        assert!(unacknowledged(dir).is_none());
+    }
+}
@@ -11,8 +11,10 @@ pub fn now_ms() -> i64 {
 
 #[derive(Debug, Clone, Serialize)]
 pub struct TraceError {
-    pub kind: String,    // winapi|http|io|process|logic|parse|unknown
-    pub code: String,    // E_* | HTTP_401 | WIN_LAST_ERROR_...
+    pub kind: String, // winapi|http|io|process|logic|parse|unknown
+    pub code: String, // E_* | HTTP_401 | WIN_LAST_ERROR_...
+    // Empty in the performance stream.
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub message: String, // short
     #[serde(skip_serializing_if = "Option::is_none")]
     pub raw: Option<String>,
@@ -69,6 +71,10 @@ pub enum MetricsRecord {
         asr_preprocess_threshold_db: f64,
         asr_preprocess_trim_start_ms: u64,
         asr_preprocess_trim_end_ms: u64,
+        // From the ffprobe pass over the input, before preprocessing.
+        input_format: Option<String>,
+        input_codec: Option<String>,
+        input_duration_ms: Option<u64>,
     },
     TaskDone {
         ts_ms: i64,
@@ -86,10 +92,58 @@ pub enum MetricsRecord {
         sha256: String,
         note: Option<String>,
     },
+    RewriteCache {
+        ts_ms: i64,
+        task_id: String,
+        hit: bool,
+        entries: usize,
+        hits: u64,
+        misses: u64,
+    },
+    WebhookDelivery {
+        ts_ms: i64,
+        task_id: String,
+        delivery_id: String,
+        status: String, // delivered|failed
+        attempts: u32,
+        http_status: Option<u16>,
+        elapsed_ms: u128,
+        error_code: Option<String>,
+    },
+    RecordInput {
+        ts_ms: i64,
+        task_id: String,
+        endpoint_id: Option<String>,
+        friendly_name: Option<String>,
+    },
+    // ASR text scored against the user's manual correction of it.
+    AsrAccuracy {
+        ts_ms: i64,
+        task_id: String,
+        asr_model: String,
+        microphone: String,
+        ref_words: usize,
+        word_errors: usize,
+        wer: f64,
+        ref_chars: usize,
+        char_errors: usize,
+        cer: f64,
+    },
     LoggerDropped {
         ts_ms: i64,
         stream: String,
         count: u64,
         queue_capacity: usize,
     },
+    LockContention {
+        ts_ms: i64,
+        lock: String,
+        window_ms: i64,
+        acquisitions: u64,
+        contended: u64,
+        wait_total_us: u64,
+        wait_max_us: u64,
+        hold_total_us: u64,
+        hold_max_us: u64,
+    },
 }
@@ -10,6 +10,36 @@ use super::schema::{now_ms, TraceError, TraceEvent};
 use super::writer;
 
 const DEFAULT_BACKTRACE_MAX_CHARS: usize = 12_000;
+// String ctx values that describe configuration or control flow rather than
+// user content; every other string stays out of the performance stream.
+const PERF_CTX_STRING_KEYS: &[&str] = &[
+    "api",
+    "code",
+    "device_used",
+    "error_code",
+    "export_format",
+    "format",
+    "input_method",
+    "kind",
+    "mode",
+    "model",
+    "model_id",
+    "output_format",
+    "phase",
+    "policy",
+    "priority",
+    "provider",
+    "record_input_strategy",
+    "source",
+    "stage",
+    "state",
+    "status",
+    "step",
+    "strategy",
+    "style",
+    "terminal_mode",
+    "type",
+];
 
 fn env_bool_default_true(key: &str) -> bool {
     match std::env::var(key) {
@@ -25,15 +55,44 @@ pub fn enabled() -> bool {
     env_bool_default_true("TYPEVOICE_TRACE_ENABLED")
 }
 
+// Opt-in: the payload stream may contain window titles, text snippets and raw
+// provider errors.
+pub fn payload_enabled() -> bool {
+    match std::env::var("TYPEVOICE_TRACE_PAYLOAD") {
+        Ok(_) => env_bool_default_true("TYPEVOICE_TRACE_PAYLOAD"),
+        Err(_) => false,
+    }
+}
+
+// Backtraces only ever reach the payload stream, so skip capturing them otherwise.
 fn backtrace_enabled() -> bool {
-    env_bool_default_true("TYPEVOICE_TRACE_BACKTRACE")
+    payload_enabled() && env_bool_default_true("TYPEVOICE_TRACE_BACKTRACE")
 }
 
-#[cfg_attr(not(test), allow(dead_code))]
 pub fn trace_path(data_dir: &Path) -> PathBuf {
     data_dir.join("trace.jsonl")
 }
 
+pub fn trace_payload_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("trace_payload.jsonl")
+}
+
+// One task's events, oldest first: the full payload events when that stream
+// has any for the task, otherwise the performance view. Call after a flush.
+pub fn read_task_events(data_dir: &Path, task_id: &str) -> Vec<Value> {
+    let for_task = |path: PathBuf| -> Vec<Value> {
+        super::metrics::read_rotated(&path)
+            .into_iter()
+            .filter(|v| v.get("task_id").and_then(Value::as_str) == Some(task_id))
+            .collect()
+    };
+    let payload = for_task(trace_payload_path(data_dir));
+    if !payload.is_empty() {
+        return payload;
+    }
+    for_task(trace_path(data_dir))
+}
+
 fn clamp_chars(s: &str, max_chars: usize) -> String {
     if max_chars == 0 {
         return String::new();
@@ -164,13 +223,63 @@ fn ctx_with_backtrace(extra: Option<Value>) -> Option<Value> {
     Some(merge_ctx(map, extra))
 }
 
+// Keeps what support needs to read timings and failures: ids, op/status,
+// duration, error kind/code, and numeric, boolean or allowlisted ctx fields.
+fn performance_view(ev: &TraceEvent) -> TraceEvent {
+    TraceEvent {
+        ts_ms: ev.ts_ms,
+        task_id: ev.task_id.clone(),
+        stage: ev.stage.clone(),
+        step_id: ev.step_id.clone(),
+        op: ev.op.clone(),
+        status: ev.status.clone(),
+        duration_ms: ev.duration_ms,
+        error: ev.error.as_ref().map(|e| TraceError {
+            kind: e.kind.clone(),
+            code: e.code.clone(),
+            message: String::new(),
+            raw: None,
+            debug: None,
+            chain: None,
+            source_type: None,
+        }),
+        ctx: ev
+            .ctx
+            .as_ref()
+            .and_then(performance_ctx)
+            .filter(|v| v.as_object().is_some_and(|m| !m.is_empty())),
+    }
+}
+
+fn performance_ctx(v: &Value) -> Option<Value> {
+    let map = v.as_object()?;
+    let mut out = serde_json::Map::new();
+    for (k, v) in map {
+        let keep = match v {
+            Value::Null | Value::Bool(_) | Value::Number(_) => Some(v.clone()),
+            Value::String(_) if PERF_CTX_STRING_KEYS.contains(&k.as_str()) => Some(v.clone()),
+            Value::Object(_) => performance_ctx(v),
+            _ => None,
+        };
+        if let Some(keep) = keep {
+            out.insert(k.clone(), keep);
+        }
+    }
+    Some(Value::Object(out))
+}
+
 fn emit_event(data_dir: &Path, ev: &TraceEvent) {
     if !enabled() {
         return;
     }
-    if let Err(e) = writer::emit_trace_event(data_dir, ev) {
+    if let Err(e) = writer::emit_trace_event(data_dir, &performance_view(ev)) {
         crate::safe_eprintln!("trace: emit failed: {e:#}");
     }
+    if payload_enabled() {
+        if let Err(e) = writer::emit_trace_payload_event(data_dir, ev) {
+            crate::safe_eprintln!("trace: payload emit failed: {e:#}");
+        }
+    }
 }
 
 pub fn event(
@@ -451,11 +560,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn read_task_events_keeps_one_task_oldest_first() {
+        let td = tempfile::tempdir().expect("tempdir");
+        let dir = td.path();
+        let line = |task: &str, step: &str| {
+            serde_json::json!({"task_id": task, "step_id": step}).to_string() + "\n"
+        };
+        fs::write(
+            dir.join("trace.jsonl.1"),
+            line("a", "A.one") + &line("b", "B.one"),
+        )
+        .expect("write");
+        fs::write(dir.join("trace.jsonl"), line("a", "A.two")).expect("write");
+
+        let steps: Vec<String> = read_task_events(dir, "a")
+            .iter()
+            .map(|v| v["step_id"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(steps, ["A.one", "A.two"]);
+
+        fs::write(dir.join("trace_payload.jsonl"), line("a", "A.full")).expect("write");
+        assert_eq!(read_task_events(dir, "a").len(), 1);
+        assert_eq!(read_task_events(dir, "b").len(), 1);
+    }
+
     #[test]
     fn event_err_serializes_raw_error_fields() {
         let _writer_guard = writer::test_writer_lock().lock().unwrap();
         let td = tempfile::tempdir().expect("tempdir");
         let dir = td.path().to_path_buf();
+        std::env::set_var("TYPEVOICE_TRACE_PAYLOAD", "1");
 
         event_err(
             &dir,
@@ -469,9 +604,10 @@ mod tests {
             },
             "read failed: raw detail",
         );
+        std::env::remove_var("TYPEVOICE_TRACE_PAYLOAD");
 
         assert!(writer::flush(2_000), "trace writer flush timeout");
-        let raw = fs::read_to_string(trace_path(&dir)).expect("read trace");
+        let raw = fs::read_to_string(trace_payload_path(&dir)).expect("read trace");
         let v: serde_json::Value =
             serde_json::from_str(raw.lines().last().expect("trace line")).expect("valid json");
         let error = v.get("error").expect("error object");
@@ -501,6 +637,7 @@ mod tests {
         let td = tempfile::tempdir().expect("tempdir");
         let dir = td.path().to_path_buf();
         let err = anyhow::anyhow!("root cause").context("outer context");
+        std::env::set_var("TYPEVOICE_TRACE_PAYLOAD", "1");
 
         event_err_anyhow(
             &dir,
@@ -514,9 +651,10 @@ mod tests {
             },
             &err,
         );
+        std::env::remove_var("TYPEVOICE_TRACE_PAYLOAD");
 
         assert!(writer::flush(2_000), "trace writer flush timeout");
-        let raw = fs::read_to_string(trace_path(&dir)).expect("read trace");
+        let raw = fs::read_to_string(trace_payload_path(&dir)).expect("read trace");
         let v: serde_json::Value =
             serde_json::from_str(raw.lines().last().expect("trace line")).expect("valid json");
         let error = v.get("error").expect("error object");
@@ -545,4 +683,49 @@ mod tests {
         assert_eq!(chain[0].as_str(), Some("outer context"));
         assert_eq!(chain[1].as_str(), Some("root cause"));
     }
+
+    #[test]
+    fn performance_stream_keeps_timings_and_codes_but_no_content() {
+        let _writer_guard = writer::test_writer_lock().lock().unwrap();
+        std::env::remove_var("TYPEVOICE_TRACE_PAYLOAD");
+        let td = tempfile::tempdir().expect("tempdir");
+        let dir = td.path().to_path_buf();
+
+        event_err(
+            &dir,
+            ErrorEvent {
+                task_id: Some("task-perf"),
+                stage: "TraceTest",
+                step_id: "TRACE.perf",
+                kind: "http",
+                code: "E_TRACE_PERF",
+                ctx: Some(serde_json::json!({
+                    "provider": "remote",
+                    "title": "Secret.docx - Word",
+                    "chars": 42,
+                    "window": {"has_title": true, "process": "winword.exe"},
+                })),
+            },
+            "body={\"text\":\"dictated words\"}",
+        );
+
+        assert!(writer::flush(2_000), "trace writer flush timeout");
+        let raw = fs::read_to_string(trace_path(&dir)).expect("read trace");
+        let v: serde_json::Value =
+            serde_json::from_str(raw.lines().last().expect("trace line")).expect("valid json");
+        assert_eq!(
+            v["error"],
+            serde_json::json!({"kind": "http", "code": "E_TRACE_PERF"})
+        );
+        assert_eq!(
+            v["ctx"],
+            serde_json::json!({
+                "provider": "remote",
+                "chars": 42,
+                "window": {"has_title": true},
+            })
+        );
+        assert!(!raw.contains("Secret.docx") && !raw.contains("dictated"));
+        assert!(!trace_payload_path(&dir).exists());
+    }
 }
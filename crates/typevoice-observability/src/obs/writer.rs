@@ -4,6 +4,7 @@ use std::{
     io::Write,
     path::{Path, PathBuf},
     sync::{
+        atomic::{AtomicU64, Ordering},
         mpsc::{self, Receiver, SyncSender, TrySendError},
         Mutex, OnceLock,
     },
@@ -11,18 +12,24 @@ use std::{
 };
 
 use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
 
 use super::schema::{now_ms, MetricsRecord, TraceEvent};
 
 const DEFAULT_QUEUE_CAPACITY: usize = 8192;
 const DEFAULT_TRACE_MAX_BYTES: u64 = 10_000_000;
 const DEFAULT_TRACE_MAX_FILES: usize = 5;
+const DEFAULT_TRACE_PAYLOAD_MAX_BYTES: u64 = 5_000_000;
+const DEFAULT_TRACE_PAYLOAD_MAX_FILES: usize = 2;
 const DEFAULT_METRICS_MAX_BYTES: u64 = 10_000_000;
 const DEFAULT_METRICS_MAX_FILES: usize = 5;
+// Records already queued are written together, one open and write per file.
+const MAX_BATCH_RECORDS: usize = 512;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum StreamKind {
     Trace,
+    TracePayload,
     Metrics,
 }
 
@@ -30,6 +37,7 @@ impl StreamKind {
     fn file_name(&self) -> &'static str {
         match self {
             Self::Trace => "trace.jsonl",
+            Self::TracePayload => "trace_payload.jsonl",
             Self::Metrics => "metrics.jsonl",
         }
     }
@@ -37,9 +45,42 @@ impl StreamKind {
     fn as_str(&self) -> &'static str {
         match self {
             Self::Trace => "trace",
+            Self::TracePayload => "trace_payload",
             Self::Metrics => "metrics",
         }
     }
+
+    fn total_dropped(&self) -> &'static AtomicU64 {
+        match self {
+            Self::Trace => &DROPPED_TOTAL[0],
+            Self::TracePayload => &DROPPED_TOTAL[1],
+            Self::Metrics => &DROPPED_TOTAL[2],
+        }
+    }
+}
+
+// Records dropped on a full queue since start, per stream. The per-dir counts
+// below are drained into logger_dropped records; these never reset, so they
+// still tell when the drops could not be written either.
+static DROPPED_TOTAL: [AtomicU64; 3] = [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DroppedRecords {
+    pub trace: u64,
+    pub trace_payload: u64,
+    pub metrics: u64,
+    pub queue_capacity: usize,
+}
+
+pub fn dropped_records() -> DroppedRecords {
+    DroppedRecords {
+        trace: StreamKind::Trace.total_dropped().load(Ordering::Relaxed),
+        trace_payload: StreamKind::TracePayload
+            .total_dropped()
+            .load(Ordering::Relaxed),
+        metrics: StreamKind::Metrics.total_dropped().load(Ordering::Relaxed),
+        queue_capacity: queue_capacity(),
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -88,6 +129,20 @@ fn trace_max_files() -> usize {
     env_usize("TYPEVOICE_TRACE_MAX_FILES", DEFAULT_TRACE_MAX_FILES)
 }
 
+fn trace_payload_max_bytes() -> u64 {
+    env_u64(
+        "TYPEVOICE_TRACE_PAYLOAD_MAX_BYTES",
+        DEFAULT_TRACE_PAYLOAD_MAX_BYTES,
+    )
+}
+
+fn trace_payload_max_files() -> usize {
+    env_usize(
+        "TYPEVOICE_TRACE_PAYLOAD_MAX_FILES",
+        DEFAULT_TRACE_PAYLOAD_MAX_FILES,
+    )
+}
+
 fn metrics_max_bytes() -> u64 {
     env_u64("TYPEVOICE_METRICS_MAX_BYTES", DEFAULT_METRICS_MAX_BYTES)
 }
@@ -120,6 +175,7 @@ pub(crate) fn test_writer_lock() -> &'static Mutex<()> {
 }
 
 fn note_dropped(data_dir: &Path, stream: StreamKind) {
+    stream.total_dropped().fetch_add(1, Ordering::Relaxed);
     let mut g = dropped_counts().lock().unwrap();
     let key = DropKey {
         data_dir: data_dir.to_path_buf(),
@@ -136,6 +192,7 @@ fn take_dropped_counts() -> HashMap<DropKey, u64> {
 fn rotation_for(stream: StreamKind) -> (u64, usize) {
     match stream {
         StreamKind::Trace => (trace_max_bytes(), trace_max_files()),
+        StreamKind::TracePayload => (trace_payload_max_bytes(), trace_payload_max_files()),
         StreamKind::Metrics => (metrics_max_bytes(), metrics_max_files()),
     }
 }
@@ -173,23 +230,63 @@ fn rotate_if_needed_best_effort(
     let _ = std::fs::rename(&p, &first);
 }
 
-fn append_line(data_dir: &Path, stream: StreamKind, line: &str) -> Result<()> {
-    std::fs::create_dir_all(data_dir).context("create data dir failed")?;
-    let (max_bytes, max_files) = rotation_for(stream);
-    rotate_if_needed_best_effort(data_dir, stream.file_name(), max_bytes, max_files);
-    let path = data_dir.join(stream.file_name());
+fn write_buf(path: &Path, file_name: &str, buf: &str) -> Result<()> {
+    if buf.is_empty() {
+        return Ok(());
+    }
     let mut f = OpenOptions::new()
         .create(true)
         .append(true)
-        .open(&path)
-        .with_context(|| format!("open {} failed: {}", stream.file_name(), path.display()))?;
-    f.write_all(line.as_bytes())
-        .with_context(|| format!("write {} line failed", stream.file_name()))?;
-    f.write_all(b"\n")
-        .with_context(|| format!("write {} newline failed", stream.file_name()))?;
+        .open(path)
+        .with_context(|| format!("open {file_name} failed: {}", path.display()))?;
+    f.write_all(buf.as_bytes())
+        .with_context(|| format!("write {file_name} lines failed"))?;
     Ok(())
 }
 
+// Rotation is checked per line as before, but the file is only reopened when a
+// batch actually crosses the size limit.
+fn append_lines(data_dir: &Path, stream: StreamKind, lines: &[String]) -> Result<()> {
+    std::fs::create_dir_all(data_dir).context("create data dir failed")?;
+    let (max_bytes, max_files) = rotation_for(stream);
+    let file_name = stream.file_name();
+    let path = data_dir.join(file_name);
+    let mut len = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    let mut buf = String::new();
+    for line in lines {
+        if len > max_bytes {
+            write_buf(&path, file_name, &buf)?;
+            buf.clear();
+            rotate_if_needed_best_effort(data_dir, file_name, max_bytes, max_files);
+            len = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        }
+        buf.push_str(line);
+        buf.push('\n');
+        len += line.len() as u64 + 1;
+    }
+    write_buf(&path, file_name, &buf)
+}
+
+// Groups by target file while keeping each file's lines in arrival order.
+fn write_batch(batch: Vec<RecordMsg>) {
+    let mut groups: Vec<(DropKey, Vec<String>)> = Vec::new();
+    for msg in batch {
+        let key = DropKey {
+            data_dir: msg.data_dir,
+            stream: msg.stream,
+        };
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, lines)) => lines.push(msg.line),
+            None => groups.push((key, vec![msg.line])),
+        }
+    }
+    for (key, lines) in groups {
+        if let Err(e) = append_lines(&key.data_dir, key.stream, &lines) {
+            crate::safe_eprintln!("obs writer: append failed: {e:#}");
+        }
+    }
+}
+
 fn emit_logger_dropped_direct(data_dir: &Path, stream: StreamKind, count: u64) {
     let record = MetricsRecord::LoggerDropped {
         ts_ms: now_ms(),
@@ -204,7 +301,7 @@ fn emit_logger_dropped_direct(data_dir: &Path, stream: StreamKind, count: u64) {
             return;
         }
     };
-    if let Err(e) = append_line(data_dir, StreamKind::Metrics, &line) {
+    if let Err(e) = append_lines(data_dir, StreamKind::Metrics, &[line]) {
         crate::safe_eprintln!("obs writer: write logger_dropped failed: {e:#}");
     }
 }
@@ -219,11 +316,24 @@ fn flush_dropped_counts() {
 fn writer_loop(rx: Receiver<Msg>) {
     loop {
         match rx.recv_timeout(Duration::from_millis(250)) {
-            Ok(Msg::Record(msg)) => {
-                if let Err(e) = append_line(&msg.data_dir, msg.stream, &msg.line) {
-                    crate::safe_eprintln!("obs writer: append failed: {e:#}");
+            Ok(Msg::Record(first)) => {
+                let mut batch = vec![first];
+                let mut flush_ack = None;
+                while batch.len() < MAX_BATCH_RECORDS {
+                    match rx.try_recv() {
+                        Ok(Msg::Record(msg)) => batch.push(msg),
+                        Ok(Msg::Flush(ack)) => {
+                            flush_ack = Some(ack);
+                            break;
+                        }
+                        Err(_) => break,
+                    }
                 }
+                write_batch(batch);
                 flush_dropped_counts();
+                if let Some(ack) = flush_ack {
+                    let _ = ack.send(());
+                }
             }
             Ok(Msg::Flush(ack)) => {
                 flush_dropped_counts();
@@ -262,6 +372,11 @@ pub fn emit_trace_event(data_dir: &Path, ev: &TraceEvent) -> Result<()> {
     emit_record_line(data_dir, StreamKind::Trace, line)
 }
 
+pub fn emit_trace_payload_event(data_dir: &Path, ev: &TraceEvent) -> Result<()> {
+    let line = serde_json::to_string(ev).context("serialize trace payload event failed")?;
+    emit_record_line(data_dir, StreamKind::TracePayload, line)
+}
+
 pub fn emit_metrics_record(data_dir: &Path, rec: &MetricsRecord) -> Result<()> {
     let line = serde_json::to_string(rec).context("serialize metrics record failed")?;
     emit_record_line(data_dir, StreamKind::Metrics, line)
@@ -331,6 +446,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn dropped_totals_survive_draining_into_records() {
+        let _writer_guard = test_writer_lock().lock().unwrap();
+        let td = tempfile::tempdir().expect("tempdir");
+        let before = dropped_records();
+        note_dropped(td.path(), StreamKind::Trace);
+        note_dropped(td.path(), StreamKind::Trace);
+        note_dropped(td.path(), StreamKind::Metrics);
+        flush_dropped_counts();
+
+        let after = dropped_records();
+        assert_eq!(after.trace - before.trace, 2);
+        assert_eq!(after.metrics - before.metrics, 1);
+        assert_eq!(after.trace_payload, before.trace_payload);
+        let raw = fs::read_to_string(td.path().join("metrics.jsonl")).expect("read metrics");
+        let counts: Vec<(String, u64)> = raw
+            .lines()
+            .map(|l| serde_json::from_str::<serde_json::Value>(l).expect("json"))
+            .map(|v| {
+                (
+                    v["stream"].as_str().unwrap().to_string(),
+                    v["count"].as_u64().unwrap(),
+                )
+            })
+            .collect();
+        assert!(counts.contains(&("trace".to_string(), 2)));
+        assert!(counts.contains(&("metrics".to_string(), 1)));
+    }
+
     #[test]
     fn trace_rotation_creates_suffix_file() {
         let _writer_guard = test_writer_lock().lock().unwrap();
@@ -0,0 +1,58 @@
+// Screen reader announcements for the hotkey flow. On Windows the main
+// window's host provider raises a UIA notification, which Narrator and NVDA
+// read even while another app has focus. Elsewhere the frontend's live region
+// carries the text through WebKit to AT-SPI / NSAccessibility, so this
+// reports false.
+pub fn announce(hwnd: Option<isize>, text: &str, important: bool) -> bool {
+    if text.trim().is_empty() {
+        return false;
+    }
+
+    #[cfg(windows)]
+    {
+        hwnd.is_some_and(|h| windows::raise_notification(h, text, important))
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = (hwnd, important);
+        false
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use windows::core::BSTR;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::System::Com::{CoInitializeEx, COINIT_MULTITHREADED};
+    use windows::Win32::UI::Accessibility::{
+        NotificationKind_Other, NotificationProcessing_ImportantMostRecent,
+        NotificationProcessing_MostRecent, UiaHostProviderFromHwnd, UiaRaiseNotificationEvent,
+    };
+
+    // One activity id so a newer status replaces a queued older one.
+    const ACTIVITY_ID: &str = "TypeVoice.status";
+
+    pub fn raise_notification(hwnd: isize, text: &str, important: bool) -> bool {
+        unsafe {
+            // Already initialized (either mode) is fine for raising events.
+            let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+            let Ok(provider) = UiaHostProviderFromHwnd(HWND(hwnd as *mut _)) else {
+                return false;
+            };
+            let processing = if important {
+                NotificationProcessing_ImportantMostRecent
+            } else {
+                NotificationProcessing_MostRecent
+            };
+            UiaRaiseNotificationEvent(
+                &provider,
+                NotificationKind_Other,
+                processing,
+                &BSTR::from(text),
+                &BSTR::from(ACTIVITY_ID),
+            )
+            .is_ok()
+        }
+    }
+}
@@ -1,11 +1,38 @@
 use std::path::Path;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 
 #[cfg(windows)]
 use serde_json::json;
 
 use crate::record_input_cache::RecordInputCacheState;
 
+// Payload of `tv_audio_devices_changed`, emitted by the app through the hook.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AudioDevicesChanged {
+    pub event_type: String,
+    pub endpoint_id: String,
+    pub flow: Option<String>,
+    pub role: Option<String>,
+    pub cache_invalidated: bool,
+    pub ts_ms: i64,
+}
+
+pub type AudioDevicesChangedHook = Arc<dyn Fn(AudioDevicesChanged) + Send + Sync>;
+
+#[cfg_attr(not(windows), allow(dead_code))]
+const DEVICE_STATE_ACTIVE_RAW: u32 = 0x1;
+
+// Removal, or any transition out of the active state (disabled, unplugged,
+// not present), means a resolution pointing at this endpoint is stale.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn is_endpoint_gone(event_type: &str, state: Option<u32>) -> bool {
+    match event_type {
+        "device_removed" => true,
+        "device_state_changed" => state.is_some_and(|v| v & DEVICE_STATE_ACTIVE_RAW == 0),
+        _ => false,
+    }
+}
+
 #[cfg_attr(not(windows), allow(dead_code))]
 pub struct AudioDeviceNotificationState {
     guard: Mutex<Option<AudioDeviceNotificationGuard>>,
@@ -18,11 +45,17 @@ impl AudioDeviceNotificationState {
         }
     }
 
-    pub fn start_best_effort(&self, data_dir: &Path, cache: RecordInputCacheState) {
+    pub fn start_best_effort(
+        &self,
+        data_dir: &Path,
+        cache: RecordInputCacheState,
+        on_change: AudioDevicesChangedHook,
+    ) {
         #[cfg(not(windows))]
         {
             let _ = data_dir;
             let _ = cache;
+            let _ = on_change;
         }
 
         #[cfg(windows)]
@@ -39,7 +72,7 @@ impl AudioDeviceNotificationState {
                 span.ok(Some(json!({ "already_running": true })));
                 return;
             }
-            match imp::start_listener(data_dir, cache) {
+            match imp::start_listener(data_dir, cache, on_change) {
                 Ok(listener_guard) => {
                     *g = Some(listener_guard);
                     span.ok(Some(json!({ "started": true })));
@@ -96,18 +129,22 @@ mod imp {
     };
     use windows::Win32::UI::Shell::PropertiesSystem::PROPERTYKEY;
 
-    use crate::audio_device_notifications_windows::AudioDeviceNotificationGuard;
+    use crate::audio_device_notifications_windows::{
+        is_endpoint_gone, AudioDeviceNotificationGuard, AudioDevicesChanged,
+        AudioDevicesChangedHook,
+    };
     use crate::record_input_cache::RecordInputCacheState;
 
     pub fn start_listener(
         data_dir: &Path,
         cache: RecordInputCacheState,
+        on_change: AudioDevicesChangedHook,
     ) -> Result<AudioDeviceNotificationGuard, String> {
         let (stop_tx, stop_rx) = mpsc::channel::<()>();
         let (init_tx, init_rx) = mpsc::channel::<Result<(), String>>();
         let data_dir_buf = data_dir.to_path_buf();
         let join = std::thread::spawn(move || {
-            listener_thread(data_dir_buf, cache, stop_rx, init_tx);
+            listener_thread(data_dir_buf, cache, on_change, stop_rx, init_tx);
         });
 
         match init_rx.recv_timeout(Duration::from_secs(3)) {
@@ -132,6 +169,7 @@ mod imp {
     fn listener_thread(
         data_dir: PathBuf,
         cache: RecordInputCacheState,
+        on_change: AudioDevicesChangedHook,
         stop_rx: mpsc::Receiver<()>,
         init_tx: mpsc::Sender<Result<(), String>>,
     ) {
@@ -157,6 +195,7 @@ mod imp {
         let client_impl = DeviceNotificationClient {
             data_dir: data_dir.clone(),
             cache,
+            on_change,
         };
         let client: IMMNotificationClient = client_impl.into();
 
@@ -208,6 +247,7 @@ mod imp {
     struct DeviceNotificationClient {
         data_dir: PathBuf,
         cache: RecordInputCacheState,
+        on_change: AudioDevicesChangedHook,
     }
 
     impl IMMNotificationClient_Impl for DeviceNotificationClient_Impl {
@@ -220,6 +260,7 @@ mod imp {
             emit_event(
                 &self.data_dir,
                 &self.cache,
+                &self.on_change,
                 DeviceEvent {
                     event_type: "device_state_changed",
                     flow: None,
@@ -237,6 +278,7 @@ mod imp {
             emit_event(
                 &self.data_dir,
                 &self.cache,
+                &self.on_change,
                 DeviceEvent {
                     event_type: "device_added",
                     flow: None,
@@ -254,6 +296,7 @@ mod imp {
             emit_event(
                 &self.data_dir,
                 &self.cache,
+                &self.on_change,
                 DeviceEvent {
                     event_type: "device_removed",
                     flow: None,
@@ -277,6 +320,7 @@ mod imp {
             emit_event(
                 &self.data_dir,
                 &self.cache,
+                &self.on_change,
                 DeviceEvent {
                     event_type: "default_device_changed",
                     flow: Some(flow),
@@ -298,6 +342,7 @@ mod imp {
             emit_event(
                 &self.data_dir,
                 &self.cache,
+                &self.on_change,
                 DeviceEvent {
                     event_type: "property_value_changed",
                     flow: None,
@@ -324,7 +369,17 @@ mod imp {
         should_refresh: bool,
     }
 
-    fn emit_event(data_dir: &Path, cache: &RecordInputCacheState, event: DeviceEvent<'_>) {
+    fn emit_event(
+        data_dir: &Path,
+        cache: &RecordInputCacheState,
+        on_change: &AudioDevicesChangedHook,
+        event: DeviceEvent<'_>,
+    ) {
+        let gone = is_endpoint_gone(event.event_type, event.state);
+        let cache_invalidated = gone && cache.invalidate_endpoint(event.endpoint_id);
+        let last_working_cleared = gone
+            && crate::record_input::forget_last_working_endpoint(data_dir, event.endpoint_id)
+                .unwrap_or(false);
         crate::obs::event(
             data_dir,
             None,
@@ -338,9 +393,24 @@ mod imp {
                 "endpoint_id": event.endpoint_id,
                 "state": event.state,
                 "refresh_requested": event.should_refresh,
+                "cache_invalidated": cache_invalidated,
+                "last_working_cleared": last_working_cleared,
             })),
         );
 
+        // Property changes fire on every volume tweak; the UI only cares about
+        // the device list itself.
+        if event.event_type != "property_value_changed" {
+            on_change(AudioDevicesChanged {
+                event_type: event.event_type.to_string(),
+                endpoint_id: event.endpoint_id.to_string(),
+                flow: event.flow.map(flow_label),
+                role: event.role.map(role_label),
+                cache_invalidated,
+                ts_ms: crate::obs::schema::now_ms(),
+            });
+        }
+
         if event.should_refresh {
             cache.request_refresh(
                 data_dir.to_path_buf(),
@@ -384,3 +454,17 @@ mod imp {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::is_endpoint_gone;
+
+    #[test]
+    fn removal_and_inactive_states_mark_endpoint_gone() {
+        assert!(is_endpoint_gone("device_removed", None));
+        assert!(is_endpoint_gone("device_state_changed", Some(0x4)));
+        assert!(!is_endpoint_gone("device_state_changed", Some(0x1)));
+        assert!(!is_endpoint_gone("device_added", None));
+        assert!(!is_endpoint_gone("default_device_changed", None));
+    }
+}
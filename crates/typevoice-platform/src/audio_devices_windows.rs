@@ -10,15 +10,46 @@ pub struct AudioEndpointInfo {
     pub friendly_name: String,
 }
 
+// Identity of a capture endpoint as seen by the device topology rather than
+// its id string. `endpoint_guid` is the GUID DirectShow uses in its
+// `wave_{...}` moniker; container and adapter ids identify the physical device
+// behind it.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct EndpointTopology {
+    pub endpoint_id: String,
+    pub friendly_name: String,
+    pub endpoint_guid: Option<String>,
+    pub container_id: Option<String>,
+    pub adapter_device_id: Option<String>,
+}
+
+// Shared-mode mix format of a capture endpoint as reported by WASAPI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct CaptureFormatInfo {
+    pub sample_rate_hz: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+}
+
+// Below 16 kHz is the narrowband telephony range, which is what a Bluetooth
+// headset exposes once it drops into the hands-free (HFP) profile.
+pub const LOW_QUALITY_SAMPLE_RATE_HZ: u32 = 16_000;
+
+pub fn is_low_quality_capture_format(format: &CaptureFormatInfo) -> bool {
+    format.sample_rate_hz < LOW_QUALITY_SAMPLE_RATE_HZ
+}
+
 #[cfg(windows)]
 mod imp {
-    use super::{AudioEndpointInfo, DefaultCaptureRole};
+    use super::{AudioEndpointInfo, CaptureFormatInfo, DefaultCaptureRole, EndpointTopology};
     use windows::core::{HRESULT, HSTRING, PWSTR};
-    use windows::Win32::Devices::FunctionDiscovery::PKEY_Device_FriendlyName;
+    use windows::Win32::Devices::FunctionDiscovery::{
+        PKEY_Device_ContainerId, PKEY_Device_FriendlyName,
+    };
     use windows::Win32::Foundation::RPC_E_CHANGED_MODE;
     use windows::Win32::Media::Audio::{
-        eCapture, eCommunications, eConsole, ERole, IMMDevice, IMMDeviceEnumerator,
-        MMDeviceEnumerator, DEVICE_STATE_ACTIVE,
+        eCapture, eCommunications, eConsole, ERole, IAudioClient, IDeviceTopology, IMMDevice,
+        IMMDeviceEnumerator, MMDeviceEnumerator, PKEY_AudioEndpoint_GUID, DEVICE_STATE_ACTIVE,
     };
     use windows::Win32::System::Com::StructuredStorage::PropVariantToStringAlloc;
     use windows::Win32::System::Com::{
@@ -124,6 +155,58 @@ mod imp {
         })
     }
 
+    // Topology lookups are best effort per field: a missing container id or an
+    // endpoint without connectors still leaves the GUID usable for mapping.
+    fn topology_from_device(device: &IMMDevice) -> Result<EndpointTopology, String> {
+        let endpoint = endpoint_from_device(device)?;
+        let (endpoint_guid, container_id) = unsafe {
+            let store = device.OpenPropertyStore(STGM_READ).map_err(|e| {
+                format!(
+                    "E_RECORD_INPUT_PROPERTY_STORE_FAILED: IMMDevice::OpenPropertyStore failed: {e}"
+                )
+            })?;
+            let endpoint_guid = store
+                .GetValue(&PKEY_AudioEndpoint_GUID)
+                .ok()
+                .and_then(|value| PropVariantToStringAlloc(&value).ok())
+                .map(|ptr| {
+                    let text = pwstr_to_string(ptr);
+                    CoTaskMemFree(Some(ptr.0.cast()));
+                    text
+                });
+            let container_id = store
+                .GetValue(&PKEY_Device_ContainerId)
+                .ok()
+                .and_then(|value| PropVariantToStringAlloc(&value).ok())
+                .map(|ptr| {
+                    let text = pwstr_to_string(ptr);
+                    CoTaskMemFree(Some(ptr.0.cast()));
+                    text
+                });
+            (endpoint_guid, container_id)
+        };
+        let adapter_device_id = unsafe {
+            device
+                .Activate::<IDeviceTopology>(CLSCTX_ALL, None)
+                .and_then(|topology| topology.GetConnector(0))
+                .and_then(|connector| connector.GetDeviceIdConnectedTo())
+                .ok()
+                .map(|ptr| {
+                    let text = pwstr_to_string(ptr);
+                    CoTaskMemFree(Some(ptr.0.cast()));
+                    text
+                })
+        };
+        let non_empty = |v: Option<String>| v.filter(|s| !s.trim().is_empty());
+        Ok(EndpointTopology {
+            endpoint_id: endpoint.endpoint_id,
+            friendly_name: endpoint.friendly_name,
+            endpoint_guid: non_empty(endpoint_guid),
+            container_id: non_empty(container_id),
+            adapter_device_id: non_empty(adapter_device_id),
+        })
+    }
+
     fn role_to_erole(role: DefaultCaptureRole) -> ERole {
         match role {
             DefaultCaptureRole::Communications => eCommunications,
@@ -164,6 +247,86 @@ mod imp {
         })
     }
 
+    pub fn get_capture_endpoint_format(endpoint_id: &str) -> Result<CaptureFormatInfo, String> {
+        let trimmed = endpoint_id.trim();
+        if trimmed.is_empty() {
+            return Err("E_RECORD_INPUT_FORMAT_FAILED: endpoint id is empty".to_string());
+        }
+        with_enumerator(|enumerator| {
+            let target = HSTRING::from(trimmed);
+            unsafe {
+                let device = enumerator.GetDevice(&target).map_err(|e| {
+                    format!(
+                        "E_RECORD_INPUT_FORMAT_FAILED: IMMDeviceEnumerator::GetDevice failed: {e}"
+                    )
+                })?;
+                let client: IAudioClient = device.Activate(CLSCTX_ALL, None).map_err(|e| {
+                    format!("E_RECORD_INPUT_FORMAT_FAILED: IMMDevice::Activate failed: {e}")
+                })?;
+                let format_ptr = client.GetMixFormat().map_err(|e| {
+                    format!("E_RECORD_INPUT_FORMAT_FAILED: IAudioClient::GetMixFormat failed: {e}")
+                })?;
+                if format_ptr.is_null() {
+                    return Err("E_RECORD_INPUT_FORMAT_FAILED: mix format is null".to_string());
+                }
+                let format = *format_ptr;
+                CoTaskMemFree(Some(format_ptr.cast()));
+                Ok(CaptureFormatInfo {
+                    sample_rate_hz: format.nSamplesPerSec,
+                    channels: format.nChannels,
+                    bits_per_sample: format.wBitsPerSample,
+                })
+            }
+        })
+    }
+
+    pub fn get_capture_endpoint_topology(endpoint_id: &str) -> Result<EndpointTopology, String> {
+        let trimmed = endpoint_id.trim();
+        if trimmed.is_empty() {
+            return Err("E_RECORD_INPUT_TOPOLOGY_FAILED: endpoint id is empty".to_string());
+        }
+        with_enumerator(|enumerator| {
+            let target = HSTRING::from(trimmed);
+            let device = unsafe {
+                enumerator.GetDevice(&target).map_err(|e| {
+                    format!(
+                        "E_RECORD_INPUT_TOPOLOGY_FAILED: IMMDeviceEnumerator::GetDevice failed: {e}"
+                    )
+                })?
+            };
+            topology_from_device(&device)
+        })
+    }
+
+    pub fn list_active_capture_topologies() -> Result<Vec<EndpointTopology>, String> {
+        with_enumerator(|enumerator| {
+            let collection = unsafe {
+                enumerator
+                    .EnumAudioEndpoints(eCapture, DEVICE_STATE_ACTIVE)
+                    .map_err(|e| {
+                        format!(
+                            "E_RECORD_INPUT_ENUM_FAILED: IMMDeviceEnumerator::EnumAudioEndpoints failed: {e}"
+                        )
+                    })?
+            };
+            let count = unsafe {
+                collection.GetCount().map_err(|e| {
+                    format!("E_RECORD_INPUT_ENUM_FAILED: IMMDeviceCollection::GetCount failed: {e}")
+                })?
+            };
+            let mut out = Vec::with_capacity(count as usize);
+            for idx in 0..count {
+                let device = unsafe {
+                    collection.Item(idx).map_err(|e| {
+                        format!("E_RECORD_INPUT_ENUM_FAILED: IMMDeviceCollection::Item failed: {e}")
+                    })?
+                };
+                out.push(topology_from_device(&device)?);
+            }
+            Ok(out)
+        })
+    }
+
     pub fn list_active_capture_endpoints() -> Result<Vec<AudioEndpointInfo>, String> {
         with_enumerator(|enumerator| {
             let collection = unsafe {
@@ -194,24 +357,123 @@ mod imp {
     }
 }
 
-#[cfg(windows)]
 pub use imp::{
-    get_capture_endpoint_by_id, get_default_capture_endpoint, list_active_capture_endpoints,
+    get_capture_endpoint_by_id, get_capture_endpoint_format, get_capture_endpoint_topology,
+    get_default_capture_endpoint, list_active_capture_endpoints, list_active_capture_topologies,
 };
 
+// Elsewhere devices come from cpal's default host (ALSA, which PulseAudio and
+// PipeWire expose through their plugins, on Linux; CoreAudio on macOS). cpal
+// has no stable endpoint ids, so the device name doubles as the id and both
+// default roles map to the host's default input.
 #[cfg(not(windows))]
-pub fn get_default_capture_endpoint(
-    _role: DefaultCaptureRole,
-) -> Result<AudioEndpointInfo, String> {
-    Err("E_RECORD_UNSUPPORTED: backend recording is only supported on Windows".to_string())
-}
+mod imp {
+    use super::{AudioEndpointInfo, CaptureFormatInfo, DefaultCaptureRole, EndpointTopology};
+    use cpal::traits::{DeviceTrait, HostTrait};
 
-#[cfg(not(windows))]
-pub fn get_capture_endpoint_by_id(_endpoint_id: &str) -> Result<AudioEndpointInfo, String> {
-    Err("E_RECORD_UNSUPPORTED: backend recording is only supported on Windows".to_string())
+    fn endpoint(device: &cpal::Device) -> Result<AudioEndpointInfo, String> {
+        let name = device
+            .name()
+            .map_err(|e| format!("E_RECORD_INPUT_DISCOVERY_FAILED: device name: {e}"))?;
+        Ok(AudioEndpointInfo {
+            endpoint_id: name.clone(),
+            friendly_name: name,
+        })
+    }
+
+    fn find_input(endpoint_id: &str) -> Result<cpal::Device, String> {
+        let devices = cpal::default_host()
+            .input_devices()
+            .map_err(|e| format!("E_RECORD_INPUT_DISCOVERY_FAILED: enumerate inputs: {e}"))?;
+        for device in devices {
+            if device.name().is_ok_and(|n| n == endpoint_id) {
+                return Ok(device);
+            }
+        }
+        Err(format!(
+            "E_RECORD_DEVICE_NOT_FOUND: no input device named \"{endpoint_id}\""
+        ))
+    }
+
+    pub fn get_default_capture_endpoint(
+        _role: DefaultCaptureRole,
+    ) -> Result<AudioEndpointInfo, String> {
+        let device = cpal::default_host().default_input_device().ok_or_else(|| {
+            "E_RECORD_INPUT_DISCOVERY_FAILED: no default input device".to_string()
+        })?;
+        endpoint(&device)
+    }
+
+    pub fn get_capture_endpoint_by_id(endpoint_id: &str) -> Result<AudioEndpointInfo, String> {
+        endpoint(&find_input(endpoint_id)?)
+    }
+
+    pub fn list_active_capture_endpoints() -> Result<Vec<AudioEndpointInfo>, String> {
+        let devices = cpal::default_host()
+            .input_devices()
+            .map_err(|e| format!("E_RECORD_INPUT_DISCOVERY_FAILED: enumerate inputs: {e}"))?;
+        let mut out: Vec<AudioEndpointInfo> = Vec::new();
+        for device in devices {
+            // Devices whose name cannot be read cannot be selected again later.
+            if let Ok(info) = endpoint(&device) {
+                if !out.iter().any(|e| e.endpoint_id == info.endpoint_id) {
+                    out.push(info);
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn get_capture_endpoint_format(endpoint_id: &str) -> Result<CaptureFormatInfo, String> {
+        let config = find_input(endpoint_id)?
+            .default_input_config()
+            .map_err(|e| format!("E_RECORD_INPUT_FORMAT_FAILED: default input config: {e}"))?;
+        Ok(CaptureFormatInfo {
+            sample_rate_hz: config.sample_rate().0,
+            channels: config.channels(),
+            bits_per_sample: (config.sample_format().sample_size() * 8) as u16,
+        })
+    }
+
+    pub fn get_capture_endpoint_topology(endpoint_id: &str) -> Result<EndpointTopology, String> {
+        let info = get_capture_endpoint_by_id(endpoint_id)?;
+        Ok(EndpointTopology {
+            endpoint_id: info.endpoint_id,
+            friendly_name: info.friendly_name,
+            endpoint_guid: None,
+            container_id: None,
+            adapter_device_id: None,
+        })
+    }
+
+    pub fn list_active_capture_topologies() -> Result<Vec<EndpointTopology>, String> {
+        Ok(list_active_capture_endpoints()?
+            .into_iter()
+            .map(|info| EndpointTopology {
+                endpoint_id: info.endpoint_id,
+                friendly_name: info.friendly_name,
+                endpoint_guid: None,
+                container_id: None,
+                adapter_device_id: None,
+            })
+            .collect())
+    }
 }
 
-#[cfg(not(windows))]
-pub fn list_active_capture_endpoints() -> Result<Vec<AudioEndpointInfo>, String> {
-    Err("E_RECORD_UNSUPPORTED: backend recording is only supported on Windows".to_string())
+#[cfg(test)]
+mod tests {
+    use super::{is_low_quality_capture_format, CaptureFormatInfo};
+
+    #[test]
+    fn hands_free_narrowband_format_is_low_quality() {
+        let format = |sample_rate_hz| CaptureFormatInfo {
+            sample_rate_hz,
+            channels: 1,
+            bits_per_sample: 16,
+        };
+
+        assert!(is_low_quality_capture_format(&format(8_000)));
+        assert!(!is_low_quality_capture_format(&format(16_000)));
+        assert!(!is_low_quality_capture_format(&format(48_000)));
+    }
 }
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use std::path::Path;
 
 use crate::context_pack::{ContextBudget, ContextSnapshot, HistorySnippet};
-use crate::{history, settings};
+use crate::{formatting_profiles, history, settings, workspaces};
 use crate::{obs, obs::Span};
 #[cfg(windows)]
 use anyhow::{anyhow, Result};
@@ -16,9 +16,11 @@ use crate::obs::debug;
 #[derive(Debug, Clone)]
 pub struct ContextConfig {
     pub include_history: bool,
+    pub history_same_app: bool,
     pub include_clipboard: bool,
     pub include_prev_window_meta: bool,
     pub include_prev_window_screenshot: bool,
+    pub injection_guard: bool,
     pub budget: ContextBudget,
     pub llm_supports_vision: bool,
 }
@@ -27,9 +29,11 @@ impl Default for ContextConfig {
     fn default() -> Self {
         Self {
             include_history: true,
+            history_same_app: false,
             include_clipboard: true,
             include_prev_window_meta: true,
             include_prev_window_screenshot: true,
+            injection_guard: true,
             budget: ContextBudget::default(),
             llm_supports_vision: true,
         }
@@ -59,6 +63,15 @@ pub fn config_from_settings(s: &settings::Settings) -> ContextConfig {
     if let Some(v) = s.context_include_history {
         cfg.include_history = v;
     }
+    if let Some(v) = s.context_history_same_app {
+        cfg.history_same_app = v;
+    }
+    if let Some(v) = s.context_history_summarize {
+        cfg.budget.summarize_older_history = v;
+    }
+    if let Some(v) = s.context_injection_guard_enabled {
+        cfg.injection_guard = v;
+    }
     if let Some(v) = s.llm_supports_vision {
         cfg.llm_supports_vision = v;
     }
@@ -76,6 +89,38 @@ pub fn config_from_settings(s: &settings::Settings) -> ContextConfig {
     cfg
 }
 
+// Screenshots of a shared screen would capture what the audience sees, so
+// they are skipped while a share is detected. Low-power mode skips them too;
+// capture and PNG encoding are the costliest part of the context.
+fn screenshot_allowed(data_dir: &Path, task_id: Option<&str>, cfg: &ContextConfig) -> bool {
+    if !cfg.include_prev_window_screenshot {
+        return false;
+    }
+    if let Some(reason) = crate::power::active() {
+        obs::event(
+            data_dir,
+            task_id,
+            "ContextCapture",
+            "CTX.low_power_guard",
+            "ok",
+            Some(serde_json::json!({ "reason": reason, "skipped": "screenshot" })),
+        );
+        return false;
+    }
+    let Some(app) = crate::screen_share::active() else {
+        return true;
+    };
+    obs::event(
+        data_dir,
+        task_id,
+        "ContextCapture",
+        "CTX.screen_share_guard",
+        "ok",
+        Some(serde_json::json!({ "app": app, "skipped": "screenshot" })),
+    );
+    false
+}
+
 #[cfg(windows)]
 fn env_u32(key: &str, default: u32) -> u32 {
     match std::env::var(key) {
@@ -92,7 +137,7 @@ fn env_u32(key: &str, default: u32) -> u32 {
 #[derive(Clone)]
 pub struct ContextService {
     #[cfg(windows)]
-    inner: std::sync::Arc<std::sync::Mutex<Inner>>,
+    inner: std::sync::Arc<obs::lock_metrics::TimedMutex<Inner>>,
 }
 
 #[cfg(windows)]
@@ -116,7 +161,10 @@ impl ContextService {
                 hotkey_capture_registry: HashMap::new(),
             };
             Self {
-                inner: std::sync::Arc::new(std::sync::Mutex::new(inner)),
+                inner: std::sync::Arc::new(obs::lock_metrics::TimedMutex::new(
+                    "ContextService.inner",
+                    inner,
+                )),
             }
         }
         #[cfg(not(windows))]
@@ -152,7 +200,7 @@ impl ContextService {
             })),
         );
 
-        if !cfg.include_prev_window_screenshot {
+        if !screenshot_allowed(data_dir, None, cfg) {
             let mut g = self.inner.lock().unwrap();
             let mut snapshot = ContextSnapshot {
                 recent_history: vec![],
@@ -317,51 +365,6 @@ impl ContextService {
 
         let mut snap = ContextSnapshot::default();
 
-        if cfg.include_history && cfg.budget.max_history_items > 0 {
-            let db = data_dir.join("history.sqlite3");
-            let before = Some(captured_at_ms);
-            let span = Span::start(
-                data_dir,
-                Some(task_id),
-                "ContextCapture",
-                "CTX.history.list",
-                Some(serde_json::json!({
-                    "limit": (cfg.budget.max_history_items as i64).max(1),
-                    "before_ms": before,
-                })),
-            );
-            match history::list(&db, (cfg.budget.max_history_items as i64).max(1), before) {
-                Ok(mut rows) => {
-                    let min_ms = captured_at_ms.saturating_sub(cfg.budget.history_window_ms);
-                    rows.retain(|h| h.created_at_ms >= min_ms);
-                    snap.recent_history = rows
-                        .into_iter()
-                        .map(|h| HistorySnippet {
-                            created_at_ms: h.created_at_ms,
-                            asr_text: h.asr_text,
-                            final_text: h.final_text,
-                            template_id: h.template_id,
-                        })
-                        .collect();
-                    span.ok(Some(serde_json::json!({
-                        "items": snap.recent_history.len(),
-                        "min_ms": min_ms,
-                    })));
-                }
-                Err(e) => {
-                    span.err(
-                        "io",
-                        "E_HISTORY_LIST",
-                        &e.to_string(),
-                        Some(serde_json::json!({
-                            "db": "history.sqlite3",
-                        })),
-                    );
-                    // best-effort: ignore history failures.
-                }
-            }
-        }
-
         if cfg.include_clipboard {
             #[cfg(windows)]
             {
@@ -417,7 +420,7 @@ impl ContextService {
             }
         }
 
-        if cfg.include_prev_window_screenshot {
+        if screenshot_allowed(data_dir, Some(task_id), cfg) {
             #[cfg(windows)]
             {
                 let g = self.inner.lock().unwrap();
@@ -508,6 +511,70 @@ impl ContextService {
             }
         }
 
+        if cfg.include_history && cfg.budget.max_history_items > 0 {
+            let db = workspaces::history_db_path(data_dir);
+            let before = Some(captured_at_ms);
+            // Needs the foreground app, so it runs after the window capture;
+            // without one the filter is dropped rather than leaving no history.
+            let same_app = cfg
+                .history_same_app
+                .then(|| snap.prev_window.as_ref()?.process_image.as_deref())
+                .flatten()
+                .map(formatting_profiles::process_file_name);
+            let same_app = same_app.as_deref();
+            let extra = if cfg.budget.summarize_older_history {
+                crate::context_pack::HISTORY_SUMMARY_EXTRA_ITEMS
+            } else {
+                0
+            };
+            let limit = ((cfg.budget.max_history_items + extra) as i64).max(1);
+            let span = Span::start(
+                data_dir,
+                Some(task_id),
+                "ContextCapture",
+                "CTX.history.list",
+                Some(serde_json::json!({
+                    "limit": limit,
+                    "before_ms": before,
+                    "same_app": same_app.is_some(),
+                })),
+            );
+            let rows = match same_app {
+                Some(app) => history::list_for_app(&db, app, limit, captured_at_ms),
+                None => history::list(&db, limit, before),
+            };
+            match rows {
+                Ok(mut rows) => {
+                    let min_ms = captured_at_ms.saturating_sub(cfg.budget.history_window_ms);
+                    rows.retain(|h| h.created_at_ms >= min_ms);
+                    snap.recent_history = rows
+                        .into_iter()
+                        .map(|h| HistorySnippet {
+                            created_at_ms: h.created_at_ms,
+                            asr_text: h.asr_text,
+                            final_text: h.final_text,
+                            template_id: h.template_id,
+                        })
+                        .collect();
+                    span.ok(Some(serde_json::json!({
+                        "items": snap.recent_history.len(),
+                        "min_ms": min_ms,
+                    })));
+                }
+                Err(e) => {
+                    span.err(
+                        "io",
+                        "E_HISTORY_LIST",
+                        &e.to_string(),
+                        Some(serde_json::json!({
+                            "db": "history.sqlite3",
+                        })),
+                    );
+                    // best-effort: ignore history failures.
+                }
+            }
+        }
+
         // Mark the overall span as ok (it may contain inner errs/skips).
         // Note: we intentionally do not fail the pipeline based on context capture.
         obs::event(
@@ -136,22 +136,7 @@ impl WindowsContext {
     }
 
     pub fn foreground_window_info_best_effort(&self) -> Option<WindowInfo> {
-        let hwnd = unsafe { GetForegroundWindow() };
-        if hwnd.is_null() {
-            return None;
-        }
-        if unsafe { IsWindow(hwnd) } == 0 {
-            return None;
-        }
-        let mut pid: u32 = 0;
-        unsafe { GetWindowThreadProcessId(hwnd, &mut pid) };
-        if pid == 0 {
-            return None;
-        }
-        Some(WindowInfo {
-            title: get_window_title_best_effort(hwnd),
-            process_image: get_process_image_best_effort(pid),
-        })
+        foreground_window_info_now_best_effort()
     }
 
     pub fn capture_last_external_window_png_best_effort(
@@ -320,6 +305,11 @@ struct ExternalSnapshot {
     process_image: Option<String>,
 }
 
+const TRACK_INTERVAL: Duration = Duration::from_millis(80);
+// Slower polling in low-power mode; a focus change shorter than this may be
+// missed, which only affects which window the context describes.
+const LOW_POWER_TRACK_INTERVAL: Duration = Duration::from_millis(400);
+
 #[derive(Clone)]
 struct ForegroundTracker {
     started: Arc<AtomicBool>,
@@ -367,7 +357,12 @@ impl ForegroundTracker {
                         g.process_image = img;
                     }
                 }
-                std::thread::sleep(Duration::from_millis(80));
+                let interval = if crate::power::active().is_some() {
+                    LOW_POWER_TRACK_INTERVAL
+                } else {
+                    TRACK_INTERVAL
+                };
+                std::thread::sleep(interval);
             })
             .ok();
     }
@@ -377,6 +372,40 @@ impl ForegroundTracker {
     }
 }
 
+pub fn foreground_window_info_now_best_effort() -> Option<WindowInfo> {
+    let hwnd = unsafe { GetForegroundWindow() };
+    if hwnd.is_null() {
+        return None;
+    }
+    if unsafe { IsWindow(hwnd) } == 0 {
+        return None;
+    }
+    let mut pid: u32 = 0;
+    unsafe { GetWindowThreadProcessId(hwnd, &mut pid) };
+    if pid == 0 {
+        return None;
+    }
+    Some(WindowInfo {
+        title: get_window_title_best_effort(hwnd),
+        process_image: get_process_image_best_effort(pid),
+    })
+}
+
+pub fn window_info_best_effort(hwnd: isize) -> Option<WindowInfo> {
+    let hwnd = hwnd as HWND;
+    if hwnd.is_null() || unsafe { IsWindow(hwnd) } == 0 {
+        return None;
+    }
+    let mut pid: u32 = 0;
+    unsafe { GetWindowThreadProcessId(hwnd, &mut pid) };
+    Some(WindowInfo {
+        title: get_window_title_best_effort(hwnd),
+        process_image: (pid != 0)
+            .then(|| get_process_image_best_effort(pid))
+            .flatten(),
+    })
+}
+
 fn get_window_title_best_effort(hwnd: HWND) -> Option<String> {
     let len = unsafe { GetWindowTextLengthW(hwnd) };
     if len <= 0 {
@@ -391,7 +420,7 @@ fn get_window_title_best_effort(hwnd: HWND) -> Option<String> {
     Some(String::from_utf16_lossy(&buf).trim().to_string())
 }
 
-fn get_process_image_best_effort(pid: u32) -> Option<String> {
+pub(crate) fn get_process_image_best_effort(pid: u32) -> Option<String> {
     unsafe {
         let h = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
         if h.is_null() {
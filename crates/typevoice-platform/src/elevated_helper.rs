@@ -0,0 +1,328 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::context_pack::sha256_hex;
+use crate::export::{ExportError, InputMethod};
+use crate::formatting_profiles::TerminalPasteMode;
+
+// Windows drops input from a normal process into an elevated one (UIPI). When
+// enabled in settings, TypeVoice relaunches itself through UAC with this
+// argument and the elevated copy performs a single injection, then exits.
+// The request file lives in the user-writable data dir, so the command line
+// also carries a per-request nonce and the payload's SHA-256; the helper
+// refuses any file that does not match both.
+pub const HELPER_ARG: &str = "--typevoice-inject-helper";
+
+#[cfg(windows)]
+const EXIT_OK: i32 = 0;
+const EXIT_BAD_REQUEST: i32 = 2;
+const EXIT_INJECT_FAILED: i32 = 3;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct HelperRequest {
+    nonce: String,
+    text: String,
+    terminal_mode: Option<TerminalPasteMode>,
+    target_hwnd: Option<isize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct HelperArgs {
+    path: PathBuf,
+    nonce: String,
+    sha256: String,
+}
+
+fn helper_args_from(args: &[String]) -> Option<HelperArgs> {
+    let i = args.iter().position(|a| a == HELPER_ARG)?;
+    let arg = |n: usize| args.get(i + n).filter(|v| !v.trim().is_empty());
+    Some(HelperArgs {
+        path: PathBuf::from(arg(1)?),
+        nonce: arg(2)?.clone(),
+        sha256: arg(3)?.clone(),
+    })
+}
+
+// Only a request whose bytes hash to the launcher's digest and that carries
+// the launcher's nonce is trusted.
+fn verified_request(bytes: &[u8], args: &HelperArgs) -> Option<HelperRequest> {
+    if sha256_hex(bytes) != args.sha256 {
+        return None;
+    }
+    serde_json::from_slice::<HelperRequest>(bytes)
+        .ok()
+        .filter(|req| req.nonce == args.nonce)
+}
+
+// Called first thing at startup. Returns the exit code when this process was
+// launched as the helper, None for a normal start.
+pub fn run_from_args() -> Option<i32> {
+    let args: Vec<String> = std::env::args().collect();
+    let args = helper_args_from(&args)?;
+    Some(run_helper(&args))
+}
+
+fn run_helper(args: &HelperArgs) -> i32 {
+    let req = fs::read(&args.path)
+        .ok()
+        .and_then(|b| verified_request(&b, args));
+    // The request holds dictated text; it never outlives the helper.
+    let _ = fs::remove_file(&args.path);
+    let Some(req) = req else {
+        return EXIT_BAD_REQUEST;
+    };
+
+    #[cfg(windows)]
+    {
+        let _ = crate::export::focus_window_best_effort(req.target_hwnd);
+        std::thread::sleep(std::time::Duration::from_millis(80));
+        match crate::export::inject_in_process(&req.text, req.terminal_mode) {
+            Ok(_) => EXIT_OK,
+            Err(_) => EXIT_INJECT_FAILED,
+        }
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = req;
+        EXIT_INJECT_FAILED
+    }
+}
+
+// Hands the injection to an elevated copy of TypeVoice. `text` must already be
+// prepared for the target (terminal text when `terminal_mode` is set).
+pub async fn paste_via_helper(
+    data_dir: &Path,
+    text: &str,
+    terminal_mode: Option<TerminalPasteMode>,
+    target_hwnd: Option<isize>,
+) -> Result<InputMethod, ExportError> {
+    let dir = data_dir.join("helper");
+    fs::create_dir_all(&dir).map_err(|e| {
+        ExportError::new(
+            "E_EXPORT_ELEVATED_HELPER_FAILED",
+            format!("create helper dir failed: {e}"),
+        )
+    })?;
+    let path = dir.join(format!("inject-{}.json", uuid::Uuid::new_v4()));
+    let nonce = uuid::Uuid::new_v4().simple().to_string();
+    let req = HelperRequest {
+        nonce: nonce.clone(),
+        text: text.to_string(),
+        terminal_mode,
+        target_hwnd,
+    };
+    let bytes = serde_json::to_vec(&req).map_err(|e| {
+        ExportError::new(
+            "E_EXPORT_ELEVATED_HELPER_FAILED",
+            format!("serialize helper request failed: {e}"),
+        )
+    })?;
+    let args = HelperArgs {
+        path: path.clone(),
+        nonce,
+        sha256: sha256_hex(&bytes),
+    };
+    fs::write(&path, bytes).map_err(|e| {
+        ExportError::new(
+            "E_EXPORT_ELEVATED_HELPER_FAILED",
+            format!("write helper request failed: {e}"),
+        )
+    })?;
+
+    let launched = launch(&args).await;
+    // Normally the helper removed it already; a declined prompt leaves it.
+    let _ = fs::remove_file(&path);
+    launched.map(|()| InputMethod::ElevatedHelper)
+}
+
+#[cfg(windows)]
+async fn launch(args: &HelperArgs) -> Result<(), ExportError> {
+    imp::launch(args).await
+}
+
+#[cfg(not(windows))]
+async fn launch(_args: &HelperArgs) -> Result<(), ExportError> {
+    Err(ExportError::new(
+        "E_EXPORT_PASTE_UNSUPPORTED",
+        "the elevated paste helper is only available on Windows",
+    ))
+}
+
+#[cfg(windows)]
+mod imp {
+    use super::{ExportError, HelperArgs, HELPER_ARG};
+    use std::ffi::OsStr;
+    use std::mem;
+    use std::os::windows::ffi::OsStrExt;
+    use std::time::{Duration, Instant};
+    use windows_sys::Win32::Foundation::{CloseHandle, GetLastError, ERROR_CANCELLED, HANDLE};
+    use windows_sys::Win32::System::Threading::{GetExitCodeProcess, TerminateProcess};
+    use windows_sys::Win32::UI::Shell::{
+        ShellExecuteExW, SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::SW_HIDE;
+
+    // Covers the time the user needs to answer the UAC prompt.
+    const HELPER_TIMEOUT: Duration = Duration::from_secs(60);
+    const POLL_INTERVAL: Duration = Duration::from_millis(100);
+    const STILL_ACTIVE: u32 = 259;
+
+    fn wide(s: &OsStr) -> Vec<u16> {
+        s.encode_wide().chain(std::iter::once(0)).collect()
+    }
+
+    // The process handle is kept as an integer so the polling future stays Send.
+    fn spawn_elevated(args: &HelperArgs) -> Result<isize, ExportError> {
+        let exe = std::env::current_exe().map_err(|e| {
+            ExportError::new(
+                "E_EXPORT_ELEVATED_HELPER_FAILED",
+                format!("resolve current exe failed: {e}"),
+            )
+        })?;
+        let verb = wide(OsStr::new("runas"));
+        let file = wide(exe.as_os_str());
+        let params = wide(OsStr::new(&format!(
+            "{HELPER_ARG} \"{}\" {} {}",
+            args.path.display(),
+            args.nonce,
+            args.sha256
+        )));
+        let mut info: SHELLEXECUTEINFOW = unsafe { mem::zeroed() };
+        info.cbSize = mem::size_of::<SHELLEXECUTEINFOW>() as u32;
+        info.fMask = SEE_MASK_NOCLOSEPROCESS;
+        info.lpVerb = verb.as_ptr();
+        info.lpFile = file.as_ptr();
+        info.lpParameters = params.as_ptr();
+        info.nShow = SW_HIDE;
+        if unsafe { ShellExecuteExW(&mut info) } == 0 {
+            let err = unsafe { GetLastError() };
+            if err == ERROR_CANCELLED {
+                return Err(ExportError::new(
+                    "E_EXPORT_ELEVATION_DECLINED",
+                    "administrator consent for the paste helper was declined",
+                ));
+            }
+            return Err(ExportError::new(
+                "E_EXPORT_ELEVATED_HELPER_FAILED",
+                format!("ShellExecuteExW(runas) failed: last_error={err}"),
+            ));
+        }
+        if info.hProcess.is_null() {
+            return Err(ExportError::new(
+                "E_EXPORT_ELEVATED_HELPER_FAILED",
+                "ShellExecuteExW returned no process handle",
+            ));
+        }
+        Ok(info.hProcess as isize)
+    }
+
+    // None while the helper is still running.
+    fn exit_code(process: isize) -> Result<Option<u32>, ExportError> {
+        let mut code: u32 = 0;
+        if unsafe { GetExitCodeProcess(process as HANDLE, &mut code) } == 0 {
+            let err = unsafe { GetLastError() };
+            return Err(ExportError::new(
+                "E_EXPORT_ELEVATED_HELPER_FAILED",
+                format!("GetExitCodeProcess failed: last_error={err}"),
+            ));
+        }
+        Ok((code != STILL_ACTIVE).then_some(code))
+    }
+
+    pub async fn launch(args: &HelperArgs) -> Result<(), ExportError> {
+        let process = spawn_elevated(args)?;
+        let deadline = Instant::now() + HELPER_TIMEOUT;
+        let result = loop {
+            match exit_code(process) {
+                Err(e) => break Err(e),
+                Ok(Some(0)) => break Ok(()),
+                Ok(Some(code)) => {
+                    break Err(ExportError::new(
+                        "E_EXPORT_ELEVATED_HELPER_FAILED",
+                        format!("elevated paste helper exited with code {code}"),
+                    ))
+                }
+                Ok(None) => {}
+            }
+            if Instant::now() >= deadline {
+                let _ = unsafe { TerminateProcess(process as HANDLE, 1) };
+                break Err(ExportError::new(
+                    "E_EXPORT_ELEVATED_HELPER_TIMEOUT",
+                    "elevated paste helper did not finish in time",
+                ));
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        };
+        let _ = unsafe { CloseHandle(process as HANDLE) };
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn helper_args_follow_the_flag() {
+        let args = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            helper_args_from(&args(&[
+                "TypeVoice.exe",
+                HELPER_ARG,
+                r"C:\d\inject.json",
+                "n1",
+                "abc"
+            ])),
+            Some(HelperArgs {
+                path: PathBuf::from(r"C:\d\inject.json"),
+                nonce: "n1".to_string(),
+                sha256: "abc".to_string(),
+            })
+        );
+        assert_eq!(helper_args_from(&args(&["TypeVoice.exe"])), None);
+        assert_eq!(
+            helper_args_from(&args(&["TypeVoice.exe", HELPER_ARG, r"C:\d\inject.json"])),
+            None
+        );
+    }
+
+    #[test]
+    fn helper_rejects_a_swapped_request_and_consumes_the_file() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("inject.json");
+        let req = HelperRequest {
+            nonce: "n1".to_string(),
+            text: "hello".to_string(),
+            terminal_mode: Some(TerminalPasteMode::Typed),
+            target_hwnd: None,
+        };
+        let bytes = serde_json::to_vec(&req).expect("ser");
+        let args = HelperArgs {
+            path: path.clone(),
+            nonce: "n1".to_string(),
+            sha256: sha256_hex(&bytes),
+        };
+        assert_eq!(verified_request(&bytes, &args), Some(req.clone()));
+
+        let swapped = serde_json::to_vec(&HelperRequest {
+            text: "rm -rf".to_string(),
+            ..req.clone()
+        })
+        .expect("ser");
+        assert_eq!(verified_request(&swapped, &args), None);
+        let wrong_nonce = HelperArgs {
+            nonce: "n2".to_string(),
+            ..args.clone()
+        };
+        assert_eq!(verified_request(&bytes, &wrong_nonce), None);
+
+        fs::write(&path, &swapped).expect("write");
+        assert_eq!(run_helper(&args), EXIT_BAD_REQUEST);
+        assert!(!path.exists());
+    }
+}
@@ -1,3 +1,11 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::export_format::{self, ExportFormat};
+use crate::formatting_profiles::TerminalPasteMode;
+use crate::obs;
+
 #[derive(Debug, Clone)]
 pub struct ExportError {
     pub code: String,
@@ -14,6 +22,10 @@ impl ExportError {
 }
 
 pub fn copy_text_to_clipboard(text: &str) -> Result<(), ExportError> {
+    copy_text_to_clipboard_as(text, ExportFormat::PlainText)
+}
+
+pub fn copy_text_to_clipboard_as(text: &str, format: ExportFormat) -> Result<(), ExportError> {
     if text.trim().is_empty() {
         return Err(ExportError::new(
             "E_EXPORT_EMPTY_TEXT",
@@ -21,6 +33,17 @@ pub fn copy_text_to_clipboard(text: &str) -> Result<(), ExportError> {
         ));
     }
 
+    // An older timer must not wipe this copy if it happens to be the same text.
+    cancel_clipboard_clear();
+    let payload = export_format::build_clipboard_payload(text, format);
+
+    // An X11/Wayland selection lives only as long as its owner; hand plain
+    // text to a detached holder so it survives TypeVoice exiting.
+    #[cfg(target_os = "linux")]
+    if payload.html.is_none() && linux_clipboard::copy_via_holder(&payload.plain) {
+        return Ok(());
+    }
+
     let mut clipboard = arboard::Clipboard::new().map_err(|e| {
         ExportError::new(
             "E_EXPORT_CLIPBOARD_UNAVAILABLE",
@@ -28,15 +51,117 @@ pub fn copy_text_to_clipboard(text: &str) -> Result<(), ExportError> {
         )
     })?;
 
-    clipboard.set_text(text.to_string()).map_err(|e| {
+    let written = match payload.html {
+        // arboard writes CF_HTML on Windows, public.html on macOS and text/html on Linux,
+        // alongside the plain-text alternative.
+        Some(html) => clipboard.set_html(html, Some(payload.plain)),
+        None => clipboard.set_text(payload.plain),
+    };
+    written.map_err(|e| {
         ExportError::new(
             "E_EXPORT_COPY_FAILED",
             format!("clipboard write failed: {e}"),
         )
-    })
+    })?;
+
+    #[cfg(target_os = "linux")]
+    linux_clipboard::hold(clipboard);
+    Ok(())
+}
+
+// Bumped by every schedule and cancel; a pending clear only runs if it is
+// still the latest one when its timer fires.
+static CLIPBOARD_CLEAR_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+// Clears the clipboard after `after` if it still holds exactly the plain text
+// this export put there; anything copied since is left alone. Scheduling
+// again replaces the pending clear.
+pub fn schedule_clipboard_clear(
+    data_dir: PathBuf,
+    task_id: Option<String>,
+    text: &str,
+    format: ExportFormat,
+    after: Duration,
+) {
+    let expected = export_format::build_clipboard_payload(text, format).plain;
+    let generation = CLIPBOARD_CLEAR_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let spawned = std::thread::Builder::new()
+        .name("typevoice_clipboard_clear".to_string())
+        .spawn(move || {
+            std::thread::sleep(after);
+            if CLIPBOARD_CLEAR_GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            let outcome = clear_clipboard_if_unchanged(&expected);
+            obs::event(
+                &data_dir,
+                task_id.as_deref(),
+                "Export",
+                "EXPORT.clipboard_auto_clear",
+                if outcome.is_ok() { "ok" } else { "err" },
+                Some(serde_json::json!({
+                    "after_secs": after.as_secs(),
+                    "cleared": outcome.as_ref().is_ok_and(|cleared| *cleared),
+                    "error": outcome.err(),
+                })),
+            );
+        });
+    if let Err(e) = spawned {
+        obs::event(
+            &data_dir,
+            None,
+            "Export",
+            "EXPORT.clipboard_auto_clear",
+            "err",
+            Some(serde_json::json!({ "error": e.to_string() })),
+        );
+    }
+}
+
+pub fn cancel_clipboard_clear() {
+    CLIPBOARD_CLEAR_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+fn clear_clipboard_if_unchanged(expected: &str) -> Result<bool, String> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+    // Images, files or an empty clipboard read as an error: nothing of ours.
+    match clipboard.get_text() {
+        Ok(current) if current == expected => {}
+        _ => return Ok(false),
+    }
+    clipboard.clear().map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+// How the text reached the target control, reported in the insert trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputMethod {
+    UiaValue,
+    UiaTextRange,
+    WmChar,
+    SendInput,
+    AtspiEditableText,
+    TerminalShortcut,
+    TerminalKeystrokes,
+    ElevatedHelper,
 }
 
-pub async fn auto_paste_text(text: &str) -> Result<(), ExportError> {
+impl InputMethod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::UiaValue => "uia_value",
+            Self::UiaTextRange => "uia_text_range",
+            Self::WmChar => "wm_char",
+            Self::SendInput => "send_input",
+            Self::AtspiEditableText => "atspi_editable_text",
+            Self::TerminalShortcut => "terminal_shortcut",
+            Self::TerminalKeystrokes => "terminal_keystrokes",
+            Self::ElevatedHelper => "elevated_helper",
+        }
+    }
+}
+
+pub async fn auto_paste_text(text: &str) -> Result<InputMethod, ExportError> {
     if text.trim().is_empty() {
         return Err(ExportError::new(
             "E_EXPORT_EMPTY_TEXT",
@@ -51,11 +176,19 @@ pub async fn auto_paste_text(text: &str) -> Result<(), ExportError> {
 
     #[cfg(target_os = "linux")]
     {
-        linux::auto_input_text(text).await
+        linux::auto_input_text(text)
+            .await
+            .map(|()| InputMethod::AtspiEditableText)
     }
 
     #[cfg(not(any(windows, target_os = "linux")))]
     {
+        // Report the missing permission rather than a generic failure, so the
+        // permissions checklist can point at the right pane.
+        use crate::permissions::{self, PermissionKind};
+        permissions::check(PermissionKind::Accessibility).map_err(|e| {
+            ExportError::new(PermissionKind::Accessibility.denied_code(), e.to_string())
+        })?;
         Err(ExportError::new(
             "E_EXPORT_PASTE_UNSUPPORTED",
             "auto input is only supported on Linux and Windows",
@@ -63,6 +196,48 @@ pub async fn auto_paste_text(text: &str) -> Result<(), ExportError> {
     }
 }
 
+// Terminals bypass the injection chain: they expose no editable UIA or AT-SPI
+// text and would run each injected line as a command. `text` is expected to be
+// prepared with `formatting_profiles::terminal_text`.
+pub async fn auto_paste_terminal(
+    text: &str,
+    mode: TerminalPasteMode,
+) -> Result<InputMethod, ExportError> {
+    if text.trim().is_empty() {
+        return Err(ExportError::new(
+            "E_EXPORT_EMPTY_TEXT",
+            "empty text cannot be exported",
+        ));
+    }
+
+    #[cfg(windows)]
+    {
+        windows::auto_input_terminal(text, mode)
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = mode;
+        Err(ExportError::new(
+            "E_EXPORT_PASTE_UNSUPPORTED",
+            "terminal paste is only supported on Windows",
+        ))
+    }
+}
+
+// Synchronous injection into the current foreground target, for the elevated
+// helper process which has no async runtime.
+#[cfg(windows)]
+pub(crate) fn inject_in_process(
+    text: &str,
+    terminal_mode: Option<TerminalPasteMode>,
+) -> Result<InputMethod, ExportError> {
+    match terminal_mode {
+        Some(mode) => windows::auto_input_terminal(text, mode),
+        None => windows::auto_input_text(text),
+    }
+}
+
 pub fn focus_window_best_effort(hwnd: Option<isize>) -> bool {
     #[cfg(windows)]
     {
@@ -76,25 +251,224 @@ pub fn focus_window_best_effort(hwnd: Option<isize>) -> bool {
     }
 }
 
+// Foreground top-level window unless it belongs to TypeVoice itself.
+pub fn external_foreground_window() -> Option<isize> {
+    #[cfg(windows)]
+    {
+        windows::external_foreground_window()
+    }
+
+    #[cfg(not(windows))]
+    {
+        None
+    }
+}
+
+pub fn window_alive(hwnd: isize) -> bool {
+    #[cfg(windows)]
+    {
+        windows::window_alive(hwnd)
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = hwnd;
+        false
+    }
+}
+
+// True when `hwnd` (or the top-level window owning it) is in the foreground.
+pub fn window_is_foreground(hwnd: isize) -> bool {
+    #[cfg(windows)]
+    {
+        windows::window_is_foreground(hwnd)
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = hwnd;
+        false
+    }
+}
+
 #[cfg(any(windows, test))]
 fn utf16_code_units(text: &str) -> Vec<u16> {
     text.encode_utf16().collect()
 }
 
+// Edit controls treat WM_CHAR '\r' as Enter and ignore a bare '\n'.
+#[cfg(any(windows, test))]
+fn wm_char_units(text: &str) -> Vec<u16> {
+    text.replace("\r\n", "\n")
+        .encode_utf16()
+        .map(|u| if u == 0x000A { 0x000D } else { u })
+        .collect()
+}
+
+// Replaces the UTF-16 range [start, end) of `value` with `text`. None when the
+// range does not fall on character boundaries of the current value.
+#[cfg(any(windows, test))]
+fn splice_utf16(value: &str, start: usize, end: usize, text: &str) -> Option<String> {
+    let units = utf16_code_units(value);
+    if start > end || end > units.len() {
+        return None;
+    }
+    let head = String::from_utf16(&units[..start]).ok()?;
+    let tail = String::from_utf16(&units[end..]).ok()?;
+    Some(format!("{head}{text}{tail}"))
+}
+
+#[cfg(any(windows, test))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ImeState {
+    Off,
+    Open,
+    Composing,
+}
+
+// An unfinished composition would swallow or reorder injected text, so it must
+// be committed first. While the IME stays open it is switched off for the
+// injection so typed units are not composed again. Ok(true) means "close the
+// IME while injecting".
+#[cfg(any(windows, test))]
+fn ime_precondition(state: ImeState, committed: bool) -> Result<bool, ExportError> {
+    match state {
+        ImeState::Off => Ok(false),
+        ImeState::Open => Ok(true),
+        ImeState::Composing if committed => Ok(true),
+        ImeState::Composing => Err(ExportError::new(
+            "E_EXPORT_IME_COMPOSING",
+            "target IME is composing and the composition could not be committed",
+        )),
+    }
+}
+
+// Windows mandatory integrity level of a process token, or why it is unknown.
+#[cfg(any(windows, test))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenProbe {
+    Level(u32),
+    Denied,
+    Unknown,
+}
+
+#[cfg(any(windows, test))]
+const SECURITY_MANDATORY_HIGH_RID: u32 = 0x3000;
+
+// UIPI drops input sent to a process above our integrity level. A token we may
+// not query is treated as elevated: medium-integrity callers are denied
+// TOKEN_QUERY on administrator processes.
+#[cfg(any(windows, test))]
+fn target_outranks(self_level: u32, target: TokenProbe) -> bool {
+    match target {
+        TokenProbe::Level(level) => level > self_level,
+        TokenProbe::Denied => self_level < SECURITY_MANDATORY_HIGH_RID,
+        TokenProbe::Unknown => false,
+    }
+}
+
+// Injection paths tried on Windows, most precise first. SendInput stays last
+// and is never skipped: it is the one path that works without any
+// accessibility support in the target.
+#[cfg(any(windows, test))]
+const WINDOWS_INPUT_CHAIN: [InputMethod; 4] = [
+    InputMethod::UiaValue,
+    InputMethod::UiaTextRange,
+    InputMethod::WmChar,
+    InputMethod::SendInput,
+];
+
+// Per (process image, UIA control type) memory of which injection path worked
+// and which ones were unavailable, so later inserts into the same kind of
+// control go straight to the path that works.
+#[cfg(any(windows, test))]
+#[derive(Debug, Default)]
+struct CapabilityCache {
+    entries: std::collections::HashMap<(String, i32), Capability>,
+}
+
+#[cfg(any(windows, test))]
+#[derive(Debug, Default)]
+struct Capability {
+    working: Option<InputMethod>,
+    unavailable: Vec<InputMethod>,
+}
+
+#[cfg(any(windows, test))]
+impl CapabilityCache {
+    const MAX_ENTRIES: usize = 256;
+
+    fn plan(&self, key: &(String, i32)) -> Vec<InputMethod> {
+        let Some(cap) = self.entries.get(key) else {
+            return WINDOWS_INPUT_CHAIN.to_vec();
+        };
+        let mut out = Vec::with_capacity(WINDOWS_INPUT_CHAIN.len());
+        out.extend(cap.working);
+        for m in WINDOWS_INPUT_CHAIN {
+            let skip = m != InputMethod::SendInput && cap.unavailable.contains(&m);
+            if !skip && !out.contains(&m) {
+                out.push(m);
+            }
+        }
+        out
+    }
+
+    fn record_working(&mut self, key: (String, i32), method: InputMethod) {
+        self.entry(key).working = Some(method);
+    }
+
+    fn record_unavailable(&mut self, key: (String, i32), method: InputMethod) {
+        let cap = self.entry(key);
+        if cap.working == Some(method) {
+            cap.working = None;
+        }
+        if !cap.unavailable.contains(&method) {
+            cap.unavailable.push(method);
+        }
+    }
+
+    fn entry(&mut self, key: (String, i32)) -> &mut Capability {
+        if self.entries.len() >= Self::MAX_ENTRIES && !self.entries.contains_key(&key) {
+            self.entries.clear();
+        }
+        self.entries.entry(key).or_default()
+    }
+}
+
 #[cfg(windows)]
 mod windows {
-    use super::{utf16_code_units, ExportError};
+    use super::{
+        target_outranks, utf16_code_units, wm_char_units, CapabilityCache, ExportError,
+        InputMethod, TerminalPasteMode,
+    };
+    use crate::formatting_profiles;
     use std::mem::{self, size_of};
+    use std::sync::{Mutex, OnceLock};
     use windows_sys::Win32::Foundation::{GetLastError, HWND};
     use windows_sys::Win32::System::Threading::GetCurrentProcessId;
     use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
         SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, KEYEVENTF_UNICODE,
+        VIRTUAL_KEY, VK_CONTROL, VK_SHIFT, VK_V,
     };
     use windows_sys::Win32::UI::WindowsAndMessaging::{
-        GetForegroundWindow, GetGUIThreadInfo, GetWindowThreadProcessId, IsWindow,
-        SetForegroundWindow, GUITHREADINFO,
+        GetAncestor, GetForegroundWindow, GetGUIThreadInfo, GetWindowThreadProcessId, IsWindow,
+        PostMessageW, SetForegroundWindow, GA_ROOT, GUITHREADINFO, WM_CHAR,
     };
 
+    // Outcome of one injection path. `Unavailable` guarantees nothing reached
+    // the target, so the next path may run; `Failed` means input may have been
+    // partially delivered and the chain must stop.
+    enum Attempt {
+        Delivered,
+        Unavailable(String),
+        Failed(ExportError),
+    }
+
+    fn capability_cache() -> &'static Mutex<CapabilityCache> {
+        static CACHE: OnceLock<Mutex<CapabilityCache>> = OnceLock::new();
+        CACHE.get_or_init(|| Mutex::new(CapabilityCache::default()))
+    }
+
     pub fn focus_window_best_effort(hwnd: Option<isize>) -> bool {
         let Some(hwnd) = hwnd else {
             return false;
@@ -106,7 +480,85 @@ mod windows {
         unsafe { SetForegroundWindow(hwnd) != 0 }
     }
 
-    pub fn auto_input_text(text: &str) -> Result<(), ExportError> {
+    pub fn auto_input_text(text: &str) -> Result<InputMethod, ExportError> {
+        let target = resolve_input_target()?;
+        let _ime_bypass = ime::prepare(target.hwnd)?;
+        let focused = uia::focused_element();
+        let key = (
+            crate::context_capture_windows::get_process_image_best_effort(target.focus_pid)
+                .map(|v| v.to_lowercase())
+                .unwrap_or_else(|| format!("pid:{}", target.focus_pid)),
+            focused.as_ref().map(|f| f.control_type).unwrap_or(0),
+        );
+        let plan = capability_cache()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .plan(&key);
+
+        let mut skipped = Vec::new();
+        for method in plan {
+            let attempt = match method {
+                InputMethod::UiaValue => match focused.as_ref() {
+                    Some(f) => uia::insert_via_value(f, text)
+                        .map_or_else(Attempt::Unavailable, |()| Attempt::Delivered),
+                    None => Attempt::Unavailable("no UIA focused element".to_string()),
+                },
+                InputMethod::UiaTextRange => match focused.as_ref() {
+                    Some(f) => match uia::pin_caret_via_text_range(f) {
+                        Ok(()) => send_unicode_input(text, &target),
+                        Err(reason) => Attempt::Unavailable(reason),
+                    },
+                    None => Attempt::Unavailable("no UIA focused element".to_string()),
+                },
+                InputMethod::WmChar => post_wm_chars(text, &target, focused.as_ref()),
+                _ => send_unicode_input(text, &target),
+            };
+            let mut cache = capability_cache().lock().unwrap_or_else(|e| e.into_inner());
+            match attempt {
+                Attempt::Delivered => {
+                    cache.record_working(key, method);
+                    return Ok(method);
+                }
+                Attempt::Unavailable(reason) => {
+                    cache.record_unavailable(key.clone(), method);
+                    skipped.push(format!("{}: {reason}", method.as_str()));
+                }
+                Attempt::Failed(e) => return Err(e),
+            }
+        }
+        Err(ExportError::new(
+            "E_EXPORT_PASTE_FAILED",
+            format!("no input method available: {}", skipped.join("; ")),
+        ))
+    }
+
+    pub fn external_foreground_window() -> Option<isize> {
+        let foreground = unsafe { GetForegroundWindow() };
+        if foreground.is_null() || unsafe { IsWindow(foreground) } == 0 {
+            return None;
+        }
+        let mut pid: u32 = 0;
+        let _ = unsafe { GetWindowThreadProcessId(foreground, &mut pid) };
+        if pid == 0 || pid == unsafe { GetCurrentProcessId() } {
+            return None;
+        }
+        Some(unsafe { GetAncestor(foreground, GA_ROOT) } as isize)
+    }
+
+    pub fn window_alive(hwnd: isize) -> bool {
+        let hwnd = hwnd as HWND;
+        !hwnd.is_null() && unsafe { IsWindow(hwnd) } != 0
+    }
+
+    pub fn window_is_foreground(hwnd: isize) -> bool {
+        let foreground = unsafe { GetForegroundWindow() };
+        if foreground.is_null() || !window_alive(hwnd) {
+            return false;
+        }
+        unsafe { GetAncestor(foreground, GA_ROOT) == GetAncestor(hwnd as HWND, GA_ROOT) }
+    }
+
+    fn resolve_input_target() -> Result<ForegroundFocusTarget, ExportError> {
         let target = resolve_foreground_focus_window().ok_or_else(|| {
             ExportError::new(
                 "E_EXPORT_TARGET_UNAVAILABLE",
@@ -122,13 +574,76 @@ mod windows {
                 ),
             ));
         }
+        if let Some(self_level) = integrity::current_level() {
+            if target_outranks(self_level, integrity::probe_process(target.focus_pid)) {
+                return Err(ExportError::new(
+                    "E_EXPORT_TARGET_ELEVATED",
+                    format!(
+                        "target process runs as administrator and Windows blocks input from TypeVoice (focus_pid={}); run TypeVoice as administrator or enable the elevated paste helper in Settings > Export",
+                        target.focus_pid
+                    ),
+                ));
+            }
+        }
+        Ok(target)
+    }
+
+    pub fn auto_input_terminal(
+        text: &str,
+        mode: TerminalPasteMode,
+    ) -> Result<InputMethod, ExportError> {
+        let target = resolve_input_target()?;
+        let Some(keys) = formatting_profiles::terminal_keystrokes(mode, text) else {
+            return send_paste_shortcut(&target);
+        };
+        let _ime_bypass = ime::prepare(target.hwnd)?;
+        match send_unicode_input(&keys, &target) {
+            Attempt::Delivered => Ok(InputMethod::TerminalKeystrokes),
+            Attempt::Unavailable(reason) => Err(ExportError::new(
+                "E_EXPORT_PASTE_FAILED",
+                format!("terminal input failed: {reason}"),
+            )),
+            Attempt::Failed(e) => Err(e),
+        }
+    }
+
+    // Ctrl+Shift+V is the paste binding shared by Windows Terminal, conhost
+    // and the common Linux-style emulators; Ctrl+V would reach the shell as ^V.
+    fn send_paste_shortcut(target: &ForegroundFocusTarget) -> Result<InputMethod, ExportError> {
+        let inputs = [
+            vk_input(VK_CONTROL, 0),
+            vk_input(VK_SHIFT, 0),
+            vk_input(VK_V, 0),
+            vk_input(VK_V, KEYEVENTF_KEYUP),
+            vk_input(VK_SHIFT, KEYEVENTF_KEYUP),
+            vk_input(VK_CONTROL, KEYEVENTF_KEYUP),
+        ];
+        let expected = inputs.len() as u32;
+        let sent = unsafe { SendInput(expected, inputs.as_ptr(), size_of::<INPUT>() as i32) };
+        if sent != expected {
+            let err = unsafe { GetLastError() };
+            return Err(ExportError::new(
+                "E_EXPORT_PASTE_FAILED",
+                format!(
+                    "SendInput(Ctrl+Shift+V) failed: last_error={err}, sent={sent}, expected={expected}, focus_pid={}",
+                    target.focus_pid,
+                ),
+            ));
+        }
+        Ok(InputMethod::TerminalShortcut)
+    }
 
+    fn send_unicode_input(text: &str, target: &ForegroundFocusTarget) -> Attempt {
         let inputs = build_unicode_key_inputs(text);
         let expected = inputs.len() as u32;
         let sent = unsafe { SendInput(expected, inputs.as_ptr(), size_of::<INPUT>() as i32) };
+        if sent == 0 {
+            let err = unsafe { GetLastError() };
+            return Attempt::Unavailable(format!("SendInput blocked: last_error={err}"));
+        }
         if sent != expected {
             let err = unsafe { GetLastError() };
-            return Err(ExportError::new(
+            return Attempt::Failed(ExportError::new(
                 "E_EXPORT_PASTE_FAILED",
                 format!(
                     "SendInput(unicode) failed: last_error={err}, sent={sent}, expected={expected}, focus_hwnd={:p}, foreground_hwnd={:p}, foreground_pid={}, focus_pid={}",
@@ -136,7 +651,34 @@ mod windows {
                 ),
             ));
         }
-        Ok(())
+        Attempt::Delivered
+    }
+
+    // Only windowed controls (classic Edit/RichEdit) read WM_CHAR from their own
+    // queue; windowless UI such as browsers would drop the messages silently.
+    fn post_wm_chars(
+        text: &str,
+        target: &ForegroundFocusTarget,
+        focused: Option<&uia::FocusedElement>,
+    ) -> Attempt {
+        match focused {
+            Some(f) if f.native_hwnd == target.hwnd as isize => {}
+            _ => return Attempt::Unavailable("focused control is not a native window".to_string()),
+        }
+        for (i, unit) in wm_char_units(text).into_iter().enumerate() {
+            let ok = unsafe { PostMessageW(target.hwnd, WM_CHAR, unit as usize, 1) };
+            if ok == 0 {
+                let err = unsafe { GetLastError() };
+                if i == 0 {
+                    return Attempt::Unavailable(format!("PostMessageW failed: last_error={err}"));
+                }
+                return Attempt::Failed(ExportError::new(
+                    "E_EXPORT_PASTE_FAILED",
+                    format!("PostMessageW(WM_CHAR) failed after {i} units: last_error={err}"),
+                ));
+            }
+        }
+        Attempt::Delivered
     }
 
     fn build_unicode_key_inputs(text: &str) -> Vec<INPUT> {
@@ -164,6 +706,21 @@ mod windows {
         }
     }
 
+    fn vk_input(vk: VIRTUAL_KEY, flags: u32) -> INPUT {
+        INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: vk,
+                    wScan: 0,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: 0,
+                },
+            },
+        }
+    }
+
     struct ForegroundFocusTarget {
         hwnd: HWND,
         foreground_hwnd: HWND,
@@ -203,6 +760,423 @@ mod windows {
             self_pid,
         })
     }
+
+    mod integrity {
+        use super::super::TokenProbe;
+        use std::ptr;
+        use windows_sys::Win32::Foundation::{
+            CloseHandle, GetLastError, ERROR_ACCESS_DENIED, ERROR_INSUFFICIENT_BUFFER, HANDLE,
+        };
+        use windows_sys::Win32::Security::{
+            GetSidSubAuthority, GetSidSubAuthorityCount, GetTokenInformation, TokenIntegrityLevel,
+            TOKEN_MANDATORY_LABEL, TOKEN_QUERY,
+        };
+        use windows_sys::Win32::System::Threading::{
+            GetCurrentProcess, OpenProcess, OpenProcessToken, PROCESS_QUERY_LIMITED_INFORMATION,
+        };
+
+        pub fn current_level() -> Option<u32> {
+            match probe_handle(unsafe { GetCurrentProcess() }) {
+                TokenProbe::Level(level) => Some(level),
+                _ => None,
+            }
+        }
+
+        pub fn probe_process(pid: u32) -> TokenProbe {
+            unsafe {
+                let h = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+                if h.is_null() {
+                    return denied_or_unknown();
+                }
+                let probe = probe_handle(h);
+                let _ = CloseHandle(h);
+                probe
+            }
+        }
+
+        fn denied_or_unknown() -> TokenProbe {
+            if unsafe { GetLastError() } == ERROR_ACCESS_DENIED {
+                TokenProbe::Denied
+            } else {
+                TokenProbe::Unknown
+            }
+        }
+
+        fn probe_handle(process: HANDLE) -> TokenProbe {
+            unsafe {
+                let mut token: HANDLE = ptr::null_mut();
+                if OpenProcessToken(process, TOKEN_QUERY, &mut token) == 0 {
+                    return denied_or_unknown();
+                }
+                // u64 storage keeps the label's SID pointer aligned.
+                let mut buf = vec![0u64; 8];
+                let mut len: u32 = 0;
+                let mut ok = GetTokenInformation(
+                    token,
+                    TokenIntegrityLevel,
+                    buf.as_mut_ptr().cast(),
+                    (buf.len() * 8) as u32,
+                    &mut len,
+                );
+                if ok == 0 && GetLastError() == ERROR_INSUFFICIENT_BUFFER {
+                    buf.resize((len as usize).div_ceil(8), 0);
+                    ok = GetTokenInformation(
+                        token,
+                        TokenIntegrityLevel,
+                        buf.as_mut_ptr().cast(),
+                        (buf.len() * 8) as u32,
+                        &mut len,
+                    );
+                }
+                let _ = CloseHandle(token);
+                if ok == 0 {
+                    return TokenProbe::Unknown;
+                }
+                let label = &*(buf.as_ptr() as *const TOKEN_MANDATORY_LABEL);
+                let sid = label.Label.Sid;
+                let count = *GetSidSubAuthorityCount(sid);
+                if count == 0 {
+                    return TokenProbe::Unknown;
+                }
+                TokenProbe::Level(*GetSidSubAuthority(sid, u32::from(count) - 1))
+            }
+        }
+    }
+
+    mod ime {
+        use super::super::{ime_precondition, ImeState};
+        use super::ExportError;
+        use std::ptr;
+        use std::time::Duration;
+        use windows_sys::Win32::Foundation::HWND;
+        use windows_sys::Win32::UI::Input::Ime::{
+            ImmGetCompositionStringW, ImmGetContext, ImmGetDefaultIMEWnd, ImmNotifyIME,
+            ImmReleaseContext, CPS_COMPLETE, GCS_COMPSTR, IMC_GETOPENSTATUS, IMC_SETOPENSTATUS,
+            NI_COMPOSITIONSTR,
+        };
+        use windows_sys::Win32::UI::WindowsAndMessaging::{
+            SendMessageTimeoutW, SMTO_ABORTIFHUNG, WM_IME_CONTROL,
+        };
+
+        const IME_CONTROL_TIMEOUT_MS: u32 = 200;
+        // Injected input is queued behind sent messages in the target thread,
+        // so the IME is reopened only after the queue had time to drain.
+        const REOPEN_DELAY: Duration = Duration::from_millis(150);
+
+        // Reopens the IME when the injection is done.
+        pub struct ImeBypass {
+            ime_wnd: HWND,
+        }
+
+        impl Drop for ImeBypass {
+            fn drop(&mut self) {
+                std::thread::sleep(REOPEN_DELAY);
+                let _ = ime_control(self.ime_wnd, IMC_SETOPENSTATUS, 1);
+            }
+        }
+
+        // TSF keeps its thread manager per thread and exposes nothing across
+        // processes; the IMM32 layer mirrors both composition and open status
+        // for TSF IMEs, so it is the probe used here.
+        pub fn prepare(hwnd: HWND) -> Result<Option<ImeBypass>, ExportError> {
+            let ime_wnd = unsafe { ImmGetDefaultIMEWnd(hwnd) };
+            let state = if composing(hwnd) {
+                ImeState::Composing
+            } else if !ime_wnd.is_null() && ime_control(ime_wnd, IMC_GETOPENSTATUS, 0) == Some(1) {
+                ImeState::Open
+            } else {
+                ImeState::Off
+            };
+            let committed = state == ImeState::Composing && commit(hwnd) && !composing(hwnd);
+            if !ime_precondition(state, committed)? || ime_wnd.is_null() {
+                return Ok(None);
+            }
+            match ime_control(ime_wnd, IMC_SETOPENSTATUS, 0) {
+                Some(_) => Ok(Some(ImeBypass { ime_wnd })),
+                None => Ok(None),
+            }
+        }
+
+        // The input context is only reachable when the target shares it with
+        // us; otherwise composition is reported as absent and the open-status
+        // bypass still applies.
+        fn composing(hwnd: HWND) -> bool {
+            unsafe {
+                let himc = ImmGetContext(hwnd);
+                if himc.is_null() {
+                    return false;
+                }
+                let bytes = ImmGetCompositionStringW(himc, GCS_COMPSTR, ptr::null_mut(), 0);
+                let _ = ImmReleaseContext(hwnd, himc);
+                bytes > 0
+            }
+        }
+
+        fn commit(hwnd: HWND) -> bool {
+            unsafe {
+                let himc = ImmGetContext(hwnd);
+                if himc.is_null() {
+                    return false;
+                }
+                let ok = ImmNotifyIME(himc, NI_COMPOSITIONSTR, CPS_COMPLETE, 0);
+                let _ = ImmReleaseContext(hwnd, himc);
+                ok != 0
+            }
+        }
+
+        fn ime_control(ime_wnd: HWND, command: u32, value: isize) -> Option<usize> {
+            let mut result: usize = 0;
+            let ok = unsafe {
+                SendMessageTimeoutW(
+                    ime_wnd,
+                    WM_IME_CONTROL,
+                    command as usize,
+                    value,
+                    SMTO_ABORTIFHUNG,
+                    IME_CONTROL_TIMEOUT_MS,
+                    &mut result,
+                )
+            };
+            (ok != 0).then_some(result)
+        }
+    }
+
+    mod uia {
+        use super::super::splice_utf16;
+        use windows::core::BSTR;
+        use windows::Win32::Foundation::RPC_E_CHANGED_MODE;
+        use windows::Win32::System::Com::{
+            CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_INPROC_SERVER,
+            COINIT_MULTITHREADED,
+        };
+        use windows::Win32::UI::Accessibility::{
+            CUIAutomation, IUIAutomation, IUIAutomationElement, IUIAutomationTextPattern,
+            IUIAutomationTextRange, IUIAutomationValuePattern, TextPatternRangeEndpoint_End,
+            TextPatternRangeEndpoint_Start, UIA_TextPatternId, UIA_ValuePatternId,
+        };
+
+        struct ComInitGuard {
+            should_uninit: bool,
+        }
+
+        impl Drop for ComInitGuard {
+            fn drop(&mut self) {
+                if self.should_uninit {
+                    unsafe {
+                        CoUninitialize();
+                    }
+                }
+            }
+        }
+
+        fn ensure_com_initialized() -> Option<ComInitGuard> {
+            let hr = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+            if hr.is_ok() {
+                return Some(ComInitGuard {
+                    should_uninit: true,
+                });
+            }
+            (hr == RPC_E_CHANGED_MODE).then_some(ComInitGuard {
+                should_uninit: false,
+            })
+        }
+
+        // Field order matters: the element must be released before COM is
+        // uninitialized.
+        pub struct FocusedElement {
+            element: IUIAutomationElement,
+            pub control_type: i32,
+            pub native_hwnd: isize,
+            _com: ComInitGuard,
+        }
+
+        pub fn focused_element() -> Option<FocusedElement> {
+            let com = ensure_com_initialized()?;
+            unsafe {
+                let automation: IUIAutomation =
+                    CoCreateInstance(&CUIAutomation, None, CLSCTX_INPROC_SERVER).ok()?;
+                let element = automation.GetFocusedElement().ok()?;
+                let control_type = element.CurrentControlType().map(|v| v.0).unwrap_or(0);
+                let native_hwnd = element
+                    .CurrentNativeWindowHandle()
+                    .map(|h| h.0 as isize)
+                    .unwrap_or(0);
+                Some(FocusedElement {
+                    element,
+                    control_type,
+                    native_hwnd,
+                    _com: com,
+                })
+            }
+        }
+
+        // ValuePattern only replaces the whole value, so the text is spliced in
+        // at the caret reported by TextPattern. Without a caret only an empty
+        // field is safe to fill.
+        pub fn insert_via_value(f: &FocusedElement, text: &str) -> Result<(), String> {
+            unsafe {
+                let pattern: IUIAutomationValuePattern = f
+                    .element
+                    .GetCurrentPatternAs(UIA_ValuePatternId)
+                    .map_err(|e| format!("ValuePattern unsupported: {e}"))?;
+                if pattern
+                    .CurrentIsReadOnly()
+                    .map(|v| v.as_bool())
+                    .unwrap_or(true)
+                {
+                    return Err("ValuePattern is read-only".to_string());
+                }
+                let before = pattern
+                    .CurrentValue()
+                    .map_err(|e| format!("ValuePattern.CurrentValue failed: {e}"))?
+                    .to_string();
+                let (start, end) = if before.is_empty() {
+                    (0, 0)
+                } else {
+                    selection_utf16(f).ok_or("caret position unavailable")?
+                };
+                // TextPattern and ValuePattern can disagree (e.g. line endings);
+                // a caret outside the value means the offsets are not comparable.
+                let after = splice_utf16(&before, start, end, text)
+                    .ok_or("caret does not map into the current value")?;
+                pattern
+                    .SetValue(&BSTR::from(after.as_str()))
+                    .map_err(|e| format!("ValuePattern.SetValue failed: {e}"))?;
+                let now = pattern
+                    .CurrentValue()
+                    .map(|v| v.to_string())
+                    .unwrap_or_default();
+                if now == before {
+                    return Err("ValuePattern.SetValue left the value unchanged".to_string());
+                }
+                Ok(())
+            }
+        }
+
+        // UIA text ranges are read-only, so this path restores the caret through
+        // the pattern's own selection range and lets keystrokes land there.
+        pub fn pin_caret_via_text_range(f: &FocusedElement) -> Result<(), String> {
+            unsafe {
+                let (_, range) = caret_range(f)?;
+                f.element
+                    .SetFocus()
+                    .map_err(|e| format!("UIA SetFocus failed: {e}"))?;
+                range
+                    .Select()
+                    .map_err(|e| format!("TextRange.Select failed: {e}"))
+            }
+        }
+
+        unsafe fn caret_range(
+            f: &FocusedElement,
+        ) -> Result<(IUIAutomationTextPattern, IUIAutomationTextRange), String> {
+            let pattern: IUIAutomationTextPattern = f
+                .element
+                .GetCurrentPatternAs(UIA_TextPatternId)
+                .map_err(|e| format!("TextPattern unsupported: {e}"))?;
+            let selection = pattern
+                .GetSelection()
+                .map_err(|e| format!("TextPattern.GetSelection failed: {e}"))?;
+            if selection.Length().unwrap_or(0) < 1 {
+                return Err("TextPattern has no caret range".to_string());
+            }
+            let range = selection
+                .GetElement(0)
+                .map_err(|e| format!("TextPattern selection unreadable: {e}"))?;
+            Ok((pattern, range))
+        }
+
+        fn selection_utf16(f: &FocusedElement) -> Option<(usize, usize)> {
+            unsafe {
+                let (pattern, range) = caret_range(f).ok()?;
+                let prefix = pattern.DocumentRange().ok()?;
+                prefix
+                    .MoveEndpointByRange(
+                        TextPatternRangeEndpoint_End,
+                        &range,
+                        TextPatternRangeEndpoint_Start,
+                    )
+                    .ok()?;
+                let start = prefix.GetText(-1).ok()?.len();
+                let selected = range.GetText(-1).ok()?.len();
+                Some((start, start + selected))
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux_clipboard {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    // wl-copy and xclip fork a background process that serves the selection
+    // until something else is copied, which clipboard managers also pick up.
+    // Each serves one MIME type, so HTML stays with arboard.
+    const HOLDER_WAIT: Duration = Duration::from_secs(2);
+
+    // The arboard fallback keeps its Clipboard for the life of the process;
+    // dropping it would give up the selection, and on exit arboard offers the
+    // contents to a running clipboard manager.
+    static HELD: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
+
+    pub fn hold(clipboard: arboard::Clipboard) {
+        *HELD.lock().unwrap_or_else(|e| e.into_inner()) = Some(clipboard);
+    }
+
+    type Holder = (&'static str, &'static [&'static str]);
+
+    pub(super) fn holder_commands(wayland: bool, x11: bool) -> Vec<Holder> {
+        let mut cmds: Vec<Holder> = Vec::new();
+        if wayland {
+            cmds.push(("wl-copy", &["--type", "text/plain;charset=utf-8"]));
+        }
+        if x11 {
+            cmds.push(("xclip", &["-selection", "clipboard", "-in"]));
+            cmds.push(("xsel", &["--clipboard", "--input"]));
+        }
+        cmds
+    }
+
+    // True when a holder took the text. Missing tools fall through to arboard.
+    pub fn copy_via_holder(text: &str) -> bool {
+        let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+        let x11 = std::env::var_os("DISPLAY").is_some();
+        holder_commands(wayland, x11)
+            .into_iter()
+            .any(|(program, args)| run_holder(program, args, text))
+    }
+
+    fn run_holder(program: &str, args: &[&str], text: &str) -> bool {
+        let Ok(mut child) = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            return false;
+        };
+        let written = child
+            .stdin
+            .take()
+            .is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+        // The foreground process exits once its background copy owns the
+        // selection; one that keeps running is left alone as the holder.
+        let started = Instant::now();
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) => return written && status.success(),
+                Ok(None) if started.elapsed() < HOLDER_WAIT => {
+                    std::thread::sleep(Duration::from_millis(20))
+                }
+                Ok(None) => return written,
+                Err(_) => return false,
+            }
+        }
+    }
 }
 
 #[cfg(target_os = "linux")]
@@ -350,7 +1324,11 @@ mod linux {
 
 #[cfg(test)]
 mod tests {
-    use super::utf16_code_units;
+    use super::{
+        ime_precondition, splice_utf16, target_outranks, utf16_code_units, wm_char_units,
+        CapabilityCache, ImeState, InputMethod, TokenProbe, SECURITY_MANDATORY_HIGH_RID,
+        WINDOWS_INPUT_CHAIN,
+    };
 
     #[test]
     fn utf16_code_units_preserve_newline() {
@@ -361,4 +1339,93 @@ mod tests {
     fn utf16_code_units_support_surrogate_pairs() {
         assert_eq!(utf16_code_units("😀").len(), 2);
     }
+
+    #[test]
+    fn wm_char_units_send_carriage_return_for_newlines() {
+        assert_eq!(
+            wm_char_units("a\r\nb\nc"),
+            vec![0x61, 0x0D, 0x62, 0x0D, 0x63]
+        );
+    }
+
+    #[test]
+    fn splice_utf16_replaces_selection_and_rejects_split_surrogates() {
+        assert_eq!(
+            splice_utf16("hello world", 6, 11, "rust").as_deref(),
+            Some("hello rust")
+        );
+        assert_eq!(splice_utf16("😀x", 2, 2, "y").as_deref(), Some("😀yx"));
+        assert_eq!(splice_utf16("😀x", 1, 1, "y"), None);
+        assert_eq!(splice_utf16("ab", 1, 3, "y"), None);
+    }
+
+    #[test]
+    fn capability_cache_prefers_working_path_and_skips_unavailable_ones() {
+        let mut cache = CapabilityCache::default();
+        let key = ("c:\\app\\notepad.exe".to_string(), 50004);
+        assert_eq!(cache.plan(&key), WINDOWS_INPUT_CHAIN.to_vec());
+
+        cache.record_unavailable(key.clone(), InputMethod::UiaValue);
+        cache.record_working(key.clone(), InputMethod::WmChar);
+        assert_eq!(
+            cache.plan(&key),
+            vec![
+                InputMethod::WmChar,
+                InputMethod::UiaTextRange,
+                InputMethod::SendInput
+            ]
+        );
+
+        // A cached path that stops working is dropped; SendInput is never skipped.
+        cache.record_unavailable(key.clone(), InputMethod::WmChar);
+        cache.record_unavailable(key.clone(), InputMethod::SendInput);
+        assert_eq!(
+            cache.plan(&key),
+            vec![InputMethod::UiaTextRange, InputMethod::SendInput]
+        );
+        assert_eq!(
+            cache.plan(&("other.exe".to_string(), 50004)),
+            WINDOWS_INPUT_CHAIN.to_vec()
+        );
+    }
+
+    #[test]
+    fn ime_composition_must_be_committed_before_injection() {
+        assert!(!ime_precondition(ImeState::Off, false).expect("off"));
+        assert!(ime_precondition(ImeState::Open, false).expect("open"));
+        assert!(ime_precondition(ImeState::Composing, true).expect("committed"));
+        let err = ime_precondition(ImeState::Composing, false).expect_err("composing");
+        assert_eq!(err.code, "E_EXPORT_IME_COMPOSING");
+    }
+
+    #[test]
+    fn elevated_or_unqueryable_targets_outrank_medium_integrity() {
+        let medium = 0x2000;
+        assert!(target_outranks(
+            medium,
+            TokenProbe::Level(SECURITY_MANDATORY_HIGH_RID)
+        ));
+        assert!(target_outranks(medium, TokenProbe::Denied));
+        assert!(!target_outranks(medium, TokenProbe::Level(medium)));
+        assert!(!target_outranks(medium, TokenProbe::Unknown));
+        assert!(!target_outranks(
+            SECURITY_MANDATORY_HIGH_RID,
+            TokenProbe::Denied
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn clipboard_holders_follow_display_server() {
+        use super::linux_clipboard::holder_commands;
+        let names = |w, x| {
+            holder_commands(w, x)
+                .into_iter()
+                .map(|(p, _)| p)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(true, false), ["wl-copy"]);
+        assert_eq!(names(true, true), ["wl-copy", "xclip", "xsel"]);
+        assert!(names(false, false).is_empty());
+    }
 }
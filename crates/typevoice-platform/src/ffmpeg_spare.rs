@@ -0,0 +1,196 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::Path,
+    process::{Child, Command, Stdio},
+    sync::Mutex,
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crate::subprocess::CommandNoConsoleExt;
+
+// Starting ffmpeg costs 100-300ms on Windows before it reads a byte. The spare
+// is spawned ahead of the next task with the preprocess filter chain already
+// on its command line and blocks on stdin; the task streams its audio in and
+// raw PCM out. If the app exits the pipe closes and the spare exits with it.
+const SPARE_MAX_IDLE: Duration = Duration::from_secs(30 * 60);
+
+// Containers ffmpeg can demux from a pipe. MP4/M4A may keep their index at the
+// end of the file and need the seekable file path.
+const STREAMABLE_EXTENSIONS: &[&str] = &["wav", "flac", "ogg", "opus", "mp3", "webm"];
+
+const SAMPLE_RATE: u32 = 16_000;
+const CHANNELS: u16 = 1;
+const BITS: u16 = 16;
+
+struct Spare {
+    cmd: String,
+    args: Vec<String>,
+    child: Child,
+    spawned_at: Instant,
+}
+
+static SPARE: Mutex<Option<Spare>> = Mutex::new(None);
+
+pub fn streamable_input(input: &Path) -> bool {
+    input
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| STREAMABLE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+// The spare, when it was started with exactly these arguments and still runs.
+// A mismatched or stale spare is killed.
+pub fn take(cmd: &str, args: &[String]) -> Option<Child> {
+    let mut spare = SPARE.lock().unwrap().take()?;
+    let usable = spare.cmd == cmd
+        && spare.args == args
+        && spare.spawned_at.elapsed() < SPARE_MAX_IDLE
+        && matches!(spare.child.try_wait(), Ok(None));
+    if usable {
+        return Some(spare.child);
+    }
+    kill(spare.child);
+    None
+}
+
+// Spawns the next spare off the caller's thread. A spawn failure is left for
+// the next task's cold start to report.
+pub fn refill(cmd: String, args: Vec<String>) {
+    std::thread::spawn(move || {
+        let Ok(child) = spawn_piped(&cmd, &args) else {
+            return;
+        };
+        let previous = SPARE.lock().unwrap().replace(Spare {
+            cmd,
+            args,
+            child,
+            spawned_at: Instant::now(),
+        });
+        if let Some(old) = previous {
+            kill(old.child);
+        }
+    });
+}
+
+pub fn discard() {
+    let spare = SPARE.lock().unwrap().take();
+    if let Some(spare) = spare {
+        kill(spare.child);
+    }
+}
+
+fn kill(mut child: Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+fn spawn_piped(cmd: &str, args: &[String]) -> io::Result<Child> {
+    Command::new(cmd)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .no_console()
+        .spawn()
+}
+
+// Pumps for one piped run: input file into stdin, stdout into the WAV at the
+// output path, stderr into memory. Killing the child ends all three.
+pub struct PipeIo {
+    feed: JoinHandle<io::Result<u64>>,
+    drain: JoinHandle<io::Result<u64>>,
+    stderr: JoinHandle<String>,
+}
+
+pub fn start_io(child: &mut Child, mut input: File, output: &Path) -> io::Result<PipeIo> {
+    let (Some(mut stdin), Some(stdout), Some(mut stderr)) =
+        (child.stdin.take(), child.stdout.take(), child.stderr.take())
+    else {
+        return Err(io::Error::other("ffmpeg pipes missing"));
+    };
+    let out = File::create(output)?;
+    Ok(PipeIo {
+        // Dropping stdin at the end signals EOF to ffmpeg.
+        feed: std::thread::spawn(move || io::copy(&mut input, &mut stdin)),
+        drain: std::thread::spawn(move || write_wav(stdout, out)),
+        stderr: std::thread::spawn(move || {
+            let mut buf = String::new();
+            let _ = stderr.read_to_string(&mut buf);
+            buf.trim().to_string()
+        }),
+    })
+}
+
+impl PipeIo {
+    // PCM bytes written and ffmpeg's stderr. A feed error is not returned:
+    // ffmpeg closing stdin early already shows in its exit status.
+    pub fn finish(self) -> (io::Result<u64>, String) {
+        let _ = self.feed.join();
+        let written = self
+            .drain
+            .join()
+            .unwrap_or_else(|_| Err(io::Error::other("wav writer panicked")));
+        let stderr = self.stderr.join().unwrap_or_default();
+        (written, stderr)
+    }
+}
+
+// ffmpeg cannot seek a pipe to fix up its WAV header, so it writes raw PCM and
+// the header is filled in once the length is known.
+fn write_wav(mut pcm: impl Read, mut out: File) -> io::Result<u64> {
+    out.write_all(&wav_header(0))?;
+    let len = io::copy(&mut pcm, &mut out)?;
+    let data_len = u32::try_from(len)
+        .map_err(|_| io::Error::other("preprocessed audio exceeds the 4 GiB WAV limit"))?;
+    out.seek(SeekFrom::Start(0))?;
+    out.write_all(&wav_header(data_len))?;
+    out.sync_data()?;
+    Ok(len)
+}
+
+fn wav_header(data_len: u32) -> [u8; 44] {
+    let block_align = CHANNELS * (BITS / 8);
+    let mut h = [0u8; 44];
+    h[0..4].copy_from_slice(b"RIFF");
+    h[4..8].copy_from_slice(&(36 + data_len).to_le_bytes());
+    h[8..16].copy_from_slice(b"WAVEfmt ");
+    h[16..20].copy_from_slice(&16_u32.to_le_bytes());
+    h[20..22].copy_from_slice(&1_u16.to_le_bytes());
+    h[22..24].copy_from_slice(&CHANNELS.to_le_bytes());
+    h[24..28].copy_from_slice(&SAMPLE_RATE.to_le_bytes());
+    h[28..32].copy_from_slice(&(SAMPLE_RATE * u32::from(block_align)).to_le_bytes());
+    h[32..34].copy_from_slice(&block_align.to_le_bytes());
+    h[34..36].copy_from_slice(&BITS.to_le_bytes());
+    h[36..40].copy_from_slice(b"data");
+    h[40..44].copy_from_slice(&data_len.to_le_bytes());
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wav_written_from_a_pipe_gets_its_final_length() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("out.wav");
+        let pcm = [1u8, 0, 2, 0, 3, 0];
+        let out = File::create(&path).expect("create");
+        assert_eq!(write_wav(&pcm[..], out).expect("write"), 6);
+        let bytes = std::fs::read(&path).expect("read");
+        assert_eq!(bytes.len(), 50);
+        assert_eq!(&bytes[4..8], &42_u32.to_le_bytes());
+        assert_eq!(&bytes[40..44], &6_u32.to_le_bytes());
+        assert_eq!(&bytes[44..], &pcm);
+    }
+
+    #[test]
+    fn only_pipe_friendly_containers_use_the_spare() {
+        assert!(streamable_input(Path::new("recording-1.wav")));
+        assert!(streamable_input(Path::new("memo.OPUS")));
+        assert!(!streamable_input(Path::new("voice.m4a")));
+        assert!(!streamable_input(Path::new("noext")));
+    }
+}
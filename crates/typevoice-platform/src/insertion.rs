@@ -1,16 +1,25 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::context_pack::sha256_hex;
+use crate::export_format::ExportFormat;
+use crate::formatting_profiles::{self, TerminalExport, TerminalPasteMode};
 use crate::ports::{PortError, PortResult};
-use crate::{data_dir, export, obs, settings};
+use crate::{audit_log, data_dir, elevated_helper, export, obs, settings};
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct InsertTextRequest {
     pub transcript_id: Option<String>,
     pub text: String,
+    #[serde(default)]
+    pub template_id: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct InsertResult {
     pub copied: bool,
@@ -52,6 +61,72 @@ impl InsertResult {
     }
 }
 
+// Window every export is sent to until unlocked. Lives for the app session
+// only; window handles are meaningless after a restart.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportTargetLock {
+    pub hwnd: isize,
+    pub title: Option<String>,
+    pub process_image: Option<String>,
+    pub locked_at_ms: i64,
+}
+
+static TARGET_LOCK: Mutex<Option<ExportTargetLock>> = Mutex::new(None);
+
+fn target_lock_slot() -> std::sync::MutexGuard<'static, Option<ExportTargetLock>> {
+    TARGET_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn lock_export_target(hwnd: isize) -> PortResult<ExportTargetLock> {
+    if !export::window_alive(hwnd) {
+        return Err(PortError::new(
+            "E_EXPORT_TARGET_LOCK_UNAVAILABLE",
+            "no external window available to lock as export target",
+        ));
+    }
+    let (title, process_image) = window_meta_best_effort(hwnd);
+    let lock = ExportTargetLock {
+        hwnd,
+        title,
+        process_image,
+        locked_at_ms: obs::schema::now_ms(),
+    };
+    *target_lock_slot() = Some(lock.clone());
+    Ok(lock)
+}
+
+pub fn unlock_export_target() -> Option<ExportTargetLock> {
+    target_lock_slot().take()
+}
+
+pub fn export_target_lock() -> Option<ExportTargetLock> {
+    target_lock_slot().clone()
+}
+
+// A locked target must really be in front before anything is typed; pasting
+// into whatever else has focus is exactly what the lock exists to prevent.
+// A closed target releases the lock.
+fn ensure_locked_target_focused(lock: &ExportTargetLock) -> Result<(), export::ExportError> {
+    if !export::window_alive(lock.hwnd) {
+        let mut slot = target_lock_slot();
+        if slot.as_ref().map(|l| l.hwnd) == Some(lock.hwnd) {
+            *slot = None;
+        }
+        return Err(export::ExportError::new(
+            "E_EXPORT_TARGET_LOCK_LOST",
+            "locked export target window was closed; the lock was released",
+        ));
+    }
+    if !export::window_is_foreground(lock.hwnd) {
+        return Err(export::ExportError::new(
+            "E_EXPORT_TARGET_LOCK_FOCUS_FAILED",
+            "locked export target window could not be brought to the foreground",
+        ));
+    }
+    Ok(())
+}
+
 pub async fn insert_text(req: InsertTextRequest) -> PortResult<InsertResult> {
     insert_text_after_focus(req, None).await
 }
@@ -73,33 +148,93 @@ pub async fn insert_text_after_focus(
         })),
     );
 
-    if let Err(e) = export::copy_text_to_clipboard(&req.text) {
+    let current_settings = settings::load_settings_strict(&dir)
+        .map_err(|e| PortError::from_message("E_SETTINGS_INVALID", e.to_string()))?;
+    let template_id = req.template_id.as_deref();
+    let format = resolve_export_format(&current_settings, template_id);
+    let allowed = |target| settings::export_target_allowed(&current_settings, template_id, target);
+    let paste_allowed = allowed(settings::ExportTarget::Paste);
+    if !allowed(settings::ExportTarget::Clipboard) {
+        let message = "the template does not allow copying or pasting its text";
+        span.err(
+            "insert",
+            "E_EXPORT_TARGET_NOT_ALLOWED",
+            message,
+            Some(serde_json::json!({ "template_id": template_id })),
+        );
+        return Err(PortError::new("E_EXPORT_TARGET_NOT_ALLOWED", message));
+    }
+    if let Err(e) = export::copy_text_to_clipboard_as(&req.text, format) {
         span.err("insert", &e.code, &e.message, None);
         return Err(PortError::new(&e.code, e.message));
     }
 
-    let current_settings = settings::load_settings_strict(&dir)
-        .map_err(|e| PortError::from_message("E_SETTINGS_INVALID", e.to_string()))?;
-    if !settings::resolve_auto_paste_enabled(&current_settings) {
+    // A template that only allows the clipboard is copy-only whatever the
+    // global auto-paste setting says.
+    if !paste_allowed || !settings::resolve_auto_paste_enabled(&current_settings) {
+        let auto_clear = settings::resolve_clipboard_auto_clear(&current_settings);
+        if let Some(after) = auto_clear {
+            export::schedule_clipboard_clear(
+                dir.clone(),
+                req.transcript_id.clone(),
+                &req.text,
+                format,
+                after,
+            );
+        }
         span.ok(Some(serde_json::json!({
             "copied": true,
+            "export_format": format.as_str(),
             "auto_paste_enabled": false,
             "auto_paste_attempted": false,
+            "paste_allowed": paste_allowed,
+            "clipboard_auto_clear_secs": auto_clear.map(|d| d.as_secs()),
         })));
+        record_export_audit_best_effort(&dir, &current_settings, &req, format, "copy");
         return Ok(InsertResult::copy_only());
     }
 
+    let locked = export_target_lock();
+    let target_hwnd = locked.as_ref().map(|l| l.hwnd).or(target_hwnd);
     let _ = export::focus_window_best_effort(target_hwnd);
     tokio::time::sleep(std::time::Duration::from_millis(80)).await;
 
-    match export::auto_paste_text(&req.text).await {
-        Ok(()) => {
+    let terminal = terminal_export_best_effort(&dir);
+    let pasted = match locked.as_ref().map(ensure_locked_target_focused) {
+        Some(Err(e)) => Err(e),
+        _ => match &terminal {
+            Some(t) => paste_into_terminal(t, &req.text).await,
+            None => export::auto_paste_text(&req.text).await,
+        },
+    };
+    let pasted = match pasted {
+        Err(e)
+            if e.code == "E_EXPORT_TARGET_ELEVATED"
+                && settings::resolve_export_elevated_helper_enabled(&current_settings) =>
+        {
+            let text = match &terminal {
+                Some(t) => formatting_profiles::terminal_text(t, &req.text),
+                None => req.text.clone(),
+            };
+            let mode = terminal.as_ref().map(|t| t.mode);
+            elevated_helper::paste_via_helper(&dir, &text, mode, target_hwnd).await
+        }
+        other => other,
+    };
+    let terminal_mode = terminal.as_ref().map(|t| t.mode.as_str());
+    match pasted {
+        Ok(method) => {
             span.ok(Some(serde_json::json!({
                 "copied": true,
+                "export_format": format.as_str(),
                 "auto_paste_enabled": true,
                 "auto_paste_attempted": true,
                 "auto_paste_ok": true,
+                "input_method": method.as_str(),
+                "terminal_mode": terminal_mode,
+                "target_locked": locked.is_some(),
             })));
+            record_export_audit_best_effort(&dir, &current_settings, &req, format, "paste");
             Ok(InsertResult::pasted())
         }
         Err(e) => {
@@ -111,17 +246,121 @@ pub async fn insert_text_after_focus(
                     "copied": true,
                     "auto_paste_enabled": true,
                     "auto_paste_attempted": true,
+                    "terminal_mode": terminal_mode,
+                    "target_locked": locked.is_some(),
                 })),
             );
+            record_export_audit_best_effort(&dir, &current_settings, &req, format, "paste_failed");
             Ok(InsertResult::paste_failed(&e.code, e.message))
         }
     }
 }
 
+// The foreground window is the restored target at this point. Profiles that
+// fail to load still leave the built-in terminal list in effect.
+fn terminal_export_best_effort(dir: &Path) -> Option<TerminalExport> {
+    let (process_image, _) = foreground_window_meta_best_effort();
+    let profiles = formatting_profiles::load_profiles(dir).unwrap_or_default();
+    formatting_profiles::resolve_terminal_export(&profiles, process_image.as_deref())
+}
+
+async fn paste_into_terminal(
+    t: &TerminalExport,
+    text: &str,
+) -> Result<export::InputMethod, export::ExportError> {
+    let text = formatting_profiles::terminal_text(t, text);
+    // The terminal pastes from the clipboard, so it must hold the prepared
+    // text rather than the original with its trailing newline.
+    if t.mode == TerminalPasteMode::ClipboardShortcut {
+        export::copy_text_to_clipboard(&text)?;
+    }
+    export::auto_paste_terminal(&text, t.mode).await
+}
+
+// Audit failures never fail the export itself; they are only traced.
+fn record_export_audit_best_effort(
+    dir: &Path,
+    s: &settings::Settings,
+    req: &InsertTextRequest,
+    format: ExportFormat,
+    mode: &str,
+) {
+    if !settings::resolve_audit_log_enabled(s) {
+        return;
+    }
+    let (process_image, window_title) = foreground_window_meta_best_effort();
+    let entry = audit_log::AuditEntry {
+        id: 0,
+        ts_ms: obs::schema::now_ms(),
+        task_id: req.transcript_id.clone().filter(|id| !id.trim().is_empty()),
+        process_image,
+        window_title,
+        export_mode: mode.to_string(),
+        export_format: format.as_str().to_string(),
+        text_sha256: sha256_hex(req.text.as_bytes()),
+        char_count: req.text.chars().count() as i64,
+        text: settings::resolve_audit_log_include_text(s).then(|| req.text.clone()),
+    };
+    let _ = audit_log::append(
+        &audit_log::audit_db_path(dir),
+        &entry,
+        settings::resolve_audit_retention(s),
+    );
+}
+
+fn window_meta_best_effort(hwnd: isize) -> (Option<String>, Option<String>) {
+    #[cfg(windows)]
+    {
+        crate::context_capture_windows::window_info_best_effort(hwnd)
+            .map(|w| (w.title, w.process_image))
+            .unwrap_or((None, None))
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = hwnd;
+        (None, None)
+    }
+}
+
+fn foreground_window_meta_best_effort() -> (Option<String>, Option<String>) {
+    #[cfg(windows)]
+    {
+        crate::context_capture_windows::foreground_window_info_now_best_effort()
+            .map(|w| (w.process_image, w.title))
+            .unwrap_or((None, None))
+    }
+
+    #[cfg(not(windows))]
+    {
+        (None, None)
+    }
+}
+
+pub fn resolve_export_format(s: &settings::Settings, template_id: Option<&str>) -> ExportFormat {
+    ExportFormat::parse(&settings::resolve_export_format(s, template_id))
+        .unwrap_or(ExportFormat::PlainText)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn closed_locked_target_releases_the_lock() {
+        *target_lock_slot() = Some(ExportTargetLock {
+            hwnd: 0,
+            title: Some("notes.md".to_string()),
+            process_image: None,
+            locked_at_ms: 1,
+        });
+        let lock = export_target_lock().expect("locked");
+        let err = ensure_locked_target_focused(&lock).expect_err("closed window");
+        assert_eq!(err.code, "E_EXPORT_TARGET_LOCK_LOST");
+        assert_eq!(export_target_lock(), None);
+        assert_eq!(unlock_export_target(), None);
+    }
+
     #[test]
     fn insert_result_preserves_copy_success_when_paste_fails() {
         let result = InsertResult::paste_failed("E_EXPORT_PASTE_FAILED", "target unavailable");
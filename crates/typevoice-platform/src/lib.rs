@@ -1,16 +1,28 @@
-pub use typevoice_core::{context_pack, ports};
+pub use typevoice_core::{
+    context_pack, export_format, ports, text_casing, text_normalize, wav_preprocess,
+};
 pub use typevoice_observability::obs;
-pub use typevoice_storage::{data_dir, history, settings};
+pub use typevoice_storage::{
+    audit_log, data_dir, formatting_profiles, guest_mode, history, settings, workspaces, write_gate,
+};
 
+pub mod a11y_announce;
 pub mod audio_device_notifications_windows;
 pub mod audio_devices_windows;
 pub mod context_capture;
 pub mod context_capture_windows;
+pub mod elevated_helper;
 pub mod export;
+pub mod ffmpeg_spare;
 pub mod insertion;
 pub mod overlay_layout;
+pub mod permissions;
 pub mod pipeline;
+pub mod postprocess_plugins;
+pub mod power;
 pub mod record_input;
 pub mod record_input_cache;
+pub mod screen_share;
+pub mod sinks;
 pub mod subprocess;
 pub mod toolchain;
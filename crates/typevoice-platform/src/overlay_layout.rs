@@ -1,4 +1,4 @@
-use tauri::{LogicalSize, PhysicalPosition};
+use tauri::{LogicalSize, PhysicalPosition, PhysicalSize};
 use typevoice_storage::{data_dir, settings};
 
 pub fn apply_overlay_layout(w: &tauri::WebviewWindow) -> anyhow::Result<()> {
@@ -75,3 +75,74 @@ fn push_overlay_work_area(areas: &mut Vec<settings::OverlayWorkArea>, monitor: &
         areas.push(next);
     }
 }
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CaptionsMonitor {
+    pub index: usize,
+    pub name: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+}
+
+pub fn apply_captions_layout(w: &tauri::WebviewWindow) -> anyhow::Result<()> {
+    let dir = data_dir::data_dir()?;
+    let s = settings::load_settings_strict(&dir)?;
+    let config = settings::resolve_captions_config(&s);
+    apply_captions_layout_with_config(w, &config)
+}
+
+pub fn apply_captions_layout_with_config(
+    w: &tauri::WebviewWindow,
+    config: &settings::CaptionsConfigResolved,
+) -> anyhow::Result<()> {
+    let rect = settings::resolve_captions_rect(config, &captions_work_areas(w));
+    w.set_size(PhysicalSize::new(
+        rect.width.round() as u32,
+        rect.height.round() as u32,
+    ))?;
+    w.set_position(PhysicalPosition::new(
+        rect.x.round() as i32,
+        rect.y.round() as i32,
+    ))?;
+    Ok(())
+}
+
+// Unlike `overlay_work_areas`, this keeps the platform enumeration order so a
+// saved `captions_monitor_index` keeps pointing at the same display.
+pub fn captions_work_areas(w: &tauri::WebviewWindow) -> Vec<settings::OverlayWorkArea> {
+    let mut areas = Vec::new();
+    if let Ok(monitors) = w.available_monitors() {
+        for monitor in monitors {
+            let area = monitor.work_area();
+            areas.push(settings::OverlayWorkArea {
+                x: area.position.x as f64,
+                y: area.position.y as f64,
+                width: area.size.width as f64,
+                height: area.size.height as f64,
+                scale_factor: monitor.scale_factor(),
+            });
+        }
+    }
+    if areas.is_empty() {
+        if let Some(monitor) = w.primary_monitor().ok().flatten() {
+            push_overlay_work_area(&mut areas, &monitor);
+        }
+    }
+    areas
+}
+
+pub fn list_captions_monitors(w: &tauri::WebviewWindow) -> Vec<CaptionsMonitor> {
+    w.available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .map(|(index, monitor)| CaptionsMonitor {
+            index,
+            name: monitor.name().cloned(),
+            width: monitor.size().width,
+            height: monitor.size().height,
+            scale_factor: monitor.scale_factor(),
+        })
+        .collect()
+}
@@ -0,0 +1,377 @@
+use std::process::Command;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::subprocess::CommandNoConsoleExt;
+
+// macOS gates the three capabilities TypeVoice needs behind separate TCC
+// permissions. Other platforms report `not_required` so the same checklist
+// renders everywhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionKind {
+    Microphone,
+    Accessibility,
+    ScreenRecording,
+}
+
+pub const ALL_KINDS: [PermissionKind; 3] = [
+    PermissionKind::Microphone,
+    PermissionKind::Accessibility,
+    PermissionKind::ScreenRecording,
+];
+
+impl PermissionKind {
+    // The code a workflow step reports when this permission is missing.
+    pub fn denied_code(self) -> &'static str {
+        match self {
+            Self::Microphone => "E_PERMISSION_MICROPHONE_DENIED",
+            Self::Accessibility => "E_PERMISSION_ACCESSIBILITY_DENIED",
+            Self::ScreenRecording => "E_PERMISSION_SCREEN_RECORDING_DENIED",
+        }
+    }
+
+    // Existing step codes whose failures this permission explains.
+    pub fn affected_codes(self) -> &'static [&'static str] {
+        match self {
+            Self::Microphone => &["E_RECORD_START_FAILED"],
+            Self::Accessibility => &["E_EXPORT_PASTE_FAILED", "E_HOTKEY_REGISTER_FAILED"],
+            Self::ScreenRecording => &["E_SCREENSHOT", "E_CONTEXT_CAPTURE_FAILED"],
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    fn settings_anchor(self) -> &'static str {
+        match self {
+            Self::Microphone => "Privacy_Microphone",
+            Self::Accessibility => "Privacy_Accessibility",
+            Self::ScreenRecording => "Privacy_ScreenCapture",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionState {
+    Granted,
+    Denied,
+    NotDetermined,
+    NotRequired,
+}
+
+// What the checklist offers next for one permission.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionStep {
+    Done,
+    // Ask the OS to show its prompt.
+    Request,
+    // The prompt was already answered; only System Settings can change it.
+    OpenSettings,
+    // Screen recording is granted in System Settings but only takes effect
+    // after the app restarts.
+    Restart,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionStatus {
+    pub kind: PermissionKind,
+    pub state: PermissionState,
+    pub step: PermissionStep,
+    pub denied_code: &'static str,
+    pub affected_codes: &'static [&'static str],
+}
+
+// Kinds whose System Settings pane was opened in this process. macOS keeps
+// reporting screen recording as denied until relaunch, so this is what turns
+// `open_settings` into `restart`.
+static SETTINGS_OPENED: Mutex<Vec<PermissionKind>> = Mutex::new(Vec::new());
+
+fn settings_opened(kind: PermissionKind) -> bool {
+    SETTINGS_OPENED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .contains(&kind)
+}
+
+// Kinds whose OS prompt was shown in this process.
+static REQUESTED: Mutex<Vec<PermissionKind>> = Mutex::new(Vec::new());
+
+fn mark_requested(kind: PermissionKind) {
+    let mut requested = REQUESTED.lock().unwrap_or_else(|e| e.into_inner());
+    if !requested.contains(&kind) {
+        requested.push(kind);
+    }
+}
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn requested(kind: PermissionKind) -> bool {
+    REQUESTED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .contains(&kind)
+}
+
+fn next_step(kind: PermissionKind, state: PermissionState, opened: bool) -> PermissionStep {
+    match state {
+        PermissionState::Granted | PermissionState::NotRequired => PermissionStep::Done,
+        PermissionState::NotDetermined => PermissionStep::Request,
+        PermissionState::Denied if opened && kind == PermissionKind::ScreenRecording => {
+            PermissionStep::Restart
+        }
+        PermissionState::Denied => PermissionStep::OpenSettings,
+    }
+}
+
+pub fn status(kind: PermissionKind) -> PermissionStatus {
+    let state = imp::state(kind);
+    PermissionStatus {
+        kind,
+        state,
+        step: next_step(kind, state, settings_opened(kind)),
+        denied_code: kind.denied_code(),
+        affected_codes: kind.affected_codes(),
+    }
+}
+
+pub fn permissions_status() -> Vec<PermissionStatus> {
+    ALL_KINDS.into_iter().map(status).collect()
+}
+
+// Err(denied_code) when `kind` is known to be missing; an undetermined
+// permission passes so the OS can prompt on first use.
+pub fn check(kind: PermissionKind) -> Result<()> {
+    if imp::state(kind) == PermissionState::Denied {
+        return Err(anyhow!(
+            "{}: {kind:?} permission is not granted",
+            kind.denied_code()
+        ));
+    }
+    Ok(())
+}
+
+// Shows the OS prompt where one exists. The microphone prompt appears the
+// first time a recording starts, so the checklist runs a mic test instead.
+pub fn request(kind: PermissionKind) -> PermissionStatus {
+    imp::request(kind);
+    status(kind)
+}
+
+pub fn open_settings(kind: PermissionKind) -> Result<()> {
+    let url = imp::settings_url(kind).ok_or_else(|| {
+        anyhow!("E_PERMISSION_SETTINGS_UNSUPPORTED: no settings pane for {kind:?} on this platform")
+    })?;
+    open_url(&url)?;
+    let mut opened = SETTINGS_OPENED.lock().unwrap_or_else(|e| e.into_inner());
+    if !opened.contains(&kind) {
+        opened.push(kind);
+    }
+    Ok(())
+}
+
+fn open_url(url: &str) -> Result<()> {
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut c = Command::new("cmd");
+        c.args(["/C", "start", "", url]);
+        c
+    };
+    #[cfg(not(windows))]
+    let mut cmd = {
+        let mut c = Command::new(if cfg!(target_os = "macos") {
+            "open"
+        } else {
+            "xdg-open"
+        });
+        c.arg(url);
+        c
+    };
+    let status = cmd
+        .no_console()
+        .status()
+        .context("E_PERMISSION_SETTINGS_OPEN: launch settings failed")?;
+    if !status.success() {
+        return Err(anyhow!(
+            "E_PERMISSION_SETTINGS_OPEN: settings exited with {status}"
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use std::ffi::{c_char, c_void, CStr};
+
+    use super::{PermissionKind, PermissionState};
+
+    type Id = *mut c_void;
+
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn AXIsProcessTrusted() -> bool;
+        fn AXIsProcessTrustedWithOptions(options: *const c_void) -> bool;
+        static kAXTrustedCheckOptionPrompt: *const c_void;
+    }
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGPreflightScreenCaptureAccess() -> bool;
+        fn CGRequestScreenCaptureAccess() -> bool;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        static kCFBooleanTrue: *const c_void;
+        static kCFTypeDictionaryKeyCallBacks: c_void;
+        static kCFTypeDictionaryValueCallBacks: c_void;
+        fn CFDictionaryCreate(
+            allocator: *const c_void,
+            keys: *const *const c_void,
+            values: *const *const c_void,
+            count: isize,
+            key_callbacks: *const c_void,
+            value_callbacks: *const c_void,
+        ) -> *const c_void;
+        fn CFRelease(cf: *const c_void);
+    }
+
+    #[link(name = "AVFoundation", kind = "framework")]
+    extern "C" {
+        static AVMediaTypeAudio: Id;
+    }
+
+    #[link(name = "objc")]
+    extern "C" {
+        fn objc_getClass(name: *const c_char) -> Id;
+        fn sel_registerName(name: *const c_char) -> *const c_void;
+        fn objc_msgSend();
+    }
+
+    // AVAuthorizationStatus: 0 not determined, 1 restricted, 2 denied, 3 authorized.
+    fn microphone() -> PermissionState {
+        let class = CStr::from_bytes_with_nul(b"AVCaptureDevice\0").unwrap();
+        let sel = CStr::from_bytes_with_nul(b"authorizationStatusForMediaType:\0").unwrap();
+        let status = unsafe {
+            let cls = objc_getClass(class.as_ptr());
+            if cls.is_null() {
+                return PermissionState::NotRequired;
+            }
+            let send: unsafe extern "C" fn(Id, *const c_void, Id) -> isize =
+                std::mem::transmute(objc_msgSend as unsafe extern "C" fn());
+            send(cls, sel_registerName(sel.as_ptr()), AVMediaTypeAudio)
+        };
+        match status {
+            3 => PermissionState::Granted,
+            0 => PermissionState::NotDetermined,
+            _ => PermissionState::Denied,
+        }
+    }
+
+    // Neither API can tell "never asked" from "denied"; the prompt only shows
+    // once, so an untrusted process is reported as not determined until the
+    // user has been sent to System Settings.
+    pub fn state(kind: PermissionKind) -> PermissionState {
+        let granted = match kind {
+            PermissionKind::Microphone => return microphone(),
+            PermissionKind::Accessibility => unsafe { AXIsProcessTrusted() },
+            PermissionKind::ScreenRecording => unsafe { CGPreflightScreenCaptureAccess() },
+        };
+        match (
+            granted,
+            super::settings_opened(kind) || super::requested(kind),
+        ) {
+            (true, _) => PermissionState::Granted,
+            (false, true) => PermissionState::Denied,
+            (false, false) => PermissionState::NotDetermined,
+        }
+    }
+
+    pub fn request(kind: PermissionKind) {
+        super::mark_requested(kind);
+        match kind {
+            PermissionKind::Microphone => {}
+            PermissionKind::Accessibility => unsafe {
+                let keys = [kAXTrustedCheckOptionPrompt];
+                let values = [kCFBooleanTrue];
+                let options = CFDictionaryCreate(
+                    std::ptr::null(),
+                    keys.as_ptr(),
+                    values.as_ptr(),
+                    1,
+                    &kCFTypeDictionaryKeyCallBacks as *const c_void,
+                    &kCFTypeDictionaryValueCallBacks as *const c_void,
+                );
+                AXIsProcessTrustedWithOptions(options);
+                if !options.is_null() {
+                    CFRelease(options);
+                }
+            },
+            PermissionKind::ScreenRecording => unsafe {
+                CGRequestScreenCaptureAccess();
+            },
+        }
+    }
+
+    pub fn settings_url(kind: PermissionKind) -> Option<String> {
+        Some(format!(
+            "x-apple.systempreferences:com.apple.preference.security?{}",
+            kind.settings_anchor()
+        ))
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod imp {
+    use super::{PermissionKind, PermissionState};
+
+    pub fn state(_kind: PermissionKind) -> PermissionState {
+        PermissionState::NotRequired
+    }
+
+    pub fn request(kind: PermissionKind) {
+        super::mark_requested(kind);
+    }
+
+    // Windows has a per-app microphone switch; the rest needs nothing.
+    pub fn settings_url(kind: PermissionKind) -> Option<String> {
+        (cfg!(windows) && kind == PermissionKind::Microphone)
+            .then(|| "ms-settings:privacy-microphone".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn screen_recording_needs_restart_after_settings() {
+        use PermissionKind::*;
+        use PermissionState::*;
+        assert_eq!(
+            next_step(Microphone, NotDetermined, false),
+            PermissionStep::Request
+        );
+        assert_eq!(
+            next_step(Accessibility, Denied, true),
+            PermissionStep::OpenSettings
+        );
+        assert_eq!(
+            next_step(ScreenRecording, Denied, false),
+            PermissionStep::OpenSettings
+        );
+        assert_eq!(
+            next_step(ScreenRecording, Denied, true),
+            PermissionStep::Restart
+        );
+        assert_eq!(
+            next_step(ScreenRecording, Granted, true),
+            PermissionStep::Done
+        );
+        assert_eq!(
+            next_step(Microphone, NotRequired, false),
+            PermissionStep::Done
+        );
+    }
+}
@@ -1,16 +1,21 @@
 use std::{
+    fs::File,
     io::Read,
-    path::Path,
-    process::{Command, Stdio},
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Stdio},
     time::Instant,
 };
 
 use anyhow::{anyhow, Context, Result};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
+use crate::ffmpeg_spare;
+use crate::guest_mode;
 use crate::obs::debug;
 use crate::obs::Span;
+use crate::settings;
 use crate::subprocess::CommandNoConsoleExt;
+use crate::wav_preprocess::{self, NativePreprocess, SilenceTrim};
 
 fn cmd_hint_for_trace(cmd: &str) -> String {
     let t = cmd.trim();
@@ -26,6 +31,7 @@ pub struct PreprocessConfig {
     pub silence_threshold_db: f64,
     pub silence_trim_start_ms: u64,
     pub silence_trim_end_ms: u64,
+    pub gain_db: f64,
 }
 
 impl Default for PreprocessConfig {
@@ -35,6 +41,7 @@ impl Default for PreprocessConfig {
             silence_threshold_db: -50.0,
             silence_trim_start_ms: 300,
             silence_trim_end_ms: 300,
+            gain_db: 0.0,
         }
     }
 }
@@ -79,6 +86,11 @@ fn clamp_preprocess_config(mut cfg: PreprocessConfig) -> PreprocessConfig {
     if cfg.silence_trim_end_ms > 60_000 {
         cfg.silence_trim_end_ms = 60_000;
     }
+    cfg.gain_db = if cfg.gain_db.is_finite() {
+        cfg.gain_db.clamp(-20.0, 20.0)
+    } else {
+        0.0
+    };
     cfg
 }
 
@@ -96,7 +108,6 @@ fn build_ffmpeg_preprocess_args(
         .ok_or_else(|| anyhow!("non-utf8 output path"))?
         .to_string();
 
-    let cfg = clamp_preprocess_config(cfg.clone());
     let mut args = vec![
         "-y".to_string(),
         "-hide_banner".to_string(),
@@ -111,7 +122,47 @@ fn build_ffmpeg_preprocess_args(
         "-c:a".to_string(),
         "pcm_s16le".to_string(),
     ];
+    if let Some(filter) = preprocess_filter(cfg) {
+        args.push("-af".to_string());
+        args.push(filter);
+    }
+    args.push("-vn".to_string());
+    args.push(output_s);
+    Ok(args)
+}
+
+// Same conversion for a warm spare: no paths on the command line, so one
+// spare serves any task with the same filter settings.
+fn build_ffmpeg_pipe_args(cfg: &PreprocessConfig) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "-hide_banner",
+        "-loglevel",
+        "error",
+        "-i",
+        "pipe:0",
+        "-ac",
+        "1",
+        "-ar",
+        "16000",
+    ]
+    .map(String::from)
+    .to_vec();
+    if let Some(filter) = preprocess_filter(cfg) {
+        args.push("-af".to_string());
+        args.push(filter);
+    }
+    args.extend(["-vn", "-f", "s16le", "-c:a", "pcm_s16le", "pipe:1"].map(String::from));
+    args
+}
 
+fn preprocess_filter(cfg: &PreprocessConfig) -> Option<String> {
+    let cfg = clamp_preprocess_config(cfg.clone());
+    let mut filters = Vec::new();
+    // Gain runs first so the silence threshold is compared against the
+    // adjusted level, matching how noise calibration suggests both values.
+    if cfg.gain_db != 0.0 {
+        filters.push(format!("volume={:.1}dB", cfg.gain_db));
+    }
     if cfg.silence_trim_enabled {
         let start = (cfg.silence_trim_start_ms as f64) / 1000.0;
         let end = (cfg.silence_trim_end_ms as f64) / 1000.0;
@@ -121,13 +172,9 @@ fn build_ffmpeg_preprocess_args(
             end = end,
             thr = cfg.silence_threshold_db,
         );
-        args.push("-af".to_string());
-        args.push(filter);
+        filters.push(filter);
     }
-
-    args.push("-vn".to_string());
-    args.push(output_s);
-    Ok(args)
+    (!filters.is_empty()).then(|| filters.join(","))
 }
 
 pub fn preprocess_to_temp_wav(data_dir: &Path, task_id: &str) -> Result<std::path::PathBuf> {
@@ -136,14 +183,25 @@ pub fn preprocess_to_temp_wav(data_dir: &Path, task_id: &str) -> Result<std::pat
     Ok(tmp.join(format!("{task_id}.wav")))
 }
 
+// TYPEVOICE_KEEP_AUDIO=1 or the keep_recordings setting, ignored in guest mode.
+fn keep_audio_requested(data_dir: &Path) -> bool {
+    if guest_mode::enabled() {
+        return false;
+    }
+    std::env::var("TYPEVOICE_KEEP_AUDIO").ok().as_deref() == Some("1")
+        || settings::load_settings(data_dir)
+            .map(|s| settings::resolve_keep_recordings(&s))
+            .unwrap_or(false)
+}
+
 pub fn cleanup_audio_artifacts(input_audio: &Path, wav_path: &Path, data_dir: &Path) -> Result<()> {
     // Default: do not persist audio artifacts.
-    let keep_audio = std::env::var("TYPEVOICE_KEEP_AUDIO").ok().as_deref() == Some("1");
+    let keep_audio = keep_audio_requested(data_dir);
     cleanup_audio_artifacts_with_keep(input_audio, wav_path, data_dir, keep_audio)
 }
 
 pub fn cleanup_input_audio_artifact(input_audio: &Path, data_dir: &Path) -> Result<()> {
-    let keep_audio = std::env::var("TYPEVOICE_KEEP_AUDIO").ok().as_deref() == Some("1");
+    let keep_audio = keep_audio_requested(data_dir);
     cleanup_input_audio_artifact_with_keep(input_audio, data_dir, keep_audio)
 }
 
@@ -175,10 +233,111 @@ fn cleanup_input_audio_artifact_with_keep(
     Ok(())
 }
 
+fn native_wav_requested(data_dir: &Path, input: &Path) -> bool {
+    input
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("wav"))
+        && settings::load_settings(data_dir)
+            .map(|s| settings::resolve_preprocess_native_wav_enabled(&s))
+            .unwrap_or(false)
+}
+
+fn native_preprocess_config(cfg: &PreprocessConfig) -> NativePreprocess {
+    let cfg = clamp_preprocess_config(cfg.clone());
+    NativePreprocess {
+        gain_db: cfg.gain_db,
+        silence_trim: cfg.silence_trim_enabled.then_some(SilenceTrim {
+            threshold_db: cfg.silence_threshold_db,
+            start_ms: cfg.silence_trim_start_ms,
+            end_ms: cfg.silence_trim_end_ms,
+        }),
+    }
+}
+
+// Ok(None) when the file is a WAV the native path cannot read; ffmpeg takes it.
+fn preprocess_native(
+    data_dir: &Path,
+    task_id: &str,
+    input: &Path,
+    output: &Path,
+    token: &tokio_util::sync::CancellationToken,
+    cfg: &PreprocessConfig,
+) -> Result<Option<u128>> {
+    let span = Span::start(
+        data_dir,
+        Some(task_id),
+        "Preprocess",
+        "NATIVE.preprocess",
+        None,
+    );
+    let t0 = Instant::now();
+    let wav = match std::fs::read(input) {
+        Ok(v) => v,
+        Err(e) => {
+            let message = format!("read preprocess input failed: {e}");
+            span.err("io", "E_PREPROCESS_FAILED", &message, None);
+            return Err(anyhow!("E_PREPROCESS_FAILED: {message}"));
+        }
+    };
+    let out = match wav_preprocess::preprocess_wav(&wav, &native_preprocess_config(cfg)) {
+        Ok(v) => v,
+        Err(reason) => {
+            span.skipped(reason, None);
+            return Ok(None);
+        }
+    };
+    if token.is_cancelled() {
+        span.err("logic", "E_CANCELLED", "cancelled", None);
+        return Err(anyhow!("cancelled"));
+    }
+    if let Err(e) = std::fs::write(output, &out) {
+        let message = format!("writing preprocessed audio failed: {e}");
+        span.err("io", "E_PREPROCESS_FAILED", &message, None);
+        return Err(anyhow!("E_PREPROCESS_FAILED: {message}"));
+    }
+    let ms = t0.elapsed().as_millis();
+    span.ok(Some(serde_json::json!({
+        "elapsed_ms": ms,
+        "input_bytes": wav.len(),
+        "output_bytes": out.len(),
+    })));
+    Ok(Some(ms))
+}
+
+fn warm_spare_requested(data_dir: &Path) -> bool {
+    settings::load_settings(data_dir)
+        .map(|s| settings::resolve_preprocess_warm_spare_enabled(&s))
+        .unwrap_or(false)
+}
+
 fn managed_audio_artifact(path: &Path, data_dir: &Path) -> bool {
     path.starts_with(data_dir.join("preprocess")) || path.starts_with(data_dir.join("recordings"))
 }
 
+enum WaitError {
+    Cancelled,
+    TryWait(std::io::Error),
+}
+
+fn wait_cancellable(
+    child: &mut Child,
+    token: &tokio_util::sync::CancellationToken,
+) -> Result<ExitStatus, WaitError> {
+    loop {
+        if token.is_cancelled() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(WaitError::Cancelled);
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => return Ok(status),
+            Ok(None) => {}
+            Err(e) => return Err(WaitError::TryWait(e)),
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+}
+
 pub fn preprocess_ffmpeg_cancellable(
     data_dir: &Path,
     task_id: &str,
@@ -188,7 +347,14 @@ pub fn preprocess_ffmpeg_cancellable(
     pid_slot: &std::sync::Arc<std::sync::Mutex<Option<u32>>>,
     cfg: &PreprocessConfig,
 ) -> Result<u128> {
+    // Checked before resolving ffmpeg so recordings preprocess without it.
+    if native_wav_requested(data_dir, input) {
+        if let Some(ms) = preprocess_native(data_dir, task_id, input, output, token, cfg)? {
+            return Ok(ms);
+        }
+    }
     let cmd = ffmpeg_cmd()?;
+    let warm_spare = warm_spare_requested(data_dir);
     let span = Span::start(
         data_dir,
         Some(task_id),
@@ -196,105 +362,396 @@ pub fn preprocess_ffmpeg_cancellable(
         "FFMPEG.preprocess",
         Some(serde_json::json!({
             "cmd_hint": cmd_hint_for_trace(&cmd),
+            "warm_spare": warm_spare,
         })),
     );
 
     let t0 = Instant::now();
-    let args = match build_ffmpeg_preprocess_args(input, output, cfg) {
+    let mut spare = None;
+    if warm_spare {
+        let pipe_args = build_ffmpeg_pipe_args(cfg);
+        if ffmpeg_spare::streamable_input(input) {
+            spare = ffmpeg_spare::take(&cmd, &pipe_args);
+        }
+        // Spawned now so it is warm by the time the next task arrives.
+        ffmpeg_spare::refill(cmd.clone(), pipe_args);
+    } else {
+        ffmpeg_spare::discard();
+    }
+
+    let (mut child, pipe_io) = match spare {
+        Some(mut child) => {
+            match File::open(input).and_then(|f| ffmpeg_spare::start_io(&mut child, f, output)) {
+                Ok(io) => (child, Some(io)),
+                Err(e) => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    let message = format!("preprocess input/output failed: {e}");
+                    span.err("io", "E_PREPROCESS_FAILED", &message, None);
+                    return Err(anyhow!("E_PREPROCESS_FAILED: {message}"));
+                }
+            }
+        }
+        None => {
+            let args = match build_ffmpeg_preprocess_args(input, output, cfg) {
+                Ok(v) => v,
+                Err(e) => {
+                    span.err("io", "E_PATH_UTF8", &e.to_string(), None);
+                    return Err(e);
+                }
+            };
+            match Command::new(&cmd)
+                .args(args)
+                .stdout(Stdio::null())
+                .stderr(Stdio::piped())
+                .no_console()
+                .spawn()
+            {
+                Ok(c) => (c, None),
+                Err(e) => {
+                    if e.kind() == std::io::ErrorKind::NotFound {
+                        span.err(
+                            "process",
+                            "E_FFMPEG_NOT_FOUND",
+                            &format!("ffmpeg not found (cmd={cmd})"),
+                            None,
+                        );
+                        return Err(anyhow!("E_FFMPEG_NOT_FOUND: ffmpeg not found (cmd={cmd})"));
+                    }
+                    span.err(
+                        "process",
+                        "E_FFMPEG_FAILED",
+                        &format!("failed to start ffmpeg (cmd={cmd}): {e}"),
+                        None,
+                    );
+                    return Err(anyhow!(
+                        "E_FFMPEG_FAILED: failed to start ffmpeg (cmd={cmd}): {e}"
+                    ));
+                }
+            }
+        }
+    };
+    let spare_used = pipe_io.is_some();
+
+    *pid_slot.lock().unwrap() = Some(child.id());
+    let waited = wait_cancellable(&mut child, token);
+    *pid_slot.lock().unwrap() = None;
+    // Drained on success too, to avoid holding OS pipes unnecessarily.
+    let (written, excerpt) = match pipe_io {
+        Some(io) => {
+            let (written, excerpt) = io.finish();
+            (Some(written), excerpt)
+        }
+        None => (None, stderr_excerpt_from_child(child.stderr.take())),
+    };
+    let status = match waited {
+        Ok(status) => status,
+        Err(WaitError::Cancelled) => {
+            span.err("logic", "E_CANCELLED", "cancelled", None);
+            return Err(anyhow!("cancelled"));
+        }
+        Err(WaitError::TryWait(e)) => {
+            span.err(
+                "io",
+                "E_FFMPEG_TRYWAIT",
+                &format!("ffmpeg try_wait failed: {e}"),
+                None,
+            );
+            return Err(anyhow!("ffmpeg try_wait failed: {e}"));
+        }
+    };
+    if !status.success() {
+        if debug::verbose_enabled() {
+            let _ = debug::write_payload_best_effort(
+                data_dir,
+                task_id,
+                "ffmpeg_stderr.txt",
+                excerpt.as_bytes().to_vec(),
+            );
+        }
+        let message = format!("ffmpeg preprocess failed: exit={status} stderr={excerpt}");
+        span.err(
+            "process",
+            "E_FFMPEG_FAILED",
+            &message,
+            Some(serde_json::json!({
+                "exit": status.to_string(),
+                "stderr_chars": excerpt.len(),
+                "warm_spare_used": spare_used,
+            })),
+        );
+        return Err(anyhow!("E_FFMPEG_FAILED: {message}"));
+    }
+    if let Some(Err(e)) = written {
+        let message = format!("writing preprocessed audio failed: {e}");
+        span.err("io", "E_PREPROCESS_FAILED", &message, None);
+        return Err(anyhow!("E_PREPROCESS_FAILED: {message}"));
+    }
+    let ms = t0.elapsed().as_millis();
+    span.ok(Some(serde_json::json!({
+        "elapsed_ms": ms,
+        "warm_spare_used": spare_used,
+    })));
+    Ok(ms)
+}
+
+// What ffprobe found in an input asset before any decoding work is spent on it.
+// `duration_ms` is None when the container carries no duration (e.g. a
+// recording whose header was never finalized); that is not an error.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InputProbe {
+    pub format_name: String,
+    pub codec_name: String,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    pub duration_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FfprobeOutput {
+    #[serde(default)]
+    streams: Vec<FfprobeStream>,
+    format: Option<FfprobeFormat>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FfprobeStream {
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    sample_rate: Option<String>,
+    channels: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FfprobeFormat {
+    format_name: Option<String>,
+    duration: Option<String>,
+}
+
+fn build_ffprobe_args(input: &Path) -> Result<Vec<String>> {
+    let input_s = input
+        .to_str()
+        .ok_or_else(|| anyhow!("non-utf8 input path"))?
+        .to_string();
+    Ok(vec![
+        "-v".to_string(),
+        "error".to_string(),
+        "-show_entries".to_string(),
+        "stream=codec_type,codec_name,sample_rate,channels:format=format_name,duration".to_string(),
+        "-of".to_string(),
+        "json".to_string(),
+        input_s,
+    ])
+}
+
+fn parse_ffprobe_output(stdout: &str) -> Result<InputProbe> {
+    let out: FfprobeOutput = serde_json::from_str(stdout)
+        .map_err(|e| anyhow!("E_INPUT_INVALID: unreadable ffprobe output: {e}"))?;
+    let format = out
+        .format
+        .ok_or_else(|| anyhow!("E_INPUT_INVALID: no container format detected"))?;
+    let audio = out
+        .streams
+        .into_iter()
+        .find(|s| s.codec_type.as_deref() == Some("audio"))
+        .ok_or_else(|| anyhow!("E_INPUT_INVALID: input has no audio stream"))?;
+    let duration_ms = format
+        .duration
+        .as_deref()
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .filter(|v| v.is_finite() && *v >= 0.0)
+        .map(|v| (v * 1000.0).round() as u64);
+    Ok(InputProbe {
+        format_name: format.format_name.unwrap_or_default(),
+        codec_name: audio.codec_name.unwrap_or_default(),
+        sample_rate: audio.sample_rate.and_then(|v| v.parse().ok()),
+        channels: audio.channels,
+        duration_ms,
+    })
+}
+
+// Runs before preprocessing so a file ffmpeg cannot decode is rejected with
+// `E_INPUT_INVALID` instead of surfacing as an ffmpeg failure.
+pub fn probe_input(data_dir: &Path, task_id: &str, input: &Path) -> Result<InputProbe> {
+    let cmd = ffprobe_cmd()?;
+    let span = Span::start(
+        data_dir,
+        Some(task_id),
+        "Probe",
+        "FFPROBE.input",
+        Some(serde_json::json!({
+            "cmd_hint": cmd_hint_for_trace(&cmd),
+        })),
+    );
+    let args = match build_ffprobe_args(input) {
         Ok(v) => v,
         Err(e) => {
             span.err("io", "E_PATH_UTF8", &e.to_string(), None);
             return Err(e);
         }
     };
-
-    let mut child = match Command::new(&cmd)
+    let output = match Command::new(&cmd)
         .args(args)
-        .stdout(Stdio::null())
-        .stderr(Stdio::piped())
+        .stdin(Stdio::null())
         .no_console()
-        .spawn()
+        .output()
     {
-        Ok(c) => c,
+        Ok(o) => o,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let message = format!("ffprobe not found (cmd={cmd})");
+            span.err("process", "E_FFPROBE_NOT_FOUND", &message, None);
+            return Err(anyhow!("E_FFPROBE_NOT_FOUND: {message}"));
+        }
         Err(e) => {
-            if e.kind() == std::io::ErrorKind::NotFound {
-                span.err(
-                    "process",
-                    "E_FFMPEG_NOT_FOUND",
-                    &format!("ffmpeg not found (cmd={cmd})"),
-                    None,
-                );
-                return Err(anyhow!("E_FFMPEG_NOT_FOUND: ffmpeg not found (cmd={cmd})"));
-            }
-            span.err(
-                "process",
-                "E_FFMPEG_FAILED",
-                &format!("failed to start ffmpeg (cmd={cmd}): {e}"),
-                None,
-            );
-            return Err(anyhow!(
-                "E_FFMPEG_FAILED: failed to start ffmpeg (cmd={cmd}): {e}"
-            ));
+            let message = format!("failed to start ffprobe (cmd={cmd}): {e}");
+            span.err("process", "E_FFPROBE_FAILED", &message, None);
+            return Err(anyhow!("E_FFPROBE_FAILED: {message}"));
         }
     };
+    if !output.status.success() {
+        let excerpt = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let message = format!("input rejected by ffprobe: {excerpt}");
+        span.err(
+            "input",
+            "E_INPUT_INVALID",
+            &message,
+            Some(serde_json::json!({
+                "exit": output.status.to_string(),
+                "stderr_chars": excerpt.len(),
+            })),
+        );
+        return Err(anyhow!("E_INPUT_INVALID: {message}"));
+    }
+    match parse_ffprobe_output(&String::from_utf8_lossy(&output.stdout)) {
+        Ok(probe) => {
+            span.ok(Some(serde_json::json!({
+                "format": probe.format_name,
+                "codec": probe.codec_name,
+                "sample_rate": probe.sample_rate,
+                "channels": probe.channels,
+                "duration_ms": probe.duration_ms,
+            })));
+            Ok(probe)
+        }
+        Err(e) => {
+            span.err_anyhow("input", "E_INPUT_INVALID", &e, None);
+            Err(e)
+        }
+    }
+}
 
-    *pid_slot.lock().unwrap() = Some(child.id());
+const RETAINED_RECORDING_EXTENSIONS: &[&str] = &["wav", "flac", "opus"];
 
-    loop {
-        if token.is_cancelled() {
-            let _ = child.kill();
-            let _ = child.wait();
-            *pid_slot.lock().unwrap() = None;
-            span.err("logic", "E_CANCELLED", "cancelled", None);
-            return Err(anyhow!("cancelled"));
+// The kept preprocessed audio for a task, in whichever format it was stored.
+pub fn retained_recording(data_dir: &Path, task_id: &str) -> Option<PathBuf> {
+    let dir = data_dir.join("preprocess");
+    RETAINED_RECORDING_EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(format!("{task_id}.{ext}")))
+        .find(|p| p.is_file())
+}
+
+fn build_recording_transcode_args(
+    input: &Path,
+    output: &Path,
+    format: &str,
+) -> Result<Vec<String>> {
+    let input_s = input
+        .to_str()
+        .ok_or_else(|| anyhow!("non-utf8 input path"))?
+        .to_string();
+    let output_s = output
+        .to_str()
+        .ok_or_else(|| anyhow!("non-utf8 output path"))?
+        .to_string();
+    let codec: &[&str] = match format {
+        "flac" => &["-c:a", "flac"],
+        // Speech at 16 kHz mono stays intelligible well below this rate.
+        "opus" => &["-c:a", "libopus", "-b:a", "24k"],
+        other => return Err(anyhow!("unsupported recording format: {other}")),
+    };
+    let mut args = vec![
+        "-y".to_string(),
+        "-hide_banner".to_string(),
+        "-loglevel".to_string(),
+        "error".to_string(),
+        "-i".to_string(),
+        input_s,
+        "-vn".to_string(),
+    ];
+    args.extend(codec.iter().map(|s| s.to_string()));
+    args.push(output_s);
+    Ok(args)
+}
+
+// Re-encodes a kept task WAV next to itself. The WAV is only removed once the
+// new file is written; on failure the partial output goes and the WAV stays.
+pub fn transcode_retained_recording(
+    data_dir: &Path,
+    task_id: &str,
+    wav_path: &Path,
+    format: &str,
+) -> Result<PathBuf> {
+    let output = wav_path.with_extension(format);
+    let span = Span::start(
+        data_dir,
+        Some(task_id),
+        "Retain",
+        "FFMPEG.transcode_recording",
+        Some(serde_json::json!({ "format": format })),
+    );
+    match run_recording_transcode(wav_path, &output, format) {
+        Ok(()) => {
+            let wav_bytes = std::fs::metadata(wav_path).map(|m| m.len()).ok();
+            let out_bytes = std::fs::metadata(&output).map(|m| m.len()).ok();
+            let _ = std::fs::remove_file(wav_path);
+            span.ok(Some(serde_json::json!({
+                "wav_bytes": wav_bytes,
+                "out_bytes": out_bytes,
+            })));
+            Ok(output)
         }
-        let status_opt = match child.try_wait() {
-            Ok(s) => s,
-            Err(e) => {
-                span.err(
-                    "io",
-                    "E_FFMPEG_TRYWAIT",
-                    &format!("ffmpeg try_wait failed: {e}"),
-                    None,
-                );
-                *pid_slot.lock().unwrap() = None;
-                return Err(anyhow!("ffmpeg try_wait failed: {e}"));
-            }
-        };
-        if let Some(status) = status_opt {
-            if !status.success() {
-                let excerpt = stderr_excerpt_from_child(child.stderr.take());
-                *pid_slot.lock().unwrap() = None;
-                if debug::verbose_enabled() {
-                    let _ = debug::write_payload_best_effort(
-                        data_dir,
-                        task_id,
-                        "ffmpeg_stderr.txt",
-                        excerpt.as_bytes().to_vec(),
-                    );
-                }
-                let message = format!("ffmpeg preprocess failed: exit={status} stderr={excerpt}");
-                span.err(
-                    "process",
-                    "E_FFMPEG_FAILED",
-                    &message,
-                    Some(serde_json::json!({
-                        "exit": status.to_string(),
-                        "stderr_chars": excerpt.len(),
-                    })),
-                );
-                return Err(anyhow!("E_FFMPEG_FAILED: {message}"));
-            }
-            break;
+        Err((code, message)) => {
+            let _ = std::fs::remove_file(&output);
+            span.err("process", code, &message, None);
+            Err(anyhow!("{code}: {message}"))
         }
-        std::thread::sleep(std::time::Duration::from_millis(10));
     }
-    // Drain stderr on success too, to avoid holding OS pipes unnecessarily.
-    let _ = stderr_excerpt_from_child(child.stderr.take());
-    *pid_slot.lock().unwrap() = None;
-    let ms = t0.elapsed().as_millis();
-    span.ok(Some(serde_json::json!({ "elapsed_ms": ms })));
-    Ok(ms)
+}
+
+fn run_recording_transcode(
+    wav_path: &Path,
+    output: &Path,
+    format: &str,
+) -> std::result::Result<(), (&'static str, String)> {
+    let cmd = ffmpeg_cmd().map_err(|e| ("E_FFMPEG_NOT_FOUND", e.to_string()))?;
+    let args = build_recording_transcode_args(wav_path, output, format)
+        .map_err(|e| ("E_RECORDING_TRANSCODE_FAILED", e.to_string()))?;
+    let o = Command::new(&cmd)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .no_console()
+        .output()
+        .map_err(|e| {
+            let code = if e.kind() == std::io::ErrorKind::NotFound {
+                "E_FFMPEG_NOT_FOUND"
+            } else {
+                "E_RECORDING_TRANSCODE_FAILED"
+            };
+            (code, format!("failed to start ffmpeg (cmd={cmd}): {e}"))
+        })?;
+    if !o.status.success() || !output.is_file() {
+        return Err((
+            "E_RECORDING_TRANSCODE_FAILED",
+            format!(
+                "ffmpeg exited with {}: {}",
+                o.status,
+                String::from_utf8_lossy(&o.stderr).trim()
+            ),
+        ));
+    }
+    Ok(())
 }
 
 // Intentionally no generic "run_audio_pipeline" helper to keep call sites explicit.
@@ -303,6 +760,35 @@ pub fn preprocess_ffmpeg_cancellable(
 mod tests {
     use super::*;
 
+    #[test]
+    fn recording_transcode_args_pick_codec_by_format() {
+        let args =
+            build_recording_transcode_args(Path::new("t1.wav"), Path::new("t1.opus"), "opus")
+                .expect("args");
+        assert_eq!(args[args.len() - 1], "t1.opus");
+        assert!(args.windows(2).any(|w| w == ["-c:a", "libopus"]));
+        let args =
+            build_recording_transcode_args(Path::new("t1.wav"), Path::new("t1.flac"), "flac")
+                .expect("args");
+        assert!(args.windows(2).any(|w| w == ["-c:a", "flac"]));
+        assert!(
+            build_recording_transcode_args(Path::new("a.wav"), Path::new("a.wav"), "wav").is_err()
+        );
+    }
+
+    #[test]
+    fn retained_recording_finds_any_kept_format() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        assert!(retained_recording(tmp.path(), "t1").is_none());
+        let dir = tmp.path().join("preprocess");
+        std::fs::create_dir_all(&dir).expect("mkdir");
+        std::fs::write(dir.join("t1.flac"), b"x").expect("write");
+        assert_eq!(
+            retained_recording(tmp.path(), "t1"),
+            Some(dir.join("t1.flac"))
+        );
+    }
+
     #[test]
     fn ffmpeg_preprocess_args_keep_asr_input_format() {
         let args = build_ffmpeg_preprocess_args(
@@ -322,6 +808,111 @@ mod tests {
             "pcm_s16le"
         );
         assert_eq!(args.last().map(String::as_str), Some("out.wav"));
+        assert!(!args.iter().any(|v| v == "-af"));
+    }
+
+    #[test]
+    fn ffmpeg_preprocess_args_apply_gain_before_silence_trim() {
+        let cfg = PreprocessConfig {
+            silence_trim_enabled: true,
+            gain_db: 42.0,
+            ..PreprocessConfig::default()
+        };
+        let args = build_ffmpeg_preprocess_args(Path::new("in.ogg"), Path::new("out.wav"), &cfg)
+            .expect("build args");
+        let filter = &args[args.iter().position(|v| v == "-af").unwrap() + 1];
+
+        assert!(filter.starts_with("volume=20.0dB,silenceremove="));
+    }
+
+    #[test]
+    fn ffmpeg_pipe_args_share_the_filter_chain_without_paths() {
+        let cfg = PreprocessConfig {
+            gain_db: -3.0,
+            ..Default::default()
+        };
+        let args = build_ffmpeg_pipe_args(&cfg);
+        let file_args =
+            build_ffmpeg_preprocess_args(Path::new("in.wav"), Path::new("out.wav"), &cfg)
+                .expect("args");
+        let filter = |a: &[String]| {
+            let i = a.iter().position(|v| v == "-af").expect("-af");
+            a[i + 1].clone()
+        };
+        assert_eq!(filter(&args), filter(&file_args));
+        assert_eq!(
+            args[args.iter().position(|v| v == "-i").unwrap() + 1],
+            "pipe:0"
+        );
+        assert_eq!(args.last().map(String::as_str), Some("pipe:1"));
+        assert!(!args.iter().any(|v| v.ends_with(".wav")));
+    }
+
+    // Needs a real ffmpeg (TYPEVOICE_FFMPEG or the bundled toolchain); skipped
+    // when none is found.
+    #[test]
+    fn native_wav_preprocess_matches_ffmpeg_for_recorder_output() {
+        let Ok(cmd) = ffmpeg_cmd() else {
+            return;
+        };
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let samples: Vec<f32> = (0..16_000)
+            .map(|i| ((i * 37) % 2000) as f32 / 32768.0 - 0.03)
+            .collect();
+        let input = tmp.path().join("recording.wav");
+        std::fs::write(&input, wav_preprocess::encode_wav_s16(&samples, 16_000)).expect("input");
+        let cfg = PreprocessConfig::default();
+        let output = tmp.path().join("ffmpeg.wav");
+        let args = build_ffmpeg_preprocess_args(&input, &output, &cfg).expect("args");
+        let status = Command::new(&cmd).args(args).status().expect("run ffmpeg");
+        assert!(status.success());
+
+        // ffmpeg adds its own header chunks, so compare the decoded samples.
+        let pcm_hash = |wav: &[u8]| {
+            let audio = wav_preprocess::decode_mono(wav).expect("decode");
+            crate::context_pack::sha256_hex(
+                &wav_preprocess::encode_wav_s16(&audio.samples, audio.sample_rate)[44..],
+            )
+        };
+        let native = wav_preprocess::preprocess_wav(
+            &std::fs::read(&input).expect("read"),
+            &native_preprocess_config(&cfg),
+        )
+        .expect("native");
+        assert_eq!(
+            pcm_hash(&native),
+            pcm_hash(&std::fs::read(&output).expect("ffmpeg output"))
+        );
+    }
+
+    #[test]
+    fn ffprobe_output_yields_audio_stream_and_duration() {
+        let probe = parse_ffprobe_output(
+            r#"{"streams":[{"codec_type":"video","codec_name":"mjpeg"},
+                {"codec_type":"audio","codec_name":"opus","sample_rate":"48000","channels":2}],
+                "format":{"format_name":"ogg","duration":"3.2005"}}"#,
+        )
+        .expect("probe");
+        assert_eq!(probe.codec_name, "opus");
+        assert_eq!(probe.sample_rate, Some(48_000));
+        assert_eq!(probe.duration_ms, Some(3_201));
+
+        let no_duration = parse_ffprobe_output(
+            r#"{"streams":[{"codec_type":"audio","codec_name":"pcm_s16le"}],"format":{"format_name":"wav","duration":"N/A"}}"#,
+        )
+        .expect("probe");
+        assert_eq!(no_duration.duration_ms, None);
+    }
+
+    #[test]
+    fn ffprobe_output_without_audio_is_invalid_input() {
+        let err = parse_ffprobe_output(
+            r#"{"streams":[{"codec_type":"video","codec_name":"h264"}],"format":{"format_name":"mp4"}}"#,
+        )
+        .expect_err("no audio");
+        assert!(err.to_string().starts_with("E_INPUT_INVALID"));
+        assert!(parse_ffprobe_output("{}").is_err());
+        assert!(parse_ffprobe_output("not json").is_err());
     }
 
     #[test]
@@ -0,0 +1,544 @@
+use std::{
+    io::{Read, Write},
+    path::Path,
+    process::{Command, Stdio},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
+use crate::obs;
+use crate::ports::{PortError, PortResult};
+use crate::settings::PostprocessorResolved;
+use crate::subprocess::CommandNoConsoleExt;
+use crate::write_gate;
+
+const OUTPUT_LIMIT_BYTES: usize = 1 << 20;
+const STDERR_EXCERPT_BYTES: usize = 2_000;
+const WASM_MEMORY_LIMIT_BYTES: usize = 64 << 20;
+// The interpreter runs roughly this many instructions per millisecond, so the
+// fuel budget stands in for a wall-clock timeout.
+const WASM_FUEL_PER_MS: u64 = 100_000;
+// Passed through to commands; everything else in the environment is dropped.
+const COMMAND_ENV_ALLOWLIST: &[&str] = &["PATH", "SYSTEMROOT", "TEMP", "TMP", "LANG", "HOME"];
+
+// Sent to every post-processor, as JSON on stdin or in WASM linear memory.
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginInput<'a> {
+    pub version: u32,
+    pub task_id: &'a str,
+    pub mode: &'a str, // dictation|rewrite
+    pub text: &'a str,
+}
+
+// `text` replaces the current text; a missing field or empty output leaves it
+// unchanged, for plugins that only forward the text somewhere.
+#[derive(Debug, Deserialize)]
+struct PluginOutput {
+    text: Option<String>,
+}
+
+// Runs the chain in order. A failing plugin is reported and skipped so the
+// text it was given flows on to the next one. Blocking: callers on an async
+// runtime or the UI thread go through `spawn_blocking`. Cancelling stops the
+// chain and kills a running command.
+pub fn run_chain(
+    data_dir: &Path,
+    task_id: &str,
+    mode: &str,
+    stage: &str,
+    plugins: &[PostprocessorResolved],
+    text: String,
+    cancel: &CancellationToken,
+) -> String {
+    let mut text = text;
+    for plugin in plugins {
+        if cancel.is_cancelled() {
+            break;
+        }
+        let t0 = Instant::now();
+        let input = PluginInput {
+            version: 1,
+            task_id,
+            mode,
+            text: &text,
+        };
+        match run_one(data_dir, plugin, &input, cancel) {
+            Ok(next) => {
+                obs::event(
+                    data_dir,
+                    Some(task_id),
+                    stage,
+                    "TEXT.postprocessor",
+                    "ok",
+                    Some(serde_json::json!({
+                        "id": plugin.id,
+                        "kind": plugin.kind,
+                        "changed": next.as_ref().is_some_and(|v| *v != text),
+                        "elapsed_ms": t0.elapsed().as_millis(),
+                    })),
+                );
+                if let Some(next) = next {
+                    text = next;
+                }
+            }
+            Err(e) => obs::event_err(
+                data_dir,
+                obs::ErrorEvent {
+                    task_id: Some(task_id),
+                    stage,
+                    step_id: "TEXT.postprocessor",
+                    kind: "plugin",
+                    code: &e.code,
+                    ctx: Some(serde_json::json!({
+                        "id": plugin.id,
+                        "kind": plugin.kind,
+                        "elapsed_ms": t0.elapsed().as_millis(),
+                    })),
+                },
+                &e.message,
+            ),
+        }
+    }
+    text
+}
+
+pub fn run_one(
+    data_dir: &Path,
+    plugin: &PostprocessorResolved,
+    input: &PluginInput<'_>,
+    cancel: &CancellationToken,
+) -> PortResult<Option<String>> {
+    let payload = serde_json::to_vec(input)
+        .map_err(|e| PortError::new("E_POSTPROCESS_INPUT", e.to_string()))?;
+    let output = match plugin.kind.as_str() {
+        "command" => run_command(data_dir, plugin, payload, cancel)?,
+        "wasm" => run_wasm(plugin, &payload)?,
+        other => {
+            return Err(PortError::new(
+                "E_POSTPROCESS_KIND",
+                format!("unknown post-processor kind: {other}"),
+            ))
+        }
+    };
+    parse_output(&output)
+}
+
+fn parse_output(output: &[u8]) -> PortResult<Option<String>> {
+    if output.iter().all(u8::is_ascii_whitespace) {
+        return Ok(None);
+    }
+    let parsed: PluginOutput = serde_json::from_slice(output).map_err(|e| {
+        PortError::new(
+            "E_POSTPROCESS_OUTPUT_INVALID",
+            format!("expected {{\"text\": ...}} JSON: {e}"),
+        )
+    })?;
+    Ok(parsed.text)
+}
+
+// The command runs in its own work directory under the data dir with a
+// minimal environment, gets the input on stdin and is killed at the timeout.
+fn run_command(
+    data_dir: &Path,
+    plugin: &PostprocessorResolved,
+    payload: Vec<u8>,
+    cancel: &CancellationToken,
+) -> PortResult<Vec<u8>> {
+    let work_dir = data_dir
+        .join("postprocessors")
+        .join(sanitize_id(&plugin.id));
+    // Guest mode and a locked data dir must not gain a work dir either.
+    write_gate::check(&work_dir)
+        .map_err(|e| PortError::from_message("E_POSTPROCESS_START", e.to_string()))?;
+    std::fs::create_dir_all(&work_dir)
+        .map_err(|e| PortError::new("E_POSTPROCESS_START", format!("create work dir: {e}")))?;
+    let mut cmd = Command::new(&plugin.path);
+    cmd.args(&plugin.args).current_dir(&work_dir).env_clear();
+    for key in COMMAND_ENV_ALLOWLIST {
+        if let Some(v) = std::env::var_os(key) {
+            cmd.env(key, v);
+        }
+    }
+    let mut child = cmd
+        .env("TYPEVOICE_POSTPROCESSOR_ID", &plugin.id)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .no_console()
+        .spawn()
+        .map_err(|e| {
+            PortError::new(
+                "E_POSTPROCESS_START",
+                format!("failed to start '{}': {e}", plugin.path),
+            )
+        })?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // A plugin may exit without reading stdin; that is not an error here.
+        thread::spawn(move || {
+            let _ = stdin.write_all(&payload);
+        });
+    }
+    let stdout = spawn_reader(child.stdout.take(), OUTPUT_LIMIT_BYTES + 1);
+    let stderr = spawn_reader(child.stderr.take(), STDERR_EXCERPT_BYTES);
+
+    let deadline = Instant::now() + Duration::from_millis(plugin.timeout_ms);
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if cancel.is_cancelled() => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(PortError::new(
+                    "E_CANCELLED",
+                    format!("'{}' was cancelled", plugin.id),
+                ));
+            }
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(PortError::new(
+                    "E_POSTPROCESS_TIMEOUT",
+                    format!("'{}' did not finish in {} ms", plugin.id, plugin.timeout_ms),
+                ));
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(10)),
+            Err(e) => return Err(PortError::new("E_POSTPROCESS_WAIT", e.to_string())),
+        }
+    };
+    // Readers finish once the pipes close; a grandchild holding them open is
+    // given the rest of the budget at most.
+    let remaining = deadline.saturating_duration_since(Instant::now()) + Duration::from_millis(100);
+    let out = stdout.recv_timeout(remaining).unwrap_or_default();
+    let err = stderr
+        .recv_timeout(Duration::from_millis(100))
+        .unwrap_or_default();
+    if !status.success() {
+        return Err(PortError::new(
+            "E_POSTPROCESS_EXIT",
+            format!(
+                "'{}' exited with {status}: {}",
+                plugin.id,
+                String::from_utf8_lossy(&err).trim()
+            ),
+        ));
+    }
+    if out.len() > OUTPUT_LIMIT_BYTES {
+        return Err(output_too_large(&plugin.id));
+    }
+    Ok(out)
+}
+
+fn spawn_reader<R: Read + Send + 'static>(
+    pipe: Option<R>,
+    limit: usize,
+) -> mpsc::Receiver<Vec<u8>> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(pipe) = pipe {
+            let _ = pipe.take(limit as u64).read_to_end(&mut buf);
+        }
+        let _ = tx.send(buf);
+    });
+    rx
+}
+
+// A module exports `memory`, `alloc(len: i32) -> i32` and
+// `postprocess(ptr: i32, len: i32) -> i64` returning `(out_ptr << 32) | out_len`.
+// Modules with imports are rejected, so plugins have no host access at all.
+fn run_wasm(plugin: &PostprocessorResolved, payload: &[u8]) -> PortResult<Vec<u8>> {
+    let bytes = std::fs::read(&plugin.path).map_err(|e| {
+        PortError::new(
+            "E_POSTPROCESS_START",
+            format!("failed to read '{}': {e}", plugin.path),
+        )
+    })?;
+    let mut config = wasmi::Config::default();
+    config.consume_fuel(true);
+    let engine = wasmi::Engine::new(&config);
+    let module = wasmi::Module::new(&engine, &bytes[..])
+        .map_err(|e| PortError::new("E_POSTPROCESS_WASM_INVALID", e.to_string()))?;
+    if module.imports().len() > 0 {
+        return Err(PortError::new(
+            "E_POSTPROCESS_WASM_IMPORTS",
+            "post-processor modules must not import host functions",
+        ));
+    }
+    let limits = wasmi::StoreLimitsBuilder::new()
+        .memory_size(WASM_MEMORY_LIMIT_BYTES)
+        .build();
+    let mut store = wasmi::Store::new(&engine, limits);
+    store.limiter(|limits| limits);
+    store
+        .set_fuel(plugin.timeout_ms.saturating_mul(WASM_FUEL_PER_MS))
+        .map_err(|e| wasm_error(plugin, e))?;
+    let linker = wasmi::Linker::<wasmi::StoreLimits>::new(&engine);
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .and_then(|pre| pre.start(&mut store))
+        .map_err(|e| wasm_error(plugin, e))?;
+    let memory = instance
+        .get_memory(&store, "memory")
+        .ok_or_else(|| missing_export("memory"))?;
+    let alloc = instance
+        .get_typed_func::<i32, i32>(&store, "alloc")
+        .map_err(|_| missing_export("alloc"))?;
+    let postprocess = instance
+        .get_typed_func::<(i32, i32), i64>(&store, "postprocess")
+        .map_err(|_| missing_export("postprocess"))?;
+
+    let len = i32::try_from(payload.len()).map_err(|_| output_too_large(&plugin.id))?;
+    let ptr = alloc
+        .call(&mut store, len)
+        .map_err(|e| wasm_error(plugin, e))?;
+    memory
+        .write(&mut store, ptr as u32 as usize, payload)
+        .map_err(|e| wasm_error(plugin, e))?;
+    let packed = postprocess
+        .call(&mut store, (ptr, len))
+        .map_err(|e| wasm_error(plugin, e))? as u64;
+    let out_ptr = (packed >> 32) as usize;
+    let out_len = (packed & 0xffff_ffff) as usize;
+    if out_len > OUTPUT_LIMIT_BYTES {
+        return Err(output_too_large(&plugin.id));
+    }
+    let mut out = vec![0u8; out_len];
+    memory
+        .read(&store, out_ptr, &mut out)
+        .map_err(|e| wasm_error(plugin, e))?;
+    Ok(out)
+}
+
+fn wasm_error(plugin: &PostprocessorResolved, e: impl std::fmt::Display) -> PortError {
+    let message = e.to_string();
+    if message.contains("fuel") {
+        return PortError::new(
+            "E_POSTPROCESS_TIMEOUT",
+            format!(
+                "'{}' ran out of its {} ms budget",
+                plugin.id, plugin.timeout_ms
+            ),
+        );
+    }
+    PortError::new("E_POSTPROCESS_WASM_TRAP", message)
+}
+
+fn missing_export(name: &str) -> PortError {
+    PortError::new(
+        "E_POSTPROCESS_WASM_INVALID",
+        format!("module does not export '{name}' with the expected type"),
+    )
+}
+
+fn output_too_large(id: &str) -> PortError {
+    PortError::new(
+        "E_POSTPROCESS_OUTPUT_TOO_LARGE",
+        format!("'{id}' exchanged more than {OUTPUT_LIMIT_BYTES} bytes"),
+    )
+}
+
+fn sanitize_id(id: &str) -> String {
+    id.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugin(kind: &str, path: &str, args: &[&str], timeout_ms: u64) -> PostprocessorResolved {
+        PostprocessorResolved {
+            id: "test".to_string(),
+            kind: kind.to_string(),
+            path: path.to_string(),
+            args: args.iter().map(|v| v.to_string()).collect(),
+            timeout_ms,
+        }
+    }
+
+    fn input(text: &str) -> PluginInput<'_> {
+        PluginInput {
+            version: 1,
+            task_id: "t1",
+            mode: "dictation",
+            text,
+        }
+    }
+
+    // Upper-cases ASCII in place and returns the input JSON unchanged in
+    // length, which still carries the "text" field.
+    const UPPER_WAT: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+          (func (export "postprocess") (param $ptr i32) (param $len i32) (result i64)
+            (local $i i32) (local $c i32)
+            (block $done
+              (loop $next
+                (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+                (local.set $c (i32.load8_u (i32.add (local.get $ptr) (local.get $i))))
+                (if (i32.and (i32.ge_u (local.get $c) (i32.const 97))
+                             (i32.le_u (local.get $c) (i32.const 122)))
+                  (then (i32.store8 (i32.add (local.get $ptr) (local.get $i))
+                                    (i32.sub (local.get $c) (i32.const 32)))))
+                (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                (br $next)))
+            (i64.or (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+                    (i64.extend_i32_u (local.get $len)))))
+    "#;
+
+    fn write_wasm(dir: &Path, name: &str, wat: &str) -> String {
+        let path = dir.join(name);
+        std::fs::write(&path, wat::parse_str(wat).expect("wat")).expect("write wasm");
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn output_json_replaces_text_and_empty_output_keeps_it() {
+        assert_eq!(
+            parse_output(br#"{"text":"hi"}"#).expect("json"),
+            Some("hi".to_string())
+        );
+        assert_eq!(parse_output(b" \n").expect("empty"), None);
+        assert_eq!(parse_output(br#"{"ok":true}"#).expect("no text"), None);
+        assert_eq!(
+            parse_output(b"plain").expect_err("invalid").code,
+            "E_POSTPROCESS_OUTPUT_INVALID"
+        );
+    }
+
+    #[test]
+    fn wasm_module_transforms_text_and_is_sandboxed() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let upper = write_wasm(tmp.path(), "upper.wasm", UPPER_WAT);
+        // Keys are upper-cased as well, so check the raw module output.
+        let out = run_wasm(&plugin("wasm", &upper, &[], 1_000), br#"{"text":"abc"}"#).expect("run");
+        assert_eq!(out, br#"{"TEXT":"ABC"}"#);
+
+        let spin = write_wasm(
+            tmp.path(),
+            "spin.wasm",
+            r#"(module
+                 (memory (export "memory") 1)
+                 (func (export "alloc") (param i32) (result i32) (i32.const 0))
+                 (func (export "postprocess") (param i32 i32) (result i64)
+                   (loop $l (br $l)) (i64.const 0)))"#,
+        );
+        let err = run_one(
+            tmp.path(),
+            &plugin("wasm", &spin, &[], 100),
+            &input("x"),
+            &CancellationToken::new(),
+        )
+        .expect_err("fuel");
+        assert_eq!(err.code, "E_POSTPROCESS_TIMEOUT");
+
+        let host = write_wasm(
+            tmp.path(),
+            "host.wasm",
+            r#"(module (import "env" "system" (func)) (memory (export "memory") 1))"#,
+        );
+        let err = run_one(
+            tmp.path(),
+            &plugin("wasm", &host, &[], 100),
+            &input("x"),
+            &CancellationToken::new(),
+        )
+        .expect_err("imports");
+        assert_eq!(err.code, "E_POSTPROCESS_WASM_IMPORTS");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn command_gets_json_on_stdin_with_timeout_and_clean_env() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::env::set_var("TYPEVOICE_TEST_SECRET", "leak");
+        let echo = plugin(
+            "command",
+            "/bin/sh",
+            &[
+                "-c",
+                r#"read -r line; printf '{"text":"%s|%s"}' "${TYPEVOICE_TEST_SECRET:-clean}" "$TYPEVOICE_POSTPROCESSOR_ID""#,
+            ],
+            2_000,
+        );
+        let text = run_chain(
+            tmp.path(),
+            "t1",
+            "dictation",
+            "Transcribe",
+            &[echo],
+            "hi".into(),
+            &CancellationToken::new(),
+        );
+        assert_eq!(text, "clean|test");
+
+        let slow = plugin("command", "/bin/sh", &["-c", "sleep 5"], 200);
+        let err = run_one(tmp.path(), &slow, &input("x"), &CancellationToken::new())
+            .expect_err("timeout");
+        assert_eq!(err.code, "E_POSTPROCESS_TIMEOUT");
+
+        let failing = plugin(
+            "command",
+            "/bin/sh",
+            &["-c", "echo nope >&2; exit 3"],
+            2_000,
+        );
+        let err = run_one(tmp.path(), &failing, &input("x"), &CancellationToken::new())
+            .expect_err("exit");
+        assert_eq!(err.code, "E_POSTPROCESS_EXIT");
+        assert!(err.message.contains("nope"), "{}", err.message);
+        // A failing plugin is skipped and the text passes through.
+        let text = run_chain(
+            tmp.path(),
+            "t1",
+            "dictation",
+            "Transcribe",
+            &[failing],
+            "keep".into(),
+            &CancellationToken::new(),
+        );
+        assert_eq!(text, "keep");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cancelling_kills_a_running_command() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let slow = plugin("command", "/bin/sh", &["-c", "sleep 5"], 10_000);
+        let cancel = CancellationToken::new();
+        let canceller = cancel.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            canceller.cancel();
+        });
+        let t0 = Instant::now();
+        let err = run_one(tmp.path(), &slow, &input("x"), &cancel).expect_err("cancelled");
+        assert_eq!(err.code, "E_CANCELLED");
+        assert!(t0.elapsed() < Duration::from_secs(3));
+
+        // A cancelled chain does not start the next plugin.
+        let text = run_chain(
+            tmp.path(),
+            "t1",
+            "rewrite",
+            "Rewrite",
+            &[slow],
+            "keep".into(),
+            &cancel,
+        );
+        assert_eq!(text, "keep");
+    }
+}
@@ -0,0 +1,191 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use typevoice_storage::settings;
+
+// Power source changes on a scale of minutes and the foreground tracker asks
+// several times a second, so the answer is cached.
+const DETECT_CACHE_TTL: Duration = Duration::from_secs(30);
+
+struct Guard {
+    mode: &'static str,
+    asr_provider: String,
+    remote_asr_model: Option<String>,
+    cached: Option<(Instant, Option<bool>)>,
+}
+
+static GUARD: Mutex<Guard> = Mutex::new(Guard {
+    mode: settings::DEFAULT_LOW_POWER_MODE,
+    asr_provider: String::new(),
+    remote_asr_model: None,
+    cached: None,
+});
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PowerStatus {
+    pub mode: &'static str,
+    // None when the platform does not report a power source.
+    pub on_battery: Option<bool>,
+    // "manual" or "battery" while low power is active.
+    pub active: Option<&'static str>,
+}
+
+pub fn apply_from_settings(s: &settings::Settings) {
+    let mut g = GUARD.lock().unwrap();
+    g.mode = settings::resolve_low_power_mode(s);
+    g.asr_provider = settings::resolve_low_power_asr_provider(s);
+    g.remote_asr_model = settings::resolve_low_power_remote_asr_model(s);
+}
+
+fn on_battery_cached(g: &mut Guard) -> Option<bool> {
+    if let Some((at, found)) = g.cached {
+        if at.elapsed() < DETECT_CACHE_TTL {
+            return found;
+        }
+    }
+    let found = on_battery();
+    g.cached = Some((Instant::now(), found));
+    found
+}
+
+fn active_locked(g: &mut Guard) -> Option<&'static str> {
+    match g.mode {
+        "on" => Some("manual"),
+        "auto" => on_battery_cached(g).unwrap_or(false).then_some("battery"),
+        _ => None,
+    }
+}
+
+// Why low power is active right now, if it is.
+pub fn active() -> Option<&'static str> {
+    active_locked(&mut GUARD.lock().unwrap())
+}
+
+pub fn status() -> PowerStatus {
+    let mut g = GUARD.lock().unwrap();
+    let active = active_locked(&mut g);
+    PowerStatus {
+        mode: g.mode,
+        on_battery: on_battery_cached(&mut g),
+        active,
+    }
+}
+
+// The ASR provider and remote model a new task should use; outside low power
+// these are the regular settings.
+pub fn asr_provider(s: &settings::Settings) -> String {
+    let mut g = GUARD.lock().unwrap();
+    if active_locked(&mut g).is_some() && !g.asr_provider.is_empty() {
+        return g.asr_provider.clone();
+    }
+    settings::resolve_asr_provider(s)
+}
+
+pub fn remote_asr_model(s: &settings::Settings) -> Option<String> {
+    let mut g = GUARD.lock().unwrap();
+    if active_locked(&mut g).is_some() {
+        return g.remote_asr_model.clone();
+    }
+    settings::resolve_remote_asr_model(s)
+}
+
+#[cfg(windows)]
+fn on_battery() -> Option<bool> {
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return None;
+    }
+    // 0 = offline, 1 = online, 255 = unknown.
+    match status.ACLineStatus {
+        0 => Some(true),
+        1 => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn on_battery() -> Option<bool> {
+    let mut supplies = Vec::new();
+    for entry in std::fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+        let read = |name: &str| {
+            std::fs::read_to_string(entry.path().join(name))
+                .map(|v| v.trim().to_string())
+                .unwrap_or_default()
+        };
+        supplies.push((read("type"), read("online"), read("status")));
+    }
+    linux_on_battery(&supplies)
+}
+
+// Supplies as (type, online, status). Any online mains adapter means AC; a
+// discharging battery without one means battery.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn linux_on_battery(supplies: &[(String, String, String)]) -> Option<bool> {
+    if supplies
+        .iter()
+        .any(|(kind, online, _)| kind == "Mains" && online == "1")
+    {
+        return Some(false);
+    }
+    let batteries: Vec<_> = supplies
+        .iter()
+        .filter(|(kind, _, _)| kind == "Battery")
+        .collect();
+    if batteries.is_empty() {
+        return None;
+    }
+    Some(
+        batteries
+            .iter()
+            .any(|(_, _, status)| status == "Discharging"),
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn on_battery() -> Option<bool> {
+    let out = std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&out.stdout);
+    let first = text.lines().next()?;
+    if first.contains("'Battery Power'") {
+        Some(true)
+    } else if first.contains("'AC Power'") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux", target_os = "macos")))]
+fn on_battery() -> Option<bool> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::linux_on_battery;
+
+    fn supply(kind: &str, online: &str, status: &str) -> (String, String, String) {
+        (kind.to_string(), online.to_string(), status.to_string())
+    }
+
+    #[test]
+    fn linux_mains_online_wins_over_battery_status() {
+        let discharging = supply("Battery", "", "Discharging");
+        assert_eq!(linux_on_battery(&[discharging.clone()]), Some(true));
+        assert_eq!(
+            linux_on_battery(&[supply("Mains", "1", ""), discharging]),
+            Some(false)
+        );
+        assert_eq!(
+            linux_on_battery(&[supply("Mains", "0", ""), supply("Battery", "", "Full")]),
+            Some(false)
+        );
+        assert_eq!(linux_on_battery(&[supply("USB", "0", "")]), None);
+    }
+}
@@ -1,6 +1,8 @@
 use std::path::Path;
 
-use crate::audio_devices_windows::{self, AudioEndpointInfo, DefaultCaptureRole};
+use crate::audio_devices_windows::{
+    self, AudioEndpointInfo, CaptureFormatInfo, DefaultCaptureRole, EndpointTopology,
+};
 use crate::settings::{self, Settings};
 use crate::subprocess::CommandNoConsoleExt;
 
@@ -26,6 +28,9 @@ pub struct ResolvedRecordInput {
     pub friendly_name: Option<String>,
     pub resolved_by: String,
     pub resolution_log: Vec<ResolveLogEntry>,
+    pub device_override: Option<settings::RecordDeviceOverride>,
+    pub capture_format: Option<CaptureFormatInfo>,
+    pub quality_warning: Option<String>,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -81,9 +86,10 @@ struct DshowDevice {
 }
 
 #[derive(Debug)]
-struct AutoCandidate {
+struct AutoCandidate<'a> {
     spec: String,
     display_name: String,
+    endpoint: Option<&'a EndpointTopology>,
     score: i32,
     order: usize,
 }
@@ -139,6 +145,30 @@ fn normalize_record_input_spec(raw: &str) -> String {
     trimmed.to_string()
 }
 
+// Record input specs keep the dshow `audio=<device>` form on every platform so
+// the cached and last-working specs stay comparable; this maps one to the
+// ffmpeg input format and `-i` value of the current platform. On Linux the
+// default device goes through PulseAudio (which PipeWire also serves) and named
+// devices through ALSA, matching the names cpal enumerates.
+pub fn ffmpeg_input(spec: &str) -> (&'static str, String) {
+    let spec = normalize_record_input_spec(spec);
+    if cfg!(windows) {
+        return ("dshow", spec);
+    }
+    let device = spec
+        .strip_prefix("audio=")
+        .unwrap_or(&spec)
+        .trim()
+        .to_string();
+    if cfg!(target_os = "macos") {
+        return ("avfoundation", format!(":{device}"));
+    }
+    match device.as_str() {
+        "" | "default" | "pulse" | "pipewire" => ("pulse", "default".to_string()),
+        _ => ("alsa", device),
+    }
+}
+
 fn endpoint_wave_guid_marker(endpoint_id: &str) -> Option<String> {
     let trimmed = endpoint_id.trim();
     let start = trimmed.rfind('{')?;
@@ -147,11 +177,50 @@ fn endpoint_wave_guid_marker(endpoint_id: &str) -> Option<String> {
     if end == 0 {
         return None;
     }
-    let guid = tail[..end].trim();
-    if guid.is_empty() {
+    wave_marker_from_guid(&tail[..end])
+}
+
+fn wave_marker_from_guid(guid: &str) -> Option<String> {
+    let inner = guid
+        .trim()
+        .trim_start_matches('{')
+        .trim_end_matches('}')
+        .trim();
+    if inner.is_empty() {
         return None;
     }
-    Some(format!("wave_{{{}}}", guid.to_ascii_uppercase()))
+    Some(format!("wave_{{{}}}", inner.to_ascii_uppercase()))
+}
+
+// The `wave_{GUID}` part of a dshow moniker such as
+// `@device_cm_{33D9A762-...}\wave_{52B28A7E-...}`.
+fn dshow_moniker_wave_marker(moniker: &str) -> Option<String> {
+    let lower = moniker.to_ascii_lowercase();
+    let start = lower.rfind("wave_{")? + "wave_".len();
+    let tail = &moniker[start..];
+    let end = tail.find('}')?;
+    wave_marker_from_guid(&tail[..=end])
+}
+
+// Maps a dshow device back to its WASAPI endpoint by endpoint GUID, so two
+// microphones with the same friendly name are never confused.
+fn topology_for_dshow_device<'a>(
+    device: &DshowDevice,
+    topologies: &'a [EndpointTopology],
+) -> Option<&'a EndpointTopology> {
+    let Some(moniker) = device.alternative_name.as_deref() else {
+        // cpal devices carry no moniker and their name is the endpoint id.
+        return topologies
+            .iter()
+            .find(|t| t.endpoint_guid.is_none() && t.endpoint_id == device.name);
+    };
+    let marker = dshow_moniker_wave_marker(moniker)?;
+    topologies.iter().find(|t| {
+        t.endpoint_guid
+            .as_deref()
+            .and_then(wave_marker_from_guid)
+            .is_some_and(|m| m == marker)
+    })
 }
 
 fn push_resolution_log(
@@ -210,6 +279,15 @@ fn parse_dshow_audio_devices(stderr: &str) -> Vec<DshowDevice> {
 }
 
 fn list_dshow_audio_devices(ffmpeg: &Path) -> Result<Vec<DshowDevice>, String> {
+    if !cfg!(windows) {
+        return Ok(audio_devices_windows::list_active_capture_endpoints()?
+            .into_iter()
+            .map(|e| DshowDevice {
+                name: e.friendly_name,
+                alternative_name: None,
+            })
+            .collect());
+    }
     let output = std::process::Command::new(ffmpeg)
         .args([
             "-hide_banner",
@@ -269,6 +347,7 @@ fn score_audio_device_name(name: &str) -> i32 {
 
 fn attempt_auto_select(
     devices: &[DshowDevice],
+    topologies: &[EndpointTopology],
     strategy_used: InputStrategy,
 ) -> Result<ResolvedRecordInput, String> {
     let mut candidates: Vec<AutoCandidate> = devices
@@ -280,10 +359,15 @@ fn attempt_auto_select(
                 .as_deref()
                 .unwrap_or(d.name.as_str())
                 .to_string();
+            let endpoint = topology_for_dshow_device(d, topologies);
+            let score_name = endpoint
+                .map(|t| t.friendly_name.as_str())
+                .unwrap_or(&target);
             AutoCandidate {
                 spec: format!("audio={target}"),
                 display_name: d.name.clone(),
-                score: score_audio_device_name(&target),
+                endpoint,
+                score: score_audio_device_name(score_name),
                 order: idx,
             }
         })
@@ -297,24 +381,54 @@ fn attempt_auto_select(
     Ok(ResolvedRecordInput {
         spec: normalize_record_input_spec(cand.spec.as_str()),
         strategy_used: strategy_used.as_str().to_string(),
-        endpoint_id: None,
-        friendly_name: Some(cand.display_name),
-        resolved_by: "auto_select_ranked".to_string(),
+        endpoint_id: cand.endpoint.map(|t| t.endpoint_id.clone()),
+        friendly_name: Some(
+            cand.endpoint
+                .map(|t| t.friendly_name.clone())
+                .unwrap_or(cand.display_name),
+        ),
+        resolved_by: if cand.endpoint.is_some() {
+            "auto_select_ranked_topology".to_string()
+        } else {
+            "auto_select_ranked".to_string()
+        },
         resolution_log: Vec::new(),
+        device_override: None,
+        capture_format: None,
+        quality_warning: None,
     })
 }
 
+// The endpoint GUID property from the device topology is authoritative; the
+// GUID embedded in the endpoint id string is only used when it is unavailable.
+// Without dshow monikers the device name is the spec.
 fn endpoint_to_dshow_spec(endpoint: &AudioEndpointInfo) -> Result<(String, String), String> {
-    let marker = endpoint_wave_guid_marker(endpoint.endpoint_id.as_str()).ok_or_else(|| {
-        format!(
-            "E_RECORD_INPUT_MAP_FAILED: endpoint id has no wave GUID, friendly name=\"{}\"",
-            endpoint.friendly_name
-        )
-    })?;
+    if !cfg!(windows) {
+        let spec = format!("audio={}", endpoint.friendly_name);
+        return Ok((spec, "endpoint_name".to_string()));
+    }
+    let topology_marker =
+        audio_devices_windows::get_capture_endpoint_topology(endpoint.endpoint_id.as_str())
+            .ok()
+            .and_then(|t| t.endpoint_guid)
+            .and_then(|guid| wave_marker_from_guid(&guid));
+    let (marker, resolved_by) = match topology_marker {
+        Some(marker) => (marker, "endpoint_topology"),
+        None => {
+            let marker =
+                endpoint_wave_guid_marker(endpoint.endpoint_id.as_str()).ok_or_else(|| {
+                    format!(
+                        "E_RECORD_INPUT_MAP_FAILED: endpoint id has no wave GUID, friendly name=\"{}\"",
+                        endpoint.friendly_name
+                    )
+                })?;
+            (marker, "endpoint_guid")
+        }
+    };
     let spec = normalize_record_input_spec(
         format!("audio=@device_cm_{{33D9A762-90C8-11D0-BD43-00A0C911CE86}}\\{marker}").as_str(),
     );
-    Ok((spec, "endpoint_guid".to_string()))
+    Ok((spec, resolved_by.to_string()))
 }
 
 fn attempt_follow_default(
@@ -330,6 +444,9 @@ fn attempt_follow_default(
         friendly_name: Some(endpoint.friendly_name),
         resolved_by,
         resolution_log: Vec::new(),
+        device_override: None,
+        capture_format: None,
+        quality_warning: None,
     })
 }
 
@@ -346,6 +463,9 @@ fn attempt_fixed(
         friendly_name: Some(endpoint.friendly_name),
         resolved_by,
         resolution_log: Vec::new(),
+        device_override: None,
+        capture_format: None,
+        quality_warning: None,
     })
 }
 
@@ -373,6 +493,9 @@ fn attempt_last_working(settings: &Settings) -> Result<ResolvedRecordInput, Stri
         friendly_name: settings.record_last_working_friendly_name.clone(),
         resolved_by: "last_working_spec".to_string(),
         resolution_log: Vec::new(),
+        device_override: None,
+        capture_format: None,
+        quality_warning: None,
     })
 }
 
@@ -388,32 +511,57 @@ fn save_last_working_cache(
     settings: &mut Settings,
     resolved: &ResolvedRecordInput,
 ) -> Result<(), String> {
-    let mut changed = false;
     let next_endpoint_id = resolved.endpoint_id.clone();
     let next_friendly_name = resolved.friendly_name.clone();
     let next_spec = Some(resolved.spec.clone());
-    if settings.record_last_working_endpoint_id != next_endpoint_id {
-        settings.record_last_working_endpoint_id = next_endpoint_id;
-        changed = true;
-    }
-    if settings.record_last_working_friendly_name != next_friendly_name {
-        settings.record_last_working_friendly_name = next_friendly_name;
-        changed = true;
-    }
-    if settings.record_last_working_dshow_spec != next_spec {
-        settings.record_last_working_dshow_spec = next_spec;
-        changed = true;
-    }
     let next_ts = Some(now_epoch_ms());
-    if settings.record_last_working_ts_ms != next_ts {
-        settings.record_last_working_ts_ms = next_ts;
-        changed = true;
-    }
-    if !changed {
-        return Ok(());
-    }
-    settings::save_settings(data_dir, settings)
-        .map_err(|e| format!("E_RECORD_INPUT_CACHE_SAVE_FAILED: {e}"))
+    // Applied to the freshly loaded file rather than `settings`, which may be stale.
+    let saved = settings::modify_settings(data_dir, |s| {
+        let mut changed = false;
+        if s.record_last_working_endpoint_id != next_endpoint_id {
+            s.record_last_working_endpoint_id = next_endpoint_id;
+            changed = true;
+        }
+        if s.record_last_working_friendly_name != next_friendly_name {
+            s.record_last_working_friendly_name = next_friendly_name;
+            changed = true;
+        }
+        if s.record_last_working_dshow_spec != next_spec {
+            s.record_last_working_dshow_spec = next_spec;
+            changed = true;
+        }
+        if s.record_last_working_ts_ms != next_ts {
+            s.record_last_working_ts_ms = next_ts;
+            changed = true;
+        }
+        Ok(changed)
+    })
+    .map_err(|e| format!("E_RECORD_INPUT_CACHE_SAVE_FAILED: {e}"))?;
+    *settings = saved;
+    Ok(())
+}
+
+// Drops the persisted last-working cache when it points at an endpoint that was
+// just removed or disabled, so the next resolution does not probe a dead spec.
+pub fn forget_last_working_endpoint(data_dir: &Path, endpoint_id: &str) -> Result<bool, String> {
+    let mut forgotten = false;
+    settings::modify_settings(data_dir, |s| {
+        let matches = s
+            .record_last_working_endpoint_id
+            .as_deref()
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case(endpoint_id.trim()));
+        if !matches {
+            return Ok(false);
+        }
+        s.record_last_working_endpoint_id = None;
+        s.record_last_working_friendly_name = None;
+        s.record_last_working_dshow_spec = None;
+        s.record_last_working_ts_ms = None;
+        forgotten = true;
+        Ok(true)
+    })
+    .map_err(|e| format!("E_RECORD_INPUT_CACHE_SAVE_FAILED: {e}"))?;
+    Ok(forgotten)
 }
 
 fn build_resolve_failed(
@@ -455,6 +603,241 @@ fn load_dshow_devices_for_auto(
     }
 }
 
+// Preferred endpoints that are currently active, in the user's order. Endpoint
+// ids are compared case-insensitively because Windows does not normalize them.
+fn ordered_active_preferences(preference: &[String], active_ids: &[String]) -> Vec<String> {
+    preference
+        .iter()
+        .filter_map(|id| {
+            active_ids
+                .iter()
+                .find(|active| active.eq_ignore_ascii_case(id))
+                .cloned()
+        })
+        .collect()
+}
+
+fn attempt_preferred(
+    settings: &Settings,
+    strategy: InputStrategy,
+    decision_logs: &mut Vec<ResolveLogEntry>,
+    errors: &mut Vec<String>,
+) -> Option<ResolvedRecordInput> {
+    let preference = settings::resolve_record_device_preference(settings);
+    if preference.is_empty() {
+        return None;
+    }
+    let active_ids: Vec<String> = match audio_devices_windows::list_active_capture_endpoints() {
+        Ok(v) => v.into_iter().map(|e| e.endpoint_id).collect(),
+        Err(e) => {
+            push_resolution_log(
+                decision_logs,
+                "preference.list_endpoints",
+                "fail",
+                e.as_str(),
+            );
+            errors.push(e);
+            return None;
+        }
+    };
+    let ordered = ordered_active_preferences(&preference, &active_ids);
+    push_resolution_log(
+        decision_logs,
+        "preference.list_endpoints",
+        "ok",
+        format!("preferred={}, active={}", preference.len(), ordered.len()),
+    );
+    for (rank, id) in ordered.iter().enumerate() {
+        match attempt_fixed(id, strategy) {
+            Ok(mut v) => {
+                v.resolved_by = format!("preference_rank_{}", rank + 1);
+                push_resolution_log(
+                    decision_logs,
+                    "preference.try",
+                    "selected",
+                    format!("endpoint_id={id}, spec={}", v.spec),
+                );
+                return Some(v);
+            }
+            Err(e) => {
+                push_resolution_log(decision_logs, "preference.try", "fail", e.as_str());
+                errors.push(e);
+            }
+        }
+    }
+    None
+}
+
+// Other active endpoints to try when the resolved one is low quality: the
+// user's preferences first, then everything else in enumeration order.
+fn quality_fallback_candidates(
+    preference: &[String],
+    active_ids: &[String],
+    current: &str,
+) -> Vec<String> {
+    let mut out = ordered_active_preferences(preference, active_ids);
+    for id in active_ids {
+        if !out.iter().any(|v| v.eq_ignore_ascii_case(id)) {
+            out.push(id.clone());
+        }
+    }
+    out.retain(|id| !id.eq_ignore_ascii_case(current));
+    out
+}
+
+// Flags endpoints whose mix format is narrowband (typically a Bluetooth headset
+// in the hands-free profile). When the user opted in, another active endpoint
+// with a usable format is picked instead; a fixed device is only ever warned
+// about.
+fn check_capture_quality(
+    settings: &Settings,
+    strategy: InputStrategy,
+    mut resolved: ResolvedRecordInput,
+    decision_logs: &mut Vec<ResolveLogEntry>,
+) -> ResolvedRecordInput {
+    let Some(endpoint_id) = resolved.endpoint_id.clone() else {
+        push_resolution_log(decision_logs, "quality.check", "skipped", "no endpoint id");
+        return resolved;
+    };
+    let format = match audio_devices_windows::get_capture_endpoint_format(&endpoint_id) {
+        Ok(v) => v,
+        Err(e) => {
+            push_resolution_log(decision_logs, "quality.check", "fail", e.as_str());
+            return resolved;
+        }
+    };
+    resolved.capture_format = Some(format);
+    if !audio_devices_windows::is_low_quality_capture_format(&format) {
+        push_resolution_log(
+            decision_logs,
+            "quality.check",
+            "ok",
+            format!("sample_rate_hz={}", format.sample_rate_hz),
+        );
+        return resolved;
+    }
+    let warning = format!(
+        "E_RECORD_LOW_QUALITY_PROFILE: {} captures at {} Hz (Bluetooth hands-free profile?); recognition accuracy will suffer",
+        resolved.friendly_name.as_deref().unwrap_or(endpoint_id.as_str()),
+        format.sample_rate_hz
+    );
+    push_resolution_log(decision_logs, "quality.check", "low", warning.as_str());
+    resolved.quality_warning = Some(warning);
+
+    if !settings::resolve_record_avoid_low_quality_profile(settings)
+        || matches!(strategy, InputStrategy::FixedDevice)
+    {
+        return resolved;
+    }
+    let active_ids: Vec<String> = match audio_devices_windows::list_active_capture_endpoints() {
+        Ok(v) => v.into_iter().map(|e| e.endpoint_id).collect(),
+        Err(e) => {
+            push_resolution_log(decision_logs, "quality.fallback", "fail", e.as_str());
+            return resolved;
+        }
+    };
+    let preference = settings::resolve_record_device_preference(settings);
+    for id in quality_fallback_candidates(&preference, &active_ids, &endpoint_id) {
+        let candidate_format = match audio_devices_windows::get_capture_endpoint_format(&id) {
+            Ok(v) if !audio_devices_windows::is_low_quality_capture_format(&v) => v,
+            Ok(v) => {
+                push_resolution_log(
+                    decision_logs,
+                    "quality.fallback",
+                    "skip",
+                    format!("endpoint_id={id}, sample_rate_hz={}", v.sample_rate_hz),
+                );
+                continue;
+            }
+            Err(e) => {
+                push_resolution_log(decision_logs, "quality.fallback", "skip", e.as_str());
+                continue;
+            }
+        };
+        match attempt_fixed(&id, strategy) {
+            Ok(mut v) => {
+                v.resolved_by = "quality_fallback".to_string();
+                v.capture_format = Some(candidate_format);
+                v.quality_warning = resolved.quality_warning.take().map(|w| {
+                    format!(
+                        "{w}; switched to {}",
+                        v.friendly_name.as_deref().unwrap_or(id.as_str())
+                    )
+                });
+                push_resolution_log(
+                    decision_logs,
+                    "quality.fallback",
+                    "selected",
+                    format!("endpoint_id={id}, spec={}", v.spec),
+                );
+                return v;
+            }
+            Err(e) => {
+                push_resolution_log(decision_logs, "quality.fallback", "fail", e.as_str());
+            }
+        }
+    }
+    push_resolution_log(
+        decision_logs,
+        "quality.fallback",
+        "none",
+        "no better endpoint available",
+    );
+    resolved
+}
+
+// The user's ordered preference list is consulted before auto-select scoring.
+fn attempt_preferred_then_auto(
+    settings: &Settings,
+    ffmpeg: &Path,
+    strategy: InputStrategy,
+    decision_logs: &mut Vec<ResolveLogEntry>,
+    errors: &mut Vec<String>,
+) -> Option<ResolvedRecordInput> {
+    if let Some(v) = attempt_preferred(settings, strategy, decision_logs, errors) {
+        return Some(v);
+    }
+    push_resolution_log(
+        decision_logs,
+        "auto.try",
+        "start",
+        "attempt auto_select candidates",
+    );
+    let topologies = match audio_devices_windows::list_active_capture_topologies() {
+        Ok(v) => {
+            push_resolution_log(
+                decision_logs,
+                "topology.list",
+                "ok",
+                format!("endpoints={}", v.len()),
+            );
+            v
+        }
+        Err(e) => {
+            push_resolution_log(decision_logs, "topology.list", "fail", e.as_str());
+            Vec::new()
+        }
+    };
+    match load_dshow_devices_for_auto(ffmpeg, decision_logs)
+        .and_then(|devices| attempt_auto_select(&devices, &topologies, strategy))
+    {
+        Ok(v) => {
+            push_resolution_log(
+                decision_logs,
+                "auto.try",
+                "selected",
+                format!("resolved_by={}, spec={}", v.resolved_by, v.spec),
+            );
+            Some(v)
+        }
+        Err(e) => {
+            push_resolution_log(decision_logs, "auto.try", "fail", e.as_str());
+            errors.push(e);
+            None
+        }
+    }
+}
+
 pub fn resolve_record_input_for_recording(
     data_dir: &Path,
     ffmpeg_cmd: &str,
@@ -496,7 +879,7 @@ pub fn resolve_record_input_for_recording(
 
     let mut errors = Vec::new();
 
-    let mut resolved = match strategy {
+    let resolved = match strategy {
         InputStrategy::FixedDevice => {
             let mut resolved: Option<ResolvedRecordInput> = None;
             if let Some(id) = settings
@@ -584,29 +967,13 @@ pub fn resolve_record_input_for_recording(
                 }
             }
             if resolved.is_none() {
-                push_resolution_log(
+                resolved = attempt_preferred_then_auto(
+                    &settings,
+                    ffmpeg,
+                    strategy,
                     &mut decision_logs,
-                    "auto.try",
-                    "start",
-                    "attempt auto_select candidates",
+                    &mut errors,
                 );
-                match load_dshow_devices_for_auto(ffmpeg, &mut decision_logs)
-                    .and_then(|devices| attempt_auto_select(&devices, strategy))
-                {
-                    Ok(v) => {
-                        push_resolution_log(
-                            &mut decision_logs,
-                            "auto.try",
-                            "selected",
-                            format!("resolved_by={}, spec={}", v.resolved_by, v.spec),
-                        );
-                        resolved = Some(v);
-                    }
-                    Err(e) => {
-                        push_resolution_log(&mut decision_logs, "auto.try", "fail", e.as_str());
-                        errors.push(e);
-                    }
-                }
             }
             resolved.ok_or_else(|| build_resolve_failed(strategy, &errors, &decision_logs))?
         }
@@ -674,53 +1041,28 @@ pub fn resolve_record_input_for_recording(
                 }
             }
             if resolved.is_none() {
-                push_resolution_log(
+                resolved = attempt_preferred_then_auto(
+                    &settings,
+                    ffmpeg,
+                    strategy,
                     &mut decision_logs,
-                    "auto.try",
-                    "start",
-                    "attempt auto_select candidates",
+                    &mut errors,
                 );
-                match load_dshow_devices_for_auto(ffmpeg, &mut decision_logs)
-                    .and_then(|devices| attempt_auto_select(&devices, strategy))
-                {
-                    Ok(v) => {
-                        push_resolution_log(
-                            &mut decision_logs,
-                            "auto.try",
-                            "selected",
-                            format!("resolved_by={}, spec={}", v.resolved_by, v.spec),
-                        );
-                        resolved = Some(v);
-                    }
-                    Err(e) => {
-                        push_resolution_log(&mut decision_logs, "auto.try", "fail", e.as_str());
-                        errors.push(e);
-                    }
-                }
             }
             resolved.ok_or_else(|| build_resolve_failed(strategy, &errors, &decision_logs))?
         }
-        InputStrategy::AutoSelect => {
-            push_resolution_log(
-                &mut decision_logs,
-                "auto.try",
-                "start",
-                "attempt auto_select candidates",
-            );
-            let dshow_devices =
-                load_dshow_devices_for_auto(ffmpeg, &mut decision_logs).map_err(|e| {
-                    push_resolution_log(&mut decision_logs, "auto.try", "fail", e.as_str());
-                    errors.push(e);
-                    build_resolve_failed(strategy, &errors, &decision_logs)
-                })?;
-            attempt_auto_select(&dshow_devices, strategy).map_err(|e| {
-                push_resolution_log(&mut decision_logs, "auto.try", "fail", e.as_str());
-                errors.push(e);
-                build_resolve_failed(strategy, &errors, &decision_logs)
-            })?
-        }
+        InputStrategy::AutoSelect => attempt_preferred_then_auto(
+            &settings,
+            ffmpeg,
+            strategy,
+            &mut decision_logs,
+            &mut errors,
+        )
+        .ok_or_else(|| build_resolve_failed(strategy, &errors, &decision_logs))?,
     };
 
+    let mut resolved = check_capture_quality(&settings, strategy, resolved, &mut decision_logs);
+
     push_resolution_log(
         &mut decision_logs,
         "resolve.final",
@@ -731,6 +1073,10 @@ pub fn resolve_record_input_for_recording(
         ),
     );
     resolved.resolution_log = decision_logs;
+    resolved.device_override = resolved
+        .endpoint_id
+        .as_deref()
+        .and_then(|id| settings::resolve_record_device_override(&settings, id));
 
     let _ = save_last_working_cache(data_dir, &mut settings, &resolved);
     Ok(resolved)
@@ -793,8 +1139,9 @@ pub fn default_role() -> &'static str {
 #[cfg(test)]
 mod tests {
     use super::{
-        endpoint_wave_guid_marker, normalize_default_role_for_settings,
-        normalize_strategy_for_settings,
+        dshow_moniker_wave_marker, endpoint_wave_guid_marker, normalize_default_role_for_settings,
+        normalize_strategy_for_settings, ordered_active_preferences, quality_fallback_candidates,
+        topology_for_dshow_device, DshowDevice, EndpointTopology,
     };
 
     #[test]
@@ -832,4 +1179,71 @@ mod tests {
         assert_eq!(endpoint_wave_guid_marker(""), None);
         assert_eq!(endpoint_wave_guid_marker("invalid"), None);
     }
+
+    #[test]
+    fn preferences_keep_user_order_and_skip_inactive_endpoints() {
+        let preference = vec![
+            "{0.0.1}.{USB}".to_string(),
+            "{0.0.1}.{bt}".to_string(),
+            "{0.0.1}.{array}".to_string(),
+        ];
+        let active = vec!["{0.0.1}.{array}".to_string(), "{0.0.1}.{usb}".to_string()];
+
+        assert_eq!(
+            ordered_active_preferences(&preference, &active),
+            vec!["{0.0.1}.{usb}".to_string(), "{0.0.1}.{array}".to_string()]
+        );
+        assert!(ordered_active_preferences(&[], &active).is_empty());
+    }
+
+    #[test]
+    fn quality_fallback_prefers_user_order_and_skips_current_endpoint() {
+        let preference = vec!["{0.0.1}.{usb}".to_string()];
+        let active = vec![
+            "{0.0.1}.{bt}".to_string(),
+            "{0.0.1}.{array}".to_string(),
+            "{0.0.1}.{USB}".to_string(),
+        ];
+
+        assert_eq!(
+            quality_fallback_candidates(&preference, &active, "{0.0.1}.{BT}"),
+            vec!["{0.0.1}.{USB}".to_string(), "{0.0.1}.{array}".to_string()]
+        );
+    }
+
+    #[test]
+    fn dshow_monikers_map_to_endpoints_by_guid_not_name() {
+        let topology = |id: &str, guid: &str| EndpointTopology {
+            endpoint_id: id.to_string(),
+            friendly_name: "Microphone (USB Audio Device)".to_string(),
+            endpoint_guid: Some(guid.to_string()),
+            container_id: None,
+            adapter_device_id: None,
+        };
+        let topologies = vec![
+            topology("{0.0.1}.{first}", "{11111111-0000-0000-0000-00000000000a}"),
+            topology("{0.0.1}.{second}", "{22222222-0000-0000-0000-00000000000b}"),
+        ];
+        let device = DshowDevice {
+            name: "Microphone (USB Audio Device)".to_string(),
+            alternative_name: Some(
+                "@device_cm_{33D9A762-90C8-11D0-BD43-00A0C911CE86}\\wave_{22222222-0000-0000-0000-00000000000B}"
+                    .to_string(),
+            ),
+        };
+
+        assert_eq!(
+            dshow_moniker_wave_marker(device.alternative_name.as_deref().unwrap()),
+            Some("wave_{22222222-0000-0000-0000-00000000000B}".to_string())
+        );
+        assert_eq!(
+            topology_for_dshow_device(&device, &topologies).map(|t| t.endpoint_id.as_str()),
+            Some("{0.0.1}.{second}")
+        );
+        let unnamed = DshowDevice {
+            name: device.name.clone(),
+            alternative_name: None,
+        };
+        assert!(topology_for_dshow_device(&unnamed, &topologies).is_none());
+    }
 }
@@ -116,11 +116,28 @@ impl RecordInputCacheState {
             "record_input_resolved_by": cached.resolved.resolved_by,
             "record_input_endpoint_id": cached.resolved.endpoint_id,
             "record_input_friendly_name": cached.resolved.friendly_name,
+            "record_input_quality_warning": cached.resolved.quality_warning,
             "record_input_resolution_log": cached.resolved.resolution_log,
         })));
         Ok(cached)
     }
 
+    // Clears the cached resolution if it targets `endpoint_id`; recordings then
+    // wait for the refresh instead of starting on a device that is gone.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub fn invalidate_endpoint(&self, endpoint_id: &str) -> bool {
+        let mut g = self.inner.lock().unwrap();
+        let matches = g
+            .last_ok
+            .as_ref()
+            .and_then(|v| v.resolved.endpoint_id.as_deref())
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case(endpoint_id.trim()));
+        if matches {
+            g.last_ok = None;
+        }
+        matches
+    }
+
     #[cfg_attr(not(windows), allow(dead_code))]
     pub fn request_refresh(&self, data_dir: PathBuf, reason: impl Into<String>) {
         let first_reason = reason.into();
@@ -196,3 +213,42 @@ fn now_epoch_ms() -> i64 {
         Err(_) => 0,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{CachedRecordInput, RecordInputCacheState};
+    use crate::record_input::ResolvedRecordInput;
+
+    fn cached(endpoint_id: Option<&str>) -> CachedRecordInput {
+        CachedRecordInput {
+            resolved: ResolvedRecordInput {
+                spec: "audio=@device_cm_{33D9A762-90C8-11D0-BD43-00A0C911CE86}\\wave_{A}"
+                    .to_string(),
+                strategy_used: "follow_default".to_string(),
+                endpoint_id: endpoint_id.map(str::to_string),
+                friendly_name: Some("Mic".to_string()),
+                resolved_by: "endpoint_topology".to_string(),
+                resolution_log: Vec::new(),
+                device_override: None,
+                capture_format: None,
+                quality_warning: None,
+            },
+            refreshed_at_ms: 1,
+            reason: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn invalidate_endpoint_only_clears_matching_resolution() {
+        let cache = RecordInputCacheState::new();
+        cache.inner.lock().unwrap().last_ok = Some(cached(Some("{0.0.1}.{abc}")));
+
+        assert!(!cache.invalidate_endpoint("{0.0.1}.{other}"));
+        assert!(cache.get_last_ok().is_some());
+        assert!(cache.invalidate_endpoint("{0.0.1}.{ABC}"));
+        assert!(cache.get_last_ok().is_none());
+
+        cache.inner.lock().unwrap().last_ok = Some(cached(None));
+        assert!(!cache.invalidate_endpoint("{0.0.1}.{abc}"));
+    }
+}
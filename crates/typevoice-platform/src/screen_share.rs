@@ -0,0 +1,122 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use typevoice_storage::{formatting_profiles, settings};
+
+// Windows offers no public way to see another app's graphics capture session,
+// so sharing is inferred from helper processes that conferencing apps only run
+// while a share is live. Users can add their own names in settings.
+const SHARE_HOST_PROCESSES: &[(&str, &str)] = &[
+    // Zoom's screen share host.
+    ("cpthost.exe", "Zoom"),
+];
+
+// A process snapshot per overlay update would be wasteful; shares start and
+// stop on a scale of seconds.
+const DETECT_CACHE_TTL: Duration = Duration::from_secs(2);
+
+struct Guard {
+    enabled: bool,
+    extra_processes: Vec<String>,
+    cached: Option<(Instant, Option<String>)>,
+}
+
+static GUARD: Mutex<Guard> = Mutex::new(Guard {
+    enabled: false,
+    extra_processes: Vec::new(),
+    cached: None,
+});
+
+pub fn apply_from_settings(s: &settings::Settings) {
+    let mut g = GUARD.lock().unwrap();
+    g.enabled = settings::resolve_screen_share_guard_enabled(s);
+    g.extra_processes = settings::resolve_screen_share_extra_processes(s);
+    g.cached = None;
+}
+
+// Name of the app that is sharing the screen, when the guard is on and one is.
+pub fn active() -> Option<String> {
+    let mut g = GUARD.lock().unwrap();
+    if !g.enabled {
+        return None;
+    }
+    if let Some((at, found)) = g.cached.as_ref() {
+        if at.elapsed() < DETECT_CACHE_TTL {
+            return found.clone();
+        }
+    }
+    let found = sharing_app(running_process_names(), &g.extra_processes);
+    g.cached = Some((Instant::now(), found.clone()));
+    found
+}
+
+pub fn sharing_app(
+    process_names: impl IntoIterator<Item = String>,
+    extra_processes: &[String],
+) -> Option<String> {
+    for raw in process_names {
+        let name = formatting_profiles::process_file_name(&raw);
+        if let Some((_, app)) = SHARE_HOST_PROCESSES.iter().find(|(p, _)| *p == name) {
+            return Some((*app).to_string());
+        }
+        if extra_processes.contains(&name) {
+            return Some(name);
+        }
+    }
+    None
+}
+
+#[cfg(windows)]
+fn running_process_names() -> Vec<String> {
+    use windows_sys::Win32::Foundation::{CloseHandle, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+        TH32CS_SNAPPROCESS,
+    };
+
+    let mut out = Vec::new();
+    unsafe {
+        let snap = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+        if snap == INVALID_HANDLE_VALUE {
+            return out;
+        }
+        let mut entry: PROCESSENTRY32W = std::mem::zeroed();
+        entry.dwSize = std::mem::size_of::<PROCESSENTRY32W>() as u32;
+        let mut ok = Process32FirstW(snap, &mut entry);
+        while ok != 0 {
+            let len = entry
+                .szExeFile
+                .iter()
+                .position(|c| *c == 0)
+                .unwrap_or(entry.szExeFile.len());
+            out.push(String::from_utf16_lossy(&entry.szExeFile[..len]));
+            ok = Process32NextW(snap, &mut entry);
+        }
+        let _ = CloseHandle(snap);
+    }
+    out
+}
+
+#[cfg(not(windows))]
+fn running_process_names() -> Vec<String> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_known_and_configured_share_hosts() {
+        let procs = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            sharing_app(procs(&["explorer.exe", r"C:\Zoom\bin\CptHost.exe"]), &[]),
+            Some("Zoom".to_string())
+        );
+        assert_eq!(sharing_app(procs(&["explorer.exe", "zoom.exe"]), &[]), None);
+        assert_eq!(
+            sharing_app(procs(&["Presenter.EXE"]), &["presenter.exe".to_string()]),
+            Some("presenter.exe".to_string())
+        );
+    }
+}
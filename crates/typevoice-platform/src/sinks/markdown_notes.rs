@@ -0,0 +1,229 @@
+use std::{
+    fs,
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use super::CompletedDictation;
+use crate::obs;
+use crate::ports::{PortError, PortResult};
+use crate::settings::{self, MarkdownNotesResolved, Settings};
+
+// Serializes appends so two dictations finishing together cannot interleave or
+// both write the front matter of a new note.
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+// Appends the dictation to today's note when the sink is enabled. Returns the
+// note path, or None when the sink is off.
+pub fn export(
+    data_dir: &Path,
+    s: &Settings,
+    entry: &CompletedDictation,
+) -> PortResult<Option<PathBuf>> {
+    let Some(cfg) = settings::resolve_markdown_notes(s, entry.template_id.as_deref()) else {
+        return Ok(None);
+    };
+    match append_entry(&cfg, entry) {
+        Ok(path) => {
+            obs::event(
+                data_dir,
+                Some(&entry.task_id),
+                "Sink",
+                "SINK.markdown_notes",
+                "ok",
+                Some(serde_json::json!({
+                    "note": path.file_name().map(|v| v.to_string_lossy().to_string()),
+                    "template_id": entry.template_id,
+                    "chars": entry.text.chars().count(),
+                })),
+            );
+            Ok(Some(path))
+        }
+        Err(e) => {
+            obs::event_err(
+                data_dir,
+                obs::ErrorEvent {
+                    task_id: Some(&entry.task_id),
+                    stage: "Sink",
+                    step_id: "SINK.markdown_notes",
+                    kind: "io",
+                    code: &e.code,
+                    ctx: Some(serde_json::json!({ "template_id": entry.template_id })),
+                },
+                &e.message,
+            );
+            Err(e)
+        }
+    }
+}
+
+// One note per local day, `<folder>/YYYY-MM-DD.md`. A new note starts with
+// front matter and a date heading; every entry is separated by a blank line.
+pub fn append_entry(
+    cfg: &MarkdownNotesResolved,
+    entry: &CompletedDictation,
+) -> PortResult<PathBuf> {
+    // The vault may live on a removable or synced drive; never create it.
+    if !cfg.folder.is_dir() {
+        return Err(PortError::new(
+            "E_SINK_MARKDOWN_FOLDER_MISSING",
+            format!("notes folder not found: {}", cfg.folder.display()),
+        ));
+    }
+    let date = entry.completed_at.format("%Y-%m-%d").to_string();
+    let path = cfg.folder.join(format!("{date}.md"));
+    let body = render_entry(&cfg.entry_template, entry);
+
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let written = match fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+    {
+        Ok(mut f) => f.write_all(format!("{}{body}", front_matter(&date)).as_bytes()),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => fs::OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut f| {
+                let sep = separator(&mut f)?;
+                f.write_all(format!("{sep}{body}").as_bytes())
+            }),
+        Err(e) => Err(e),
+    };
+    written.map_err(|e| {
+        PortError::new(
+            "E_SINK_MARKDOWN_WRITE",
+            format!("write {}: {e}", path.display()),
+        )
+    })?;
+    Ok(path)
+}
+
+fn front_matter(date: &str) -> String {
+    format!("---\ndate: {date}\nsource: typevoice\ntags:\n  - dictation\n---\n\n# {date}\n\n")
+}
+
+// Notes may be edited in the vault between dictations, so the separator is
+// derived from how the file currently ends.
+fn separator(f: &mut fs::File) -> io::Result<&'static str> {
+    let len = f.metadata()?.len();
+    if len == 0 {
+        return Ok("");
+    }
+    let n = len.min(2) as usize;
+    f.seek(SeekFrom::End(-(n as i64)))?;
+    let mut tail = [0u8; 2];
+    f.read_exact(&mut tail[..n])?;
+    let tail = &tail[..n];
+    Ok(if tail.ends_with(b"\n\n") {
+        ""
+    } else if tail.ends_with(b"\n") {
+        "\n"
+    } else {
+        "\n\n"
+    })
+}
+
+// Placeholders are substituted in one pass, so braces inside the dictated
+// text are never expanded. Unknown placeholders are kept as written.
+pub fn render_entry(template: &str, entry: &CompletedDictation) -> String {
+    let mut out = String::with_capacity(template.len() + entry.text.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            out.push_str(&rest[start..]);
+            rest = "";
+            break;
+        };
+        let value = match after[..end].trim() {
+            "date" => entry.completed_at.format("%Y-%m-%d").to_string(),
+            "time" => entry.completed_at.format("%H:%M").to_string(),
+            "text" => entry.text.trim().to_string(),
+            "template" => entry
+                .template_id
+                .clone()
+                .unwrap_or_else(|| "dictation".to_string()),
+            "task_id" => entry.task_id.clone(),
+            _ => rest[start..start + 2 + end + 2].to_string(),
+        };
+        out.push_str(&value);
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    if !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    fn entry(text: &str, template_id: Option<&str>, hour: u32) -> CompletedDictation {
+        CompletedDictation {
+            task_id: "t1".to_string(),
+            text: text.to_string(),
+            template_id: template_id.map(ToOwned::to_owned),
+            completed_at: Local
+                .with_ymd_and_hms(2026, 10, 17, hour, 5, 0)
+                .single()
+                .expect("local time"),
+        }
+    }
+
+    #[test]
+    fn daily_note_gets_front_matter_once_and_blank_line_between_entries() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let cfg = MarkdownNotesResolved {
+            folder: tmp.path().to_path_buf(),
+            entry_template: settings::DEFAULT_MARKDOWN_NOTES_ENTRY_TEMPLATE.to_string(),
+        };
+        let path = append_entry(&cfg, &entry("first note", None, 9)).expect("first");
+        assert_eq!(path, tmp.path().join("2026-10-17.md"));
+        // Simulate an edit in the vault that left no trailing newline.
+        let mut f = fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .expect("open");
+        f.write_all(b"edited").expect("edit");
+        append_entry(&cfg, &entry(" second ", None, 10)).expect("second");
+        let got = fs::read_to_string(&path).expect("read");
+        assert_eq!(
+            got,
+            "---\ndate: 2026-10-17\nsource: typevoice\ntags:\n  - dictation\n---\n\n\
+             # 2026-10-17\n\n## 09:05\n\nfirst note\nedited\n\n## 10:05\n\nsecond\n"
+        );
+    }
+
+    #[test]
+    fn entry_template_is_rendered_in_one_pass() {
+        let e = entry("use {{time}} literally", Some("meeting"), 9);
+        assert_eq!(
+            render_entry("- {{ time }} [{{template}}] {{text}} {{unknown}} {{", &e),
+            "- 09:05 [meeting] use {{time}} literally {{unknown}} {{\n"
+        );
+        assert_eq!(
+            render_entry("{{date}} {{template}}", &entry("x", None, 9)),
+            "2026-10-17 dictation\n"
+        );
+    }
+
+    #[test]
+    fn missing_folder_is_reported_not_created() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let folder = tmp.path().join("vault");
+        let cfg = MarkdownNotesResolved {
+            folder: folder.clone(),
+            entry_template: "{{text}}".to_string(),
+        };
+        let err = append_entry(&cfg, &entry("x", None, 9)).expect_err("missing");
+        assert_eq!(err.code, "E_SINK_MARKDOWN_FOLDER_MISSING");
+        assert!(!folder.exists());
+    }
+}
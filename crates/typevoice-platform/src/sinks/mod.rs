@@ -0,0 +1,27 @@
+pub mod markdown_notes;
+
+use chrono::{DateTime, Local};
+
+// A dictation that reached the user (inserted or reported as inserted) and was
+// handed to the history writer; sinks export it after that point.
+#[derive(Debug, Clone)]
+pub struct CompletedDictation {
+    pub task_id: String,
+    pub text: String,
+    pub template_id: Option<String>,
+    pub completed_at: DateTime<Local>,
+}
+
+impl CompletedDictation {
+    pub fn now(task_id: &str, text: &str, template_id: Option<&str>) -> Self {
+        Self {
+            task_id: task_id.to_string(),
+            text: text.to_string(),
+            template_id: template_id
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(ToOwned::to_owned),
+            completed_at: Local::now(),
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use std::{
     path::{Path, PathBuf},
     process::Command,
+    sync::{Mutex, RwLock},
+    time::SystemTime,
 };
 
 use anyhow::{anyhow, Context, Result};
@@ -40,6 +42,24 @@ const LINUX_X86_64_SPEC: PlatformSpec = PlatformSpec {
     ffprobe_sha256: "4f231a1960d83e403d08f7971e271707bec278a9ae18e21b8b5b03186668450d",
 };
 
+// Binaries picked by the last check. Kept here rather than in the process
+// environment, which must not be written once other threads are running.
+#[derive(Debug, Clone)]
+struct ResolvedToolchain {
+    dir: PathBuf,
+    ffmpeg: PathBuf,
+    ffprobe: PathBuf,
+}
+
+static RESOLVED: RwLock<Option<ResolvedToolchain>> = RwLock::new(None);
+
+// Files that already passed the checksum and version check, keyed by path,
+// size and mtime, so rechecks of an unchanged toolchain skip the hashing.
+static VERIFIED: Mutex<Vec<(PathBuf, u64, Option<SystemTime>)>> = Mutex::new(Vec::new());
+
+// Last failure written to the trace; a recheck that fails the same way stays quiet.
+static LAST_FAILURE: Mutex<Option<String>> = Mutex::new(None);
+
 #[derive(Debug, Clone, Serialize)]
 pub struct ToolchainStatus {
     pub ready: bool,
@@ -63,6 +83,19 @@ impl ToolchainStatus {
     }
 }
 
+// Failures that another check cannot fix without a new build or a manual
+// refresh after replacing the binaries.
+pub fn is_permanent_failure(code: Option<&str>) -> bool {
+    matches!(
+        code,
+        Some(
+            "E_TOOLCHAIN_PLATFORM_UNSUPPORTED"
+                | "E_TOOLCHAIN_CHECKSUM_MISMATCH"
+                | "E_TOOLCHAIN_VERSION_MISMATCH"
+        )
+    )
+}
+
 pub fn current_platform_id() -> Result<&'static str> {
     Ok(current_spec()?.id)
 }
@@ -164,6 +197,22 @@ pub fn resolve_tool_binary(env_key: &str, file_name: &str) -> Result<PathBuf> {
         }
     }
 
+    let resolved = RESOLVED.read().unwrap().clone();
+    if let Some(r) = resolved {
+        let p = match env_key {
+            "TYPEVOICE_FFMPEG" => r.ffmpeg,
+            "TYPEVOICE_FFPROBE" => r.ffprobe,
+            _ => tool_binary_from_dir(&r.dir, file_name),
+        };
+        if !p.exists() {
+            return Err(anyhow!(
+                "E_TOOLCHAIN_NOT_READY: missing tool binary {}",
+                p.display()
+            ));
+        }
+        return Ok(p);
+    }
+
     let dir = env_toolchain_dir().ok_or_else(|| {
         anyhow!(
             "E_TOOLCHAIN_NOT_READY: TYPEVOICE_TOOLCHAIN_DIR is not set and {} is empty",
@@ -185,13 +234,10 @@ pub fn initialize_and_verify(app: &AppHandle, data_dir: &Path) -> ToolchainStatu
         Ok(s) => s,
         Err(e) => {
             let msg = e.to_string();
-            obs::event(
+            trace_failure(
                 data_dir,
-                None,
-                "Toolchain",
                 "TC.init",
-                "err",
-                Some(serde_json::json!({"code":"E_TOOLCHAIN_PLATFORM_UNSUPPORTED","message":msg})),
+                serde_json::json!({"code":"E_TOOLCHAIN_PLATFORM_UNSUPPORTED","message":msg}),
             );
             return ToolchainStatus {
                 ready: false,
@@ -208,13 +254,11 @@ pub fn initialize_and_verify(app: &AppHandle, data_dir: &Path) -> ToolchainStatu
         Ok(d) => d,
         Err(e) => {
             let msg = e.to_string();
-            obs::event(
+            *RESOLVED.write().unwrap() = None;
+            trace_failure(
                 data_dir,
-                None,
-                "Toolchain",
                 "TC.resolve_dir",
-                "err",
-                Some(serde_json::json!({"code":"E_TOOLCHAIN_NOT_READY","message":msg})),
+                serde_json::json!({"code":"E_TOOLCHAIN_NOT_READY","message":msg}),
             );
             return ToolchainStatus {
                 ready: false,
@@ -229,12 +273,15 @@ pub fn initialize_and_verify(app: &AppHandle, data_dir: &Path) -> ToolchainStatu
 
     let ffmpeg = tool_binary_from_dir(&dir, spec.ffmpeg_file);
     let ffprobe = tool_binary_from_dir(&dir, spec.ffprobe_file);
-    std::env::set_var("TYPEVOICE_TOOLCHAIN_DIR", dir.display().to_string());
-    std::env::set_var("TYPEVOICE_FFMPEG", ffmpeg.display().to_string());
-    std::env::set_var("TYPEVOICE_FFPROBE", ffprobe.display().to_string());
+    *RESOLVED.write().unwrap() = Some(ResolvedToolchain {
+        dir: dir.clone(),
+        ffmpeg,
+        ffprobe,
+    });
 
     match verify_toolchain_dir(&dir, spec) {
         Ok(()) => {
+            *LAST_FAILURE.lock().unwrap() = None;
             obs::event(
                 data_dir,
                 None,
@@ -261,17 +308,14 @@ pub fn initialize_and_verify(app: &AppHandle, data_dir: &Path) -> ToolchainStatu
             let code = detect_code(&msg)
                 .unwrap_or("E_TOOLCHAIN_NOT_READY")
                 .to_string();
-            obs::event(
+            trace_failure(
                 data_dir,
-                None,
-                "Toolchain",
                 "TC.verify",
-                "err",
-                Some(serde_json::json!({
+                serde_json::json!({
                     "code": code,
                     "message": msg,
                     "toolchain_dir": dir.display().to_string(),
-                })),
+                }),
             );
             ToolchainStatus {
                 ready: false,
@@ -285,6 +329,17 @@ pub fn initialize_and_verify(app: &AppHandle, data_dir: &Path) -> ToolchainStatu
     }
 }
 
+fn trace_failure(data_dir: &Path, step_id: &str, ctx: serde_json::Value) {
+    let key = format!("{step_id}:{ctx}");
+    let mut last = LAST_FAILURE.lock().unwrap();
+    if last.as_deref() == Some(key.as_str()) {
+        return;
+    }
+    *last = Some(key);
+    drop(last);
+    obs::event(data_dir, None, "Toolchain", step_id, "err", Some(ctx));
+}
+
 fn detect_code(msg: &str) -> Option<&'static str> {
     if msg.contains("E_TOOLCHAIN_CHECKSUM_MISMATCH") {
         return Some("E_TOOLCHAIN_CHECKSUM_MISMATCH");
@@ -315,15 +370,34 @@ fn verify_toolchain_dir(dir: &Path, spec: &PlatformSpec) -> Result<()> {
         ));
     }
 
-    verify_sha256(&ffmpeg, spec.ffmpeg_sha256, "ffmpeg")?;
-    verify_sha256(&ffprobe, spec.ffprobe_sha256, "ffprobe")?;
+    verify_binary(&ffmpeg, spec.ffmpeg_sha256, spec.version, "ffmpeg")?;
+    verify_binary(&ffprobe, spec.ffprobe_sha256, spec.version, "ffprobe")?;
 
-    verify_version(&ffmpeg, spec.version, "ffmpeg")?;
-    verify_version(&ffprobe, spec.version, "ffprobe")?;
+    Ok(())
+}
+
+fn verify_binary(path: &Path, sha256: &str, version: &str, tool_name: &str) -> Result<()> {
+    let fingerprint = file_fingerprint(path);
+    let mut verified = VERIFIED.lock().unwrap();
+    if fingerprint.as_ref().is_some_and(|f| verified.contains(f)) {
+        return Ok(());
+    }
+    verified.retain(|(p, ..)| p != path);
+    drop(verified);
 
+    verify_sha256(path, sha256, tool_name)?;
+    verify_version(path, version, tool_name)?;
+    if let Some(f) = fingerprint {
+        VERIFIED.lock().unwrap().push(f);
+    }
     Ok(())
 }
 
+fn file_fingerprint(path: &Path) -> Option<(PathBuf, u64, Option<SystemTime>)> {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((path.to_path_buf(), meta.len(), meta.modified().ok()))
+}
+
 fn verify_sha256(path: &Path, expected: &str, tool_name: &str) -> Result<()> {
     let actual = sha256_file(path)?;
     if !actual.eq_ignore_ascii_case(expected) {
@@ -381,7 +455,7 @@ fn verify_version(bin: &Path, expected_version: &str, tool_name: &str) -> Result
 
 #[cfg(test)]
 mod tests {
-    use super::resolve_tool_binary;
+    use super::{is_permanent_failure, resolve_tool_binary};
     use std::sync::{Mutex, OnceLock};
 
     fn env_lock() -> &'static Mutex<()> {
@@ -428,4 +502,14 @@ mod tests {
 
         std::env::remove_var("TYPEVOICE_TOOLCHAIN_DIR");
     }
+
+    #[test]
+    fn only_missing_toolchain_is_worth_rechecking() {
+        assert!(is_permanent_failure(Some("E_TOOLCHAIN_CHECKSUM_MISMATCH")));
+        assert!(is_permanent_failure(Some(
+            "E_TOOLCHAIN_PLATFORM_UNSUPPORTED"
+        )));
+        assert!(!is_permanent_failure(Some("E_TOOLCHAIN_NOT_READY")));
+        assert!(!is_permanent_failure(None));
+    }
 }
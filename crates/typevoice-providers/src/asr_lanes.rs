@@ -0,0 +1,115 @@
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use tokio::sync::watch;
+
+// Interactive dictation and batch work (ASR comparison, remote slices of a
+// batch run) share the ASR providers. Batch work runs in units and waits for
+// the interactive lane to go idle before each one, so a hotkey dictation never
+// queues behind a long job. Finished units are kept, so a paused job resumes
+// at its next unit. A unit waits at most BATCH_MAX_WAIT, which keeps a steady
+// stream of dictations from starving batch work.
+pub const BATCH_MAX_WAIT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AsrLane {
+    #[default]
+    Interactive,
+    Batch,
+}
+
+#[derive(Debug, Clone)]
+pub struct AsrLanes {
+    interactive: Arc<watch::Sender<usize>>,
+}
+
+impl Default for AsrLanes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AsrLanes {
+    pub fn new() -> Self {
+        Self {
+            interactive: Arc::new(watch::Sender::new(0)),
+        }
+    }
+
+    // Held for as long as a dictation is recording or transcribing.
+    pub fn interactive(&self) -> InteractiveLane {
+        self.interactive.send_modify(|n| *n += 1);
+        InteractiveLane {
+            interactive: self.interactive.clone(),
+        }
+    }
+
+    pub fn interactive_busy(&self) -> bool {
+        *self.interactive.borrow() > 0
+    }
+
+    // Waits until no dictation is in flight, or BATCH_MAX_WAIT has passed.
+    // Returns how long the unit was held back.
+    pub async fn batch_turn(&self) -> Duration {
+        self.batch_turn_within(BATCH_MAX_WAIT).await
+    }
+
+    async fn batch_turn_within(&self, max_wait: Duration) -> Duration {
+        let started = Instant::now();
+        let mut rx = self.interactive.subscribe();
+        let _ = tokio::time::timeout(max_wait, rx.wait_for(|n| *n == 0)).await;
+        started.elapsed()
+    }
+}
+
+// Process-wide lanes: every ASR entry point shares one interactive count.
+pub fn global() -> &'static AsrLanes {
+    static LANES: OnceLock<AsrLanes> = OnceLock::new();
+    LANES.get_or_init(AsrLanes::new)
+}
+
+#[derive(Debug)]
+pub struct InteractiveLane {
+    interactive: Arc<watch::Sender<usize>>,
+}
+
+impl Drop for InteractiveLane {
+    fn drop(&mut self) {
+        self.interactive.send_modify(|n| *n = n.saturating_sub(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cancel::mock;
+
+    #[test]
+    fn batch_waits_for_interactive_work_to_finish() {
+        mock::block_on(async {
+            let lanes = AsrLanes::new();
+            assert!(lanes.batch_turn().await < Duration::from_millis(50));
+            let dictation = lanes.interactive();
+            assert!(lanes.interactive_busy());
+            let waiter = {
+                let lanes = lanes.clone();
+                tokio::spawn(async move { lanes.batch_turn().await })
+            };
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            assert!(!waiter.is_finished());
+            drop(dictation);
+            let waited = waiter.await.expect("join");
+            assert!(waited >= Duration::from_millis(50));
+            assert!(!lanes.interactive_busy());
+        });
+    }
+
+    #[test]
+    fn batch_is_not_starved_by_back_to_back_dictation() {
+        let lanes = AsrLanes::new();
+        let _dictation = lanes.interactive();
+        let waited = mock::block_on(lanes.batch_turn_within(Duration::from_millis(30)));
+        assert!(waited >= Duration::from_millis(30));
+        assert!(lanes.interactive_busy());
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::obs::{schema::now_ms, Span};
+use crate::settings::{self, AsrWarmPoolResolved};
+use crate::{asr_lanes, data_dir, gpu_coordinator, remote_asr};
+
+// A local ASR server pays a long model load (30-60 s for large models) the
+// first time a model is requested after it was evicted. With
+// asr_warm_pool_enabled a small set stays resident: the active model, then the
+// most recently used ones, then the configured list, up to max_models and the
+// VRAM budget. Members are kept loaded by a short silent request once per
+// keepalive, sent only while no dictation is in flight; a real request counts
+// as a keepalive. Models are keyed by name, "" being the server's default.
+const TICK: Duration = Duration::from_secs(15);
+const MAX_RECENT: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemberReason {
+    Active,
+    Recent,
+    Configured,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    MaxModels,
+    VramBudget,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PoolMember {
+    pub model: String,
+    pub vram_mb: u64,
+    pub reason: MemberReason,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkippedModel {
+    pub model: String,
+    pub vram_mb: u64,
+    pub reason: SkipReason,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PoolPlan {
+    pub members: Vec<PoolMember>,
+    pub skipped: Vec<SkippedModel>,
+    pub vram_mb: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WarmRecord {
+    pub at_ms: i64,
+    // "request" when dictation or a comparison used the model.
+    pub source: &'static str,
+    pub elapsed_ms: Option<i64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WarmPoolStatus {
+    pub enabled: bool,
+    // Set when the pool is on but not warming, e.g. "gpu_coordination".
+    pub paused: Option<&'static str>,
+    pub plan: PoolPlan,
+    pub warmed: BTreeMap<String, WarmRecord>,
+}
+
+struct PoolState {
+    recent: Vec<String>,
+    warmed: BTreeMap<String, WarmRecord>,
+}
+
+static STATE: Mutex<PoolState> = Mutex::new(PoolState {
+    recent: Vec::new(),
+    warmed: BTreeMap::new(),
+});
+
+// Called after every successful remote transcription.
+pub fn note_used(model: Option<&str>) {
+    let model = model.map(str::trim).unwrap_or_default().to_string();
+    let mut st = STATE.lock().unwrap();
+    st.recent.retain(|m| *m != model);
+    st.recent.insert(0, model.clone());
+    st.recent.truncate(MAX_RECENT);
+    st.warmed.insert(
+        model,
+        WarmRecord {
+            at_ms: now_ms(),
+            source: "request",
+            elapsed_ms: None,
+            error: None,
+        },
+    );
+}
+
+pub fn plan(cfg: &AsrWarmPoolResolved, recent: &[String]) -> PoolPlan {
+    let mut candidates: Vec<(&str, MemberReason)> = Vec::new();
+    let active = cfg
+        .active_model
+        .as_deref()
+        .map(|m| (m, MemberReason::Active));
+    let recent = recent.iter().map(|m| (m.as_str(), MemberReason::Recent));
+    let configured = cfg
+        .models
+        .iter()
+        .map(|m| (m.as_str(), MemberReason::Configured));
+    for (model, reason) in active.into_iter().chain(recent).chain(configured) {
+        if !candidates.iter().any(|(m, _)| *m == model) {
+            candidates.push((model, reason));
+        }
+    }
+
+    let mut plan = PoolPlan::default();
+    for (model, reason) in candidates {
+        let vram_mb = cfg.vram_mb(model);
+        // The active model is always kept; limits only decide the others.
+        let skip = if reason == MemberReason::Active {
+            None
+        } else if plan.members.len() >= cfg.max_models {
+            Some(SkipReason::MaxModels)
+        } else if cfg.vram_budget_mb > 0 && plan.vram_mb + vram_mb > cfg.vram_budget_mb {
+            Some(SkipReason::VramBudget)
+        } else {
+            None
+        };
+        match skip {
+            None => {
+                plan.vram_mb += vram_mb;
+                plan.members.push(PoolMember {
+                    model: model.to_string(),
+                    vram_mb,
+                    reason,
+                });
+            }
+            Some(reason) => plan.skipped.push(SkippedModel {
+                model: model.to_string(),
+                vram_mb,
+                reason,
+            }),
+        }
+    }
+    plan
+}
+
+// GPU coordination unloads the ASR model whenever the LLM takes a turn;
+// keeping it warm would undo that, so coordination wins.
+fn paused_by(s: &settings::Settings, cfg: &AsrWarmPoolResolved) -> Option<&'static str> {
+    (settings::resolve_gpu_coordination(s).is_some()
+        && gpu_coordinator::is_local_endpoint(&cfg.url))
+    .then_some("gpu_coordination")
+}
+
+pub fn status(s: &settings::Settings) -> WarmPoolStatus {
+    let st = STATE.lock().unwrap();
+    let Some(cfg) = settings::resolve_asr_warm_pool(s) else {
+        return WarmPoolStatus {
+            enabled: false,
+            paused: None,
+            plan: PoolPlan::default(),
+            warmed: BTreeMap::new(),
+        };
+    };
+    let plan = plan(&cfg, &st.recent);
+    let warmed = st
+        .warmed
+        .iter()
+        .filter(|(m, _)| plan.members.iter().any(|p| p.model == **m))
+        .map(|(m, r)| (m.clone(), r.clone()))
+        .collect();
+    WarmPoolStatus {
+        enabled: true,
+        paused: paused_by(s, &cfg),
+        plan,
+        warmed,
+    }
+}
+
+// Started once at app launch; idles while the pool is off.
+pub async fn run() {
+    loop {
+        tokio::time::sleep(TICK).await;
+        let Ok(dir) = data_dir::data_dir() else {
+            continue;
+        };
+        let Ok(s) = settings::load_settings(&dir) else {
+            continue;
+        };
+        let Some(cfg) = settings::resolve_asr_warm_pool(&s) else {
+            continue;
+        };
+        if paused_by(&s, &cfg).is_some() {
+            continue;
+        }
+        let recent = STATE.lock().unwrap().recent.clone();
+        let keepalive_ms = (cfg.keepalive_secs * 1000) as i64;
+        for member in plan(&cfg, &recent).members {
+            let last = STATE
+                .lock()
+                .unwrap()
+                .warmed
+                .get(&member.model)
+                .map(|r| r.at_ms);
+            if last.is_some_and(|at| now_ms() - at < keepalive_ms) {
+                continue;
+            }
+            asr_lanes::global().batch_turn().await;
+            if asr_lanes::global().interactive_busy() {
+                break;
+            }
+            let record = warm(&dir, &cfg, &member).await;
+            STATE
+                .lock()
+                .unwrap()
+                .warmed
+                .insert(member.model.clone(), record);
+        }
+    }
+}
+
+async fn warm(dir: &Path, cfg: &AsrWarmPoolResolved, member: &PoolMember) -> WarmRecord {
+    let span = Span::start(
+        dir,
+        None,
+        "Transcribe",
+        "ASR.warm_pool_keepalive",
+        Some(serde_json::json!({
+            "model": member.model,
+            "reason": member.reason,
+            "vram_mb": member.vram_mb,
+        })),
+    );
+    let model = Some(member.model.as_str()).filter(|m| !m.is_empty());
+    let result = remote_asr::warm_model(dir, &cfg.url, model).await;
+    let mut record = WarmRecord {
+        at_ms: now_ms(),
+        source: "keepalive",
+        elapsed_ms: None,
+        error: None,
+    };
+    match result {
+        Ok(elapsed_ms) => {
+            span.ok(Some(serde_json::json!({ "elapsed_ms": elapsed_ms })));
+            record.elapsed_ms = Some(elapsed_ms);
+        }
+        Err(e) => {
+            span.err("remote", &e.code, &e.message, None);
+            record.error = Some(e.to_string());
+        }
+    }
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg() -> AsrWarmPoolResolved {
+        AsrWarmPoolResolved {
+            url: "http://127.0.0.1:8000/asr".to_string(),
+            active_model: Some("large".to_string()),
+            models: vec!["small".to_string(), "medium".to_string()],
+            max_models: 3,
+            vram_budget_mb: 5_000,
+            model_vram_mb: BTreeMap::from([
+                ("large".to_string(), 4_000),
+                ("small".to_string(), 500),
+                ("medium".to_string(), 1_500),
+            ]),
+            keepalive_secs: 240,
+        }
+    }
+
+    #[test]
+    fn active_model_leads_and_limits_decide_the_rest() {
+        let recent = ["small".to_string(), "large".to_string(), "tiny".to_string()];
+        let p = plan(&cfg(), &recent);
+        let members: Vec<(&str, MemberReason)> = p
+            .members
+            .iter()
+            .map(|m| (m.model.as_str(), m.reason))
+            .collect();
+        assert_eq!(
+            members,
+            [
+                ("large", MemberReason::Active),
+                ("small", MemberReason::Recent)
+            ]
+        );
+        assert_eq!(p.vram_mb, 4_500);
+        // tiny counts the default estimate and no longer fits the budget.
+        let skipped: Vec<(&str, SkipReason)> = p
+            .skipped
+            .iter()
+            .map(|m| (m.model.as_str(), m.reason))
+            .collect();
+        assert_eq!(
+            skipped,
+            [
+                ("tiny", SkipReason::VramBudget),
+                ("medium", SkipReason::VramBudget)
+            ]
+        );
+
+        let unbounded = AsrWarmPoolResolved {
+            vram_budget_mb: 0,
+            active_model: None,
+            ..cfg()
+        };
+        let p = plan(&unbounded, &recent);
+        assert_eq!(p.members.len(), 3);
+        assert_eq!(p.skipped[0].reason, SkipReason::MaxModels);
+    }
+}
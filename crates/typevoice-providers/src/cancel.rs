@@ -0,0 +1,77 @@
+use std::future::Future;
+
+use tokio_util::sync::CancellationToken;
+
+pub const CANCELLED_CODE: &str = "E_CANCELLED";
+
+// Every network future a provider awaits runs through here with the task token.
+// Cancelling drops the future, which drops the in-flight reqwest connection
+// instead of handing it back to the pool, so the socket closes right away
+// rather than after the request runs to completion.
+pub async fn or_cancelled<F: Future>(token: &CancellationToken, fut: F) -> Option<F::Output> {
+    tokio::select! {
+        biased;
+        _ = token.cancelled() => None,
+        v = fut => Some(v),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod mock {
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    // Accepts one request, reads it and never answers. Reports when the client
+    // closed its side of the socket.
+    pub fn stalled_server() -> (String, mpsc::Receiver<Instant>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let url = format!("http://{}/v1", listener.local_addr().expect("addr"));
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().expect("accept");
+            stream
+                .set_read_timeout(Some(Duration::from_secs(10)))
+                .expect("timeout");
+            let mut reader = BufReader::new(stream);
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                    break;
+                }
+            }
+            let mut buf = [0u8; 4096];
+            while let Ok(n) = reader.read(&mut buf) {
+                if n == 0 {
+                    let _ = tx.send(Instant::now());
+                    return;
+                }
+            }
+        });
+        (url, rx)
+    }
+
+    pub fn block_on<F: std::future::Future>(f: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("runtime")
+            .block_on(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{mock, or_cancelled};
+    use tokio_util::sync::CancellationToken;
+
+    #[test]
+    fn cancelled_token_wins_over_a_ready_future() {
+        let token = CancellationToken::new();
+        token.cancel();
+        assert_eq!(mock::block_on(or_cancelled(&token, async { 1 })), None);
+        let live = CancellationToken::new();
+        assert_eq!(mock::block_on(or_cancelled(&live, async { 1 })), Some(1));
+    }
+}
@@ -7,6 +7,7 @@ use serde_json::Value;
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
 
 use crate::llm::ApiKeyStatus;
+use crate::{data_dir, http_client, secret_store, settings};
 
 const KEYRING_SERVICE: &str = "typevoice";
 const APP_KEY_USER: &str = "doubao_asr_app_key";
@@ -41,26 +42,14 @@ pub fn set_credentials(app_key: &str, access_key: &str) -> Result<()> {
             "E_DOUBAO_ASR_ACCESS_KEY_MISSING: access key is required"
         ));
     }
-    keyring::Entry::new(KEYRING_SERVICE, APP_KEY_USER)
-        .map_err(|e| anyhow!("{e:?}"))?
-        .set_password(app_key)
-        .map_err(|e| anyhow!("{e:?}"))?;
-    keyring::Entry::new(KEYRING_SERVICE, ACCESS_KEY_USER)
-        .map_err(|e| anyhow!("{e:?}"))?
-        .set_password(access_key)
-        .map_err(|e| anyhow!("{e:?}"))?;
+    secret_store::set(KEYRING_SERVICE, APP_KEY_USER, app_key)?;
+    secret_store::set(KEYRING_SERVICE, ACCESS_KEY_USER, access_key)?;
     Ok(())
 }
 
 pub fn clear_credentials() -> Result<()> {
-    keyring::Entry::new(KEYRING_SERVICE, APP_KEY_USER)
-        .map_err(|e| anyhow!("{e:?}"))?
-        .set_password("")
-        .map_err(|e| anyhow!("{e:?}"))?;
-    keyring::Entry::new(KEYRING_SERVICE, ACCESS_KEY_USER)
-        .map_err(|e| anyhow!("{e:?}"))?
-        .set_password("")
-        .map_err(|e| anyhow!("{e:?}"))?;
+    secret_store::clear(KEYRING_SERVICE, APP_KEY_USER)?;
+    secret_store::clear(KEYRING_SERVICE, ACCESS_KEY_USER)?;
     Ok(())
 }
 
@@ -72,20 +61,20 @@ pub fn credentials_status() -> ApiKeyStatus {
             reason: None,
         };
     }
-    match keyring_credentials() {
-        Ok(Some(_)) => ApiKeyStatus {
+    match stored_credentials() {
+        Ok((Some(_), backend)) => ApiKeyStatus {
             configured: true,
-            source: "keyring".to_string(),
+            source: backend.as_str().to_string(),
             reason: None,
         },
-        Ok(None) => ApiKeyStatus {
+        Ok((None, backend)) => ApiKeyStatus {
             configured: false,
-            source: "keyring".to_string(),
+            source: backend.as_str().to_string(),
             reason: Some("empty".to_string()),
         },
         Err(e) => ApiKeyStatus {
             configured: false,
-            source: "keyring".to_string(),
+            source: secret_store::Backend::EncryptedFile.as_str().to_string(),
             reason: Some(e.to_string()),
         },
     }
@@ -95,15 +84,16 @@ pub fn load_credentials() -> Result<DoubaoCredentials> {
     if let Some(v) = env_credentials() {
         return Ok(v);
     }
-    match keyring_credentials()? {
-        Some(v) => Ok(v),
-        None => Err(anyhow!(
+    match stored_credentials()? {
+        (Some(v), _) => Ok(v),
+        (None, _) => Err(anyhow!(
             "E_DOUBAO_ASR_CREDENTIALS_MISSING: doubao ASR credentials are missing"
         )),
     }
 }
 
 pub async fn check_credentials_live() -> Result<()> {
+    http_client::ensure_online(&settings::load_settings(&data_dir::data_dir()?)?)?;
     let creds = load_credentials()?;
     let req = build_websocket_request(&creds)?;
     let (ws, _) = tokio::time::timeout(
@@ -287,26 +277,19 @@ fn env_credentials() -> Option<DoubaoCredentials> {
     })
 }
 
-fn keyring_credentials() -> Result<Option<DoubaoCredentials>> {
-    let app_key = keyring::Entry::new(KEYRING_SERVICE, APP_KEY_USER)
-        .map_err(|e| anyhow!("{e:?}"))?
-        .get_password()
-        .unwrap_or_default()
-        .trim()
-        .to_string();
-    let access_key = keyring::Entry::new(KEYRING_SERVICE, ACCESS_KEY_USER)
-        .map_err(|e| anyhow!("{e:?}"))?
-        .get_password()
-        .unwrap_or_default()
-        .trim()
-        .to_string();
-    if app_key.is_empty() || access_key.is_empty() {
-        return Ok(None);
-    }
-    Ok(Some(DoubaoCredentials {
-        app_key,
-        access_key,
-    }))
+fn stored_credentials() -> Result<(Option<DoubaoCredentials>, secret_store::Backend)> {
+    let (app_key, backend) = secret_store::get(KEYRING_SERVICE, APP_KEY_USER)?;
+    let (access_key, _) = secret_store::get(KEYRING_SERVICE, ACCESS_KEY_USER)?;
+    let (Some(app_key), Some(access_key)) = (app_key, access_key) else {
+        return Ok((None, backend));
+    };
+    Ok((
+        Some(DoubaoCredentials {
+            app_key: app_key.trim().to_string(),
+            access_key: access_key.trim().to_string(),
+        }),
+        backend,
+    ))
 }
 
 fn gzip(bytes: &[u8]) -> Result<Vec<u8>> {
@@ -0,0 +1,183 @@
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+use tokio_util::sync::CancellationToken;
+
+use crate::http_client;
+use crate::obs::Span;
+use crate::settings;
+
+// A local ASR server and a local (vision) LLM may not both fit in GPU memory.
+// With gpu_coordination_enabled their requests take turns: only backends whose
+// endpoint is on this machine join, and when the GPU changes hands the idle
+// backend's unload URL, if set, is POSTed first. Each turn is traced as
+// GPU.lease with its wait and unload, which is what explains a slow dictation
+// right after a rewrite.
+const UNLOAD_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuBackend {
+    Asr,
+    Llm,
+}
+
+impl GpuBackend {
+    fn stage(self) -> &'static str {
+        match self {
+            Self::Asr => "Transcribe",
+            Self::Llm => "Rewrite",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct GpuCoordinator {
+    // The backend that used the GPU last; locked for the length of a turn.
+    last: Arc<Mutex<Option<GpuBackend>>>,
+}
+
+pub struct GpuTurn {
+    pub waited: Duration,
+    pub previous: Option<GpuBackend>,
+    _guard: OwnedMutexGuard<Option<GpuBackend>>,
+}
+
+impl GpuCoordinator {
+    pub async fn take_turn(&self, backend: GpuBackend) -> GpuTurn {
+        let t0 = Instant::now();
+        let mut guard = self.last.clone().lock_owned().await;
+        let previous = guard.replace(backend);
+        GpuTurn {
+            waited: t0.elapsed(),
+            previous,
+            _guard: guard,
+        }
+    }
+}
+
+fn shared() -> &'static GpuCoordinator {
+    static SHARED: OnceLock<GpuCoordinator> = OnceLock::new();
+    SHARED.get_or_init(GpuCoordinator::default)
+}
+
+pub fn is_local_endpoint(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url.trim()) else {
+        return false;
+    };
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    host.eq_ignore_ascii_case("localhost")
+        || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+// Held while the backend's request runs. None when coordination is off, the
+// endpoint is remote or the task was cancelled while waiting; the caller then
+// goes ahead without a turn.
+pub async fn acquire(
+    data_dir: &Path,
+    task_id: Option<&str>,
+    backend: GpuBackend,
+    endpoint_url: &str,
+    cancel: &CancellationToken,
+) -> Option<GpuTurn> {
+    let cfg = settings::load_settings(data_dir)
+        .ok()
+        .and_then(|s| settings::resolve_gpu_coordination(&s))?;
+    if !is_local_endpoint(endpoint_url) {
+        return None;
+    }
+    let span = Span::start(
+        data_dir,
+        task_id,
+        backend.stage(),
+        "GPU.lease",
+        Some(serde_json::json!({ "backend": backend })),
+    );
+    let turn = tokio::select! {
+        turn = shared().take_turn(backend) => turn,
+        _ = cancel.cancelled() => {
+            span.skipped("cancelled", None);
+            return None;
+        }
+    };
+    let handed_over = turn.previous.filter(|p| *p != backend);
+    let unload_url = handed_over.and_then(|p| match p {
+        GpuBackend::Asr => cfg.asr_unload_url.as_deref(),
+        GpuBackend::Llm => cfg.llm_unload_url.as_deref(),
+    });
+    let unload = match unload_url {
+        Some(url) => Some(unload(data_dir, url).await),
+        None => None,
+    };
+    let ctx = serde_json::json!({
+        "backend": backend,
+        "waited_ms": turn.waited.as_millis() as u64,
+        "previous_backend": turn.previous,
+        "unloaded_backend": unload.as_ref().and(handed_over),
+    });
+    match unload {
+        // The turn still goes ahead; the model may fit anyway.
+        Some(Err(message)) => span.err("http", "E_GPU_UNLOAD_FAILED", &message, Some(ctx)),
+        _ => span.ok(Some(ctx)),
+    }
+    Some(turn)
+}
+
+async fn unload(data_dir: &Path, url: &str) -> Result<(), String> {
+    let client = http_client::client(data_dir).map_err(|e| e.to_string())?;
+    let resp = client
+        .post(url)
+        .timeout(UNLOAD_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("unload request failed: {e}"))?;
+    if !resp.status().is_success() {
+        return Err(format!("unload returned {}", resp.status()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cancel::mock;
+
+    #[test]
+    fn only_loopback_endpoints_share_the_gpu() {
+        assert!(is_local_endpoint("http://localhost:11434/v1"));
+        assert!(is_local_endpoint("http://127.0.0.1:8000/asr"));
+        assert!(is_local_endpoint("http://[::1]:8000/asr"));
+        assert!(!is_local_endpoint("https://api.openai.com/v1"));
+        assert!(!is_local_endpoint("http://192.168.1.20:8000"));
+        assert!(!is_local_endpoint("not a url"));
+    }
+
+    #[test]
+    fn turns_are_serialized_and_report_the_previous_backend() {
+        mock::block_on(async {
+            let gpu = Arc::new(GpuCoordinator::default());
+            let asr = gpu.take_turn(GpuBackend::Asr).await;
+            assert_eq!(asr.previous, None);
+            let waiter = {
+                let gpu = gpu.clone();
+                tokio::spawn(async move {
+                    let turn = gpu.take_turn(GpuBackend::Llm).await;
+                    (turn.waited, turn.previous)
+                })
+            };
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            assert!(!waiter.is_finished());
+            drop(asr);
+            let (waited, previous) = waiter.await.expect("join");
+            assert!(waited >= Duration::from_millis(50));
+            assert_eq!(previous, Some(GpuBackend::Asr));
+        });
+    }
+}
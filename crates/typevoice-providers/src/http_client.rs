@@ -0,0 +1,533 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use reqwest::{Certificate, Client, NoProxy, Proxy};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::{ring as rustls_ring, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{CertificateError, DigitallySignedStruct, RootCertStore, SignatureScheme};
+
+use crate::llm::ApiKeyStatus;
+use crate::obs::event;
+use crate::secret_store;
+use crate::settings::{self, HttpTimeoutsResolved, ProxyResolved, TlsTrustResolved};
+
+const KEYRING_SERVICE: &str = "typevoice";
+const PROXY_USERNAME_USER: &str = "proxy_username";
+const PROXY_PASSWORD_USER: &str = "proxy_password";
+const PROXY_SCHEMES: &[&str] = &["http", "https", "socks5", "socks5h"];
+const PROXY_TEST_TIMEOUT: Duration = Duration::from_secs(10);
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+const POOL_MAX_IDLE_PER_HOST: usize = 8;
+const USER_AGENT: &str = concat!("TypeVoice/", env!("CARGO_PKG_VERSION"));
+
+// One pooled client is shared by every network module and rebuilt only when the
+// effective options change, so rewrites reuse warm TLS/HTTP2 connections.
+static SHARED: Mutex<Option<(ClientOptions, Client)>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyConfig {
+    pub url: String,
+    pub no_proxy: Vec<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsTrust {
+    pub ca_bundle_pem: Option<Vec<u8>>,
+    pub pinned_sha256: Option<[u8; 32]>,
+    // The only hosts the pin applies to; the rest keep chain validation.
+    pub pinned_hosts: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientOptions {
+    pub proxy: Option<ProxyConfig>,
+    pub tls: TlsTrust,
+    pub timeouts: HttpTimeoutsResolved,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProxyTestOutcome {
+    pub status: u16,
+    pub elapsed_ms: i64,
+}
+
+// Every outbound reqwest client goes through here so LLM, remote ASR and future
+// downloads share one proxy, TLS and timeout policy.
+pub fn client(data_dir: &Path) -> Result<Client> {
+    let s = settings::load_settings(data_dir)?;
+    if let Err(e) = ensure_online(&s) {
+        event(data_dir, None, "Http", "NET.offline_blocked", "err", None);
+        return Err(e);
+    }
+    let opts = client_options(&s)?;
+    let (client, reused) = shared_client(&opts)?;
+    if !reused {
+        event(
+            data_dir,
+            None,
+            "Http",
+            "HTTP.client_built",
+            "ok",
+            Some(serde_json::json!({
+                "has_proxy": opts.proxy.is_some(),
+                "has_ca_bundle": opts.tls.ca_bundle_pem.is_some(),
+                "pinned": opts.tls.pinned_sha256.is_some(),
+                "connect_timeout_ms": opts.timeouts.connect_ms,
+                "request_timeout_ms": opts.timeouts.request_ms,
+            })),
+        );
+    }
+    Ok(client)
+}
+
+// Central offline gate. HTTP goes through client() above; websocket and other
+// non-reqwest paths must call this themselves before connecting.
+pub fn ensure_online(s: &settings::Settings) -> Result<()> {
+    if settings::resolve_offline_mode(s) {
+        return Err(anyhow!(
+            "E_OFFLINE_MODE: network access is disabled by offline_mode"
+        ));
+    }
+    Ok(())
+}
+
+pub fn client_options(s: &settings::Settings) -> Result<ClientOptions> {
+    Ok(ClientOptions {
+        proxy: proxy_config(s)?,
+        tls: tls_trust(s)?,
+        timeouts: settings::resolve_http_timeouts(s),
+    })
+}
+
+fn shared_client(opts: &ClientOptions) -> Result<(Client, bool)> {
+    let mut guard = SHARED.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((cached, client)) = guard.as_ref() {
+        if cached == opts {
+            return Ok((client.clone(), true));
+        }
+    }
+    let client = build_client(opts)?;
+    *guard = Some((opts.clone(), client.clone()));
+    Ok((client, false))
+}
+
+pub fn tls_trust(s: &settings::Settings) -> Result<TlsTrust> {
+    let TlsTrustResolved {
+        ca_bundle_path,
+        pinned_sha256,
+        pinned_hosts,
+    } = settings::resolve_tls_trust(s);
+    let ca_bundle_pem = match ca_bundle_path {
+        Some(p) => Some(
+            std::fs::read(&p)
+                .map_err(|e| anyhow!("E_TLS_CONFIG: read tls_ca_bundle_path failed ({p}): {e}"))?,
+        ),
+        None => None,
+    };
+    let pinned_sha256 = match pinned_sha256 {
+        Some(hex) => Some(parse_fingerprint(&hex)?),
+        None => None,
+    };
+    Ok(TlsTrust {
+        ca_bundle_pem,
+        pinned_sha256,
+        pinned_hosts,
+    })
+}
+
+pub fn proxy_config(s: &settings::Settings) -> Result<Option<ProxyConfig>> {
+    let Some(ProxyResolved { url, no_proxy }) = settings::resolve_proxy(s) else {
+        return Ok(None);
+    };
+    let (username, password) = stored_credentials()?;
+    Ok(Some(ProxyConfig {
+        url,
+        no_proxy,
+        username,
+        password,
+    }))
+}
+
+pub fn build_client(opts: &ClientOptions) -> Result<Client> {
+    let mut b = Client::builder()
+        .user_agent(USER_AGENT)
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
+        .connect_timeout(Duration::from_millis(opts.timeouts.connect_ms));
+    if let Some(ms) = opts.timeouts.request_ms {
+        b = b.timeout(Duration::from_millis(ms));
+    }
+    if let Some(p) = &opts.proxy {
+        b = b.proxy(to_reqwest_proxy(p)?);
+    }
+    let tls = &opts.tls;
+    if let Some(pem) = &tls.ca_bundle_pem {
+        let certs = Certificate::from_pem_bundle(pem)
+            .map_err(|e| anyhow!("E_TLS_CONFIG: invalid CA bundle: {e}"))?;
+        if certs.is_empty() {
+            return Err(anyhow!("E_TLS_CONFIG: CA bundle has no certificates"));
+        }
+        for cert in certs {
+            b = b.add_root_certificate(cert);
+        }
+    }
+    // A pin replaces chain validation on the pinned hosts, which is what
+    // self-signed servers need. reqwest drops its own roots for a
+    // preconfigured config, so the bundle is passed to the verifier as well.
+    if let Some(pin) = tls.pinned_sha256 {
+        let verifier =
+            PinnedCertVerifier::new(pin, &tls.pinned_hosts, tls.ca_bundle_pem.as_deref())?;
+        b = b.use_preconfigured_tls(pinned_tls_config(verifier)?);
+    }
+    b.build()
+        .map_err(|e| anyhow!("E_HTTP_CLIENT_BUILD: http client build failed: {e}"))
+}
+
+fn to_reqwest_proxy(p: &ProxyConfig) -> Result<Proxy> {
+    let url = p.url.trim();
+    let scheme = url.split_once("://").map(|(s, _)| s).unwrap_or("");
+    if !PROXY_SCHEMES.iter().any(|v| scheme.eq_ignore_ascii_case(v)) {
+        return Err(anyhow!(
+            "E_PROXY_CONFIG: proxy_url must start with one of {}",
+            PROXY_SCHEMES.join("/")
+        ));
+    }
+    let mut proxy =
+        Proxy::all(url).map_err(|e| anyhow!("E_PROXY_CONFIG: invalid proxy_url: {e}"))?;
+    if let Some(user) = p.username.as_deref().filter(|v| !v.is_empty()) {
+        proxy = proxy.basic_auth(user, p.password.as_deref().unwrap_or(""));
+    }
+    if !p.no_proxy.is_empty() {
+        proxy = proxy.no_proxy(NoProxy::from_string(&p.no_proxy.join(",")));
+    }
+    Ok(proxy)
+}
+
+// Send failures caused by certificate validation get their own code so users
+// are pointed at the TLS settings instead of the network.
+pub fn send_error_code(e: &reqwest::Error, fallback: &'static str) -> &'static str {
+    if is_tls_verify_error(e) {
+        "E_TLS_VERIFY"
+    } else {
+        fallback
+    }
+}
+
+fn is_tls_verify_error(e: &reqwest::Error) -> bool {
+    let mut cur: Option<&(dyn std::error::Error + 'static)> = Some(e);
+    while let Some(err) = cur {
+        // io::Error::source skips the wrapped error, so unwrap it explicitly.
+        let inner = err
+            .downcast_ref::<std::io::Error>()
+            .and_then(|io| io.get_ref())
+            .map(|v| v as &(dyn std::error::Error + 'static));
+        for candidate in [Some(err), inner].into_iter().flatten() {
+            if let Some(rustls::Error::InvalidCertificate(_)) = candidate.downcast_ref() {
+                return true;
+            }
+        }
+        cur = inner.and_then(|v| v.source()).or_else(|| err.source());
+    }
+    false
+}
+
+fn parse_fingerprint(hex: &str) -> Result<[u8; 32]> {
+    let bad = || anyhow!("E_TLS_CONFIG: tls_pinned_sha256 must be 64 hex characters");
+    if hex.len() != 64 || !hex.is_ascii() {
+        return Err(bad());
+    }
+    let mut out = [0u8; 32];
+    for (i, b) in out.iter_mut().enumerate() {
+        *b = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| bad())?;
+    }
+    Ok(out)
+}
+
+fn pinned_tls_config(verifier: PinnedCertVerifier) -> Result<rustls::ClientConfig> {
+    let provider = verifier.provider.clone();
+    let mut config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| anyhow!("E_TLS_CONFIG: {e}"))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth();
+    // reqwest does not set ALPN on a preconfigured config; keep HTTP/2 available.
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+#[derive(Debug)]
+struct PinnedCertVerifier {
+    pin: [u8; 32],
+    hosts: Vec<String>,
+    // Normal chain validation (public roots plus the CA bundle) for other hosts.
+    fallback: Arc<WebPkiServerVerifier>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl PinnedCertVerifier {
+    fn new(pin: [u8; 32], hosts: &[String], ca_bundle_pem: Option<&[u8]>) -> Result<Self> {
+        let provider = Arc::new(rustls_ring::default_provider());
+        let mut roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        if let Some(pem) = ca_bundle_pem {
+            for cert in CertificateDer::pem_slice_iter(pem) {
+                let cert = cert.map_err(|e| anyhow!("E_TLS_CONFIG: invalid CA bundle: {e}"))?;
+                roots
+                    .add(cert)
+                    .map_err(|e| anyhow!("E_TLS_CONFIG: invalid CA bundle: {e}"))?;
+            }
+        }
+        let fallback =
+            WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+                .build()
+                .map_err(|e| anyhow!("E_TLS_CONFIG: {e}"))?;
+        Ok(Self {
+            pin,
+            hosts: hosts.to_vec(),
+            fallback,
+            provider,
+        })
+    }
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let host = server_name.to_str();
+        if !self.hosts.iter().any(|h| h.eq_ignore_ascii_case(&host)) {
+            return self.fallback.verify_server_cert(
+                end_entity,
+                intermediates,
+                server_name,
+                ocsp_response,
+                now,
+            );
+        }
+        let digest = ring::digest::digest(&ring::digest::SHA256, end_entity.as_ref());
+        if digest.as_ref() == self.pin {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::InvalidCertificate(
+                CertificateError::ApplicationVerificationFailure,
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+// Any HTTP response proves the proxy tunnel works; the status is only reported.
+pub async fn test_proxy(opts: &ClientOptions, target_url: &str) -> Result<ProxyTestOutcome> {
+    let client = build_client(opts)?;
+    let started = Instant::now();
+    let resp = client
+        .get(target_url)
+        .timeout(PROXY_TEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| anyhow!("{}: {e}", send_error_code(&e, "E_PROXY_TEST_SEND")))?;
+    Ok(ProxyTestOutcome {
+        status: resp.status().as_u16(),
+        elapsed_ms: started.elapsed().as_millis() as i64,
+    })
+}
+
+pub fn set_credentials(username: &str, password: &str) -> Result<()> {
+    let username = username.trim();
+    if username.is_empty() {
+        return Err(anyhow!(
+            "E_PROXY_USERNAME_MISSING: proxy username is required"
+        ));
+    }
+    secret_store::set(KEYRING_SERVICE, PROXY_USERNAME_USER, username)?;
+    secret_store::set(KEYRING_SERVICE, PROXY_PASSWORD_USER, password)?;
+    Ok(())
+}
+
+pub fn clear_credentials() -> Result<()> {
+    secret_store::clear(KEYRING_SERVICE, PROXY_USERNAME_USER)?;
+    secret_store::clear(KEYRING_SERVICE, PROXY_PASSWORD_USER)
+}
+
+pub fn credentials_status() -> ApiKeyStatus {
+    secret_store::status(KEYRING_SERVICE, PROXY_USERNAME_USER)
+}
+
+fn stored_credentials() -> Result<(Option<String>, Option<String>)> {
+    let (username, _) = secret_store::get(KEYRING_SERVICE, PROXY_USERNAME_USER)?;
+    if username.is_none() {
+        return Ok((None, None));
+    }
+    let (password, _) = secret_store::get(KEYRING_SERVICE, PROXY_PASSWORD_USER)?;
+    Ok((username, password))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_client, client, parse_fingerprint, shared_client, ClientOptions, PinnedCertVerifier,
+        ProxyConfig, TlsTrust,
+    };
+    use crate::settings::{self, HttpTimeoutsResolved, Settings};
+    use rustls::client::danger::ServerCertVerifier;
+    use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+
+    fn opts(proxy: Option<ProxyConfig>, tls: TlsTrust) -> ClientOptions {
+        ClientOptions {
+            proxy,
+            tls,
+            timeouts: HttpTimeoutsResolved {
+                connect_ms: 1_000,
+                request_ms: Some(5_000),
+            },
+        }
+    }
+
+    fn proxy(url: &str) -> ProxyConfig {
+        ProxyConfig {
+            url: url.to_string(),
+            no_proxy: vec!["localhost".to_string()],
+            username: Some("alice".to_string()),
+            password: Some("pw".to_string()),
+        }
+    }
+
+    #[test]
+    fn build_client_accepts_http_and_socks_proxies() {
+        for url in [
+            "http://proxy.corp:8080",
+            "https://proxy.corp:8443",
+            "socks5h://proxy.corp:1080",
+        ] {
+            assert!(
+                build_client(&opts(Some(proxy(url)), TlsTrust::default())).is_ok(),
+                "{url}"
+            );
+        }
+        assert!(build_client(&opts(None, TlsTrust::default())).is_ok());
+    }
+
+    #[test]
+    fn build_client_rejects_unknown_scheme() {
+        let err = build_client(&opts(Some(proxy("ftp://proxy.corp")), TlsTrust::default()))
+            .expect_err("scheme");
+        assert!(err.to_string().starts_with("E_PROXY_CONFIG"));
+        let err = build_client(&opts(Some(proxy("proxy.corp:8080")), TlsTrust::default()))
+            .expect_err("scheme");
+        assert!(err.to_string().starts_with("E_PROXY_CONFIG"));
+    }
+
+    #[test]
+    fn build_client_applies_pin_and_rejects_bad_tls_config() {
+        let pin = "ab".repeat(32);
+        let tls = TlsTrust {
+            ca_bundle_pem: None,
+            pinned_sha256: Some(parse_fingerprint(&pin).expect("pin")),
+            pinned_hosts: vec!["llm.lan".to_string()],
+        };
+        assert!(build_client(&opts(None, tls.clone())).is_ok());
+        assert!(parse_fingerprint("abcd")
+            .expect_err("short")
+            .to_string()
+            .starts_with("E_TLS_CONFIG"));
+
+        let tls = TlsTrust {
+            ca_bundle_pem: Some(b"not a pem".to_vec()),
+            pinned_sha256: None,
+            pinned_hosts: Vec::new(),
+        };
+        let err = build_client(&opts(None, tls)).expect_err("bundle");
+        assert!(err.to_string().starts_with("E_TLS_CONFIG"));
+    }
+
+    #[test]
+    fn pin_only_replaces_chain_validation_on_pinned_hosts() {
+        let cert = CertificateDer::from(b"self-signed".to_vec());
+        let digest = ring::digest::digest(&ring::digest::SHA256, cert.as_ref());
+        let mut pin = [0u8; 32];
+        pin.copy_from_slice(digest.as_ref());
+        let verifier =
+            PinnedCertVerifier::new(pin, &["llm.lan".to_string()], None).expect("verifier");
+        let verify = |host: &'static str| {
+            verifier.verify_server_cert(
+                &cert,
+                &[],
+                &ServerName::try_from(host).expect("name"),
+                &[],
+                UnixTime::now(),
+            )
+        };
+        assert!(verify("LLM.lan").is_ok());
+        // Any other host goes through the normal chain check and fails.
+        assert!(verify("api.openai.com").is_err());
+    }
+
+    #[test]
+    fn shared_client_is_reused_until_options_change() {
+        let direct = opts(None, TlsTrust::default());
+        shared_client(&direct).expect("first");
+        assert!(shared_client(&direct).expect("second").1);
+        let proxied = opts(Some(proxy("http://proxy.corp:8080")), TlsTrust::default());
+        assert!(!shared_client(&proxied).expect("rebuilt").1);
+        assert!(shared_client(&proxied).expect("reused").1);
+    }
+
+    #[test]
+    fn client_is_refused_in_offline_mode() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let s = Settings {
+            offline_mode: Some(true),
+            ..Default::default()
+        };
+        settings::save_settings(dir.path(), &s).expect("save");
+        let err = client(dir.path()).expect_err("offline");
+        assert!(err.to_string().starts_with("E_OFFLINE_MODE"));
+    }
+}
@@ -1,7 +1,14 @@
 pub use typevoice_core::context_pack;
 pub use typevoice_observability::obs;
-pub use typevoice_storage::{data_dir, history, settings};
+pub use typevoice_storage::{atomic_file, data_dir, history, settings, write_gate};
 
+pub mod asr_lanes;
+pub mod asr_warm_pool;
+pub mod cancel;
 pub mod doubao_asr;
+pub mod gpu_coordinator;
+pub mod http_client;
 pub mod llm;
 pub mod remote_asr;
+pub mod secret_store;
+pub mod webhook;
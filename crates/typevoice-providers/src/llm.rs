@@ -1,17 +1,24 @@
 use anyhow::{anyhow, Result};
 use base64::Engine;
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
 
-use crate::context_pack::PreparedContext;
+use crate::cancel;
+use crate::context_pack::{self, PreparedContext};
+use crate::http_client;
 use crate::obs::debug;
+use crate::obs::llm_capture::{self, LlmCapture};
 use crate::obs::{event, Span};
+use crate::secret_store;
 use crate::settings;
 
+const KEYRING_SERVICE: &str = "typevoice";
+const KEYRING_USER: &str = "llm_api_key";
+
 #[derive(Debug, Clone, Serialize)]
 pub struct ApiKeyStatus {
     pub configured: bool,
-    pub source: String, // env|keyring
+    pub source: String, // env|keyring|encrypted_file
     pub reason: Option<String>,
 }
 
@@ -20,6 +27,15 @@ pub struct LlmConfig {
     pub base_url: String, // e.g. https://api.openai.com/v1
     pub model: String,
     pub reasoning_effort: Option<String>,
+    pub system_preamble: Option<String>,
+}
+
+// What rewrite_with_context would send for a bare transcript (no captured context).
+#[derive(Debug, Clone, Serialize)]
+pub struct RewritePreview {
+    pub system_prompt: String,
+    pub user_text: String,
+    pub has_preamble: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -30,8 +46,70 @@ struct ChatReq {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     reasoning_effort: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<serde_json::Value>,
+}
+
+// How the model's reply must be shaped. JSON output is repaired (code fences,
+// surrounding prose) and validated before it is accepted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    JsonSchema(Option<serde_json::Value>),
+}
+
+impl OutputFormat {
+    pub fn from_settings(s: &settings::Settings, template_id: Option<&str>) -> Result<Self> {
+        let resolved = settings::resolve_rewrite_output_format(s, template_id);
+        if resolved.format != "json_schema" {
+            return Ok(Self::Text);
+        }
+        let schema = match resolved.json_schema.as_deref() {
+            Some(raw) => Some(serde_json::from_str(raw).map_err(|e| {
+                anyhow!("E_SETTINGS_REWRITE_OUTPUT_SCHEMA_INVALID: rewrite output schema is not valid JSON: {e}")
+            })?),
+            None => None,
+        };
+        Ok(Self::JsonSchema(schema))
+    }
+
+    fn response_format(&self) -> Option<serde_json::Value> {
+        match self {
+            Self::Text => None,
+            Self::JsonSchema(None) => Some(serde_json::json!({"type": "json_object"})),
+            Self::JsonSchema(Some(schema)) => Some(serde_json::json!({
+                "type": "json_schema",
+                "json_schema": {"name": "typevoice_output", "schema": schema},
+            })),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::JsonSchema(_) => "json_schema",
+        }
+    }
 }
 
+// Returned (inside anyhow) when JSON output cannot be repaired or fails the
+// schema; callers may fall back to `raw_text`.
+#[derive(Debug, Clone)]
+pub struct OutputInvalid {
+    pub raw_text: String,
+    pub reason: String,
+}
+
+impl std::fmt::Display for OutputInvalid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "E_LLM_OUTPUT_INVALID: {}", self.reason)
+    }
+}
+
+impl std::error::Error for OutputInvalid {}
+
 #[derive(Debug, Clone, Serialize)]
 struct Message {
     role: String,
@@ -60,6 +138,17 @@ struct ImageUrl {
 #[derive(Debug, Deserialize)]
 struct ChatResp {
     choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<TokenUsage>,
+}
+
+// Token counts as reported by the endpoint; not every server sends them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    #[serde(default)]
+    pub prompt_tokens: u64,
+    #[serde(default)]
+    pub completion_tokens: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -79,6 +168,14 @@ pub struct RewriteContextPolicy {
     pub include_prev_window_meta: bool,
     pub include_prev_window_screenshot: bool,
     pub include_glossary: bool,
+    #[serde(skip)]
+    pub output_format: OutputFormat,
+    // Overrides the configured endpoint and model for this request.
+    #[serde(skip)]
+    pub endpoint: Option<LlmConfig>,
+    // The task's token; the default one is never cancelled.
+    #[serde(skip)]
+    pub cancel: CancellationToken,
 }
 
 fn normalize_base_url(s: &str) -> Result<String> {
@@ -110,6 +207,7 @@ fn normalize_reasoning_effort(s: &str) -> Option<String> {
 
 pub fn load_config(data_dir: &std::path::Path) -> Result<LlmConfig> {
     let s = settings::load_settings_strict(data_dir)?;
+    let system_preamble = settings::resolve_rewrite_system_preamble(&s);
 
     let base_url = s
         .llm_base_url
@@ -138,6 +236,7 @@ pub fn load_config(data_dir: &std::path::Path) -> Result<LlmConfig> {
         base_url: normalize_base_url(&base_url)?,
         model,
         reasoning_effort,
+        system_preamble,
     })
 }
 
@@ -156,6 +255,7 @@ pub fn config_from_values(
         base_url: normalize_base_url(base_url)?,
         model: model.to_string(),
         reasoning_effort: reasoning_effort.and_then(normalize_reasoning_effort),
+        system_preamble: None,
     })
 }
 
@@ -165,35 +265,19 @@ pub fn load_api_key() -> Result<String> {
             return Ok(k);
         }
     }
-    let entry = keyring::Entry::new("typevoice", "llm_api_key")
-        .map_err(|e| anyhow!("keyring entry init failed: {e:?}"))?;
-    let k = entry
-        .get_password()
-        .map_err(|e| anyhow!("keyring get failed: {e:?}"))?;
-    if k.trim().is_empty() {
-        return Err(anyhow!("empty api key"));
+    match secret_store::get(KEYRING_SERVICE, KEYRING_USER)? {
+        (Some(k), _) => Ok(k),
+        (None, _) => Err(anyhow!("empty api key")),
     }
-    Ok(k)
 }
 
 pub fn set_api_key(key: &str) -> Result<()> {
-    let entry = keyring::Entry::new("typevoice", "llm_api_key")
-        .map_err(|e| anyhow!("keyring entry init failed: {e:?}"))?;
-    entry
-        .set_password(key)
-        .map_err(|e| anyhow!("keyring set failed: {e:?}"))?;
+    secret_store::set(KEYRING_SERVICE, KEYRING_USER, key)?;
     Ok(())
 }
 
 pub fn clear_api_key() -> Result<()> {
-    let entry = keyring::Entry::new("typevoice", "llm_api_key")
-        .map_err(|e| anyhow!("keyring entry init failed: {e:?}"))?;
-    // keyring v3 does not expose a cross-platform delete API. We overwrite with
-    // an empty password and treat empty as "not configured".
-    let _ = entry
-        .set_password("")
-        .map_err(|e| anyhow!("keyring set failed: {e:?}"));
-    Ok(())
+    secret_store::clear(KEYRING_SERVICE, KEYRING_USER)
 }
 
 pub fn api_key_status() -> ApiKeyStatus {
@@ -206,45 +290,12 @@ pub fn api_key_status() -> ApiKeyStatus {
             };
         }
     }
-
-    let entry = match keyring::Entry::new("typevoice", "llm_api_key") {
-        Ok(e) => e,
-        Err(e) => {
-            return ApiKeyStatus {
-                configured: false,
-                source: "keyring".to_string(),
-                reason: Some(format!("keyring_entry_init_failed:{e:?}")),
-            };
-        }
-    };
-
-    let k = match entry.get_password() {
-        Ok(k) => k,
-        Err(e) => {
-            return ApiKeyStatus {
-                configured: false,
-                source: "keyring".to_string(),
-                reason: Some(format!("keyring_get_failed:{e:?}")),
-            };
-        }
-    };
-    if k.trim().is_empty() {
-        return ApiKeyStatus {
-            configured: false,
-            source: "keyring".to_string(),
-            reason: Some("empty".to_string()),
-        };
-    }
-    ApiKeyStatus {
-        configured: true,
-        source: "keyring".to_string(),
-        reason: None,
-    }
+    secret_store::status(KEYRING_SERVICE, KEYRING_USER)
 }
 
 pub async fn check_api_key_live(cfg: &LlmConfig) -> Result<()> {
     let key = load_api_key()?;
-    let client = Client::new();
+    let client = http_client::client(&crate::data_dir::data_dir()?)?;
     let url = format!("{}/chat/completions", cfg.base_url);
     let req = ChatReq {
         model: cfg.model.clone(),
@@ -263,6 +314,7 @@ pub async fn check_api_key_live(cfg: &LlmConfig) -> Result<()> {
         ],
         temperature: 0.0,
         reasoning_effort: cfg.reasoning_effort.clone(),
+        response_format: None,
     };
 
     let resp = client
@@ -271,7 +323,10 @@ pub async fn check_api_key_live(cfg: &LlmConfig) -> Result<()> {
         .json(&req)
         .send()
         .await
-        .map_err(|e| anyhow!("E_LLM_CHECK_HTTP_SEND: request failed: {e}"))?;
+        .map_err(|e| {
+            let code = http_client::send_error_code(&e, "E_LLM_CHECK_HTTP_SEND");
+            anyhow!("{code}: request failed: {e}")
+        })?;
 
     let status = resp.status();
     let body = resp.text().await.unwrap_or_default();
@@ -323,6 +378,30 @@ pub async fn rewrite_with_context(
     rewrite_glossary: &[String],
     policy: &RewriteContextPolicy,
 ) -> Result<String> {
+    rewrite_with_usage(
+        data_dir,
+        task_id,
+        system_prompt,
+        asr_text,
+        ctx,
+        rewrite_glossary,
+        policy,
+    )
+    .await
+    .map(|(text, _)| text)
+}
+
+// Same as rewrite_with_context, also returning the endpoint's token usage.
+pub async fn rewrite_with_usage(
+    data_dir: &std::path::Path,
+    task_id: &str,
+    system_prompt: &str,
+    asr_text: &str,
+    ctx: Option<&PreparedContext>,
+    rewrite_glossary: &[String],
+    policy: &RewriteContextPolicy,
+) -> Result<(String, Option<TokenUsage>)> {
+    let output_format = &policy.output_format;
     let span = Span::start(
         data_dir,
         Some(task_id),
@@ -332,10 +411,15 @@ pub async fn rewrite_with_context(
             "has_context": ctx.is_some(),
             "has_screenshot": ctx.and_then(|c| c.screenshot.as_ref()).is_some(),
             "policy": policy,
+            "output_format": output_format.name(),
         })),
     );
 
-    let cfg = match load_config(data_dir) {
+    let cfg = match policy
+        .endpoint
+        .clone()
+        .map_or_else(|| load_config(data_dir), Ok)
+    {
         Ok(c) => c,
         Err(e) => {
             span.err_anyhow("config", "E_LLM_CONFIG", &e, None);
@@ -349,11 +433,23 @@ pub async fn rewrite_with_context(
             return Err(e);
         }
     };
-    let client = Client::new();
+    let client = match http_client::client(data_dir) {
+        Ok(c) => c,
+        Err(e) => {
+            span.err_anyhow("config", "E_LLM_HTTP_CLIENT", &e, None);
+            return Err(e);
+        }
+    };
     let url = format!("{}/chat/completions", cfg.base_url);
 
     let (user_content_send, user_content_debug) =
         build_user_content(asr_text, ctx, rewrite_glossary, policy);
+    let system_text = build_system_text(
+        cfg.system_preamble.as_deref(),
+        system_prompt,
+        output_format,
+        ctx,
+    );
 
     // Record the exact request "shape" the model will receive (text vs multimodal parts).
     let (kind, has_image_url) = user_content_shape(&user_content_send);
@@ -367,7 +463,10 @@ pub async fn rewrite_with_context(
             "user_content_kind": kind,
             "has_image_url": has_image_url,
             "asr_chars": asr_text.len(),
-            "system_prompt_chars": system_prompt.len(),
+            "system_prompt_chars": system_text.len(),
+            "has_preamble": cfg.system_preamble.is_some(),
+            "injection_guard": ctx.is_some_and(|c| c.injection_guard),
+            "stripped_injections": ctx.map(|c| c.stripped_injections).unwrap_or(0),
             "glossary_count": rewrite_glossary.len(),
             "include_glossary": policy.include_glossary,
         })),
@@ -377,7 +476,7 @@ pub async fn rewrite_with_context(
         messages: vec![
             Message {
                 role: "system".to_string(),
-                content: MessageContent::Text(system_prompt.to_string()),
+                content: MessageContent::Text(system_text.clone()),
             },
             Message {
                 role: "user".to_string(),
@@ -386,6 +485,7 @@ pub async fn rewrite_with_context(
         ],
         temperature: 0.2,
         reasoning_effort: cfg.reasoning_effort.clone(),
+        response_format: output_format.response_format(),
     };
 
     let req_debug = ChatReq {
@@ -393,7 +493,7 @@ pub async fn rewrite_with_context(
         messages: vec![
             Message {
                 role: "system".to_string(),
-                content: MessageContent::Text(system_prompt.to_string()),
+                content: MessageContent::Text(system_text.clone()),
             },
             Message {
                 role: "user".to_string(),
@@ -402,6 +502,7 @@ pub async fn rewrite_with_context(
         ],
         temperature: 0.2,
         reasoning_effort: cfg.reasoning_effort.clone(),
+        response_format: output_format.response_format(),
     };
 
     if debug::verbose_enabled() && debug::include_llm() {
@@ -426,19 +527,36 @@ pub async fn rewrite_with_context(
         }
     }
 
-    let resp = match client
-        .post(url.clone())
-        .bearer_auth(key)
-        .json(&req_send)
-        .send()
-        .await
-    {
-        Ok(r) => r,
-        Err(e) => {
-            let ae = anyhow!("llm http request failed: {e}");
+    // req_debug already carries screenshots as hashes.
+    let capture = settings::load_settings(data_dir)
+        .ok()
+        .and_then(|s| settings::resolve_llm_capture(&s))
+        .map(|max| {
+            let request = serde_json::to_string(&req_debug)
+                .ok()
+                .and_then(|raw| serde_json::from_str(&redact_secret(&raw, &key)).ok())
+                .unwrap_or(serde_json::Value::Null);
+            let url = redact_url_credentials(&url);
+            (max, LlmCapture::new(task_id, &url, &cfg.model, request))
+        });
+
+    let (status, body) = match post_chat(&client, &url, &key, &req_send, &policy.cancel).await {
+        Ok(v) => v,
+        Err(ChatSendError::Cancelled) => {
+            let ae = anyhow!("{}: llm request cancelled", cancel::CANCELLED_CODE);
+            span.err_anyhow("cancelled", cancel::CANCELLED_CODE, &ae, None);
+            return Err(ae);
+        }
+        Err(ChatSendError::Http(e)) => {
+            let code = http_client::send_error_code(&e, "E_LLM_HTTP_SEND");
+            let ae = anyhow!("{code}: llm http request failed: {e}");
+            if let Some((max, mut c)) = capture {
+                c.error = Some(redact_secret(&ae.to_string(), &key));
+                llm_capture::record_best_effort(data_dir, c, max);
+            }
             span.err_anyhow(
                 "http",
-                "E_LLM_HTTP_SEND",
+                code,
                 &ae,
                 Some(serde_json::json!({"url": url, "model": cfg.model})),
             );
@@ -446,8 +564,11 @@ pub async fn rewrite_with_context(
         }
     };
 
-    let status = resp.status();
-    let body = resp.text().await.unwrap_or_default();
+    if let Some((max, mut c)) = capture {
+        c.status = Some(status.as_u16());
+        c.response = Some(redact_secret(&body, &key));
+        llm_capture::record_best_effort(data_dir, c, max);
+    }
 
     if debug::verbose_enabled() && debug::include_llm() {
         if let Some(info) = debug::write_payload_best_effort(
@@ -504,12 +625,76 @@ pub async fn rewrite_with_context(
         span.err_anyhow("logic", "E_LLM_EMPTY", &ae, None);
         return Err(ae);
     }
+    let content = match enforce_output_format(&content, output_format) {
+        Ok(v) => v,
+        Err(invalid) => {
+            let ae = anyhow::Error::new(invalid);
+            span.err_anyhow(
+                "validate",
+                "E_LLM_OUTPUT_INVALID",
+                &ae,
+                Some(serde_json::json!({"content_chars": content.len()})),
+            );
+            return Err(ae);
+        }
+    };
     span.ok(Some(serde_json::json!({
         "status": status.as_u16(),
         "content_chars": content.len(),
         "model": cfg.model,
+        "usage": r.usage,
     })));
-    Ok(content)
+    Ok((content, r.usage))
+}
+
+// The key only travels in the Authorization header, but a proxy or endpoint may
+// echo it back, and base URLs can carry user:password.
+fn redact_secret(text: &str, secret: &str) -> String {
+    if secret.len() < 8 {
+        return text.to_string();
+    }
+    text.replace(secret, "<redacted>")
+}
+
+fn redact_url_credentials(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let authority_end = rest.find('/').unwrap_or(rest.len());
+    match rest[..authority_end].rfind('@') {
+        Some(at) => format!("{scheme}://<redacted>@{}", &rest[at + 1..]),
+        None => url.to_string(),
+    }
+}
+
+enum ChatSendError {
+    Cancelled,
+    Http(reqwest::Error),
+}
+
+// Send and body read both run under the task token, so a cancelled rewrite
+// drops its connection instead of waiting for the model to finish.
+async fn post_chat(
+    client: &reqwest::Client,
+    url: &str,
+    key: &str,
+    req: &ChatReq,
+    token: &CancellationToken,
+) -> Result<(reqwest::StatusCode, String), ChatSendError> {
+    let exchange = async {
+        let resp = client
+            .post(url)
+            .bearer_auth(key)
+            .json(req)
+            .send()
+            .await
+            .map_err(ChatSendError::Http)?;
+        let status = resp.status();
+        Ok((status, resp.text().await.unwrap_or_default()))
+    };
+    cancel::or_cancelled(token, exchange)
+        .await
+        .unwrap_or(Err(ChatSendError::Cancelled))
 }
 
 fn user_content_shape(content: &MessageContent) -> (&'static str, bool) {
@@ -524,6 +709,191 @@ fn user_content_shape(content: &MessageContent) -> (&'static str, bool) {
     }
 }
 
+const JSON_OUTPUT_INSTRUCTION: &str =
+    "Respond with a single JSON value only, without code fences or commentary.";
+
+// Models often wrap JSON in ``` fences or a sentence of prose; accept the first
+// parseable JSON value found that way.
+fn repair_json(content: &str) -> Option<serde_json::Value> {
+    let trimmed = content.trim();
+    if let Ok(v) = serde_json::from_str(trimmed) {
+        return Some(v);
+    }
+    let unfenced = trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```JSON"))
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|rest| rest.trim_end().strip_suffix("```"))
+        .map(str::trim);
+    if let Some(v) = unfenced.and_then(|u| serde_json::from_str(u).ok()) {
+        return Some(v);
+    }
+    let start = trimmed.find(['{', '['])?;
+    let close = if trimmed[start..].starts_with('{') {
+        '}'
+    } else {
+        ']'
+    };
+    let end = trimmed.rfind(close)?;
+    if end <= start {
+        return None;
+    }
+    serde_json::from_str(&trimmed[start..=end]).ok()
+}
+
+fn json_type_matches(value: &serde_json::Value, ty: &str) -> bool {
+    match ty {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+// Covers the subset of JSON Schema form-filling templates use: type, enum,
+// required, properties, additionalProperties=false and items.
+fn validate_json_schema(
+    value: &serde_json::Value,
+    schema: &serde_json::Value,
+    path: &str,
+) -> std::result::Result<(), String> {
+    let Some(schema) = schema.as_object() else {
+        return Ok(());
+    };
+    if let Some(ty) = schema.get("type") {
+        let types: Vec<&str> = match ty {
+            serde_json::Value::String(t) => vec![t.as_str()],
+            serde_json::Value::Array(ts) => ts.iter().filter_map(|t| t.as_str()).collect(),
+            _ => vec![],
+        };
+        if !types.is_empty() && !types.iter().any(|t| json_type_matches(value, t)) {
+            return Err(format!("{path}: expected type {}", types.join("|")));
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(|v| v.as_array()) {
+        if !allowed.contains(value) {
+            return Err(format!("{path}: value not in enum"));
+        }
+    }
+    if let Some(obj) = value.as_object() {
+        if let Some(required) = schema.get("required").and_then(|v| v.as_array()) {
+            for key in required.iter().filter_map(|k| k.as_str()) {
+                if !obj.contains_key(key) {
+                    return Err(format!("{path}: missing required property {key}"));
+                }
+            }
+        }
+        let props = schema.get("properties").and_then(|v| v.as_object());
+        for (key, item) in obj {
+            match props.and_then(|p| p.get(key)) {
+                Some(sub) => validate_json_schema(item, sub, &format!("{path}.{key}"))?,
+                None if schema.get("additionalProperties")
+                    == Some(&serde_json::Value::Bool(false)) =>
+                {
+                    return Err(format!("{path}: unexpected property {key}"));
+                }
+                None => {}
+            }
+        }
+    }
+    if let (Some(items), Some(sub)) = (value.as_array(), schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            validate_json_schema(item, sub, &format!("{path}[{i}]"))?;
+        }
+    }
+    Ok(())
+}
+
+fn enforce_output_format(
+    content: &str,
+    output_format: &OutputFormat,
+) -> std::result::Result<String, OutputInvalid> {
+    let schema = match output_format {
+        OutputFormat::Text => return Ok(content.to_string()),
+        OutputFormat::JsonSchema(schema) => schema,
+    };
+    let invalid = |reason: String| OutputInvalid {
+        raw_text: content.to_string(),
+        reason,
+    };
+    let value = repair_json(content).ok_or_else(|| invalid("reply is not JSON".to_string()))?;
+    if let Some(schema) = schema {
+        validate_json_schema(&value, schema, "$").map_err(invalid)?;
+    }
+    serde_json::to_string_pretty(&value).map_err(|e| invalid(e.to_string()))
+}
+
+const PREAMBLE_PRECEDENCE_NOTE: &str =
+    "The instructions below are specific to this rewrite and take precedence over the general guidance above when they conflict.";
+
+pub fn rewrite_preview(
+    s: &settings::Settings,
+    system_prompt: &str,
+    asr_text: &str,
+) -> RewritePreview {
+    let preamble = settings::resolve_rewrite_system_preamble(s);
+    let include_glossary = s.rewrite_include_glossary.unwrap_or(true);
+    let glossary = if include_glossary {
+        s.rewrite_glossary.clone().unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    let policy = RewriteContextPolicy {
+        include_glossary,
+        ..RewriteContextPolicy::default()
+    };
+    // An invalid schema surfaces when rewriting; the preview just shows text mode.
+    let output_format = OutputFormat::from_settings(s, None).unwrap_or_default();
+    RewritePreview {
+        system_prompt: build_system_text(preamble.as_deref(), system_prompt, &output_format, None),
+        user_text: build_rewrite_user_text(asr_text, None, &glossary, &policy),
+        has_preamble: preamble.is_some(),
+    }
+}
+
+// Order: preamble (global house style), then the prompt itself, then the
+// output format and injection guard. Later sections win, so neither can be
+// overridden by a user-authored part.
+fn build_system_text(
+    preamble: Option<&str>,
+    system_prompt: &str,
+    output_format: &OutputFormat,
+    ctx: Option<&PreparedContext>,
+) -> String {
+    let prompt = system_prompt.trim_end();
+    let mut out = match preamble.map(str::trim).filter(|p| !p.is_empty()) {
+        // Prompts that already start with the preamble are not doubled up.
+        Some(p) if !prompt.trim_start().starts_with(p) => {
+            format!("{p}\n\n{PREAMBLE_PRECEDENCE_NOTE}\n\n{prompt}")
+        }
+        _ => system_prompt.to_string(),
+    };
+    match output_format {
+        OutputFormat::Text => {}
+        OutputFormat::JsonSchema(None) => {
+            out = format!("{}\n\n{JSON_OUTPUT_INSTRUCTION}", out.trim_end());
+        }
+        OutputFormat::JsonSchema(Some(schema)) => {
+            out = format!(
+                "{}\n\n{JSON_OUTPUT_INSTRUCTION} It must match this JSON Schema:\n{schema}",
+                out.trim_end()
+            );
+        }
+    }
+    if ctx.is_some_and(|c| c.injection_guard) {
+        out = format!(
+            "{}\n\n{}",
+            out.trim_end(),
+            context_pack::INJECTION_GUARD_INSTRUCTION
+        );
+    }
+    out
+}
+
 fn build_rewrite_glossary_section(rewrite_glossary: &[String]) -> Option<String> {
     if rewrite_glossary.is_empty() {
         return None;
@@ -660,7 +1030,38 @@ fn build_user_content(
 #[cfg(test)]
 mod tests {
     use super::api_key_status;
+    use super::build_system_text;
+    use super::enforce_output_format;
     use super::normalize_base_url;
+    use super::post_chat;
+    use super::redact_secret;
+    use super::redact_url_credentials;
+    use super::rewrite_preview;
+    use super::ChatReq;
+    use super::ChatSendError;
+    use super::OutputFormat;
+    use crate::cancel::mock;
+    use crate::context_pack::{self, PreparedContext};
+    use crate::settings;
+    use std::time::{Duration, Instant};
+    use tokio_util::sync::CancellationToken;
+
+    #[test]
+    fn captures_redact_echoed_keys_and_url_credentials() {
+        assert_eq!(
+            redact_secret("{\"echo\":\"sk-test-123456\"}", "sk-test-123456"),
+            "{\"echo\":\"<redacted>\"}"
+        );
+        assert_eq!(redact_secret("short key kept", "short"), "short key kept");
+        assert_eq!(
+            redact_url_credentials("https://user:pw@llm.local/v1/chat/completions"),
+            "https://<redacted>@llm.local/v1/chat/completions"
+        );
+        assert_eq!(
+            redact_url_credentials("https://llm.local/v1/a@b"),
+            "https://llm.local/v1/a@b"
+        );
+    }
 
     #[test]
     fn normalize_base_url_handles_empty_and_endpoint_suffix() {
@@ -687,4 +1088,150 @@ mod tests {
         assert_eq!(st.source, "env");
         std::env::remove_var("TYPEVOICE_LLM_API_KEY");
     }
+
+    #[test]
+    fn build_system_text_appends_guard_instruction_only_when_enabled() {
+        let mut ctx = PreparedContext {
+            user_text: String::new(),
+            screenshot: None,
+            injection_guard: true,
+            stripped_injections: 0,
+        };
+        let guarded = build_system_text(None, "rewrite it\n", &OutputFormat::Text, Some(&ctx));
+        assert!(guarded.starts_with("rewrite it\n\n"));
+        assert!(guarded.ends_with(context_pack::INJECTION_GUARD_INSTRUCTION));
+
+        ctx.injection_guard = false;
+        assert_eq!(
+            build_system_text(None, "rewrite it", &OutputFormat::Text, Some(&ctx)),
+            "rewrite it"
+        );
+        assert_eq!(
+            build_system_text(None, "rewrite it", &OutputFormat::Text, None),
+            "rewrite it"
+        );
+    }
+
+    #[test]
+    fn build_system_text_puts_preamble_first_and_guard_last() {
+        let ctx = PreparedContext {
+            user_text: String::new(),
+            screenshot: None,
+            injection_guard: true,
+            stripped_injections: 0,
+        };
+        let text = build_system_text(
+            Some(" Use zh-CN. "),
+            "rewrite it",
+            &OutputFormat::Text,
+            Some(&ctx),
+        );
+        let preamble_at = text.find("Use zh-CN.").expect("preamble");
+        let prompt_at = text.find("rewrite it").expect("prompt");
+        let guard_at = text
+            .find(context_pack::INJECTION_GUARD_INSTRUCTION)
+            .expect("guard");
+        assert!(preamble_at < prompt_at && prompt_at < guard_at);
+
+        let once = build_system_text(
+            Some("Use zh-CN."),
+            "Use zh-CN.\nrewrite it",
+            &OutputFormat::Text,
+            None,
+        );
+        assert_eq!(once, "Use zh-CN.\nrewrite it");
+        assert_eq!(
+            build_system_text(Some("  "), "rewrite it", &OutputFormat::Text, None),
+            "rewrite it"
+        );
+    }
+
+    #[test]
+    fn rewrite_preview_shows_preamble_and_glossary() {
+        let s = settings::Settings {
+            rewrite_system_preamble: Some("Keep product names.".to_string()),
+            rewrite_glossary: Some(vec!["TypeVoice".to_string()]),
+            ..Default::default()
+        };
+        let preview = rewrite_preview(&s, "rewrite it", "hello");
+        assert!(preview.has_preamble);
+        assert!(preview.system_prompt.starts_with("Keep product names."));
+        assert!(preview.user_text.starts_with("### TRANSCRIPT\nhello"));
+        assert!(preview.user_text.contains("- TypeVoice"));
+    }
+
+    #[test]
+    fn enforce_output_format_repairs_fenced_json() {
+        let format = OutputFormat::JsonSchema(Some(serde_json::json!({
+            "type": "object",
+            "required": ["name", "priority"],
+            "properties": {
+                "name": {"type": "string"},
+                "priority": {"enum": ["low", "high"]},
+            },
+            "additionalProperties": false,
+        })));
+        let out = enforce_output_format(
+            "Here you go:\n```json\n{\"name\": \"Ann\", \"priority\": \"high\"}\n```",
+            &format,
+        )
+        .expect("valid");
+        let v: serde_json::Value = serde_json::from_str(&out).expect("json");
+        assert_eq!(v["name"], "Ann");
+
+        let err = enforce_output_format(r#"{"name": "Ann", "priority": "urgent"}"#, &format)
+            .expect_err("enum");
+        assert!(err.reason.contains("$.priority"));
+        assert_eq!(err.raw_text, r#"{"name": "Ann", "priority": "urgent"}"#);
+        assert!(enforce_output_format(r#"{"name": "Ann"}"#, &format).is_err());
+        assert!(enforce_output_format("not json at all", &OutputFormat::JsonSchema(None)).is_err());
+        assert_eq!(
+            enforce_output_format("plain", &OutputFormat::Text).expect("text"),
+            "plain"
+        );
+    }
+
+    #[test]
+    fn output_format_from_settings_rejects_bad_schema() {
+        let s = settings::Settings {
+            rewrite_output_format: Some("json_schema".to_string()),
+            rewrite_output_json_schema: Some("{not json".to_string()),
+            ..Default::default()
+        };
+        let err = OutputFormat::from_settings(&s, None).expect_err("bad schema");
+        assert!(err
+            .to_string()
+            .starts_with("E_SETTINGS_REWRITE_OUTPUT_SCHEMA_INVALID"));
+        assert_eq!(
+            OutputFormat::from_settings(&settings::Settings::default(), None).expect("text"),
+            OutputFormat::Text
+        );
+    }
+
+    #[test]
+    fn cancel_closes_a_pending_chat_request() {
+        let (url, closed) = mock::stalled_server();
+        let client = reqwest::Client::new();
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        let cancelled_at = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            canceller.cancel();
+            Instant::now()
+        });
+        let req = ChatReq {
+            model: "m".to_string(),
+            messages: vec![],
+            temperature: 0.2,
+            reasoning_effort: None,
+            response_format: None,
+        };
+        let got = mock::block_on(post_chat(&client, &url, "key", &req, &token));
+        assert!(matches!(got, Err(ChatSendError::Cancelled)));
+        let cancelled_at = cancelled_at.join().expect("canceller");
+        let closed_at = closed
+            .recv_timeout(Duration::from_secs(2))
+            .expect("socket closed");
+        assert!(closed_at.saturating_duration_since(cancelled_at) < Duration::from_millis(200));
+    }
 }
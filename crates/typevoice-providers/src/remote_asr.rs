@@ -1,14 +1,29 @@
-use std::path::Path;
-use std::time::Instant;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use anyhow::{anyhow, Result};
-use reqwest::{multipart, Client};
+use anyhow::Result;
+use futures_util::Stream;
+use reqwest::{multipart, Body, Client};
 use serde::Deserialize;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tokio::task::JoinSet;
 use tokio_util::sync::CancellationToken;
 
+use crate::asr_lanes;
+use crate::asr_warm_pool;
+use crate::atomic_file;
+use crate::cancel;
+use crate::http_client;
 use crate::llm::ApiKeyStatus;
-use crate::obs::Span;
+use crate::obs::{debug, Span};
+use crate::secret_store;
+use crate::write_gate;
+
+pub use crate::asr_lanes::AsrLane;
 
 const KEYRING_SERVICE: &str = "typevoice";
 const KEYRING_USER: &str = "remote_asr_api_key";
@@ -16,12 +31,32 @@ const API_KEY_ENV: &str = "TYPEVOICE_REMOTE_ASR_API_KEY";
 const DEFAULT_SLICE_SEC: f64 = 60.0;
 const DEFAULT_OVERLAP_SEC: f64 = 0.5;
 const MAX_DEDUPE_CHARS: usize = 64;
+const WARM_SAMPLES: usize = 8_000;
+// Transient slice failures (network, 408, 429, 5xx) are retried with doubling
+// backoff without restarting the other slices.
+const SLICE_MAX_ATTEMPTS: u32 = 3;
+const SLICE_BACKOFF_BASE: Duration = Duration::from_millis(500);
+// Slices stream from the recording on disk in chunks of this size. Chunks read
+// but not yet taken by a connection share one budget per run, so memory stays
+// flat however long the recording is.
+const UPLOAD_CHUNK_BYTES: usize = 256 * 1024;
+const MAX_IN_FLIGHT_BYTES: usize = 2 * 1024 * 1024;
+// Finished slices of a run that failed transiently, keyed by the recording's
+// path, size and mtime plus the slice range, so repeating the run on the same
+// kept recording only uploads what is missing. Any other end of a run clears
+// them; leftovers of runs never repeated expire like the rewrite cache.
+const RESUME_DIR: &str = "remote_asr_resume";
+const RESUME_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
 
 #[derive(Debug, Clone)]
 pub struct RemoteAsrConfig {
     pub url: String,
     pub model: Option<String>,
     pub concurrency: usize,
+    // Batch runs yield to dictation before every slice.
+    pub lane: AsrLane,
+    // Keep finished slices of a failed run on disk; see RESUME_DIR.
+    pub resume: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -33,12 +68,17 @@ pub struct RemoteAsrMetrics {
     pub concurrency_used: usize,
     pub model_id: String,
     pub model_version: Option<String>,
+    // Slices taken from an earlier failed run instead of uploaded again.
+    pub resumed_slices: usize,
+    pub retried_uploads: usize,
 }
 
 #[derive(Debug, Clone)]
 pub struct RemoteAsrOutput {
     pub text: String,
     pub metrics: RemoteAsrMetrics,
+    // Parsed response body per slice, kept for the debug artifact.
+    pub raw_responses: Vec<serde_json::Value>,
 }
 
 #[derive(Debug, Clone)]
@@ -61,15 +101,39 @@ struct WavInfo {
     sample_rate: u32,
     bits_per_sample: u16,
     block_align: u16,
-    data_offset: usize,
-    data_len: usize,
+    data_offset: u64,
+    data_len: u64,
     duration_seconds: f64,
 }
 
 #[derive(Debug, Clone)]
 struct SliceRequest {
     index: usize,
-    wav_bytes: Vec<u8>,
+    body: SliceBody,
+}
+
+#[derive(Debug, Clone)]
+enum SliceBody {
+    // Short clips built in memory: key check, warm-up, live preview.
+    Wav(Vec<u8>),
+    File(FileRange),
+}
+
+// Part of the recording's data chunk, sent behind its own WAV header.
+#[derive(Debug, Clone)]
+struct FileRange {
+    path: PathBuf,
+    offset: u64,
+    len: u64,
+    header: Vec<u8>,
+    budget: Arc<Semaphore>,
+}
+
+struct SliceDone {
+    index: usize,
+    text: String,
+    raw: serde_json::Value,
+    resumed: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -84,16 +148,25 @@ fn err(code: &str, message: impl Into<String>) -> RemoteAsrError {
     }
 }
 
+fn client_err(e: anyhow::Error) -> RemoteAsrError {
+    let msg = e.to_string();
+    let code = if msg.starts_with("E_TLS_CONFIG") {
+        "E_TLS_CONFIG"
+    } else if msg.starts_with("E_OFFLINE_MODE") {
+        "E_OFFLINE_MODE"
+    } else {
+        "E_REMOTE_ASR_HTTP_CLIENT"
+    };
+    err(code, msg)
+}
+
 pub fn set_api_key(key: &str) -> Result<()> {
-    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).map_err(|e| anyhow!("{e:?}"))?;
-    entry.set_password(key).map_err(|e| anyhow!("{e:?}"))?;
+    secret_store::set(KEYRING_SERVICE, KEYRING_USER, key)?;
     Ok(())
 }
 
 pub fn clear_api_key() -> Result<()> {
-    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).map_err(|e| anyhow!("{e:?}"))?;
-    let _ = entry.set_password("").map_err(|e| anyhow!("{e:?}"));
-    Ok(())
+    secret_store::clear(KEYRING_SERVICE, KEYRING_USER)
 }
 
 pub fn api_key_status() -> ApiKeyStatus {
@@ -106,33 +179,7 @@ pub fn api_key_status() -> ApiKeyStatus {
             };
         }
     }
-    let entry = match keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER) {
-        Ok(v) => v,
-        Err(e) => {
-            return ApiKeyStatus {
-                configured: false,
-                source: "keyring".to_string(),
-                reason: Some(format!("keyring_entry_init_failed:{e:?}")),
-            };
-        }
-    };
-    match entry.get_password() {
-        Ok(k) if !k.trim().is_empty() => ApiKeyStatus {
-            configured: true,
-            source: "keyring".to_string(),
-            reason: None,
-        },
-        Ok(_) => ApiKeyStatus {
-            configured: false,
-            source: "keyring".to_string(),
-            reason: Some("empty".to_string()),
-        },
-        Err(e) => ApiKeyStatus {
-            configured: false,
-            source: "keyring".to_string(),
-            reason: Some(format!("keyring_get_failed:{e:?}")),
-        },
-    }
+    secret_store::status(KEYRING_SERVICE, KEYRING_USER)
 }
 
 pub async fn check_api_key_live(cfg: &RemoteAsrConfig) -> Result<(), RemoteAsrError> {
@@ -142,17 +189,18 @@ pub async fn check_api_key_live(cfg: &RemoteAsrConfig) -> Result<(), RemoteAsrEr
     }
 
     let key = load_api_key()?;
-    let client = Client::new();
+    let client = crate::data_dir::data_dir()
+        .and_then(|dir| http_client::client(&dir))
+        .map_err(client_err)?;
     let token = CancellationToken::new();
     let sample_count = 1_600usize;
     let pcm = vec![0_u8; sample_count * 2];
-    let wav_bytes = build_wav_bytes(&pcm, 1, 16_000, 16, 2);
     let slice = SliceRequest {
         index: 0,
-        wav_bytes,
+        body: SliceBody::Wav(build_wav_bytes(&pcm, 1, 16_000, 16, 2)),
     };
 
-    transcribe_one_slice(&client, url, &key, cfg.model.as_deref(), slice, &token)
+    transcribe_one_slice(&client, url, &key, cfg.model.as_deref(), &slice, &token)
         .await
         .map(|_| ())
 }
@@ -163,25 +211,17 @@ fn load_api_key() -> Result<String, RemoteAsrError> {
             return Ok(v);
         }
     }
-    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).map_err(|e| {
-        err(
-            "E_REMOTE_ASR_API_KEY_MISSING",
-            format!("keyring init failed: {e:?}"),
-        )
-    })?;
-    let v = entry.get_password().map_err(|e| {
-        err(
-            "E_REMOTE_ASR_API_KEY_MISSING",
-            format!("keyring get failed: {e:?}"),
-        )
-    })?;
-    if v.trim().is_empty() {
-        return Err(err(
+    match secret_store::get(KEYRING_SERVICE, KEYRING_USER) {
+        Ok((Some(v), _)) => Ok(v),
+        Ok((None, _)) => Err(err(
             "E_REMOTE_ASR_API_KEY_MISSING",
             "remote ASR API key is empty",
-        ));
+        )),
+        Err(e) => Err(err(
+            "E_REMOTE_ASR_API_KEY_MISSING",
+            format!("secret store read failed: {e}"),
+        )),
     }
-    Ok(v)
 }
 
 pub async fn transcribe_remote(
@@ -205,7 +245,16 @@ pub async fn transcribe_remote(
         })),
     );
 
-    let out = transcribe_remote_inner(wav_path, token, cfg).await;
+    let out = transcribe_remote_inner(data_dir, wav_path, token, cfg).await;
+    if let Ok(v) = &out {
+        if debug::verbose_enabled() && debug::include_asr_segments() {
+            let bytes = serde_json::to_vec_pretty(&v.raw_responses).unwrap_or_default();
+            let _ = debug::write_payload_best_effort(data_dir, task_id, "asr_response.json", bytes);
+        }
+    }
+    if out.is_ok() {
+        asr_warm_pool::note_used(cfg.model.as_deref());
+    }
     match &out {
         Ok(v) => span.ok(Some(serde_json::json!({
             "slice_count": v.metrics.slice_count,
@@ -213,19 +262,61 @@ pub async fn transcribe_remote(
             "elapsed_ms": v.metrics.elapsed_ms,
             "rtf": v.metrics.rtf,
             "audio_seconds": v.metrics.audio_seconds,
+            "resumed_slices": v.metrics.resumed_slices,
+            "retried_uploads": v.metrics.retried_uploads,
         }))),
         Err(e) => span.err("remote", &e.code, &e.message, None),
     }
     out
 }
 
+// Half a second of silence as one request, enough for the server to load
+// `model`. Returns how long the server took.
+pub async fn warm_model(
+    data_dir: &Path,
+    url: &str,
+    model: Option<&str>,
+) -> Result<i64, RemoteAsrError> {
+    let started = Instant::now();
+    transcribe_pcm_chunk(
+        data_dir,
+        url,
+        model,
+        &vec![0u8; WARM_SAMPLES * 2],
+        &CancellationToken::new(),
+    )
+    .await?;
+    Ok(started.elapsed().as_millis() as i64)
+}
+
+// One request for a short 16 kHz mono s16le chunk, without slicing or retries;
+// the live preview while recording sends its chunks through here.
+pub async fn transcribe_pcm_chunk(
+    data_dir: &Path,
+    url: &str,
+    model: Option<&str>,
+    pcm: &[u8],
+    token: &CancellationToken,
+) -> Result<String, RemoteAsrError> {
+    let key = load_api_key()?;
+    let client = http_client::client(data_dir).map_err(client_err)?;
+    let slice = SliceRequest {
+        index: 0,
+        body: SliceBody::Wav(build_wav_bytes(pcm, 1, 16_000, 16, 2)),
+    };
+    let (_, text, _) =
+        transcribe_one_slice(&client, url.trim(), &key, model, &slice, token).await?;
+    Ok(text)
+}
+
 async fn transcribe_remote_inner(
+    data_dir: &Path,
     wav_path: &Path,
     token: &CancellationToken,
     cfg: &RemoteAsrConfig,
 ) -> Result<RemoteAsrOutput, RemoteAsrError> {
     if token.is_cancelled() {
-        return Err(err("E_CANCELLED", "cancelled"));
+        return Err(err(cancel::CANCELLED_CODE, "cancelled"));
     }
     let url = cfg.url.trim();
     if url.is_empty() {
@@ -239,11 +330,24 @@ async fn transcribe_remote_inner(
     }
 
     let key = load_api_key()?;
-    let bytes = tokio::fs::read(wav_path)
+    // Only the header is read here; slices stream from the file as they upload.
+    let path = wav_path.to_path_buf();
+    let wav = tokio::task::spawn_blocking(move || read_wav_info(&path))
         .await
-        .map_err(|e| err("E_REMOTE_ASR_WAV_READ", format!("read wav failed: {e}")))?;
-    let wav = parse_wav(&bytes)?;
-    let slices = build_slice_requests(&bytes, &wav, DEFAULT_SLICE_SEC, DEFAULT_OVERLAP_SEC)?;
+        .map_err(|e| {
+            err(
+                "E_REMOTE_ASR_INTERNAL",
+                format!("wav header read failed: {e}"),
+            )
+        })??;
+    let budget = Arc::new(Semaphore::new(MAX_IN_FLIGHT_BYTES));
+    let slices = build_slice_requests(
+        wav_path,
+        &wav,
+        DEFAULT_SLICE_SEC,
+        DEFAULT_OVERLAP_SEC,
+        &budget,
+    );
     if slices.is_empty() {
         return Err(err(
             "E_REMOTE_ASR_WAV_UNSUPPORTED",
@@ -251,44 +355,140 @@ async fn transcribe_remote_inner(
         ));
     }
 
-    let client = Client::new();
+    // Guest mode and a read-only data dir keep nothing, like every other store.
+    let resume = cfg.resume && write_gate::check(&data_dir.join(RESUME_DIR)).is_ok();
+    if resume {
+        prune_resumed(data_dir);
+    }
+    let keys: Vec<Option<String>> = slices
+        .iter()
+        .map(|slice| {
+            resume
+                .then(|| resume_key(slice, url, cfg.model.as_deref()))
+                .flatten()
+        })
+        .collect();
+    let client = http_client::client(data_dir).map_err(client_err)?;
     let concurrency_used = cfg.concurrency.min(slices.len()).max(1);
     let mut parts = vec![String::new(); slices.len()];
+    let mut raw_responses = vec![serde_json::Value::Null; slices.len()];
+    let retries = Arc::new(AtomicUsize::new(0));
     let mut set = JoinSet::new();
-    let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(concurrency_used));
+    let semaphore = Arc::new(Semaphore::new(concurrency_used));
     let started = Instant::now();
 
     for slice in slices {
+        let data_dir2 = data_dir.to_path_buf();
+        let retries2 = retries.clone();
         let client2 = client.clone();
         let key2 = key.clone();
         let model2 = cfg.model.clone();
         let url2 = url.to_string();
         let token2 = token.clone();
         let semaphore2 = semaphore.clone();
+        let lane = cfg.lane;
+        let resume_key = keys[slice.index].clone();
         set.spawn(async move {
             let _permit = semaphore2
                 .acquire_owned()
                 .await
                 .map_err(|_| err("E_REMOTE_ASR_INTERNAL", "semaphore closed"))?;
+            if lane == AsrLane::Batch {
+                asr_lanes::global().batch_turn().await;
+            }
             if token2.is_cancelled() {
-                return Err(err("E_CANCELLED", "cancelled"));
+                return Err(err(cancel::CANCELLED_CODE, "cancelled"));
             }
-            transcribe_one_slice(&client2, &url2, &key2, model2.as_deref(), slice, &token2).await
+            if let Some((text, raw)) = resume_key
+                .as_deref()
+                .and_then(|key| load_resumed(&data_dir2, key))
+            {
+                return Ok(SliceDone {
+                    index: slice.index,
+                    text,
+                    raw,
+                    resumed: true,
+                });
+            }
+            let (index, text, raw) = transcribe_slice_with_retry(
+                &client2,
+                &url2,
+                &key2,
+                model2.as_deref(),
+                &slice,
+                &token2,
+                &retries2,
+            )
+            .await?;
+            if let Some(key) = &resume_key {
+                store_resumed(&data_dir2, key, &text, &raw);
+            }
+            Ok(SliceDone {
+                index,
+                text,
+                raw,
+                resumed: false,
+            })
         });
     }
 
+    let joined = join_slices(&mut set, token, &mut parts, &mut raw_responses).await;
+    // Only a transient failure keeps finished slices for a repeated run;
+    // success, cancel and permanent errors end the task's use of them.
+    let keep = matches!(&joined, Err(e) if is_retryable_slice_error(&e.code));
+    if !keep {
+        for key in keys.iter().flatten() {
+            let _ = std::fs::remove_file(resume_path(data_dir, key));
+        }
+    }
+    let resumed_slices = joined?;
+    let text = merge_slices(&parts);
+    let elapsed_ms = started.elapsed().as_millis() as i64;
+    let audio_seconds = wav.duration_seconds;
+    let rtf = (elapsed_ms as f64 / 1000.0) / audio_seconds.max(1e-6);
+    Ok(RemoteAsrOutput {
+        text,
+        metrics: RemoteAsrMetrics {
+            audio_seconds,
+            elapsed_ms,
+            rtf,
+            slice_count: parts.len(),
+            concurrency_used,
+            model_id: cfg
+                .model
+                .clone()
+                .unwrap_or_else(|| "remote/transcribe".to_string()),
+            model_version: None,
+            resumed_slices,
+            retried_uploads: retries.load(Ordering::Relaxed),
+        },
+        raw_responses,
+    })
+}
+
+// Collects every slice into `parts`; the first failure or a cancel aborts the
+// rest. Returns how many slices came from the resume cache.
+async fn join_slices(
+    set: &mut JoinSet<Result<SliceDone, RemoteAsrError>>,
+    token: &CancellationToken,
+    parts: &mut [String],
+    raw_responses: &mut [serde_json::Value],
+) -> Result<usize, RemoteAsrError> {
+    let mut resumed_slices = 0usize;
     let mut completed = 0usize;
     while completed < parts.len() {
         let next = tokio::select! {
             _ = token.cancelled() => {
                 set.abort_all();
-                return Err(err("E_CANCELLED", "cancelled"));
+                return Err(err(cancel::CANCELLED_CODE, "cancelled"));
             }
             v = set.join_next() => v
         };
         match next {
-            Some(Ok(Ok((index, text)))) => {
-                parts[index] = text;
+            Some(Ok(Ok(done))) => {
+                parts[done.index] = done.text;
+                raw_responses[done.index] = done.raw;
+                resumed_slices += usize::from(done.resumed);
                 completed += 1;
             }
             Some(Ok(Err(e))) => {
@@ -305,7 +505,6 @@ async fn transcribe_remote_inner(
             None => break,
         }
     }
-
     if completed != parts.len() {
         return Err(err(
             "E_REMOTE_ASR_INTERNAL",
@@ -315,26 +514,44 @@ async fn transcribe_remote_inner(
             ),
         ));
     }
+    Ok(resumed_slices)
+}
 
-    let text = merge_slices(&parts);
-    let elapsed_ms = started.elapsed().as_millis() as i64;
-    let audio_seconds = wav.duration_seconds;
-    let rtf = (elapsed_ms as f64 / 1000.0) / audio_seconds.max(1e-6);
-    Ok(RemoteAsrOutput {
-        text,
-        metrics: RemoteAsrMetrics {
-            audio_seconds,
-            elapsed_ms,
-            rtf,
-            slice_count: parts.len(),
-            concurrency_used,
-            model_id: cfg
-                .model
-                .clone()
-                .unwrap_or_else(|| "remote/transcribe".to_string()),
-            model_version: None,
-        },
-    })
+// Retries transient failures of one slice; the other slices keep going.
+async fn transcribe_slice_with_retry(
+    client: &Client,
+    url: &str,
+    key: &str,
+    model: Option<&str>,
+    slice: &SliceRequest,
+    token: &CancellationToken,
+    retries: &AtomicUsize,
+) -> Result<(usize, String, serde_json::Value), RemoteAsrError> {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match transcribe_one_slice(client, url, key, model, slice, token).await {
+            Err(e) if attempt < SLICE_MAX_ATTEMPTS && is_retryable_slice_error(&e.code) => {
+                retries.fetch_add(1, Ordering::Relaxed);
+                let backoff = SLICE_BACKOFF_BASE * 2u32.pow(attempt - 1);
+                if cancel::or_cancelled(token, tokio::time::sleep(backoff))
+                    .await
+                    .is_none()
+                {
+                    return Err(err(cancel::CANCELLED_CODE, "cancelled"));
+                }
+            }
+            out => return out,
+        }
+    }
+}
+
+fn is_retryable_slice_error(code: &str) -> bool {
+    code == "E_REMOTE_ASR_HTTP_SEND"
+        || code
+            .strip_prefix("E_REMOTE_ASR_HTTP_STATUS_")
+            .and_then(|s| s.parse::<u16>().ok())
+            .is_some_and(|s| s == 408 || s == 429 || s >= 500)
 }
 
 async fn transcribe_one_slice(
@@ -342,10 +559,18 @@ async fn transcribe_one_slice(
     url: &str,
     key: &str,
     model: Option<&str>,
-    slice: SliceRequest,
+    slice: &SliceRequest,
     token: &CancellationToken,
-) -> Result<(usize, String), RemoteAsrError> {
-    let part = multipart::Part::bytes(slice.wav_bytes)
+) -> Result<(usize, String, serde_json::Value), RemoteAsrError> {
+    // Rebuilt on every attempt: a streamed body can only be sent once.
+    let part = match &slice.body {
+        SliceBody::Wav(bytes) => multipart::Part::bytes(bytes.clone()),
+        SliceBody::File(range) => multipart::Part::stream_with_length(
+            Body::wrap_stream(file_range_stream(range.clone())),
+            range.header.len() as u64 + range.len,
+        ),
+    };
+    let part = part
         .file_name(format!("segment_{}.wav", slice.index))
         .mime_str("audio/wav")
         .map_err(|e| err("E_REMOTE_ASR_CONFIG", format!("invalid mime: {e}")))?;
@@ -357,59 +582,95 @@ async fn transcribe_one_slice(
         }
     }
 
-    let req = client
-        .post(url.to_string())
-        .bearer_auth(key)
-        .multipart(form)
-        .send();
-    let resp = tokio::select! {
-        _ = token.cancelled() => return Err(err("E_CANCELLED", "cancelled")),
-        v = req => v
-    }
-    .map_err(|e| err("E_REMOTE_ASR_HTTP_SEND", format!("request failed: {e}")))?;
-
-    let status = resp.status();
-    let body = resp
-        .text()
+    // The body read stays under the token too: a server that answers headers
+    // and then trickles the body must not outlive a cancel.
+    let exchange = async {
+        let resp = client
+            .post(url.to_string())
+            .bearer_auth(key)
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| {
+                err(
+                    http_client::send_error_code(&e, "E_REMOTE_ASR_HTTP_SEND"),
+                    format!("request failed: {e}"),
+                )
+            })?;
+        let status = resp.status();
+        let body = resp
+            .text()
+            .await
+            .map_err(|e| err("E_REMOTE_ASR_PARSE", format!("read response failed: {e}")))?;
+        Ok::<_, RemoteAsrError>((status, body))
+    };
+    let (status, body) = cancel::or_cancelled(token, exchange)
         .await
-        .map_err(|e| err("E_REMOTE_ASR_PARSE", format!("read response failed: {e}")))?;
+        .ok_or_else(|| err(cancel::CANCELLED_CODE, "cancelled"))??;
 
     if !status.is_success() {
         let code = format!("E_REMOTE_ASR_HTTP_STATUS_{}", status.as_u16());
         return Err(err(&code, body));
     }
 
-    let parsed: RemoteResp = serde_json::from_str(&body).map_err(|e| {
+    let raw: serde_json::Value = serde_json::from_str(&body).map_err(|e| {
+        err(
+            "E_REMOTE_ASR_PARSE",
+            format!("invalid json response: {e}; body={body}"),
+        )
+    })?;
+    let parsed: RemoteResp = serde_json::from_value(raw.clone()).map_err(|e| {
         err(
             "E_REMOTE_ASR_PARSE",
             format!("invalid json response: {e}; body={body}"),
         )
     })?;
     let text = parsed.text.unwrap_or_default().trim().to_string();
-    Ok((slice.index, text))
+    Ok((slice.index, text, raw))
+}
+
+fn read_wav_info(path: &Path) -> Result<WavInfo, RemoteAsrError> {
+    let mut file = std::fs::File::open(path).map_err(wav_read_err)?;
+    let len = file.metadata().map_err(wav_read_err)?.len();
+    parse_wav(&mut file, len)
+}
+
+fn wav_read_err(e: std::io::Error) -> RemoteAsrError {
+    err("E_REMOTE_ASR_WAV_READ", format!("read wav failed: {e}"))
 }
 
-fn parse_wav(bytes: &[u8]) -> Result<WavInfo, RemoteAsrError> {
-    if bytes.len() < 12 {
+fn read_at<R: Read + Seek>(r: &mut R, pos: u64, buf: &mut [u8]) -> Result<(), RemoteAsrError> {
+    r.seek(SeekFrom::Start(pos))
+        .and_then(|_| r.read_exact(buf))
+        .map_err(wav_read_err)
+}
+
+// Walks the chunk headers without loading the audio.
+fn parse_wav<R: Read + Seek>(r: &mut R, len: u64) -> Result<WavInfo, RemoteAsrError> {
+    if len < 12 {
         return Err(err("E_REMOTE_ASR_WAV_UNSUPPORTED", "wav header too short"));
     }
-    if &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+    let mut head = [0u8; 12];
+    read_at(r, 0, &mut head)?;
+    if &head[0..4] != b"RIFF" || &head[8..12] != b"WAVE" {
         return Err(err("E_REMOTE_ASR_WAV_UNSUPPORTED", "not a RIFF/WAVE file"));
     }
 
-    let mut pos = 12usize;
+    let mut pos = 12u64;
     let mut channels = None;
     let mut sample_rate = None;
     let mut bits_per_sample = None;
     let mut block_align = None;
     let mut data_offset = None;
     let mut data_len = None;
-    while pos + 8 <= bytes.len() {
-        let chunk_id = &bytes[pos..pos + 4];
-        let chunk_size = le_u32(bytes, pos + 4)? as usize;
+    while pos + 8 <= len {
+        let mut chunk = [0u8; 8];
+        read_at(r, pos, &mut chunk)?;
+        let chunk_id = &chunk[0..4];
+        let chunk_size = le_u32(&chunk, 4)? as u64;
         let data_start = pos + 8;
         let data_end = data_start.saturating_add(chunk_size);
-        if data_end > bytes.len() {
+        if data_end > len {
             return Err(err(
                 "E_REMOTE_ASR_WAV_UNSUPPORTED",
                 "wav chunk out of bounds",
@@ -420,11 +681,13 @@ fn parse_wav(bytes: &[u8]) -> Result<WavInfo, RemoteAsrError> {
             if chunk_size < 16 {
                 return Err(err("E_REMOTE_ASR_WAV_UNSUPPORTED", "fmt chunk too short"));
             }
-            let audio_format = le_u16(bytes, data_start)?;
-            let ch = le_u16(bytes, data_start + 2)?;
-            let sr = le_u32(bytes, data_start + 4)?;
-            let ba = le_u16(bytes, data_start + 12)?;
-            let bps = le_u16(bytes, data_start + 14)?;
+            let mut fmt = [0u8; 16];
+            read_at(r, data_start, &mut fmt)?;
+            let audio_format = le_u16(&fmt, 0)?;
+            let ch = le_u16(&fmt, 2)?;
+            let sr = le_u32(&fmt, 4)?;
+            let ba = le_u16(&fmt, 12)?;
+            let bps = le_u16(&fmt, 14)?;
             if audio_format != 1 {
                 return Err(err(
                     "E_REMOTE_ASR_WAV_UNSUPPORTED",
@@ -471,7 +734,7 @@ fn parse_wav(bytes: &[u8]) -> Result<WavInfo, RemoteAsrError> {
             "block_align must be > 0",
         ));
     }
-    let bytes_per_sec = sample_rate as usize * block_align as usize;
+    let bytes_per_sec = sample_rate as u64 * block_align as u64;
     if bytes_per_sec == 0 {
         return Err(err(
             "E_REMOTE_ASR_WAV_UNSUPPORTED",
@@ -491,13 +754,14 @@ fn parse_wav(bytes: &[u8]) -> Result<WavInfo, RemoteAsrError> {
 }
 
 fn build_slice_requests(
-    source: &[u8],
+    path: &Path,
     wav: &WavInfo,
     slice_sec: f64,
     overlap_sec: f64,
-) -> Result<Vec<SliceRequest>, RemoteAsrError> {
+    budget: &Arc<Semaphore>,
+) -> Vec<SliceRequest> {
     if wav.duration_seconds <= 0.0 {
-        return Ok(vec![]);
+        return vec![];
     }
     let mut out = Vec::new();
     let mut index = 0usize;
@@ -514,63 +778,207 @@ fn build_slice_requests(
         } else {
             (base_end + overlap_sec).min(wav.duration_seconds)
         };
-        let data = extract_segment_pcm(source, wav, start, end)?;
-        if !data.is_empty() {
-            let wav_bytes = build_wav_bytes(
-                &data,
+        if let Some((offset, len)) = segment_range(wav, start, end) {
+            let header = wav_header(
+                len as u32,
                 wav.channels,
                 wav.sample_rate,
                 wav.bits_per_sample,
                 wav.block_align,
             );
-            out.push(SliceRequest { index, wav_bytes });
+            out.push(SliceRequest {
+                index,
+                body: SliceBody::File(FileRange {
+                    path: path.to_path_buf(),
+                    offset,
+                    len,
+                    header,
+                    budget: budget.clone(),
+                }),
+            });
         }
         index += 1;
         base_start += slice_sec;
     }
-    Ok(out)
+    out
 }
 
-fn extract_segment_pcm(
-    source: &[u8],
-    wav: &WavInfo,
-    start_sec: f64,
-    end_sec: f64,
-) -> Result<Vec<u8>, RemoteAsrError> {
+// File offset and length of the data bytes between two timestamps.
+fn segment_range(wav: &WavInfo, start_sec: f64, end_sec: f64) -> Option<(u64, u64)> {
     if end_sec <= start_sec {
-        return Ok(Vec::new());
-    }
-    let samples_start = (start_sec * wav.sample_rate as f64).floor().max(0.0) as usize;
-    let samples_end = (end_sec * wav.sample_rate as f64).ceil().max(0.0) as usize;
-    let mut byte_start = samples_start.saturating_mul(wav.block_align as usize);
-    let mut byte_end = samples_end.saturating_mul(wav.block_align as usize);
-    byte_start = byte_start.min(wav.data_len);
-    byte_end = byte_end.min(wav.data_len);
+        return None;
+    }
+    let samples_start = (start_sec * wav.sample_rate as f64).floor().max(0.0) as u64;
+    let samples_end = (end_sec * wav.sample_rate as f64).ceil().max(0.0) as u64;
+    let byte_start = samples_start
+        .saturating_mul(wav.block_align as u64)
+        .min(wav.data_len);
+    let byte_end = samples_end
+        .saturating_mul(wav.block_align as u64)
+        .min(wav.data_len);
     if byte_end <= byte_start {
-        return Ok(Vec::new());
+        return None;
     }
-    let abs_start = wav.data_offset + byte_start;
-    let abs_end = wav.data_offset + byte_end;
-    if abs_end > source.len() || abs_start > abs_end {
-        return Err(err(
-            "E_REMOTE_ASR_WAV_UNSUPPORTED",
-            "segment range out of bounds",
-        ));
+    Some((wav.data_offset + byte_start, byte_end - byte_start))
+}
+
+// Yields the header, then the range in chunks. Each chunk holds its share of
+// the run's budget until the connection polls for the next one.
+fn file_range_stream(range: FileRange) -> impl Stream<Item = std::io::Result<Vec<u8>>> {
+    let reader = RangeReader {
+        range,
+        file: None,
+        sent: 0,
+        header_sent: false,
+        held: None,
+        failed: false,
+    };
+    futures_util::stream::unfold(reader, |mut reader| async move {
+        reader.held = None;
+        if !reader.header_sent {
+            reader.header_sent = true;
+            return Some((Ok(reader.range.header.clone()), reader));
+        }
+        let remaining = reader.range.len - reader.sent;
+        if reader.failed || remaining == 0 {
+            return None;
+        }
+        let n = (remaining as usize).min(UPLOAD_CHUNK_BYTES);
+        match reader.next_chunk(n).await {
+            Ok(chunk) => {
+                reader.sent += n as u64;
+                Some((Ok(chunk), reader))
+            }
+            Err(e) => {
+                reader.failed = true;
+                Some((Err(e), reader))
+            }
+        }
+    })
+}
+
+struct RangeReader {
+    range: FileRange,
+    file: Option<tokio::fs::File>,
+    sent: u64,
+    header_sent: bool,
+    held: Option<OwnedSemaphorePermit>,
+    failed: bool,
+}
+
+impl RangeReader {
+    async fn next_chunk(&mut self, n: usize) -> std::io::Result<Vec<u8>> {
+        let permit = self
+            .range
+            .budget
+            .clone()
+            .acquire_many_owned(n as u32)
+            .await
+            .map_err(std::io::Error::other)?;
+        if self.file.is_none() {
+            self.file = Some(open_range(&self.range).await?);
+        }
+        let mut buf = vec![0u8; n];
+        if let Some(file) = self.file.as_mut() {
+            file.read_exact(&mut buf).await?;
+        }
+        self.held = Some(permit);
+        Ok(buf)
     }
-    Ok(source[abs_start..abs_end].to_vec())
 }
 
-fn build_wav_bytes(
-    pcm_data: &[u8],
+async fn open_range(range: &FileRange) -> std::io::Result<tokio::fs::File> {
+    let mut file = tokio::fs::File::open(&range.path).await?;
+    file.seek(SeekFrom::Start(range.offset)).await?;
+    Ok(file)
+}
+
+// Same slice of the same recording, endpoint and model gives the same key
+// across runs. File slices key on the recording's path, size and mtime instead
+// of its bytes, so the audio is read once, by the upload; None when the file
+// cannot be stat'ed.
+fn resume_key(slice: &SliceRequest, url: &str, model: Option<&str>) -> Option<String> {
+    let mut ctx = ring::digest::Context::new(&ring::digest::SHA256);
+    ctx.update(url.as_bytes());
+    ctx.update(&[0]);
+    ctx.update(model.unwrap_or_default().trim().as_bytes());
+    ctx.update(&[0]);
+    match &slice.body {
+        SliceBody::Wav(bytes) => ctx.update(bytes),
+        SliceBody::File(range) => {
+            let meta = std::fs::metadata(&range.path).ok()?;
+            let mtime = meta
+                .modified()
+                .ok()?
+                .duration_since(std::time::UNIX_EPOCH)
+                .ok()?
+                .as_nanos();
+            ctx.update(range.path.to_string_lossy().as_bytes());
+            ctx.update(&[0]);
+            ctx.update(&meta.len().to_le_bytes());
+            ctx.update(&mtime.to_le_bytes());
+            ctx.update(&range.offset.to_le_bytes());
+            ctx.update(&range.len.to_le_bytes());
+            ctx.update(&range.header);
+        }
+    }
+    Some(
+        ctx.finish()
+            .as_ref()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect(),
+    )
+}
+
+fn resume_path(data_dir: &Path, hash: &str) -> PathBuf {
+    data_dir.join(RESUME_DIR).join(format!("{hash}.json"))
+}
+
+fn load_resumed(data_dir: &Path, hash: &str) -> Option<(String, serde_json::Value)> {
+    let bytes = std::fs::read(resume_path(data_dir, hash)).ok()?;
+    let v: serde_json::Value = serde_json::from_slice(&bytes).ok()?;
+    let text = v.get("text")?.as_str()?.to_string();
+    Some((text, v.get("raw").cloned().unwrap_or_default()))
+}
+
+// Best effort: a slice that cannot be saved is uploaded again on a retry.
+fn store_resumed(data_dir: &Path, hash: &str, text: &str, raw: &serde_json::Value) {
+    let path = resume_path(data_dir, hash);
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let body = serde_json::json!({ "text": text, "raw": raw });
+    let _ = atomic_file::write(&path, body.to_string().as_bytes());
+}
+
+fn prune_resumed(data_dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(data_dir.join(RESUME_DIR)) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let stale = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.elapsed().ok())
+            .is_some_and(|age| age > RESUME_MAX_AGE);
+        if stale {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
+fn wav_header(
+    data_len: u32,
     channels: u16,
     sample_rate: u32,
     bits_per_sample: u16,
     block_align: u16,
 ) -> Vec<u8> {
     let byte_rate = sample_rate * block_align as u32;
-    let data_len = pcm_data.len() as u32;
     let riff_len = 36u32 + data_len;
-    let mut out = Vec::with_capacity((44 + pcm_data.len()).max(44));
+    let mut out = Vec::with_capacity(44);
     out.extend_from_slice(b"RIFF");
     out.extend_from_slice(&riff_len.to_le_bytes());
     out.extend_from_slice(b"WAVE");
@@ -584,6 +992,23 @@ fn build_wav_bytes(
     out.extend_from_slice(&bits_per_sample.to_le_bytes());
     out.extend_from_slice(b"data");
     out.extend_from_slice(&data_len.to_le_bytes());
+    out
+}
+
+fn build_wav_bytes(
+    pcm_data: &[u8],
+    channels: u16,
+    sample_rate: u32,
+    bits_per_sample: u16,
+    block_align: u16,
+) -> Vec<u8> {
+    let mut out = wav_header(
+        pcm_data.len() as u32,
+        channels,
+        sample_rate,
+        bits_per_sample,
+        block_align,
+    );
     out.extend_from_slice(pcm_data);
     out
 }
@@ -677,7 +1102,64 @@ fn skip_first_chars(s: &str, n: usize) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{merge_slices, parse_wav};
+    use super::{
+        build_slice_requests, build_wav_bytes, load_resumed, merge_slices, read_wav_info,
+        resume_key, store_resumed, transcribe_one_slice, transcribe_slice_with_retry, SliceBody,
+        SliceRequest, MAX_IN_FLIGHT_BYTES,
+    };
+    use crate::cancel::mock;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{mpsc, Arc};
+    use std::time::{Duration, Instant};
+    use tokio::sync::Semaphore;
+    use tokio_util::sync::CancellationToken;
+
+    // Answers `fail_first` requests with 503 and the rest with a transcript,
+    // passing each request body back.
+    fn flaky_server(requests: usize, fail_first: usize) -> (String, mpsc::Receiver<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let url = format!("http://{}/v1", listener.local_addr().expect("addr"));
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for i in 0..requests {
+                let (stream, _) = listener.accept().expect("accept");
+                let mut reader = BufReader::new(stream);
+                let mut content_length = 0usize;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).expect("head");
+                    if line == "\r\n" {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_length = value.trim().parse().expect("length");
+                        }
+                    }
+                }
+                let mut body = vec![0u8; content_length];
+                reader.read_exact(&mut body).expect("body");
+                let _ = tx.send(body);
+                let (status, reply) = if i < fail_first {
+                    ("503 Service Unavailable", "busy")
+                } else {
+                    ("200 OK", r#"{"text":"hi"}"#)
+                };
+                let resp = format!(
+                    "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{reply}",
+                    reply.len()
+                );
+                reader.get_mut().write_all(resp.as_bytes()).expect("reply");
+            }
+        });
+        (url, rx)
+    }
+
+    fn pattern_pcm(bytes: usize) -> Vec<u8> {
+        (0..bytes).map(|i| (i % 251) as u8).collect()
+    }
 
     fn build_test_wav(seconds: usize) -> Vec<u8> {
         let sample_rate = 16_000u32;
@@ -709,8 +1191,10 @@ mod tests {
 
     #[test]
     fn parse_wav_accepts_mono_16k_16bit() {
-        let wav = build_test_wav(2);
-        let info = parse_wav(&wav).expect("parse");
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("a.wav");
+        std::fs::write(&path, build_test_wav(2)).expect("write");
+        let info = read_wav_info(&path).expect("parse");
         assert_eq!(info.channels, 1);
         assert_eq!(info.sample_rate, 16_000);
         assert_eq!(info.bits_per_sample, 16);
@@ -726,4 +1210,105 @@ mod tests {
         ]);
         assert_eq!(merged, "hello world this is a test for remote asr");
     }
+
+    #[test]
+    fn cancel_closes_a_pending_upload() {
+        let (url, closed) = mock::stalled_server();
+        let client = reqwest::Client::new();
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        let cancelled_at = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            canceller.cancel();
+            Instant::now()
+        });
+        let slice = SliceRequest {
+            index: 0,
+            body: SliceBody::Wav(build_wav_bytes(&[0u8; 3_200], 1, 16_000, 16, 2)),
+        };
+        let e = mock::block_on(transcribe_one_slice(
+            &client, &url, "key", None, &slice, &token,
+        ))
+        .expect_err("cancelled");
+        assert_eq!(e.code, "E_CANCELLED");
+        let cancelled_at = cancelled_at.join().expect("canceller");
+        let closed_at = closed
+            .recv_timeout(Duration::from_secs(2))
+            .expect("socket closed");
+        assert!(closed_at.saturating_duration_since(cancelled_at) < Duration::from_millis(200));
+    }
+
+    #[test]
+    fn file_slices_stream_from_disk_and_retry_server_errors() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("long.wav");
+        let pcm = pattern_pcm(80_000);
+        std::fs::write(&path, build_wav_bytes(&pcm, 1, 16_000, 16, 2)).expect("write");
+        let info = read_wav_info(&path).expect("parse");
+        let budget = Arc::new(Semaphore::new(MAX_IN_FLIGHT_BYTES));
+        let slices = build_slice_requests(&path, &info, 1.0, 0.0, &budget);
+        assert_eq!(slices.len(), 3);
+
+        let (url, bodies) = flaky_server(2, 1);
+        let retries = AtomicUsize::new(0);
+        let (index, text, _) = mock::block_on(transcribe_slice_with_retry(
+            &reqwest::Client::new(),
+            &url,
+            "key",
+            None,
+            &slices[1],
+            &CancellationToken::new(),
+            &retries,
+        ))
+        .expect("transcribed");
+        assert_eq!((index, text.as_str()), (1, "hi"));
+        assert_eq!(retries.load(Ordering::Relaxed), 1);
+
+        let expected = build_wav_bytes(&pcm[32_000..64_000], 1, 16_000, 16, 2);
+        for _ in 0..2 {
+            let body = bodies.recv_timeout(Duration::from_secs(5)).expect("body");
+            assert!(body.windows(expected.len()).any(|w| w == expected));
+        }
+        assert_eq!(budget.available_permits(), MAX_IN_FLIGHT_BYTES);
+    }
+
+    #[test]
+    fn resumed_slices_are_keyed_by_recording_endpoint_and_model() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("a.wav");
+        let pcm = pattern_pcm(32_000);
+        std::fs::write(&path, build_wav_bytes(&pcm, 1, 16_000, 16, 2)).expect("write");
+        let budget = Arc::new(Semaphore::new(MAX_IN_FLIGHT_BYTES));
+        let slice = || {
+            let info = read_wav_info(&path).expect("parse");
+            build_slice_requests(&path, &info, 60.0, 0.5, &budget).remove(0)
+        };
+        let in_memory = SliceRequest {
+            index: 0,
+            body: SliceBody::Wav(build_wav_bytes(&pcm, 1, 16_000, 16, 2)),
+        };
+
+        let hash = |slice: &SliceRequest, model: Option<&str>| {
+            resume_key(slice, "http://asr/v1", model).expect("key")
+        };
+        let key = hash(&slice(), Some("small"));
+        assert_eq!(key, hash(&slice(), Some(" small ")));
+        assert_ne!(key, hash(&slice(), Some("large")));
+        assert_ne!(key, hash(&in_memory, Some("small")));
+
+        // A rewritten recording no longer matches its old slices.
+        std::fs::write(&path, build_wav_bytes(&pcm[..16_000], 1, 16_000, 16, 2)).expect("rewrite");
+        assert_ne!(key, hash(&slice(), Some("small")));
+
+        assert!(load_resumed(tmp.path(), &key).is_none());
+        store_resumed(
+            tmp.path(),
+            &key,
+            "hello",
+            &serde_json::json!({"text": "hello"}),
+        );
+        let (text, raw) = load_resumed(tmp.path(), &key).expect("resumed");
+        assert_eq!(text, "hello");
+        assert_eq!(raw["text"], "hello");
+    }
 }
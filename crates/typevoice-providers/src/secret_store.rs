@@ -0,0 +1,435 @@
+use std::collections::BTreeMap;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+
+use crate::llm::ApiKeyStatus;
+use crate::{atomic_file, data_dir};
+
+// Secrets live in the OS keyring when one is reachable. Some Linux desktops have
+// no secret service, so we fall back to an AEAD-encrypted file in the data dir.
+const SECRETS_FILE: &str = "secrets.enc.json";
+const FILE_VERSION: u32 = 1;
+const PBKDF2_ITERATIONS: u32 = 100_000;
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+const PASSPHRASE_ENV: &str = "TYPEVOICE_SECRETS_PASSPHRASE";
+const BACKEND_ENV: &str = "TYPEVOICE_SECRETS_BACKEND"; // keyring|file
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Keyring,
+    EncryptedFile,
+}
+
+impl Backend {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Keyring => "keyring",
+            Self::EncryptedFile => "encrypted_file",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum KeySource {
+    Machine,
+    Passphrase(String),
+}
+
+impl KeySource {
+    fn from_env() -> Self {
+        match std::env::var(PASSPHRASE_ENV) {
+            Ok(p) if !p.is_empty() => Self::Passphrase(p),
+            _ => Self::Machine,
+        }
+    }
+
+    fn kdf_name(&self) -> &'static str {
+        match self {
+            Self::Machine => "machine",
+            Self::Passphrase(_) => "passphrase",
+        }
+    }
+
+    fn secret(&self) -> Vec<u8> {
+        match self {
+            Self::Machine => format!("typevoice:{}:{}", os_user(), machine_id()).into_bytes(),
+            Self::Passphrase(p) => p.as_bytes().to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SecretsFile {
+    version: u32,
+    kdf: String,
+    salt: String,
+    #[serde(default)]
+    entries: BTreeMap<String, SealedEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SealedEntry {
+    nonce: String,
+    ciphertext: String,
+}
+
+struct FileStore {
+    path: PathBuf,
+    key_source: KeySource,
+}
+
+pub fn get(service: &str, user: &str) -> Result<(Option<String>, Backend)> {
+    if !force_file() {
+        match keyring_get(service, user) {
+            Ok(Some(v)) => return Ok((Some(v), Backend::Keyring)),
+            Ok(None) => return after_keyring_miss(&default_file_store()?, service, user),
+            Err(_) => {}
+        }
+    }
+    let v = default_file_store()?.get(service, user)?;
+    Ok((v, Backend::EncryptedFile))
+}
+
+// An earlier fallback write may still hold the value. A file that cannot be
+// opened (say, it needs the passphrase) is an error, not "not configured".
+fn after_keyring_miss(
+    file: &FileStore,
+    service: &str,
+    user: &str,
+) -> Result<(Option<String>, Backend)> {
+    Ok(match file.get(service, user)? {
+        Some(v) => (Some(v), Backend::EncryptedFile),
+        None => (None, Backend::Keyring),
+    })
+}
+
+pub fn set(service: &str, user: &str, value: &str) -> Result<Backend> {
+    if !force_file() && keyring_set(service, user, value).is_ok() {
+        // Drop any stale fallback copy so the two backends never disagree.
+        if let Ok(f) = default_file_store() {
+            let _ = f.remove(service, user);
+        }
+        return Ok(Backend::Keyring);
+    }
+    default_file_store()?.set(service, user, value)?;
+    Ok(Backend::EncryptedFile)
+}
+
+pub fn clear(service: &str, user: &str) -> Result<()> {
+    if !force_file() {
+        // keyring v3 does not expose a cross-platform delete API. We overwrite with
+        // an empty password and treat empty as "not configured".
+        let _ = keyring_set(service, user, "");
+    }
+    default_file_store()?.remove(service, user)
+}
+
+pub fn status(service: &str, user: &str) -> ApiKeyStatus {
+    match get(service, user) {
+        Ok((Some(_), backend)) => ApiKeyStatus {
+            configured: true,
+            source: backend.as_str().to_string(),
+            reason: None,
+        },
+        Ok((None, backend)) => ApiKeyStatus {
+            configured: false,
+            source: backend.as_str().to_string(),
+            reason: Some("empty".to_string()),
+        },
+        Err(e) => ApiKeyStatus {
+            configured: false,
+            source: Backend::EncryptedFile.as_str().to_string(),
+            reason: Some(format!("secret_store_failed:{e}")),
+        },
+    }
+}
+
+fn force_file() -> bool {
+    std::env::var(BACKEND_ENV)
+        .map(|v| v.trim().eq_ignore_ascii_case("file"))
+        .unwrap_or(false)
+}
+
+fn keyring_get(service: &str, user: &str) -> Result<Option<String>> {
+    let entry = keyring::Entry::new(service, user).map_err(|e| anyhow!("{e:?}"))?;
+    match entry.get_password() {
+        Ok(v) if !v.trim().is_empty() => Ok(Some(v)),
+        Ok(_) | Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(anyhow!("{e:?}")),
+    }
+}
+
+fn keyring_set(service: &str, user: &str, value: &str) -> Result<()> {
+    keyring::Entry::new(service, user)
+        .map_err(|e| anyhow!("{e:?}"))?
+        .set_password(value)
+        .map_err(|e| anyhow!("{e:?}"))
+}
+
+fn default_file_store() -> Result<FileStore> {
+    Ok(FileStore::new(
+        data_dir::data_dir()?.join(SECRETS_FILE),
+        KeySource::from_env(),
+    ))
+}
+
+impl FileStore {
+    fn new(path: PathBuf, key_source: KeySource) -> Self {
+        Self { path, key_source }
+    }
+
+    fn get(&self, service: &str, user: &str) -> Result<Option<String>> {
+        let Some(file) = self.read()? else {
+            return Ok(None);
+        };
+        let Some(entry) = file.entries.get(&entry_id(service, user)) else {
+            return Ok(None);
+        };
+        let key = self.key_for(&file)?;
+        let v = open(&key, &entry_id(service, user), entry)?;
+        Ok(Some(v).filter(|v| !v.trim().is_empty()))
+    }
+
+    fn set(&self, service: &str, user: &str, value: &str) -> Result<()> {
+        let rng = SystemRandom::new();
+        let mut file = match self.read()? {
+            Some(f) => f,
+            None => {
+                let mut salt = [0u8; SALT_LEN];
+                rng.fill(&mut salt)
+                    .map_err(|_| anyhow!("E_SECRETS_RNG: random salt failed"))?;
+                SecretsFile {
+                    version: FILE_VERSION,
+                    kdf: self.key_source.kdf_name().to_string(),
+                    salt: b64().encode(salt),
+                    entries: BTreeMap::new(),
+                }
+            }
+        };
+        let key = self.key_for(&file)?;
+        let id = entry_id(service, user);
+        let sealed = seal(&key, &rng, &id, value)?;
+        file.entries.insert(id, sealed);
+        self.write(&file)
+    }
+
+    fn remove(&self, service: &str, user: &str) -> Result<()> {
+        let Some(mut file) = self.read()? else {
+            return Ok(());
+        };
+        if file.entries.remove(&entry_id(service, user)).is_none() {
+            return Ok(());
+        }
+        self.write(&file)
+    }
+
+    fn read(&self) -> Result<Option<SecretsFile>> {
+        let raw = match std::fs::read_to_string(&self.path) {
+            Ok(v) => v,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(anyhow!("E_SECRETS_READ: {e}")),
+        };
+        let file: SecretsFile =
+            serde_json::from_str(&raw).map_err(|e| anyhow!("E_SECRETS_PARSE: {e}"))?;
+        if file.version != FILE_VERSION {
+            return Err(anyhow!(
+                "E_SECRETS_VERSION: unsupported secrets file version {}",
+                file.version
+            ));
+        }
+        Ok(Some(file))
+    }
+
+    fn write(&self, file: &SecretsFile) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).context("create data dir failed")?;
+        }
+        let s = serde_json::to_string_pretty(file).context("serialize secrets failed")?;
+        atomic_file::write_with(&self.path, s.as_bytes(), restrict_permissions)
+            .map_err(|e| anyhow!("E_SECRETS_WRITE: {e}"))
+    }
+
+    fn key_for(&self, file: &SecretsFile) -> Result<LessSafeKey> {
+        if file.kdf != self.key_source.kdf_name() {
+            return Err(match file.kdf.as_str() {
+                "passphrase" => anyhow!(
+                    "E_SECRETS_PASSPHRASE_REQUIRED: set {PASSPHRASE_ENV} to unlock {}",
+                    self.path.display()
+                ),
+                other => anyhow!("E_SECRETS_KDF_MISMATCH: secrets file uses kdf {other}"),
+            });
+        }
+        let salt = b64()
+            .decode(&file.salt)
+            .map_err(|e| anyhow!("E_SECRETS_PARSE: bad salt: {e}"))?;
+        derive_key(&self.key_source.secret(), &salt)
+    }
+}
+
+fn derive_key(secret: &[u8], salt: &[u8]) -> Result<LessSafeKey> {
+    let mut out = [0u8; KEY_LEN];
+    let iterations = NonZeroU32::new(PBKDF2_ITERATIONS).expect("non-zero iterations");
+    ring::pbkdf2::derive(
+        ring::pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        salt,
+        secret,
+        &mut out,
+    );
+    let unbound =
+        UnboundKey::new(&CHACHA20_POLY1305, &out).map_err(|_| anyhow!("E_SECRETS_KEY"))?;
+    Ok(LessSafeKey::new(unbound))
+}
+
+fn seal(key: &LessSafeKey, rng: &SystemRandom, id: &str, value: &str) -> Result<SealedEntry> {
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut nonce)
+        .map_err(|_| anyhow!("E_SECRETS_RNG: random nonce failed"))?;
+    let mut buf = value.as_bytes().to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(id.as_bytes()),
+        &mut buf,
+    )
+    .map_err(|_| anyhow!("E_SECRETS_ENCRYPT: seal failed"))?;
+    Ok(SealedEntry {
+        nonce: b64().encode(nonce),
+        ciphertext: b64().encode(buf),
+    })
+}
+
+fn open(key: &LessSafeKey, id: &str, entry: &SealedEntry) -> Result<String> {
+    let nonce: [u8; NONCE_LEN] = b64()
+        .decode(&entry.nonce)
+        .ok()
+        .and_then(|v| v.try_into().ok())
+        .ok_or_else(|| anyhow!("E_SECRETS_PARSE: bad nonce"))?;
+    let mut buf = b64()
+        .decode(&entry.ciphertext)
+        .map_err(|e| anyhow!("E_SECRETS_PARSE: bad ciphertext: {e}"))?;
+    let plain = key
+        .open_in_place(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(id.as_bytes()),
+            &mut buf,
+        )
+        .map_err(|_| anyhow!("E_SECRETS_DECRYPT: wrong key or corrupted secrets file"))?;
+    String::from_utf8(plain.to_vec()).map_err(|_| anyhow!("E_SECRETS_DECRYPT: not utf-8"))
+}
+
+fn entry_id(service: &str, user: &str) -> String {
+    format!("{service}/{user}")
+}
+
+fn b64() -> base64::engine::GeneralPurpose {
+    base64::engine::general_purpose::STANDARD
+}
+
+fn os_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
+fn machine_id() -> String {
+    for p in ["/etc/machine-id", "/var/lib/dbus/machine-id"] {
+        if let Ok(v) = std::fs::read_to_string(p) {
+            if !v.trim().is_empty() {
+                return v.trim().to_string();
+            }
+        }
+    }
+    if let Ok(v) = std::env::var("COMPUTERNAME") {
+        return v;
+    }
+    std::fs::read_to_string("/etc/hostname")
+        .map(|v| v.trim().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(unix)]
+fn restrict_permissions(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+    let _ = std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600));
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path) {}
+
+#[cfg(test)]
+mod tests {
+    use super::{after_keyring_miss, Backend, FileStore, KeySource};
+
+    #[test]
+    fn file_store_round_trips_without_leaking_plaintext() {
+        let td = tempfile::tempdir().expect("tempdir");
+        let path = td.path().join("secrets.enc.json");
+        let store = FileStore::new(path.clone(), KeySource::Machine);
+        assert_eq!(store.get("typevoice", "llm_api_key").expect("get"), None);
+
+        store
+            .set("typevoice", "llm_api_key", "sk-secret-123")
+            .expect("set");
+        store
+            .set("typevoice", "remote_asr_api_key", "asr-456")
+            .expect("set");
+        let raw = std::fs::read_to_string(&path).expect("read");
+        assert!(!raw.contains("sk-secret-123"));
+        assert_eq!(
+            store
+                .get("typevoice", "llm_api_key")
+                .expect("get")
+                .as_deref(),
+            Some("sk-secret-123")
+        );
+
+        store.remove("typevoice", "llm_api_key").expect("remove");
+        assert_eq!(store.get("typevoice", "llm_api_key").expect("get"), None);
+        assert_eq!(
+            store
+                .get("typevoice", "remote_asr_api_key")
+                .expect("get")
+                .as_deref(),
+            Some("asr-456")
+        );
+    }
+
+    #[test]
+    fn file_store_rejects_wrong_passphrase() {
+        let td = tempfile::tempdir().expect("tempdir");
+        let path = td.path().join("secrets.enc.json");
+        FileStore::new(path.clone(), KeySource::Passphrase("right".to_string()))
+            .set("typevoice", "llm_api_key", "sk")
+            .expect("set");
+
+        let wrong = FileStore::new(path.clone(), KeySource::Passphrase("wrong".to_string()));
+        let err = wrong.get("typevoice", "llm_api_key").expect_err("decrypt");
+        assert!(err.to_string().starts_with("E_SECRETS_DECRYPT"));
+
+        let machine = FileStore::new(path, KeySource::Machine);
+        let err = machine.get("typevoice", "llm_api_key").expect_err("kdf");
+        assert!(err.to_string().starts_with("E_SECRETS_PASSPHRASE_REQUIRED"));
+    }
+
+    #[test]
+    fn keyring_miss_reports_a_locked_file_store() {
+        let td = tempfile::tempdir().expect("tempdir");
+        let path = td.path().join("secrets.enc.json");
+        let machine = FileStore::new(path.clone(), KeySource::Machine);
+        let (v, backend) = after_keyring_miss(&machine, "typevoice", "llm_api_key").expect("get");
+        assert_eq!((v, backend), (None, Backend::Keyring));
+
+        FileStore::new(path.clone(), KeySource::Passphrase("right".to_string()))
+            .set("typevoice", "llm_api_key", "sk")
+            .expect("set");
+        let err = after_keyring_miss(&machine, "typevoice", "llm_api_key").expect_err("locked");
+        assert!(err.to_string().starts_with("E_SECRETS_PASSPHRASE_REQUIRED"));
+    }
+}
@@ -0,0 +1,382 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use reqwest::{Client, StatusCode};
+use serde_json::{Map, Value};
+use typevoice_core::ports::PortError;
+
+use crate::history::HistoryItem;
+use crate::http_client;
+use crate::llm::ApiKeyStatus;
+use crate::obs::{self, metrics, schema::MetricsRecord};
+use crate::secret_store;
+use crate::settings::{self, WebhookResolved};
+
+const KEYRING_SERVICE: &str = "typevoice";
+const WEBHOOK_SECRET_USER: &str = "webhook_secret";
+const EVENT_TASK_DONE: &str = "task_done";
+const MAX_ATTEMPTS: u32 = 4;
+const BACKOFF_BASE: Duration = Duration::from_secs(1);
+const RESPONSE_EXCERPT_CHARS: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookDelivery {
+    pub delivery_id: String,
+    pub attempts: u32,
+    pub http_status: u16,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookFailure {
+    pub error: PortError,
+    pub attempts: u32,
+    pub http_status: Option<u16>,
+}
+
+// Posts the finished task to the configured URL. Ok(None) means the sink is
+// off; every attempted delivery, good or bad, lands in metrics.
+pub async fn deliver_task_done(
+    data_dir: &Path,
+    item: &HistoryItem,
+) -> Result<Option<WebhookDelivery>, PortError> {
+    let s = settings::load_settings(data_dir).unwrap_or_default();
+    let Some(cfg) = settings::resolve_webhook(&s) else {
+        return Ok(None);
+    };
+    let delivery_id = uuid::Uuid::new_v4().to_string();
+    let started = Instant::now();
+    let result = match http_client::client(data_dir) {
+        Ok(client) => {
+            let secret = stored_secret();
+            let body = build_payload(item, &cfg, obs::schema::now_ms()).to_string();
+            post_with_retry(
+                &client,
+                &cfg.url,
+                &delivery_id,
+                &body,
+                secret.as_deref(),
+                BACKOFF_BASE,
+            )
+            .await
+        }
+        Err(e) => Err(WebhookFailure {
+            error: PortError::from_message("E_WEBHOOK_CLIENT", e.to_string()),
+            attempts: 0,
+            http_status: None,
+        }),
+    };
+    let (status, attempts, http_status, error_code) = match &result {
+        Ok(d) => ("delivered", d.attempts, Some(d.http_status), None),
+        Err(f) => (
+            "failed",
+            f.attempts,
+            f.http_status,
+            Some(f.error.code.clone()),
+        ),
+    };
+    let _ = metrics::emit(
+        data_dir,
+        MetricsRecord::WebhookDelivery {
+            ts_ms: obs::schema::now_ms(),
+            task_id: item.task_id.clone(),
+            delivery_id,
+            status: status.to_string(),
+            attempts,
+            http_status,
+            elapsed_ms: started.elapsed().as_millis(),
+            error_code,
+        },
+    );
+    result.map(Some).map_err(|f| f.error)
+}
+
+// `task_id` and `event` are always sent; the rest follow the selected fields.
+pub fn build_payload(item: &HistoryItem, cfg: &WebhookResolved, sent_at_ms: i64) -> Value {
+    let mut out = Map::new();
+    out.insert("event".into(), EVENT_TASK_DONE.into());
+    out.insert("sent_at_ms".into(), sent_at_ms.into());
+    out.insert("task_id".into(), item.task_id.clone().into());
+    for field in &cfg.fields {
+        let value: Value = match *field {
+            "created_at_ms" => item.created_at_ms.into(),
+            "asr_text" => item.asr_text.clone().into(),
+            "final_text" => item.final_text.clone().into(),
+            "inserted_text" => item.inserted_text.clone().into(),
+            "template_id" => item.template_id.clone().into(),
+            "rewrite_enabled" => (!item.rewritten_text.is_empty()).into(),
+            "rtf" => item.rtf.into(),
+            "device_used" => item.device_used.clone().into(),
+            "preprocess_ms" => item.preprocess_ms.into(),
+            "asr_ms" => item.asr_ms.into(),
+            _ => continue,
+        };
+        out.insert((*field).to_string(), value);
+    }
+    Value::Object(out)
+}
+
+// GitHub/Stripe style: HMAC-SHA256 over "<timestamp>.<body>", hex encoded.
+// The timestamp is signed too so receivers can reject replays.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, secret.as_bytes());
+    let tag = ring::hmac::sign(&key, format!("{timestamp}.{body}").as_bytes());
+    let mut out = String::with_capacity(7 + 64);
+    out.push_str("sha256=");
+    for b in tag.as_ref() {
+        out.push_str(&format!("{b:02x}"));
+    }
+    out
+}
+
+// Network errors, 408, 429 and 5xx are retried with doubling backoff; other
+// statuses mean the receiver rejected the payload and retrying will not help.
+pub async fn post_with_retry(
+    client: &Client,
+    url: &str,
+    delivery_id: &str,
+    body: &str,
+    secret: Option<&str>,
+    backoff_base: Duration,
+) -> Result<WebhookDelivery, WebhookFailure> {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let timestamp = obs::schema::now_ms() / 1000;
+        let mut req = client
+            .post(url)
+            .header("content-type", "application/json")
+            .header("x-typevoice-event", EVENT_TASK_DONE)
+            .header("x-typevoice-delivery", delivery_id)
+            .header("x-typevoice-timestamp", timestamp.to_string())
+            .body(body.to_string());
+        if let Some(secret) = secret {
+            req = req.header("x-typevoice-signature", sign(secret, timestamp, body));
+        }
+        let (error, http_status, retryable) = match req.send().await {
+            Ok(resp) if resp.status().is_success() => {
+                return Ok(WebhookDelivery {
+                    delivery_id: delivery_id.to_string(),
+                    attempts: attempt,
+                    http_status: resp.status().as_u16(),
+                });
+            }
+            Ok(resp) => {
+                let status = resp.status();
+                let excerpt: String = resp
+                    .text()
+                    .await
+                    .unwrap_or_default()
+                    .chars()
+                    .take(RESPONSE_EXCERPT_CHARS)
+                    .collect();
+                (
+                    PortError::new(
+                        &format!("E_WEBHOOK_HTTP_STATUS_{}", status.as_u16()),
+                        format!("webhook returned {status}: {excerpt}"),
+                    ),
+                    Some(status.as_u16()),
+                    is_retryable_status(status),
+                )
+            }
+            Err(e) => {
+                let code = http_client::send_error_code(&e, "E_WEBHOOK_SEND");
+                (
+                    PortError::new(code, format!("webhook request failed: {e}")),
+                    None,
+                    // A bad URL or certificate will not fix itself between attempts.
+                    !e.is_builder() && code != "E_TLS_VERIFY",
+                )
+            }
+        };
+        if !retryable || attempt >= MAX_ATTEMPTS {
+            return Err(WebhookFailure {
+                error,
+                attempts: attempt,
+                http_status,
+            });
+        }
+        tokio::time::sleep(backoff_base * 2u32.pow(attempt - 1)).await;
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status.is_server_error()
+}
+
+pub fn set_secret(secret: &str) -> Result<()> {
+    let secret = secret.trim();
+    if secret.is_empty() {
+        return Err(anyhow::anyhow!(
+            "E_WEBHOOK_SECRET_MISSING: webhook secret is required"
+        ));
+    }
+    secret_store::set(KEYRING_SERVICE, WEBHOOK_SECRET_USER, secret)?;
+    Ok(())
+}
+
+pub fn clear_secret() -> Result<()> {
+    secret_store::clear(KEYRING_SERVICE, WEBHOOK_SECRET_USER)
+}
+
+pub fn secret_status() -> ApiKeyStatus {
+    secret_store::status(KEYRING_SERVICE, WEBHOOK_SECRET_USER)
+}
+
+// An unreadable secret store sends unsigned rather than dropping the delivery;
+// receivers that require a signature will reject it visibly.
+fn stored_secret() -> Option<String> {
+    secret_store::get(KEYRING_SERVICE, WEBHOOK_SECRET_USER)
+        .ok()
+        .and_then(|(v, _)| v)
+        .filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_payload, post_with_retry, sign};
+    use crate::history::HistoryItem;
+    use crate::settings::WebhookResolved;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    fn item() -> HistoryItem {
+        HistoryItem {
+            task_id: "task-1".to_string(),
+            created_at_ms: 1_700_000_000_000,
+            asr_text: "raw".to_string(),
+            rewritten_text: "rewritten".to_string(),
+            inserted_text: "inserted".to_string(),
+            final_text: "inserted".to_string(),
+            template_id: None,
+            rtf: 0.25,
+            device_used: "cloud".to_string(),
+            preprocess_ms: 12,
+            asr_ms: 340,
+            target_app: None,
+            target_title_hash: None,
+            tags: Vec::new(),
+            note: String::new(),
+        }
+    }
+
+    // Answers each connection with the next status and reports the request
+    // headers (lower-cased) and body.
+    fn serve(statuses: Vec<u16>) -> (String, mpsc::Receiver<(Vec<String>, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let url = format!("http://{}/hook", listener.local_addr().expect("addr"));
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for status in statuses {
+                let (stream, _) = listener.accept().expect("accept");
+                let mut reader = BufReader::new(stream.try_clone().expect("clone"));
+                let mut headers = Vec::new();
+                let mut len = 0usize;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).expect("read");
+                    let line = line.trim_end().to_ascii_lowercase();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some(v) = line.strip_prefix("content-length:") {
+                        len = v.trim().parse().expect("length");
+                    }
+                    headers.push(line);
+                }
+                let mut body = vec![0u8; len];
+                reader.read_exact(&mut body).expect("body");
+                let _ = tx.send((headers, String::from_utf8(body).expect("utf8")));
+                let mut stream = stream;
+                write!(
+                    stream,
+                    "HTTP/1.1 {status} X\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                )
+                .expect("respond");
+            }
+        });
+        (url, rx)
+    }
+
+    fn block_on<F: std::future::Future>(f: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("runtime")
+            .block_on(f)
+    }
+
+    #[test]
+    fn payload_carries_only_selected_fields() {
+        let cfg = WebhookResolved {
+            url: "http://x".to_string(),
+            fields: vec!["final_text", "rewrite_enabled", "template_id"],
+        };
+        let v = build_payload(&item(), &cfg, 5);
+        assert_eq!(
+            v,
+            serde_json::json!({
+                "event": "task_done",
+                "sent_at_ms": 5,
+                "task_id": "task-1",
+                "final_text": "inserted",
+                "rewrite_enabled": true,
+                "template_id": null,
+            })
+        );
+    }
+
+    #[test]
+    fn signature_covers_timestamp_and_body() {
+        // Reference value from: printf '1700000000.{}' | openssl dgst -sha256 -hmac s3cret
+        assert_eq!(
+            sign("s3cret", 1_700_000_000, "{}"),
+            "sha256=97926816e98fbb41ccb1673225ff29a2f35369099990e1b1561651e7bd097ebf"
+        );
+        assert_ne!(
+            sign("s3cret", 1_700_000_001, "{}"),
+            sign("s3cret", 1_700_000_000, "{}")
+        );
+    }
+
+    #[test]
+    fn retries_server_errors_and_stops_on_client_errors() {
+        let client = reqwest::Client::new();
+        let (url, rx) = serve(vec![503, 200]);
+        let got = block_on(post_with_retry(
+            &client,
+            &url,
+            "d1",
+            r#"{"task_id":"task-1"}"#,
+            Some("s3cret"),
+            Duration::from_millis(10),
+        ))
+        .expect("delivered");
+        assert_eq!(got.attempts, 2);
+        assert_eq!(got.http_status, 200);
+        let (headers, body) = rx.recv().expect("first request");
+        assert_eq!(body, r#"{"task_id":"task-1"}"#);
+        assert!(headers.iter().any(|h| h == "x-typevoice-delivery: d1"));
+        assert!(headers
+            .iter()
+            .any(|h| h.starts_with("x-typevoice-signature: sha256=")));
+
+        let (url, _rx) = serve(vec![410]);
+        let err = block_on(post_with_retry(
+            &client,
+            &url,
+            "d2",
+            "{}",
+            None,
+            Duration::from_millis(10),
+        ))
+        .expect_err("rejected");
+        assert_eq!(err.attempts, 1);
+        assert_eq!(err.http_status, Some(410));
+        assert_eq!(err.error.code, "E_WEBHOOK_HTTP_STATUS_410");
+    }
+}
@@ -0,0 +1,324 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::corrections::word_tokens;
+use crate::obs::{
+    metrics,
+    schema::{now_ms, MetricsRecord},
+};
+
+// Edit distance is quadratic; texts this long are not scored.
+const MAX_SCORED_UNITS: usize = 4_000;
+const WEEK_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+const UNKNOWN: &str = "unknown";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskAccuracy {
+    pub ref_words: usize,
+    pub word_errors: usize,
+    pub ref_chars: usize,
+    pub char_errors: usize,
+}
+
+impl TaskAccuracy {
+    pub fn wer(&self) -> f64 {
+        ratio(self.word_errors, self.ref_words)
+    }
+
+    pub fn cer(&self) -> f64 {
+        ratio(self.char_errors, self.ref_chars)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AccuracyBucket {
+    pub week_start_ms: i64,
+    pub tasks: u32,
+    pub wer: f64,
+    pub cer: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AccuracyGroup {
+    pub asr_model: String,
+    pub microphone: String,
+    pub tasks: u32,
+    pub wer: f64,
+    pub cer: f64,
+    pub first_ms: i64,
+    pub last_ms: i64,
+    pub weekly: Vec<AccuracyBucket>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccuracyRecord {
+    ts_ms: i64,
+    task_id: String,
+    asr_model: String,
+    microphone: String,
+    ref_words: usize,
+    word_errors: usize,
+    ref_chars: usize,
+    char_errors: usize,
+}
+
+fn ratio(errors: usize, reference: usize) -> f64 {
+    if reference == 0 {
+        0.0
+    } else {
+        errors as f64 / reference as f64
+    }
+}
+
+fn edit_distance<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+    for (i, x) in a.iter().enumerate() {
+        cur[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(x != y);
+            cur[j + 1] = substitute.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()]
+}
+
+// Approximate WER/CER of the ASR text, taking the user's correction as the
+// reference. Case and punctuation are ignored; Han and kana count one word per
+// character, so for Chinese WER and CER are close.
+pub fn measure(asr_text: &str, corrected_text: &str) -> Option<TaskAccuracy> {
+    let hyp_words: Vec<String> = word_tokens(asr_text)
+        .into_iter()
+        .map(str::to_lowercase)
+        .collect();
+    let ref_words: Vec<String> = word_tokens(corrected_text)
+        .into_iter()
+        .map(str::to_lowercase)
+        .collect();
+    let chars = |words: &[String]| -> Vec<char> {
+        words
+            .iter()
+            .flat_map(|w| w.chars())
+            .filter(|c| c.is_alphanumeric())
+            .collect()
+    };
+    let (hyp_chars, ref_chars) = (chars(&hyp_words), chars(&ref_words));
+    if ref_words.is_empty()
+        || ref_chars.len() > MAX_SCORED_UNITS
+        || hyp_chars.len() > MAX_SCORED_UNITS
+    {
+        return None;
+    }
+    Some(TaskAccuracy {
+        ref_words: ref_words.len(),
+        word_errors: edit_distance(&hyp_words, &ref_words),
+        ref_chars: ref_chars.len(),
+        char_errors: edit_distance(&hyp_chars, &ref_chars),
+    })
+}
+
+// Model and microphone a task was dictated with, from its `task_perf` and
+// `record_input` metrics. Streaming tasks have no `task_perf`; the caller
+// passes the provider recorded in history instead.
+fn task_source(
+    records: &[serde_json::Value],
+    task_id: &str,
+    fallback_model: &str,
+) -> (String, String) {
+    let mut model = None;
+    let mut microphone = None;
+    for r in records.iter().filter(|r| r["task_id"] == task_id) {
+        let text = |key: &str| {
+            r[key]
+                .as_str()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(ToOwned::to_owned)
+        };
+        match r["type"].as_str() {
+            Some("task_perf") => model = text("asr_model_id").or_else(|| text("asr_provider")),
+            Some("record_input") => {
+                microphone = text("friendly_name").or_else(|| text("endpoint_id"))
+            }
+            _ => {}
+        }
+    }
+    let fallback = Some(fallback_model.trim())
+        .filter(|v| !v.is_empty())
+        .unwrap_or(UNKNOWN);
+    (
+        model.unwrap_or_else(|| fallback.to_string()),
+        microphone.unwrap_or_else(|| UNKNOWN.to_string()),
+    )
+}
+
+// Scores a saved correction and appends an `asr_accuracy` metric for it.
+pub fn record_correction(
+    data_dir: &Path,
+    task_id: &str,
+    asr_text: &str,
+    corrected_text: &str,
+    fallback_model: &str,
+) -> Option<TaskAccuracy> {
+    let acc = measure(asr_text, corrected_text)?;
+    let (asr_model, microphone) =
+        task_source(&metrics::read_all(data_dir), task_id, fallback_model);
+    let _ = metrics::emit(
+        data_dir,
+        MetricsRecord::AsrAccuracy {
+            ts_ms: now_ms(),
+            task_id: task_id.to_string(),
+            asr_model,
+            microphone,
+            ref_words: acc.ref_words,
+            word_errors: acc.word_errors,
+            wer: acc.wer(),
+            ref_chars: acc.ref_chars,
+            char_errors: acc.char_errors,
+            cer: acc.cer(),
+        },
+    );
+    Some(acc)
+}
+
+#[derive(Default)]
+struct Totals {
+    tasks: u32,
+    ref_words: usize,
+    word_errors: usize,
+    ref_chars: usize,
+    char_errors: usize,
+    first_ms: i64,
+    last_ms: i64,
+}
+
+impl Totals {
+    fn add(&mut self, r: &AccuracyRecord) {
+        if self.tasks == 0 {
+            (self.first_ms, self.last_ms) = (r.ts_ms, r.ts_ms);
+        }
+        self.first_ms = self.first_ms.min(r.ts_ms);
+        self.last_ms = self.last_ms.max(r.ts_ms);
+        self.tasks += 1;
+        self.ref_words += r.ref_words;
+        self.word_errors += r.word_errors;
+        self.ref_chars += r.ref_chars;
+        self.char_errors += r.char_errors;
+    }
+}
+
+// Error rates pooled over tasks (total errors / total reference length) per
+// model and microphone, with weekly buckets. A task corrected several times
+// counts once, with its latest score.
+pub fn aggregate(records: &[serde_json::Value]) -> Vec<AccuracyGroup> {
+    let mut latest: HashMap<String, AccuracyRecord> = HashMap::new();
+    for r in records.iter().filter(|r| r["type"] == "asr_accuracy") {
+        if let Ok(rec) = serde_json::from_value::<AccuracyRecord>(r.clone()) {
+            latest.insert(rec.task_id.clone(), rec);
+        }
+    }
+
+    let mut groups: BTreeMap<(String, String), (Totals, BTreeMap<i64, Totals>)> = BTreeMap::new();
+    for rec in latest.values() {
+        let (total, weeks) = groups
+            .entry((rec.asr_model.clone(), rec.microphone.clone()))
+            .or_default();
+        total.add(rec);
+        weeks
+            .entry(rec.ts_ms - rec.ts_ms.rem_euclid(WEEK_MS))
+            .or_default()
+            .add(rec);
+    }
+
+    let mut out: Vec<AccuracyGroup> = groups
+        .into_iter()
+        .map(|((asr_model, microphone), (total, weeks))| AccuracyGroup {
+            asr_model,
+            microphone,
+            tasks: total.tasks,
+            wer: ratio(total.word_errors, total.ref_words),
+            cer: ratio(total.char_errors, total.ref_chars),
+            first_ms: total.first_ms,
+            last_ms: total.last_ms,
+            weekly: weeks
+                .into_iter()
+                .map(|(week_start_ms, t)| AccuracyBucket {
+                    week_start_ms,
+                    tasks: t.tasks,
+                    wer: ratio(t.word_errors, t.ref_words),
+                    cer: ratio(t.char_errors, t.ref_chars),
+                })
+                .collect(),
+        })
+        .collect();
+    out.sort_by(|a, b| b.last_ms.cmp(&a.last_ms));
+    out
+}
+
+pub fn stats(data_dir: &Path) -> Vec<AccuracyGroup> {
+    aggregate(&metrics::read_all(data_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measure_scores_words_and_characters_against_the_correction() {
+        let acc = measure("open the type voice app", "Open the TypeVoice app.").expect("scored");
+        assert_eq!((acc.ref_words, acc.word_errors), (4, 2));
+        assert_eq!(acc.ref_chars, 19);
+        assert_eq!(acc.char_errors, 0);
+
+        let acc = measure("我在用泰普写代码", "我在用TypeVoice写代码").expect("scored");
+        assert_eq!((acc.ref_words, acc.word_errors), (7, 2));
+        assert!(measure("anything", " ... ").is_none());
+    }
+
+    #[test]
+    fn aggregate_pools_latest_score_per_task_by_model_and_microphone() {
+        let rec = |ts: i64, task: &str, model: &str, words: usize, errors: usize| {
+            serde_json::json!({
+                "type": "asr_accuracy", "ts_ms": ts, "task_id": task,
+                "asr_model": model, "microphone": "Headset",
+                "ref_words": words, "word_errors": errors, "wer": 0.0,
+                "ref_chars": words * 4, "char_errors": errors, "cer": 0.0,
+            })
+        };
+        let records = vec![
+            rec(1, "t1", "whisper", 10, 5),
+            rec(2, "t1", "whisper", 10, 1),
+            rec(WEEK_MS + 3, "t2", "whisper", 30, 3),
+            rec(4, "t3", "doubao", 10, 0),
+            serde_json::json!({"type": "task_done", "task_id": "t1"}),
+        ];
+        let out = aggregate(&records);
+        assert_eq!(out.len(), 2);
+        let whisper = &out[0];
+        assert_eq!(whisper.asr_model, "whisper");
+        assert_eq!(whisper.tasks, 2);
+        assert!((whisper.wer - 0.1).abs() < 1e-9);
+        assert_eq!(whisper.weekly.len(), 2);
+        assert_eq!(whisper.weekly[1].week_start_ms, WEEK_MS);
+        assert_eq!(out[1].asr_model, "doubao");
+    }
+
+    #[test]
+    fn task_source_prefers_metrics_and_falls_back_to_history_provider() {
+        let records = vec![
+            serde_json::json!({"type": "record_input", "task_id": "t1", "friendly_name": "USB Mic", "endpoint_id": "ep"}),
+            serde_json::json!({"type": "task_perf", "task_id": "t1", "asr_provider": "remote", "asr_model_id": "whisper-large-v3"}),
+        ];
+        assert_eq!(
+            task_source(&records, "t1", "cuda"),
+            ("whisper-large-v3".to_string(), "USB Mic".to_string())
+        );
+        assert_eq!(
+            task_source(&records, "t2", "doubao"),
+            ("doubao".to_string(), UNKNOWN.to_string())
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::write_gate;
+
+// Writes to a sibling temp file, flushes it to disk and renames it over
+// `path`, so a reader or a crash never sees a half-written store. The temp
+// name carries the pid so two processes never share one.
+pub fn write(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    write_with(path, bytes, |_| {})
+}
+
+// Like `write`; `before_rename` runs on the finished temp file, e.g. to
+// tighten its permissions.
+pub fn write_with(path: &Path, bytes: &[u8], before_rename: impl FnOnce(&Path)) -> io::Result<()> {
+    write_gate::check(path).map_err(|e| io::Error::other(e.to_string()))?;
+    replace(path, bytes, before_rename)
+}
+
+// For the lock's own owner file, which is written before the lock is held.
+pub(crate) fn write_unchecked(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    replace(path, bytes, |_| {})
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!("{name}.{}.tmp", std::process::id()))
+}
+
+fn replace(path: &Path, bytes: &[u8], before_rename: impl FnOnce(&Path)) -> io::Result<()> {
+    let tmp = temp_path(path);
+    let written = (|| {
+        let mut f = fs::File::create(&tmp)?;
+        f.write_all(bytes)?;
+        f.sync_all()
+    })();
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    before_rename(&tmp);
+    fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_replaces_the_file_and_leaves_no_temp() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = tmp.path().join("store.json");
+        write(&path, b"{\"v\":1}").expect("first");
+        write(&path, b"{\"v\":2}").expect("second");
+        assert_eq!(fs::read_to_string(&path).expect("read"), "{\"v\":2}");
+        let names: Vec<_> = fs::read_dir(tmp.path())
+            .expect("dir")
+            .map(|e| e.expect("entry").file_name())
+            .collect();
+        assert_eq!(names, ["store.json"]);
+    }
+}
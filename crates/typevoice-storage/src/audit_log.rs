@@ -0,0 +1,290 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::obs::Span;
+use crate::write_gate;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+// One export action. Only the hash and length of the text are kept unless the
+// user opted into storing the text itself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64, // assigned on append
+    pub ts_ms: i64,
+    pub task_id: Option<String>,
+    pub process_image: Option<String>,
+    pub window_title: Option<String>,
+    pub export_mode: String, // copy|paste|paste_failed
+    pub export_format: String,
+    pub text_sha256: String,
+    pub char_count: i64,
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditRetention {
+    pub retention_days: i64, // 0 keeps entries regardless of age
+    pub max_entries: i64,    // 0 keeps entries regardless of count
+}
+
+pub fn audit_db_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("audit_log.sqlite3")
+}
+
+fn conn(db_path: &Path) -> Result<Connection> {
+    let c = Connection::open(db_path).context("open sqlite failed")?;
+    c.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS export_audit (
+          id INTEGER PRIMARY KEY AUTOINCREMENT,
+          ts_ms INTEGER NOT NULL,
+          task_id TEXT NULL,
+          process_image TEXT NULL,
+          window_title TEXT NULL,
+          export_mode TEXT NOT NULL,
+          export_format TEXT NOT NULL,
+          text_sha256 TEXT NOT NULL,
+          char_count INTEGER NOT NULL,
+          text TEXT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_export_audit_ts ON export_audit(ts_ms DESC);
+        "#,
+    )
+    .context("init audit sqlite schema failed")?;
+    Ok(c)
+}
+
+// Appends an entry and applies retention in the same connection, so the log
+// never grows past its limits between reads.
+fn write_conn(db_path: &Path) -> Result<Connection> {
+    write_gate::check(db_path)?;
+    conn(db_path)
+}
+
+pub fn append(db_path: &Path, entry: &AuditEntry, retention: AuditRetention) -> Result<i64> {
+    let data_dir = db_path.parent().unwrap_or_else(|| Path::new("."));
+    let span = Span::start(
+        data_dir,
+        entry.task_id.as_deref(),
+        "Audit",
+        "AUDIT.append",
+        Some(serde_json::json!({
+            "export_mode": entry.export_mode,
+            "export_format": entry.export_format,
+            "char_count": entry.char_count,
+            "has_process": entry.process_image.is_some(),
+            "has_text": entry.text.is_some(),
+        })),
+    );
+    let c = match write_conn(db_path) {
+        Ok(c) => c,
+        Err(e) => {
+            span.err_anyhow("db", "E_AUDIT_CONN", &e, None);
+            return Err(e);
+        }
+    };
+    let r = c.execute(
+        r#"
+        INSERT INTO export_audit
+        (ts_ms, task_id, process_image, window_title, export_mode, export_format, text_sha256, char_count, text)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+        "#,
+        params![
+            entry.ts_ms,
+            entry.task_id,
+            entry.process_image,
+            entry.window_title,
+            entry.export_mode,
+            entry.export_format,
+            entry.text_sha256,
+            entry.char_count,
+            entry.text,
+        ],
+    );
+    if let Err(e) = r {
+        let ae = anyhow::anyhow!(e).context("insert audit entry failed");
+        span.err_anyhow("db", "E_AUDIT_INSERT", &ae, None);
+        return Err(ae);
+    }
+    let id = c.last_insert_rowid();
+    match prune_conn(&c, entry.ts_ms, retention) {
+        Ok(pruned) => {
+            span.ok(Some(serde_json::json!({"id": id, "pruned": pruned})));
+            Ok(id)
+        }
+        Err(e) => {
+            span.err_anyhow("db", "E_AUDIT_PRUNE", &e, None);
+            Err(e)
+        }
+    }
+}
+
+pub fn list(db_path: &Path, limit: i64) -> Result<Vec<AuditEntry>> {
+    let data_dir = db_path.parent().unwrap_or_else(|| Path::new("."));
+    let span = Span::start(
+        data_dir,
+        None,
+        "Audit",
+        "AUDIT.list",
+        Some(serde_json::json!({"limit": limit})),
+    );
+    let result = (|| -> Result<Vec<AuditEntry>> {
+        let c = conn(db_path)?;
+        let mut stmt = c
+            .prepare(
+                r#"
+                SELECT id, ts_ms, task_id, process_image, window_title, export_mode, export_format, text_sha256, char_count, text
+                FROM export_audit
+                ORDER BY ts_ms DESC, id DESC
+                LIMIT ?1
+                "#,
+            )
+            .context("prepare audit list failed")?;
+        let rows = stmt
+            .query_map(params![limit.max(0)], |row| {
+                Ok(AuditEntry {
+                    id: row.get(0)?,
+                    ts_ms: row.get(1)?,
+                    task_id: row.get(2)?,
+                    process_image: row.get(3)?,
+                    window_title: row.get(4)?,
+                    export_mode: row.get(5)?,
+                    export_format: row.get(6)?,
+                    text_sha256: row.get(7)?,
+                    char_count: row.get(8)?,
+                    text: row.get(9)?,
+                })
+            })
+            .context("query audit list failed")?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    })();
+
+    match result {
+        Ok(out) => {
+            span.ok(Some(serde_json::json!({"items": out.len()})));
+            Ok(out)
+        }
+        Err(e) => {
+            span.err_anyhow("db", "E_AUDIT_LIST", &e, None);
+            Err(e)
+        }
+    }
+}
+
+pub fn clear(db_path: &Path) -> Result<()> {
+    let data_dir = db_path.parent().unwrap_or_else(|| Path::new("."));
+    let span = Span::start(data_dir, None, "Audit", "AUDIT.clear", None);
+    let c = match write_conn(db_path) {
+        Ok(c) => c,
+        Err(e) => {
+            span.err_anyhow("db", "E_AUDIT_CONN", &e, None);
+            return Err(e);
+        }
+    };
+    match c.execute("DELETE FROM export_audit", []) {
+        Ok(_) => {
+            span.ok(None);
+            Ok(())
+        }
+        Err(e) => {
+            let ae = anyhow::anyhow!(e).context("clear audit log failed");
+            span.err_anyhow("db", "E_AUDIT_CLEAR", &ae, None);
+            Err(ae)
+        }
+    }
+}
+
+fn prune_conn(c: &Connection, now_ms: i64, retention: AuditRetention) -> Result<usize> {
+    let mut pruned = 0;
+    if retention.retention_days > 0 {
+        let cutoff = now_ms.saturating_sub(retention.retention_days.saturating_mul(DAY_MS));
+        pruned += c
+            .execute("DELETE FROM export_audit WHERE ts_ms < ?1", params![cutoff])
+            .context("prune audit by age failed")?;
+    }
+    if retention.max_entries > 0 {
+        pruned += c
+            .execute(
+                r#"
+                DELETE FROM export_audit
+                WHERE id NOT IN (
+                  SELECT id FROM export_audit ORDER BY ts_ms DESC, id DESC LIMIT ?1
+                )
+                "#,
+                params![retention.max_entries],
+            )
+            .context("prune audit by count failed")?;
+    }
+    Ok(pruned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(ts_ms: i64, mode: &str) -> AuditEntry {
+        AuditEntry {
+            id: 0,
+            ts_ms,
+            task_id: Some("task-1".to_string()),
+            process_image: Some("notepad.exe".to_string()),
+            window_title: Some("notes.txt - Notepad".to_string()),
+            export_mode: mode.to_string(),
+            export_format: "plain".to_string(),
+            text_sha256: "abc".to_string(),
+            char_count: 5,
+            text: None,
+        }
+    }
+
+    const KEEP_ALL: AuditRetention = AuditRetention {
+        retention_days: 0,
+        max_entries: 0,
+    };
+
+    #[test]
+    fn append_and_list_newest_first() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let db = audit_db_path(tmp.path());
+        append(&db, &entry(1_000, "copy"), KEEP_ALL).expect("append");
+        append(&db, &entry(2_000, "paste"), KEEP_ALL).expect("append");
+
+        let items = list(&db, 10).expect("list");
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].export_mode, "paste");
+        assert_eq!(items[0].process_image.as_deref(), Some("notepad.exe"));
+        assert_eq!(items[0].text, None);
+        assert_eq!(list(&db, 1).expect("list").len(), 1);
+    }
+
+    #[test]
+    fn append_applies_age_and_count_retention() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let db = audit_db_path(tmp.path());
+        append(&db, &entry(0, "copy"), KEEP_ALL).expect("append");
+        let now = 3 * DAY_MS;
+        for i in 0..4 {
+            append(
+                &db,
+                &entry(now + i, "paste"),
+                AuditRetention {
+                    retention_days: 1,
+                    max_entries: 3,
+                },
+            )
+            .expect("append");
+        }
+
+        let items = list(&db, 10).expect("list");
+        assert_eq!(items.len(), 3);
+        assert!(items.iter().all(|item| item.ts_ms > now));
+    }
+}
@@ -0,0 +1,369 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use rusqlite::{backup::Progress, Connection, DatabaseName, OpenFlags};
+use serde::{Deserialize, Serialize};
+
+use crate::integrity::check_sqlite;
+use crate::obs::{schema::now_ms, Span};
+use crate::settings::{self, Settings};
+use crate::workspaces::{self, WorkspaceTemplates, HISTORY_DB, TEMPLATES_FILE};
+use crate::write_gate;
+
+const BACKUP_FORMAT: &str = "typevoice-backup";
+// Bumped when the layout changes in a way older builds cannot restore.
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+const MANIFEST_FILE: &str = "backup.json";
+const PRE_RESTORE_DIR: &str = "backups";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupWorkspace {
+    pub name: String,
+    pub history: bool,
+    pub templates: bool,
+}
+
+// `backup.json` at the root of a backup directory. The rest mirrors the data
+// dir: `settings.json` and `workspaces/<name>/` with the history DB and the
+// workspace's template set (the default workspace included, by name).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format: String,
+    pub format_version: u32,
+    pub app_version: String,
+    pub created_at_ms: i64,
+    pub settings: bool,
+    pub workspaces: Vec<BackupWorkspace>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupResult {
+    pub path: String,
+    pub manifest: BackupManifest,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreResult {
+    pub settings: bool,
+    pub workspaces: Vec<String>,
+    // Where the data that was replaced was saved first.
+    pub pre_restore_backup: String,
+}
+
+fn backup_workspace_dir(root: &Path, name: &str) -> PathBuf {
+    root.join("workspaces").join(name)
+}
+
+// Writes a consistent snapshot of settings, every workspace's template set and
+// history DB to `dest`, which must not exist or be an empty directory. History
+// is copied with the SQLite backup API, so writes in flight are either in the
+// copy or not. The snapshot is assembled next to `dest` and renamed into place
+// at the end, so a failed backup leaves nothing behind.
+pub fn backup_data(data_dir: &Path, dest: &Path) -> Result<BackupResult> {
+    let span = Span::start(data_dir, None, "App", "APP.backup_data", None);
+    let result = write_backup(data_dir, dest);
+    match &result {
+        Ok(out) => span.ok(Some(serde_json::json!({
+            "workspaces": out.manifest.workspaces.len(),
+            "settings": out.manifest.settings,
+        }))),
+        Err(e) => span.err_anyhow("io", "E_BACKUP_WRITE", e, None),
+    }
+    result
+}
+
+fn write_backup(data_dir: &Path, dest: &Path) -> Result<BackupResult> {
+    let empty_dir = dest.is_dir()
+        && fs::read_dir(dest)
+            .map(|mut d| d.next().is_none())
+            .unwrap_or(false);
+    if dest.exists() && !empty_dir {
+        return Err(anyhow!(
+            "E_BACKUP_TARGET_EXISTS: {} already exists and is not empty",
+            dest.display()
+        ));
+    }
+    let name = dest
+        .file_name()
+        .ok_or_else(|| anyhow!("E_BACKUP_TARGET_INVALID: {} has no name", dest.display()))?;
+    let staging = dest.with_file_name(format!(
+        "{}.partial-{}",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&staging);
+    let written = write_snapshot(data_dir, &staging).and_then(|manifest| {
+        if empty_dir {
+            fs::remove_dir(dest).context("replace empty backup dir failed")?;
+        }
+        fs::rename(&staging, dest).context("move backup into place failed")?;
+        Ok(manifest)
+    });
+    match written {
+        Ok(manifest) => Ok(BackupResult {
+            path: dest.display().to_string(),
+            manifest,
+        }),
+        Err(e) => {
+            let _ = fs::remove_dir_all(&staging);
+            Err(e)
+        }
+    }
+}
+
+fn write_snapshot(data_dir: &Path, root: &Path) -> Result<BackupManifest> {
+    fs::create_dir_all(root).context("create backup dir failed")?;
+    let settings_src = settings::settings_path(data_dir);
+    let settings = settings_src.exists();
+    if settings {
+        fs::copy(&settings_src, root.join("settings.json")).context("copy settings failed")?;
+    }
+    let mut out = Vec::new();
+    for name in workspaces::workspace_names(data_dir) {
+        let dir = backup_workspace_dir(root, &name);
+        fs::create_dir_all(&dir).context("create backup workspace dir failed")?;
+        let templates_src = workspaces::templates_path(data_dir, &name);
+        let templates = templates_src.exists();
+        if templates {
+            fs::copy(&templates_src, dir.join(TEMPLATES_FILE)).context("copy templates failed")?;
+        }
+        let db = workspaces::workspace_history_db(data_dir, &name);
+        let history = db.exists();
+        if history {
+            Connection::open_with_flags(&db, OpenFlags::SQLITE_OPEN_READ_ONLY)
+                .and_then(|c| c.backup(DatabaseName::Main, dir.join(HISTORY_DB), None))
+                .with_context(|| format!("back up history of workspace {name} failed"))?;
+        }
+        out.push(BackupWorkspace {
+            name,
+            history,
+            templates,
+        });
+    }
+    let manifest = BackupManifest {
+        format: BACKUP_FORMAT.to_string(),
+        format_version: BACKUP_FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at_ms: now_ms(),
+        settings,
+        workspaces: out,
+    };
+    let bytes = serde_json::to_vec_pretty(&manifest).context("serialize manifest failed")?;
+    fs::write(root.join(MANIFEST_FILE), bytes).context("write manifest failed")?;
+    Ok(manifest)
+}
+
+// Replaces settings, template sets and history with the backup at `src`.
+// Everything in the backup is checked (manifest version, JSON, SQLite
+// integrity) before anything is touched, and the current data is backed up to
+// `backups/pre-restore-<time>` first. Each file is then replaced atomically:
+// history through the SQLite backup API, JSON stores by rename. Workspaces the
+// backup does not mention are left as they are.
+pub fn restore_data(data_dir: &Path, src: &Path) -> Result<RestoreResult> {
+    let span = Span::start(data_dir, None, "App", "APP.restore_data", None);
+    let result = apply_restore(data_dir, src);
+    match &result {
+        Ok(out) => span.ok(Some(serde_json::json!({
+            "workspaces": out.workspaces.len(),
+            "settings": out.settings,
+        }))),
+        Err(e) => span.err_anyhow("io", "E_BACKUP_RESTORE", e, None),
+    }
+    result
+}
+
+struct CheckedBackup {
+    settings: Option<Settings>,
+    templates: Vec<(String, WorkspaceTemplates)>,
+    // Normalized names of the workspaces whose history was checked.
+    histories: Vec<String>,
+}
+
+fn check_backup(src: &Path) -> Result<CheckedBackup> {
+    let invalid = |what: String| anyhow!("E_BACKUP_INVALID: {what}");
+    let raw = fs::read(src.join(MANIFEST_FILE))
+        .map_err(|e| invalid(format!("read {MANIFEST_FILE} failed: {e}")))?;
+    let manifest: BackupManifest = serde_json::from_slice(&raw)
+        .map_err(|e| invalid(format!("parse {MANIFEST_FILE} failed: {e}")))?;
+    if manifest.format != BACKUP_FORMAT {
+        return Err(invalid(format!("unknown format {}", manifest.format)));
+    }
+    if manifest.format_version > BACKUP_FORMAT_VERSION {
+        return Err(anyhow!(
+            "E_BACKUP_VERSION_UNSUPPORTED: backup format {} from app {} is newer than this app supports ({BACKUP_FORMAT_VERSION})",
+            manifest.format_version,
+            manifest.app_version
+        ));
+    }
+    let settings = if manifest.settings {
+        Some(read_json(&src.join("settings.json"))?)
+    } else {
+        None
+    };
+    let mut templates = Vec::new();
+    let mut histories = Vec::new();
+    for ws in &manifest.workspaces {
+        let name = workspaces::normalize_name(&ws.name)?;
+        let dir = backup_workspace_dir(src, &name);
+        if ws.templates {
+            templates.push((name.clone(), read_json(&dir.join(TEMPLATES_FILE))?));
+        }
+        if ws.history {
+            let db = dir.join(HISTORY_DB);
+            if !db.exists() {
+                return Err(invalid(format!("history of {name} is missing")));
+            }
+            if let Some(reason) = check_sqlite(&db)? {
+                return Err(invalid(format!("history of {name}: {reason}")));
+            }
+            histories.push(name);
+        }
+    }
+    Ok(CheckedBackup {
+        settings,
+        templates,
+        histories,
+    })
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T> {
+    let raw = fs::read(path)
+        .map_err(|e| anyhow!("E_BACKUP_INVALID: read {} failed: {e}", path.display()))?;
+    serde_json::from_slice(&raw)
+        .map_err(|e| anyhow!("E_BACKUP_INVALID: {}: invalid json: {e}", path.display()))
+}
+
+fn apply_restore(data_dir: &Path, src: &Path) -> Result<RestoreResult> {
+    let checked = check_backup(src)?;
+    write_gate::check(&settings::settings_path(data_dir))?;
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let pre = data_dir
+        .join(PRE_RESTORE_DIR)
+        .join(format!("pre-restore-{stamp}"));
+    backup_data(data_dir, &pre).context("back up current data before restore failed")?;
+
+    let mut restored = Vec::new();
+    for name in &checked.histories {
+        let dst = workspaces::workspace_history_db(data_dir, name);
+        write_gate::check(&dst)?;
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent).context("create workspace dir failed")?;
+        }
+        let from = backup_workspace_dir(src, name).join(HISTORY_DB);
+        Connection::open(&dst)
+            .and_then(|mut c| c.restore(DatabaseName::Main, from, None::<fn(Progress)>))
+            .with_context(|| format!("restore history of workspace {name} failed"))?;
+        restored.push(name.clone());
+    }
+    for (name, templates) in &checked.templates {
+        workspaces::save_templates(data_dir, name, templates)?;
+        if !restored.contains(name) {
+            restored.push(name.clone());
+        }
+    }
+    // Last, so the active workspace it names already has its data.
+    if let Some(s) = &checked.settings {
+        settings::save_settings(data_dir, s)?;
+    }
+    Ok(RestoreResult {
+        settings: checked.settings.is_some(),
+        workspaces: restored,
+        pre_restore_backup: pre.display().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{self, HistoryItem};
+
+    fn item(task_id: &str) -> HistoryItem {
+        HistoryItem {
+            task_id: task_id.to_string(),
+            created_at_ms: 1,
+            asr_text: "raw".to_string(),
+            rewritten_text: String::new(),
+            inserted_text: String::new(),
+            final_text: "final".to_string(),
+            template_id: None,
+            rtf: 0.4,
+            device_used: "cuda".to_string(),
+            preprocess_ms: 10,
+            asr_ms: 20,
+            target_app: None,
+            target_title_hash: None,
+            tags: Vec::new(),
+            note: String::new(),
+        }
+    }
+
+    fn task_ids(db: &Path) -> Vec<String> {
+        let mut ids: Vec<String> = history::list(db, 100, None)
+            .expect("list")
+            .into_iter()
+            .map(|h| h.task_id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn restore_brings_back_the_snapshot_and_keeps_the_replaced_data() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let data = tmp.path().join("data");
+        let s = Settings {
+            llm_prompt: Some("before".to_string()),
+            ..Settings::default()
+        };
+        settings::save_settings(&data, &s).expect("settings");
+        let db = workspaces::workspace_history_db(&data, workspaces::DEFAULT_WORKSPACE);
+        history::append(&db, &item("a")).expect("append");
+        workspaces::switch(&data, "work").expect("switch");
+        let work_db = workspaces::workspace_history_db(&data, "work");
+        history::append(&work_db, &item("w")).expect("append");
+
+        let dest = tmp.path().join("snapshot");
+        let out = backup_data(&data, &dest).expect("backup");
+        assert_eq!(out.manifest.workspaces.len(), 2);
+        assert!(backup_data(&data, &dest).is_err());
+
+        history::append(&db, &item("b")).expect("append");
+        settings::modify_settings(&data, |s| {
+            s.llm_prompt = Some("after".to_string());
+            Ok(true)
+        })
+        .expect("modify");
+
+        // A hand-edited name that only validates once trimmed restores from
+        // the same directory that was checked.
+        let mut manifest: serde_json::Value =
+            serde_json::from_slice(&fs::read(dest.join(MANIFEST_FILE)).unwrap()).unwrap();
+        for ws in manifest["workspaces"].as_array_mut().unwrap() {
+            if ws["name"] == "work" {
+                ws["name"] = " work ".into();
+            }
+        }
+        fs::write(dest.join(MANIFEST_FILE), manifest.to_string()).unwrap();
+
+        let restored = restore_data(&data, &dest).expect("restore");
+        assert_eq!(restored.workspaces, ["default", "work"]);
+        assert_eq!(task_ids(&db), ["a"]);
+        assert_eq!(task_ids(&work_db), ["w"]);
+        let s = settings::load_settings_strict(&data).expect("settings");
+        assert_eq!(s.active_workspace.as_deref(), Some("work"));
+        assert_eq!(s.llm_prompt.as_deref(), Some("before"));
+        let pre = PathBuf::from(&restored.pre_restore_backup);
+        assert_eq!(
+            task_ids(&backup_workspace_dir(&pre, "default").join(HISTORY_DB)),
+            ["a", "b"]
+        );
+
+        let mut manifest: serde_json::Value =
+            serde_json::from_slice(&fs::read(dest.join(MANIFEST_FILE)).unwrap()).unwrap();
+        manifest["format_version"] = (BACKUP_FORMAT_VERSION + 1).into();
+        fs::write(dest.join(MANIFEST_FILE), manifest.to_string()).unwrap();
+        let err = restore_data(&data, &dest).expect_err("newer format");
+        assert!(err.to_string().starts_with("E_BACKUP_VERSION_UNSUPPORTED"));
+    }
+}
@@ -0,0 +1,327 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use crate::history::CorrectionSample;
+
+pub const DEFAULT_MIN_OCCURRENCES: u32 = 2;
+pub const DEFAULT_SAMPLE_LIMIT: i64 = 500;
+
+// Longer texts are skipped rather than diffed; the LCS table is quadratic.
+const MAX_DIFF_TOKENS: usize = 1_500;
+// A changed run longer than this is a rewrite, not a term correction.
+const MAX_SPAN_TOKENS: usize = 8;
+const MAX_TERM_CHARS: usize = 48;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CorrectionSuggestion {
+    pub heard: String,
+    pub corrected: String,
+    pub occurrences: u32,
+    pub last_seen_ms: i64,
+    pub in_glossary: bool,
+}
+
+// One stretch of two aligned texts; `a` and `b` are equal unless `changed`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AlignedSegment {
+    pub changed: bool,
+    pub a: String,
+    pub b: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    start: usize,
+    end: usize,
+}
+
+// Han and kana are written without spaces, so each character is its own
+// token; other letters and digits group into words, punctuation stands alone.
+fn is_unspaced_script(c: char) -> bool {
+    matches!(
+        c,
+        '\u{3040}'..='\u{30FF}'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{F900}'..='\u{FAFF}'
+    )
+}
+
+fn is_word_char(c: char) -> bool {
+    (c.is_alphanumeric() && !is_unspaced_script(c)) || c == '_' || c == '\''
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut out = Vec::new();
+    let mut word_start: Option<usize> = None;
+    for (i, c) in text.char_indices() {
+        if is_word_char(c) {
+            word_start.get_or_insert(i);
+            continue;
+        }
+        if let Some(start) = word_start.take() {
+            out.push(Token {
+                text: &text[start..i],
+                start,
+                end: i,
+            });
+        }
+        if !c.is_whitespace() {
+            let end = i + c.len_utf8();
+            out.push(Token {
+                text: &text[i..end],
+                start: i,
+                end,
+            });
+        }
+    }
+    if let Some(start) = word_start {
+        out.push(Token {
+            text: &text[start..],
+            start,
+            end: text.len(),
+        });
+    }
+    out
+}
+
+// Words and unspaced-script characters, without punctuation.
+pub(crate) fn word_tokens(text: &str) -> Vec<&str> {
+    tokenize(text)
+        .into_iter()
+        .map(|t| t.text)
+        .filter(|t| t.chars().any(char::is_alphanumeric))
+        .collect()
+}
+
+// Changed runs as (heard, corrected) token ranges, from a longest common
+// subsequence over tokens.
+fn changed_runs(a: &[Token<'_>], b: &[Token<'_>]) -> Vec<((usize, usize), (usize, usize))> {
+    let (n, m) = (a.len(), b.len());
+    let mut lcs = vec![0u16; (n + 1) * (m + 1)];
+    let at = |i: usize, j: usize| i * (m + 1) + j;
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[at(i, j)] = if a[i].text == b[j].text {
+                lcs[at(i + 1, j + 1)] + 1
+            } else {
+                lcs[at(i + 1, j)].max(lcs[at(i, j + 1)])
+            };
+        }
+    }
+
+    let mut runs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let mut run_start: Option<(usize, usize)> = None;
+    while i < n || j < m {
+        if i < n && j < m && a[i].text == b[j].text {
+            if let Some((si, sj)) = run_start.take() {
+                runs.push(((si, i), (sj, j)));
+            }
+            i += 1;
+            j += 1;
+            continue;
+        }
+        run_start.get_or_insert((i, j));
+        if j < m && (i == n || lcs[at(i, j + 1)] >= lcs[at(i + 1, j)]) {
+            j += 1;
+        } else {
+            i += 1;
+        }
+    }
+    if let Some((si, sj)) = run_start {
+        runs.push(((si, n), (sj, m)));
+    }
+    runs
+}
+
+fn span_text<'a>(text: &'a str, tokens: &[Token<'_>], range: (usize, usize)) -> &'a str {
+    if range.0 >= range.1 {
+        return "";
+    }
+    &text[tokens[range.0].start..tokens[range.1 - 1].end]
+}
+
+// Token-level alignment of two transcripts of the same audio, as alternating
+// same/changed segments. Texts too long to diff come back as one changed
+// segment.
+pub fn align(a_text: &str, b_text: &str) -> Vec<AlignedSegment> {
+    let a = tokenize(a_text);
+    let b = tokenize(b_text);
+    if a.len() > MAX_DIFF_TOKENS || b.len() > MAX_DIFF_TOKENS {
+        return vec![AlignedSegment {
+            changed: a_text.trim() != b_text.trim(),
+            a: a_text.trim().to_string(),
+            b: b_text.trim().to_string(),
+        }];
+    }
+    let segment = |changed: bool, ra: (usize, usize), rb: (usize, usize)| AlignedSegment {
+        changed,
+        a: span_text(a_text, &a, ra).to_string(),
+        b: span_text(b_text, &b, rb).to_string(),
+    };
+    let mut out = Vec::new();
+    let (mut ia, mut ib) = (0, 0);
+    for (ra, rb) in changed_runs(&a, &b) {
+        if ra.0 > ia {
+            out.push(segment(false, (ia, ra.0), (ib, rb.0)));
+        }
+        out.push(segment(true, ra, rb));
+        (ia, ib) = (ra.1, rb.1);
+    }
+    if ia < a.len() {
+        out.push(segment(false, (ia, a.len()), (ib, b.len())));
+    }
+    out
+}
+
+// Term-level replacements between what ASR heard and what the user kept.
+// Pure insertions and deletions are edits, not mishearings, and are ignored.
+pub fn replacements(asr_text: &str, corrected_text: &str) -> Vec<(String, String)> {
+    let a = tokenize(asr_text);
+    let b = tokenize(corrected_text);
+    if a.len() > MAX_DIFF_TOKENS || b.len() > MAX_DIFF_TOKENS {
+        return Vec::new();
+    }
+    let mut out = Vec::new();
+    for (ra, rb) in changed_runs(&a, &b) {
+        let (la, lb) = (ra.1 - ra.0, rb.1 - rb.0);
+        if la == 0 || lb == 0 || la > MAX_SPAN_TOKENS || lb > MAX_SPAN_TOKENS {
+            continue;
+        }
+        let heard = span_text(asr_text, &a, ra);
+        let corrected = span_text(corrected_text, &b, rb);
+        if !corrected.chars().any(char::is_alphanumeric)
+            || !heard.chars().any(char::is_alphanumeric)
+            || heard.chars().count() > MAX_TERM_CHARS
+            || corrected.chars().count() > MAX_TERM_CHARS
+        {
+            continue;
+        }
+        out.push((heard.to_string(), corrected.to_string()));
+    }
+    out
+}
+
+// Corrections seen in at least `min_occurrences` separate dictations, most
+// frequent first. Each dictation counts a pair once however often it recurs.
+pub fn suggest(
+    samples: &[CorrectionSample],
+    glossary: &[String],
+    min_occurrences: u32,
+) -> Vec<CorrectionSuggestion> {
+    let mut seen: BTreeMap<(String, String), (u32, i64)> = BTreeMap::new();
+    for sample in samples {
+        let pairs: BTreeSet<(String, String)> =
+            replacements(&sample.asr_text, &sample.corrected_text)
+                .into_iter()
+                .collect();
+        for pair in pairs {
+            let entry = seen.entry(pair).or_insert((0, sample.created_at_ms));
+            entry.0 += 1;
+            entry.1 = entry.1.max(sample.created_at_ms);
+        }
+    }
+
+    let known: BTreeSet<String> = glossary
+        .iter()
+        .map(|g| g.trim().to_lowercase())
+        .filter(|g| !g.is_empty())
+        .collect();
+    let mut out: Vec<CorrectionSuggestion> = seen
+        .into_iter()
+        .filter(|(_, (count, _))| *count >= min_occurrences.max(1))
+        .map(|((heard, corrected), (occurrences, last_seen_ms))| {
+            let in_glossary = known.contains(&corrected.to_lowercase());
+            CorrectionSuggestion {
+                heard,
+                corrected,
+                occurrences,
+                last_seen_ms,
+                in_glossary,
+            }
+        })
+        .collect();
+    out.sort_by(|x, y| {
+        y.occurrences
+            .cmp(&x.occurrences)
+            .then(y.last_seen_ms.cmp(&x.last_seen_ms))
+            .then(x.heard.cmp(&y.heard))
+    });
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(ms: i64, asr: &str, corrected: &str) -> CorrectionSample {
+        CorrectionSample {
+            task_id: format!("task-{ms}"),
+            created_at_ms: ms,
+            asr_text: asr.to_string(),
+            corrected_text: corrected.to_string(),
+        }
+    }
+
+    #[test]
+    fn replacements_find_misheard_terms_in_spaced_and_unspaced_text() {
+        assert_eq!(
+            replacements("open the type voice app", "open the TypeVoice app"),
+            vec![("type voice".to_string(), "TypeVoice".to_string())]
+        );
+        assert_eq!(
+            replacements("我在用泰普沃伊斯写代码", "我在用TypeVoice写代码"),
+            vec![("泰普沃伊斯".to_string(), "TypeVoice".to_string())]
+        );
+        // Added words and punctuation-only edits are not term corrections.
+        assert!(replacements("ship it", "ship it today.").is_empty());
+        assert!(replacements("ok, go", "ok. go").is_empty());
+    }
+
+    #[test]
+    fn align_alternates_same_and_changed_segments() {
+        let seg = |changed: bool, a: &str, b: &str| AlignedSegment {
+            changed,
+            a: a.to_string(),
+            b: b.to_string(),
+        };
+        assert_eq!(
+            align(
+                "open the type voice app now",
+                "open TypeVoice app now please"
+            ),
+            vec![
+                seg(false, "open", "open"),
+                seg(true, "the type voice", "TypeVoice"),
+                seg(false, "app now", "app now"),
+                seg(true, "", "please"),
+            ]
+        );
+        assert_eq!(align("", ""), Vec::new());
+    }
+
+    #[test]
+    fn suggestions_need_repeated_corrections_and_flag_known_terms() {
+        let samples = vec![
+            sample(1, "ask clawed about it", "ask Claude about it"),
+            sample(2, "clawed said no", "Claude said no"),
+            sample(3, "push to get hub", "push to GitHub"),
+            sample(4, "then clawed left", "then Claude left"),
+        ];
+        let out = suggest(&samples, &["claude".to_string()], 2);
+        assert_eq!(
+            out,
+            vec![CorrectionSuggestion {
+                heard: "clawed".to_string(),
+                corrected: "Claude".to_string(),
+                occurrences: 3,
+                last_seen_ms: 4,
+                in_glossary: true,
+            }]
+        );
+        assert_eq!(suggest(&samples, &[], 1).len(), 2);
+    }
+}
@@ -0,0 +1,316 @@
+use std::{
+    fs::{self, File, TryLockError},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::atomic_file;
+use crate::obs::schema::now_ms;
+
+// The OS lock on LOCK_FILE excludes other processes on this machine, whatever
+// user they run as. A sync service copying the data dir to another machine
+// carries only OWNER_FILE, so a fresh heartbeat from another host also counts
+// as held.
+const LOCK_FILE: &str = "typevoice.lock";
+const OWNER_FILE: &str = "typevoice.lock.json";
+// Held only while OWNER_FILE is read and rewritten, so a heartbeat can never
+// overwrite a takeover record written between its read and its write.
+const OWNER_LOCK_FILE: &str = "typevoice.lock.owner";
+pub const HEARTBEAT: Duration = Duration::from_secs(30);
+const STALE_MS: i64 = 3 * 60_000;
+// The holder sees a takeover on its next heartbeat and lets go.
+const TAKEOVER_WAIT: Duration = Duration::from_secs(2 * 30 + 5);
+const TAKEOVER_POLL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockOwner {
+    pub instance_id: String,
+    pub pid: u32,
+    pub user: String,
+    pub host: String,
+    pub acquired_at_ms: i64,
+    pub heartbeat_ms: i64,
+}
+
+impl LockOwner {
+    fn current() -> Self {
+        let now = now_ms();
+        Self {
+            instance_id: uuid::Uuid::new_v4().to_string(),
+            pid: std::process::id(),
+            user: env_first(&["USERNAME", "USER"]).unwrap_or_default(),
+            host: host_name(),
+            acquired_at_ms: now,
+            heartbeat_ms: now,
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("{}@{} (pid {})", self.user, self.host, self.pid)
+    }
+}
+
+fn env_first(keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .filter_map(|k| std::env::var(k).ok())
+        .map(|v| v.trim().to_string())
+        .find(|v| !v.is_empty())
+}
+
+fn host_name() -> String {
+    env_first(&["COMPUTERNAME", "HOSTNAME"])
+        .or_else(|| {
+            fs::read_to_string("/etc/hostname")
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        })
+        .unwrap_or_default()
+}
+
+// The data dir this process guards, and whether it currently holds it.
+// Unset until `enforce` is called, so tools and tests write freely.
+static GUARD: Mutex<Option<(PathBuf, bool)>> = Mutex::new(None);
+
+fn set_held(data_dir: &Path, held: bool) {
+    if let Some((dir, h)) = GUARD.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        if dir == data_dir {
+            *h = held;
+        }
+    }
+}
+
+// From now on writes under `data_dir` fail with E_DATA_DIR_LOCKED unless this
+// process holds its lock.
+pub fn enforce(data_dir: &Path) {
+    *GUARD.lock().unwrap_or_else(|e| e.into_inner()) = Some((data_dir.to_path_buf(), false));
+}
+
+pub fn check_writable(path: &Path) -> Result<()> {
+    let guard = GUARD.lock().unwrap_or_else(|e| e.into_inner());
+    match guard.as_ref() {
+        Some((dir, false)) if path.starts_with(dir) => Err(anyhow!(
+            "E_DATA_DIR_LOCKED: the data dir is held by another TypeVoice instance; take over to write"
+        )),
+        _ => Ok(()),
+    }
+}
+
+pub fn read_owner(data_dir: &Path) -> Option<LockOwner> {
+    let s = fs::read_to_string(data_dir.join(OWNER_FILE)).ok()?;
+    serde_json::from_str(&s).ok()
+}
+
+// Runs `f` with the owner record locked against other processes.
+fn with_owner_lock<T>(data_dir: &Path, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .read(true)
+        .write(true)
+        .truncate(false)
+        .open(data_dir.join(OWNER_LOCK_FILE))
+        .context("E_DATA_DIR_LOCK: open owner lock file failed")?;
+    file.lock()
+        .map_err(|e| anyhow!("E_DATA_DIR_LOCK: lock owner record failed: {e}"))?;
+    let out = f();
+    let _ = file.unlock();
+    out
+}
+
+// Refreshes `owner`'s heartbeat unless another instance has claimed the
+// record; false means this instance has been taken over.
+pub fn refresh_heartbeat(data_dir: &Path, owner: &mut LockOwner) -> Result<bool> {
+    with_owner_lock(data_dir, || match read_owner(data_dir) {
+        Some(o) if o.instance_id != owner.instance_id => Ok(false),
+        _ => {
+            owner.heartbeat_ms = now_ms();
+            write_owner(data_dir, owner)?;
+            Ok(true)
+        }
+    })
+}
+
+// Removes the owner record if it is still `instance_id`'s.
+fn clear_owner(data_dir: &Path, instance_id: &str) {
+    let _ = with_owner_lock(data_dir, || {
+        if read_owner(data_dir).is_some_and(|o| o.instance_id == instance_id) {
+            let _ = fs::remove_file(data_dir.join(OWNER_FILE));
+        }
+        Ok(())
+    });
+}
+
+fn write_owner(data_dir: &Path, owner: &LockOwner) -> Result<()> {
+    let s = serde_json::to_vec_pretty(owner).context("serialize lock owner failed")?;
+    atomic_file::write_unchecked(&data_dir.join(OWNER_FILE), &s)
+        .context("E_DATA_DIR_LOCK: write lock owner failed")
+}
+
+fn locked_error(owner: Option<&LockOwner>) -> anyhow::Error {
+    let by = owner
+        .map(LockOwner::describe)
+        .unwrap_or_else(|| "another process".to_string());
+    anyhow!("E_DATA_DIR_LOCKED: the data dir is in use by {by}; close it or take over")
+}
+
+// Held for the life of the process; dropping it releases the data dir.
+#[derive(Debug)]
+pub struct DataDirLock {
+    dir: PathBuf,
+    file: File,
+    owner: LockOwner,
+}
+
+impl DataDirLock {
+    pub fn owner(&self) -> &LockOwner {
+        &self.owner
+    }
+
+    // Refreshes the heartbeat. Returns false once another instance has taken
+    // over; the caller must then drop the lock.
+    pub fn heartbeat(&mut self) -> Result<bool> {
+        refresh_heartbeat(&self.dir, &mut self.owner)
+    }
+
+    // Records a heartbeat taken with `refresh_heartbeat` on a copy of the owner.
+    pub fn note_heartbeat(&mut self, heartbeat_ms: i64) {
+        self.owner.heartbeat_ms = heartbeat_ms;
+    }
+}
+
+impl Drop for DataDirLock {
+    fn drop(&mut self) {
+        set_held(&self.dir, false);
+        clear_owner(&self.dir, &self.owner.instance_id);
+        let _ = self.file.unlock();
+    }
+}
+
+fn try_os_lock(file: &File) -> Result<bool> {
+    match file.try_lock() {
+        Ok(()) => Ok(true),
+        Err(TryLockError::WouldBlock) => Ok(false),
+        Err(TryLockError::Error(e)) => Err(anyhow!("E_DATA_DIR_LOCK: lock data dir failed: {e}")),
+    }
+}
+
+// Locks the data dir for this process. With `takeover` an existing holder is
+// asked to let go and this waits for it; a holder that is gone, or a stale
+// heartbeat from another host, never needs a takeover.
+pub fn acquire(data_dir: &Path, takeover: bool) -> Result<DataDirLock> {
+    fs::create_dir_all(data_dir).context("create data dir failed")?;
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .read(true)
+        .write(true)
+        .truncate(false)
+        .open(data_dir.join(LOCK_FILE))
+        .context("E_DATA_DIR_LOCK: open lock file failed")?;
+    let owner = LockOwner::current();
+
+    if takeover {
+        with_owner_lock(data_dir, || write_owner(data_dir, &owner))?;
+        let started = Instant::now();
+        while !try_os_lock(&file)? {
+            if started.elapsed() >= TAKEOVER_WAIT {
+                // Hand the record back so the holder keeps its heartbeat going.
+                clear_owner(data_dir, &owner.instance_id);
+                return Err(anyhow!(
+                    "E_DATA_DIR_TAKEOVER_TIMEOUT: the other instance did not release the data dir"
+                ));
+            }
+            std::thread::sleep(TAKEOVER_POLL);
+        }
+    } else {
+        let previous = read_owner(data_dir);
+        if !try_os_lock(&file)? {
+            return Err(locked_error(previous.as_ref()));
+        }
+        let remote_alive = previous.as_ref().is_some_and(|o| {
+            o.host != owner.host && now_ms().saturating_sub(o.heartbeat_ms) < STALE_MS
+        });
+        // A record that changed since it was read belongs to a takeover in
+        // progress; that instance gets the data dir.
+        let claimed = !remote_alive
+            && with_owner_lock(data_dir, || {
+                if read_owner(data_dir) != previous {
+                    return Ok(false);
+                }
+                write_owner(data_dir, &owner)?;
+                Ok(true)
+            })?;
+        if !claimed {
+            let _ = file.unlock();
+            return Err(locked_error(read_owner(data_dir).as_ref()));
+        }
+    }
+    set_held(data_dir, true);
+    Ok(DataDirLock {
+        dir: data_dir.to_path_buf(),
+        file,
+        owner,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_acquire_is_locked_and_remote_heartbeats_count() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let dir = tmp.path();
+        let mut first = acquire(dir, false).expect("first");
+        let err = acquire(dir, false).expect_err("locked");
+        assert!(err.to_string().starts_with("E_DATA_DIR_LOCKED"));
+        assert!(first.heartbeat().expect("heartbeat"));
+        drop(first);
+        assert!(read_owner(dir).is_none());
+
+        // A synced owner file from another host with a fresh heartbeat.
+        let remote = LockOwner {
+            host: "other-host".to_string(),
+            ..LockOwner::current()
+        };
+        write_owner(dir, &remote).expect("owner");
+        assert!(acquire(dir, false).is_err());
+        write_owner(
+            dir,
+            &LockOwner {
+                heartbeat_ms: now_ms() - STALE_MS - 1,
+                ..remote
+            },
+        )
+        .expect("owner");
+        let lock = acquire(dir, false).expect("stale owner");
+        assert_eq!(read_owner(dir).as_ref(), Some(lock.owner()));
+    }
+
+    #[test]
+    fn heartbeat_yields_to_takeover_record_and_timeout_clears_it() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let dir = tmp.path();
+        let mut held = acquire(dir, false).expect("held");
+
+        // A takeover that gave up hands the record back.
+        let waiting = LockOwner::current();
+        write_owner(dir, &waiting).expect("owner");
+        clear_owner(dir, &waiting.instance_id);
+        assert!(read_owner(dir).is_none());
+        assert!(held.heartbeat().expect("heartbeat"));
+        assert_eq!(read_owner(dir).as_ref(), Some(held.owner()));
+
+        // A takeover record is never overwritten by the old holder.
+        let mut copy = held.owner().clone();
+        with_owner_lock(dir, || write_owner(dir, &waiting)).expect("takeover record");
+        assert!(!refresh_heartbeat(dir, &mut copy).expect("heartbeat"));
+        assert_eq!(read_owner(dir), Some(waiting.clone()));
+        held.note_heartbeat(copy.heartbeat_ms);
+        drop(held);
+        assert_eq!(read_owner(dir), Some(waiting));
+    }
+}
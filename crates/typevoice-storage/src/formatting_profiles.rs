@@ -0,0 +1,555 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::atomic_file;
+use crate::obs::Span;
+
+pub const MIN_WRAP_COLUMNS: u64 = 20;
+pub const MAX_WRAP_COLUMNS: u64 = 400;
+
+// Terminal emulators and console hosts recognised without a profile. Shells
+// and multiplexers such as tmux run inside one of these, so the foreground
+// process is always the emulator.
+pub const TERMINAL_PROCESS_NAMES: &[&str] = &[
+    "windowsterminal.exe",
+    "openconsole.exe",
+    "conhost.exe",
+    "cmd.exe",
+    "powershell.exe",
+    "pwsh.exe",
+    "wezterm-gui.exe",
+    "alacritty.exe",
+    "mintty.exe",
+    "kitty",
+    "alacritty",
+    "wezterm-gui",
+    "gnome-terminal-server",
+    "konsole",
+    "xterm",
+    "foot",
+    "tilix",
+];
+
+const BRACKETED_PASTE_START: &str = "\x1b[200~";
+const BRACKETED_PASTE_END: &str = "\x1b[201~";
+
+// How text reaches a terminal. `ClipboardShortcut` leaves bracketed paste to
+// the terminal itself, which only wraps the paste when the running program
+// asked for it; `BracketedPaste` types the markers directly and needs a shell
+// or tmux pane with bracketed paste enabled; `Typed` joins lines so nothing
+// runs before the user presses Enter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminalPasteMode {
+    ClipboardShortcut,
+    BracketedPaste,
+    Typed,
+}
+
+impl TerminalPasteMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ClipboardShortcut => "clipboard_shortcut",
+            Self::BracketedPaste => "bracketed_paste",
+            Self::Typed => "typed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TerminalExport {
+    pub profile_id: Option<String>,
+    pub mode: TerminalPasteMode,
+    pub strip_trailing_newline: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormattingProfile {
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Matched case-insensitively against the file name of the captured
+    // previous-window process image, e.g. "slack.exe".
+    #[serde(default)]
+    pub process_names: Vec<String>,
+    #[serde(default)]
+    pub strip_trailing_newline: bool,
+    #[serde(default)]
+    pub wrap_columns: Option<u64>,
+    #[serde(default)]
+    pub line_prefix: Option<String>,
+    #[serde(default)]
+    pub greeting: Option<String>,
+    #[serde(default)]
+    pub sign_off: Option<String>,
+    // Set on profiles for terminal targets; export then goes through the
+    // terminal paste path instead of text injection.
+    #[serde(default)]
+    pub terminal_paste: Option<TerminalPasteMode>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+// Built-ins ship disabled so output only changes once the user opts in.
+// Known terminals still get the clipboard-shortcut default without a profile.
+pub fn default_profiles() -> Vec<FormattingProfile> {
+    vec![
+        FormattingProfile {
+            id: "slack".to_string(),
+            name: "Slack".to_string(),
+            enabled: false,
+            process_names: vec!["slack.exe".to_string(), "slack".to_string()],
+            strip_trailing_newline: true,
+            wrap_columns: None,
+            line_prefix: None,
+            greeting: None,
+            sign_off: None,
+            terminal_paste: None,
+        },
+        FormattingProfile {
+            id: "vscode".to_string(),
+            name: "VS Code".to_string(),
+            enabled: false,
+            process_names: vec!["code.exe".to_string(), "code".to_string()],
+            strip_trailing_newline: true,
+            wrap_columns: Some(80),
+            line_prefix: Some("// ".to_string()),
+            greeting: None,
+            sign_off: None,
+            terminal_paste: None,
+        },
+        FormattingProfile {
+            id: "outlook".to_string(),
+            name: "Outlook".to_string(),
+            enabled: false,
+            process_names: vec!["outlook.exe".to_string(), "olk.exe".to_string()],
+            strip_trailing_newline: false,
+            wrap_columns: None,
+            line_prefix: None,
+            greeting: Some("Hi,".to_string()),
+            sign_off: Some("Best regards,".to_string()),
+            terminal_paste: None,
+        },
+        FormattingProfile {
+            id: "terminals".to_string(),
+            name: "Terminals".to_string(),
+            enabled: false,
+            process_names: TERMINAL_PROCESS_NAMES
+                .iter()
+                .map(|v| v.to_string())
+                .collect(),
+            strip_trailing_newline: true,
+            wrap_columns: None,
+            line_prefix: None,
+            greeting: None,
+            sign_off: None,
+            terminal_paste: Some(TerminalPasteMode::ClipboardShortcut),
+        },
+    ]
+}
+
+pub fn profiles_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("formatting_profiles.json")
+}
+
+pub fn load_profiles(data_dir: &Path) -> Result<Vec<FormattingProfile>> {
+    let p = profiles_path(data_dir);
+    if !p.exists() {
+        return Ok(default_profiles());
+    }
+    let s = fs::read_to_string(&p).context("read formatting_profiles.json failed")?;
+    let v: Vec<FormattingProfile> =
+        serde_json::from_str(&s).context("parse formatting_profiles.json failed")?;
+    Ok(v)
+}
+
+pub fn save_profiles(data_dir: &Path, profiles: &[FormattingProfile]) -> Result<()> {
+    let span = Span::start(
+        data_dir,
+        None,
+        "Settings",
+        "FORMATTING_PROFILES.save",
+        Some(serde_json::json!({"count": profiles.len()})),
+    );
+    std::fs::create_dir_all(data_dir).context("create data dir failed")?;
+    let s = serde_json::to_string_pretty(profiles).context("serialize profiles failed")?;
+    if let Err(e) = atomic_file::write(&profiles_path(data_dir), s.as_bytes()) {
+        let ae = anyhow!("write formatting_profiles.json failed: {e}");
+        span.err_anyhow("io", "E_FORMATTING_PROFILES_WRITE", &ae, None);
+        return Err(ae);
+    }
+    span.ok(None);
+    Ok(())
+}
+
+pub fn normalize_profile(mut profile: FormattingProfile) -> Result<FormattingProfile> {
+    profile.id = profile.id.trim().to_string();
+    if profile.id.is_empty() {
+        return Err(anyhow!(
+            "E_FORMATTING_PROFILE_ID_MISSING: profile id is required"
+        ));
+    }
+    profile.name = profile.name.trim().to_string();
+    if profile.name.is_empty() {
+        profile.name = profile.id.clone();
+    }
+    profile.process_names = profile
+        .process_names
+        .iter()
+        .map(|v| v.trim().to_ascii_lowercase())
+        .filter(|v| !v.is_empty())
+        .collect();
+    if profile.process_names.is_empty() {
+        return Err(anyhow!(
+            "E_FORMATTING_PROFILE_PROCESS_MISSING: at least one process name is required"
+        ));
+    }
+    if let Some(cols) = profile.wrap_columns {
+        if !(MIN_WRAP_COLUMNS..=MAX_WRAP_COLUMNS).contains(&cols) {
+            return Err(anyhow!(
+                "E_FORMATTING_PROFILE_WRAP_INVALID: wrap_columns must be between {MIN_WRAP_COLUMNS} and {MAX_WRAP_COLUMNS}"
+            ));
+        }
+    }
+    profile.line_prefix = profile.line_prefix.filter(|v| !v.is_empty());
+    profile.greeting = profile.greeting.filter(|v| !v.trim().is_empty());
+    profile.sign_off = profile.sign_off.filter(|v| !v.trim().is_empty());
+    Ok(profile)
+}
+
+pub fn upsert_profile(
+    data_dir: &Path,
+    profile: FormattingProfile,
+) -> Result<Vec<FormattingProfile>> {
+    let profile = normalize_profile(profile)?;
+    let mut profiles = load_profiles(data_dir)?;
+    match profiles.iter_mut().find(|p| p.id == profile.id) {
+        Some(existing) => *existing = profile,
+        None => profiles.push(profile),
+    }
+    save_profiles(data_dir, &profiles)?;
+    Ok(profiles)
+}
+
+pub fn delete_profile(data_dir: &Path, id: &str) -> Result<Vec<FormattingProfile>> {
+    let mut profiles = load_profiles(data_dir)?;
+    let before = profiles.len();
+    profiles.retain(|p| p.id != id.trim());
+    if profiles.len() == before {
+        return Err(anyhow!(
+            "E_FORMATTING_PROFILE_NOT_FOUND: profile '{}' not found",
+            id.trim()
+        ));
+    }
+    save_profiles(data_dir, &profiles)?;
+    Ok(profiles)
+}
+
+// History rows store the same lowercase file name.
+pub fn process_file_name(process_image: &str) -> String {
+    process_image
+        .rsplit(['\\', '/'])
+        .next()
+        .unwrap_or(process_image)
+        .trim()
+        .to_ascii_lowercase()
+}
+
+pub fn match_profile<'a>(
+    profiles: &'a [FormattingProfile],
+    process_image: Option<&str>,
+) -> Option<&'a FormattingProfile> {
+    let name = process_file_name(process_image?);
+    if name.is_empty() {
+        return None;
+    }
+    profiles.iter().filter(|p| p.enabled).find(|p| {
+        p.process_names
+            .iter()
+            .any(|n| n.eq_ignore_ascii_case(&name))
+    })
+}
+
+pub fn apply_profile(profile: &FormattingProfile, text: &str) -> String {
+    let mut body = text.to_string();
+    if let Some(cols) = profile.wrap_columns {
+        let prefix_chars = profile
+            .line_prefix
+            .as_deref()
+            .map(|p| p.chars().count())
+            .unwrap_or(0);
+        let width = (cols as usize).saturating_sub(prefix_chars).max(1);
+        body = wrap_text(&body, width);
+    }
+    if let Some(prefix) = profile.line_prefix.as_deref() {
+        body = body
+            .lines()
+            .map(|line| format!("{prefix}{line}").trim_end().to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+    if let Some(greeting) = profile.greeting.as_deref() {
+        if !body.trim_start().starts_with(greeting.trim()) {
+            body = format!("{}\n\n{}", greeting.trim(), body.trim_start());
+        }
+    }
+    if let Some(sign_off) = profile.sign_off.as_deref() {
+        if !body.trim_end().ends_with(sign_off.trim()) {
+            body = format!("{}\n\n{}", body.trim_end(), sign_off.trim());
+        }
+    }
+    if profile.strip_trailing_newline {
+        body = body.trim_end_matches(['\r', '\n']).to_string();
+    }
+    body
+}
+
+// A matching profile with `terminal_paste` decides the mode and newline
+// handling. Known terminals without one keep the built-in default: clipboard
+// shortcut with trailing newlines stripped.
+pub fn resolve_terminal_export(
+    profiles: &[FormattingProfile],
+    process_image: Option<&str>,
+) -> Option<TerminalExport> {
+    if let Some(p) = match_profile(profiles, process_image) {
+        if let Some(mode) = p.terminal_paste {
+            return Some(TerminalExport {
+                profile_id: Some(p.id.clone()),
+                mode,
+                strip_trailing_newline: p.strip_trailing_newline,
+            });
+        }
+    }
+    let name = process_file_name(process_image?);
+    TERMINAL_PROCESS_NAMES
+        .contains(&name.as_str())
+        .then_some(TerminalExport {
+            profile_id: None,
+            mode: TerminalPasteMode::ClipboardShortcut,
+            strip_trailing_newline: true,
+        })
+}
+
+// Text as it should land in the terminal. Control characters other than
+// newlines and tabs are dropped so dictated text can never carry its own
+// escape sequence, in particular an early bracketed-paste end marker.
+pub fn terminal_text(cfg: &TerminalExport, text: &str) -> String {
+    let mut out: String = text
+        .replace("\r\n", "\n")
+        .chars()
+        .filter(|c| !c.is_control() || *c == '\n' || *c == '\t')
+        .collect();
+    if cfg.strip_trailing_newline {
+        out.truncate(out.trim_end_matches('\n').len());
+    }
+    if cfg.mode == TerminalPasteMode::Typed {
+        out = out.split('\n').collect::<Vec<_>>().join(" ");
+    }
+    out
+}
+
+// Characters to inject for the typed modes. Terminals submit a line on CR, so
+// newlines inside a bracketed paste are sent as CR like a real paste would.
+pub fn terminal_keystrokes(mode: TerminalPasteMode, text: &str) -> Option<String> {
+    match mode {
+        TerminalPasteMode::ClipboardShortcut => None,
+        TerminalPasteMode::Typed => Some(text.to_string()),
+        TerminalPasteMode::BracketedPaste => Some(format!(
+            "{BRACKETED_PASTE_START}{}{BRACKETED_PASTE_END}",
+            text.replace('\n', "\r")
+        )),
+    }
+}
+
+// Breaks only at whitespace and drops just the run a break lands on;
+// indentation, repeated spaces and line endings stay as written.
+fn wrap_text(text: &str, width: usize) -> String {
+    let mut out = Vec::new();
+    for line in text.split('\n') {
+        if line.chars().count() <= width {
+            out.push(line.to_string());
+            continue;
+        }
+        let mut current = String::new();
+        let mut has_word = false;
+        let mut pending = "";
+        for run in whitespace_runs(line) {
+            if run.starts_with(char::is_whitespace) {
+                if has_word {
+                    pending = run;
+                } else {
+                    current.push_str(run);
+                }
+                continue;
+            }
+            let len = current.chars().count() + pending.chars().count() + run.chars().count();
+            if has_word && len > width {
+                out.push(std::mem::take(&mut current));
+            } else {
+                current.push_str(pending);
+            }
+            current.push_str(run);
+            has_word = true;
+            pending = "";
+        }
+        current.push_str(pending);
+        out.push(current);
+    }
+    out.join("\n")
+}
+
+// Alternating runs of whitespace and non-whitespace, covering the whole line.
+fn whitespace_runs(line: &str) -> Vec<&str> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    let mut prev_ws = None;
+    for (i, c) in line.char_indices() {
+        let ws = c.is_whitespace();
+        if prev_ws.is_some_and(|p| p != ws) {
+            runs.push(&line[start..i]);
+            start = i;
+        }
+        prev_ws = Some(ws);
+    }
+    if start < line.len() {
+        runs.push(&line[start..]);
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled_defaults() -> Vec<FormattingProfile> {
+        default_profiles()
+            .into_iter()
+            .map(|p| FormattingProfile { enabled: true, ..p })
+            .collect()
+    }
+
+    #[test]
+    fn match_profile_uses_process_file_name() {
+        assert!(default_profiles().iter().all(|p| !p.enabled));
+        assert!(match_profile(&default_profiles(), Some("slack.exe")).is_none());
+        let profiles = enabled_defaults();
+        let hit = match_profile(
+            &profiles,
+            Some(r"C:\Users\me\AppData\Local\slack\app-4.41\Slack.exe"),
+        );
+        assert_eq!(hit.map(|p| p.id.as_str()), Some("slack"));
+        assert!(match_profile(&profiles, Some("/usr/bin/firefox")).is_none());
+        assert!(match_profile(&profiles, None).is_none());
+    }
+
+    #[test]
+    fn apply_profile_wraps_prefixes_and_adds_greeting() {
+        let profiles = enabled_defaults();
+        let vscode = profiles.iter().find(|p| p.id == "vscode").unwrap();
+        let out = apply_profile(
+            vscode,
+            "this comment explains why the cache must be refreshed before the device list is read again\n",
+        );
+        for line in out.lines() {
+            assert!(line.starts_with("// "));
+            assert!(line.chars().count() <= 80);
+        }
+        assert!(!out.ends_with('\n'));
+
+        let outlook = profiles.iter().find(|p| p.id == "outlook").unwrap();
+        let out = apply_profile(outlook, "The report is attached.");
+        assert_eq!(out, "Hi,\n\nThe report is attached.\n\nBest regards,");
+    }
+
+    #[test]
+    fn wrap_text_keeps_whitespace_as_written() {
+        assert_eq!(
+            wrap_text("  indented  line with   gaps\nshort\n", 12),
+            "  indented\nline with\ngaps\nshort\n"
+        );
+        assert_eq!(wrap_text("a  b\tc", 20), "a  b\tc");
+        assert_eq!(wrap_text("one two  three four", 9), "one two\nthree\nfour");
+    }
+
+    #[test]
+    fn terminals_get_clipboard_shortcut_and_profile_overrides() {
+        let builtin = resolve_terminal_export(&default_profiles(), Some("WindowsTerminal.exe"))
+            .expect("terminal without an enabled profile");
+        assert_eq!(builtin.mode, TerminalPasteMode::ClipboardShortcut);
+        assert_eq!(builtin.profile_id, None);
+
+        let profiles = enabled_defaults();
+        let wt = resolve_terminal_export(
+            &profiles,
+            Some(r"C:\Program Files\WindowsApps\WindowsTerminal.exe"),
+        )
+        .expect("windows terminal");
+        assert_eq!(wt.mode, TerminalPasteMode::ClipboardShortcut);
+        assert!(wt.strip_trailing_newline);
+        assert_eq!(wt.profile_id.as_deref(), Some("terminals"));
+        assert!(resolve_terminal_export(&profiles, Some("slack.exe")).is_none());
+
+        // Saved profiles from before terminal support still detect terminals.
+        let legacy: Vec<_> = profiles
+            .iter()
+            .filter(|p| p.terminal_paste.is_none())
+            .cloned()
+            .collect();
+        let hit = resolve_terminal_export(&legacy, Some("/usr/bin/konsole")).expect("konsole");
+        assert_eq!(hit.profile_id, None);
+
+        let mut custom = profiles.clone();
+        custom.insert(
+            0,
+            FormattingProfile {
+                id: "wezterm".to_string(),
+                name: "WezTerm".to_string(),
+                enabled: true,
+                process_names: vec!["wezterm-gui.exe".to_string()],
+                strip_trailing_newline: false,
+                wrap_columns: None,
+                line_prefix: None,
+                greeting: None,
+                sign_off: None,
+                terminal_paste: Some(TerminalPasteMode::BracketedPaste),
+            },
+        );
+        let hit = resolve_terminal_export(&custom, Some("wezterm-gui.exe")).expect("wezterm");
+        assert_eq!(hit.mode, TerminalPasteMode::BracketedPaste);
+        assert!(!hit.strip_trailing_newline);
+    }
+
+    #[test]
+    fn terminal_text_strips_newlines_and_escape_sequences() {
+        let cfg = TerminalExport {
+            profile_id: None,
+            mode: TerminalPasteMode::BracketedPaste,
+            strip_trailing_newline: true,
+        };
+        let text = terminal_text(&cfg, "git status\r\necho \x1b[201~done\n\n");
+        assert_eq!(text, "git status\necho [201~done");
+        assert_eq!(
+            terminal_keystrokes(cfg.mode, &text).as_deref(),
+            Some("\x1b[200~git status\recho [201~done\x1b[201~")
+        );
+
+        let typed = TerminalExport {
+            mode: TerminalPasteMode::Typed,
+            strip_trailing_newline: false,
+            ..cfg
+        };
+        assert_eq!(terminal_text(&typed, "ls\n-la\n"), "ls -la ");
+        assert_eq!(
+            terminal_keystrokes(TerminalPasteMode::ClipboardShortcut, "ls"),
+            None
+        );
+    }
+}
@@ -1,10 +1,13 @@
 use std::path::Path;
 
 use anyhow::{Context, Result};
-use rusqlite::{params, Connection};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection};
 use serde::{Deserialize, Serialize};
 
 use crate::obs::Span;
+use crate::timestamps::{self, TimeFormat};
+use crate::write_gate;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HistoryItem {
@@ -19,6 +22,100 @@ pub struct HistoryItem {
     pub device_used: String,
     pub preprocess_ms: i64,
     pub asr_ms: i64,
+    // File name of the app that was focused when dictation started.
+    #[serde(default)]
+    pub target_app: Option<String>,
+    // SHA-256 of that window's title: groups dictation per document or
+    // conversation without keeping the title itself.
+    #[serde(default)]
+    pub target_title_hash: Option<String>,
+    // Set by the user after the fact to organize dictations.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub note: String,
+}
+
+// A row as the UI and exports show it: the stored item plus its creation
+// time in the local zone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimedHistoryItem {
+    #[serde(flatten)]
+    pub item: HistoryItem,
+    pub created_at_iso: String,
+    pub created_at_display: String,
+}
+
+impl TimedHistoryItem {
+    pub fn new(item: HistoryItem, format: TimeFormat) -> Self {
+        let t = timestamps::format_local(item.created_at_ms, format);
+        Self {
+            item,
+            created_at_iso: t.iso,
+            created_at_display: t.display,
+        }
+    }
+}
+
+// Final text the user edited by hand, next to what ASR heard.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CorrectionSample {
+    pub task_id: String,
+    pub created_at_ms: i64,
+    pub asr_text: String,
+    pub corrected_text: String,
+}
+
+// Rows picked by a bulk operation: `from_ms` inclusive, `to_ms` exclusive,
+// and every field that is set must match.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoryFilter {
+    pub from_ms: Option<i64>,
+    pub to_ms: Option<i64>,
+    pub tag: Option<String>,
+    pub target_app: Option<String>,
+    pub template_id: Option<String>,
+}
+
+impl HistoryFilter {
+    pub fn is_empty(&self) -> bool {
+        self.from_ms.is_none()
+            && self.to_ms.is_none()
+            && self.tag.is_none()
+            && self.target_app.is_none()
+            && self.template_id.is_none()
+    }
+
+    fn where_clause(&self) -> (String, Vec<Value>) {
+        let mut terms = vec!["1 = 1".to_string()];
+        let mut args = Vec::new();
+        let mut push = |term: &str, arg: Value| {
+            args.push(arg);
+            terms.push(term.replace('?', &format!("?{}", args.len())));
+        };
+        if let Some(ms) = self.from_ms {
+            push("created_at_ms >= ?", Value::Integer(ms));
+        }
+        if let Some(ms) = self.to_ms {
+            push("created_at_ms < ?", Value::Integer(ms));
+        }
+        if let Some(tag) = &self.tag {
+            let tag = normalize_tags(std::slice::from_ref(tag))
+                .pop()
+                .unwrap_or_default();
+            push(
+                "EXISTS (SELECT 1 FROM json_each(history.tags) WHERE value = ?)",
+                Value::Text(tag),
+            );
+        }
+        if let Some(app) = &self.target_app {
+            push("target_app = ? COLLATE NOCASE", Value::Text(app.clone()));
+        }
+        if let Some(id) = &self.template_id {
+            push("template_id = ?", Value::Text(id.clone()));
+        }
+        (terms.join(" AND "), args)
+    }
 }
 
 fn conn(db_path: &Path) -> Result<Connection> {
@@ -44,9 +141,72 @@ fn conn(db_path: &Path) -> Result<Connection> {
     .context("init sqlite schema failed")?;
     ensure_column(&c, "rewritten_text", "TEXT NOT NULL DEFAULT ''")?;
     ensure_column(&c, "inserted_text", "TEXT NOT NULL DEFAULT ''")?;
+    ensure_column(&c, "corrected_text", "TEXT NOT NULL DEFAULT ''")?;
+    ensure_column(&c, "recording_path", "TEXT NULL")?;
+    ensure_column(&c, "target_app", "TEXT NULL")?;
+    ensure_column(&c, "target_title_hash", "TEXT NULL")?;
+    // JSON array of normalized tags.
+    ensure_column(&c, "tags", "TEXT NOT NULL DEFAULT '[]'")?;
+    ensure_column(&c, "note", "TEXT NOT NULL DEFAULT ''")?;
+    ensure_search_index(&c)?;
     Ok(c)
 }
 
+// Full-text index over the dictated and final text, kept in step by triggers.
+// Trigram rather than unicode61 so Chinese matches inside a run of characters.
+// `append` uses INSERT OR REPLACE, whose implicit delete only fires the delete
+// trigger with recursive_triggers on.
+fn ensure_search_index(c: &Connection) -> Result<()> {
+    c.execute_batch("PRAGMA recursive_triggers = ON")
+        .context("enable recursive triggers failed")?;
+    let existed: bool = c
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'history_fts')",
+            [],
+            |row| row.get(0),
+        )
+        .context("inspect history search index failed")?;
+    c.execute_batch(
+        r#"
+        CREATE VIRTUAL TABLE IF NOT EXISTS history_fts USING fts5(
+          asr_text, final_text,
+          content = 'history', content_rowid = 'rowid', tokenize = 'trigram'
+        );
+        CREATE TRIGGER IF NOT EXISTS history_fts_insert AFTER INSERT ON history BEGIN
+          INSERT INTO history_fts(rowid, asr_text, final_text)
+          VALUES (new.rowid, new.asr_text, new.final_text);
+        END;
+        CREATE TRIGGER IF NOT EXISTS history_fts_delete AFTER DELETE ON history BEGIN
+          INSERT INTO history_fts(history_fts, rowid, asr_text, final_text)
+          VALUES ('delete', old.rowid, old.asr_text, old.final_text);
+        END;
+        CREATE TRIGGER IF NOT EXISTS history_fts_update AFTER UPDATE OF asr_text, final_text ON history BEGIN
+          INSERT INTO history_fts(history_fts, rowid, asr_text, final_text)
+          VALUES ('delete', old.rowid, old.asr_text, old.final_text);
+          INSERT INTO history_fts(rowid, asr_text, final_text)
+          VALUES (new.rowid, new.asr_text, new.final_text);
+        END;
+        "#,
+    )
+    .context("init history search index failed")?;
+    if !existed {
+        // Rows written before the index existed.
+        c.execute(
+            "INSERT INTO history_fts(history_fts) VALUES ('rebuild')",
+            [],
+        )
+        .context("build history search index failed")?;
+    }
+    Ok(())
+}
+
+// Reads stay open in guest mode and to an instance that lost the data dir
+// lock; writes do not.
+fn write_conn(db_path: &Path) -> Result<Connection> {
+    write_gate::check(db_path)?;
+    conn(db_path)
+}
+
 fn ensure_column(c: &Connection, column: &str, definition: &str) -> Result<()> {
     let mut stmt = c
         .prepare("PRAGMA table_info(history)")
@@ -67,6 +227,44 @@ fn ensure_column(c: &Connection, column: &str, definition: &str) -> Result<()> {
     Ok(())
 }
 
+fn item_from_row(row: &rusqlite::Row) -> rusqlite::Result<HistoryItem> {
+    Ok(HistoryItem {
+        task_id: row.get(0)?,
+        created_at_ms: row.get(1)?,
+        asr_text: row.get(2)?,
+        rewritten_text: row.get(3)?,
+        inserted_text: row.get(4)?,
+        final_text: row.get(5)?,
+        template_id: row.get(6)?,
+        rtf: row.get(7)?,
+        device_used: row.get(8)?,
+        preprocess_ms: row.get(9)?,
+        asr_ms: row.get(10)?,
+        target_app: row.get(11)?,
+        target_title_hash: row.get(12)?,
+        tags: serde_json::from_str(&row.get::<_, String>(13)?).unwrap_or_default(),
+        note: row.get(14)?,
+    })
+}
+
+// Tags compare as typed but case-folded: trimmed, lowercased, inner
+// whitespace collapsed, empties and duplicates dropped, sorted.
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = tags
+        .iter()
+        .map(|t| {
+            t.split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_lowercase()
+        })
+        .filter(|t| !t.is_empty())
+        .collect();
+    out.sort();
+    out.dedup();
+    out
+}
+
 pub fn append(db_path: &Path, item: &HistoryItem) -> Result<()> {
     let data_dir = db_path.parent().unwrap_or_else(|| Path::new("."));
     let span = Span::start(
@@ -83,7 +281,7 @@ pub fn append(db_path: &Path, item: &HistoryItem) -> Result<()> {
         })),
     );
 
-    let c = match conn(db_path) {
+    let c = match write_conn(db_path) {
         Ok(c) => c,
         Err(e) => {
             span.err_anyhow("db", "E_HISTORY_CONN", &e, None);
@@ -93,8 +291,8 @@ pub fn append(db_path: &Path, item: &HistoryItem) -> Result<()> {
     let r = c.execute(
         r#"
         INSERT OR REPLACE INTO history
-        (task_id, created_at_ms, asr_text, rewritten_text, inserted_text, final_text, template_id, rtf, device_used, preprocess_ms, asr_ms)
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+        (task_id, created_at_ms, asr_text, rewritten_text, inserted_text, final_text, template_id, rtf, device_used, preprocess_ms, asr_ms, target_app, target_title_hash, tags, note)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
         "#,
         params![
             item.task_id,
@@ -108,6 +306,10 @@ pub fn append(db_path: &Path, item: &HistoryItem) -> Result<()> {
             item.device_used,
             item.preprocess_ms,
             item.asr_ms,
+            item.target_app,
+            item.target_title_hash,
+            serde_json::to_string(&normalize_tags(&item.tags))?,
+            item.note,
         ],
     );
     match r {
@@ -141,7 +343,7 @@ pub fn list(db_path: &Path, limit: i64, before_ms: Option<i64>) -> Result<Vec<Hi
                 let mut stmt = c
                     .prepare(
                         r#"
-                        SELECT task_id, created_at_ms, asr_text, rewritten_text, inserted_text, final_text, template_id, rtf, device_used, preprocess_ms, asr_ms
+                        SELECT task_id, created_at_ms, asr_text, rewritten_text, inserted_text, final_text, template_id, rtf, device_used, preprocess_ms, asr_ms, target_app, target_title_hash, tags, note
                         FROM history
                         WHERE created_at_ms < ?1
                         ORDER BY created_at_ms DESC
@@ -150,21 +352,7 @@ pub fn list(db_path: &Path, limit: i64, before_ms: Option<i64>) -> Result<Vec<Hi
                     )
                     .context("prepare history list failed")?;
                 let rows = stmt
-                    .query_map(params![ms, limit], |row| {
-                        Ok(HistoryItem {
-                            task_id: row.get(0)?,
-                            created_at_ms: row.get(1)?,
-                            asr_text: row.get(2)?,
-                            rewritten_text: row.get(3)?,
-                            inserted_text: row.get(4)?,
-                            final_text: row.get(5)?,
-                            template_id: row.get(6)?,
-                            rtf: row.get(7)?,
-                            device_used: row.get(8)?,
-                            preprocess_ms: row.get(9)?,
-                            asr_ms: row.get(10)?,
-                        })
-                    })
+                    .query_map(params![ms, limit], item_from_row)
                     .context("query history list failed")?;
                 for r in rows {
                     out.push(r?);
@@ -174,7 +362,7 @@ pub fn list(db_path: &Path, limit: i64, before_ms: Option<i64>) -> Result<Vec<Hi
                 let mut stmt = c
                     .prepare(
                         r#"
-                        SELECT task_id, created_at_ms, asr_text, rewritten_text, inserted_text, final_text, template_id, rtf, device_used, preprocess_ms, asr_ms
+                        SELECT task_id, created_at_ms, asr_text, rewritten_text, inserted_text, final_text, template_id, rtf, device_used, preprocess_ms, asr_ms, target_app, target_title_hash, tags, note
                         FROM history
                         ORDER BY created_at_ms DESC
                         LIMIT ?1
@@ -182,21 +370,7 @@ pub fn list(db_path: &Path, limit: i64, before_ms: Option<i64>) -> Result<Vec<Hi
                     )
                     .context("prepare history list failed")?;
                 let rows = stmt
-                    .query_map(params![limit], |row| {
-                        Ok(HistoryItem {
-                            task_id: row.get(0)?,
-                            created_at_ms: row.get(1)?,
-                            asr_text: row.get(2)?,
-                            rewritten_text: row.get(3)?,
-                            inserted_text: row.get(4)?,
-                            final_text: row.get(5)?,
-                            template_id: row.get(6)?,
-                            rtf: row.get(7)?,
-                            device_used: row.get(8)?,
-                            preprocess_ms: row.get(9)?,
-                            asr_ms: row.get(10)?,
-                        })
-                    })
+                    .query_map(params![limit], item_from_row)
                     .context("query history list failed")?;
                 for r in rows {
                     out.push(r?);
@@ -218,6 +392,173 @@ pub fn list(db_path: &Path, limit: i64, before_ms: Option<i64>) -> Result<Vec<Hi
     }
 }
 
+// Newest rows dictated into the given app (matched by file name, any case),
+// for context that should not mix in other apps.
+pub fn list_for_app(
+    db_path: &Path,
+    target_app: &str,
+    limit: i64,
+    before_ms: i64,
+) -> Result<Vec<HistoryItem>> {
+    let c = conn(db_path)?;
+    let mut stmt = c
+        .prepare(
+            r#"
+            SELECT task_id, created_at_ms, asr_text, rewritten_text, inserted_text, final_text, template_id, rtf, device_used, preprocess_ms, asr_ms, target_app, target_title_hash, tags, note
+            FROM history
+            WHERE target_app = ?1 COLLATE NOCASE AND created_at_ms < ?2
+            ORDER BY created_at_ms DESC
+            LIMIT ?3
+            "#,
+        )
+        .context("prepare history list_for_app failed")?;
+    let rows = stmt
+        .query_map(params![target_app, before_ms, limit], item_from_row)
+        .context("query history list_for_app failed")?;
+    Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+}
+
+// Newest rows carrying the tag, paged like `list`.
+pub fn list_by_tag(
+    db_path: &Path,
+    tag: &str,
+    limit: i64,
+    before_ms: Option<i64>,
+) -> Result<Vec<HistoryItem>> {
+    let tag = normalize_tags(&[tag.to_string()]).pop().unwrap_or_default();
+    let c = conn(db_path)?;
+    let mut stmt = c
+        .prepare(
+            r#"
+            SELECT task_id, created_at_ms, asr_text, rewritten_text, inserted_text, final_text, template_id, rtf, device_used, preprocess_ms, asr_ms, target_app, target_title_hash, tags, note
+            FROM history
+            WHERE EXISTS (SELECT 1 FROM json_each(history.tags) WHERE value = ?1)
+              AND created_at_ms < ?2
+            ORDER BY created_at_ms DESC
+            LIMIT ?3
+            "#,
+        )
+        .context("prepare history list_by_tag failed")?;
+    let rows = stmt
+        .query_map(
+            params![tag, before_ms.unwrap_or(i64::MAX), limit],
+            item_from_row,
+        )
+        .context("query history list_by_tag failed")?;
+    Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+}
+
+// Trigram matching needs three characters, so shorter terms fall back to LIKE.
+const FTS_MIN_TERM_CHARS: usize = 3;
+
+// Whitespace-separated terms, all of which must appear in the ASR or final
+// text. Newest first and paged by `before_ms` like `list`; a blank query lists.
+pub fn search(
+    db_path: &Path,
+    query: &str,
+    limit: i64,
+    before_ms: Option<i64>,
+) -> Result<Vec<HistoryItem>> {
+    let terms: Vec<&str> = query.split_whitespace().collect();
+    if terms.is_empty() {
+        return list(db_path, limit, before_ms);
+    }
+    let data_dir = db_path.parent().unwrap_or_else(|| Path::new("."));
+    let span = Span::start(
+        data_dir,
+        None,
+        "History",
+        "HISTORY.search",
+        Some(serde_json::json!({
+            "terms": terms.len(),
+            "limit": limit,
+            "before_ms": before_ms,
+        })),
+    );
+
+    let result: Result<Vec<HistoryItem>> = (|| {
+        let (clause, args) = search_clause(&terms, limit, before_ms);
+        let c = conn(db_path)?;
+        let mut stmt = c
+            .prepare(&format!(
+                r#"
+                SELECT task_id, created_at_ms, asr_text, rewritten_text, inserted_text, final_text, template_id, rtf, device_used, preprocess_ms, asr_ms, target_app, target_title_hash, tags, note
+                FROM history
+                WHERE {clause}
+                ORDER BY created_at_ms DESC
+                LIMIT ?
+                "#
+            ))
+            .context("prepare history search failed")?;
+        let rows = stmt
+            .query_map(params_from_iter(args), item_from_row)
+            .context("query history search failed")?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    })();
+
+    match result {
+        Ok(out) => {
+            span.ok(Some(serde_json::json!({"items": out.len()})));
+            Ok(out)
+        }
+        Err(e) => {
+            span.err_anyhow("db", "E_HISTORY_SEARCH", &e, None);
+            Err(e)
+        }
+    }
+}
+
+// Terms are quoted so FTS operators typed by the user match literally; the
+// last argument is the limit.
+fn search_clause(terms: &[&str], limit: i64, before_ms: Option<i64>) -> (String, Vec<Value>) {
+    let mut clauses = vec!["created_at_ms < ?".to_string()];
+    let mut args = vec![Value::Integer(before_ms.unwrap_or(i64::MAX))];
+    let (long, short): (Vec<&str>, Vec<&str>) = terms
+        .iter()
+        .partition(|t| t.chars().count() >= FTS_MIN_TERM_CHARS);
+    if !long.is_empty() {
+        let expr = long
+            .iter()
+            .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+        clauses
+            .push("rowid IN (SELECT rowid FROM history_fts WHERE history_fts MATCH ?)".to_string());
+        args.push(Value::Text(expr));
+    }
+    for term in short {
+        let pattern = format!(
+            "%{}%",
+            term.replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        clauses.push("(asr_text LIKE ? ESCAPE '\\' OR final_text LIKE ? ESCAPE '\\')".to_string());
+        args.push(Value::Text(pattern.clone()));
+        args.push(Value::Text(pattern));
+    }
+    args.push(Value::Integer(limit));
+    (clauses.join(" AND "), args)
+}
+
+// Single row lookup for consumers that run after a write has landed.
+pub fn get(db_path: &Path, task_id: &str) -> Result<Option<HistoryItem>> {
+    let c = conn(db_path)?;
+    let mut stmt = c
+        .prepare(
+            r#"
+            SELECT task_id, created_at_ms, asr_text, rewritten_text, inserted_text, final_text, template_id, rtf, device_used, preprocess_ms, asr_ms, target_app, target_title_hash, tags, note
+            FROM history
+            WHERE task_id = ?1
+            "#,
+        )
+        .context("prepare history get failed")?;
+    let mut rows = stmt
+        .query_map(params![task_id], item_from_row)
+        .context("query history get failed")?;
+    Ok(rows.next().transpose()?)
+}
+
 pub fn update_final_text(
     db_path: &Path,
     task_id: &str,
@@ -235,7 +576,7 @@ pub fn update_final_text(
             "final_chars": final_text.len(),
         })),
     );
-    let c = match conn(db_path) {
+    let c = match write_conn(db_path) {
         Ok(c) => c,
         Err(e) => {
             span.err_anyhow("db", "E_HISTORY_CONN", &e, None);
@@ -279,7 +620,7 @@ pub fn update_inserted_text(db_path: &Path, task_id: &str, inserted_text: &str)
             "inserted_chars": inserted_text.len(),
         })),
     );
-    let c = match conn(db_path) {
+    let c = match write_conn(db_path) {
         Ok(c) => c,
         Err(e) => {
             span.err_anyhow("db", "E_HISTORY_CONN", &e, None);
@@ -312,10 +653,307 @@ pub fn update_inserted_text(db_path: &Path, task_id: &str, inserted_text: &str)
     }
 }
 
+// Where the retained recording for a task lives, once it has been kept (and
+// possibly transcoded) after ASR.
+pub fn update_recording_path(db_path: &Path, task_id: &str, recording_path: &str) -> Result<()> {
+    let data_dir = db_path.parent().unwrap_or_else(|| Path::new("."));
+    let span = Span::start(
+        data_dir,
+        Some(task_id),
+        "History",
+        "HISTORY.update_recording_path",
+        Some(serde_json::json!({
+            "recording_path": recording_path,
+        })),
+    );
+    let c = match write_conn(db_path) {
+        Ok(c) => c,
+        Err(e) => {
+            span.err_anyhow("db", "E_HISTORY_CONN", &e, None);
+            return Err(e);
+        }
+    };
+    let r = c.execute(
+        "UPDATE history SET recording_path = ?2 WHERE task_id = ?1",
+        params![task_id, recording_path],
+    );
+    match r {
+        Ok(0) => {
+            let ae = anyhow::anyhow!("E_HISTORY_NOT_FOUND: task_id not found");
+            span.err_anyhow("db", "E_HISTORY_NOT_FOUND", &ae, None);
+            Err(ae)
+        }
+        Ok(_) => {
+            span.ok(None);
+            Ok(())
+        }
+        Err(e) => {
+            let ae = anyhow::anyhow!(e).context("update history recording_path failed");
+            span.err_anyhow("db", "E_HISTORY_UPDATE", &ae, None);
+            Err(ae)
+        }
+    }
+}
+
+pub fn recording_path(db_path: &Path, task_id: &str) -> Result<Option<String>> {
+    let c = conn(db_path)?;
+    let r = c.query_row(
+        "SELECT recording_path FROM history WHERE task_id = ?1",
+        params![task_id],
+        |row| row.get::<_, Option<String>>(0),
+    );
+    match r {
+        Ok(v) => Ok(v),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(anyhow::anyhow!(e).context("query history recording_path failed")),
+    }
+}
+
+// A manual edit of the final text. Kept apart from rewrite and insert updates
+// so correction suggestions only learn from what the user typed.
+pub fn update_corrected_text(db_path: &Path, task_id: &str, corrected_text: &str) -> Result<()> {
+    let data_dir = db_path.parent().unwrap_or_else(|| Path::new("."));
+    let span = Span::start(
+        data_dir,
+        Some(task_id),
+        "History",
+        "HISTORY.update_corrected_text",
+        Some(serde_json::json!({
+            "corrected_chars": corrected_text.len(),
+        })),
+    );
+    let c = match write_conn(db_path) {
+        Ok(c) => c,
+        Err(e) => {
+            span.err_anyhow("db", "E_HISTORY_CONN", &e, None);
+            return Err(e);
+        }
+    };
+    let r = c.execute(
+        r#"
+        UPDATE history
+        SET corrected_text = ?2, final_text = ?2
+        WHERE task_id = ?1
+        "#,
+        params![task_id, corrected_text],
+    );
+    match r {
+        Ok(0) => {
+            let ae = anyhow::anyhow!("E_HISTORY_NOT_FOUND: task_id not found");
+            span.err_anyhow("db", "E_HISTORY_NOT_FOUND", &ae, None);
+            Err(ae)
+        }
+        Ok(_) => {
+            span.ok(None);
+            Ok(())
+        }
+        Err(e) => {
+            let ae = anyhow::anyhow!(e).context("update history corrected_text failed");
+            span.err_anyhow("db", "E_HISTORY_UPDATE", &ae, None);
+            Err(ae)
+        }
+    }
+}
+
+// Replaces the row's tags; returns them normalized.
+pub fn set_tags(db_path: &Path, task_id: &str, tags: &[String]) -> Result<Vec<String>> {
+    let tags = normalize_tags(tags);
+    let data_dir = db_path.parent().unwrap_or_else(|| Path::new("."));
+    let span = Span::start(
+        data_dir,
+        Some(task_id),
+        "History",
+        "HISTORY.set_tags",
+        Some(serde_json::json!({ "tags": tags.len() })),
+    );
+    let c = match write_conn(db_path) {
+        Ok(c) => c,
+        Err(e) => {
+            span.err_anyhow("db", "E_HISTORY_CONN", &e, None);
+            return Err(e);
+        }
+    };
+    let r = c.execute(
+        "UPDATE history SET tags = ?2 WHERE task_id = ?1",
+        params![task_id, serde_json::to_string(&tags)?],
+    );
+    finish_update(span, r, "tags").map(|()| tags)
+}
+
+// An empty note clears it.
+pub fn set_note(db_path: &Path, task_id: &str, note: &str) -> Result<()> {
+    let note = note.trim();
+    let data_dir = db_path.parent().unwrap_or_else(|| Path::new("."));
+    let span = Span::start(
+        data_dir,
+        Some(task_id),
+        "History",
+        "HISTORY.set_note",
+        Some(serde_json::json!({ "note_chars": note.len() })),
+    );
+    let c = match write_conn(db_path) {
+        Ok(c) => c,
+        Err(e) => {
+            span.err_anyhow("db", "E_HISTORY_CONN", &e, None);
+            return Err(e);
+        }
+    };
+    let r = c.execute(
+        "UPDATE history SET note = ?2 WHERE task_id = ?1",
+        params![task_id, note],
+    );
+    finish_update(span, r, "note")
+}
+
+fn finish_update(span: Span, r: rusqlite::Result<usize>, column: &str) -> Result<()> {
+    match r {
+        Ok(0) => {
+            let ae = anyhow::anyhow!("E_HISTORY_NOT_FOUND: task_id not found");
+            span.err_anyhow("db", "E_HISTORY_NOT_FOUND", &ae, None);
+            Err(ae)
+        }
+        Ok(_) => {
+            span.ok(None);
+            Ok(())
+        }
+        Err(e) => {
+            let ae = anyhow::anyhow!(e).context(format!("update history {column} failed"));
+            span.err_anyhow("db", "E_HISTORY_UPDATE", &ae, None);
+            Err(ae)
+        }
+    }
+}
+
+// Newest first, for bulk operations to work through in batches.
+pub fn matching_task_ids(db_path: &Path, filter: &HistoryFilter) -> Result<Vec<String>> {
+    let (clause, args) = filter.where_clause();
+    let c = conn(db_path)?;
+    let mut stmt = c
+        .prepare(&format!(
+            "SELECT task_id FROM history WHERE {clause} ORDER BY created_at_ms DESC"
+        ))
+        .context("prepare history filter failed")?;
+    let rows = stmt
+        .query_map(params_from_iter(args), |row| row.get(0))
+        .context("query history filter failed")?;
+    Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+}
+
+fn id_placeholders(n: usize) -> String {
+    (1..=n)
+        .map(|i| format!("?{i}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn get_many(db_path: &Path, task_ids: &[String]) -> Result<Vec<HistoryItem>> {
+    let c = conn(db_path)?;
+    let mut stmt = c
+        .prepare(&format!(
+            r#"
+            SELECT task_id, created_at_ms, asr_text, rewritten_text, inserted_text, final_text, template_id, rtf, device_used, preprocess_ms, asr_ms, target_app, target_title_hash, tags, note
+            FROM history
+            WHERE task_id IN ({})
+            ORDER BY created_at_ms DESC
+            "#,
+            id_placeholders(task_ids.len())
+        ))
+        .context("prepare history get_many failed")?;
+    let rows = stmt
+        .query_map(params_from_iter(task_ids), item_from_row)
+        .context("query history get_many failed")?;
+    Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+}
+
+pub fn delete_many(db_path: &Path, task_ids: &[String]) -> Result<usize> {
+    let c = write_conn(db_path)?;
+    c.execute(
+        &format!(
+            "DELETE FROM history WHERE task_id IN ({})",
+            id_placeholders(task_ids.len())
+        ),
+        params_from_iter(task_ids),
+    )
+    .context("delete history rows failed")
+}
+
+// Adds and removes tags on every row in one transaction; returns the number
+// of rows whose tags changed.
+pub fn retag_many(
+    db_path: &Path,
+    task_ids: &[String],
+    add: &[String],
+    remove: &[String],
+) -> Result<usize> {
+    let add = normalize_tags(add);
+    let remove = normalize_tags(remove);
+    let mut c = write_conn(db_path)?;
+    let tx = c.transaction().context("begin history retag failed")?;
+    let mut changed = 0;
+    for task_id in task_ids {
+        let raw: Option<String> = match tx.query_row(
+            "SELECT tags FROM history WHERE task_id = ?1",
+            params![task_id],
+            |row| row.get(0),
+        ) {
+            Ok(raw) => Some(raw),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(anyhow::anyhow!(e).context("read history tags failed")),
+        };
+        let Some(raw) = raw else {
+            continue;
+        };
+        let before: Vec<String> = serde_json::from_str(&raw).unwrap_or_default();
+        let mut after: Vec<String> = before.iter().chain(&add).cloned().collect();
+        after.retain(|t| !remove.contains(t));
+        let after = normalize_tags(&after);
+        if after != before {
+            tx.execute(
+                "UPDATE history SET tags = ?2 WHERE task_id = ?1",
+                params![task_id, serde_json::to_string(&after)?],
+            )
+            .context("update history tags failed")?;
+            changed += 1;
+        }
+    }
+    tx.commit().context("commit history retag failed")?;
+    Ok(changed)
+}
+
+pub fn list_corrections(db_path: &Path, limit: i64) -> Result<Vec<CorrectionSample>> {
+    let c = conn(db_path)?;
+    let mut stmt = c
+        .prepare(
+            r#"
+            SELECT task_id, created_at_ms, asr_text, corrected_text
+            FROM history
+            WHERE corrected_text != '' AND corrected_text != asr_text
+            ORDER BY created_at_ms DESC
+            LIMIT ?1
+            "#,
+        )
+        .context("prepare history corrections failed")?;
+    let rows = stmt
+        .query_map(params![limit], |row| {
+            Ok(CorrectionSample {
+                task_id: row.get(0)?,
+                created_at_ms: row.get(1)?,
+                asr_text: row.get(2)?,
+                corrected_text: row.get(3)?,
+            })
+        })
+        .context("query history corrections failed")?;
+    let mut out = Vec::new();
+    for r in rows {
+        out.push(r?);
+    }
+    Ok(out)
+}
+
 pub fn clear(db_path: &Path) -> Result<()> {
     let data_dir = db_path.parent().unwrap_or_else(|| Path::new("."));
     let span = Span::start(data_dir, None, "History", "HISTORY.clear", None);
-    let c = match conn(db_path) {
+    let c = match write_conn(db_path) {
         Ok(c) => c,
         Err(e) => {
             span.err_anyhow("db", "E_HISTORY_CONN", &e, None);
@@ -357,6 +995,10 @@ mod tests {
                 device_used: "cuda".to_string(),
                 preprocess_ms: 10,
                 asr_ms: 20,
+                target_app: None,
+                target_title_hash: None,
+                tags: Vec::new(),
+                note: String::new(),
             },
         )
         .expect("append");
@@ -387,6 +1029,10 @@ mod tests {
                 device_used: "cuda".to_string(),
                 preprocess_ms: 10,
                 asr_ms: 20,
+                target_app: None,
+                target_title_hash: None,
+                tags: Vec::new(),
+                note: String::new(),
             },
         )
         .expect("append");
@@ -395,10 +1041,206 @@ mod tests {
 
         let rows = list(&db, 10, None).expect("list");
         assert_eq!(rows[0].inserted_text, "inserted");
+        let row = get(&db, "task-1").expect("get").expect("row");
+        assert_eq!(row.inserted_text, "inserted");
+        assert!(get(&db, "task-2").expect("get").is_none());
         assert_eq!(rows[0].final_text, "inserted");
         assert_eq!(rows[0].rewritten_text, "rewritten");
     }
 
+    #[test]
+    fn recording_path_is_stored_per_task() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let db = tmp.path().join("history.sqlite3");
+        append(
+            &db,
+            &HistoryItem {
+                task_id: "task-1".to_string(),
+                created_at_ms: 1,
+                asr_text: "raw".to_string(),
+                rewritten_text: String::new(),
+                inserted_text: String::new(),
+                final_text: "raw".to_string(),
+                template_id: None,
+                rtf: 0.4,
+                device_used: "cuda".to_string(),
+                preprocess_ms: 10,
+                asr_ms: 20,
+                target_app: None,
+                target_title_hash: None,
+                tags: Vec::new(),
+                note: String::new(),
+            },
+        )
+        .expect("append");
+        assert_eq!(recording_path(&db, "task-1").expect("read"), None);
+
+        update_recording_path(&db, "task-1", "preprocess/task-1.flac").expect("update");
+
+        assert_eq!(
+            recording_path(&db, "task-1").expect("read").as_deref(),
+            Some("preprocess/task-1.flac")
+        );
+        assert!(update_recording_path(&db, "task-2", "x.flac").is_err());
+    }
+
+    #[test]
+    fn target_window_is_stored_and_filters_by_app() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let db = tmp.path().join("history.sqlite3");
+        for (id, ms, app) in [
+            ("task-1", 1, Some("OUTLOOK.EXE")),
+            ("task-2", 2, Some("code.exe")),
+            ("task-3", 3, None),
+            ("task-4", 4, Some("outlook.exe")),
+        ] {
+            append(
+                &db,
+                &HistoryItem {
+                    task_id: id.to_string(),
+                    created_at_ms: ms,
+                    asr_text: "raw".to_string(),
+                    rewritten_text: String::new(),
+                    inserted_text: String::new(),
+                    final_text: "raw".to_string(),
+                    template_id: None,
+                    rtf: 0.4,
+                    device_used: "cuda".to_string(),
+                    preprocess_ms: 10,
+                    asr_ms: 20,
+                    target_app: app.map(str::to_string),
+                    target_title_hash: app.map(|a| format!("title-of-{a}")),
+                    tags: Vec::new(),
+                    note: String::new(),
+                },
+            )
+            .expect("append");
+        }
+
+        let ids = |rows: Vec<HistoryItem>| rows.into_iter().map(|h| h.task_id).collect::<Vec<_>>();
+        assert_eq!(
+            ids(list_for_app(&db, "outlook.exe", 10, 10).expect("list")),
+            ["task-4", "task-1"]
+        );
+        assert_eq!(
+            ids(list_for_app(&db, "outlook.exe", 10, 4).expect("list")),
+            ["task-1"]
+        );
+        let row = get(&db, "task-2").expect("get").expect("row");
+        assert_eq!(row.target_app.as_deref(), Some("code.exe"));
+        assert_eq!(row.target_title_hash.as_deref(), Some("title-of-code.exe"));
+    }
+
+    #[test]
+    fn corrected_text_is_listed_as_correction_sample() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let db = tmp.path().join("history.sqlite3");
+        for (id, ms) in [("task-1", 1), ("task-2", 2)] {
+            append(
+                &db,
+                &HistoryItem {
+                    task_id: id.to_string(),
+                    created_at_ms: ms,
+                    asr_text: "raw".to_string(),
+                    rewritten_text: "rewritten".to_string(),
+                    inserted_text: String::new(),
+                    final_text: "rewritten".to_string(),
+                    template_id: None,
+                    rtf: 0.4,
+                    device_used: "cuda".to_string(),
+                    preprocess_ms: 10,
+                    asr_ms: 20,
+                    target_app: None,
+                    target_title_hash: None,
+                    tags: Vec::new(),
+                    note: String::new(),
+                },
+            )
+            .expect("append");
+        }
+
+        update_corrected_text(&db, "task-2", "fixed").expect("update");
+        assert!(update_corrected_text(&db, "task-3", "fixed").is_err());
+
+        let row = get(&db, "task-2").expect("get").expect("row");
+        assert_eq!(row.final_text, "fixed");
+        assert_eq!(row.rewritten_text, "rewritten");
+        let samples = list_corrections(&db, 10).expect("corrections");
+        assert_eq!(
+            samples,
+            vec![CorrectionSample {
+                task_id: "task-2".to_string(),
+                created_at_ms: 2,
+                asr_text: "raw".to_string(),
+                corrected_text: "fixed".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn tags_and_notes_are_normalized_and_filterable() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let db = tmp.path().join("history.sqlite3");
+        for (id, ms) in [("task-1", 1), ("task-2", 2), ("task-3", 3)] {
+            append(
+                &db,
+                &HistoryItem {
+                    task_id: id.to_string(),
+                    created_at_ms: ms,
+                    asr_text: "raw".to_string(),
+                    rewritten_text: String::new(),
+                    inserted_text: String::new(),
+                    final_text: "raw".to_string(),
+                    template_id: None,
+                    rtf: 0.4,
+                    device_used: "cuda".to_string(),
+                    preprocess_ms: 10,
+                    asr_ms: 20,
+                    target_app: None,
+                    target_title_hash: None,
+                    tags: Vec::new(),
+                    note: String::new(),
+                },
+            )
+            .expect("append");
+        }
+
+        let tags = [
+            "Meeting".to_string(),
+            " weekly  sync ".to_string(),
+            "meeting".to_string(),
+            " ".to_string(),
+        ];
+        assert_eq!(
+            set_tags(&db, "task-1", &tags).expect("tags"),
+            ["meeting", "weekly sync"]
+        );
+        set_tags(&db, "task-3", &["meeting".to_string()]).expect("tags");
+        set_note(&db, "task-1", "  follow up with Ana  ").expect("note");
+        assert!(set_tags(&db, "task-9", &tags).is_err());
+        assert!(set_note(&db, "task-9", "x").is_err());
+
+        let row = get(&db, "task-1").expect("get").expect("row");
+        assert_eq!(row.tags, ["meeting", "weekly sync"]);
+        assert_eq!(row.note, "follow up with Ana");
+        let ids = |rows: Vec<HistoryItem>| rows.into_iter().map(|h| h.task_id).collect::<Vec<_>>();
+        assert_eq!(
+            ids(list_by_tag(&db, "MEETING", 10, None).expect("list")),
+            ["task-3", "task-1"]
+        );
+        assert_eq!(
+            ids(list_by_tag(&db, "meeting", 10, Some(3)).expect("list")),
+            ["task-1"]
+        );
+        assert!(list_by_tag(&db, "sync", 10, None).expect("list").is_empty());
+
+        set_tags(&db, "task-1", &[]).expect("clear tags");
+        set_note(&db, "task-1", "").expect("clear note");
+        let row = get(&db, "task-1").expect("get").expect("row");
+        assert!(row.tags.is_empty());
+        assert_eq!(row.note, "");
+    }
+
     #[test]
     fn old_history_schema_gets_new_text_columns() {
         let tmp = tempfile::tempdir().expect("tempdir");
@@ -431,5 +1273,59 @@ mod tests {
         assert_eq!(rows[0].rewritten_text, "");
         assert_eq!(rows[0].inserted_text, "");
         assert_eq!(rows[0].final_text, "final");
+        assert!(rows[0].tags.is_empty());
+        assert_eq!(rows[0].note, "");
+        let found = search(&db, "final", 10, None).expect("search");
+        assert_eq!(found.len(), 1);
+    }
+
+    #[test]
+    fn search_matches_all_terms_and_follows_edits() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let db = tmp.path().join("history.sqlite3");
+        let item = |id: &str, ms: i64, text: &str| HistoryItem {
+            task_id: id.to_string(),
+            created_at_ms: ms,
+            asr_text: text.to_string(),
+            rewritten_text: String::new(),
+            inserted_text: String::new(),
+            final_text: text.to_string(),
+            template_id: None,
+            rtf: 0.4,
+            device_used: "cuda".to_string(),
+            preprocess_ms: 10,
+            asr_ms: 20,
+            target_app: None,
+            target_title_hash: None,
+            tags: Vec::new(),
+            note: String::new(),
+        };
+        append(&db, &item("task-1", 1, "明天下午三点开会")).expect("append");
+        append(&db, &item("task-2", 2, "ship the release notes")).expect("append");
+        append(&db, &item("task-3", 3, "release 50% done")).expect("append");
+        let ids = |query: &str, before_ms: Option<i64>| -> Vec<String> {
+            search(&db, query, 10, before_ms)
+                .expect("search")
+                .into_iter()
+                .map(|r| r.task_id)
+                .collect()
+        };
+
+        assert_eq!(ids("release", None), ["task-3", "task-2"]);
+        assert_eq!(ids("RELEASE notes", None), ["task-2"]);
+        assert_eq!(ids("release", Some(3)), ["task-2"]);
+        assert_eq!(ids("下午三点", None), ["task-1"]);
+        assert_eq!(ids("开会", None), ["task-1"]);
+        assert_eq!(ids("50%", None), ["task-3"]);
+        assert!(ids("\"ship OR", None).is_empty());
+        assert_eq!(search(&db, "  ", 2, None).expect("blank").len(), 2);
+
+        update_final_text(&db, "task-2", "shipped it", None).expect("update");
+        assert_eq!(ids("shipped", None), ["task-2"]);
+        assert_eq!(ids("notes", None), ["task-2"]);
+        append(&db, &item("task-1", 1, "other words")).expect("replace");
+        assert!(ids("下午三点", None).is_empty());
+        delete_many(&db, &["task-3".to_string()]).expect("delete");
+        assert_eq!(ids("release", None), ["task-2"]);
     }
 }
@@ -0,0 +1,257 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::history::{self, HistoryFilter, TimedHistoryItem};
+use crate::obs::Span;
+use crate::timestamps::TimeFormat;
+
+// Rows are handled a batch at a time so progress can be reported and no single
+// write transaction holds the database for long.
+pub const BATCH_SIZE: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum BulkAction {
+    Delete,
+    // JSON Lines, one history item per line, newest first, with its creation
+    // time formatted as in the history list.
+    Export {
+        path: PathBuf,
+    },
+    Tag {
+        #[serde(default)]
+        add: Vec<String>,
+        #[serde(default)]
+        remove: Vec<String>,
+    },
+}
+
+impl BulkAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Delete => "delete",
+            Self::Export { .. } => "export",
+            Self::Tag { .. } => "tag",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BulkProgress {
+    pub action: &'static str,
+    pub done: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BulkResult {
+    pub action: &'static str,
+    pub matched: usize,
+    // Rows deleted, exported or whose tags changed.
+    pub affected: usize,
+}
+
+// Batches already committed stay committed when a later one fails; a failed
+// export removes its partial file.
+pub fn run(
+    db_path: &Path,
+    action: &BulkAction,
+    filter: &HistoryFilter,
+    time_format: TimeFormat,
+    mut on_progress: impl FnMut(&BulkProgress),
+) -> Result<BulkResult> {
+    let data_dir = db_path.parent().unwrap_or_else(|| Path::new("."));
+    let span = Span::start(
+        data_dir,
+        None,
+        "History",
+        "HISTORY.bulk",
+        Some(serde_json::json!({
+            "action": action.name(),
+            "has_range": filter.from_ms.is_some() || filter.to_ms.is_some(),
+            "has_tag": filter.tag.is_some(),
+            "has_app": filter.target_app.is_some(),
+            "has_template": filter.template_id.is_some(),
+        })),
+    );
+    if matches!(action, BulkAction::Delete) && filter.is_empty() {
+        let ae = anyhow::anyhow!(
+            "E_HISTORY_BULK_EMPTY_FILTER: bulk delete needs a filter; use clear history to delete everything"
+        );
+        span.err_anyhow("db", "E_HISTORY_BULK_EMPTY_FILTER", &ae, None);
+        return Err(ae);
+    }
+
+    let result = run_batches(db_path, action, filter, time_format, &mut on_progress);
+    if let (Err(_), BulkAction::Export { path }) = (&result, action) {
+        let _ = std::fs::remove_file(path);
+    }
+    match result {
+        Ok(out) => {
+            span.ok(Some(serde_json::json!({
+                "matched": out.matched,
+                "affected": out.affected,
+            })));
+            Ok(out)
+        }
+        Err(e) => {
+            span.err_anyhow("db", "E_HISTORY_BULK", &e, None);
+            Err(e)
+        }
+    }
+}
+
+fn run_batches(
+    db_path: &Path,
+    action: &BulkAction,
+    filter: &HistoryFilter,
+    time_format: TimeFormat,
+    on_progress: &mut impl FnMut(&BulkProgress),
+) -> Result<BulkResult> {
+    let ids = history::matching_task_ids(db_path, filter)?;
+    let mut progress = BulkProgress {
+        action: action.name(),
+        done: 0,
+        total: ids.len(),
+    };
+    on_progress(&progress);
+    let mut export = match action {
+        BulkAction::Export { path } => Some(BufWriter::new(
+            File::create(path).context("create history export failed")?,
+        )),
+        _ => None,
+    };
+    let mut affected = 0;
+    for batch in ids.chunks(BATCH_SIZE) {
+        affected += match action {
+            BulkAction::Delete => history::delete_many(db_path, batch)?,
+            BulkAction::Tag { add, remove } => history::retag_many(db_path, batch, add, remove)?,
+            BulkAction::Export { .. } => {
+                let out = export.as_mut().expect("export writer");
+                let items = history::get_many(db_path, batch)?;
+                let n = items.len();
+                for item in items {
+                    serde_json::to_writer(&mut *out, &TimedHistoryItem::new(item, time_format))?;
+                    out.write_all(b"\n")
+                        .context("write history export failed")?;
+                }
+                n
+            }
+        };
+        progress.done += batch.len();
+        on_progress(&progress);
+    }
+    if let Some(mut out) = export {
+        out.flush().context("write history export failed")?;
+    }
+    Ok(BulkResult {
+        action: action.name(),
+        matched: ids.len(),
+        affected,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::HistoryItem;
+
+    fn seed(db: &Path, n: i64) {
+        for ms in 1..=n {
+            history::append(
+                db,
+                &HistoryItem {
+                    task_id: format!("task-{ms}"),
+                    created_at_ms: ms,
+                    asr_text: "raw".to_string(),
+                    rewritten_text: String::new(),
+                    inserted_text: String::new(),
+                    final_text: format!("text {ms}"),
+                    template_id: (ms % 2 == 0).then(|| "notes".to_string()),
+                    rtf: 0.4,
+                    device_used: "cuda".to_string(),
+                    preprocess_ms: 10,
+                    asr_ms: 20,
+                    target_app: Some("code.exe".to_string()),
+                    target_title_hash: None,
+                    tags: Vec::new(),
+                    note: String::new(),
+                },
+            )
+            .expect("append");
+        }
+    }
+
+    #[test]
+    fn tags_exports_and_deletes_by_filter_in_batches() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let db = tmp.path().join("history.sqlite3");
+        let n = BATCH_SIZE as i64 + 50;
+        seed(&db, n);
+
+        let mut seen = Vec::new();
+        let tag = BulkAction::Tag {
+            add: vec!["Test".to_string()],
+            remove: Vec::new(),
+        };
+        let filter = HistoryFilter {
+            from_ms: Some(1),
+            to_ms: Some(n),
+            ..Default::default()
+        };
+        let out = run(&db, &tag, &filter, TimeFormat::Iso, |p| seen.push(p.done)).expect("tag");
+        assert_eq!(out.affected, n as usize - 1);
+        assert_eq!(seen, [0, BATCH_SIZE, n as usize - 1]);
+        // Already tagged rows do not count again.
+        assert_eq!(
+            run(&db, &tag, &filter, TimeFormat::Iso, |_| {})
+                .expect("tag")
+                .affected,
+            0
+        );
+
+        let path = tmp.path().join("export.jsonl");
+        let by_tag = HistoryFilter {
+            tag: Some("test".to_string()),
+            template_id: Some("notes".to_string()),
+            ..Default::default()
+        };
+        let export = BulkAction::Export { path: path.clone() };
+        let out = run(&db, &export, &by_tag, TimeFormat::Iso, |_| {}).expect("export");
+        let lines: Vec<TimedHistoryItem> = std::fs::read_to_string(&path)
+            .expect("read")
+            .lines()
+            .map(|l| serde_json::from_str(l).expect("item"))
+            .collect();
+        assert_eq!(out.affected, lines.len());
+        assert_eq!(lines.len(), (n as usize - 1) / 2);
+        assert_eq!(lines[0].item.task_id, format!("task-{}", n - 2));
+        assert_eq!(lines[0].item.tags, ["test"]);
+        let at = chrono::DateTime::parse_from_rfc3339(&lines[0].created_at_iso).expect("iso");
+        assert_eq!(at.timestamp(), lines[0].item.created_at_ms / 1000);
+
+        assert!(run(
+            &db,
+            &BulkAction::Delete,
+            &HistoryFilter::default(),
+            TimeFormat::Iso,
+            |_| {}
+        )
+        .is_err());
+        let out = run(&db, &BulkAction::Delete, &by_tag, TimeFormat::Iso, |_| {}).expect("delete");
+        assert_eq!(out.affected, lines.len());
+        let left = history::list(&db, 1000, None).expect("list");
+        assert_eq!(left.len(), n as usize - lines.len());
+        // Only the untagged row past the range keeps the template.
+        let kept: Vec<&str> = left
+            .iter()
+            .filter(|h| h.template_id.is_some())
+            .map(|h| h.task_id.as_str())
+            .collect();
+        assert_eq!(kept, [format!("task-{n}")]);
+    }
+}
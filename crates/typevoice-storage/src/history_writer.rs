@@ -0,0 +1,433 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Mutex, OnceLock,
+    },
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::atomic_file;
+use crate::guest_mode;
+use crate::history::{self, HistoryItem};
+use crate::obs::{self, schema::now_ms};
+
+// Backoff doubles from RETRY_BASE_MS up to RETRY_MAX_MS; after MAX_ATTEMPTS the
+// op stays in the journal and is replayed on the next start.
+const RETRY_BASE_MS: u64 = 250;
+const RETRY_MAX_MS: u64 = 8_000;
+const MAX_ATTEMPTS: u32 = 6;
+
+// Serializes journal rewrites between the worker and replay.
+static JOURNAL_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum HistoryOp {
+    Append {
+        item: Box<HistoryItem>,
+    },
+    UpdateFinalText {
+        task_id: String,
+        final_text: String,
+        template_id: Option<String>,
+    },
+    UpdateInsertedText {
+        task_id: String,
+        inserted_text: String,
+    },
+    UpdateRecordingPath {
+        task_id: String,
+        recording_path: String,
+    },
+}
+
+impl HistoryOp {
+    pub fn task_id(&self) -> &str {
+        match self {
+            Self::Append { item } => &item.task_id,
+            Self::UpdateFinalText { task_id, .. }
+            | Self::UpdateInsertedText { task_id, .. }
+            | Self::UpdateRecordingPath { task_id, .. } => task_id,
+        }
+    }
+
+    fn apply(&self, db_path: &Path) -> Result<()> {
+        match self {
+            Self::Append { item } => history::append(db_path, item),
+            Self::UpdateFinalText {
+                task_id,
+                final_text,
+                template_id,
+            } => history::update_final_text(db_path, task_id, final_text, template_id.as_deref()),
+            Self::UpdateInsertedText {
+                task_id,
+                inserted_text,
+            } => history::update_inserted_text(db_path, task_id, inserted_text),
+            Self::UpdateRecordingPath {
+                task_id,
+                recording_path,
+            } => history::update_recording_path(db_path, task_id, recording_path),
+        }
+    }
+}
+
+// Reported for every failed attempt; `gave_up` means retries are exhausted and
+// the op waits in the journal for the next start.
+#[derive(Debug, Clone, Serialize)]
+pub struct PersistWarning {
+    pub task_id: String,
+    pub code: String,
+    pub message: String,
+    pub attempt: u32,
+    pub journaled: bool,
+    pub gave_up: bool,
+}
+
+type WarningFn = Box<dyn Fn(&PersistWarning) + Send>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct JournalEntry {
+    id: String,
+    op: HistoryOp,
+}
+
+struct Job {
+    db_path: PathBuf,
+    op: HistoryOp,
+    journal_id: Option<String>,
+    on_warning: Option<WarningFn>,
+}
+
+enum Msg {
+    Job(Box<Job>),
+    Flush(mpsc::Sender<()>),
+}
+
+fn writer_tx() -> &'static Mutex<Sender<Msg>> {
+    static TX: OnceLock<Mutex<Sender<Msg>>> = OnceLock::new();
+    TX.get_or_init(|| {
+        let (tx, rx) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("typevoice-history-writer".to_string())
+            .spawn(move || writer_loop(rx))
+            .expect("failed to start history writer thread");
+        Mutex::new(tx)
+    })
+}
+
+fn send(msg: Msg) -> bool {
+    writer_tx()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .send(msg)
+        .is_ok()
+}
+
+pub fn journal_path(db_path: &Path) -> PathBuf {
+    db_path.with_file_name("history_journal.jsonl")
+}
+
+// Queues the write and returns at once; ops run in submission order, so an
+// update never overtakes the append of the same task.
+pub fn submit(
+    db_path: &Path,
+    op: HistoryOp,
+    on_warning: impl Fn(&PersistWarning) + Send + 'static,
+) {
+    // Guest dictations are not recorded at all, so there is nothing to retry.
+    if guest_mode::enabled() {
+        return;
+    }
+    send(Msg::Job(Box::new(Job {
+        db_path: db_path.to_path_buf(),
+        op,
+        journal_id: None,
+        on_warning: Some(Box::new(on_warning)),
+    })));
+}
+
+// Re-queues ops left in the journal by a previous run. Returns how many were
+// queued; an unreadable line is skipped rather than blocking the rest.
+pub fn replay_journal(db_path: &Path) -> Result<usize> {
+    let entries = {
+        let _guard = JOURNAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        read_journal(db_path)?
+    };
+    let n = entries.len();
+    for entry in entries {
+        send(Msg::Job(Box::new(Job {
+            db_path: db_path.to_path_buf(),
+            op: entry.op,
+            journal_id: Some(entry.id),
+            on_warning: None,
+        })));
+    }
+    Ok(n)
+}
+
+// Waits until every op queued before the call has been attempted to completion.
+pub fn flush(timeout_ms: u64) -> bool {
+    let (ack_tx, ack_rx) = mpsc::channel();
+    if !send(Msg::Flush(ack_tx)) {
+        return false;
+    }
+    ack_rx
+        .recv_timeout(Duration::from_millis(timeout_ms))
+        .is_ok()
+}
+
+fn writer_loop(rx: Receiver<Msg>) {
+    while let Ok(msg) = rx.recv() {
+        match msg {
+            Msg::Job(job) => run_job(*job),
+            Msg::Flush(ack) => {
+                let _ = ack.send(());
+            }
+        }
+    }
+}
+
+fn run_job(mut job: Job) {
+    let data_dir = job
+        .db_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .to_path_buf();
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let err = match job.op.apply(&job.db_path) {
+            Ok(()) => {
+                if let Some(id) = job.journal_id.as_deref() {
+                    if let Err(e) = remove_journal_entry(&job.db_path, id) {
+                        journal_error(&data_dir, &job.op, &e);
+                    }
+                }
+                if attempt > 1 {
+                    obs::event(
+                        &data_dir,
+                        Some(job.op.task_id()),
+                        "History",
+                        "HISTORY.persist_recovered",
+                        "ok",
+                        Some(serde_json::json!({ "attempts": attempt })),
+                    );
+                }
+                return;
+            }
+            Err(e) => e,
+        };
+        let raw = format!("{err:#}");
+        // A missing row will not appear by retrying (e.g. history was cleared).
+        let permanent = raw.contains("E_HISTORY_NOT_FOUND");
+        if job.journal_id.is_none() && !permanent {
+            match append_journal_entry(&job.db_path, &job.op) {
+                Ok(id) => job.journal_id = Some(id),
+                Err(e) => journal_error(&data_dir, &job.op, &e),
+            }
+        }
+        if permanent {
+            if let Some(id) = job.journal_id.take() {
+                let _ = remove_journal_entry(&job.db_path, &id);
+            }
+        }
+        let gave_up = permanent || attempt >= MAX_ATTEMPTS;
+        let warning = PersistWarning {
+            task_id: job.op.task_id().to_string(),
+            code: if permanent {
+                "E_HISTORY_NOT_FOUND".to_string()
+            } else {
+                "E_HISTORY_PERSIST_RETRY".to_string()
+            },
+            message: raw,
+            attempt,
+            journaled: job.journal_id.is_some(),
+            gave_up,
+        };
+        obs::event_err(
+            &data_dir,
+            obs::ErrorEvent {
+                task_id: Some(job.op.task_id()),
+                stage: "History",
+                step_id: "HISTORY.persist_retry",
+                kind: "db",
+                code: &warning.code,
+                ctx: Some(serde_json::json!({
+                    "attempt": attempt,
+                    "journaled": warning.journaled,
+                    "gave_up": gave_up,
+                })),
+            },
+            &warning.message,
+        );
+        if let Some(f) = job.on_warning.as_ref() {
+            f(&warning);
+        }
+        if gave_up {
+            return;
+        }
+        let backoff = (RETRY_BASE_MS << (attempt - 1)).min(RETRY_MAX_MS);
+        std::thread::sleep(Duration::from_millis(backoff));
+    }
+}
+
+fn journal_error(data_dir: &Path, op: &HistoryOp, err: &anyhow::Error) {
+    obs::event_err_anyhow(
+        data_dir,
+        obs::ErrorEvent {
+            task_id: Some(op.task_id()),
+            stage: "History",
+            step_id: "HISTORY.journal",
+            kind: "io",
+            code: "E_HISTORY_JOURNAL",
+            ctx: None,
+        },
+        err,
+    );
+}
+
+fn read_journal(db_path: &Path) -> Result<Vec<JournalEntry>> {
+    let raw = match fs::read_to_string(journal_path(db_path)) {
+        Ok(v) => v,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).context("E_HISTORY_JOURNAL: read history journal failed"),
+    };
+    Ok(raw
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+fn append_journal_entry(db_path: &Path, op: &HistoryOp) -> Result<String> {
+    static SEQ: AtomicU64 = AtomicU64::new(0);
+    let _guard = JOURNAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let entry = JournalEntry {
+        id: format!("{}-{}", now_ms(), SEQ.fetch_add(1, Ordering::Relaxed)),
+        op: op.clone(),
+    };
+    let line = serde_json::to_string(&entry).context("serialize history journal entry failed")?;
+    let mut f = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(journal_path(db_path))
+        .context("E_HISTORY_JOURNAL: open history journal failed")?;
+    writeln!(f, "{line}").context("E_HISTORY_JOURNAL: write history journal failed")?;
+    Ok(entry.id)
+}
+
+fn remove_journal_entry(db_path: &Path, id: &str) -> Result<()> {
+    let _guard = JOURNAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let rest: Vec<JournalEntry> = read_journal(db_path)?
+        .into_iter()
+        .filter(|e| e.id != id)
+        .collect();
+    let path = journal_path(db_path);
+    if rest.is_empty() {
+        return match fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e).context("E_HISTORY_JOURNAL: remove history journal failed"),
+        };
+    }
+    let mut s = String::new();
+    for entry in &rest {
+        s.push_str(&serde_json::to_string(entry).context("serialize history journal failed")?);
+        s.push('\n');
+    }
+    atomic_file::write(&path, s.as_bytes())
+        .map_err(|e| anyhow!("E_HISTORY_JOURNAL: write history journal failed: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn item(task_id: &str) -> HistoryItem {
+        HistoryItem {
+            task_id: task_id.to_string(),
+            created_at_ms: 1,
+            asr_text: "raw".to_string(),
+            rewritten_text: String::new(),
+            inserted_text: String::new(),
+            final_text: "raw".to_string(),
+            template_id: None,
+            rtf: 0.4,
+            device_used: "remote".to_string(),
+            preprocess_ms: 10,
+            asr_ms: 20,
+            target_app: None,
+            target_title_hash: None,
+            tags: Vec::new(),
+            note: String::new(),
+        }
+    }
+
+    #[test]
+    fn failed_write_is_journaled_and_retried_in_order() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let db = tmp.path().join("history.sqlite3");
+        // A directory at the db path makes sqlite open fail until it is removed.
+        fs::create_dir(&db).expect("block db path");
+        let warnings = Arc::new(Mutex::new(Vec::<PersistWarning>::new()));
+        let sink = warnings.clone();
+        submit(
+            &db,
+            HistoryOp::Append {
+                item: Box::new(item("t1")),
+            },
+            move |w| {
+                sink.lock().unwrap().push(w.clone());
+            },
+        );
+        submit(
+            &db,
+            HistoryOp::UpdateFinalText {
+                task_id: "t1".to_string(),
+                final_text: "rewritten".to_string(),
+                template_id: None,
+            },
+            |_| {},
+        );
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while warnings.lock().unwrap().is_empty() {
+            assert!(std::time::Instant::now() < deadline, "no warning reported");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(journal_path(&db).exists());
+        fs::remove_dir(&db).expect("unblock db path");
+        assert!(flush(10_000), "history writer flush timeout");
+
+        let w = warnings.lock().unwrap()[0].clone();
+        assert_eq!(w.code, "E_HISTORY_PERSIST_RETRY");
+        assert!(w.journaled && !w.gave_up);
+        let rows = history::list(&db, 10, None).expect("list");
+        assert_eq!(rows[0].final_text, "rewritten");
+        assert!(!journal_path(&db).exists());
+    }
+
+    #[test]
+    fn replay_journal_applies_pending_ops() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let db = tmp.path().join("history.sqlite3");
+        append_journal_entry(
+            &db,
+            &HistoryOp::Append {
+                item: Box::new(item("t2")),
+            },
+        )
+        .expect("journal");
+        assert_eq!(replay_journal(&db).expect("replay"), 1);
+        assert!(flush(5_000), "history writer flush timeout");
+        assert_eq!(history::list(&db, 10, None).expect("list").len(), 1);
+        assert!(!journal_path(&db).exists());
+    }
+}
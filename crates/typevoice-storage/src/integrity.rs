@@ -0,0 +1,262 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Result};
+use rusqlite::{Connection, ErrorCode};
+use serde::Serialize;
+
+use crate::obs::{self, schema::now_ms, Span};
+use crate::settings::{self, Settings};
+use crate::workspaces::{self, WorkspaceTemplates};
+
+const CORRUPT_DIR: &str = "corrupt";
+// Moved along with a quarantined database so a stale WAL is not replayed
+// into the fresh one.
+const SQLITE_SIDECARS: &[&str] = &["-wal", "-shm", "-journal"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataFileKind {
+    Settings,
+    Templates,
+    History,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QuarantinedFile {
+    pub kind: DataFileKind,
+    pub path: String,
+    pub quarantined_to: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveryReport {
+    pub ts_ms: i64,
+    pub corrupt_dir: String,
+    pub files: Vec<QuarantinedFile>,
+}
+
+static LAST_RECOVERY: Mutex<Option<RecoveryReport>> = Mutex::new(None);
+
+pub fn corrupt_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(CORRUPT_DIR)
+}
+
+// What the startup check moved aside in this process, for a UI that starts
+// listening after the event was sent.
+pub fn last_recovery() -> Option<RecoveryReport> {
+    LAST_RECOVERY.lock().unwrap().clone()
+}
+
+// Run once at startup, before anything opens the files. settings.json and every
+// workspace's template set must parse and every workspace's history DB must
+// pass `PRAGMA integrity_check`; a file that does not is moved to `corrupt/`
+// under a timestamped name so the app starts from defaults instead of failing
+// every command. A check that fails for another reason (a locked or unreadable
+// file) is traced and leaves the file alone. Returns None when nothing moved.
+pub fn check_and_quarantine(data_dir: &Path) -> Option<RecoveryReport> {
+    let span = Span::start(data_dir, None, "App", "APP.data_integrity", None);
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let mut files = Vec::new();
+    let mut failed = Vec::new();
+    let mut check = |kind: DataFileKind, path: PathBuf, verdict: Result<Option<String>>| {
+        let moved = match verdict {
+            Ok(None) => return,
+            Ok(Some(reason)) => quarantine(data_dir, &path, &stamp).map(|to| QuarantinedFile {
+                kind,
+                path: path.display().to_string(),
+                quarantined_to: to.display().to_string(),
+                reason,
+            }),
+            Err(e) => Err(e),
+        };
+        match moved {
+            Ok(file) => files.push(file),
+            Err(e) => failed.push(serde_json::json!({
+                "kind": kind,
+                "path": path.display().to_string(),
+                "error": e.to_string(),
+            })),
+        }
+    };
+
+    // Settings first: it names the active workspace the other checks use.
+    let settings_path = settings::settings_path(data_dir);
+    check(
+        DataFileKind::Settings,
+        settings_path.clone(),
+        check_json::<Settings>(&settings_path),
+    );
+    for name in workspaces::workspace_names(data_dir) {
+        let templates = workspaces::templates_path(data_dir, &name);
+        let verdict = check_json::<WorkspaceTemplates>(&templates);
+        check(DataFileKind::Templates, templates, verdict);
+        let db = workspaces::workspace_history_db(data_dir, &name);
+        let verdict = check_sqlite(&db);
+        check(DataFileKind::History, db, verdict);
+    }
+    if files.iter().any(|f| f.kind == DataFileKind::Settings) {
+        if let Err(e) = settings::ensure_settings(data_dir) {
+            failed.push(serde_json::json!({"kind": "settings", "error": e.to_string()}));
+        }
+    }
+
+    let ctx = serde_json::json!({
+        "quarantined": files.iter().map(|f| serde_json::json!({
+            "kind": f.kind,
+            "reason": f.reason,
+        })).collect::<Vec<_>>(),
+        "failed": failed,
+    });
+    if failed.is_empty() {
+        span.ok(Some(ctx));
+    } else {
+        span.err(
+            "io",
+            "E_DATA_INTEGRITY_CHECK",
+            "some data files could not be checked or moved",
+            Some(ctx),
+        );
+    }
+    if files.is_empty() {
+        return None;
+    }
+    let report = RecoveryReport {
+        ts_ms: now_ms(),
+        corrupt_dir: corrupt_dir(data_dir).display().to_string(),
+        files,
+    };
+    for f in &report.files {
+        obs::event(
+            data_dir,
+            None,
+            "App",
+            "APP.data_quarantined",
+            "ok",
+            serde_json::to_value(f).ok(),
+        );
+    }
+    *LAST_RECOVERY.lock().unwrap() = Some(report.clone());
+    Some(report)
+}
+
+// Some(reason) when the file exists and cannot be used.
+fn check_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<Option<String>> {
+    let bytes = match fs::read(path) {
+        Ok(v) => v,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(anyhow!("read {} failed: {e}", path.display())),
+    };
+    Ok(serde_json::from_slice::<T>(&bytes)
+        .err()
+        .map(|e| format!("invalid json: {e}")))
+}
+
+pub(crate) fn check_sqlite(path: &Path) -> Result<Option<String>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let corrupt = |e: &rusqlite::Error| {
+        matches!(
+            e.sqlite_error_code(),
+            Some(ErrorCode::NotADatabase | ErrorCode::DatabaseCorrupt)
+        )
+    };
+    let rows = Connection::open(path).and_then(|c| {
+        let mut stmt = c.prepare("PRAGMA integrity_check")?;
+        let rows = stmt
+            .query_map([], |r| r.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>();
+        rows
+    });
+    match rows {
+        Ok(rows) if rows.len() == 1 && rows[0] == "ok" => Ok(None),
+        Ok(rows) => Ok(Some(format!(
+            "integrity_check: {}",
+            rows.into_iter().take(3).collect::<Vec<_>>().join("; ")
+        ))),
+        Err(e) if corrupt(&e) => Ok(Some(format!("sqlite: {e}"))),
+        Err(e) => Err(anyhow!("check {} failed: {e}", path.display())),
+    }
+}
+
+// `corrupt/<stamp>-<path relative to the data dir>`, separators flattened,
+// with a counter if that name is taken.
+fn quarantine(data_dir: &Path, path: &Path, stamp: &str) -> Result<PathBuf> {
+    let dir = corrupt_dir(data_dir);
+    fs::create_dir_all(&dir).map_err(|e| anyhow!("create {} failed: {e}", dir.display()))?;
+    let rel = path.strip_prefix(data_dir).unwrap_or(path);
+    let label = rel
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("_");
+    let mut to = dir.join(format!("{stamp}-{label}"));
+    for n in 2.. {
+        if !to.exists() {
+            break;
+        }
+        to = dir.join(format!("{stamp}-{n}-{label}"));
+    }
+    fs::rename(path, &to).map_err(|e| anyhow!("move {} failed: {e}", path.display()))?;
+    for suffix in SQLITE_SIDECARS {
+        let side = PathBuf::from(format!("{}{suffix}", path.display()));
+        if side.exists() {
+            let _ = fs::rename(&side, PathBuf::from(format!("{}{suffix}", to.display())));
+        }
+    }
+    Ok(to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corrupt_files_are_moved_aside_and_settings_reset() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let dir = tmp.path();
+        assert!(check_and_quarantine(dir).is_none());
+
+        fs::write(settings::settings_path(dir), b"{\"rewrite_enabled\": tru").expect("write");
+        let other = workspaces::workspace_dir(dir, "work");
+        fs::create_dir_all(&other).expect("mkdir");
+        fs::write(
+            workspaces::templates_path(dir, "work"),
+            b"{\"llm_prompt\": 3}",
+        )
+        .expect("write");
+        let db = workspaces::workspace_history_db(dir, "work");
+        fs::write(&db, vec![0x5a; 4096]).expect("write");
+        fs::write(format!("{}-wal", db.display()), b"stale").expect("write");
+        let healthy = workspaces::workspace_history_db(dir, workspaces::DEFAULT_WORKSPACE);
+        crate::history::list(&healthy, 1, None).expect("create db");
+
+        let report = check_and_quarantine(dir).expect("report");
+        let kinds: Vec<DataFileKind> = report.files.iter().map(|f| f.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                DataFileKind::Settings,
+                DataFileKind::Templates,
+                DataFileKind::History
+            ]
+        );
+        for f in &report.files {
+            let to = Path::new(&f.quarantined_to);
+            assert!(to.exists() && to.starts_with(corrupt_dir(dir)));
+        }
+        assert!(!db.exists() && !workspaces::templates_path(dir, "work").exists());
+        assert!(!Path::new(&format!("{}-wal", db.display())).exists());
+        assert!(healthy.exists());
+        let reset = settings::load_settings_strict(dir).expect("defaults");
+        assert_eq!(
+            serde_json::to_value(reset).unwrap(),
+            serde_json::to_value(Settings::default()).unwrap()
+        );
+        assert_eq!(last_recovery().map(|r| r.files.len()), Some(3));
+        assert!(check_and_quarantine(dir).is_none());
+    }
+}
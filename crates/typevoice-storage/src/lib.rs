@@ -1,5 +1,20 @@
-pub use typevoice_observability::obs;
+pub use typevoice_observability::{guest_mode, obs};
 
+pub mod asr_accuracy;
+pub mod atomic_file;
+pub mod audit_log;
+pub mod backup;
+pub mod corrections;
 pub mod data_dir;
+pub mod dir_lock;
+pub mod formatting_profiles;
 pub mod history;
+pub mod history_bulk;
+pub mod history_writer;
+pub mod integrity;
+pub mod rewrite_cache;
 pub mod settings;
+pub mod settings_validation;
+pub mod timestamps;
+pub mod workspaces;
+pub mod write_gate;
@@ -0,0 +1,172 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::atomic_file;
+
+pub const DEFAULT_CAPACITY: usize = 32;
+// The cache is for retries of a recent rewrite, not a long-term store of text.
+pub const MAX_AGE_MS: i64 = 24 * 60 * 60 * 1000;
+
+// Background rewrites can finish concurrently; serialize load-modify-save.
+static LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub key: String, // hash of asr_text + template + prompt + context
+    pub final_text: String,
+    pub created_ms: i64,
+}
+
+// Entries are kept most-recently-used first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CacheFile {
+    #[serde(default)]
+    entries: Vec<CacheEntry>,
+    #[serde(default)]
+    hits: u64,
+    #[serde(default)]
+    misses: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheFile {
+    fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.len(),
+            hits: self.hits,
+            misses: self.misses,
+        }
+    }
+
+    fn drop_expired(&mut self, now_ms: i64) {
+        self.entries
+            .retain(|e| now_ms.saturating_sub(e.created_ms) < MAX_AGE_MS);
+    }
+}
+
+pub fn cache_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("rewrite_cache.json")
+}
+
+// A missing or unreadable file is an empty cache; it is rebuilt on the next store.
+fn load(data_dir: &Path) -> CacheFile {
+    fs::read_to_string(cache_path(data_dir))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save(data_dir: &Path, file: &CacheFile) -> Result<()> {
+    fs::create_dir_all(data_dir).context("create data dir failed")?;
+    let path = cache_path(data_dir);
+    let s = serde_json::to_string(file).context("serialize rewrite cache failed")?;
+    atomic_file::write(&path, s.as_bytes())
+        .map_err(|e| anyhow!("E_REWRITE_CACHE_WRITE: write rewrite cache failed: {e}"))
+}
+
+// Counts a hit or miss; a hit moves the entry to the front of the LRU order.
+pub fn lookup(data_dir: &Path, key: &str, now_ms: i64) -> Result<(Option<String>, CacheStats)> {
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut file = load(data_dir);
+    file.drop_expired(now_ms);
+    let found = file.entries.iter().position(|e| e.key == key);
+    let text = match found {
+        Some(i) => {
+            let entry = file.entries.remove(i);
+            let text = entry.final_text.clone();
+            file.entries.insert(0, entry);
+            file.hits += 1;
+            Some(text)
+        }
+        None => {
+            file.misses += 1;
+            None
+        }
+    };
+    save(data_dir, &file)?;
+    Ok((text, file.stats()))
+}
+
+pub fn store(
+    data_dir: &Path,
+    key: &str,
+    final_text: &str,
+    capacity: usize,
+    now_ms: i64,
+) -> Result<CacheStats> {
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut file = load(data_dir);
+    file.drop_expired(now_ms);
+    file.entries.retain(|e| e.key != key);
+    file.entries.insert(
+        0,
+        CacheEntry {
+            key: key.to_string(),
+            final_text: final_text.to_string(),
+            created_ms: now_ms,
+        },
+    );
+    file.entries.truncate(capacity.max(1));
+    save(data_dir, &file)?;
+    Ok(file.stats())
+}
+
+pub fn clear(data_dir: &Path) -> Result<()> {
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    match fs::remove_file(cache_path(data_dir)) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(anyhow::anyhow!(
+            "E_REWRITE_CACHE_WRITE: remove rewrite cache failed: {e}"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clear, lookup, store, MAX_AGE_MS};
+
+    #[test]
+    fn lookup_counts_hits_and_misses_and_evicts_lru() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let d = dir.path();
+        let (hit, stats) = lookup(d, "a", 0).expect("miss");
+        assert!(hit.is_none());
+        assert_eq!((stats.hits, stats.misses), (0, 1));
+
+        store(d, "a", "A", 2, 0).expect("store a");
+        store(d, "b", "B", 2, 0).expect("store b");
+        // Touch "a" so "b" becomes the least recently used.
+        assert_eq!(lookup(d, "a", 1).expect("hit").0.as_deref(), Some("A"));
+        let stats = store(d, "c", "C", 2, 2).expect("store c");
+        assert_eq!(stats.entries, 2);
+        assert!(lookup(d, "b", 3).expect("evicted").0.is_none());
+        let (hit, stats) = lookup(d, "c", 4).expect("hit c");
+        assert_eq!(hit.as_deref(), Some("C"));
+        assert_eq!((stats.hits, stats.misses), (2, 2));
+
+        clear(d).expect("clear");
+        assert!(lookup(d, "a", 5).expect("cleared").0.is_none());
+    }
+
+    #[test]
+    fn expired_entries_are_misses() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        store(dir.path(), "k", "text", 4, 0).expect("store");
+        let (hit, stats) = lookup(dir.path(), "k", MAX_AGE_MS).expect("lookup");
+        assert!(hit.is_none());
+        assert_eq!(stats.entries, 0);
+    }
+}
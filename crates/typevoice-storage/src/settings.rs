@@ -1,38 +1,197 @@
 use std::{
+    collections::BTreeMap,
     fs,
     path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard, OnceLock},
 };
 
 use anyhow::{anyhow, Context, Result};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::atomic_file;
+use crate::audit_log::AuditRetention;
 use crate::obs::Span;
+use crate::timestamps::TimeFormat;
 
 pub const DEFAULT_ASR_PROVIDER: &str = "doubao";
+pub const DEFAULT_LOW_POWER_MODE: &str = "off";
 pub const DEFAULT_REMOTE_ASR_URL: &str = "https://api.server/transcribe";
 pub const DEFAULT_REMOTE_ASR_CONCURRENCY: usize = 4;
 pub const MAX_REMOTE_ASR_CONCURRENCY: usize = 16;
+pub const DEFAULT_ASR_WARM_POOL_MAX_MODELS: usize = 2;
+pub const MAX_ASR_WARM_POOL_MODELS: usize = 4;
+// Counted for models without an entry in asr_warm_pool_model_vram_mb.
+pub const DEFAULT_ASR_MODEL_VRAM_MB: u64 = 2_000;
+pub const DEFAULT_ASR_WARM_POOL_KEEPALIVE_SECS: u64 = 240;
+pub const MIN_ASR_WARM_POOL_KEEPALIVE_SECS: u64 = 30;
+// Off by default: a background rewrite finishes after the next take has
+// started, so the user opts in to results that arrive out of order.
+pub const DEFAULT_PIPELINE_MAX_BACKGROUND_REWRITES: usize = 0;
+pub const MAX_PIPELINE_MAX_BACKGROUND_REWRITES: usize = 4;
+// Longer than the default HTTP request timeout, so a slow provider call fails
+// on its own before the watchdog steps in.
+pub const DEFAULT_TASK_STALL_TIMEOUT_MS: u64 = 300_000;
+pub const MIN_TASK_STALL_TIMEOUT_MS: u64 = 30_000;
+pub const DEFAULT_REWRITE_CHUNK_THRESHOLD_TOKENS: u64 = 6_000;
+pub const DEFAULT_REWRITE_CHUNK_TOKENS: u64 = 2_000;
+pub const MIN_REWRITE_CHUNK_TOKENS: u64 = 200;
+// 0 turns the cap off; smaller caps would leave no room for the prompt.
+pub const MIN_REWRITE_TOKEN_CAP: u64 = 500;
+pub const MAX_CLIPBOARD_AUTO_CLEAR_SECS: u64 = 3_600;
+pub const DEFAULT_HTTP_CONNECT_TIMEOUT_MS: u64 = 10_000;
+pub const DEFAULT_HTTP_REQUEST_TIMEOUT_MS: u64 = 180_000;
+const MIN_HTTP_TIMEOUT_MS: u64 = 1_000;
+pub const DEFAULT_HOTKEY_DEBOUNCE_MS: u64 = 250;
+pub const MAX_HOTKEY_DEBOUNCE_MS: u64 = 2_000;
 pub const DEFAULT_OVERLAY_BACKGROUND_OPACITY: f64 = 0.78;
 pub const DEFAULT_OVERLAY_FONT_SIZE_PX: u64 = 32;
 pub const DEFAULT_OVERLAY_WIDTH_PX: u64 = 960;
 pub const DEFAULT_OVERLAY_HEIGHT_PX: u64 = 160;
+pub const DEFAULT_OVERLAY_FONT_SCALE: f64 = 1.0;
+pub const DEFAULT_OVERLAY_STATUS_LANGUAGE: &str = "en";
+// Workflow phases the overlay can show, with their default color and labels.
+pub const OVERLAY_STATUSES: &[(&str, &str, &str, &str)] = &[
+    ("idle", "#ffffff", "Ready", "就绪"),
+    ("recording", "#ff6b6b", "Listening", "正在听"),
+    ("transcribing", "#ffd166", "Creating text", "正在转写"),
+    ("rewriting", "#ffd166", "Improving text", "正在润色"),
+    ("inserting", "#ffd166", "Pasting text", "正在粘贴"),
+    ("transcribed", "#7bd88f", "Text ready", "文本就绪"),
+    ("rewritten", "#7bd88f", "Text improved", "润色完成"),
+    ("failed", "#ff6b6b", "Action needed", "需要处理"),
+];
+pub const DEFAULT_CAPTIONS_FONT_SIZE_PX: u64 = 44;
+pub const DEFAULT_CAPTIONS_BACKGROUND_OPACITY: f64 = 0.6;
+pub const DEFAULT_WAKE_WORD_PHRASE: &str = "hey typevoice";
+pub const DEFAULT_WAKE_WORD_SENSITIVITY: f64 = 0.5;
+pub const DEFAULT_EXPORT_FORMAT: &str = "plain";
+pub const DEFAULT_REWRITE_OUTPUT_FORMAT: &str = "text";
+pub const DEFAULT_LLM_CAPTURE_MAX_ENTRIES: u64 = 20;
+pub const MAX_LLM_CAPTURE_MAX_ENTRIES: u64 = 200;
+pub const DEFAULT_AUDIT_LOG_RETENTION_DAYS: i64 = 90;
+pub const DEFAULT_AUDIT_LOG_MAX_ENTRIES: i64 = 10_000;
+pub const DEFAULT_TEXT_CASING: &str = "preserve";
+pub const DEFAULT_RECORDING_FORMAT: &str = "wav";
+pub const DEFAULT_RECORD_BACKEND: &str = "native";
+pub const DEFAULT_RECORD_VAD_SILENCE_MS: u64 = 1_500;
+pub const MIN_RECORD_VAD_SILENCE_MS: u64 = 300;
+pub const MAX_RECORD_VAD_SILENCE_MS: u64 = 60_000;
+pub const DEFAULT_TEXT_NORMALIZE_LOCALES: &[&str] = &["zh", "en"];
+pub const POSTPROCESSOR_KINDS: &[&str] = &["command", "wasm"];
+pub const DEFAULT_POSTPROCESSOR_TIMEOUT_MS: u64 = 5_000;
+pub const MIN_POSTPROCESSOR_TIMEOUT_MS: u64 = 100;
+pub const MAX_POSTPROCESSOR_TIMEOUT_MS: u64 = 60_000;
+pub const DEFAULT_MARKDOWN_NOTES_ENTRY_TEMPLATE: &str = "## {{time}}\n\n{{text}}\n";
+pub const DEFAULT_MQTT_TOPIC_PREFIX: &str = "typevoice";
+pub const DEFAULT_MQTT_PORT: u16 = 1883;
+pub const DEFAULT_MQTT_TLS_PORT: u16 = 8883;
+pub const DEFAULT_DAILY_SUMMARY_TIME: &str = "18:00";
+pub const DEFAULT_DAILY_SUMMARY_PROMPT: &str = "The TRANSCRIPT lists today's dictations in time order. Write a concise Markdown digest for a standup or journal: group related items under short headings, list decisions, tasks and open questions as bullets, and keep names, numbers and terms exactly as dictated. Do not invent content.";
+// History fields a webhook may carry besides the always-present task_id.
+pub const WEBHOOK_FIELDS: &[&str] = &[
+    "created_at_ms",
+    "asr_text",
+    "final_text",
+    "inserted_text",
+    "template_id",
+    "rewrite_enabled",
+    "rtf",
+    "device_used",
+    "preprocess_ms",
+    "asr_ms",
+];
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RecordDeviceOverride {
+    pub gain_db: Option<f64>,
+    pub extra_ffmpeg_args: Option<Vec<String>>,
+}
+
+// Context sections a template asks for; unset ones follow the global
+// context_include_* settings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TemplateContextSections {
+    pub history: Option<bool>,
+    pub clipboard: Option<bool>,
+    pub prev_window_meta: Option<bool>,
+    pub screenshot: Option<bool>,
+}
+
+// A user post-processor run on the final text; the list order is the run order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PostprocessorConfig {
+    pub id: String,
+    pub kind: String,              // command|wasm
+    pub path: String,              // executable, or .wasm module
+    pub args: Option<Vec<String>>, // command only
+    pub enabled: Option<bool>,     // default true
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Settings {
     pub asr_provider: Option<String>, // doubao|remote
     pub remote_asr_url: Option<String>,
     pub remote_asr_model: Option<String>,
     pub remote_asr_concurrency: Option<u64>,
+    // Keep the active remote model and up to max_models - 1 others loaded on
+    // the ASR server so switching between them skips the model load.
+    pub asr_warm_pool_enabled: Option<bool>,
+    pub asr_warm_pool_models: Option<Vec<String>>, // kept warm besides recently used ones
+    pub asr_warm_pool_max_models: Option<u64>,
+    pub asr_warm_pool_vram_budget_mb: Option<u64>, // 0 = no budget
+    pub asr_warm_pool_model_vram_mb: Option<BTreeMap<String, u64>>, // estimates by model
+    pub asr_warm_pool_keepalive_secs: Option<u64>,
+    // Remote ASR only: transcribe short chunks while still recording so the
+    // overlay shows live text; the final text still comes from the full file.
+    pub remote_asr_live_preview: Option<bool>,
     pub asr_preprocess_silence_trim_enabled: Option<bool>,
     pub asr_preprocess_silence_threshold_db: Option<f64>,
     pub asr_preprocess_silence_start_ms: Option<u64>,
     pub asr_preprocess_silence_end_ms: Option<u64>,
+    pub asr_preprocess_gain_db: Option<f64>,
+    // Keep one idle ffmpeg ready for the next preprocess instead of spawning per task.
+    pub asr_preprocess_warm_spare_enabled: Option<bool>,
+    // Preprocess WAV input in-process; other formats still go through ffmpeg.
+    pub asr_preprocess_native_wav_enabled: Option<bool>,
+
+    // Outbound HTTP proxy (http|https|socks5|socks5h). Credentials live in the secret store.
+    pub proxy_url: Option<String>,
+    pub proxy_no_proxy: Option<Vec<String>>,
+    // Extra trust for self-hosted endpoints: a PEM root bundle and/or a pinned
+    // SHA-256 fingerprint of the server certificate. The pin only covers the
+    // LLM and remote ASR hosts; every other host keeps chain validation.
+    pub tls_ca_bundle_path: Option<String>,
+    pub tls_pinned_sha256: Option<String>,
+    pub http_connect_timeout_ms: Option<u64>,
+    pub http_request_timeout_ms: Option<u64>, // 0 = no overall limit
+    // Hard switch: every outbound request (ASR, rewrite, checks) is refused.
+    pub offline_mode: Option<bool>,
 
     // LLM settings (non-sensitive). API key is stored in OS keyring.
     pub llm_base_url: Option<String>, // e.g. https://api.openai.com/v1
     pub llm_model: Option<String>,    // e.g. gpt-4o-mini
     pub llm_reasoning_effort: Option<String>, // e.g. none|minimal|low|medium|high|xhigh
     pub llm_prompt: Option<String>,
+    // Keep the last N redacted LLM request/response pairs for prompt debugging.
+    pub llm_capture_enabled: Option<bool>,
+    pub llm_capture_max_entries: Option<u64>,
+    // Serialize ASR and LLM work on a shared local GPU; the unload URLs are
+    // POSTed to free the idle backend's model before the other one runs.
+    pub gpu_coordination_enabled: Option<bool>,
+    pub gpu_asr_unload_url: Option<String>,
+    pub gpu_llm_unload_url: Option<String>,
+    // House style prepended to every rewrite prompt; the prompt wins on conflict.
+    pub rewrite_system_preamble: Option<String>,
+    pub rewrite_output_format: Option<String>, // text|json_schema
+    pub rewrite_output_format_by_template: Option<BTreeMap<String, String>>,
+    // JSON Schema text used when the format is json_schema; template wins.
+    pub rewrite_output_json_schema: Option<String>,
+    pub rewrite_output_json_schema_by_template: Option<BTreeMap<String, String>>,
+    // Template the workflow's rewrite runs with; its text carries the id on to
+    // insert, copy and sinks, so the *_by_template settings apply. None = no template.
+    pub rewrite_template_id: Option<String>,
 
     // UX settings
     pub record_input_spec: Option<String>, // ffmpeg dshow input spec, e.g. audio=default
@@ -40,27 +199,81 @@ pub struct Settings {
     pub record_follow_default_role: Option<String>, // communications|console
     pub record_fixed_endpoint_id: Option<String>,
     pub record_fixed_friendly_name: Option<String>,
+    pub record_device_preference: Option<Vec<String>>, // endpoint ids, most preferred first
+    pub record_device_overrides: Option<BTreeMap<String, RecordDeviceOverride>>, // by endpoint id
+    pub record_avoid_low_quality_profile: Option<bool>, // e.g. Bluetooth hands-free 8 kHz capture
+    pub record_backend: Option<String>, // native (in-process cpal)|ffmpeg (dshow child process)
+    pub record_vad_enabled: Option<bool>, // stop recording after trailing silence
+    pub record_vad_silence_ms: Option<u64>,
     pub record_last_working_endpoint_id: Option<String>,
     pub record_last_working_friendly_name: Option<String>,
     pub record_last_working_dshow_spec: Option<String>,
     pub record_last_working_ts_ms: Option<i64>,
     pub rewrite_enabled: Option<bool>,
     pub rewrite_glossary: Option<Vec<String>>,
+    // Rewrites allowed to finish in the background while the next recording runs; 0 = off.
+    pub pipeline_max_background_rewrites: Option<u64>,
+    // A transcribing/rewriting/inserting task with no progress for this long is
+    // failed and releases its slot; 0 = off.
+    pub task_stall_timeout_ms: Option<u64>,
+    // Transcripts estimated above the threshold are rewritten chunk by chunk
+    // and merged; 0 disables chunking.
+    pub rewrite_chunk_threshold_tokens: Option<u64>,
+    pub rewrite_chunk_tokens: Option<u64>,
+    // Reuse the result for an identical transcript + template + context.
+    pub rewrite_cache_enabled: Option<bool>,
+    // Estimated tokens per rewrite request; context is dropped to fit, 0 = off.
+    pub rewrite_token_cap: Option<u64>,
     pub auto_paste_enabled: Option<bool>,
+    // Inject through a UAC-elevated helper when the target runs elevated.
+    pub export_elevated_helper_enabled: Option<bool>,
+    // Copy-only exports are wiped from the clipboard after this many seconds
+    // if it still holds them; 0 = off.
+    pub clipboard_auto_clear_secs: Option<u64>,
+    pub export_format: Option<String>, // plain|markdown_html
+    pub export_format_by_template: Option<BTreeMap<String, String>>,
+    // Where a template's text may go (see EXPORT_TARGETS); templates without
+    // an entry are unrestricted.
+    pub export_targets_by_template: Option<BTreeMap<String, Vec<String>>>,
+    pub audit_log_enabled: Option<bool>,
+    pub audit_log_include_text: Option<bool>, // default stores only hash + length
+    pub audit_log_retention_days: Option<i64>, // 0 = no age limit
+    pub audit_log_max_entries: Option<i64>,   // 0 = no count limit
+    // Keep task audio after ASR (TYPEVOICE_KEEP_AUDIO=1 also keeps it).
+    pub keep_recordings: Option<bool>,
+    pub recording_format: Option<String>, // wav|flac|opus, transcoded after ASR
+    pub time_format: Option<String>,      // 24h|12h|iso, for history times
+    pub text_casing: Option<String>,      // preserve|sentence|lower|title
+    // Keyed by mode ("dictation"/"rewrite") or template id; template wins.
+    pub text_casing_by_mode: Option<BTreeMap<String, String>>,
+    pub text_normalize_enabled: Option<bool>,
+    pub text_normalize_locales: Option<Vec<String>>, // zh|en, applied in order
 
     // Context settings (for LLM rewrite)
     pub context_include_prev_window_meta: Option<bool>,
     pub context_include_history: Option<bool>,
     pub context_history_n: Option<i64>,
     pub context_history_window_ms: Option<i64>,
+    // Only recent dictation into the same foreground app goes into context.
+    pub context_history_same_app: Option<bool>,
+    // Condense history that does not fit into one line instead of cutting it.
+    pub context_history_summarize: Option<bool>,
     pub context_include_clipboard: Option<bool>,
     pub context_include_prev_window_screenshot: Option<bool>,
+    pub context_sections_by_template: Option<BTreeMap<String, TemplateContextSections>>,
+    // Fence and scrub untrusted context before it reaches the LLM.
+    pub context_injection_guard_enabled: Option<bool>,
     pub rewrite_include_glossary: Option<bool>,
     pub llm_supports_vision: Option<bool>,
 
     // Hotkeys / overlay (post-MVP)
     pub hotkeys_enabled: Option<bool>,
     pub hotkey_primary: Option<String>,
+    // Presses within this many ms of the last accepted one are dropped; 0 = off.
+    pub hotkey_debounce_ms: Option<u64>,
+    // Process file names (e.g. code.exe); when non-empty the hotkey only
+    // starts dictation while one of them is focused.
+    pub hotkey_app_allowlist: Option<Vec<String>>,
     pub hotkeys_show_overlay: Option<bool>,
     pub overlay_background_opacity: Option<f64>,
     pub overlay_font_size_px: Option<u64>,
@@ -68,6 +281,76 @@ pub struct Settings {
     pub overlay_height_px: Option<u64>,
     pub overlay_position_x: Option<i64>,
     pub overlay_position_y: Option<i64>,
+    // Keyed by workflow phase (OVERLAY_STATUSES); value is #rgb or #rrggbb.
+    pub overlay_status_colors: Option<BTreeMap<String, String>>,
+    pub overlay_font_scale: Option<f64>,
+    pub overlay_status_language: Option<String>, // en|zh
+
+    // Live captions window (partial transcription text)
+    pub captions_enabled: Option<bool>,
+    pub captions_font_size_px: Option<u64>,
+    pub captions_background_opacity: Option<f64>,
+    pub captions_monitor_index: Option<u64>,
+
+    // Screen reader announcements of recording/transcription/error changes
+    pub a11y_announcements: Option<bool>,
+
+    // Always-on-top capture light while any microphone capture is open; when
+    // on, captures are refused if the light cannot be shown.
+    pub capture_indicator_enforced: Option<bool>,
+
+    // Hide the overlay and skip screenshot context while a screen share is
+    // detected; extra process names count as a share in progress.
+    pub screen_share_guard_enabled: Option<bool>,
+    pub screen_share_extra_processes: Option<Vec<String>>,
+
+    // Low-power mode: "auto" (while on battery), "on" or "off". While active,
+    // screenshot context is skipped, the foreground tracker polls less often
+    // and ASR uses the low_power_* choices when they are set.
+    pub low_power_mode: Option<String>,
+    pub low_power_asr_provider: Option<String>,
+    pub low_power_remote_asr_model: Option<String>,
+
+    // Always-on wake word listener
+    pub wake_word_enabled: Option<bool>,
+    pub wake_word_phrase: Option<String>,
+    pub wake_word_sensitivity: Option<f64>,
+    // Detector process reading 16 kHz mono s16le PCM on stdin.
+    pub wake_word_detector_command: Option<String>,
+    pub postprocessors: Option<Vec<PostprocessorConfig>>,
+
+    // Daily Markdown note sink (e.g. an Obsidian vault folder)
+    pub markdown_notes_enabled: Option<bool>,
+    pub markdown_notes_folder: Option<String>,
+    // Placeholders: {{date}} {{time}} {{text}} {{template}} {{task_id}}.
+    pub markdown_notes_entry_template: Option<String>,
+    pub markdown_notes_entry_template_by_template: Option<BTreeMap<String, String>>,
+
+    // Webhook sink; the signing secret lives in the secret store.
+    pub webhook_enabled: Option<bool>,
+    pub webhook_url: Option<String>,
+    // Subset of WEBHOOK_FIELDS; None or empty sends all of them.
+    pub webhook_fields: Option<Vec<String>>,
+
+    // MQTT publisher for home automation; the password lives in the secret store.
+    pub mqtt_enabled: Option<bool>,
+    pub mqtt_broker_host: Option<String>,
+    // None picks 1883, or 8883 with TLS.
+    pub mqtt_broker_port: Option<u16>,
+    pub mqtt_tls: Option<bool>,
+    pub mqtt_topic_prefix: Option<String>,
+    pub mqtt_username: Option<String>,
+
+    // Scheduled digest of the day's dictations, written by the scheduler.
+    pub daily_summary_enabled: Option<bool>,
+    // Local time of day, "HH:MM".
+    pub daily_summary_time: Option<String>,
+    // System prompt for the digest; None uses DEFAULT_DAILY_SUMMARY_PROMPT.
+    pub daily_summary_prompt: Option<String>,
+
+    // None is the default workspace. Only `workspaces::switch` changes it, so
+    // the template set is swapped along with it; it is not in SettingsPatch.
+    pub active_workspace: Option<String>,
 }
 
 impl Default for Settings {
@@ -77,36 +360,98 @@ impl Default for Settings {
             remote_asr_url: Some(DEFAULT_REMOTE_ASR_URL.to_string()),
             remote_asr_model: None,
             remote_asr_concurrency: Some(DEFAULT_REMOTE_ASR_CONCURRENCY as u64),
+            asr_warm_pool_enabled: Some(false),
+            asr_warm_pool_models: None,
+            asr_warm_pool_max_models: Some(DEFAULT_ASR_WARM_POOL_MAX_MODELS as u64),
+            asr_warm_pool_vram_budget_mb: Some(0),
+            asr_warm_pool_model_vram_mb: None,
+            asr_warm_pool_keepalive_secs: Some(DEFAULT_ASR_WARM_POOL_KEEPALIVE_SECS),
+            remote_asr_live_preview: Some(false),
             asr_preprocess_silence_trim_enabled: Some(false),
             asr_preprocess_silence_threshold_db: Some(-50.0),
             asr_preprocess_silence_start_ms: Some(300),
             asr_preprocess_silence_end_ms: Some(300),
+            asr_preprocess_gain_db: Some(0.0),
+            asr_preprocess_warm_spare_enabled: Some(true),
+            asr_preprocess_native_wav_enabled: Some(false),
+            proxy_url: None,
+            proxy_no_proxy: None,
+            tls_ca_bundle_path: None,
+            tls_pinned_sha256: None,
+            http_connect_timeout_ms: Some(DEFAULT_HTTP_CONNECT_TIMEOUT_MS),
+            http_request_timeout_ms: Some(DEFAULT_HTTP_REQUEST_TIMEOUT_MS),
+            offline_mode: Some(false),
             llm_base_url: None,
             llm_model: None,
             llm_reasoning_effort: None,
             llm_prompt: None,
+            llm_capture_enabled: Some(false),
+            llm_capture_max_entries: Some(DEFAULT_LLM_CAPTURE_MAX_ENTRIES),
+            gpu_coordination_enabled: Some(false),
+            gpu_asr_unload_url: None,
+            gpu_llm_unload_url: None,
+            rewrite_system_preamble: None,
+            rewrite_output_format: Some(DEFAULT_REWRITE_OUTPUT_FORMAT.to_string()),
+            rewrite_output_format_by_template: None,
+            rewrite_output_json_schema: None,
+            rewrite_output_json_schema_by_template: None,
+            rewrite_template_id: None,
             record_input_spec: None,
             record_input_strategy: Some("follow_default".to_string()),
             record_follow_default_role: Some("communications".to_string()),
             record_fixed_endpoint_id: None,
             record_fixed_friendly_name: None,
+            record_device_preference: None,
+            record_device_overrides: None,
+            record_avoid_low_quality_profile: Some(false),
+            record_backend: Some(DEFAULT_RECORD_BACKEND.to_string()),
+            record_vad_enabled: Some(false),
+            record_vad_silence_ms: Some(DEFAULT_RECORD_VAD_SILENCE_MS),
             record_last_working_endpoint_id: None,
             record_last_working_friendly_name: None,
             record_last_working_dshow_spec: None,
             record_last_working_ts_ms: None,
             rewrite_enabled: Some(false),
             rewrite_glossary: Some(Vec::new()),
+            pipeline_max_background_rewrites: Some(DEFAULT_PIPELINE_MAX_BACKGROUND_REWRITES as u64),
+            task_stall_timeout_ms: Some(DEFAULT_TASK_STALL_TIMEOUT_MS),
+            rewrite_chunk_threshold_tokens: Some(DEFAULT_REWRITE_CHUNK_THRESHOLD_TOKENS),
+            rewrite_chunk_tokens: Some(DEFAULT_REWRITE_CHUNK_TOKENS),
+            rewrite_cache_enabled: Some(true),
+            rewrite_token_cap: Some(0),
             auto_paste_enabled: Some(true),
+            export_elevated_helper_enabled: Some(false),
+            clipboard_auto_clear_secs: Some(0),
+            export_format: Some(DEFAULT_EXPORT_FORMAT.to_string()),
+            export_format_by_template: None,
+            export_targets_by_template: None,
+            audit_log_enabled: Some(false),
+            audit_log_include_text: Some(false),
+            audit_log_retention_days: Some(DEFAULT_AUDIT_LOG_RETENTION_DAYS),
+            audit_log_max_entries: Some(DEFAULT_AUDIT_LOG_MAX_ENTRIES),
+            keep_recordings: Some(false),
+            recording_format: Some(DEFAULT_RECORDING_FORMAT.to_string()),
+            time_format: Some("24h".to_string()),
+            text_casing: Some(DEFAULT_TEXT_CASING.to_string()),
+            text_casing_by_mode: None,
+            text_normalize_enabled: Some(false),
+            text_normalize_locales: None,
             context_include_prev_window_meta: Some(true),
             context_include_history: Some(true),
             context_history_n: Some(3),
             context_history_window_ms: Some(30 * 60 * 1000),
+            context_history_same_app: Some(false),
+            context_history_summarize: Some(false),
             context_include_clipboard: Some(true),
             context_include_prev_window_screenshot: Some(true),
+            context_sections_by_template: None,
+            context_injection_guard_enabled: Some(true),
             rewrite_include_glossary: Some(true),
             llm_supports_vision: Some(true),
             hotkeys_enabled: Some(true),
             hotkey_primary: Some("Alt".to_string()),
+            hotkey_debounce_ms: Some(DEFAULT_HOTKEY_DEBOUNCE_MS),
+            hotkey_app_allowlist: None,
             hotkeys_show_overlay: Some(true),
             overlay_background_opacity: Some(DEFAULT_OVERLAY_BACKGROUND_OPACITY),
             overlay_font_size_px: Some(DEFAULT_OVERLAY_FONT_SIZE_PX),
@@ -114,11 +459,47 @@ impl Default for Settings {
             overlay_height_px: Some(DEFAULT_OVERLAY_HEIGHT_PX),
             overlay_position_x: None,
             overlay_position_y: None,
+            overlay_status_colors: None,
+            overlay_font_scale: Some(DEFAULT_OVERLAY_FONT_SCALE),
+            overlay_status_language: Some(DEFAULT_OVERLAY_STATUS_LANGUAGE.to_string()),
+            captions_enabled: Some(false),
+            captions_font_size_px: Some(DEFAULT_CAPTIONS_FONT_SIZE_PX),
+            captions_background_opacity: Some(DEFAULT_CAPTIONS_BACKGROUND_OPACITY),
+            captions_monitor_index: None,
+            a11y_announcements: Some(false),
+            capture_indicator_enforced: Some(false),
+            screen_share_guard_enabled: Some(true),
+            screen_share_extra_processes: None,
+            low_power_mode: Some(DEFAULT_LOW_POWER_MODE.to_string()),
+            low_power_asr_provider: None,
+            low_power_remote_asr_model: None,
+            wake_word_enabled: Some(false),
+            wake_word_phrase: Some(DEFAULT_WAKE_WORD_PHRASE.to_string()),
+            wake_word_sensitivity: Some(DEFAULT_WAKE_WORD_SENSITIVITY),
+            wake_word_detector_command: None,
+            postprocessors: Some(Vec::new()),
+            markdown_notes_enabled: Some(false),
+            markdown_notes_folder: None,
+            markdown_notes_entry_template: None,
+            markdown_notes_entry_template_by_template: None,
+            webhook_enabled: Some(false),
+            webhook_url: None,
+            webhook_fields: None,
+            mqtt_enabled: Some(false),
+            mqtt_broker_host: None,
+            mqtt_broker_port: None,
+            mqtt_tls: Some(false),
+            mqtt_topic_prefix: Some(DEFAULT_MQTT_TOPIC_PREFIX.to_string()),
+            mqtt_username: None,
+            daily_summary_enabled: Some(false),
+            daily_summary_time: Some(DEFAULT_DAILY_SUMMARY_TIME.to_string()),
+            daily_summary_prompt: None,
+            active_workspace: None,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 pub struct SettingsPatch {
     // Outer Option: whether to update this field.
     // Inner Option: Some(value)=set, None=clear.
@@ -126,36 +507,98 @@ pub struct SettingsPatch {
     pub remote_asr_url: Option<Option<String>>,
     pub remote_asr_model: Option<Option<String>>,
     pub remote_asr_concurrency: Option<Option<u64>>,
+    pub asr_warm_pool_enabled: Option<Option<bool>>,
+    pub asr_warm_pool_models: Option<Option<Vec<String>>>,
+    pub asr_warm_pool_max_models: Option<Option<u64>>,
+    pub asr_warm_pool_vram_budget_mb: Option<Option<u64>>,
+    pub asr_warm_pool_model_vram_mb: Option<Option<BTreeMap<String, u64>>>,
+    pub asr_warm_pool_keepalive_secs: Option<Option<u64>>,
+    pub remote_asr_live_preview: Option<Option<bool>>,
     pub asr_preprocess_silence_trim_enabled: Option<Option<bool>>,
     pub asr_preprocess_silence_threshold_db: Option<Option<f64>>,
     pub asr_preprocess_silence_start_ms: Option<Option<u64>>,
     pub asr_preprocess_silence_end_ms: Option<Option<u64>>,
+    pub asr_preprocess_gain_db: Option<Option<f64>>,
+    pub asr_preprocess_warm_spare_enabled: Option<Option<bool>>,
+    pub asr_preprocess_native_wav_enabled: Option<Option<bool>>,
+    pub proxy_url: Option<Option<String>>,
+    pub proxy_no_proxy: Option<Option<Vec<String>>>,
+    pub tls_ca_bundle_path: Option<Option<String>>,
+    pub tls_pinned_sha256: Option<Option<String>>,
+    pub http_connect_timeout_ms: Option<Option<u64>>,
+    pub http_request_timeout_ms: Option<Option<u64>>,
+    pub offline_mode: Option<Option<bool>>,
 
     pub llm_base_url: Option<Option<String>>,
     pub llm_model: Option<Option<String>>,
     pub llm_reasoning_effort: Option<Option<String>>,
     pub llm_prompt: Option<Option<String>>,
+    pub llm_capture_enabled: Option<Option<bool>>,
+    pub llm_capture_max_entries: Option<Option<u64>>,
+    pub gpu_coordination_enabled: Option<Option<bool>>,
+    pub gpu_asr_unload_url: Option<Option<String>>,
+    pub gpu_llm_unload_url: Option<Option<String>>,
+    pub rewrite_system_preamble: Option<Option<String>>,
+    pub rewrite_output_format: Option<Option<String>>,
+    pub rewrite_output_format_by_template: Option<Option<BTreeMap<String, String>>>,
+    pub rewrite_output_json_schema: Option<Option<String>>,
+    pub rewrite_output_json_schema_by_template: Option<Option<BTreeMap<String, String>>>,
+    pub rewrite_template_id: Option<Option<String>>,
 
     pub record_input_spec: Option<Option<String>>,
     pub record_input_strategy: Option<Option<String>>,
     pub record_follow_default_role: Option<Option<String>>,
     pub record_fixed_endpoint_id: Option<Option<String>>,
     pub record_fixed_friendly_name: Option<Option<String>>,
+    pub record_device_preference: Option<Option<Vec<String>>>,
+    pub record_device_overrides: Option<Option<BTreeMap<String, RecordDeviceOverride>>>,
+    pub record_avoid_low_quality_profile: Option<Option<bool>>,
+    pub record_backend: Option<Option<String>>,
+    pub record_vad_enabled: Option<Option<bool>>,
+    pub record_vad_silence_ms: Option<Option<u64>>,
     pub rewrite_enabled: Option<Option<bool>>,
     pub rewrite_glossary: Option<Option<Vec<String>>>,
+    pub pipeline_max_background_rewrites: Option<Option<u64>>,
+    pub task_stall_timeout_ms: Option<Option<u64>>,
+    pub rewrite_chunk_threshold_tokens: Option<Option<u64>>,
+    pub rewrite_chunk_tokens: Option<Option<u64>>,
+    pub rewrite_cache_enabled: Option<Option<bool>>,
+    pub rewrite_token_cap: Option<Option<u64>>,
     pub auto_paste_enabled: Option<Option<bool>>,
+    pub export_elevated_helper_enabled: Option<Option<bool>>,
+    pub clipboard_auto_clear_secs: Option<Option<u64>>,
+    pub export_format: Option<Option<String>>,
+    pub export_format_by_template: Option<Option<BTreeMap<String, String>>>,
+    pub export_targets_by_template: Option<Option<BTreeMap<String, Vec<String>>>>,
+    pub audit_log_enabled: Option<Option<bool>>,
+    pub audit_log_include_text: Option<Option<bool>>,
+    pub audit_log_retention_days: Option<Option<i64>>,
+    pub audit_log_max_entries: Option<Option<i64>>,
+    pub keep_recordings: Option<Option<bool>>,
+    pub recording_format: Option<Option<String>>,
+    pub time_format: Option<Option<String>>,
+    pub text_casing: Option<Option<String>>,
+    pub text_casing_by_mode: Option<Option<BTreeMap<String, String>>>,
+    pub text_normalize_enabled: Option<Option<bool>>,
+    pub text_normalize_locales: Option<Option<Vec<String>>>,
 
     pub context_include_history: Option<Option<bool>>,
     pub context_history_n: Option<Option<i64>>,
     pub context_history_window_ms: Option<Option<i64>>,
+    pub context_history_same_app: Option<Option<bool>>,
+    pub context_history_summarize: Option<Option<bool>>,
     pub context_include_clipboard: Option<Option<bool>>,
     pub context_include_prev_window_screenshot: Option<Option<bool>>,
+    pub context_sections_by_template: Option<Option<BTreeMap<String, TemplateContextSections>>>,
     pub context_include_prev_window_meta: Option<Option<bool>>,
+    pub context_injection_guard_enabled: Option<Option<bool>>,
     pub rewrite_include_glossary: Option<Option<bool>>,
     pub llm_supports_vision: Option<Option<bool>>,
 
     pub hotkeys_enabled: Option<Option<bool>>,
     pub hotkey_primary: Option<Option<String>>,
+    pub hotkey_debounce_ms: Option<Option<u64>>,
+    pub hotkey_app_allowlist: Option<Option<Vec<String>>>,
     pub hotkeys_show_overlay: Option<Option<bool>>,
     pub overlay_background_opacity: Option<Option<f64>>,
     pub overlay_font_size_px: Option<Option<u64>>,
@@ -163,6 +606,48 @@ pub struct SettingsPatch {
     pub overlay_height_px: Option<Option<u64>>,
     pub overlay_position_x: Option<Option<i64>>,
     pub overlay_position_y: Option<Option<i64>>,
+    pub overlay_status_colors: Option<Option<BTreeMap<String, String>>>,
+    pub overlay_font_scale: Option<Option<f64>>,
+    pub overlay_status_language: Option<Option<String>>,
+
+    pub captions_enabled: Option<Option<bool>>,
+    pub captions_font_size_px: Option<Option<u64>>,
+    pub captions_background_opacity: Option<Option<f64>>,
+    pub captions_monitor_index: Option<Option<u64>>,
+
+    pub a11y_announcements: Option<Option<bool>>,
+    pub capture_indicator_enforced: Option<Option<bool>>,
+    pub screen_share_guard_enabled: Option<Option<bool>>,
+    pub screen_share_extra_processes: Option<Option<Vec<String>>>,
+    pub low_power_mode: Option<Option<String>>,
+    pub low_power_asr_provider: Option<Option<String>>,
+    pub low_power_remote_asr_model: Option<Option<String>>,
+
+    pub wake_word_enabled: Option<Option<bool>>,
+    pub wake_word_phrase: Option<Option<String>>,
+    pub wake_word_sensitivity: Option<Option<f64>>,
+    pub wake_word_detector_command: Option<Option<String>>,
+    pub postprocessors: Option<Option<Vec<PostprocessorConfig>>>,
+
+    pub markdown_notes_enabled: Option<Option<bool>>,
+    pub markdown_notes_folder: Option<Option<String>>,
+    pub markdown_notes_entry_template: Option<Option<String>>,
+    pub markdown_notes_entry_template_by_template: Option<Option<BTreeMap<String, String>>>,
+
+    pub webhook_enabled: Option<Option<bool>>,
+    pub webhook_url: Option<Option<String>>,
+    pub webhook_fields: Option<Option<Vec<String>>>,
+
+    pub mqtt_enabled: Option<Option<bool>>,
+    pub mqtt_broker_host: Option<Option<String>>,
+    pub mqtt_broker_port: Option<Option<u16>>,
+    pub mqtt_tls: Option<Option<bool>>,
+    pub mqtt_topic_prefix: Option<Option<String>>,
+    pub mqtt_username: Option<Option<String>>,
+
+    pub daily_summary_enabled: Option<Option<bool>>,
+    pub daily_summary_time: Option<Option<String>>,
+    pub daily_summary_prompt: Option<Option<String>>,
 }
 
 pub fn apply_patch(mut s: Settings, p: SettingsPatch) -> Settings {
@@ -178,6 +663,27 @@ pub fn apply_patch(mut s: Settings, p: SettingsPatch) -> Settings {
     if let Some(v) = p.remote_asr_concurrency {
         s.remote_asr_concurrency = v;
     }
+    if let Some(v) = p.asr_warm_pool_enabled {
+        s.asr_warm_pool_enabled = v;
+    }
+    if let Some(v) = p.asr_warm_pool_models {
+        s.asr_warm_pool_models = v;
+    }
+    if let Some(v) = p.asr_warm_pool_max_models {
+        s.asr_warm_pool_max_models = v;
+    }
+    if let Some(v) = p.asr_warm_pool_vram_budget_mb {
+        s.asr_warm_pool_vram_budget_mb = v;
+    }
+    if let Some(v) = p.asr_warm_pool_model_vram_mb {
+        s.asr_warm_pool_model_vram_mb = v;
+    }
+    if let Some(v) = p.asr_warm_pool_keepalive_secs {
+        s.asr_warm_pool_keepalive_secs = v;
+    }
+    if let Some(v) = p.remote_asr_live_preview {
+        s.remote_asr_live_preview = v;
+    }
     if let Some(v) = p.asr_preprocess_silence_trim_enabled {
         s.asr_preprocess_silence_trim_enabled = v;
     }
@@ -190,6 +696,36 @@ pub fn apply_patch(mut s: Settings, p: SettingsPatch) -> Settings {
     if let Some(v) = p.asr_preprocess_silence_end_ms {
         s.asr_preprocess_silence_end_ms = v;
     }
+    if let Some(v) = p.asr_preprocess_gain_db {
+        s.asr_preprocess_gain_db = v;
+    }
+    if let Some(v) = p.asr_preprocess_warm_spare_enabled {
+        s.asr_preprocess_warm_spare_enabled = v;
+    }
+    if let Some(v) = p.asr_preprocess_native_wav_enabled {
+        s.asr_preprocess_native_wav_enabled = v;
+    }
+    if let Some(v) = p.proxy_url {
+        s.proxy_url = v;
+    }
+    if let Some(v) = p.proxy_no_proxy {
+        s.proxy_no_proxy = v;
+    }
+    if let Some(v) = p.tls_ca_bundle_path {
+        s.tls_ca_bundle_path = v;
+    }
+    if let Some(v) = p.tls_pinned_sha256 {
+        s.tls_pinned_sha256 = v;
+    }
+    if let Some(v) = p.http_connect_timeout_ms {
+        s.http_connect_timeout_ms = v;
+    }
+    if let Some(v) = p.http_request_timeout_ms {
+        s.http_request_timeout_ms = v;
+    }
+    if let Some(v) = p.offline_mode {
+        s.offline_mode = v;
+    }
     if let Some(v) = p.llm_base_url {
         s.llm_base_url = v;
     }
@@ -202,6 +738,39 @@ pub fn apply_patch(mut s: Settings, p: SettingsPatch) -> Settings {
     if let Some(v) = p.llm_prompt {
         s.llm_prompt = v;
     }
+    if let Some(v) = p.llm_capture_enabled {
+        s.llm_capture_enabled = v;
+    }
+    if let Some(v) = p.llm_capture_max_entries {
+        s.llm_capture_max_entries = v;
+    }
+    if let Some(v) = p.gpu_coordination_enabled {
+        s.gpu_coordination_enabled = v;
+    }
+    if let Some(v) = p.gpu_asr_unload_url {
+        s.gpu_asr_unload_url = v;
+    }
+    if let Some(v) = p.gpu_llm_unload_url {
+        s.gpu_llm_unload_url = v;
+    }
+    if let Some(v) = p.rewrite_system_preamble {
+        s.rewrite_system_preamble = v;
+    }
+    if let Some(v) = p.rewrite_output_format {
+        s.rewrite_output_format = v;
+    }
+    if let Some(v) = p.rewrite_output_format_by_template {
+        s.rewrite_output_format_by_template = v;
+    }
+    if let Some(v) = p.rewrite_output_json_schema {
+        s.rewrite_output_json_schema = v;
+    }
+    if let Some(v) = p.rewrite_output_json_schema_by_template {
+        s.rewrite_output_json_schema_by_template = v;
+    }
+    if let Some(v) = p.rewrite_template_id {
+        s.rewrite_template_id = v;
+    }
     if let Some(v) = p.record_input_spec {
         s.record_input_spec = v;
     }
@@ -217,15 +786,99 @@ pub fn apply_patch(mut s: Settings, p: SettingsPatch) -> Settings {
     if let Some(v) = p.record_fixed_friendly_name {
         s.record_fixed_friendly_name = v;
     }
+    if let Some(v) = p.record_device_preference {
+        s.record_device_preference = v;
+    }
+    if let Some(v) = p.record_device_overrides {
+        s.record_device_overrides = v;
+    }
+    if let Some(v) = p.record_avoid_low_quality_profile {
+        s.record_avoid_low_quality_profile = v;
+    }
+    if let Some(v) = p.record_backend {
+        s.record_backend = v;
+    }
+    if let Some(v) = p.record_vad_enabled {
+        s.record_vad_enabled = v;
+    }
+    if let Some(v) = p.record_vad_silence_ms {
+        s.record_vad_silence_ms = v;
+    }
     if let Some(v) = p.rewrite_enabled {
         s.rewrite_enabled = v;
     }
     if let Some(v) = p.rewrite_glossary {
         s.rewrite_glossary = v;
     }
+    if let Some(v) = p.pipeline_max_background_rewrites {
+        s.pipeline_max_background_rewrites = v;
+    }
+    if let Some(v) = p.task_stall_timeout_ms {
+        s.task_stall_timeout_ms = v;
+    }
+    if let Some(v) = p.rewrite_chunk_threshold_tokens {
+        s.rewrite_chunk_threshold_tokens = v;
+    }
+    if let Some(v) = p.rewrite_chunk_tokens {
+        s.rewrite_chunk_tokens = v;
+    }
+    if let Some(v) = p.rewrite_cache_enabled {
+        s.rewrite_cache_enabled = v;
+    }
+    if let Some(v) = p.rewrite_token_cap {
+        s.rewrite_token_cap = v;
+    }
     if let Some(v) = p.auto_paste_enabled {
         s.auto_paste_enabled = v;
     }
+    if let Some(v) = p.export_elevated_helper_enabled {
+        s.export_elevated_helper_enabled = v;
+    }
+    if let Some(v) = p.clipboard_auto_clear_secs {
+        s.clipboard_auto_clear_secs = v;
+    }
+    if let Some(v) = p.export_format {
+        s.export_format = v;
+    }
+    if let Some(v) = p.export_format_by_template {
+        s.export_format_by_template = v;
+    }
+    if let Some(v) = p.export_targets_by_template {
+        s.export_targets_by_template = v;
+    }
+    if let Some(v) = p.audit_log_enabled {
+        s.audit_log_enabled = v;
+    }
+    if let Some(v) = p.audit_log_include_text {
+        s.audit_log_include_text = v;
+    }
+    if let Some(v) = p.audit_log_retention_days {
+        s.audit_log_retention_days = v;
+    }
+    if let Some(v) = p.audit_log_max_entries {
+        s.audit_log_max_entries = v;
+    }
+    if let Some(v) = p.keep_recordings {
+        s.keep_recordings = v;
+    }
+    if let Some(v) = p.recording_format {
+        s.recording_format = v;
+    }
+    if let Some(v) = p.time_format {
+        s.time_format = v;
+    }
+    if let Some(v) = p.text_casing {
+        s.text_casing = v;
+    }
+    if let Some(v) = p.text_casing_by_mode {
+        s.text_casing_by_mode = v;
+    }
+    if let Some(v) = p.text_normalize_enabled {
+        s.text_normalize_enabled = v;
+    }
+    if let Some(v) = p.text_normalize_locales {
+        s.text_normalize_locales = v;
+    }
     if let Some(v) = p.context_include_history {
         s.context_include_history = v;
     }
@@ -235,15 +888,27 @@ pub fn apply_patch(mut s: Settings, p: SettingsPatch) -> Settings {
     if let Some(v) = p.context_history_window_ms {
         s.context_history_window_ms = v;
     }
+    if let Some(v) = p.context_history_same_app {
+        s.context_history_same_app = v;
+    }
+    if let Some(v) = p.context_history_summarize {
+        s.context_history_summarize = v;
+    }
     if let Some(v) = p.context_include_clipboard {
         s.context_include_clipboard = v;
     }
     if let Some(v) = p.context_include_prev_window_screenshot {
         s.context_include_prev_window_screenshot = v;
     }
+    if let Some(v) = p.context_sections_by_template {
+        s.context_sections_by_template = v;
+    }
     if let Some(v) = p.context_include_prev_window_meta {
         s.context_include_prev_window_meta = v;
     }
+    if let Some(v) = p.context_injection_guard_enabled {
+        s.context_injection_guard_enabled = v;
+    }
     if let Some(v) = p.rewrite_include_glossary {
         s.rewrite_include_glossary = v;
     }
@@ -256,6 +921,12 @@ pub fn apply_patch(mut s: Settings, p: SettingsPatch) -> Settings {
     if let Some(v) = p.hotkey_primary {
         s.hotkey_primary = v;
     }
+    if let Some(v) = p.hotkey_debounce_ms {
+        s.hotkey_debounce_ms = v;
+    }
+    if let Some(v) = p.hotkey_app_allowlist {
+        s.hotkey_app_allowlist = v;
+    }
     if let Some(v) = p.hotkeys_show_overlay {
         s.hotkeys_show_overlay = v;
     }
@@ -277,6 +948,111 @@ pub fn apply_patch(mut s: Settings, p: SettingsPatch) -> Settings {
     if let Some(v) = p.overlay_position_y {
         s.overlay_position_y = v;
     }
+    if let Some(v) = p.overlay_status_colors {
+        s.overlay_status_colors = v;
+    }
+    if let Some(v) = p.overlay_font_scale {
+        s.overlay_font_scale = v;
+    }
+    if let Some(v) = p.overlay_status_language {
+        s.overlay_status_language = v;
+    }
+    if let Some(v) = p.captions_enabled {
+        s.captions_enabled = v;
+    }
+    if let Some(v) = p.captions_font_size_px {
+        s.captions_font_size_px = v;
+    }
+    if let Some(v) = p.captions_background_opacity {
+        s.captions_background_opacity = v;
+    }
+    if let Some(v) = p.captions_monitor_index {
+        s.captions_monitor_index = v;
+    }
+    if let Some(v) = p.a11y_announcements {
+        s.a11y_announcements = v;
+    }
+    if let Some(v) = p.capture_indicator_enforced {
+        s.capture_indicator_enforced = v;
+    }
+    if let Some(v) = p.screen_share_guard_enabled {
+        s.screen_share_guard_enabled = v;
+    }
+    if let Some(v) = p.screen_share_extra_processes {
+        s.screen_share_extra_processes = v;
+    }
+    if let Some(v) = p.low_power_mode {
+        s.low_power_mode = v;
+    }
+    if let Some(v) = p.low_power_asr_provider {
+        s.low_power_asr_provider = v;
+    }
+    if let Some(v) = p.low_power_remote_asr_model {
+        s.low_power_remote_asr_model = v;
+    }
+    if let Some(v) = p.wake_word_enabled {
+        s.wake_word_enabled = v;
+    }
+    if let Some(v) = p.wake_word_phrase {
+        s.wake_word_phrase = v;
+    }
+    if let Some(v) = p.wake_word_sensitivity {
+        s.wake_word_sensitivity = v;
+    }
+    if let Some(v) = p.wake_word_detector_command {
+        s.wake_word_detector_command = v;
+    }
+    if let Some(v) = p.postprocessors {
+        s.postprocessors = v;
+    }
+    if let Some(v) = p.markdown_notes_enabled {
+        s.markdown_notes_enabled = v;
+    }
+    if let Some(v) = p.markdown_notes_folder {
+        s.markdown_notes_folder = v;
+    }
+    if let Some(v) = p.markdown_notes_entry_template {
+        s.markdown_notes_entry_template = v;
+    }
+    if let Some(v) = p.markdown_notes_entry_template_by_template {
+        s.markdown_notes_entry_template_by_template = v;
+    }
+    if let Some(v) = p.webhook_enabled {
+        s.webhook_enabled = v;
+    }
+    if let Some(v) = p.webhook_url {
+        s.webhook_url = v;
+    }
+    if let Some(v) = p.webhook_fields {
+        s.webhook_fields = v;
+    }
+    if let Some(v) = p.mqtt_enabled {
+        s.mqtt_enabled = v;
+    }
+    if let Some(v) = p.mqtt_broker_host {
+        s.mqtt_broker_host = v;
+    }
+    if let Some(v) = p.mqtt_broker_port {
+        s.mqtt_broker_port = v;
+    }
+    if let Some(v) = p.mqtt_tls {
+        s.mqtt_tls = v;
+    }
+    if let Some(v) = p.mqtt_topic_prefix {
+        s.mqtt_topic_prefix = v;
+    }
+    if let Some(v) = p.mqtt_username {
+        s.mqtt_username = v;
+    }
+    if let Some(v) = p.daily_summary_enabled {
+        s.daily_summary_enabled = v;
+    }
+    if let Some(v) = p.daily_summary_time {
+        s.daily_summary_time = v;
+    }
+    if let Some(v) = p.daily_summary_prompt {
+        s.daily_summary_prompt = v;
+    }
     s
 }
 
@@ -308,22 +1084,300 @@ pub fn load_settings_strict(data_dir: &Path) -> Result<Settings> {
 }
 
 pub fn ensure_settings(data_dir: &Path) -> Result<()> {
+    let _guard = store_lock();
     let p = settings_path(data_dir);
     if p.exists() {
         return Ok(());
     }
-    let settings = Settings::default();
-    save_settings(data_dir, &settings)
+    write_settings(data_dir, &Settings::default())
+}
+
+pub fn resolve_rewrite_system_preamble(s: &Settings) -> Option<String> {
+    s.rewrite_system_preamble
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(ToOwned::to_owned)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewriteOutputFormatResolved {
+    pub format: String, // text|json_schema
+    pub json_schema: Option<String>,
+}
+
+fn non_empty_setting(v: Option<&String>) -> Option<&str> {
+    v.map(|v| v.trim()).filter(|v| !v.is_empty())
+}
+
+pub fn resolve_rewrite_output_format(
+    s: &Settings,
+    template_id: Option<&str>,
+) -> RewriteOutputFormatResolved {
+    let template_id = template_id.map(str::trim).filter(|id| !id.is_empty());
+    let format = template_id
+        .and_then(|id| non_empty_setting(s.rewrite_output_format_by_template.as_ref()?.get(id)))
+        .or_else(|| non_empty_setting(s.rewrite_output_format.as_ref()))
+        .unwrap_or(DEFAULT_REWRITE_OUTPUT_FORMAT)
+        .to_ascii_lowercase();
+    let json_schema = template_id
+        .and_then(|id| {
+            non_empty_setting(s.rewrite_output_json_schema_by_template.as_ref()?.get(id))
+        })
+        .or_else(|| non_empty_setting(s.rewrite_output_json_schema.as_ref()))
+        .map(ToOwned::to_owned);
+    RewriteOutputFormatResolved {
+        format,
+        json_schema,
+    }
+}
+
+pub fn resolve_rewrite_template_id(s: &Settings) -> Option<String> {
+    non_empty_setting(s.rewrite_template_id.as_ref()).map(ToOwned::to_owned)
 }
 
 pub fn resolve_auto_paste_enabled(s: &Settings) -> bool {
     s.auto_paste_enabled.unwrap_or(true)
 }
 
+pub fn resolve_clipboard_auto_clear(s: &Settings) -> Option<std::time::Duration> {
+    match s
+        .clipboard_auto_clear_secs
+        .unwrap_or(0)
+        .min(MAX_CLIPBOARD_AUTO_CLEAR_SECS)
+    {
+        0 => None,
+        secs => Some(std::time::Duration::from_secs(secs)),
+    }
+}
+
+// Off by default: every elevated paste costs a UAC prompt.
+pub fn resolve_capture_indicator_enforced(s: &Settings) -> bool {
+    s.capture_indicator_enforced.unwrap_or(false)
+}
+
+pub fn resolve_screen_share_guard_enabled(s: &Settings) -> bool {
+    s.screen_share_guard_enabled.unwrap_or(true)
+}
+
+pub fn resolve_low_power_mode(s: &Settings) -> &'static str {
+    match s.low_power_mode.as_deref().map(str::trim) {
+        Some("auto") => "auto",
+        Some("on") => "on",
+        _ => DEFAULT_LOW_POWER_MODE,
+    }
+}
+
+// ASR provider while low power is active; an unset or unknown value keeps
+// asr_provider.
+pub fn resolve_low_power_asr_provider(s: &Settings) -> String {
+    match s.low_power_asr_provider.as_deref().map(str::trim) {
+        Some("remote") => "remote".to_string(),
+        Some("doubao") => "doubao".to_string(),
+        _ => resolve_asr_provider(s),
+    }
+}
+
+pub fn resolve_low_power_remote_asr_model(s: &Settings) -> Option<String> {
+    s.low_power_remote_asr_model
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(ToOwned::to_owned)
+        .or_else(|| resolve_remote_asr_model(s))
+}
+
+pub fn resolve_screen_share_extra_processes(s: &Settings) -> Vec<String> {
+    let mut out: Vec<String> = s
+        .screen_share_extra_processes
+        .iter()
+        .flatten()
+        .map(|v| crate::formatting_profiles::process_file_name(v))
+        .filter(|v| !v.is_empty())
+        .collect();
+    out.sort();
+    out.dedup();
+    out
+}
+
+pub fn resolve_export_elevated_helper_enabled(s: &Settings) -> bool {
+    s.export_elevated_helper_enabled.unwrap_or(false)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportTarget {
+    Clipboard,
+    Paste,
+    Webhook,
+    MarkdownNotes,
+}
+
+impl ExportTarget {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Clipboard => "clipboard",
+            Self::Paste => "paste",
+            Self::Webhook => "webhook",
+            Self::MarkdownNotes => "markdown_notes",
+        }
+    }
+}
+
+pub const EXPORT_TARGETS: &[&str] = &["clipboard", "paste", "webhook", "markdown_notes"];
+
+// Every export path asks here before sending a template's text anywhere.
+// Pasting goes through the clipboard, so allowing paste allows the copy too.
+pub fn export_target_allowed(
+    s: &Settings,
+    template_id: Option<&str>,
+    target: ExportTarget,
+) -> bool {
+    let Some(allowed) = template_id
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .and_then(|id| s.export_targets_by_template.as_ref()?.get(id))
+    else {
+        return true;
+    };
+    let listed = |t: ExportTarget| {
+        allowed
+            .iter()
+            .any(|v| v.trim().eq_ignore_ascii_case(t.as_str()))
+    };
+    listed(target) || (target == ExportTarget::Clipboard && listed(ExportTarget::Paste))
+}
+
+pub fn resolve_export_format(s: &Settings, template_id: Option<&str>) -> String {
+    let by_template = template_id
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .and_then(|id| s.export_format_by_template.as_ref()?.get(id))
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty());
+    by_template
+        .or_else(|| {
+            s.export_format
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        })
+        .unwrap_or(DEFAULT_EXPORT_FORMAT)
+        .to_ascii_lowercase()
+}
+
+pub fn resolve_audit_log_enabled(s: &Settings) -> bool {
+    s.audit_log_enabled.unwrap_or(false)
+}
+
+pub fn resolve_audit_log_include_text(s: &Settings) -> bool {
+    s.audit_log_include_text.unwrap_or(false)
+}
+
+pub fn resolve_preprocess_warm_spare_enabled(s: &Settings) -> bool {
+    s.asr_preprocess_warm_spare_enabled.unwrap_or(true)
+}
+
+pub fn resolve_preprocess_native_wav_enabled(s: &Settings) -> bool {
+    s.asr_preprocess_native_wav_enabled.unwrap_or(false)
+}
+
+pub fn resolve_template_context_sections(
+    s: &Settings,
+    template_id: Option<&str>,
+) -> TemplateContextSections {
+    template_id
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .and_then(|id| s.context_sections_by_template.as_ref()?.get(id).copied())
+        .unwrap_or_default()
+}
+
+pub fn resolve_keep_recordings(s: &Settings) -> bool {
+    s.keep_recordings.unwrap_or(false)
+}
+
+// Finished remote ASR slices are only worth keeping when the recording is kept
+// too, so a failed run can be repeated on it; guest mode keeps neither.
+pub fn resolve_remote_asr_resume_enabled(s: &Settings) -> bool {
+    resolve_keep_recordings(s) && !crate::guest_mode::enabled()
+}
+
+// Unknown values fall back to wav so a bad hand edit never drops audio.
+pub fn resolve_recording_format(s: &Settings) -> &'static str {
+    match s.recording_format.as_deref().map(str::trim) {
+        Some("flac") => "flac",
+        Some("opus") => "opus",
+        _ => DEFAULT_RECORDING_FORMAT,
+    }
+}
+
+pub fn resolve_time_format(s: &Settings) -> TimeFormat {
+    s.time_format
+        .as_deref()
+        .and_then(TimeFormat::parse)
+        .unwrap_or_default()
+}
+
+pub fn resolve_audit_retention(s: &Settings) -> AuditRetention {
+    AuditRetention {
+        retention_days: s
+            .audit_log_retention_days
+            .unwrap_or(DEFAULT_AUDIT_LOG_RETENTION_DAYS)
+            .max(0),
+        max_entries: s
+            .audit_log_max_entries
+            .unwrap_or(DEFAULT_AUDIT_LOG_MAX_ENTRIES)
+            .max(0),
+    }
+}
+
+pub fn resolve_text_casing(s: &Settings, mode: &str, template_id: Option<&str>) -> String {
+    let lookup = |key: Option<&str>| {
+        let key = key.map(str::trim).filter(|k| !k.is_empty())?;
+        s.text_casing_by_mode
+            .as_ref()?
+            .get(key)
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+    };
+    lookup(template_id)
+        .or_else(|| lookup(Some(mode)))
+        .or_else(|| {
+            s.text_casing
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        })
+        .unwrap_or(DEFAULT_TEXT_CASING)
+        .to_ascii_lowercase()
+}
+
+pub fn resolve_text_normalize_locales(s: &Settings) -> Vec<String> {
+    if !s.text_normalize_enabled.unwrap_or(false) {
+        return Vec::new();
+    }
+    let configured: Vec<String> = s
+        .text_normalize_locales
+        .iter()
+        .flatten()
+        .map(|v| v.trim().to_ascii_lowercase())
+        .filter(|v| !v.is_empty())
+        .collect();
+    if configured.is_empty() {
+        return DEFAULT_TEXT_NORMALIZE_LOCALES
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+    }
+    configured
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct HotkeyConfigResolved {
     pub enabled: bool,
     pub primary: String,
+    pub debounce_ms: u64,
+    pub app_allowlist: Vec<String>,
 }
 
 pub fn resolve_hotkey_config(s: &Settings) -> Result<HotkeyConfigResolved> {
@@ -334,15 +1388,51 @@ pub fn resolve_hotkey_config(s: &Settings) -> Result<HotkeyConfigResolved> {
         return Ok(HotkeyConfigResolved {
             enabled: false,
             primary: "Alt".to_string(),
+            debounce_ms: resolve_hotkey_debounce_ms(s),
+            app_allowlist: resolve_hotkey_app_allowlist(s),
         });
     }
 
     Ok(HotkeyConfigResolved {
         enabled: true,
         primary: normalize_hotkey_primary(s.hotkey_primary.as_deref())?,
+        debounce_ms: resolve_hotkey_debounce_ms(s),
+        app_allowlist: resolve_hotkey_app_allowlist(s),
     })
 }
 
+// Normalized the way history's target_app is, so a full path or a different
+// case in settings.json still matches.
+pub fn resolve_hotkey_app_allowlist(s: &Settings) -> Vec<String> {
+    let mut out: Vec<String> = s
+        .hotkey_app_allowlist
+        .iter()
+        .flatten()
+        .map(|v| crate::formatting_profiles::process_file_name(v))
+        .filter(|v| !v.is_empty())
+        .collect();
+    out.sort();
+    out.dedup();
+    out
+}
+
+// An empty allowlist allows everything; otherwise an unknown foreground app
+// is refused, since the point is to never record by accident.
+pub fn hotkey_app_allowed(allowlist: &[String], process_image: Option<&str>) -> bool {
+    if allowlist.is_empty() {
+        return true;
+    }
+    process_image
+        .map(crate::formatting_profiles::process_file_name)
+        .is_some_and(|name| allowlist.contains(&name))
+}
+
+pub fn resolve_hotkey_debounce_ms(s: &Settings) -> u64 {
+    s.hotkey_debounce_ms
+        .unwrap_or(DEFAULT_HOTKEY_DEBOUNCE_MS)
+        .min(MAX_HOTKEY_DEBOUNCE_MS)
+}
+
 pub fn normalize_hotkey_primary(raw: Option<&str>) -> Result<String> {
     let value = raw
         .map(str::trim)
@@ -371,51 +1461,196 @@ pub fn resolve_record_input_spec(s: &Settings) -> String {
         .to_string()
 }
 
-pub fn save_settings(data_dir: &Path, settings: &Settings) -> Result<()> {
-    let span = Span::start(data_dir, None, "Settings", "SETTINGS.save", None);
-    std::fs::create_dir_all(data_dir).context("create data dir failed")?;
-    let p = settings_path(data_dir);
-    let s = serde_json::to_string_pretty(settings).context("serialize settings failed")?;
-    if let Err(e) = fs::write(&p, s) {
-        let ae = anyhow::anyhow!("write settings.json failed: {e}");
-        span.err_anyhow("io", "E_SETTINGS_WRITE", &ae, None);
-        return Err(ae);
-    }
-    span.ok(None);
-    Ok(())
+// Every writer of settings.json holds this lock across load-modify-save, so
+// concurrent paths (update_settings, record input cache, overlay position)
+// cannot drop each other's fields.
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+type ChangeListener = Box<dyn Fn(&Settings) + Send + Sync>;
+static CHANGE_LISTENER: OnceLock<ChangeListener> = OnceLock::new();
+type ConflictListener = Box<dyn Fn(&Path, SettingsConflict) + Send + Sync>;
+static CONFLICT_LISTENER: OnceLock<ConflictListener> = OnceLock::new();
+// settings.json bytes as this process last wrote or polled them, per file;
+// anything else on disk was edited by hand. Only touched under STORE_LOCK.
+static LAST_SEEN: Mutex<BTreeMap<PathBuf, Vec<u8>>> = Mutex::new(BTreeMap::new());
+
+// An in-app write landed on a hand edit the watcher had not picked up yet.
+// `Merged` writes started from the edited file (patches), so the edit survives
+// unless the same field was changed; `Overwritten` writes replaced the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingsConflict {
+    Merged,
+    Overwritten,
 }
 
-pub fn resolve_asr_provider(s: &Settings) -> String {
-    let value = s
-        .asr_provider
-        .as_deref()
-        .map(str::trim)
-        .unwrap_or(DEFAULT_ASR_PROVIDER)
-        .to_ascii_lowercase();
-    if value == "remote" {
-        "remote".to_string()
-    } else if value == "doubao" {
-        "doubao".to_string()
-    } else {
-        DEFAULT_ASR_PROVIDER.to_string()
-    }
+#[derive(Debug)]
+pub enum ExternalEdit {
+    Reloaded(Box<Settings>),
+    // Not valid settings JSON; the app keeps running on what it had.
+    Invalid(String),
 }
 
-pub fn resolve_remote_asr_url(s: &Settings) -> String {
-    s.remote_asr_url
-        .as_deref()
-        .map(str::trim)
-        .filter(|v| !v.is_empty())
-        .unwrap_or(DEFAULT_REMOTE_ASR_URL)
-        .to_string()
+fn store_lock() -> MutexGuard<'static, ()> {
+    STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner())
 }
 
-pub fn resolve_remote_asr_model(s: &Settings) -> Option<String> {
-    s.remote_asr_model
-        .as_deref()
-        .map(str::trim)
-        .filter(|v| !v.is_empty())
-        .map(ToOwned::to_owned)
+// Called after every successful write; the desktop app forwards it to the UI as
+// `tv_settings_changed`. Only the first registration takes effect.
+pub fn set_change_listener(f: impl Fn(&Settings) + Send + Sync + 'static) -> bool {
+    CHANGE_LISTENER.set(Box::new(f)).is_ok()
+}
+
+fn notify_changed(settings: &Settings) {
+    if let Some(f) = CHANGE_LISTENER.get() {
+        f(settings);
+    }
+}
+
+// Reported after the write, outside the store lock. Only the first
+// registration takes effect.
+pub fn set_conflict_listener(f: impl Fn(&Path, SettingsConflict) + Send + Sync + 'static) -> bool {
+    CONFLICT_LISTENER.set(Box::new(f)).is_ok()
+}
+
+fn notify_conflict(data_dir: &Path, conflict: Option<SettingsConflict>) {
+    if let (Some(conflict), Some(f)) = (conflict, CONFLICT_LISTENER.get()) {
+        f(data_dir, conflict);
+    }
+}
+
+fn last_seen() -> MutexGuard<'static, BTreeMap<PathBuf, Vec<u8>>> {
+    LAST_SEEN.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Whether settings.json differs from what this process last saw. A file never
+// seen before is not an edit: there is nothing to compare against yet.
+fn unseen_edit(path: &Path) -> Option<Vec<u8>> {
+    let disk = fs::read(path).ok()?;
+    match last_seen().get(path) {
+        Some(seen) if *seen != disk => Some(disk),
+        _ => None,
+    }
+}
+
+// Picks up hand edits to settings.json. The first call only records a
+// baseline. A reloaded file is announced to the change listener like any
+// other write.
+pub fn poll_external_edit(data_dir: &Path) -> Option<ExternalEdit> {
+    let path = settings_path(data_dir);
+    let edit = {
+        let _guard = store_lock();
+        let Ok(disk) = fs::read(&path) else {
+            return None;
+        };
+        let mut seen = last_seen();
+        match seen.get(&path) {
+            None => {
+                seen.insert(path, disk);
+                return None;
+            }
+            Some(prev) if *prev == disk => return None,
+            Some(_) => {}
+        }
+        seen.insert(path, disk.clone());
+        match serde_json::from_slice::<Settings>(&disk) {
+            Ok(v) => ExternalEdit::Reloaded(Box::new(v)),
+            Err(e) => ExternalEdit::Invalid(format!(
+                "E_SETTINGS_JSON_INVALID: settings.json edited outside the app is not valid: {e}"
+            )),
+        }
+    };
+    if let ExternalEdit::Reloaded(v) = &edit {
+        notify_changed(v);
+    }
+    Some(edit)
+}
+
+pub fn save_settings(data_dir: &Path, settings: &Settings) -> Result<()> {
+    let conflict = {
+        let _guard = store_lock();
+        let conflict = unseen_edit(&settings_path(data_dir)).map(|_| SettingsConflict::Overwritten);
+        write_settings(data_dir, settings)?;
+        conflict
+    };
+    notify_changed(settings);
+    notify_conflict(data_dir, conflict);
+    Ok(())
+}
+
+// Loads the current file, lets `f` edit it and saves the result under the store
+// lock. `f` returns false to leave the file untouched.
+pub fn modify_settings<F>(data_dir: &Path, f: F) -> Result<Settings>
+where
+    F: FnOnce(&mut Settings) -> Result<bool>,
+{
+    let (settings, changed, conflict) = {
+        let _guard = store_lock();
+        let conflict = unseen_edit(&settings_path(data_dir)).map(|_| SettingsConflict::Merged);
+        let mut settings = load_settings_strict(data_dir)?;
+        let changed = f(&mut settings)?;
+        if changed {
+            write_settings(data_dir, &settings)?;
+        }
+        (settings, changed, conflict)
+    };
+    if changed {
+        notify_changed(&settings);
+        notify_conflict(data_dir, conflict);
+    }
+    Ok(settings)
+}
+
+// Written atomically, so readers never see a partially written file.
+fn write_settings(data_dir: &Path, settings: &Settings) -> Result<()> {
+    let span = Span::start(data_dir, None, "Settings", "SETTINGS.save", None);
+    std::fs::create_dir_all(data_dir).context("create data dir failed")?;
+    let s = serde_json::to_string_pretty(settings).context("serialize settings failed")?;
+    let path = settings_path(data_dir);
+    if let Err(e) = atomic_file::write(&path, s.as_bytes()) {
+        let ae = anyhow::anyhow!("write settings.json failed: {e}");
+        span.err_anyhow("io", "E_SETTINGS_WRITE", &ae, None);
+        return Err(ae);
+    }
+    last_seen().insert(path, s.into_bytes());
+    span.ok(None);
+    Ok(())
+}
+
+pub fn resolve_asr_provider(s: &Settings) -> String {
+    let value = s
+        .asr_provider
+        .as_deref()
+        .map(str::trim)
+        .unwrap_or(DEFAULT_ASR_PROVIDER)
+        .to_ascii_lowercase();
+    if value == "remote" {
+        "remote".to_string()
+    } else if value == "doubao" {
+        "doubao".to_string()
+    } else {
+        DEFAULT_ASR_PROVIDER.to_string()
+    }
+}
+
+pub fn resolve_remote_asr_url(s: &Settings) -> String {
+    s.remote_asr_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .unwrap_or(DEFAULT_REMOTE_ASR_URL)
+        .to_string()
+}
+
+pub fn resolve_remote_asr_model(s: &Settings) -> Option<String> {
+    s.remote_asr_model
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(ToOwned::to_owned)
+}
+
+pub fn resolve_remote_asr_live_preview(s: &Settings) -> bool {
+    s.remote_asr_live_preview.unwrap_or(false)
 }
 
 pub fn resolve_remote_asr_concurrency(s: &Settings) -> usize {
@@ -426,6 +1661,264 @@ pub fn resolve_remote_asr_concurrency(s: &Settings) -> usize {
     raw.clamp(1, MAX_REMOTE_ASR_CONCURRENCY)
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsrWarmPoolResolved {
+    pub url: String,
+    // The model dictation uses, "" for the server's default; None when the
+    // provider is not remote.
+    pub active_model: Option<String>,
+    pub models: Vec<String>,
+    pub max_models: usize,
+    pub vram_budget_mb: u64,
+    pub model_vram_mb: BTreeMap<String, u64>,
+    pub keepalive_secs: u64,
+}
+
+impl AsrWarmPoolResolved {
+    pub fn vram_mb(&self, model: &str) -> u64 {
+        self.model_vram_mb
+            .get(model)
+            .copied()
+            .unwrap_or(DEFAULT_ASR_MODEL_VRAM_MB)
+    }
+}
+
+// None while the pool is off.
+pub fn resolve_asr_warm_pool(s: &Settings) -> Option<AsrWarmPoolResolved> {
+    if !s.asr_warm_pool_enabled.unwrap_or(false) {
+        return None;
+    }
+    let mut models: Vec<String> = Vec::new();
+    for m in s.asr_warm_pool_models.iter().flatten() {
+        let m = m.trim();
+        if !m.is_empty() && !models.iter().any(|v| v == m) {
+            models.push(m.to_string());
+        }
+    }
+    Some(AsrWarmPoolResolved {
+        url: resolve_remote_asr_url(s),
+        active_model: (resolve_asr_provider(s) == "remote")
+            .then(|| resolve_remote_asr_model(s).unwrap_or_default()),
+        models,
+        max_models: s
+            .asr_warm_pool_max_models
+            .map(|v| v as usize)
+            .unwrap_or(DEFAULT_ASR_WARM_POOL_MAX_MODELS)
+            .clamp(1, MAX_ASR_WARM_POOL_MODELS),
+        vram_budget_mb: s.asr_warm_pool_vram_budget_mb.unwrap_or(0),
+        model_vram_mb: s
+            .asr_warm_pool_model_vram_mb
+            .iter()
+            .flatten()
+            .map(|(k, v)| (k.trim().to_string(), *v))
+            .collect(),
+        keepalive_secs: s
+            .asr_warm_pool_keepalive_secs
+            .unwrap_or(DEFAULT_ASR_WARM_POOL_KEEPALIVE_SECS)
+            .max(MIN_ASR_WARM_POOL_KEEPALIVE_SECS),
+    })
+}
+
+pub fn resolve_pipeline_max_background_rewrites(s: &Settings) -> usize {
+    s.pipeline_max_background_rewrites
+        .map(|v| v as usize)
+        .unwrap_or(DEFAULT_PIPELINE_MAX_BACKGROUND_REWRITES)
+        .min(MAX_PIPELINE_MAX_BACKGROUND_REWRITES)
+}
+
+// None disables the stall watchdog.
+pub fn resolve_task_stall_timeout_ms(s: &Settings) -> Option<u64> {
+    match s
+        .task_stall_timeout_ms
+        .unwrap_or(DEFAULT_TASK_STALL_TIMEOUT_MS)
+    {
+        0 => None,
+        v => Some(v.max(MIN_TASK_STALL_TIMEOUT_MS)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyResolved {
+    pub url: String,
+    pub no_proxy: Vec<String>,
+}
+
+// None means direct connections (reqwest still honors HTTP(S)_PROXY env vars).
+pub fn resolve_proxy(s: &Settings) -> Option<ProxyResolved> {
+    let url = s
+        .proxy_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())?;
+    let no_proxy = s
+        .proxy_no_proxy
+        .iter()
+        .flatten()
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(ToOwned::to_owned)
+        .collect();
+    Some(ProxyResolved {
+        url: url.to_string(),
+        no_proxy,
+    })
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsTrustResolved {
+    pub ca_bundle_path: Option<String>,
+    pub pinned_sha256: Option<String>, // lowercase hex, separators removed
+    // Lowercase hosts the pin applies to; empty without a pin.
+    pub pinned_hosts: Vec<String>,
+}
+
+pub fn resolve_tls_trust(s: &Settings) -> TlsTrustResolved {
+    let ca_bundle_path = s
+        .tls_ca_bundle_path
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(ToOwned::to_owned);
+    let pinned_sha256 = s
+        .tls_pinned_sha256
+        .as_deref()
+        .map(|v| {
+            v.chars()
+                .filter(|c| !c.is_whitespace() && *c != ':')
+                .collect::<String>()
+                .to_ascii_lowercase()
+        })
+        .filter(|v| !v.is_empty());
+    let mut pinned_hosts = Vec::new();
+    if pinned_sha256.is_some() {
+        for url in [s.llm_base_url.clone(), Some(resolve_remote_asr_url(s))] {
+            if let Some(host) = url.as_deref().and_then(url_host) {
+                if !pinned_hosts.contains(&host) {
+                    pinned_hosts.push(host);
+                }
+            }
+        }
+    }
+    TlsTrustResolved {
+        ca_bundle_path,
+        pinned_sha256,
+        pinned_hosts,
+    }
+}
+
+// Host part of an absolute URL, lowercased, without userinfo, port or brackets.
+fn url_host(url: &str) -> Option<String> {
+    let rest = url.trim().split_once("://")?.1;
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+    let host = match host_port.strip_prefix('[') {
+        Some(v6) => v6.split_once(']')?.0,
+        None => host_port.split(':').next().unwrap_or_default(),
+    };
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpTimeoutsResolved {
+    pub connect_ms: u64,
+    pub request_ms: Option<u64>,
+}
+
+pub fn resolve_http_timeouts(s: &Settings) -> HttpTimeoutsResolved {
+    let connect_ms = s
+        .http_connect_timeout_ms
+        .unwrap_or(DEFAULT_HTTP_CONNECT_TIMEOUT_MS)
+        .max(MIN_HTTP_TIMEOUT_MS);
+    let request_ms = match s
+        .http_request_timeout_ms
+        .unwrap_or(DEFAULT_HTTP_REQUEST_TIMEOUT_MS)
+    {
+        0 => None,
+        v => Some(v.max(MIN_HTTP_TIMEOUT_MS)),
+    };
+    HttpTimeoutsResolved {
+        connect_ms,
+        request_ms,
+    }
+}
+
+pub fn resolve_offline_mode(s: &Settings) -> bool {
+    s.offline_mode.unwrap_or(false)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RewriteChunkingResolved {
+    pub threshold_tokens: usize,
+    pub chunk_tokens: usize,
+}
+
+// None when chunking is disabled. Chunks never exceed the threshold, otherwise
+// a transcript just over it would be split into a single chunk.
+// The cache keeps rewritten text, so guest mode never uses it.
+// How many LLM exchanges to keep, or None while capture is off.
+pub fn resolve_llm_capture(s: &Settings) -> Option<usize> {
+    if !s.llm_capture_enabled.unwrap_or(false) || crate::guest_mode::enabled() {
+        return None;
+    }
+    let max = s
+        .llm_capture_max_entries
+        .unwrap_or(DEFAULT_LLM_CAPTURE_MAX_ENTRIES)
+        .clamp(1, MAX_LLM_CAPTURE_MAX_ENTRIES);
+    Some(max as usize)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpuCoordinationResolved {
+    pub asr_unload_url: Option<String>,
+    pub llm_unload_url: Option<String>,
+}
+
+// None while coordination is off.
+pub fn resolve_gpu_coordination(s: &Settings) -> Option<GpuCoordinationResolved> {
+    if !s.gpu_coordination_enabled.unwrap_or(false) {
+        return None;
+    }
+    let url = |v: &Option<String>| {
+        v.as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+    Some(GpuCoordinationResolved {
+        asr_unload_url: url(&s.gpu_asr_unload_url),
+        llm_unload_url: url(&s.gpu_llm_unload_url),
+    })
+}
+
+pub fn resolve_rewrite_cache_enabled(s: &Settings) -> bool {
+    s.rewrite_cache_enabled.unwrap_or(true) && !crate::guest_mode::enabled()
+}
+
+pub fn resolve_rewrite_token_cap(s: &Settings) -> Option<usize> {
+    match s.rewrite_token_cap.unwrap_or(0) {
+        0 => None,
+        cap => Some(cap.max(MIN_REWRITE_TOKEN_CAP) as usize),
+    }
+}
+
+pub fn resolve_rewrite_chunking(s: &Settings) -> Option<RewriteChunkingResolved> {
+    let threshold = s
+        .rewrite_chunk_threshold_tokens
+        .unwrap_or(DEFAULT_REWRITE_CHUNK_THRESHOLD_TOKENS);
+    if threshold == 0 {
+        return None;
+    }
+    let threshold = threshold.max(MIN_REWRITE_CHUNK_TOKENS);
+    let chunk = s
+        .rewrite_chunk_tokens
+        .unwrap_or(DEFAULT_REWRITE_CHUNK_TOKENS)
+        .clamp(MIN_REWRITE_CHUNK_TOKENS, threshold);
+    Some(RewriteChunkingResolved {
+        threshold_tokens: threshold as usize,
+        chunk_tokens: chunk as usize,
+    })
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct OverlayConfigResolved {
     pub background_opacity: f64,
@@ -459,6 +1952,63 @@ pub fn resolve_overlay_config(s: &Settings) -> OverlayConfigResolved {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OverlayStatusStyle {
+    pub label: String,
+    pub color: String,
+}
+
+// Everything the overlay window needs to draw a status; it keeps no
+// presentation defaults of its own.
+#[derive(Debug, Clone, Serialize)]
+pub struct OverlayThemeResolved {
+    pub background_opacity: f64,
+    pub font_scale: f64,
+    pub language: String,
+    pub statuses: BTreeMap<String, OverlayStatusStyle>,
+}
+
+pub fn resolve_overlay_theme(s: &Settings) -> OverlayThemeResolved {
+    let language = match s.overlay_status_language.as_deref().map(str::trim) {
+        Some(v) if v.eq_ignore_ascii_case("zh") => "zh",
+        _ => DEFAULT_OVERLAY_STATUS_LANGUAGE,
+    };
+    let overrides = s.overlay_status_colors.as_ref();
+    let statuses = OVERLAY_STATUSES
+        .iter()
+        .map(|&(phase, color, en, zh)| {
+            let color = overrides
+                .and_then(|m| m.get(phase))
+                .map(|c| c.trim())
+                .filter(|c| is_hex_color(c))
+                .unwrap_or(color);
+            let label = if language == "zh" { zh } else { en };
+            (
+                phase.to_string(),
+                OverlayStatusStyle {
+                    label: label.to_string(),
+                    color: color.to_ascii_lowercase(),
+                },
+            )
+        })
+        .collect();
+    OverlayThemeResolved {
+        background_opacity: resolve_overlay_config(s).background_opacity,
+        font_scale: s
+            .overlay_font_scale
+            .filter(|v| v.is_finite())
+            .unwrap_or(DEFAULT_OVERLAY_FONT_SCALE)
+            .clamp(0.5, 2.0),
+        language: language.to_string(),
+        statuses,
+    }
+}
+
+pub fn is_hex_color(v: &str) -> bool {
+    v.strip_prefix('#')
+        .is_some_and(|hex| matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
 #[derive(Debug, Clone, Copy, Serialize)]
 pub struct OverlayWorkArea {
     pub x: f64,
@@ -521,20 +2071,455 @@ fn select_overlay_work_area(
     work_areas.first().copied()
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptionsConfigResolved {
+    pub enabled: bool,
+    pub font_size_px: u64,
+    pub background_opacity: f64,
+    pub monitor_index: Option<u64>,
+}
+
+pub fn resolve_captions_config(s: &Settings) -> CaptionsConfigResolved {
+    CaptionsConfigResolved {
+        enabled: s.captions_enabled.unwrap_or(false),
+        font_size_px: s
+            .captions_font_size_px
+            .unwrap_or(DEFAULT_CAPTIONS_FONT_SIZE_PX)
+            .clamp(24, 96),
+        background_opacity: s
+            .captions_background_opacity
+            .unwrap_or(DEFAULT_CAPTIONS_BACKGROUND_OPACITY)
+            .clamp(0.0, 0.95),
+        monitor_index: s.captions_monitor_index,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct CaptionsRectResolved {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+// `work_areas` must be in the platform's monitor enumeration order so that
+// `monitor_index` stays stable; an out-of-range index falls back to the first.
+pub fn resolve_captions_rect(
+    config: &CaptionsConfigResolved,
+    work_areas: &[OverlayWorkArea],
+) -> CaptionsRectResolved {
+    let area = config
+        .monitor_index
+        .and_then(|idx| work_areas.get(idx as usize))
+        .or_else(|| work_areas.first())
+        .copied()
+        .unwrap_or(OverlayWorkArea {
+            x: 0.0,
+            y: 0.0,
+            width: 1280.0,
+            height: 720.0,
+            scale_factor: 1.0,
+        });
+    let scale = area.scale_factor.max(0.1);
+    let width = (area.width * 0.8).round();
+    // Two caption lines plus padding.
+    let height = (config.font_size_px as f64 * scale * 3.0)
+        .min(area.height / 3.0)
+        .floor();
+    let bottom_padding = 48.0 * scale;
+    CaptionsRectResolved {
+        x: area.x + ((area.width - width) / 2.0).round(),
+        y: (area.y + area.height - height - bottom_padding).max(area.y),
+        width,
+        height,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WakeWordConfigResolved {
+    pub enabled: bool,
+    pub phrase: String,
+    pub sensitivity: f64,
+    pub detector_command: Option<String>,
+}
+
+pub fn resolve_wake_word_config(s: &Settings) -> WakeWordConfigResolved {
+    WakeWordConfigResolved {
+        enabled: s.wake_word_enabled.unwrap_or(false),
+        phrase: s
+            .wake_word_phrase
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .unwrap_or(DEFAULT_WAKE_WORD_PHRASE)
+            .to_string(),
+        sensitivity: s
+            .wake_word_sensitivity
+            .filter(|v| v.is_finite())
+            .unwrap_or(DEFAULT_WAKE_WORD_SENSITIVITY)
+            .clamp(0.0, 1.0),
+        detector_command: s
+            .wake_word_detector_command
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(ToOwned::to_owned),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostprocessorResolved {
+    pub id: String,
+    pub kind: String,
+    pub path: String,
+    pub args: Vec<String>,
+    pub timeout_ms: u64,
+}
+
+// Enabled post-processors in run order. Entries without a path, with an unknown
+// kind, or repeating an earlier id are dropped; blank ids become "#<index>".
+pub fn resolve_postprocessors(s: &Settings) -> Vec<PostprocessorResolved> {
+    let mut out: Vec<PostprocessorResolved> = Vec::new();
+    for (i, p) in s.postprocessors.iter().flatten().enumerate() {
+        let kind = p.kind.trim().to_ascii_lowercase();
+        let path = p.path.trim();
+        if !p.enabled.unwrap_or(true)
+            || path.is_empty()
+            || !POSTPROCESSOR_KINDS.contains(&kind.as_str())
+        {
+            continue;
+        }
+        let id = match p.id.trim() {
+            "" => format!("#{i}"),
+            v => v.to_string(),
+        };
+        if out.iter().any(|o| o.id == id) {
+            continue;
+        }
+        out.push(PostprocessorResolved {
+            id,
+            args: if kind == "command" {
+                p.args.clone().unwrap_or_default()
+            } else {
+                Vec::new()
+            },
+            kind,
+            path: path.to_string(),
+            timeout_ms: p
+                .timeout_ms
+                .unwrap_or(DEFAULT_POSTPROCESSOR_TIMEOUT_MS)
+                .clamp(MIN_POSTPROCESSOR_TIMEOUT_MS, MAX_POSTPROCESSOR_TIMEOUT_MS),
+        });
+    }
+    out
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownNotesResolved {
+    pub folder: PathBuf,
+    pub entry_template: String,
+}
+
+// None when the sink is off or has no folder. The entry template follows the
+// rewrite template when one is mapped, like the export format does.
+pub fn resolve_markdown_notes(
+    s: &Settings,
+    template_id: Option<&str>,
+) -> Option<MarkdownNotesResolved> {
+    if !s.markdown_notes_enabled.unwrap_or(false) {
+        return None;
+    }
+    let folder = non_empty_setting(s.markdown_notes_folder.as_ref())?;
+    let entry_template = template_id
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .and_then(|id| {
+            non_empty_setting(
+                s.markdown_notes_entry_template_by_template
+                    .as_ref()?
+                    .get(id),
+            )
+        })
+        .or_else(|| non_empty_setting(s.markdown_notes_entry_template.as_ref()))
+        .unwrap_or(DEFAULT_MARKDOWN_NOTES_ENTRY_TEMPLATE);
+    Some(MarkdownNotesResolved {
+        folder: PathBuf::from(folder),
+        entry_template: entry_template.to_string(),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookResolved {
+    pub url: String,
+    pub fields: Vec<&'static str>,
+}
+
+// None when the sink is off or has no URL. Fields keep WEBHOOK_FIELDS order;
+// unknown names are dropped and an empty selection means every field.
+pub fn resolve_webhook(s: &Settings) -> Option<WebhookResolved> {
+    if !s.webhook_enabled.unwrap_or(false) {
+        return None;
+    }
+    let url = non_empty_setting(s.webhook_url.as_ref())?;
+    let selected: Vec<String> = s
+        .webhook_fields
+        .iter()
+        .flatten()
+        .map(|f| f.trim().to_ascii_lowercase())
+        .collect();
+    let mut fields: Vec<&'static str> = WEBHOOK_FIELDS
+        .iter()
+        .copied()
+        .filter(|f| selected.iter().any(|v| v == f))
+        .collect();
+    if fields.is_empty() {
+        fields = WEBHOOK_FIELDS.to_vec();
+    }
+    Some(WebhookResolved {
+        url: url.to_string(),
+        fields,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MqttResolved {
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+    // No leading or trailing '/'.
+    pub topic_prefix: String,
+    pub username: Option<String>,
+}
+
+// None when the publisher is off or has no broker host.
+pub fn resolve_mqtt(s: &Settings) -> Option<MqttResolved> {
+    if !s.mqtt_enabled.unwrap_or(false) {
+        return None;
+    }
+    let host = non_empty_setting(s.mqtt_broker_host.as_ref())?;
+    let tls = s.mqtt_tls.unwrap_or(false);
+    let port = s.mqtt_broker_port.filter(|p| *p != 0).unwrap_or(if tls {
+        DEFAULT_MQTT_TLS_PORT
+    } else {
+        DEFAULT_MQTT_PORT
+    });
+    let topic_prefix = s
+        .mqtt_topic_prefix
+        .as_deref()
+        .map(|v| v.trim().trim_matches('/'))
+        .filter(|v| !v.is_empty())
+        .unwrap_or(DEFAULT_MQTT_TOPIC_PREFIX);
+    Some(MqttResolved {
+        host: host.to_string(),
+        port,
+        tls,
+        topic_prefix: topic_prefix.to_string(),
+        username: non_empty_setting(s.mqtt_username.as_ref()).map(ToOwned::to_owned),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DailySummaryResolved {
+    pub hour: u32,
+    pub minute: u32,
+    pub prompt: String,
+}
+
+// "HH:MM" in 24-hour time.
+pub fn parse_clock_time(v: &str) -> Option<(u32, u32)> {
+    let (h, m) = v.trim().split_once(':')?;
+    let (h, m) = (h.trim(), m.trim());
+    if h.is_empty() || h.len() > 2 || m.len() != 2 {
+        return None;
+    }
+    let hour: u32 = h.parse().ok()?;
+    let minute: u32 = m.parse().ok()?;
+    (hour < 24 && minute < 60).then_some((hour, minute))
+}
+
+pub fn resolve_daily_summary_prompt(s: &Settings) -> String {
+    non_empty_setting(s.daily_summary_prompt.as_ref())
+        .unwrap_or(DEFAULT_DAILY_SUMMARY_PROMPT)
+        .to_string()
+}
+
+// None while the digest is off. A blank or malformed time uses the default.
+pub fn resolve_daily_summary(s: &Settings) -> Option<DailySummaryResolved> {
+    if !s.daily_summary_enabled.unwrap_or(false) {
+        return None;
+    }
+    let (hour, minute) = non_empty_setting(s.daily_summary_time.as_ref())
+        .and_then(parse_clock_time)
+        .or_else(|| parse_clock_time(DEFAULT_DAILY_SUMMARY_TIME))?;
+    Some(DailySummaryResolved {
+        hour,
+        minute,
+        prompt: resolve_daily_summary_prompt(s),
+    })
+}
+
+// Ordered, de-duplicated endpoint ids; blank entries are dropped.
+pub fn resolve_record_device_preference(s: &Settings) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for id in s.record_device_preference.iter().flatten() {
+        let id = id.trim();
+        if !id.is_empty() && !out.iter().any(|v| v == id) {
+            out.push(id.to_string());
+        }
+    }
+    out
+}
+
+pub fn resolve_record_avoid_low_quality_profile(s: &Settings) -> bool {
+    s.record_avoid_low_quality_profile.unwrap_or(false)
+}
+
+// ffmpeg only when asked for by name; anything else records natively.
+pub fn resolve_record_backend(s: &Settings) -> &'static str {
+    match s.record_backend.as_deref().map(str::trim) {
+        Some("ffmpeg") => "ffmpeg",
+        _ => DEFAULT_RECORD_BACKEND,
+    }
+}
+
+// Trailing silence that ends a recording, or None when auto-stop is off.
+pub fn resolve_record_vad_silence_ms(s: &Settings) -> Option<u64> {
+    if !s.record_vad_enabled.unwrap_or(false) {
+        return None;
+    }
+    Some(
+        s.record_vad_silence_ms
+            .unwrap_or(DEFAULT_RECORD_VAD_SILENCE_MS)
+            .clamp(MIN_RECORD_VAD_SILENCE_MS, MAX_RECORD_VAD_SILENCE_MS),
+    )
+}
+
+// Remembered capture settings for one endpoint. Gain is clamped to what the
+// preprocess stage accepts; an override with nothing usable left is None.
+pub fn resolve_record_device_override(
+    s: &Settings,
+    endpoint_id: &str,
+) -> Option<RecordDeviceOverride> {
+    let raw = s
+        .record_device_overrides
+        .as_ref()?
+        .get(endpoint_id.trim())?;
+    let gain_db = raw
+        .gain_db
+        .filter(|v| v.is_finite() && *v != 0.0)
+        .map(|v| v.clamp(-20.0, 20.0));
+    let extra_ffmpeg_args: Vec<String> = raw
+        .extra_ffmpeg_args
+        .iter()
+        .flatten()
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(ToOwned::to_owned)
+        .collect();
+    if gain_db.is_none() && extra_ffmpeg_args.is_empty() {
+        return None;
+    }
+    Some(RecordDeviceOverride {
+        gain_db,
+        extra_ffmpeg_args: (!extra_ffmpeg_args.is_empty()).then_some(extra_ffmpeg_args),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        apply_patch, normalize_hotkey_primary, resolve_asr_provider, resolve_hotkey_config,
-        resolve_overlay_config, resolve_overlay_position, resolve_remote_asr_concurrency,
-        resolve_remote_asr_model, resolve_remote_asr_url, OverlayWorkArea, Settings, SettingsPatch,
-        DEFAULT_REMOTE_ASR_URL,
+        apply_patch, export_target_allowed, hotkey_app_allowed, load_settings_strict,
+        modify_settings, normalize_hotkey_primary, parse_clock_time, resolve_asr_provider,
+        resolve_audit_retention, resolve_captions_config, resolve_captions_rect,
+        resolve_clipboard_auto_clear, resolve_daily_summary,
+        resolve_export_elevated_helper_enabled, resolve_export_format, resolve_gpu_coordination,
+        resolve_hotkey_app_allowlist, resolve_hotkey_config, resolve_http_timeouts,
+        resolve_low_power_asr_provider, resolve_low_power_mode, resolve_low_power_remote_asr_model,
+        resolve_markdown_notes, resolve_mqtt, resolve_offline_mode, resolve_overlay_config,
+        resolve_overlay_position, resolve_overlay_theme, resolve_pipeline_max_background_rewrites,
+        resolve_postprocessors, resolve_proxy, resolve_record_backend,
+        resolve_record_device_override, resolve_record_device_preference,
+        resolve_record_vad_silence_ms, resolve_recording_format, resolve_remote_asr_concurrency,
+        resolve_remote_asr_model, resolve_remote_asr_resume_enabled, resolve_remote_asr_url,
+        resolve_rewrite_cache_enabled, resolve_rewrite_chunking, resolve_rewrite_output_format,
+        resolve_rewrite_system_preamble, resolve_rewrite_token_cap, resolve_task_stall_timeout_ms,
+        resolve_template_context_sections, resolve_text_casing, resolve_text_normalize_locales,
+        resolve_time_format, resolve_tls_trust, resolve_wake_word_config, resolve_webhook,
+        save_settings, settings_path, CaptionsConfigResolved, ExportTarget, OverlayWorkArea,
+        PostprocessorConfig, RecordDeviceOverride, Settings, SettingsPatch,
+        TemplateContextSections, TimeFormat, DEFAULT_REMOTE_ASR_URL, OVERLAY_STATUSES,
     };
 
     #[test]
-    fn apply_patch_is_partial_and_can_clear() {
-        let base = Settings {
-            asr_provider: Some("doubao".to_string()),
-            remote_asr_url: None,
+    fn concurrent_modify_settings_keeps_every_update() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let d = dir.path().to_path_buf();
+        save_settings(&d, &Settings::default()).expect("save");
+        let threads: Vec<_> = (0..16)
+            .map(|_| {
+                let d = d.clone();
+                std::thread::spawn(move || {
+                    modify_settings(&d, |s| {
+                        s.http_connect_timeout_ms = s.http_connect_timeout_ms.map(|v| v + 1);
+                        Ok(true)
+                    })
+                    .expect("modify");
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().expect("join");
+        }
+        let s = load_settings_strict(&d).expect("load");
+        assert_eq!(s.http_connect_timeout_ms, Some(10_016));
+        assert!(!settings_path(&d).with_extension("json.tmp").exists());
+
+        let unchanged = modify_settings(&d, |_| Ok(false)).expect("noop");
+        assert_eq!(unchanged.http_connect_timeout_ms, Some(10_016));
+    }
+
+    #[test]
+    fn hand_edits_are_reloaded_and_survive_a_racing_patch() {
+        use super::{poll_external_edit, ExternalEdit};
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let d = dir.path();
+        save_settings(d, &Settings::default()).expect("save");
+        assert!(poll_external_edit(d).is_none());
+
+        let edited = Settings {
+            hotkey_primary: Some("F9".to_string()),
+            ..Settings::default()
+        };
+        std::fs::write(settings_path(d), serde_json::to_vec(&edited).unwrap()).expect("edit");
+        match poll_external_edit(d) {
+            Some(ExternalEdit::Reloaded(s)) => assert_eq!(s.hotkey_primary.as_deref(), Some("F9")),
+            other => panic!("expected reload, got {other:?}"),
+        }
+        assert!(poll_external_edit(d).is_none());
+
+        std::fs::write(settings_path(d), b"{ not json").expect("edit");
+        assert!(matches!(
+            poll_external_edit(d),
+            Some(ExternalEdit::Invalid(_))
+        ));
+
+        // A patch applied before the watcher polls keeps the hand edit.
+        std::fs::write(settings_path(d), serde_json::to_vec(&edited).unwrap()).expect("edit");
+        let merged = modify_settings(d, |s| {
+            s.offline_mode = Some(true);
+            Ok(true)
+        })
+        .expect("modify");
+        assert_eq!(merged.hotkey_primary.as_deref(), Some("F9"));
+        assert!(poll_external_edit(d).is_none());
+    }
+
+    #[test]
+    fn apply_patch_is_partial_and_can_clear() {
+        let base = Settings {
+            asr_provider: Some("doubao".to_string()),
+            remote_asr_url: None,
             remote_asr_model: None,
             remote_asr_concurrency: None,
             llm_base_url: Some("https://x/v1".to_string()),
@@ -570,6 +2555,7 @@ mod tests {
             rewrite_enabled: Some(Some(true)),
             context_history_n: Some(Some(5)),
             context_include_prev_window_meta: Some(Some(true)),
+            context_injection_guard_enabled: Some(Some(false)),
             rewrite_include_glossary: Some(Some(false)),
             auto_paste_enabled: Some(Some(false)),
             hotkey_primary: Some(Some("F9".to_string())),
@@ -594,6 +2580,7 @@ mod tests {
         assert_eq!(next.hotkey_primary.as_deref(), Some("F9"));
         assert_eq!(next.context_history_n, Some(5));
         assert_eq!(next.context_include_prev_window_meta, Some(true));
+        assert_eq!(next.context_injection_guard_enabled, Some(false));
         assert_eq!(next.rewrite_include_glossary, Some(false));
     }
 
@@ -669,6 +2656,34 @@ mod tests {
         assert_eq!(clamped.height_px, 72);
     }
 
+    #[test]
+    fn resolve_overlay_theme_fills_every_status_and_ignores_bad_colors() {
+        let defaults = resolve_overlay_theme(&Settings::default());
+        assert_eq!(defaults.language, "en");
+        assert_eq!(defaults.font_scale, 1.0);
+        assert_eq!(defaults.statuses.len(), OVERLAY_STATUSES.len());
+        assert_eq!(defaults.statuses["recording"].label, "Listening");
+
+        let themed = resolve_overlay_theme(&Settings {
+            overlay_status_colors: Some(
+                [
+                    ("recording".to_string(), "#00FF00".to_string()),
+                    ("failed".to_string(), "red".to_string()),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            overlay_font_scale: Some(5.0),
+            overlay_status_language: Some("zh".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(themed.language, "zh");
+        assert_eq!(themed.font_scale, 2.0);
+        assert_eq!(themed.statuses["recording"].color, "#00ff00");
+        assert_eq!(themed.statuses["recording"].label, "正在听");
+        assert_eq!(themed.statuses["failed"].color, "#ff6b6b");
+    }
+
     #[test]
     fn resolve_overlay_position_uses_work_area_containing_saved_point() {
         let config = resolve_overlay_config(&Settings {
@@ -722,6 +2737,390 @@ mod tests {
         assert_eq!(resolve_remote_asr_concurrency(&s), 16);
     }
 
+    #[test]
+    fn audit_retention_defaults_and_rejects_negative_limits() {
+        let r = resolve_audit_retention(&Settings::default());
+        assert_eq!((r.retention_days, r.max_entries), (90, 10_000));
+        let s = Settings {
+            audit_log_retention_days: Some(-5),
+            audit_log_max_entries: Some(0),
+            ..Default::default()
+        };
+        let r = resolve_audit_retention(&s);
+        assert_eq!((r.retention_days, r.max_entries), (0, 0));
+    }
+
+    #[test]
+    fn elevated_helper_is_opt_in() {
+        assert!(!resolve_export_elevated_helper_enabled(&Settings::default()));
+        assert!(!resolve_export_elevated_helper_enabled(&Settings {
+            export_elevated_helper_enabled: None,
+            ..Default::default()
+        }));
+        assert!(resolve_export_elevated_helper_enabled(&Settings {
+            export_elevated_helper_enabled: Some(true),
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn template_context_sections_apply_only_to_their_template() {
+        let translate = TemplateContextSections {
+            history: Some(false),
+            clipboard: Some(false),
+            ..Default::default()
+        };
+        let s = Settings {
+            context_sections_by_template: Some(
+                [("translate".to_string(), translate)].into_iter().collect(),
+            ),
+            ..Default::default()
+        };
+        assert_eq!(
+            resolve_template_context_sections(&s, Some(" translate ")),
+            translate
+        );
+        assert_eq!(
+            resolve_template_context_sections(&s, Some("answer")),
+            TemplateContextSections::default()
+        );
+        assert_eq!(
+            resolve_template_context_sections(&s, None),
+            TemplateContextSections::default()
+        );
+    }
+
+    #[test]
+    fn gpu_coordination_is_off_by_default_and_drops_blank_urls() {
+        assert_eq!(resolve_gpu_coordination(&Settings::default()), None);
+        let s = Settings {
+            gpu_coordination_enabled: Some(true),
+            gpu_asr_unload_url: Some("  ".to_string()),
+            gpu_llm_unload_url: Some(" http://127.0.0.1:11434/unload ".to_string()),
+            ..Default::default()
+        };
+        let cfg = resolve_gpu_coordination(&s).expect("enabled");
+        assert_eq!(cfg.asr_unload_url, None);
+        assert_eq!(
+            cfg.llm_unload_url.as_deref(),
+            Some("http://127.0.0.1:11434/unload")
+        );
+    }
+
+    #[test]
+    fn export_targets_restrict_only_listed_templates() {
+        let s = Settings {
+            export_targets_by_template: Some(
+                [
+                    ("confidential".to_string(), vec!["Clipboard".to_string()]),
+                    ("email".to_string(), vec!["paste".to_string()]),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        };
+        let allowed = |t: Option<&str>, target| export_target_allowed(&s, t, target);
+        assert!(allowed(Some("confidential"), ExportTarget::Clipboard));
+        assert!(!allowed(Some("confidential"), ExportTarget::Paste));
+        assert!(!allowed(Some("confidential"), ExportTarget::Webhook));
+        assert!(allowed(Some("email"), ExportTarget::Clipboard));
+        assert!(!allowed(Some("email"), ExportTarget::MarkdownNotes));
+        assert!(allowed(Some("other"), ExportTarget::Webhook));
+        assert!(allowed(None, ExportTarget::Paste));
+    }
+
+    #[test]
+    fn clipboard_auto_clear_is_off_by_default_and_capped() {
+        assert_eq!(resolve_clipboard_auto_clear(&Settings::default()), None);
+        let s = Settings {
+            clipboard_auto_clear_secs: Some(86_400),
+            ..Default::default()
+        };
+        assert_eq!(
+            resolve_clipboard_auto_clear(&s),
+            Some(std::time::Duration::from_secs(3_600))
+        );
+    }
+
+    #[test]
+    fn rewrite_token_cap_is_off_by_default_and_floored() {
+        assert_eq!(resolve_rewrite_token_cap(&Settings::default()), None);
+        let cap = |v| {
+            resolve_rewrite_token_cap(&Settings {
+                rewrite_token_cap: Some(v),
+                ..Default::default()
+            })
+        };
+        assert_eq!(cap(100), Some(500));
+        assert_eq!(cap(8000), Some(8000));
+    }
+
+    #[test]
+    fn remote_asr_resume_follows_keep_recordings() {
+        assert!(!resolve_remote_asr_resume_enabled(&Settings::default()));
+        assert!(resolve_remote_asr_resume_enabled(&Settings {
+            keep_recordings: Some(true),
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn rewrite_cache_is_enabled_unless_turned_off() {
+        assert!(resolve_rewrite_cache_enabled(&Settings::default()));
+        assert!(resolve_rewrite_cache_enabled(&Settings {
+            rewrite_cache_enabled: None,
+            ..Default::default()
+        }));
+        assert!(!resolve_rewrite_cache_enabled(&Settings {
+            rewrite_cache_enabled: Some(false),
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn resolve_rewrite_chunking_defaults_disables_and_clamps() {
+        let defaults = resolve_rewrite_chunking(&Settings::default()).expect("enabled");
+        assert_eq!(defaults.threshold_tokens, 6_000);
+        assert_eq!(defaults.chunk_tokens, 2_000);
+        let mut s = Settings {
+            rewrite_chunk_threshold_tokens: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(resolve_rewrite_chunking(&s), None);
+        s.rewrite_chunk_threshold_tokens = Some(1_000);
+        s.rewrite_chunk_tokens = Some(5_000);
+        assert_eq!(
+            resolve_rewrite_chunking(&s).map(|c| c.chunk_tokens),
+            Some(1_000)
+        );
+        s.rewrite_chunk_tokens = Some(1);
+        assert_eq!(
+            resolve_rewrite_chunking(&s).map(|c| c.chunk_tokens),
+            Some(200)
+        );
+    }
+
+    #[test]
+    fn resolve_proxy_trims_and_drops_blank_entries() {
+        assert_eq!(resolve_proxy(&Settings::default()), None);
+        let s = Settings {
+            proxy_url: Some("  ".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(resolve_proxy(&s), None);
+        let s = Settings {
+            proxy_url: Some(" socks5h://proxy.corp:1080 ".to_string()),
+            proxy_no_proxy: Some(vec![
+                " localhost".to_string(),
+                String::new(),
+                "*.corp.example".to_string(),
+            ]),
+            ..Default::default()
+        };
+        let p = resolve_proxy(&s).expect("proxy");
+        assert_eq!(p.url, "socks5h://proxy.corp:1080");
+        assert_eq!(p.no_proxy, vec!["localhost", "*.corp.example"]);
+    }
+
+    #[test]
+    fn resolve_http_timeouts_defaults_clamps_and_disables() {
+        let t = resolve_http_timeouts(&Settings::default());
+        assert_eq!((t.connect_ms, t.request_ms), (10_000, Some(180_000)));
+        let s = Settings {
+            http_connect_timeout_ms: Some(5),
+            http_request_timeout_ms: Some(0),
+            ..Default::default()
+        };
+        let t = resolve_http_timeouts(&s);
+        assert_eq!((t.connect_ms, t.request_ms), (1_000, None));
+    }
+
+    #[test]
+    fn offline_mode_defaults_off_and_patch_toggles_it() {
+        let mut s = Settings::default();
+        assert!(!resolve_offline_mode(&s));
+        s = apply_patch(
+            s,
+            SettingsPatch {
+                offline_mode: Some(Some(true)),
+                ..Default::default()
+            },
+        );
+        assert!(resolve_offline_mode(&s));
+        assert!(!resolve_offline_mode(&Settings {
+            offline_mode: None,
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn record_backend_defaults_to_native() {
+        assert_eq!(resolve_record_backend(&Settings::default()), "native");
+        let with = |v: &str| Settings {
+            record_backend: Some(v.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(resolve_record_backend(&with(" ffmpeg ")), "ffmpeg");
+        assert_eq!(resolve_record_backend(&with("dshow")), "native");
+    }
+
+    #[test]
+    fn record_vad_is_off_unless_enabled_and_clamps_silence() {
+        assert_eq!(resolve_record_vad_silence_ms(&Settings::default()), None);
+        let with = |ms: Option<u64>| Settings {
+            record_vad_enabled: Some(true),
+            record_vad_silence_ms: ms,
+            ..Default::default()
+        };
+        assert_eq!(resolve_record_vad_silence_ms(&with(None)), Some(1_500));
+        assert_eq!(resolve_record_vad_silence_ms(&with(Some(50))), Some(300));
+        assert_eq!(
+            resolve_record_vad_silence_ms(&with(Some(2_000))),
+            Some(2_000)
+        );
+    }
+
+    #[test]
+    fn low_power_asr_falls_back_to_regular_choices() {
+        let s = Settings {
+            asr_provider: Some("remote".to_string()),
+            remote_asr_model: Some("large-v3".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(resolve_low_power_mode(&s), "off");
+        assert_eq!(resolve_low_power_asr_provider(&s), "remote");
+        assert_eq!(
+            resolve_low_power_remote_asr_model(&s).as_deref(),
+            Some("large-v3")
+        );
+        let s = Settings {
+            low_power_asr_provider: Some("doubao".to_string()),
+            low_power_remote_asr_model: Some(" small ".to_string()),
+            ..s
+        };
+        assert_eq!(resolve_low_power_asr_provider(&s), "doubao");
+        assert_eq!(
+            resolve_low_power_remote_asr_model(&s).as_deref(),
+            Some("small")
+        );
+    }
+
+    #[test]
+    fn recording_format_defaults_to_wav_and_ignores_unknown_values() {
+        assert_eq!(resolve_recording_format(&Settings::default()), "wav");
+        let with = |v: &str| Settings {
+            recording_format: Some(v.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(resolve_recording_format(&with(" flac ")), "flac");
+        assert_eq!(resolve_recording_format(&with("opus")), "opus");
+        assert_eq!(resolve_recording_format(&with("mp3")), "wav");
+    }
+
+    #[test]
+    fn resolve_postprocessors_keeps_order_and_drops_unusable_entries() {
+        assert!(resolve_postprocessors(&Settings::default()).is_empty());
+        let entry = |id: &str, kind: &str, path: &str| PostprocessorConfig {
+            id: id.to_string(),
+            kind: kind.to_string(),
+            path: path.to_string(),
+            ..Default::default()
+        };
+        let s = Settings {
+            postprocessors: Some(vec![
+                PostprocessorConfig {
+                    args: Some(vec!["--notes".to_string()]),
+                    timeout_ms: Some(999_999),
+                    ..entry("notes", "Command", " /usr/bin/notes ")
+                },
+                PostprocessorConfig {
+                    enabled: Some(false),
+                    ..entry("off", "command", "/bin/off")
+                },
+                entry("bad", "python", "/bin/py"),
+                entry("nopath", "wasm", "  "),
+                PostprocessorConfig {
+                    args: Some(vec!["ignored".to_string()]),
+                    ..entry("", "wasm", "/plugins/upper.wasm")
+                },
+                entry("notes", "wasm", "/plugins/dup.wasm"),
+            ]),
+            ..Default::default()
+        };
+        let got = resolve_postprocessors(&s);
+        assert_eq!(got.len(), 2);
+        assert_eq!(got[0].id, "notes");
+        assert_eq!(got[0].kind, "command");
+        assert_eq!(got[0].path, "/usr/bin/notes");
+        assert_eq!(got[0].args, vec!["--notes".to_string()]);
+        assert_eq!(got[0].timeout_ms, 60_000);
+        assert_eq!(got[1].id, "#4");
+        assert!(got[1].args.is_empty());
+        assert_eq!(got[1].timeout_ms, 5_000);
+    }
+
+    #[test]
+    fn resolve_tls_trust_normalizes_fingerprint() {
+        assert_eq!(resolve_tls_trust(&Settings::default()), Default::default());
+        let s = Settings {
+            tls_ca_bundle_path: Some(" C:\\certs\\corp.pem ".to_string()),
+            tls_pinned_sha256: Some("AB:CD:ef 01".to_string()),
+            ..Default::default()
+        };
+        let t = resolve_tls_trust(&s);
+        assert_eq!(t.ca_bundle_path.as_deref(), Some("C:\\certs\\corp.pem"));
+        assert_eq!(t.pinned_sha256.as_deref(), Some("abcdef01"));
+        // Without explicit endpoints the pin covers the default ASR host only.
+        assert_eq!(t.pinned_hosts, vec!["api.server".to_string()]);
+    }
+
+    #[test]
+    fn resolve_tls_trust_scopes_the_pin_to_endpoint_hosts() {
+        let s = Settings {
+            tls_pinned_sha256: Some("ab".repeat(32)),
+            llm_base_url: Some("https://user@LLM.lan:8443/v1".to_string()),
+            remote_asr_url: Some("http://[fd00::1]:9000/asr".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            resolve_tls_trust(&s).pinned_hosts,
+            vec!["llm.lan".to_string(), "fd00::1".to_string()]
+        );
+        let no_pin = Settings {
+            tls_pinned_sha256: None,
+            ..s
+        };
+        assert!(resolve_tls_trust(&no_pin).pinned_hosts.is_empty());
+    }
+
+    #[test]
+    fn pipeline_max_background_rewrites_defaults_and_clamps() {
+        assert_eq!(
+            resolve_pipeline_max_background_rewrites(&Settings::default()),
+            0
+        );
+        let mut s = Settings {
+            pipeline_max_background_rewrites: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(resolve_pipeline_max_background_rewrites(&s), 1);
+        s.pipeline_max_background_rewrites = Some(50);
+        assert_eq!(resolve_pipeline_max_background_rewrites(&s), 4);
+        s.pipeline_max_background_rewrites = None;
+        assert_eq!(resolve_pipeline_max_background_rewrites(&s), 0);
+    }
+
+    #[test]
+    fn task_stall_timeout_defaults_clamps_and_turns_off() {
+        let mut s = Settings::default();
+        assert_eq!(resolve_task_stall_timeout_ms(&s), Some(300_000));
+        s.task_stall_timeout_ms = Some(1_000);
+        assert_eq!(resolve_task_stall_timeout_ms(&s), Some(30_000));
+        s.task_stall_timeout_ms = Some(0);
+        assert_eq!(resolve_task_stall_timeout_ms(&s), None);
+    }
+
     #[test]
     fn hotkey_primary_defaults_and_validates_single_keys() {
         let mut s = Settings {
@@ -741,4 +3140,404 @@ mod tests {
         );
         assert!(normalize_hotkey_primary(Some("Ctrl+Alt")).is_err());
     }
+
+    #[test]
+    fn hotkey_debounce_defaults_and_clamps() {
+        let mut s = Settings {
+            hotkeys_enabled: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(resolve_hotkey_config(&s).expect("cfg").debounce_ms, 250);
+        s.hotkey_debounce_ms = Some(0);
+        assert_eq!(resolve_hotkey_config(&s).expect("cfg").debounce_ms, 0);
+        s.hotkey_debounce_ms = Some(60_000);
+        assert_eq!(resolve_hotkey_config(&s).expect("cfg").debounce_ms, 2_000);
+    }
+
+    #[test]
+    fn hotkey_app_allowlist_matches_process_file_names() {
+        let s = Settings {
+            hotkey_app_allowlist: Some(vec![
+                r"C:\Program Files\Microsoft VS Code\Code.exe".to_string(),
+                " outlook.exe ".to_string(),
+                "".to_string(),
+                "code.exe".to_string(),
+            ]),
+            ..Default::default()
+        };
+        let list = resolve_hotkey_app_allowlist(&s);
+        assert_eq!(list, ["code.exe", "outlook.exe"]);
+        assert!(hotkey_app_allowed(&list, Some(r"D:\Tools\CODE.EXE")));
+        assert!(!hotkey_app_allowed(&list, Some("game.exe")));
+        assert!(!hotkey_app_allowed(&list, None));
+        assert!(hotkey_app_allowed(&[], None));
+    }
+
+    #[test]
+    fn resolve_export_format_prefers_template_override() {
+        let s = Settings {
+            export_format: Some(" Markdown_HTML ".to_string()),
+            export_format_by_template: Some(
+                [("chat".to_string(), "plain".to_string())]
+                    .into_iter()
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        assert_eq!(resolve_export_format(&s, None), "markdown_html");
+        assert_eq!(resolve_export_format(&s, Some("notes")), "markdown_html");
+        assert_eq!(resolve_export_format(&s, Some("chat")), "plain");
+        assert_eq!(
+            resolve_export_format(&Settings::default(), Some("chat")),
+            "plain"
+        );
+    }
+
+    #[test]
+    fn resolve_markdown_notes_needs_folder_and_prefers_template_entry() {
+        let mut s = Settings {
+            markdown_notes_folder: Some(" /vault/Daily ".to_string()),
+            markdown_notes_entry_template_by_template: Some(
+                [("meeting".to_string(), "- {{time}} {{text}}".to_string())]
+                    .into_iter()
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        assert_eq!(resolve_markdown_notes(&s, None), None);
+        s.markdown_notes_enabled = Some(true);
+        let cfg = resolve_markdown_notes(&s, Some("email")).expect("enabled");
+        assert_eq!(cfg.folder, std::path::PathBuf::from("/vault/Daily"));
+        assert_eq!(cfg.entry_template, "## {{time}}\n\n{{text}}\n");
+        let cfg = resolve_markdown_notes(&s, Some("meeting")).expect("enabled");
+        assert_eq!(cfg.entry_template, "- {{time}} {{text}}");
+        s.markdown_notes_folder = Some("  ".to_string());
+        assert_eq!(resolve_markdown_notes(&s, None), None);
+    }
+
+    #[test]
+    fn resolve_webhook_keeps_known_fields_in_canonical_order() {
+        let mut s = Settings {
+            webhook_url: Some(" https://hooks.example.com/tv ".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(resolve_webhook(&s), None);
+        s.webhook_enabled = Some(true);
+        let cfg = resolve_webhook(&s).expect("enabled");
+        assert_eq!(cfg.url, "https://hooks.example.com/tv");
+        assert_eq!(cfg.fields.len(), super::WEBHOOK_FIELDS.len());
+
+        s.webhook_fields = Some(vec![
+            "final_text".to_string(),
+            " ASR_TEXT ".to_string(),
+            "audio_path".to_string(),
+        ]);
+        let cfg = resolve_webhook(&s).expect("enabled");
+        assert_eq!(cfg.fields, vec!["asr_text", "final_text"]);
+
+        s.webhook_fields = Some(vec!["audio_path".to_string()]);
+        assert_eq!(
+            resolve_webhook(&s).expect("enabled").fields.len(),
+            super::WEBHOOK_FIELDS.len()
+        );
+    }
+
+    #[test]
+    fn resolve_mqtt_defaults_port_by_tls_and_trims_prefix() {
+        let mut s = Settings {
+            mqtt_broker_host: Some(" homeassistant.local ".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(resolve_mqtt(&s), None);
+        s.mqtt_enabled = Some(true);
+        let cfg = resolve_mqtt(&s).expect("enabled");
+        assert_eq!(cfg.host, "homeassistant.local");
+        assert_eq!((cfg.port, cfg.tls), (1883, false));
+        assert_eq!(cfg.topic_prefix, "typevoice");
+
+        s.mqtt_tls = Some(true);
+        s.mqtt_topic_prefix = Some(" /home/office/ ".to_string());
+        s.mqtt_username = Some("  ".to_string());
+        let cfg = resolve_mqtt(&s).expect("enabled");
+        assert_eq!((cfg.port, cfg.tls), (8883, true));
+        assert_eq!(cfg.topic_prefix, "home/office");
+        assert_eq!(cfg.username, None);
+
+        s.mqtt_broker_port = Some(18883);
+        assert_eq!(resolve_mqtt(&s).expect("enabled").port, 18883);
+        s.mqtt_broker_host = None;
+        assert_eq!(resolve_mqtt(&s), None);
+    }
+
+    #[test]
+    fn resolve_daily_summary_parses_time_and_falls_back_to_defaults() {
+        assert_eq!(parse_clock_time(" 7:05 "), Some((7, 5)));
+        assert_eq!(parse_clock_time("23:59"), Some((23, 59)));
+        for bad in ["24:00", "12:60", "12", "12:5", "ab:cd", ""] {
+            assert_eq!(parse_clock_time(bad), None, "{bad}");
+        }
+
+        let mut s = Settings {
+            daily_summary_time: Some("08:30".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(resolve_daily_summary(&s), None);
+        s.daily_summary_enabled = Some(true);
+        let cfg = resolve_daily_summary(&s).expect("enabled");
+        assert_eq!((cfg.hour, cfg.minute), (8, 30));
+        assert_eq!(cfg.prompt, super::DEFAULT_DAILY_SUMMARY_PROMPT);
+
+        s.daily_summary_time = Some("late".to_string());
+        s.daily_summary_prompt = Some(" Bullet points only. ".to_string());
+        let cfg = resolve_daily_summary(&s).expect("enabled");
+        assert_eq!((cfg.hour, cfg.minute), (18, 0));
+        assert_eq!(cfg.prompt, "Bullet points only.");
+    }
+
+    #[test]
+    fn resolve_rewrite_output_format_prefers_template_override() {
+        let defaults = resolve_rewrite_output_format(&Settings::default(), Some("form"));
+        assert_eq!(defaults.format, "text");
+        assert_eq!(defaults.json_schema, None);
+
+        let s = Settings {
+            rewrite_output_format_by_template: Some(
+                [("form".to_string(), " JSON_Schema ".to_string())]
+                    .into_iter()
+                    .collect(),
+            ),
+            rewrite_output_json_schema: Some(r#"{"type":"object"}"#.to_string()),
+            rewrite_output_json_schema_by_template: Some(
+                [("form".to_string(), r#"{"type":"array"}"#.to_string())]
+                    .into_iter()
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        let form = resolve_rewrite_output_format(&s, Some("form"));
+        assert_eq!(form.format, "json_schema");
+        assert_eq!(form.json_schema.as_deref(), Some(r#"{"type":"array"}"#));
+        let other = resolve_rewrite_output_format(&s, Some("chat"));
+        assert_eq!(other.format, "text");
+        assert_eq!(other.json_schema.as_deref(), Some(r#"{"type":"object"}"#));
+    }
+
+    #[test]
+    fn resolve_time_format_falls_back_to_24h() {
+        assert_eq!(resolve_time_format(&Settings::default()), TimeFormat::H24);
+        let s = Settings {
+            time_format: Some("iso".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(resolve_time_format(&s), TimeFormat::Iso);
+        let s = Settings {
+            time_format: Some("locale".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(resolve_time_format(&s), TimeFormat::H24);
+    }
+
+    #[test]
+    fn resolve_text_casing_prefers_template_then_mode() {
+        let s = Settings {
+            text_casing: Some("sentence".to_string()),
+            text_casing_by_mode: Some(
+                [
+                    ("rewrite".to_string(), "preserve".to_string()),
+                    ("headings".to_string(), " Title ".to_string()),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        };
+        assert_eq!(resolve_text_casing(&s, "dictation", None), "sentence");
+        assert_eq!(resolve_text_casing(&s, "rewrite", None), "preserve");
+        assert_eq!(
+            resolve_text_casing(&s, "rewrite", Some("headings")),
+            "title"
+        );
+        assert_eq!(
+            resolve_text_casing(&Settings::default(), "dictation", None),
+            "preserve"
+        );
+    }
+
+    #[test]
+    fn resolve_rewrite_system_preamble_ignores_blank() {
+        assert_eq!(resolve_rewrite_system_preamble(&Settings::default()), None);
+        let s = apply_patch(
+            Settings::default(),
+            SettingsPatch {
+                rewrite_system_preamble: Some(Some("  \n ".to_string())),
+                ..Default::default()
+            },
+        );
+        assert_eq!(resolve_rewrite_system_preamble(&s), None);
+        let s = apply_patch(
+            s,
+            SettingsPatch {
+                rewrite_system_preamble: Some(Some(" Reply in zh-CN.\n".to_string())),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            resolve_rewrite_system_preamble(&s).as_deref(),
+            Some("Reply in zh-CN.")
+        );
+    }
+
+    #[test]
+    fn resolve_text_normalize_locales_is_off_by_default() {
+        assert!(resolve_text_normalize_locales(&Settings::default()).is_empty());
+        let s = Settings {
+            text_normalize_enabled: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(resolve_text_normalize_locales(&s), vec!["zh", "en"]);
+        let s = Settings {
+            text_normalize_enabled: Some(true),
+            text_normalize_locales: Some(vec![" EN ".to_string(), String::new()]),
+            ..Default::default()
+        };
+        assert_eq!(resolve_text_normalize_locales(&s), vec!["en"]);
+    }
+
+    #[test]
+    fn resolve_captions_rect_uses_selected_monitor() {
+        let areas = [
+            OverlayWorkArea {
+                x: 0.0,
+                y: 0.0,
+                width: 1920.0,
+                height: 1040.0,
+                scale_factor: 1.0,
+            },
+            OverlayWorkArea {
+                x: 1920.0,
+                y: 0.0,
+                width: 2560.0,
+                height: 1400.0,
+                scale_factor: 2.0,
+            },
+        ];
+        let config = resolve_captions_config(&Settings {
+            captions_monitor_index: Some(1),
+            captions_font_size_px: Some(500),
+            ..Default::default()
+        });
+        assert_eq!(config.font_size_px, 96);
+        let rect = resolve_captions_rect(&config, &areas);
+        assert_eq!(rect.width, 2048.0);
+        assert_eq!(rect.x, 1920.0 + 256.0);
+        assert!(rect.height <= 1400.0 / 3.0);
+        assert!(rect.y + rect.height <= 1400.0);
+
+        let fallback = resolve_captions_rect(
+            &CaptionsConfigResolved {
+                monitor_index: Some(7),
+                ..config
+            },
+            &areas,
+        );
+        assert_eq!(fallback.x, 192.0);
+    }
+
+    #[test]
+    fn resolve_wake_word_config_clamps_and_trims() {
+        let defaults = resolve_wake_word_config(&Settings::default());
+        assert!(!defaults.enabled);
+        assert_eq!(defaults.phrase, "hey typevoice");
+        assert_eq!(defaults.sensitivity, 0.5);
+        assert_eq!(defaults.detector_command, None);
+
+        let cfg = resolve_wake_word_config(&Settings {
+            wake_word_enabled: Some(true),
+            wake_word_phrase: Some("  ".to_string()),
+            wake_word_sensitivity: Some(3.0),
+            wake_word_detector_command: Some(" oww-detect --model hey.onnx ".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(cfg.phrase, "hey typevoice");
+        assert_eq!(cfg.sensitivity, 1.0);
+        assert_eq!(
+            cfg.detector_command.as_deref(),
+            Some("oww-detect --model hey.onnx")
+        );
+    }
+
+    #[test]
+    fn record_device_preference_and_overrides_are_sanitized() {
+        let s = Settings {
+            record_device_preference: Some(vec![
+                " {0.0.1}.{usb} ".to_string(),
+                "".to_string(),
+                "{0.0.1}.{array}".to_string(),
+                "{0.0.1}.{usb}".to_string(),
+            ]),
+            record_device_overrides: Some(
+                [
+                    (
+                        "{0.0.1}.{usb}".to_string(),
+                        RecordDeviceOverride {
+                            gain_db: Some(30.0),
+                            extra_ffmpeg_args: Some(vec![
+                                "-audio_buffer_size".to_string(),
+                                " 50 ".to_string(),
+                                " ".to_string(),
+                            ]),
+                        },
+                    ),
+                    (
+                        "{0.0.1}.{array}".to_string(),
+                        RecordDeviceOverride {
+                            gain_db: Some(0.0),
+                            extra_ffmpeg_args: Some(Vec::new()),
+                        },
+                    ),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            resolve_record_device_preference(&s),
+            vec!["{0.0.1}.{usb}".to_string(), "{0.0.1}.{array}".to_string()]
+        );
+        assert_eq!(
+            resolve_record_device_override(&s, "{0.0.1}.{usb}"),
+            Some(RecordDeviceOverride {
+                gain_db: Some(20.0),
+                extra_ffmpeg_args: Some(vec!["-audio_buffer_size".to_string(), "50".to_string()]),
+            })
+        );
+        assert_eq!(resolve_record_device_override(&s, "{0.0.1}.{array}"), None);
+        assert_eq!(resolve_record_device_override(&s, "missing"), None);
+    }
+
+    #[test]
+    fn json_schema_covers_every_settings_and_patch_field() {
+        for (schema, value) in [
+            (
+                schemars::schema_for!(Settings),
+                serde_json::to_value(Settings::default()).expect("settings"),
+            ),
+            (
+                schemars::schema_for!(SettingsPatch),
+                serde_json::to_value(SettingsPatch::default()).expect("patch"),
+            ),
+        ] {
+            let props = schema
+                .get("properties")
+                .and_then(|v| v.as_object())
+                .expect("properties");
+            let fields = value.as_object().expect("object");
+            assert_eq!(props.len(), fields.len());
+            for key in fields.keys() {
+                assert!(props.contains_key(key), "schema is missing {key}");
+            }
+        }
+    }
 }
@@ -0,0 +1,1054 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::settings::{self, Settings, SettingsPatch};
+use crate::timestamps::TIME_FORMATS;
+
+const ASR_PROVIDERS: &[&str] = &["doubao", "remote"];
+const REASONING_EFFORTS: &[&str] = &["none", "minimal", "low", "medium", "high", "xhigh"];
+const OUTPUT_FORMATS: &[&str] = &["text", "json_schema"];
+const EXPORT_FORMATS: &[&str] = &["plain", "markdown_html"];
+// "title" is the older name for "headings".
+const TEXT_CASINGS: &[&str] = &["preserve", "sentence", "lower", "headings", "title"];
+const RECORDING_FORMATS: &[&str] = &["wav", "flac", "opus"];
+const RECORD_BACKENDS: &[&str] = &["native", "ffmpeg"];
+const LOW_POWER_MODES: &[&str] = &["auto", "on", "off"];
+const NORMALIZE_LOCALES: &[&str] = &["zh", "en"];
+const HTTP_SCHEMES: &[&str] = &["http", "https"];
+const PROXY_SCHEMES: &[&str] = &["http", "https", "socks5", "socks5h"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    pub field: String, // settings key; map entries as "key.<id>"
+    pub code: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, code: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+// Checks only the fields present in `patch`, against the merged result, so a
+// stale value elsewhere in settings.json does not block an unrelated edit.
+// Ranges mirror the clamps in the resolve_* functions. Host-dependent checks
+// (hotkey registration, audio endpoints) are added by the desktop command.
+pub fn validate_patch(cur: &Settings, patch: &SettingsPatch) -> Vec<FieldError> {
+    let next = settings::apply_patch(cur.clone(), patch.clone());
+    let mut v = Validator::default();
+
+    if patch.asr_provider.is_some() {
+        v.one_of("asr_provider", next.asr_provider.as_deref(), ASR_PROVIDERS);
+    }
+    if patch.low_power_mode.is_some() {
+        v.one_of(
+            "low_power_mode",
+            next.low_power_mode.as_deref(),
+            LOW_POWER_MODES,
+        );
+    }
+    if patch.low_power_asr_provider.is_some() {
+        v.one_of(
+            "low_power_asr_provider",
+            next.low_power_asr_provider.as_deref(),
+            ASR_PROVIDERS,
+        );
+    }
+    if patch.remote_asr_url.is_some() {
+        v.url(
+            "remote_asr_url",
+            next.remote_asr_url.as_deref(),
+            HTTP_SCHEMES,
+        );
+    }
+    if patch.remote_asr_concurrency.is_some() {
+        let max = settings::MAX_REMOTE_ASR_CONCURRENCY as u64;
+        v.range_u64(
+            "remote_asr_concurrency",
+            next.remote_asr_concurrency,
+            1,
+            max,
+        );
+    }
+    if patch.asr_warm_pool_max_models.is_some() {
+        let max = settings::MAX_ASR_WARM_POOL_MODELS as u64;
+        v.range_u64(
+            "asr_warm_pool_max_models",
+            next.asr_warm_pool_max_models,
+            1,
+            max,
+        );
+    }
+    if patch.asr_warm_pool_keepalive_secs.is_some() {
+        v.range_u64(
+            "asr_warm_pool_keepalive_secs",
+            next.asr_warm_pool_keepalive_secs,
+            settings::MIN_ASR_WARM_POOL_KEEPALIVE_SECS,
+            u64::MAX,
+        );
+    }
+    if patch.proxy_url.is_some() {
+        v.url("proxy_url", next.proxy_url.as_deref(), PROXY_SCHEMES);
+    }
+    if patch.tls_ca_bundle_path.is_some() {
+        v.existing_file("tls_ca_bundle_path", next.tls_ca_bundle_path.as_deref());
+    }
+    if patch.tls_pinned_sha256.is_some() {
+        let pin = settings::resolve_tls_trust(&next).pinned_sha256;
+        if pin.is_some_and(|p| p.len() != 64 || !p.bytes().all(|b| b.is_ascii_hexdigit())) {
+            v.push(
+                "tls_pinned_sha256",
+                "E_SETTINGS_FINGERPRINT_INVALID",
+                "must be 64 hex characters",
+            );
+        }
+    }
+    if patch.http_connect_timeout_ms.is_some() {
+        v.range_u64(
+            "http_connect_timeout_ms",
+            next.http_connect_timeout_ms,
+            1_000,
+            u64::MAX,
+        );
+    }
+    if patch.http_request_timeout_ms.is_some() && next.http_request_timeout_ms != Some(0) {
+        v.range_u64(
+            "http_request_timeout_ms",
+            next.http_request_timeout_ms,
+            1_000,
+            u64::MAX,
+        );
+    }
+
+    if patch.llm_base_url.is_some() {
+        v.url("llm_base_url", next.llm_base_url.as_deref(), HTTP_SCHEMES);
+    }
+    if patch.gpu_asr_unload_url.is_some() {
+        v.url(
+            "gpu_asr_unload_url",
+            next.gpu_asr_unload_url.as_deref(),
+            HTTP_SCHEMES,
+        );
+    }
+    if patch.gpu_llm_unload_url.is_some() {
+        v.url(
+            "gpu_llm_unload_url",
+            next.gpu_llm_unload_url.as_deref(),
+            HTTP_SCHEMES,
+        );
+    }
+    if patch.llm_capture_max_entries.is_some() {
+        v.range_u64(
+            "llm_capture_max_entries",
+            next.llm_capture_max_entries,
+            1,
+            settings::MAX_LLM_CAPTURE_MAX_ENTRIES,
+        );
+    }
+    if patch.llm_reasoning_effort.is_some() {
+        v.one_of(
+            "llm_reasoning_effort",
+            next.llm_reasoning_effort.as_deref(),
+            REASONING_EFFORTS,
+        );
+    }
+    if patch.rewrite_enabled.is_some() || patch.llm_prompt.is_some() {
+        let prompt_missing = next
+            .llm_prompt
+            .as_deref()
+            .is_none_or(|p| p.trim().is_empty());
+        if next.rewrite_enabled.unwrap_or(false) && prompt_missing {
+            v.push(
+                "llm_prompt",
+                "E_SETTINGS_REQUIRED",
+                "a prompt is required while rewrite is enabled",
+            );
+        }
+    }
+    if patch.rewrite_output_format.is_some() {
+        v.one_of(
+            "rewrite_output_format",
+            next.rewrite_output_format.as_deref(),
+            OUTPUT_FORMATS,
+        );
+    }
+    if patch.rewrite_output_format_by_template.is_some() {
+        v.map_one_of(
+            "rewrite_output_format_by_template",
+            next.rewrite_output_format_by_template.as_ref(),
+            OUTPUT_FORMATS,
+        );
+    }
+    if patch.rewrite_output_json_schema.is_some() {
+        v.json(
+            "rewrite_output_json_schema",
+            next.rewrite_output_json_schema.as_deref(),
+        );
+    }
+    if let Some(Some(map)) = &patch.rewrite_output_json_schema_by_template {
+        for (id, schema) in map {
+            let field = format!("rewrite_output_json_schema_by_template.{id}");
+            v.template_id(&field, id);
+            v.json(&field, Some(schema));
+        }
+    }
+    if patch.pipeline_max_background_rewrites.is_some() {
+        let max = settings::MAX_PIPELINE_MAX_BACKGROUND_REWRITES as u64;
+        v.range_u64(
+            "pipeline_max_background_rewrites",
+            next.pipeline_max_background_rewrites,
+            0,
+            max,
+        );
+    }
+    if let Some(Some(ms)) = patch.task_stall_timeout_ms {
+        if ms != 0 {
+            v.range_u64(
+                "task_stall_timeout_ms",
+                Some(ms),
+                settings::MIN_TASK_STALL_TIMEOUT_MS,
+                u64::MAX,
+            );
+        }
+    }
+    if patch.rewrite_chunk_tokens.is_some() {
+        v.range_u64(
+            "rewrite_chunk_tokens",
+            next.rewrite_chunk_tokens,
+            settings::MIN_REWRITE_CHUNK_TOKENS,
+            u64::MAX,
+        );
+    }
+    if patch.clipboard_auto_clear_secs.is_some() {
+        v.range_u64(
+            "clipboard_auto_clear_secs",
+            next.clipboard_auto_clear_secs,
+            0,
+            settings::MAX_CLIPBOARD_AUTO_CLEAR_SECS,
+        );
+    }
+    if let Some(Some(cap)) = patch.rewrite_token_cap {
+        if cap != 0 {
+            v.range_u64(
+                "rewrite_token_cap",
+                Some(cap),
+                settings::MIN_REWRITE_TOKEN_CAP,
+                u64::MAX,
+            );
+        }
+    }
+    if patch.export_format.is_some() {
+        v.one_of(
+            "export_format",
+            next.export_format.as_deref(),
+            EXPORT_FORMATS,
+        );
+    }
+    if patch.export_format_by_template.is_some() {
+        v.map_one_of(
+            "export_format_by_template",
+            next.export_format_by_template.as_ref(),
+            EXPORT_FORMATS,
+        );
+    }
+    if patch.export_targets_by_template.is_some() {
+        for (id, targets) in next.export_targets_by_template.iter().flatten() {
+            let field = format!("export_targets_by_template.{id}");
+            v.template_id(&field, id);
+            for target in targets {
+                v.one_of(&field, Some(target), settings::EXPORT_TARGETS);
+            }
+        }
+    }
+    if patch.audit_log_retention_days.is_some() {
+        v.range_i64(
+            "audit_log_retention_days",
+            next.audit_log_retention_days,
+            0,
+            i64::MAX,
+        );
+    }
+    if patch.audit_log_max_entries.is_some() {
+        v.range_i64(
+            "audit_log_max_entries",
+            next.audit_log_max_entries,
+            0,
+            i64::MAX,
+        );
+    }
+    if patch.time_format.is_some() {
+        v.one_of("time_format", next.time_format.as_deref(), TIME_FORMATS);
+    }
+    if patch.record_backend.is_some() {
+        v.one_of(
+            "record_backend",
+            next.record_backend.as_deref(),
+            RECORD_BACKENDS,
+        );
+    }
+    if patch.record_vad_silence_ms.is_some() {
+        v.range_u64(
+            "record_vad_silence_ms",
+            next.record_vad_silence_ms,
+            settings::MIN_RECORD_VAD_SILENCE_MS,
+            settings::MAX_RECORD_VAD_SILENCE_MS,
+        );
+    }
+    if patch.recording_format.is_some() {
+        v.one_of(
+            "recording_format",
+            next.recording_format.as_deref(),
+            RECORDING_FORMATS,
+        );
+    }
+    if patch.text_casing.is_some() {
+        v.one_of("text_casing", next.text_casing.as_deref(), TEXT_CASINGS);
+    }
+    if patch.text_casing_by_mode.is_some() {
+        v.map_one_of(
+            "text_casing_by_mode",
+            next.text_casing_by_mode.as_ref(),
+            TEXT_CASINGS,
+        );
+    }
+    if let Some(Some(locales)) = &patch.text_normalize_locales {
+        for locale in locales {
+            v.one_of("text_normalize_locales", Some(locale), NORMALIZE_LOCALES);
+        }
+    }
+    if patch.context_history_n.is_some() {
+        v.range_i64("context_history_n", next.context_history_n, 0, i64::MAX);
+    }
+    if patch.context_history_window_ms.is_some() {
+        v.range_i64(
+            "context_history_window_ms",
+            next.context_history_window_ms,
+            0,
+            i64::MAX,
+        );
+    }
+
+    if patch.hotkey_primary.is_some() {
+        if let Err(e) = settings::normalize_hotkey_primary(next.hotkey_primary.as_deref()) {
+            v.push(
+                "hotkey_primary",
+                "E_SETTINGS_HOTKEY_PRIMARY_INVALID",
+                e.to_string(),
+            );
+        }
+    }
+    if patch.hotkey_debounce_ms.is_some() {
+        v.range_u64(
+            "hotkey_debounce_ms",
+            next.hotkey_debounce_ms,
+            0,
+            settings::MAX_HOTKEY_DEBOUNCE_MS,
+        );
+    }
+    if patch.overlay_background_opacity.is_some() {
+        v.range_f64(
+            "overlay_background_opacity",
+            next.overlay_background_opacity,
+            0.35,
+            0.95,
+        );
+    }
+    if patch.overlay_font_size_px.is_some() {
+        v.range_u64("overlay_font_size_px", next.overlay_font_size_px, 18, 56);
+    }
+    if patch.overlay_width_px.is_some() {
+        v.range_u64("overlay_width_px", next.overlay_width_px, 360, 1600);
+    }
+    if patch.overlay_height_px.is_some() {
+        v.range_u64("overlay_height_px", next.overlay_height_px, 72, 360);
+    }
+    if patch.overlay_status_colors.is_some() {
+        v.overlay_status_colors(next.overlay_status_colors.as_ref());
+    }
+    if patch.overlay_font_scale.is_some() {
+        v.range_f64("overlay_font_scale", next.overlay_font_scale, 0.5, 2.0);
+    }
+    if patch.overlay_status_language.is_some() {
+        v.one_of(
+            "overlay_status_language",
+            next.overlay_status_language.as_deref(),
+            &["en", "zh"],
+        );
+    }
+    if patch.captions_font_size_px.is_some() {
+        v.range_u64("captions_font_size_px", next.captions_font_size_px, 24, 96);
+    }
+    if patch.captions_background_opacity.is_some() {
+        v.range_f64(
+            "captions_background_opacity",
+            next.captions_background_opacity,
+            0.0,
+            0.95,
+        );
+    }
+    // The listener only exists on Windows; elsewhere the switch would stay on
+    // without anything listening.
+    if patch.wake_word_enabled.is_some()
+        && next.wake_word_enabled.unwrap_or(false)
+        && !cfg!(windows)
+    {
+        v.push(
+            "wake_word_enabled",
+            "E_WAKE_WORD_UNSUPPORTED",
+            "wake word listening is only supported on Windows",
+        );
+    }
+    if patch.wake_word_sensitivity.is_some() {
+        v.range_f64(
+            "wake_word_sensitivity",
+            next.wake_word_sensitivity,
+            0.0,
+            1.0,
+        );
+    }
+    if patch.postprocessors.is_some() {
+        v.postprocessors(next.postprocessors.as_deref().unwrap_or_default());
+    }
+    // The folder only has to exist while the sink is on.
+    if (patch.markdown_notes_enabled.is_some() || patch.markdown_notes_folder.is_some())
+        && next.markdown_notes_enabled.unwrap_or(false)
+    {
+        match next.markdown_notes_folder.as_deref().map(str::trim) {
+            Some(folder) if !folder.is_empty() => {
+                v.existing_dir("markdown_notes_folder", Some(folder))
+            }
+            _ => v.push(
+                "markdown_notes_folder",
+                "E_SETTINGS_REQUIRED",
+                "a notes folder is required while the sink is enabled",
+            ),
+        }
+    }
+    if patch.markdown_notes_entry_template.is_some() {
+        v.note_entry_template(
+            "markdown_notes_entry_template",
+            next.markdown_notes_entry_template.as_deref(),
+        );
+    }
+    if patch.webhook_enabled.is_some() || patch.webhook_url.is_some() {
+        let url = next.webhook_url.as_deref().map(str::trim).unwrap_or("");
+        if url.is_empty() && next.webhook_enabled.unwrap_or(false) {
+            v.push(
+                "webhook_url",
+                "E_SETTINGS_REQUIRED",
+                "a webhook URL is required while the sink is enabled",
+            );
+        }
+        v.url("webhook_url", Some(url), HTTP_SCHEMES);
+    }
+    if patch.webhook_fields.is_some() {
+        let unknown: Vec<&str> = next
+            .webhook_fields
+            .iter()
+            .flatten()
+            .map(|f| f.trim())
+            .filter(|f| !settings::WEBHOOK_FIELDS.contains(&f.to_ascii_lowercase().as_str()))
+            .collect();
+        if !unknown.is_empty() {
+            v.push(
+                "webhook_fields",
+                "E_SETTINGS_VALUE_INVALID",
+                format!(
+                    "unknown fields: {}; expected any of {}",
+                    unknown.join(", "),
+                    settings::WEBHOOK_FIELDS.join("|")
+                ),
+            );
+        }
+    }
+    if patch.mqtt_enabled.is_some() || patch.mqtt_broker_host.is_some() {
+        let host = next
+            .mqtt_broker_host
+            .as_deref()
+            .map(str::trim)
+            .unwrap_or("");
+        if host.is_empty() && next.mqtt_enabled.unwrap_or(false) {
+            v.push(
+                "mqtt_broker_host",
+                "E_SETTINGS_REQUIRED",
+                "a broker host is required while MQTT is enabled",
+            );
+        } else if host.contains("://") || host.contains(['/', ' ']) {
+            v.push(
+                "mqtt_broker_host",
+                "E_SETTINGS_VALUE_INVALID",
+                "expected a host name or IP without scheme or path",
+            );
+        }
+    }
+    if patch.mqtt_broker_port.is_some() {
+        v.range_u64(
+            "mqtt_broker_port",
+            next.mqtt_broker_port.map(u64::from),
+            1,
+            u16::MAX as u64,
+        );
+    }
+    if patch.mqtt_topic_prefix.is_some()
+        && next
+            .mqtt_topic_prefix
+            .as_deref()
+            .is_some_and(|p| p.contains(['+', '#']))
+    {
+        v.push(
+            "mqtt_topic_prefix",
+            "E_SETTINGS_VALUE_INVALID",
+            "topic prefix must not contain MQTT wildcards (+ or #)",
+        );
+    }
+    if patch.context_sections_by_template.is_some() {
+        for id in next
+            .context_sections_by_template
+            .iter()
+            .flatten()
+            .map(|(id, _)| id)
+        {
+            v.template_id(&format!("context_sections_by_template.{id}"), id);
+        }
+    }
+    if patch.markdown_notes_entry_template_by_template.is_some() {
+        for (id, value) in next
+            .markdown_notes_entry_template_by_template
+            .iter()
+            .flatten()
+        {
+            let field = format!("markdown_notes_entry_template_by_template.{id}");
+            v.template_id(&field, id);
+            v.note_entry_template(&field, Some(value));
+        }
+    }
+    if patch.daily_summary_time.is_some() {
+        let time = next.daily_summary_time.as_deref().map(str::trim);
+        if time.is_some_and(|t| !t.is_empty() && settings::parse_clock_time(t).is_none()) {
+            v.push(
+                "daily_summary_time",
+                "E_SETTINGS_VALUE_INVALID",
+                "expected a 24-hour time like 18:00",
+            );
+        }
+    }
+    v.errors
+}
+
+#[derive(Default)]
+struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    fn push(&mut self, field: &str, code: &str, message: impl Into<String>) {
+        self.errors.push(FieldError::new(field, code, message));
+    }
+
+    // Empty values are allowed everywhere: the resolver falls back to the default.
+    fn one_of(&mut self, field: &str, value: Option<&str>, allowed: &[&str]) {
+        let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+            return;
+        };
+        if !allowed.contains(&value.to_ascii_lowercase().as_str()) {
+            self.push(
+                field,
+                "E_SETTINGS_VALUE_INVALID",
+                format!("'{value}' is not one of {}", allowed.join("|")),
+            );
+        }
+    }
+
+    fn map_one_of(
+        &mut self,
+        field: &str,
+        map: Option<&BTreeMap<String, String>>,
+        allowed: &[&str],
+    ) {
+        for (id, value) in map.into_iter().flatten() {
+            let field = format!("{field}.{id}");
+            self.template_id(&field, id);
+            self.one_of(&field, Some(value), allowed);
+        }
+    }
+
+    fn overlay_status_colors(&mut self, map: Option<&BTreeMap<String, String>>) {
+        for (phase, color) in map.into_iter().flatten() {
+            let field = format!("overlay_status_colors.{phase}");
+            if !settings::OVERLAY_STATUSES.iter().any(|(p, ..)| p == phase) {
+                self.push(&field, "E_SETTINGS_VALUE_INVALID", "unknown overlay status");
+            } else if !settings::is_hex_color(color.trim()) {
+                self.push(
+                    &field,
+                    "E_SETTINGS_VALUE_INVALID",
+                    format!("'{color}' is not a #rgb or #rrggbb color"),
+                );
+            }
+        }
+    }
+
+    fn template_id(&mut self, field: &str, id: &str) {
+        if id.trim().is_empty() {
+            self.push(
+                field,
+                "E_SETTINGS_TEMPLATE_ID_EMPTY",
+                "template id is empty",
+            );
+        }
+    }
+
+    fn url(&mut self, field: &str, value: Option<&str>, schemes: &[&str]) {
+        let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+            return;
+        };
+        if !url_is_well_formed(value, schemes) {
+            self.push(
+                field,
+                "E_SETTINGS_URL_INVALID",
+                format!("expected {}://host[:port][/path]", schemes.join("|")),
+            );
+        }
+    }
+
+    fn json(&mut self, field: &str, value: Option<&str>) {
+        let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+            return;
+        };
+        if let Err(e) = serde_json::from_str::<serde_json::Value>(value) {
+            self.push(field, "E_SETTINGS_JSON_INVALID", e.to_string());
+        }
+    }
+
+    fn existing_file(&mut self, field: &str, value: Option<&str>) {
+        let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+            return;
+        };
+        if !Path::new(value).is_file() {
+            self.push(
+                field,
+                "E_SETTINGS_PATH_NOT_FOUND",
+                format!("{value} is not a file"),
+            );
+        }
+    }
+
+    fn existing_dir(&mut self, field: &str, value: Option<&str>) {
+        let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+            return;
+        };
+        if !Path::new(value).is_dir() {
+            self.push(
+                field,
+                "E_SETTINGS_PATH_NOT_FOUND",
+                format!("{value} is not a folder"),
+            );
+        }
+    }
+
+    // An entry without the text would append only headers to the note.
+    fn note_entry_template(&mut self, field: &str, value: Option<&str>) {
+        let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+            return;
+        };
+        if !value.contains("{{text}}") {
+            self.push(
+                field,
+                "E_SETTINGS_VALUE_INVALID",
+                "entry template must contain {{text}}",
+            );
+        }
+    }
+
+    // Entries are addressed by index ("postprocessors.0.path") so blank or
+    // repeated ids can still be pointed at.
+    fn postprocessors(&mut self, list: &[settings::PostprocessorConfig]) {
+        for (i, p) in list.iter().enumerate() {
+            let field = |name: &str| format!("postprocessors.{i}.{name}");
+            let id = p.id.trim();
+            if id.is_empty() {
+                self.push(
+                    &field("id"),
+                    "E_SETTINGS_REQUIRED",
+                    "post-processor id is required",
+                );
+            } else if list[..i].iter().any(|o| o.id.trim() == id) {
+                self.push(
+                    &field("id"),
+                    "E_SETTINGS_VALUE_INVALID",
+                    format!("duplicate post-processor id: {id}"),
+                );
+            }
+            self.one_of(&field("kind"), Some(&p.kind), settings::POSTPROCESSOR_KINDS);
+            if p.kind.trim().is_empty() {
+                self.push(
+                    &field("kind"),
+                    "E_SETTINGS_REQUIRED",
+                    "post-processor kind is required",
+                );
+            }
+            // A disabled entry may point at something not installed yet.
+            if p.path.trim().is_empty() {
+                self.push(
+                    &field("path"),
+                    "E_SETTINGS_REQUIRED",
+                    "post-processor path is required",
+                );
+            } else if p.enabled.unwrap_or(true) {
+                self.existing_file(&field("path"), Some(&p.path));
+            }
+            self.range_u64(
+                &field("timeout_ms"),
+                p.timeout_ms,
+                settings::MIN_POSTPROCESSOR_TIMEOUT_MS,
+                settings::MAX_POSTPROCESSOR_TIMEOUT_MS,
+            );
+        }
+    }
+
+    fn range_u64(&mut self, field: &str, value: Option<u64>, min: u64, max: u64) {
+        if let Some(v) = value.filter(|v| !(min..=max).contains(v)) {
+            self.out_of_range(field, v, min, (max != u64::MAX).then_some(max));
+        }
+    }
+
+    fn range_i64(&mut self, field: &str, value: Option<i64>, min: i64, max: i64) {
+        if let Some(v) = value.filter(|v| !(min..=max).contains(v)) {
+            self.out_of_range(field, v, min, (max != i64::MAX).then_some(max));
+        }
+    }
+
+    fn range_f64(&mut self, field: &str, value: Option<f64>, min: f64, max: f64) {
+        if let Some(v) = value.filter(|v| !v.is_finite() || !(min..=max).contains(v)) {
+            self.out_of_range(field, v, min, Some(max));
+        }
+    }
+
+    fn out_of_range<T: std::fmt::Display>(
+        &mut self,
+        field: &str,
+        value: T,
+        min: T,
+        max: Option<T>,
+    ) {
+        let expected = match max {
+            Some(max) => format!("between {min} and {max}"),
+            None => format!("at least {min}"),
+        };
+        self.push(
+            field,
+            "E_SETTINGS_OUT_OF_RANGE",
+            format!("{value} is out of range; expected {expected}"),
+        );
+    }
+}
+
+fn url_is_well_formed(value: &str, schemes: &[&str]) -> bool {
+    let Some((scheme, rest)) = value.split_once("://") else {
+        return false;
+    };
+    if !schemes.contains(&scheme.to_ascii_lowercase().as_str())
+        || value.chars().any(char::is_whitespace)
+    {
+        return false;
+    }
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_port = authority.rsplit('@').next().unwrap_or_default();
+    // Bracketed IPv6 literals contain ':' themselves.
+    let (host, port) = match host_port.strip_prefix('[') {
+        Some(v6) => match v6.split_once(']') {
+            Some((h, "")) => (h, None),
+            Some((h, p)) => match p.strip_prefix(':') {
+                Some(p) => (h, Some(p)),
+                None => return false,
+            },
+            None => return false,
+        },
+        None => match host_port.rsplit_once(':') {
+            Some((h, p)) => (h, Some(p)),
+            None => (host_port, None),
+        },
+    };
+    !host.is_empty() && port.is_none_or(|p| p.parse::<u16>().is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{url_is_well_formed, validate_patch};
+    use crate::settings::{PostprocessorConfig, Settings, SettingsPatch};
+
+    fn codes(patch: SettingsPatch) -> Vec<(String, String)> {
+        validate_patch(&Settings::default(), &patch)
+            .into_iter()
+            .map(|e| (e.field, e.code))
+            .collect()
+    }
+
+    #[test]
+    fn valid_patch_has_no_errors_and_untouched_fields_are_ignored() {
+        let cur = Settings {
+            overlay_font_size_px: Some(999),
+            ..Default::default()
+        };
+        let patch = SettingsPatch {
+            llm_base_url: Some(Some("https://api.openai.com/v1".to_string())),
+            remote_asr_concurrency: Some(Some(4)),
+            hotkey_primary: Some(Some("f9".to_string())),
+            ..Default::default()
+        };
+        assert!(validate_patch(&cur, &patch).is_empty());
+    }
+
+    #[test]
+    fn wake_word_is_rejected_off_windows() {
+        let on = codes(SettingsPatch {
+            wake_word_enabled: Some(Some(true)),
+            ..Default::default()
+        });
+        if cfg!(windows) {
+            assert!(on.is_empty());
+        } else {
+            assert_eq!(
+                on,
+                vec![(
+                    "wake_word_enabled".to_string(),
+                    "E_WAKE_WORD_UNSUPPORTED".to_string()
+                )]
+            );
+        }
+        assert!(codes(SettingsPatch {
+            wake_word_enabled: Some(Some(false)),
+            ..Default::default()
+        })
+        .is_empty());
+    }
+
+    #[test]
+    fn reports_each_invalid_field_with_its_code() {
+        let mut by_template = BTreeMap::new();
+        by_template.insert("email".to_string(), "yaml".to_string());
+        let got = codes(SettingsPatch {
+            asr_provider: Some(Some("whisper".to_string())),
+            llm_base_url: Some(Some("api.openai.com".to_string())),
+            proxy_url: Some(Some("ftp://proxy:21".to_string())),
+            remote_asr_concurrency: Some(Some(0)),
+            http_request_timeout_ms: Some(Some(0)),
+            overlay_background_opacity: Some(Some(1.5)),
+            hotkey_primary: Some(Some("Q".to_string())),
+            rewrite_output_json_schema: Some(Some("{not json".to_string())),
+            rewrite_output_format_by_template: Some(Some(by_template)),
+            tls_pinned_sha256: Some(Some("abcd".to_string())),
+            ..Default::default()
+        });
+        let expect = [
+            ("asr_provider", "E_SETTINGS_VALUE_INVALID"),
+            ("remote_asr_concurrency", "E_SETTINGS_OUT_OF_RANGE"),
+            ("proxy_url", "E_SETTINGS_URL_INVALID"),
+            ("tls_pinned_sha256", "E_SETTINGS_FINGERPRINT_INVALID"),
+            ("llm_base_url", "E_SETTINGS_URL_INVALID"),
+            (
+                "rewrite_output_format_by_template.email",
+                "E_SETTINGS_VALUE_INVALID",
+            ),
+            ("rewrite_output_json_schema", "E_SETTINGS_JSON_INVALID"),
+            ("hotkey_primary", "E_SETTINGS_HOTKEY_PRIMARY_INVALID"),
+            ("overlay_background_opacity", "E_SETTINGS_OUT_OF_RANGE"),
+        ];
+        let expect: Vec<_> = expect
+            .iter()
+            .map(|(f, c)| (f.to_string(), c.to_string()))
+            .collect();
+        assert_eq!(got, expect);
+    }
+
+    #[test]
+    fn rewrite_requires_a_prompt() {
+        let got = codes(SettingsPatch {
+            rewrite_enabled: Some(Some(true)),
+            ..Default::default()
+        });
+        assert_eq!(
+            got,
+            vec![("llm_prompt".to_string(), "E_SETTINGS_REQUIRED".to_string())]
+        );
+    }
+
+    #[test]
+    fn postprocessor_entries_are_checked_by_index() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let exe = tmp.path().join("notes");
+        std::fs::write(&exe, "").expect("write");
+        let exe = exe.to_string_lossy().to_string();
+        let entry = |id: &str, kind: &str, path: &str| PostprocessorConfig {
+            id: id.to_string(),
+            kind: kind.to_string(),
+            path: path.to_string(),
+            ..Default::default()
+        };
+        let got = codes(SettingsPatch {
+            postprocessors: Some(Some(vec![
+                entry("notes", "command", &exe),
+                PostprocessorConfig {
+                    timeout_ms: Some(10),
+                    ..entry("notes", "lua", "/missing/plugin")
+                },
+                PostprocessorConfig {
+                    enabled: Some(false),
+                    ..entry("later", "wasm", "/missing/later.wasm")
+                },
+                entry("", "", ""),
+            ])),
+            ..Default::default()
+        });
+        let expected = [
+            ("postprocessors.1.id", "E_SETTINGS_VALUE_INVALID"),
+            ("postprocessors.1.kind", "E_SETTINGS_VALUE_INVALID"),
+            ("postprocessors.1.path", "E_SETTINGS_PATH_NOT_FOUND"),
+            ("postprocessors.1.timeout_ms", "E_SETTINGS_OUT_OF_RANGE"),
+            ("postprocessors.3.id", "E_SETTINGS_REQUIRED"),
+            ("postprocessors.3.kind", "E_SETTINGS_REQUIRED"),
+            ("postprocessors.3.path", "E_SETTINGS_REQUIRED"),
+        ];
+        let expected: Vec<(String, String)> = expected
+            .iter()
+            .map(|(f, c)| (f.to_string(), c.to_string()))
+            .collect();
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn markdown_notes_folder_is_required_only_while_enabled() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let folder = tmp.path().to_string_lossy().to_string();
+        let patch = |enabled: bool, folder: Option<&str>| SettingsPatch {
+            markdown_notes_enabled: Some(Some(enabled)),
+            markdown_notes_folder: Some(folder.map(ToOwned::to_owned)),
+            ..Default::default()
+        };
+        assert!(codes(patch(false, None)).is_empty());
+        assert!(codes(patch(true, Some(&folder))).is_empty());
+        assert_eq!(
+            codes(patch(true, None)),
+            vec![(
+                "markdown_notes_folder".to_string(),
+                "E_SETTINGS_REQUIRED".to_string()
+            )]
+        );
+        assert_eq!(
+            codes(patch(true, Some("/missing/vault"))),
+            vec![(
+                "markdown_notes_folder".to_string(),
+                "E_SETTINGS_PATH_NOT_FOUND".to_string()
+            )]
+        );
+        let got = codes(SettingsPatch {
+            markdown_notes_entry_template: Some(Some("## {{time}}".to_string())),
+            markdown_notes_entry_template_by_template: Some(Some(
+                [("meeting".to_string(), "- {{text}}".to_string())]
+                    .into_iter()
+                    .collect(),
+            )),
+            ..Default::default()
+        });
+        assert_eq!(
+            got,
+            vec![(
+                "markdown_notes_entry_template".to_string(),
+                "E_SETTINGS_VALUE_INVALID".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn webhook_needs_a_url_while_enabled_and_known_fields() {
+        let got = codes(SettingsPatch {
+            webhook_enabled: Some(Some(true)),
+            webhook_fields: Some(Some(vec![
+                "final_text".to_string(),
+                "audio_path".to_string(),
+            ])),
+            ..Default::default()
+        });
+        let expected = [
+            ("webhook_url", "E_SETTINGS_REQUIRED"),
+            ("webhook_fields", "E_SETTINGS_VALUE_INVALID"),
+        ];
+        let expected: Vec<(String, String)> = expected
+            .iter()
+            .map(|(f, c)| (f.to_string(), c.to_string()))
+            .collect();
+        assert_eq!(got, expected);
+        assert_eq!(
+            codes(SettingsPatch {
+                webhook_url: Some(Some("ftp://hooks.example.com".to_string())),
+                ..Default::default()
+            }),
+            vec![(
+                "webhook_url".to_string(),
+                "E_SETTINGS_URL_INVALID".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn mqtt_needs_a_plain_host_and_wildcard_free_prefix() {
+        let got = codes(SettingsPatch {
+            mqtt_enabled: Some(Some(true)),
+            mqtt_broker_port: Some(Some(0)),
+            mqtt_topic_prefix: Some(Some("home/#".to_string())),
+            ..Default::default()
+        });
+        let expected = [
+            ("mqtt_broker_host", "E_SETTINGS_REQUIRED"),
+            ("mqtt_broker_port", "E_SETTINGS_OUT_OF_RANGE"),
+            ("mqtt_topic_prefix", "E_SETTINGS_VALUE_INVALID"),
+        ];
+        let expected: Vec<(String, String)> = expected
+            .iter()
+            .map(|(f, c)| (f.to_string(), c.to_string()))
+            .collect();
+        assert_eq!(got, expected);
+        assert_eq!(
+            codes(SettingsPatch {
+                mqtt_broker_host: Some(Some("mqtt://broker.lan".to_string())),
+                ..Default::default()
+            }),
+            vec![(
+                "mqtt_broker_host".to_string(),
+                "E_SETTINGS_VALUE_INVALID".to_string()
+            )]
+        );
+        assert!(codes(SettingsPatch {
+            mqtt_enabled: Some(Some(true)),
+            mqtt_broker_host: Some(Some("192.168.1.10".to_string())),
+            ..Default::default()
+        })
+        .is_empty());
+    }
+
+    #[test]
+    fn url_check_accepts_ports_credentials_and_ipv6() {
+        for ok in [
+            "https://api.example.com/v1",
+            "http://user:pw@10.0.0.1:8080",
+            "http://[::1]:11434/v1",
+            "socks5h://proxy.corp:1080",
+        ] {
+            assert!(
+                url_is_well_formed(ok, &["http", "https", "socks5h"]),
+                "{ok}"
+            );
+        }
+        for bad in ["https://", "https://host:99999", "https://a b", "host:80"] {
+            assert!(!url_is_well_formed(bad, &["http", "https"]), "{bad}");
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use chrono::{DateTime, Local, SecondsFormat, TimeZone};
+use serde::{Deserialize, Serialize};
+
+pub const TIME_FORMATS: &[&str] = &["24h", "12h", "iso"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeFormat {
+    #[default]
+    #[serde(rename = "24h")]
+    H24,
+    #[serde(rename = "12h")]
+    H12,
+    #[serde(rename = "iso")]
+    Iso,
+}
+
+impl TimeFormat {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim() {
+            "24h" => Some(Self::H24),
+            "12h" => Some(Self::H12),
+            "iso" => Some(Self::Iso),
+            _ => None,
+        }
+    }
+}
+
+// A stored epoch-millis instant as people read it. The offset is the one in
+// force at that instant, so rows on either side of a DST change keep their
+// wall-clock time instead of shifting with today's offset.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormattedTime {
+    // RFC 3339 with the local offset, e.g. 2026-03-29T09:15:00+02:00.
+    pub iso: String,
+    pub display: String,
+}
+
+pub fn format_local(ms: i64, format: TimeFormat) -> FormattedTime {
+    format_in(&Local, ms, format)
+}
+
+pub fn format_in<Tz: TimeZone>(tz: &Tz, ms: i64, format: TimeFormat) -> FormattedTime
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let utc = DateTime::from_timestamp_millis(ms).unwrap_or_default();
+    let at = utc.with_timezone(tz);
+    let iso = at.to_rfc3339_opts(SecondsFormat::Secs, false);
+    let display = match format {
+        TimeFormat::H24 => at.format("%Y-%m-%d %H:%M").to_string(),
+        TimeFormat::H12 => at.format("%Y-%m-%d %-I:%M %p").to_string(),
+        TimeFormat::Iso => iso.clone(),
+    };
+    FormattedTime { iso, display }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    #[test]
+    fn formats_with_the_offset_of_the_zone() {
+        // 2026-03-29T07:15:00Z
+        let ms = 1_774_768_500_000;
+        let cest = FixedOffset::east_opt(2 * 3600).expect("offset");
+        let out = format_in(&cest, ms, TimeFormat::H24);
+        assert_eq!(out.iso, "2026-03-29T09:15:00+02:00");
+        assert_eq!(out.display, "2026-03-29 09:15");
+        assert_eq!(
+            format_in(&cest, ms, TimeFormat::H12).display,
+            "2026-03-29 9:15 AM"
+        );
+        let utc = FixedOffset::east_opt(0).expect("offset");
+        let out = format_in(&utc, ms, TimeFormat::Iso);
+        assert_eq!(out.iso, "2026-03-29T07:15:00+00:00");
+        assert_eq!(out.display, out.iso);
+        assert_eq!(TimeFormat::parse(" 12h "), Some(TimeFormat::H12));
+        assert_eq!(TimeFormat::parse("locale"), None);
+    }
+}
@@ -0,0 +1,243 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::atomic_file;
+use crate::obs::Span;
+use crate::settings::{self, Settings, TemplateContextSections};
+
+// The default workspace is the data dir itself, so existing history stays put.
+pub const DEFAULT_WORKSPACE: &str = "default";
+const MAX_NAME_LEN: usize = 64;
+pub(crate) const HISTORY_DB: &str = "history.sqlite3";
+pub(crate) const TEMPLATES_FILE: &str = "workspace_templates.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WorkspaceInfo {
+    pub name: String,
+    pub active: bool,
+}
+
+// The per-workspace template set: the rewrite prompt and every setting keyed
+// by template id. Switching saves the outgoing set and loads the incoming one.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceTemplates {
+    pub llm_prompt: Option<String>,
+    pub rewrite_template_id: Option<String>,
+    pub rewrite_output_format_by_template: Option<BTreeMap<String, String>>,
+    pub rewrite_output_json_schema_by_template: Option<BTreeMap<String, String>>,
+    pub export_format_by_template: Option<BTreeMap<String, String>>,
+    pub export_targets_by_template: Option<BTreeMap<String, Vec<String>>>,
+    pub context_sections_by_template: Option<BTreeMap<String, TemplateContextSections>>,
+    pub markdown_notes_entry_template_by_template: Option<BTreeMap<String, String>>,
+}
+
+impl WorkspaceTemplates {
+    pub fn from_settings(s: &Settings) -> Self {
+        Self {
+            llm_prompt: s.llm_prompt.clone(),
+            rewrite_template_id: s.rewrite_template_id.clone(),
+            rewrite_output_format_by_template: s.rewrite_output_format_by_template.clone(),
+            rewrite_output_json_schema_by_template: s
+                .rewrite_output_json_schema_by_template
+                .clone(),
+            export_format_by_template: s.export_format_by_template.clone(),
+            export_targets_by_template: s.export_targets_by_template.clone(),
+            context_sections_by_template: s.context_sections_by_template.clone(),
+            markdown_notes_entry_template_by_template: s
+                .markdown_notes_entry_template_by_template
+                .clone(),
+        }
+    }
+
+    pub fn apply_to(self, s: &mut Settings) {
+        s.llm_prompt = self.llm_prompt;
+        s.rewrite_template_id = self.rewrite_template_id;
+        s.rewrite_output_format_by_template = self.rewrite_output_format_by_template;
+        s.rewrite_output_json_schema_by_template = self.rewrite_output_json_schema_by_template;
+        s.export_format_by_template = self.export_format_by_template;
+        s.export_targets_by_template = self.export_targets_by_template;
+        s.context_sections_by_template = self.context_sections_by_template;
+        s.markdown_notes_entry_template_by_template =
+            self.markdown_notes_entry_template_by_template;
+    }
+}
+
+// Names become directory names: 1-64 ASCII letters, digits, '-' or '_'.
+pub fn normalize_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(anyhow!(
+            "E_WORKSPACE_NAME_INVALID: workspace names use 1-{MAX_NAME_LEN} letters, digits, '-' or '_'"
+        ));
+    }
+    Ok(name.to_string())
+}
+
+pub fn resolve_active(s: &Settings) -> String {
+    s.active_workspace
+        .as_deref()
+        .and_then(|v| normalize_name(v).ok())
+        .unwrap_or_else(|| DEFAULT_WORKSPACE.to_string())
+}
+
+pub fn workspace_dir(data_dir: &Path, name: &str) -> PathBuf {
+    if name == DEFAULT_WORKSPACE {
+        data_dir.to_path_buf()
+    } else {
+        data_dir.join("workspaces").join(name)
+    }
+}
+
+// History DB of the active workspace. A settings file that cannot be read
+// falls back to the default workspace.
+pub fn history_db_path(data_dir: &Path) -> PathBuf {
+    let s = settings::load_settings(data_dir).unwrap_or_default();
+    workspace_history_db(data_dir, &resolve_active(&s))
+}
+
+pub(crate) fn workspace_history_db(data_dir: &Path, name: &str) -> PathBuf {
+    workspace_dir(data_dir, name).join(HISTORY_DB)
+}
+
+pub(crate) fn templates_path(data_dir: &Path, name: &str) -> PathBuf {
+    workspace_dir(data_dir, name).join(TEMPLATES_FILE)
+}
+
+fn load_templates(data_dir: &Path, name: &str) -> Result<Option<WorkspaceTemplates>> {
+    let p = templates_path(data_dir, name);
+    if !p.exists() {
+        return Ok(None);
+    }
+    let s = fs::read_to_string(&p).context("read workspace_templates.json failed")?;
+    let v = serde_json::from_str(&s).context("parse workspace_templates.json failed")?;
+    Ok(Some(v))
+}
+
+pub(crate) fn save_templates(
+    data_dir: &Path,
+    name: &str,
+    templates: &WorkspaceTemplates,
+) -> Result<()> {
+    let dir = workspace_dir(data_dir, name);
+    fs::create_dir_all(&dir).context("create workspace dir failed")?;
+    let s = serde_json::to_string_pretty(templates).context("serialize templates failed")?;
+    atomic_file::write(&dir.join(TEMPLATES_FILE), s.as_bytes())
+        .map_err(|e| anyhow!("E_WORKSPACE_WRITE: write workspace_templates.json failed: {e}"))
+}
+
+pub fn list(data_dir: &Path) -> Result<Vec<WorkspaceInfo>> {
+    let active = resolve_active(&settings::load_settings(data_dir)?);
+    Ok(workspace_names(data_dir)
+        .into_iter()
+        .map(|name| WorkspaceInfo {
+            active: name == active,
+            name,
+        })
+        .collect())
+}
+
+// The default workspace first, then the others by name.
+pub(crate) fn workspace_names(data_dir: &Path) -> Vec<String> {
+    let mut names = vec![DEFAULT_WORKSPACE.to_string()];
+    if let Ok(entries) = fs::read_dir(data_dir.join("workspaces")) {
+        for entry in entries.flatten() {
+            if !entry.path().is_dir() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name != DEFAULT_WORKSPACE && normalize_name(&name).is_ok() {
+                names.push(name);
+            }
+        }
+    }
+    names[1..].sort();
+    names
+}
+
+// Makes `name` the active workspace, creating it on first use. A new
+// workspace starts from a copy of the current template set.
+pub fn switch(data_dir: &Path, name: &str) -> Result<Settings> {
+    let name = normalize_name(name)?;
+    let span = Span::start(
+        data_dir,
+        None,
+        "Settings",
+        "WORKSPACES.switch",
+        Some(serde_json::json!({"workspace": name})),
+    );
+    let result = settings::modify_settings(data_dir, |s| {
+        let current = resolve_active(s);
+        if current == name {
+            return Ok(false);
+        }
+        save_templates(data_dir, &current, &WorkspaceTemplates::from_settings(s))?;
+        match load_templates(data_dir, &name)? {
+            Some(templates) => templates.apply_to(s),
+            None => save_templates(data_dir, &name, &WorkspaceTemplates::from_settings(s))?,
+        }
+        s.active_workspace = (name != DEFAULT_WORKSPACE).then(|| name.clone());
+        Ok(true)
+    });
+    match &result {
+        Ok(_) => span.ok(None),
+        Err(e) => span.err_anyhow("io", "E_WORKSPACE_SWITCH", e, None),
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switch_swaps_history_path_and_template_set() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let dir = tmp.path();
+        let s = Settings {
+            llm_prompt: Some("personal".to_string()),
+            ..Settings::default()
+        };
+        settings::save_settings(dir, &s).expect("save");
+        assert_eq!(history_db_path(dir), dir.join("history.sqlite3"));
+
+        let s = switch(dir, " client-a ").expect("switch");
+        assert_eq!(s.active_workspace.as_deref(), Some("client-a"));
+        assert_eq!(s.llm_prompt.as_deref(), Some("personal"));
+        assert_eq!(
+            history_db_path(dir),
+            dir.join("workspaces")
+                .join("client-a")
+                .join("history.sqlite3")
+        );
+
+        settings::modify_settings(dir, |s| {
+            s.llm_prompt = Some("client".to_string());
+            Ok(true)
+        })
+        .expect("edit");
+        let s = switch(dir, "default").expect("back");
+        assert_eq!(s.active_workspace, None);
+        assert_eq!(s.llm_prompt.as_deref(), Some("personal"));
+        let s = switch(dir, "client-a").expect("again");
+        assert_eq!(s.llm_prompt.as_deref(), Some("client"));
+
+        let names: Vec<_> = list(dir)
+            .expect("list")
+            .into_iter()
+            .map(|w| w.name)
+            .collect();
+        assert_eq!(names, ["default", "client-a"]);
+        assert!(switch(dir, "../x").is_err());
+    }
+}
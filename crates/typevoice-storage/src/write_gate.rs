@@ -0,0 +1,12 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::{dir_lock, guest_mode};
+
+// The one check in front of every store write (JSON stores, history and the
+// audit log): guest mode first, then the data dir lock.
+pub fn check(path: &Path) -> Result<()> {
+    guest_mode::check_writable()?;
+    dir_lock::check_writable(path)
+}
@@ -318,11 +318,35 @@ fn load_fixtures_manifest() -> Result<FixturesManifest> {
         .with_context(|| format!("cannot parse fixtures manifest: {}", path.display()))
 }
 
+// Downloads follow the same proxy as the app: TYPEVOICE_PROXY_URL plus a
+// comma-separated TYPEVOICE_NO_PROXY. Without it reqwest still reads HTTP(S)_PROXY.
+// TYPEVOICE_OFFLINE=1 mirrors the app's offline_mode and refuses every download.
+fn http_client_builder() -> Result<reqwest::blocking::ClientBuilder> {
+    if env::var("TYPEVOICE_OFFLINE").is_ok_and(|v| v.trim() == "1") {
+        return Err(anyhow!(
+            "E_OFFLINE_MODE: downloads are disabled by TYPEVOICE_OFFLINE"
+        ));
+    }
+    let builder = Client::builder();
+    let Some(url) = env::var("TYPEVOICE_PROXY_URL")
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+    else {
+        return Ok(builder);
+    };
+    let no_proxy = env::var("TYPEVOICE_NO_PROXY").unwrap_or_default();
+    let proxy = reqwest::Proxy::all(&url)
+        .with_context(|| format!("invalid TYPEVOICE_PROXY_URL: {url}"))?
+        .no_proxy(reqwest::NoProxy::from_string(&no_proxy));
+    Ok(builder.proxy(proxy))
+}
+
 fn ensure_fixtures_ready(required_files: &[&str]) -> Result<()> {
     let manifest = load_fixtures_manifest()?;
     let dir = fixtures_dir()?;
     fs::create_dir_all(&dir).with_context(|| format!("create fixtures dir: {}", dir.display()))?;
-    let client = Client::builder()
+    let client = http_client_builder()?
         .timeout(Duration::from_secs(120))
         .user_agent("TypeVoice xtask/0.1 (https://github.com/JiaJunDeng5930/TypeVoice)")
         .build()
@@ -701,7 +725,7 @@ fn install_ffmpeg_toolchain(selection: ToolchainPlatform) -> Result<()> {
     let root = repo_root()?;
     let manifest = load_ffmpeg_manifest()?;
     let targets = resolve_toolchain_targets(selection)?;
-    let client = Client::builder()
+    let client = http_client_builder()?
         .timeout(Duration::from_secs(120))
         .build()
         .context("create http client")?;
@@ -1660,7 +1684,7 @@ fn run_llm_prompt_lab(args: LlmPromptLabArgs) -> Result<()> {
         return Ok(());
     }
 
-    let client = Client::builder()
+    let client = http_client_builder()?
         .timeout(Duration::from_secs_f64(args.timeout_s))
         .build()
         .context("create http client")?;
@@ -1811,7 +1835,7 @@ fn build_messages(
     );
     match inject_mode {
         InjectMode::InlineOneUser => {
-            let user = context_pack::prepare(transcript, &snapshot, &budget).user_text;
+            let user = context_pack::prepare(transcript, &snapshot, &budget, false).user_text;
             messages.push(json!({"role": "user", "content": user}));
         }
         InjectMode::TwoUserMessages => {